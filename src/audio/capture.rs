@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Context, Result};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::devices::{self, Direction};
+use super::playback::{find_program, Player};
+
+/// Length of the window `levels` measures, in seconds.
+const LEVEL_WINDOW: f64 = 0.1;
+
+/// Time given a recorder to start before playback starts, so the start isn't missed, in
+/// seconds.
+const WARM_UP: f64 = 0.3;
+
+/// Time recorded after playback ends, to catch latency and decay, in seconds.
+const TAIL: f64 = 1.0;
+
+/// Candidate recorders writing raw interleaved f32 little-endian PCM to stdout, with the
+/// device when one is given.
+fn backend_commands(device: Option<&str>, sample_rate: u32, channels: usize) -> Vec<(&'static str, Vec<String>)> {
+    let (rate, channels) = (sample_rate.to_string(), channels.to_string());
+    let mut parec = vec!["--raw".into(), "--format=float32le".into(), format!("--rate={}", rate), format!("--channels={}", channels)];
+    let mut arecord = vec!["-q".into(), "-t".into(), "raw".into(), "-f".into(), "FLOAT_LE".into(), "-r".into(), rate.clone(), "-c".into(), channels.clone()];
+    let mut ffmpeg: Vec<String> = vec!["-v".into(), "error".into(), "-nostdin".into()];
+    if let Some(device) = device {
+        parec.push(format!("--device={}", device));
+        arecord.extend(["-D".into(), device.to_string()]);
+    }
+    let (format, default_device) = if cfg!(target_os = "macos") {
+        ("avfoundation", ":default")
+    } else if cfg!(windows) {
+        ("dshow", "audio=default")
+    } else {
+        ("pulse", "default")
+    };
+    ffmpeg.extend(["-f".into(), format.into(), "-i".into(), device.unwrap_or(default_device).to_string()]);
+    ffmpeg.extend(["-f".into(), "f32le".into(), "-ar".into(), rate.clone(), "-ac".into(), channels.clone(), "-".into()]);
+    let mut commands = vec![("parec", parec), ("arecord", arecord)];
+    // sox picks its device from AUDIODEV, which `Recorder::start` sets
+    commands.push((
+        "rec",
+        vec!["-q".into(), "-t".into(), "raw".into(), "-e".into(), "floating-point".into(), "-b".into(), "32".into(), "-r".into(), rate, "-c".into(), channels, "-".into()],
+    ));
+    commands.push(("ffmpeg", ffmpeg));
+    commands
+}
+
+/// The recorder capture would use, the first of `backend_commands` on the PATH.
+pub fn backend() -> Option<&'static str> {
+    backend_commands(None, 0, 1)
+        .into_iter()
+        .map(|(program, _)| program)
+        .find(|program| find_program(program))
+}
+
+/// Names of capture devices that record what the system plays: ALSA's snd-aloop, Windows'
+/// Stereo Mix and virtual-audio-capturer, and the BlackHole and Soundflower drivers on macOS.
+const LOOPBACK_NAMES: [&str; 6] = ["loopback", "stereo mix", "what u hear", "virtual-audio-capturer", "blackhole", "soundflower"];
+
+/// The capture device that records the system's output: the monitor of the default output
+/// with PulseAudio (and PipeWire) when the recorder talks to it, otherwise a loopback device
+/// from `devices::list`.
+pub fn loopback_device() -> Result<String> {
+    // Only parec and ffmpeg's pulse input know the monitor's name; arecord and sox don't
+    let recorder = backend();
+    let pulse = recorder == Some("parec") || (recorder == Some("ffmpeg") && cfg!(not(any(target_os = "macos", windows))));
+    if pulse && find_program("pactl") {
+        return Ok("@DEFAULT_MONITOR@".to_string());
+    }
+    let loopback = devices::list()?.into_iter().find(|device| {
+        let description = device.description.as_deref().unwrap_or(&device.name).to_lowercase();
+        device.direction == Direction::Capture && LOOPBACK_NAMES.iter().any(|name| description.contains(name))
+    });
+    loopback.map(|device| device.name).ok_or_else(|| {
+        let fix = if cfg!(target_os = "macos") {
+            "install BlackHole and play through a Multi-Output Device that includes it"
+        } else if cfg!(windows) {
+            "enable Stereo Mix under Sound settings > Recording, or install virtual-audio-capturer"
+        } else {
+            "run PulseAudio or PipeWire, or load the snd-aloop module and play to it"
+        };
+        anyhow!("No loopback device found to capture system audio; {}", fix)
+    })
+}
+
+/// Records from an input device by reading from the first available system recorder, with
+/// the audio so far available while it runs.
+pub struct Recorder {
+    /// Behind a lock so the recorder can be shared with threads reading its audio
+    child: Mutex<Child>,
+    sample_rate: u32,
+    /// Samples of each channel received so far, less those discarded
+    channels: Arc<Mutex<Vec<Vec<f32>>>>,
+    /// Samples discarded from the start of each channel, changed with `channels` locked
+    discarded: AtomicUsize,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Starts recording `channels` channels at `sample_rate` from `device`, or the system's
+    /// default input.
+    pub fn start(device: Option<&str>, sample_rate: u32, channels: usize) -> Result<Self> {
+        let channel_count = channels.max(1);
+        let commands = backend_commands(device, sample_rate, channel_count);
+        let (program, args) = commands
+            .iter()
+            .find(|(program, _)| find_program(program))
+            .ok_or_else(|| anyhow!("No audio recorder found (install parec, arecord, sox or ffmpeg)"))?;
+        let mut command = Command::new(program);
+        if let (Some(device), true) = (device, *program == "rec") {
+            command.env("AUDIODEV", device);
+        }
+        let mut child = command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to start {}", program))?;
+        let mut stdout = child.stdout.take().ok_or_else(|| anyhow!("Recorder has no stdout"))?;
+
+        let buffers = Arc::new(Mutex::new(vec![Vec::new(); channel_count]));
+        let shared = buffers.clone();
+        let reader = std::thread::spawn(move || {
+            let mut bytes = vec![0u8; 4096 * 4 * channel_count];
+            // Bytes of a sample split across reads
+            let mut pending = Vec::new();
+            let mut next_channel = 0;
+            while let Ok(read) = stdout.read(&mut bytes) {
+                if read == 0 {
+                    break;
+                }
+                pending.extend_from_slice(&bytes[..read]);
+                let whole = pending.len() / 4 * 4;
+                let mut channels = shared.lock().expect("recorder buffers are never poisoned");
+                for b in pending[..whole].chunks_exact(4) {
+                    channels[next_channel].push(f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+                    next_channel = (next_channel + 1) % channel_count;
+                }
+                drop(channels);
+                pending.drain(..whole);
+            }
+        });
+        Ok(Self {
+            child: Mutex::new(child),
+            sample_rate,
+            channels: buffers,
+            discarded: AtomicUsize::new(0),
+            reader: Some(reader),
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Seconds recorded so far.
+    pub fn elapsed(&self) -> f64 {
+        let channels = self.channels.lock().expect("recorder buffers are never poisoned");
+        let len = channels.iter().map(Vec::len).min().unwrap_or(0);
+        (len + self.discarded.load(Ordering::Relaxed)) as f64 / self.sample_rate as f64
+    }
+
+    /// The first channel's samples from index `from` on, counting from the start of the
+    /// recording.
+    pub fn samples_since(&self, from: usize) -> Vec<f32> {
+        let channels = self.channels.lock().expect("recorder buffers are never poisoned");
+        let from = from.saturating_sub(self.discarded.load(Ordering::Relaxed));
+        channels.first().map_or(Vec::new(), |samples| samples[from.min(samples.len())..].to_vec())
+    }
+
+    /// Samples of each channel from index `from` to `to`, counting from the start of the
+    /// recording; any discarded are left out.
+    pub fn channels_between(&self, from: usize, to: usize) -> Vec<Vec<f32>> {
+        let channels = self.channels.lock().expect("recorder buffers are never poisoned");
+        let discarded = self.discarded.load(Ordering::Relaxed);
+        let (from, to) = (from.saturating_sub(discarded), to.max(from).saturating_sub(discarded));
+        channels
+            .iter()
+            .map(|samples| samples[from.min(samples.len())..to.min(samples.len())].to_vec())
+            .collect()
+    }
+
+    /// Frees the samples before index `before`, for recordings too long to keep whole.
+    /// They're gone from `samples_since`, `channels_between` and `stop`.
+    pub fn discard_before(&self, before: usize) {
+        let mut channels = self.channels.lock().expect("recorder buffers are never poisoned");
+        let discarded = self.discarded.load(Ordering::Relaxed);
+        let count = before.saturating_sub(discarded).min(channels.iter().map(Vec::len).min().unwrap_or(0));
+        for samples in channels.iter_mut() {
+            samples.drain(..count);
+        }
+        self.discarded.store(discarded + count, Ordering::Relaxed);
+    }
+
+    /// RMS and peak level in dBFS of each channel over the last tenth of a second.
+    pub fn levels(&self) -> Vec<(f32, f32)> {
+        let window = (LEVEL_WINDOW * self.sample_rate as f64) as usize;
+        let channels = self.channels.lock().expect("recorder buffers are never poisoned");
+        channels
+            .iter()
+            .map(|samples| {
+                let recent = &samples[samples.len().saturating_sub(window)..];
+                if recent.is_empty() {
+                    return (f32::NEG_INFINITY, f32::NEG_INFINITY);
+                }
+                let power = recent.iter().map(|s| s * s).sum::<f32>() / recent.len() as f32;
+                let peak = recent.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+                (10.0 * power.log10(), 20.0 * peak.log10())
+            })
+            .collect()
+    }
+
+    /// Whether the recorder is still running; it exits early when the device goes away.
+    pub fn is_running(&self) -> bool {
+        let mut child = self.child.lock().expect("recorder process is never poisoned");
+        matches!(child.try_wait(), Ok(None))
+    }
+
+    /// Stops recording and returns the samples of each channel not discarded, cut to
+    /// `max_seconds` from the start of the recording when given.
+    pub fn stop(mut self, max_seconds: Option<f64>) -> Vec<Vec<f32>> {
+        self.shutdown();
+        let mut channels = std::mem::take(&mut *self.channels.lock().expect("recorder buffers are never poisoned"));
+        let discarded = self.discarded.load(Ordering::Relaxed);
+        let len = channels.iter().map(Vec::len).min().unwrap_or(0);
+        let len = max_seconds.map_or(len, |seconds| {
+            len.min(((seconds * self.sample_rate as f64) as usize).saturating_sub(discarded))
+        });
+        for channel in &mut channels {
+            channel.truncate(len);
+        }
+        channels
+    }
+
+    fn shutdown(&mut self) {
+        let child = self.child.get_mut().expect("recorder process is never poisoned");
+        let _ = child.kill();
+        let _ = child.wait();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Plays `samples` to `output` while recording `input` (the system defaults when `None`),
+/// for measuring what a device chain or room does to a signal. The recording starts
+/// `WARM_UP` before playback and runs `TAIL` past it, so it holds the whole response
+/// whatever the latency.
+pub fn play_and_record(samples: &[f32], sample_rate: u32, input: Option<&str>, output: Option<String>) -> Result<Vec<f32>> {
+    let recorder = Recorder::start(input, sample_rate, 1)?;
+    std::thread::sleep(Duration::from_secs_f64(WARM_UP));
+    let mut player = Player::new(&samples.to_vec().into(), sample_rate).with_device(output);
+    player.play()?;
+    while player.is_playing() {
+        if !recorder.is_running() {
+            return Err(anyhow!("The recorder stopped while playing; check the input device"));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    std::thread::sleep(Duration::from_secs_f64(TAIL));
+    Ok(recorder.stop(None).swap_remove(0))
+}
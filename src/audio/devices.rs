@@ -0,0 +1,163 @@
+use anyhow::{anyhow, Result};
+use std::process::Command;
+
+use super::playback::find_program;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Capture,
+    Playback,
+}
+
+impl Direction {
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Capture => "capture",
+            Direction::Playback => "playback",
+        }
+    }
+}
+
+/// An audio device as a system tool reports it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Device {
+    /// What `--device` and `--output-device` take
+    pub name: String,
+    pub description: Option<String>,
+    pub direction: Direction,
+    /// Native format, where the system reports one
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+    /// The tool that listed it, which is also the one that can use the name
+    pub backend: &'static str,
+}
+
+/// Devices from the first system tool available: PulseAudio (`pactl`), ALSA (`arecord` and
+/// `aplay`), or ffmpeg's AVFoundation (macOS) or DirectShow (Windows) input lists.
+pub fn list() -> Result<Vec<Device>> {
+    if find_program("pactl") {
+        let mut devices = parse_pactl(&run("pactl", &["list", "short", "sources"])?, Direction::Capture);
+        devices.extend(parse_pactl(&run("pactl", &["list", "short", "sinks"])?, Direction::Playback));
+        return Ok(devices);
+    }
+    if find_program("arecord") || find_program("aplay") {
+        let mut devices = Vec::new();
+        if find_program("arecord") {
+            devices.extend(parse_alsa(&run("arecord", &["-l"])?, Direction::Capture));
+        }
+        if find_program("aplay") {
+            devices.extend(parse_alsa(&run("aplay", &["-l"])?, Direction::Playback));
+        }
+        return Ok(devices);
+    }
+    if find_program("ffmpeg") && (cfg!(target_os = "macos") || cfg!(windows)) {
+        let format = if cfg!(windows) { "dshow" } else { "avfoundation" };
+        // ffmpeg prints the list to stderr and then fails for lack of an input
+        let output = Command::new("ffmpeg")
+            .args(["-hide_banner", "-list_devices", "true", "-f", format, "-i", "dummy"])
+            .output()?;
+        return Ok(parse_ffmpeg(&String::from_utf8_lossy(&output.stderr)));
+    }
+    Err(anyhow!("No tool to list audio devices found (install pactl, alsa-utils or ffmpeg)"))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `pactl list short sources|sinks`: index, name, driver, sample spec (`s16le 2ch 48000Hz`)
+/// and state, tab-separated.
+pub fn parse_pactl(output: &str, direction: Direction) -> Vec<Device> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let name = fields.get(1)?.to_string();
+            let spec = fields.get(3).copied().unwrap_or_default();
+            let part = |suffix: &str| -> Option<usize> {
+                spec.split_whitespace().find_map(|part| part.strip_suffix(suffix)?.parse().ok())
+            };
+            // Monitors of outputs are capture devices that record what plays
+            let description = name.ends_with(".monitor").then(|| "monitor of an output".to_string());
+            Some(Device {
+                name,
+                description,
+                direction,
+                sample_rate: part("Hz").map(|rate| rate as u32),
+                channels: part("ch"),
+                backend: "pulse",
+            })
+        })
+        .collect()
+}
+
+/// `arecord -l` / `aplay -l`: `card 1: USB [USB Audio], device 0: USB Audio [USB Audio]`
+/// lines, named `hw:1,0`.
+pub fn parse_alsa(output: &str, direction: Direction) -> Vec<Device> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (card, device) = line.strip_prefix("card ")?.split_once(", device ")?;
+            let (card_number, card_name) = card.split_once(": ")?;
+            let (device_number, device_name) = device.split_once(": ")?;
+            let bracketed = |text: &str| {
+                let text = text.trim();
+                text.split_once('[').and_then(|(_, rest)| rest.strip_suffix(']')).unwrap_or(text).to_string()
+            };
+            Some(Device {
+                name: format!("hw:{},{}", card_number.trim(), device_number.trim()),
+                description: Some(format!("{}: {}", bracketed(card_name), bracketed(device_name))),
+                direction,
+                sample_rate: None,
+                channels: None,
+                backend: "alsa",
+            })
+        })
+        .collect()
+}
+
+/// The audio inputs in ffmpeg's `-list_devices` log: `[0] Name` after AVFoundation's
+/// "audio devices" header, or DirectShow's `"Name" (audio)`.
+pub fn parse_ffmpeg(log: &str) -> Vec<Device> {
+    let mut audio_section = false;
+    let mut devices = Vec::new();
+    for line in log.lines() {
+        // Drop the `[AVFoundation indev @ 0x...]` prefix
+        let message = line.split_once("] ").map_or(line, |(_, rest)| rest).trim();
+        if message.contains("audio devices:") {
+            audio_section = true;
+            continue;
+        }
+        if message.contains("video devices:") {
+            audio_section = false;
+            continue;
+        }
+        let name = if let Some(rest) = message.strip_suffix(" (audio)") {
+            // DirectShow names the input `audio=<name>`
+            Some(format!("audio={}", rest.trim_matches('"')))
+        } else if audio_section {
+            message
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once("] "))
+                .map(|(index, _)| format!(":{}", index))
+        } else {
+            None
+        };
+        let description = message.split_once("] ").map_or(message, |(_, rest)| rest).trim_matches('"');
+        if let Some(name) = name {
+            devices.push(Device {
+                name,
+                description: Some(description.trim_end_matches(" (audio)").trim_matches('"').to_string()),
+                direction: Direction::Capture,
+                sample_rate: None,
+                channels: None,
+                backend: "ffmpeg",
+            });
+        }
+    }
+    devices
+}
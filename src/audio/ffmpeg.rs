@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Context, Result};
+use std::ffi::OsString;
+use std::process::Command;
+
+use super::samples::SampleWriter;
+use super::source::Input;
+use super::{AudioData, AudioTags};
+
+/// Decodes any format ffmpeg understands by piping it out as interleaved f32 PCM.
+pub fn decode(input: &Input) -> Result<AudioData> {
+    let path: OsString = match input {
+        Input::File(path) => path.into(),
+        Input::Url(url) => url.into(),
+        Input::Stdin | Input::Memory(_) => {
+            return Err(anyhow!("ffmpeg fallback is unavailable for piped or in-memory input"))
+        }
+    };
+
+    let probe = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "a:0"])
+        .args(["-show_entries", "stream=sample_rate,channels,sample_fmt,bits_per_raw_sample:format_tags=title,artist,album"])
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(&path)
+        .output()
+        .context("Failed to run ffprobe (is ffmpeg installed?)")?;
+    if !probe.status.success() {
+        return Err(anyhow!("ffprobe failed: {}", String::from_utf8_lossy(&probe.stderr).trim()));
+    }
+
+    let mut sample_rate = None;
+    let mut channels = 1;
+    let mut bits_per_sample = None;
+    let mut double = false;
+    let mut tags = AudioTags::default();
+    for line in String::from_utf8_lossy(&probe.stdout).lines() {
+        match line.split_once('=') {
+            Some(("sample_rate", value)) => sample_rate = value.parse().ok(),
+            Some(("channels", value)) => channels = value.parse().unwrap_or(1),
+            Some(("bits_per_raw_sample", value)) => bits_per_sample = value.parse().ok(),
+            // `dbl` or planar `dblp`
+            Some(("sample_fmt", value)) => double = value.starts_with("dbl"),
+            Some(("TAG:title", value)) => tags.title = Some(value.to_string()),
+            Some(("TAG:artist", value)) => tags.artist = Some(value.to_string()),
+            Some(("TAG:album", value)) => tags.album = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    let sample_rate = sample_rate.ok_or_else(|| anyhow!("ffprobe found no audio stream"))?;
+    if double {
+        bits_per_sample = Some(64);
+    }
+
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-nostdin", "-i"])
+        .arg(&path)
+        .args(["-map", "0:a:0", "-f", "f32le", "-acodec", "pcm_f32le", "-"])
+        .output()
+        .context("Failed to run ffmpeg")?;
+    if !output.status.success() {
+        return Err(anyhow!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let channels: usize = channels.max(1);
+    let mut channel_samples: Vec<SampleWriter> = (0..channels).map(|_| SampleWriter::new()).collect();
+    for (i, b) in output.stdout.chunks_exact(4).enumerate() {
+        channel_samples[i % channels].push(f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    }
+
+    let mut channel_samples = channel_samples.into_iter().map(SampleWriter::finish).collect::<Result<Vec<_>>>()?;
+    let samples = channel_samples.remove(0);
+    Ok(AudioData {
+        samples,
+        extra_channels: channel_samples,
+        sample_rate,
+        channels,
+        bits_per_sample,
+        tags,
+    })
+}
@@ -0,0 +1,241 @@
+use std::f32::consts::PI;
+
+use super::AudioData;
+
+/// Second-order IIR section (RBJ audio EQ cookbook), direct form I.
+#[derive(Clone, Copy)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    fn from_coefficients(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    pub fn lowpass(sample_rate: u32, cutoff: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate as f32;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        Self::from_coefficients(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    pub fn highpass(sample_rate: u32, cutoff: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate as f32;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        Self::from_coefficients(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    /// Band-stop at `frequency`, about `frequency / q` wide.
+    pub fn notch(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        Self::from_coefficients(1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    pub fn high_shelf(sample_rate: u32, cutoff: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * cutoff / sample_rate as f32;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let sqrt_a = a.sqrt();
+        Self::from_coefficients(
+            a * ((a + 1.0) + (a - 1.0) * cos + 2.0 * sqrt_a * alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - 2.0 * sqrt_a * alpha),
+            (a + 1.0) - (a - 1.0) * cos + 2.0 * sqrt_a * alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - 2.0 * sqrt_a * alpha,
+        )
+    }
+
+    /// The two stages of the ITU-R BS.1770 K-weighting, a +4 dB shelf above ~1.7 kHz and a
+    /// highpass at ~38 Hz, designed from the analog prototype behind the standard's 48 kHz
+    /// coefficients so other rates get the same curve.
+    pub fn k_weighting(sample_rate: u32) -> [Self; 2] {
+        let rate = sample_rate as f64;
+        let k = (std::f64::consts::PI * 1681.974450955533 / rate).tan();
+        let (q, vh) = (0.7071752369554196, 10f64.powf(3.999843853973347 / 20.0));
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Self::from_coefficients(
+            ((vh + vb * k / q + k * k) / a0) as f32,
+            (2.0 * (k * k - vh) / a0) as f32,
+            ((vh - vb * k / q + k * k) / a0) as f32,
+            1.0,
+            (2.0 * (k * k - 1.0) / a0) as f32,
+            ((1.0 - k / q + k * k) / a0) as f32,
+        );
+        let k = (std::f64::consts::PI * 38.13547087602444 / rate).tan();
+        let q = 0.5003270373238773;
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Self::from_coefficients(
+            1.0,
+            -2.0,
+            1.0,
+            1.0,
+            (2.0 * (k * k - 1.0) / a0) as f32,
+            ((1.0 - k / q + k * k) / a0) as f32,
+        );
+        [shelf, highpass]
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Quality factor of `--notch` filters: 6 Hz wide at 60 Hz, narrow enough to leave the
+/// surrounding spectrum alone.
+pub const NOTCH_Q: f32 = 10.0;
+
+/// Removes the tones at `frequencies` from every channel with a cascade of notch filters.
+pub fn apply_notches(audio: &mut AudioData, frequencies: &[f32]) {
+    let nyquist = audio.sample_rate as f32 / 2.0;
+    let rate = audio.sample_rate;
+    for channel in std::iter::once(&mut audio.samples).chain(audio.extra_channels.iter_mut()) {
+        let mut filters: Vec<Biquad> = frequencies
+            .iter()
+            .filter(|&&f| f > 0.0 && f < nyquist)
+            .map(|&f| Biquad::notch(rate, f, NOTCH_Q))
+            .collect();
+        for sample in channel.iter_mut() {
+            *sample = filters.iter_mut().fold(*sample, |x, filter| filter.process(x));
+        }
+    }
+}
+
+/// Every `factor`th sample of `samples` (at `sample_rate`), low-passed first below the new
+/// Nyquist frequency so nothing above it aliases.
+pub fn decimate(samples: &[f32], sample_rate: u32, factor: usize) -> Vec<f32> {
+    if factor <= 1 {
+        return samples.to_vec();
+    }
+    let cutoff = sample_rate as f32 / factor as f32 * 0.45;
+    let mut stages = [Biquad::lowpass(sample_rate, cutoff, std::f32::consts::FRAC_1_SQRT_2); 2];
+    samples
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &x)| {
+            let y = stages.iter_mut().fold(x, |acc, stage| stage.process(acc));
+            (i % factor == 0).then_some(y)
+        })
+        .collect()
+}
+
+/// Band sibilants ("s", "sh", "t") put their energy in, in Hz.
+pub const SIBILANT_BAND: (f32, f32) = (4000.0, 10000.0);
+
+/// Level of the sibilant band above which a burst is harsh, in dBFS RMS.
+pub const SIBILANCE_THRESHOLD_DBFS: f32 = -35.0;
+
+/// Share of a frame's power that must fall in the sibilant band: a sibilant rather than a
+/// bright vowel or broadband noise.
+pub const SIBILANT_FRACTION: f32 = 0.5;
+
+/// Time constant for the de-esser's level detector to follow a rising burst, in seconds.
+const DE_ESS_ATTACK: f32 = 0.001;
+
+/// Time constant for the de-esser's level detector to let go after a burst, in seconds.
+const DE_ESS_RELEASE: f32 = 0.06;
+
+/// Highpass then lowpass isolating `SIBILANT_BAND`, clamped below Nyquist; `None` when the
+/// sample rate is too low to carry sibilance.
+pub fn sibilant_filter(sample_rate: u32) -> Option<(Biquad, Biquad)> {
+    let (low, high) = SIBILANT_BAND;
+    let high = high.min(sample_rate as f32 * 0.45);
+    (high > low * 1.25).then(|| {
+        (
+            Biquad::highpass(sample_rate, low, std::f32::consts::FRAC_1_SQRT_2),
+            Biquad::lowpass(sample_rate, high, std::f32::consts::FRAC_1_SQRT_2),
+        )
+    })
+}
+
+/// A copy of the audio with sibilant bursts turned down: whenever the sibilant band is above
+/// `SIBILANCE_THRESHOLD_DBFS` and dominates the signal, everything above the band's lower edge
+/// is reduced by the excess, by at most `depth_db`. The rest of the signal passes untouched.
+pub fn de_ess(audio: &AudioData, depth_db: f32) -> AudioData {
+    let rate = audio.sample_rate;
+    let coefficient = |seconds: f32| 1.0 - (-1.0 / (seconds * rate as f32)).exp();
+    let (attack, release) = (coefficient(DE_ESS_ATTACK), coefficient(DE_ESS_RELEASE));
+    let follow = |envelope: &mut f32, power: f32| {
+        let rate = if power > *envelope { attack } else { release };
+        *envelope += rate * (power - *envelope);
+    };
+    let mut channels: Vec<Vec<f32>> = (0..audio.channels.max(1))
+        .filter_map(|c| audio.channel(c))
+        .map(|channel| channel.to_vec())
+        .collect();
+    for channel in &mut channels {
+        let Some((mut band_high, mut band_low)) = sibilant_filter(rate) else {
+            break;
+        };
+        let mut split = Biquad::highpass(rate, SIBILANT_BAND.0, std::f32::consts::FRAC_1_SQRT_2);
+        let (mut band_power, mut total_power) = (0.0f32, 0.0f32);
+        for sample in channel.iter_mut() {
+            let x = *sample;
+            let band = band_low.process(band_high.process(x));
+            follow(&mut band_power, band * band);
+            follow(&mut total_power, x * x);
+            let level = 10.0 * band_power.max(1e-12).log10();
+            let reduction = if band_power > SIBILANT_FRACTION * total_power {
+                (level - SIBILANCE_THRESHOLD_DBFS).clamp(0.0, depth_db)
+            } else {
+                0.0
+            };
+            let high = split.process(x);
+            *sample = x - high * (1.0 - 10f32.powf(-reduction / 20.0));
+        }
+    }
+    let mut channels = channels.into_iter();
+    AudioData {
+        samples: channels.next().unwrap_or_default().into(),
+        extra_channels: channels.map(Into::into).collect(),
+        sample_rate: rate,
+        channels: audio.channels,
+        bits_per_sample: audio.bits_per_sample,
+        tags: audio.tags.clone(),
+    }
+}
@@ -0,0 +1,39 @@
+use clap::ValueEnum;
+use rustfft::num_traits::{self, FromPrimitive};
+use rustfft::FftNum;
+
+/// Sample type the STFT can run in: `f32`, or `f64` for lower numerical noise.
+pub trait Float: FftNum + num_traits::Float + FromPrimitive {
+    fn from_sample(value: f32) -> Self;
+    fn to_sample(self) -> f32;
+}
+
+impl Float for f32 {
+    fn from_sample(value: f32) -> Self {
+        value
+    }
+
+    fn to_sample(self) -> f32 {
+        self
+    }
+}
+
+impl Float for f64 {
+    fn from_sample(value: f32) -> Self {
+        value as f64
+    }
+
+    fn to_sample(self) -> f32 {
+        self as f32
+    }
+}
+
+/// Precision of windowing and FFTs. Samples are stored as `f32`, which holds 16- and 24-bit
+/// PCM exactly; `f64` keeps the transform's own rounding error out of components far below
+/// full scale.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Precision {
+    #[default]
+    F32,
+    F64,
+}
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+
+/// Fade in and out at the ends of every signal, so starting and stopping doesn't click,
+/// in seconds.
+const FADE: f64 = 0.01;
+
+/// Measurement signals `generate` produces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Signal {
+    /// Exponential (logarithmic) sine sweep, spending equal time in every octave
+    Sweep,
+    /// Noise with equal power in every octave
+    Pink,
+    /// Noise with equal power at every frequency
+    White,
+    /// Steady sine tone
+    Tone,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SignalParams {
+    /// Length in seconds
+    pub duration: f64,
+    pub sample_rate: u32,
+    /// Level in dBFS: the peak of sweeps and tones, the RMS of noise
+    pub level_db: f32,
+    /// Where a sweep starts, in Hz
+    pub from: f32,
+    /// Where a sweep ends, in Hz
+    pub to: f32,
+    /// Frequency of a tone, in Hz
+    pub frequency: f32,
+}
+
+impl Default for SignalParams {
+    fn default() -> Self {
+        Self { duration: 10.0, sample_rate: 48000, level_db: -12.0, from: 20.0, to: 20000.0, frequency: 1000.0 }
+    }
+}
+
+/// `signal` as mono samples, faded in and out over `FADE`.
+pub fn generate(signal: Signal, params: &SignalParams) -> Result<Vec<f32>> {
+    let rate = params.sample_rate as f64;
+    let nyquist = params.sample_rate as f32 / 2.0;
+    if params.duration <= 0.0 {
+        return Err(anyhow!("The duration must be positive"));
+    }
+    if params.level_db > 0.0 {
+        return Err(anyhow!("A level of {} dBFS would clip; use 0 or less", params.level_db));
+    }
+    let len = (params.duration * rate) as usize;
+    let amplitude = 10f32.powf(params.level_db / 20.0);
+    let mut samples: Vec<f32> = match signal {
+        Signal::Sweep => {
+            if !(params.from > 0.0 && params.from < params.to && params.to <= nyquist) {
+                return Err(anyhow!(
+                    "A sweep needs 0 < from < to <= {} Hz (Nyquist), got {} to {} Hz",
+                    nyquist,
+                    params.from,
+                    params.to
+                ));
+            }
+            // Farina's exponential sweep: the phase integrates a frequency that grows by
+            // the same ratio every second
+            let (from, to) = (params.from as f64, params.to as f64);
+            let rate_of_growth = (to / from).ln() / params.duration;
+            (0..len)
+                .map(|i| {
+                    let t = i as f64 / rate;
+                    let phase = std::f64::consts::TAU * from * ((rate_of_growth * t).exp() - 1.0) / rate_of_growth;
+                    amplitude * phase.sin() as f32
+                })
+                .collect()
+        }
+        Signal::Tone => {
+            if !(params.frequency > 0.0 && params.frequency < nyquist) {
+                return Err(anyhow!("A tone needs 0 < frequency < {} Hz (Nyquist), got {} Hz", nyquist, params.frequency));
+            }
+            let step = std::f64::consts::TAU * params.frequency as f64 / rate;
+            (0..len).map(|i| amplitude * (step * i as f64).sin() as f32).collect()
+        }
+        Signal::White | Signal::Pink => {
+            let mut random = Xorshift(0x2545_f491_4f6c_dd1d);
+            let white = (0..len).map(|_| random.next_f32() * 2.0 - 1.0);
+            let noise: Vec<f32> = match signal {
+                Signal::Pink => pink(white),
+                _ => white.collect(),
+            };
+            let rms = (noise.iter().map(|s| s * s).sum::<f32>() / len.max(1) as f32).sqrt();
+            let gain = amplitude / rms.max(f32::MIN_POSITIVE);
+            // Noise peaks several times its RMS; past full scale they're clipped
+            noise.iter().map(|s| (s * gain).clamp(-1.0, 1.0)).collect()
+        }
+    };
+    let fade = ((FADE * rate) as usize).min(len / 2);
+    for i in 0..fade {
+        let gain = 0.5 - 0.5 * (std::f32::consts::PI * i as f32 / fade as f32).cos();
+        let last = len - 1 - i;
+        samples[i] *= gain;
+        samples[last] *= gain;
+    }
+    Ok(samples)
+}
+
+/// White noise filtered to fall 3 dB per octave, by Paul Kellet's economy filter (within
+/// 0.05 dB of pink above 10 Hz at 44.1 kHz).
+fn pink(white: impl Iterator<Item = f32>) -> Vec<f32> {
+    let mut b = [0.0f32; 7];
+    white
+        .map(|w| {
+            b[0] = 0.99886 * b[0] + w * 0.0555179;
+            b[1] = 0.99332 * b[1] + w * 0.0750759;
+            b[2] = 0.96900 * b[2] + w * 0.153852;
+            b[3] = 0.86650 * b[3] + w * 0.3104856;
+            b[4] = 0.55000 * b[4] + w * 0.5329522;
+            b[5] = -0.7616 * b[5] - w * 0.0168980;
+            let pink = b.iter().sum::<f32>() + w * 0.5362;
+            b[6] = w * 0.115926;
+            pink
+        })
+        .collect()
+}
+
+/// Small, fixed-seed generator: measurement noise only needs to be white, and the same
+/// every run.
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        // The top 24 bits, as many as an f32 holds exactly
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
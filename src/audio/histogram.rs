@@ -0,0 +1,46 @@
+/// Width of a histogram bin, in dB.
+pub const BIN_DB: f32 = 0.5;
+
+/// How the levels of a spectrogram are distributed: counts of its values in `BIN_DB` bins
+/// from the lowest one up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelHistogram {
+    /// Level at the bottom of the first bin
+    pub low: f32,
+    pub counts: Vec<usize>,
+    pub total: usize,
+}
+
+impl LevelHistogram {
+    /// Histogram of `frames`' first `bins` bins; values that aren't finite (silence at
+    /// -inf dB) are left out.
+    pub fn new(frames: &[Vec<f32>], bins: usize) -> Self {
+        let levels = || frames.iter().flat_map(|frame| &frame[..bins.min(frame.len())]).filter(|l| l.is_finite());
+        let (low, high) = levels().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &l| (lo.min(l), hi.max(l)));
+        if low > high {
+            return Self::default();
+        }
+        let mut counts = vec![0; ((high - low) / BIN_DB) as usize + 1];
+        for &level in levels() {
+            counts[((level - low) / BIN_DB) as usize] += 1;
+        }
+        let total = counts.iter().sum();
+        Self { low, counts, total }
+    }
+
+    /// Level below which `percent` of the values lie, to within a bin; `None` when empty.
+    pub fn percentile(&self, percent: f32) -> Option<f32> {
+        if self.total == 0 {
+            return None;
+        }
+        let target = (self.total as f32 * percent.clamp(0.0, 100.0) / 100.0).ceil() as usize;
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return Some(self.low + (index as f32 + 1.0) * BIN_DB);
+            }
+        }
+        Some(self.low + self.counts.len() as f32 * BIN_DB)
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::filter;
+use super::AudioData;
+
+/// Highest sample rate kept after demodulation; wideband IQ is decimated down to this.
+const AUDIO_RATE: u32 = 48000;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Demodulation {
+    Am,
+    Fm,
+    Usb,
+    Lsb,
+}
+
+/// Demodulates a two-channel IQ recording (I = channel 0, Q = channel 1) into mono audio.
+pub fn demodulate(iq: &AudioData, mode: Demodulation) -> Result<AudioData> {
+    let q = iq
+        .channel(1)
+        .ok_or_else(|| anyhow!("IQ input needs two channels (I and Q), found {}", iq.channels))?;
+    let signal: Vec<Complex<f32>> = iq
+        .samples
+        .iter()
+        .zip(q.iter())
+        .map(|(&i, &q)| Complex::new(i, q))
+        .collect();
+
+    let audio = match mode {
+        Demodulation::Am => {
+            let envelope: Vec<f32> = signal.iter().map(|z| z.norm()).collect();
+            let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+            envelope.iter().map(|&e| e - mean).collect()
+        }
+        Demodulation::Fm => {
+            let mut previous = Complex::new(1.0, 0.0);
+            signal
+                .iter()
+                .map(|&z| {
+                    let phase = (z * previous.conj()).arg();
+                    previous = z;
+                    phase / std::f32::consts::PI
+                })
+                .collect()
+        }
+        Demodulation::Usb => single_sideband(signal, true),
+        Demodulation::Lsb => single_sideband(signal, false),
+    };
+
+    let (samples, sample_rate) = decimate(audio, iq.sample_rate);
+    Ok(AudioData {
+        samples: samples.into(),
+        extra_channels: Vec::new(),
+        sample_rate,
+        channels: 1,
+        bits_per_sample: None,
+        tags: iq.tags.clone(),
+    })
+}
+
+/// Keeps one sideband by zeroing the other half of the spectrum, then takes the real part.
+fn single_sideband(mut signal: Vec<Complex<f32>>, upper: bool) -> Vec<f32> {
+    let len = signal.len();
+    if len == 0 {
+        return Vec::new();
+    }
+    let mut planner = FftPlanner::new();
+    planner.plan_fft_forward(len).process(&mut signal);
+    for (k, bin) in signal.iter_mut().enumerate().skip(1) {
+        let positive = k < len.div_ceil(2);
+        if positive != upper {
+            *bin = Complex::new(0.0, 0.0);
+        }
+    }
+    planner.plan_fft_inverse(len).process(&mut signal);
+    signal.iter().map(|z| z.re / len as f32).collect()
+}
+
+/// Low-pass filters and decimates to at most `AUDIO_RATE`.
+fn decimate(samples: Vec<f32>, sample_rate: u32) -> (Vec<f32>, u32) {
+    let factor = sample_rate.div_ceil(AUDIO_RATE) as usize;
+    if factor <= 1 {
+        return (samples, sample_rate);
+    }
+    (filter::decimate(&samples, sample_rate, factor), sample_rate / factor as u32)
+}
@@ -1,17 +1,145 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use rustfft::{FftPlanner, num_complex::Complex};
-use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::codecs::{
+    DecoderOptions, CODEC_TYPE_PCM_F32BE, CODEC_TYPE_PCM_F32LE, CODEC_TYPE_PCM_F64BE, CODEC_TYPE_PCM_F64LE,
+};
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
 use symphonia::core::probe::Hint;
 use symphonia::core::audio::Signal;
-use std::fs::File;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+pub mod capture;
+pub mod devices;
+#[cfg(feature = "ffmpeg")]
+pub mod ffmpeg;
+pub mod filter;
+pub mod float;
+pub mod generator;
+pub mod histogram;
+pub mod iq;
+pub mod playback;
+pub mod psd;
+pub mod pyramid;
+pub mod raw;
+pub mod samples;
+pub mod source;
+pub mod spectrum;
+pub mod stft;
+pub mod stretch;
+pub mod tracks;
+pub mod units;
+pub mod weighting;
+pub mod window;
+
+use samples::{SampleWriter, Samples};
+use source::Input;
+use units::BinScale;
+pub use float::{Float, Precision};
+pub use stft::{istft, Stft};
+pub use units::{Normalization, Units};
+pub use window::{SpectrogramParams, WindowFunction};
 
 pub struct AudioData {
-    pub samples: Vec<f32>,
+    /// First channel, which every analysis works on.
+    pub samples: Samples,
+    /// Remaining channels (1..channels) for stereo/IQ-aware processing.
+    pub extra_channels: Vec<Samples>,
     pub sample_rate: u32,
+    pub channels: usize,
+    /// Bits per sample of integer PCM and of 64-bit float sources; `None` for `f32` and
+    /// lossy sources, which the samples hold exactly
+    pub bits_per_sample: Option<u32>,
+    pub tags: AudioTags,
+}
+
+impl AudioData {
+    /// Whether the source had more precision than the `f32` samples keep (24 bits): 32-bit
+    /// integer or 64-bit float PCM.
+    pub fn wider_than_f32(&self) -> bool {
+        self.bits_per_sample.is_some_and(|bits| bits > 24)
+    }
+
+    pub fn channel(&self, index: usize) -> Option<&[f32]> {
+        match index {
+            0 => Some(&self.samples),
+            _ => self.extra_channels.get(index - 1).map(|channel| &channel[..]),
+        }
+    }
+
+    /// Replaces left and right with mid ((L+R)/2) and side ((L-R)/2).
+    pub fn to_mid_side(&mut self) -> Result<()> {
+        let right = self
+            .extra_channels
+            .first_mut()
+            .ok_or_else(|| anyhow!("Mid/side analysis needs a stereo input"))?;
+        for (left, right) in self.samples.iter_mut().zip(right.iter_mut()) {
+            (*left, *right) = ((*left + *right) / 2.0, (*left - *right) / 2.0);
+        }
+        Ok(())
+    }
+
+    /// Channel `index` on its own, with the same rate and tags.
+    pub fn mono(&self, index: usize) -> Option<AudioData> {
+        Some(AudioData {
+            samples: self.channel(index)?.to_vec().into(),
+            extra_channels: Vec::new(),
+            sample_rate: self.sample_rate,
+            channels: 1,
+            bits_per_sample: self.bits_per_sample,
+            tags: self.tags.clone(),
+        })
+    }
+
+    /// The first channel decimated by `factor` (see `filter::decimate`), at the rate that
+    /// leaves.
+    pub fn decimated(&self, factor: usize) -> AudioData {
+        AudioData {
+            samples: filter::decimate(&self.samples, self.sample_rate, factor).into(),
+            extra_channels: Vec::new(),
+            sample_rate: self.sample_rate / factor.max(1) as u32,
+            channels: 1,
+            bits_per_sample: self.bits_per_sample,
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+/// The largest factor that decimates `sample_rate` evenly to `rate` or more, so times stay
+/// exact: 48 from 48 kHz to 1 kHz, 42 from 44.1 kHz (to 1050 Hz).
+pub fn decimation_factor(sample_rate: u32, rate: u32) -> usize {
+    (1..=sample_rate / rate.max(1))
+        .rev()
+        .find(|&factor| sample_rate.is_multiple_of(factor))
+        .unwrap_or(1) as usize
+}
+
+/// Descriptive tags read from ID3, Vorbis comments, RIFF INFO and similar.
+#[derive(Clone, Default)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
+impl AudioTags {
+    /// Fills in any tags still missing from a symphonia metadata revision.
+    fn merge(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let slot = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => &mut self.title,
+                Some(StandardTagKey::Artist) => &mut self.artist,
+                Some(StandardTagKey::Album) => &mut self.album,
+                _ => continue,
+            };
+            let value = tag.value.to_string();
+            if slot.is_none() && !value.trim().is_empty() {
+                *slot = Some(value.trim().to_string());
+            }
+        }
+    }
 }
 
 pub struct SpectrogramData {
@@ -20,115 +148,269 @@ pub struct SpectrogramData {
     pub magnitudes: Vec<Vec<f32>>,
 }
 
+/// Loads audio from a file path, `-` for stdin, or an http(s) URL.
 pub fn load_audio<P: AsRef<Path>>(path: P) -> Result<AudioData> {
-    let file = File::open(path)?;
-    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    decode(Input::parse(path.as_ref()))
+}
+
+/// Decodes an encoded file (WAV, MP3, ...) held in memory.
+pub fn load_audio_bytes(bytes: Vec<u8>) -> Result<AudioData> {
+    decode(Input::Memory(bytes))
+}
+
+fn decode(input: Input) -> Result<AudioData> {
+    match decode_symphonia(&input) {
+        Ok(audio_data) => Ok(audio_data),
+        #[cfg(feature = "ffmpeg")]
+        Err(err) => {
+            log::warn!("symphonia could not decode {}: {}, trying ffmpeg", input, err);
+            ffmpeg::decode(&input).map_err(|e| anyhow!("Failed to decode audio ({}; ffmpeg: {})", err, e))
+        }
+        #[cfg(not(feature = "ffmpeg"))]
+        Err(err) => Err(err),
+    }
+}
+
+fn decode_symphonia(input: &Input) -> Result<AudioData> {
+    let mss = MediaSourceStream::new(input.open()?, Default::default());
 
     let hint = Hint::new();
     let format_opts = FormatOptions::default();
     let metadata_opts = MetadataOptions::default();
     let decoder_opts = DecoderOptions::default();
 
-    let probed = symphonia::default::get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
+    let mut probed = symphonia::default::get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
+    let mut tags = AudioTags::default();
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        tags.merge(revision);
+    }
     let mut format = probed.format;
-    let track = format.default_track().unwrap();
+    if let Some(revision) = format.metadata().current() {
+        tags.merge(revision);
+    }
+    let track = format.default_track().ok_or_else(|| anyhow!("No audio track found"))?;
     let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &decoder_opts)?;
 
-    let mut samples = Vec::new();
-    let sample_rate = track.codec_params.sample_rate.unwrap();
+    let sample_rate = track.codec_params.sample_rate.ok_or_else(|| anyhow!("Unknown sample rate"))?;
+    let channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(1).max(1);
+    let mut channel_samples: Vec<SampleWriter> = (0..channels).map(|_| SampleWriter::new()).collect();
+    let bits_per_sample = match track.codec_params.codec {
+        CODEC_TYPE_PCM_F32LE | CODEC_TYPE_PCM_F32BE => None,
+        CODEC_TYPE_PCM_F64LE | CODEC_TYPE_PCM_F64BE => Some(64),
+        _ => track.codec_params.bits_per_sample,
+    };
 
     while let Ok(packet) = format.next_packet() {
         let decoded = decoder.decode(&packet)?;
         match decoded {
             symphonia::core::audio::AudioBufferRef::F32(buf) => {
-                samples.extend_from_slice(buf.chan(0));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend_from_slice(buf.chan(ch));
+                }
             },
             symphonia::core::audio::AudioBufferRef::F64(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| x as f32));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| x as f32));
+                }
             },
             symphonia::core::audio::AudioBufferRef::U8(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| (x as f32 / 128.0) - 1.0));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| (x as f32 / 128.0) - 1.0));
+                }
             },
             symphonia::core::audio::AudioBufferRef::U16(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| (x as f32 / 32768.0) - 1.0));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| (x as f32 / 32768.0) - 1.0));
+                }
             },
             symphonia::core::audio::AudioBufferRef::U24(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| {
-                    let value = x.inner() as u32;
-                    (value as f32 / 8388608.0) - 1.0
-                }));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| {
+                        let value = x.inner();
+                        (value as f32 / 8388608.0) - 1.0
+                    }));
+                }
             },
             symphonia::core::audio::AudioBufferRef::U32(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| (x as f32 / 2147483648.0) - 1.0));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| (x as f32 / 2147483648.0) - 1.0));
+                }
             },
             symphonia::core::audio::AudioBufferRef::S8(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| x as f32 / 128.0));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| x as f32 / 128.0));
+                }
             },
             symphonia::core::audio::AudioBufferRef::S16(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| x as f32 / 32768.0));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| x as f32 / 32768.0));
+                }
             },
             symphonia::core::audio::AudioBufferRef::S24(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| {
-                    let value = x.inner() as i32;
-                    value as f32 / 8388608.0
-                }));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| {
+                        let value = x.inner();
+                        value as f32 / 8388608.0
+                    }));
+                }
             },
             symphonia::core::audio::AudioBufferRef::S32(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| x as f32 / 2147483648.0));
+                for (ch, out) in channel_samples.iter_mut().enumerate().take(buf.spec().channels.count()) {
+                    out.extend(buf.chan(ch).iter().map(|&x| x as f32 / 2147483648.0));
+                }
             },
         }
     }
 
+    let mut channel_samples = channel_samples.into_iter().map(SampleWriter::finish).collect::<Result<Vec<_>>>()?;
+    let samples = channel_samples.remove(0);
     Ok(AudioData {
         samples,
+        extra_channels: channel_samples,
         sample_rate,
+        channels,
+        bits_per_sample,
+        tags,
     })
 }
 
 pub fn compute_spectrogram(audio_data: &AudioData, window_size: usize) -> Result<SpectrogramData> {
-    let mut planner = FftPlanner::new();
-    let fft = planner.plan_fft_forward(window_size);
-    
-    let hop_size = window_size / 2;
-    let num_frames = (audio_data.samples.len() - window_size) / hop_size;
-    
-    let mut magnitudes = Vec::with_capacity(num_frames);
-    let mut time_points = Vec::with_capacity(num_frames);
-    
-    let window = hann_window(window_size);
-    
-    for frame_idx in 0..num_frames {
-        let start = frame_idx * hop_size;
-        let mut frame: Vec<Complex<f32>> = audio_data.samples[start..start + window_size]
-            .iter()
-            .zip(window.iter())
-            .map(|(&s, &w)| Complex::new(s * w, 0.0))
-            .collect();
-            
-        fft.process(&mut frame);
-        
-        let magnitude: Vec<f32> = frame[..window_size/2]
-            .iter()
-            .map(|c| (c.norm() / window_size as f32).log10() * 20.0)
-            .collect();
-            
-        magnitudes.push(magnitude);
-        time_points.push(start as f32 / audio_data.sample_rate as f32);
+    compute_spectrogram_with(audio_data, &SpectrogramParams::new(window_size), None)
+}
+
+/// Levels in `params.units` of the frames starting at `starts`, windowed and transformed
+/// in `T`.
+fn stft<T: Float>(
+    samples: &[f32],
+    params: &SpectrogramParams,
+    sample_rate: u32,
+    starts: impl Iterator<Item = usize>,
+) -> Vec<Vec<f32>> {
+    let window_size = params.window_size;
+    let fft = FftPlanner::<T>::new().plan_fft_forward(window_size);
+    let window = params.window.coefficients_in::<T>(window_size);
+    let scale = BinScale::new(params, sample_rate);
+    let constant = |value: f64| T::from_f64(value).expect("scale factors fit any float");
+    let (gain, dc_gain) = (constant(scale.gain(1)), constant(scale.gain(0)));
+    let (twenty, calibration) = (constant(20.0), constant(params.calibration as f64));
+    let zero = T::from_sample(0.0);
+    starts
+        .map(|start| {
+            let mut frame: Vec<Complex<T>> = samples[start..start + window_size]
+                .iter()
+                .zip(window.iter())
+                .map(|(&s, &w)| Complex::new(T::from_sample(s) * w, zero))
+                .collect();
+
+            fft.process(&mut frame);
+
+            frame[..window_size / 2]
+                .iter()
+                .enumerate()
+                .map(|(bin, c)| {
+                    let gain = if bin == 0 { dc_gain } else { gain };
+                    ((c.norm() * gain).log10() * twenty + calibration).to_sample()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Starts of the frames a spectrogram computes: every `hop_size` samples, or with
+/// `max_frames` only that many spread evenly over the signal.
+fn frame_starts(len: usize, params: &SpectrogramParams, max_frames: Option<usize>) -> Result<Vec<usize>> {
+    let window_size = params.window_size;
+    if window_size < 2 {
+        return Err(anyhow!("Window size must be at least 2, got {}", window_size));
+    }
+    let hop_size = params.hop_size.max(1);
+    let num_frames = len.saturating_sub(window_size) / hop_size;
+    let stride = max_frames.map_or(1, |max| num_frames.div_ceil(max.max(1)).max(1));
+    Ok((0..num_frames).step_by(stride).map(|frame_idx| frame_idx * hop_size).collect())
+}
+
+fn bin_frequencies(window_size: usize, sample_rate: u32) -> Vec<f32> {
+    (0..window_size / 2)
+        .map(|i| i as f32 * sample_rate as f32 / window_size as f32)
+        .collect()
+}
+
+/// STFT with explicit parameters. With `max_frames`, frames are spread evenly over the
+/// signal and only those are computed: enough for a display `max_frames` columns wide,
+/// without paying for frames that would never be drawn.
+pub fn compute_spectrogram_with(
+    audio_data: &AudioData,
+    params: &SpectrogramParams,
+    max_frames: Option<usize>,
+) -> Result<SpectrogramData> {
+    if params.decimation > 1 {
+        let decimated = audio_data.decimated(params.decimation);
+        return compute_spectrogram_with(&decimated, &SpectrogramParams { decimation: 1, ..*params }, max_frames);
     }
-    
-    let frequencies: Vec<f32> = (0..window_size/2)
-        .map(|i| i as f32 * audio_data.sample_rate as f32 / window_size as f32)
-        .collect();
-        
+    let starts = frame_starts(audio_data.samples.len(), params, max_frames)?;
+    let frames = starts.iter().copied();
+    let magnitudes = match params.precision {
+        Precision::F32 => stft::<f32>(&audio_data.samples, params, audio_data.sample_rate, frames),
+        Precision::F64 => stft::<f64>(&audio_data.samples, params, audio_data.sample_rate, frames),
+    };
     Ok(SpectrogramData {
-        time_points,
-        frequencies,
+        time_points: starts.iter().map(|&start| start as f32 / audio_data.sample_rate as f32).collect(),
+        frequencies: bin_frequencies(params.window_size, audio_data.sample_rate),
         magnitudes,
     })
 }
 
-fn hann_window(size: usize) -> Vec<f32> {
-    (0..size)
-        .map(|i| 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / (size - 1) as f32).cos()))
-        .collect()
-} 
\ No newline at end of file
+/// A display spectrogram (as `compute_spectrogram_with` with `max_frames`) to compute on
+/// another thread. Only the samples under its frames are copied, so starting one doesn't
+/// cost a copy of the whole signal.
+pub struct SpectrogramJob {
+    params: SpectrogramParams,
+    sample_rate: u32,
+    starts: Vec<usize>,
+    /// The samples of each frame, one after another
+    samples: Vec<f32>,
+}
+
+impl SpectrogramJob {
+    pub fn new(audio_data: &AudioData, params: &SpectrogramParams, max_frames: usize) -> Result<Self> {
+        if params.decimation > 1 {
+            let decimated = audio_data.decimated(params.decimation);
+            return Self::new(&decimated, &SpectrogramParams { decimation: 1, ..*params }, max_frames);
+        }
+        let starts = frame_starts(audio_data.samples.len(), params, Some(max_frames))?;
+        let samples = starts
+            .iter()
+            .flat_map(|&start| &audio_data.samples[start..start + params.window_size])
+            .copied()
+            .collect();
+        Ok(Self { params: *params, sample_rate: audio_data.sample_rate, starts, samples })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Computes the frames, counting each in `progress`; stops early, with the frames done
+    /// so far, once `cancelled` is set.
+    pub fn run(&self, progress: &AtomicUsize, cancelled: &AtomicBool) -> SpectrogramData {
+        let window_size = self.params.window_size;
+        let frames = (0..self.starts.len())
+            .take_while(|_| !cancelled.load(Ordering::Relaxed))
+            .inspect(|_| {
+                progress.fetch_add(1, Ordering::Relaxed);
+            })
+            .map(|i| i * window_size);
+        let magnitudes = match self.params.precision {
+            Precision::F32 => stft::<f32>(&self.samples, &self.params, self.sample_rate, frames),
+            Precision::F64 => stft::<f64>(&self.samples, &self.params, self.sample_rate, frames),
+        };
+        SpectrogramData {
+            time_points: self.starts[..magnitudes.len()]
+                .iter()
+                .map(|&start| start as f32 / self.sample_rate as f32)
+                .collect(),
+            frequencies: bin_frequencies(window_size, self.sample_rate),
+            magnitudes,
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::{anyhow, Context, Result};
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+use super::samples::Samples;
+use super::stretch::TimeStretch;
+
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 2.0;
+
+/// Output hops written to the player per block.
+const BLOCK_HOPS: usize = 16;
+
+/// Plays mono f32 audio by piping it into the first available system player.
+pub struct Player {
+    samples: Arc<Samples>,
+    sample_rate: u32,
+    speed: f64,
+    /// How many times slower than recorded the audio is played, pitch and all
+    time_expansion: f64,
+    position: f64,
+    loop_region: Option<(f64, f64)>,
+    /// Output device, as the player names it; the system default when `None`
+    device: Option<String>,
+    session: Option<Session>,
+}
+
+struct Session {
+    child: Child,
+    started: Instant,
+    from: f64,
+    stop: Arc<AtomicBool>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Player {
+    pub fn new(samples: &Samples, sample_rate: u32) -> Self {
+        Self {
+            samples: Arc::new(samples.view()),
+            sample_rate,
+            speed: 1.0,
+            time_expansion: 1.0,
+            position: 0.0,
+            loop_region: None,
+            device: None,
+            session: None,
+        }
+    }
+
+    /// Plays to `device` (a PulseAudio sink, an ALSA device such as `hw:1`, ...) instead of
+    /// the system default.
+    pub fn with_device(mut self, device: Option<String>) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Plays `factor` times slower than recorded, as a bat detector's time expansion does:
+    /// the samples go out at the sample rate divided by `factor`, so ultrasound comes down
+    /// into hearing by the same factor.
+    pub fn with_time_expansion(mut self, factor: f64) -> Self {
+        self.time_expansion = factor.max(1.0);
+        self
+    }
+
+    pub fn time_expansion(&self) -> f64 {
+        self.time_expansion
+    }
+
+    pub fn device(&self) -> Option<&str> {
+        self.device.as_deref()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.sample_rate as f64
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn loop_region(&self) -> Option<(f64, f64)> {
+        self.loop_region
+    }
+
+    pub fn is_playing(&mut self) -> bool {
+        if self.session.is_some() && self.loop_region.is_none() && self.position() >= self.duration() {
+            self.stop();
+            self.position = self.duration();
+        }
+        self.session.is_some()
+    }
+
+    /// Current playhead in source seconds (independent of playback speed).
+    pub fn position(&self) -> f64 {
+        match &self.session {
+            Some(session) => {
+                let elapsed = session.started.elapsed().as_secs_f64() * self.speed / self.time_expansion;
+                match self.loop_region {
+                    Some((start, end)) if end > start => {
+                        start + (session.from - start + elapsed) % (end - start)
+                    }
+                    _ => (session.from + elapsed).min(self.duration()),
+                }
+            }
+            None => self.position,
+        }
+    }
+
+    pub fn play(&mut self) -> Result<()> {
+        if self.session.is_some() {
+            return Ok(());
+        }
+        if self.position >= self.duration() {
+            self.position = 0.0;
+        }
+
+        let output_rate = (self.sample_rate as f64 / self.time_expansion).round().max(1.0) as u32;
+        let mut child = spawn_backend(output_rate, self.device.as_deref())?;
+        let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("Player has no stdin"))?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let to_index = |seconds: f64| (seconds * self.sample_rate as f64) as usize;
+        let start = to_index(self.position);
+        let region = self.loop_region.map(|(from, to)| (to_index(from), to_index(to)));
+        let (samples, sample_rate, speed) = (self.samples.clone(), self.sample_rate, self.speed);
+        let mut stretch = TimeStretch::new(samples.clone(), sample_rate, start, speed);
+        if let Some((_, end)) = region {
+            stretch = stretch.with_end(end);
+        }
+        let writer_stop = stop.clone();
+        let writer = std::thread::spawn(move || {
+            let mut restarted = false;
+            while !writer_stop.load(Ordering::Relaxed) {
+                let Some(block) = stretch.next_block(BLOCK_HOPS) else {
+                    match region {
+                        // A region too short to yield any output would spin forever
+                        Some((from, to)) if !restarted => {
+                            stretch = TimeStretch::new(samples.clone(), sample_rate, from, speed).with_end(to);
+                            restarted = true;
+                            continue;
+                        }
+                        _ => break,
+                    }
+                };
+                restarted = false;
+                let bytes: Vec<u8> = block.iter().flat_map(|s| s.to_le_bytes()).collect();
+                if stdin.write_all(&bytes).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.session = Some(Session {
+            child,
+            started: Instant::now(),
+            from: self.position,
+            stop,
+            writer: Some(writer),
+        });
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        self.position = self.position();
+        self.stop();
+    }
+
+    pub fn toggle(&mut self) -> Result<()> {
+        if self.is_playing() {
+            self.pause();
+            Ok(())
+        } else {
+            self.play()
+        }
+    }
+
+    pub fn seek(&mut self, seconds: f64) -> Result<()> {
+        let playing = self.is_playing();
+        self.stop();
+        self.position = seconds.clamp(0.0, self.duration());
+        if playing {
+            self.play()?;
+        }
+        Ok(())
+    }
+
+    /// Changes the playback speed (clamped to 0.5×–2×), restarting playback at the playhead.
+    pub fn set_speed(&mut self, speed: f64) -> Result<()> {
+        let position = self.position();
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        self.seek(position)
+    }
+
+    /// Loops playback over `region` (start, end in seconds), or plays straight through with
+    /// `None`. A playhead outside the region jumps to its start.
+    pub fn set_loop(&mut self, region: Option<(f64, f64)>) -> Result<()> {
+        let position = self.position();
+        self.loop_region = region.filter(|(start, end)| end > start);
+        match self.loop_region {
+            Some((start, end)) if !(start..end).contains(&position) => self.seek(start),
+            _ => self.seek(position),
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut session) = self.session.take() {
+            session.stop.store(true, Ordering::Relaxed);
+            let _ = session.child.kill();
+            let _ = session.child.wait();
+            if let Some(writer) = session.writer.take() {
+                let _ = writer.join();
+            }
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Candidate players reading raw mono f32 little-endian PCM from stdin, with the device
+/// when one is given.
+fn backend_commands(sample_rate: u32, device: Option<&str>) -> Vec<(&'static str, Vec<String>)> {
+    let rate = sample_rate.to_string();
+    let mut paplay = vec!["--raw".into(), "--format=float32le".into(), format!("--rate={}", rate), "--channels=1".into()];
+    let mut aplay = vec!["-q".into(), "-t".into(), "raw".into(), "-f".into(), "FLOAT_LE".into(), "-r".into(), rate.clone(), "-c".into(), "1".into()];
+    if let Some(device) = device {
+        paplay.push(format!("--device={}", device));
+        aplay.extend(["-D".into(), device.to_string()]);
+    }
+    // sox picks its device from AUDIODEV, which `spawn_backend` sets; ffplay can't pick one
+    vec![
+        ("paplay", paplay),
+        ("aplay", aplay),
+        ("play", vec!["-q".into(), "-t".into(), "raw".into(), "-e".into(), "floating-point".into(), "-b".into(), "32".into(), "-r".into(), rate.clone(), "-c".into(), "1".into(), "-".into()]),
+        ("ffplay", vec!["-nodisp".into(), "-autoexit".into(), "-loglevel".into(), "quiet".into(), "-f".into(), "f32le".into(), "-ar".into(), rate, "-ch_layout".into(), "mono".into(), "-i".into(), "-".into()]),
+    ]
+}
+
+/// The player playback would use, the first of `backend_commands` on the PATH.
+pub fn backend() -> Option<&'static str> {
+    backend_commands(0, None)
+        .into_iter()
+        .map(|(program, _)| program)
+        .find(|program| find_program(program))
+}
+
+fn spawn_backend(sample_rate: u32, device: Option<&str>) -> Result<Child> {
+    let program = backend().ok_or_else(|| anyhow!("No audio player found (install paplay, aplay, sox or ffplay)"))?;
+    let args = backend_commands(sample_rate, device)
+        .into_iter()
+        .find_map(|(name, args)| (name == program).then_some(args))
+        .expect("backend is one of the commands");
+    let mut command = Command::new(program);
+    if let (Some(device), "play") = (device, program) {
+        command.env("AUDIODEV", device);
+    }
+    command
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program))
+}
+
+pub fn find_program(name: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| {
+            let candidate = dir.join(name);
+            candidate.is_file() || candidate.with_extension("exe").is_file()
+        })
+    })
+}
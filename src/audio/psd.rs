@@ -0,0 +1,209 @@
+use anyhow::{bail, Result};
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::WindowFunction;
+
+/// Welch estimate settings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WelchParams {
+    /// Samples per segment; also the FFT size
+    pub segment_size: usize,
+    /// Fraction of each segment shared with the next, from 0 up to (not including) 1
+    pub overlap: f64,
+    pub window: WindowFunction,
+    /// Coverage of the confidence interval, e.g. 0.95
+    pub confidence: f64,
+}
+
+impl Default for WelchParams {
+    fn default() -> Self {
+        Self { segment_size: 4096, overlap: 0.5, window: WindowFunction::Hann, confidence: 0.95 }
+    }
+}
+
+impl WelchParams {
+    fn step(&self) -> usize {
+        ((self.segment_size as f64 * (1.0 - self.overlap)).round() as usize).max(1)
+    }
+
+    /// The segment size that fits `count` segments at this overlap into `len` samples.
+    pub fn segment_size_for(len: usize, count: usize, overlap: f64) -> usize {
+        let spans = 1.0 + count.saturating_sub(1) as f64 * (1.0 - overlap);
+        ((len as f64 / spans) as usize).max(2)
+    }
+}
+
+/// One-sided power spectral density with a confidence interval per bin, in linear units of
+/// full scale² per Hz (a full-scale sine has a total power of 0.5).
+#[derive(Clone, Debug, Default)]
+pub struct Psd {
+    pub frequencies: Vec<f32>,
+    pub density: Vec<f64>,
+    pub lower: Vec<f64>,
+    pub upper: Vec<f64>,
+    pub params: WelchParams,
+    pub segments: usize,
+    /// Equivalent degrees of freedom of each bin's chi-square distribution, reduced from
+    /// twice the segment count by the correlation of overlapping segments
+    pub degrees_of_freedom: f64,
+}
+
+/// Power in dB, floored well below anything a float sample can carry.
+pub fn density_db(power: f64) -> f64 {
+    10.0 * power.max(1e-30).log10()
+}
+
+impl Psd {
+    /// Width of a bin in Hz.
+    pub fn resolution(&self) -> f32 {
+        self.frequencies.get(1).copied().unwrap_or(0.0)
+    }
+
+    /// Total power between `from` and `to` Hz, the density integrated over its bins.
+    pub fn band_power(&self, from: f32, to: f32) -> f64 {
+        let resolution = self.resolution() as f64;
+        self.frequencies
+            .iter()
+            .zip(&self.density)
+            .filter(|(&f, _)| f >= from && f <= to)
+            .map(|(_, &p)| p * resolution)
+            .sum()
+    }
+
+    /// Factors from the estimate to the lower and upper confidence bounds, the same for
+    /// every bin.
+    pub fn interval(&self) -> (f64, f64) {
+        let alpha = 1.0 - self.params.confidence;
+        let dof = self.degrees_of_freedom;
+        (dof / chi_square_quantile(1.0 - alpha / 2.0, dof), dof / chi_square_quantile(alpha / 2.0, dof))
+    }
+}
+
+/// Welch's method: the averaged, windowed periodograms of overlapping segments. Averaging
+/// `K` segments trades frequency resolution for a variance about `K` times lower than a
+/// single periodogram's, which is what makes noise floors measurable.
+pub fn welch(samples: &[f32], sample_rate: u32, params: WelchParams) -> Result<Psd> {
+    if params.segment_size < 2 {
+        bail!("PSD segments need at least 2 samples");
+    }
+    if !(0.0..1.0).contains(&params.overlap) {
+        bail!("PSD overlap must be at least 0 and below 1, got {}", params.overlap);
+    }
+    if !(params.confidence > 0.0 && params.confidence < 1.0) {
+        bail!("Confidence level must be between 0 and 1, got {}", params.confidence);
+    }
+    let size = params.segment_size;
+    let step = params.step();
+    // Shorter input is zero-padded to one segment
+    let segments = if samples.len() > size { (samples.len() - size) / step + 1 } else { 1 };
+
+    let window = params.window.coefficients_in::<f64>(size);
+    let window_power: f64 = window.iter().map(|w| w * w).sum();
+    let fft = FftPlanner::<f64>::new().plan_fft_forward(size);
+    let bins = size / 2 + 1;
+    let mut power = vec![0.0f64; bins];
+    let mut buffer = vec![Complex::new(0.0, 0.0); size];
+    for segment in 0..segments {
+        let start = segment * step;
+        for (i, (slot, &w)) in buffer.iter_mut().zip(&window).enumerate() {
+            *slot = Complex::new(samples.get(start + i).copied().unwrap_or(0.0) as f64 * w, 0.0);
+        }
+        fft.process(&mut buffer);
+        for (sum, c) in power.iter_mut().zip(&buffer) {
+            *sum += c.norm_sqr();
+        }
+    }
+
+    let rate = sample_rate as f64;
+    let scale = 1.0 / (rate * window_power * segments as f64);
+    let density: Vec<f64> = power
+        .iter()
+        .enumerate()
+        .map(|(k, &p)| {
+            // Negative frequencies fold onto positive ones, except DC and Nyquist which have none
+            let folded = if k == 0 || 2 * k == size { 1.0 } else { 2.0 };
+            p * scale * folded
+        })
+        .collect();
+
+    let mut psd = Psd {
+        frequencies: (0..bins).map(|k| (k as f64 * rate / size as f64) as f32).collect(),
+        density,
+        params,
+        segments,
+        degrees_of_freedom: degrees_of_freedom(&window, step, segments),
+        ..Default::default()
+    };
+    let (lower, upper) = psd.interval();
+    psd.lower = psd.density.iter().map(|p| p * lower).collect();
+    psd.upper = psd.density.iter().map(|p| p * upper).collect();
+    Ok(psd)
+}
+
+/// Welch's equivalent degrees of freedom for `segments` segments `step` apart (Percival and
+/// Walden, eq. 292b): `2K / (1 + 2 Σ (1 - m/K) ρ(m))`, where `ρ(m)` is the squared overlap
+/// correlation of the window with itself shifted by `m` steps.
+fn degrees_of_freedom(window: &[f64], step: usize, segments: usize) -> f64 {
+    let energy: f64 = window.iter().map(|w| w * w).sum();
+    let k = segments as f64;
+    let correlation: f64 = (1..segments)
+        .map(|m| m * step)
+        .take_while(|&shift| shift < window.len())
+        .enumerate()
+        .map(|(i, shift)| {
+            let overlap: f64 = window.iter().zip(&window[shift..]).map(|(a, b)| a * b).sum();
+            (1.0 - (i + 1) as f64 / k) * (overlap / energy).powi(2)
+        })
+        .sum();
+    2.0 * k / (1.0 + 2.0 * correlation)
+}
+
+/// Standard normal quantile (Abramowitz and Stegun 26.2.23, within 4.5e-4).
+fn normal_quantile(p: f64) -> f64 {
+    let tail = |q: f64| {
+        let t = (-2.0 * q.ln()).sqrt();
+        t - (2.515517 + 0.802853 * t + 0.010328 * t * t) / (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t)
+    };
+    if p < 0.5 {
+        -tail(p)
+    } else {
+        tail(1.0 - p)
+    }
+}
+
+/// Chi-square quantile by the Wilson–Hilferty cube-root approximation: within a few percent
+/// from about 10 degrees of freedom; below that it makes upper bounds conservatively high.
+fn chi_square_quantile(p: f64, dof: f64) -> f64 {
+    let spread = 2.0 / (9.0 * dof);
+    (dof * (1.0 - spread + normal_quantile(p) * spread.sqrt()).powi(3)).max(f64::MIN_POSITIVE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_integrates_to_the_power_of_a_sine() {
+        // Amplitude 0.5, so a power of 0.125, between bins at 48 kHz over 4096 samples
+        let samples: Vec<f32> =
+            (0..96000).map(|i| 0.5 * (2.0 * std::f32::consts::PI * 1001.0 * i as f32 / 48000.0).sin()).collect();
+        for window in [WindowFunction::Hann, WindowFunction::Rectangular] {
+            let psd = welch(&samples, 48000, WelchParams { window, ..Default::default() }).unwrap();
+            let power = psd.band_power(0.0, 24000.0);
+            assert!((power - 0.125).abs() < 0.002, "{:?} gave {}", window, power);
+            let peak = psd.density.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+            assert!((psd.frequencies[peak] - 1001.0).abs() <= psd.resolution());
+        }
+    }
+
+    #[test]
+    fn overlapping_hann_segments_add_degrees_of_freedom() {
+        let samples = vec![0.0; 4096 * 10];
+        let psd = welch(&samples, 48000, WelchParams::default()).unwrap();
+        assert_eq!(psd.segments, 19);
+        // Half-overlapped Hann segments are correlated by about 0.167² each
+        assert!(psd.degrees_of_freedom > 2.0 * 10.0 && psd.degrees_of_freedom < 2.0 * 19.0);
+        let (lower, upper) = psd.interval();
+        assert!(lower < 1.0 && upper > 1.0);
+    }
+}
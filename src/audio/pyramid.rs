@@ -0,0 +1,153 @@
+/// Samples summarized by each entry of the finest envelope level.
+pub const ENVELOPE_BLOCK: usize = 256;
+
+/// Spectrogram frames merged by each entry of the finest spectrogram level; shorter spans
+/// read the frames directly.
+const SPECTROGRAM_BASE: usize = 4;
+
+/// Peak and power of a run of samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Envelope {
+    /// Largest magnitude
+    pub peak: f32,
+    pub sum_squares: f64,
+    pub count: usize,
+}
+
+impl Envelope {
+    fn merge(self, other: Envelope) -> Envelope {
+        Envelope {
+            peak: self.peak.max(other.peak),
+            sum_squares: self.sum_squares + other.sum_squares,
+            count: self.count + other.count,
+        }
+    }
+
+    fn of(samples: &[f32]) -> Envelope {
+        samples.iter().fold(Envelope::default(), |e, &x| Envelope {
+            peak: e.peak.max(x.abs()),
+            sum_squares: e.sum_squares + (x as f64) * (x as f64),
+            count: e.count + 1,
+        })
+    }
+
+    pub fn rms(&self) -> f32 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.sum_squares / self.count as f64).sqrt() as f32
+        }
+    }
+}
+
+/// Multi-resolution waveform envelope, like a DAW's peak file: level `k` summarizes
+/// `ENVELOPE_BLOCK << k` samples per entry, so any span can be drawn at any width from a
+/// handful of entries per column instead of every sample.
+///
+/// Built in memory when the audio is loaded and
+/// not persisted: there is no analysis cache on disk yet to keep it next to. Saving it,
+/// keyed by the input's path, modification time and the processing applied, is left for
+/// when that cache exists.
+#[derive(Clone, Debug, Default)]
+pub struct EnvelopePyramid {
+    levels: Vec<Vec<Envelope>>,
+}
+
+impl EnvelopePyramid {
+    pub fn new(samples: &[f32]) -> Self {
+        let mut levels = vec![samples.chunks(ENVELOPE_BLOCK).map(Envelope::of).collect::<Vec<_>>()];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let coarser = levels
+                .last()
+                .expect("non-empty")
+                .chunks(2)
+                .map(|pair| pair.iter().fold(Envelope::default(), |a, &b| a.merge(b)))
+                .collect();
+            levels.push(coarser);
+        }
+        Self { levels }
+    }
+
+    /// Peak and whole-file sum of squares.
+    pub fn total(&self) -> Envelope {
+        self.levels.last().and_then(|level| level.first()).copied().unwrap_or_default()
+    }
+
+    /// One envelope per column over `samples[from..to]`, read from the coarsest level with at
+    /// least one entry per column (or from the samples when columns are finer than a block).
+    /// Column edges snap to that level's entries.
+    pub fn columns(&self, samples: &[f32], from: usize, to: usize, columns: usize) -> Vec<Envelope> {
+        let to = to.min(samples.len());
+        if columns == 0 || from >= to {
+            return Vec::new();
+        }
+        let span = to - from;
+        let edge = |column: usize| from + column * span / columns;
+        if span / columns < ENVELOPE_BLOCK {
+            return (0..columns)
+                .map(|c| Envelope::of(&samples[edge(c)..edge(c + 1).max(edge(c) + 1).min(to)]))
+                .collect();
+        }
+        let level = ((span / columns / ENVELOPE_BLOCK).ilog2() as usize).min(self.levels.len() - 1);
+        let size = ENVELOPE_BLOCK << level;
+        let entries = &self.levels[level];
+        (0..columns)
+            .map(|c| {
+                let start = edge(c) / size;
+                // The last column takes the partial entry at the end
+                let end = if c + 1 == columns { entries.len() } else { (edge(c + 1) / size).max(start + 1) };
+                entries[start.min(entries.len())..end.min(entries.len())]
+                    .iter()
+                    .fold(Envelope::default(), |a, &b| a.merge(b))
+            })
+            .collect()
+    }
+}
+
+/// Spectrogram frames merged in powers of two (the loudest value per bin), so a column
+/// spanning thousands of frames shows the transients among them rather than whichever frame
+/// happens to be nearest.
+///
+/// Like [`EnvelopePyramid`], rebuilt from the spectrogram each time rather than persisted;
+/// a stored copy would also need the window size, function and hop in its key.
+#[derive(Clone, Debug, Default)]
+pub struct SpectrogramPyramid {
+    /// `levels[i]` merges `SPECTROGRAM_BASE << i` frames per entry
+    levels: Vec<Vec<Vec<f32>>>,
+}
+
+fn merge_frames(frames: &[Vec<f32>]) -> Vec<f32> {
+    let mut merged = frames[0].clone();
+    for frame in &frames[1..] {
+        merged.iter_mut().zip(frame).for_each(|(a, &b)| *a = a.max(b));
+    }
+    merged
+}
+
+impl SpectrogramPyramid {
+    pub fn new(magnitudes: &[Vec<f32>]) -> Self {
+        let mut levels: Vec<Vec<Vec<f32>>> = Vec::new();
+        if magnitudes.len() > SPECTROGRAM_BASE {
+            levels.push(magnitudes.chunks(SPECTROGRAM_BASE).map(merge_frames).collect());
+        }
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let coarser = levels.last().expect("non-empty").chunks(2).map(merge_frames).collect();
+            levels.push(coarser);
+        }
+        Self { levels }
+    }
+
+    /// Frames covering `magnitudes[from..to]`, merged as far as they go: at most a few
+    /// entries, which may reach slightly past the span.
+    pub fn frames<'a>(&'a self, magnitudes: &'a [Vec<f32>], from: usize, to: usize) -> &'a [Vec<f32>] {
+        let to = to.min(magnitudes.len()).max(from + 1).min(magnitudes.len());
+        let span = to.saturating_sub(from);
+        if span < SPECTROGRAM_BASE * 2 || self.levels.is_empty() {
+            return &magnitudes[from.min(to)..to];
+        }
+        let level = ((span / SPECTROGRAM_BASE).ilog2() as usize).min(self.levels.len() - 1);
+        let size = SPECTROGRAM_BASE << level;
+        let entries = &self.levels[level];
+        &entries[(from / size).min(entries.len() - 1)..((to - 1) / size + 1).min(entries.len())]
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use std::path::Path;
+
+use super::samples::SampleWriter;
+use super::source::Input;
+use super::{AudioData, AudioTags};
+
+/// Sample encodings accepted for headerless PCM input.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SampleFormat {
+    U8,
+    S8,
+    S16le,
+    S16be,
+    S24le,
+    S24be,
+    S32le,
+    S32be,
+    F32le,
+    F32be,
+    F64le,
+    F64be,
+}
+
+impl SampleFormat {
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            SampleFormat::U8 | SampleFormat::S8 => 1,
+            SampleFormat::S16le | SampleFormat::S16be => 2,
+            SampleFormat::S24le | SampleFormat::S24be => 3,
+            SampleFormat::S32le | SampleFormat::S32be | SampleFormat::F32le | SampleFormat::F32be => 4,
+            SampleFormat::F64le | SampleFormat::F64be => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(
+            self,
+            SampleFormat::F32le | SampleFormat::F32be | SampleFormat::F64le | SampleFormat::F64be
+        )
+    }
+
+    fn decode(self, b: &[u8]) -> f32 {
+        match self {
+            SampleFormat::U8 => (b[0] as f32 / 128.0) - 1.0,
+            SampleFormat::S8 => b[0] as i8 as f32 / 128.0,
+            SampleFormat::S16le => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+            SampleFormat::S16be => i16::from_be_bytes([b[0], b[1]]) as f32 / 32768.0,
+            SampleFormat::S24le => (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8388608.0,
+            SampleFormat::S24be => (i32::from_be_bytes([b[0], b[1], b[2], 0]) >> 8) as f32 / 8388608.0,
+            SampleFormat::S32le => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+            SampleFormat::S32be => i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+            SampleFormat::F32le => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            SampleFormat::F32be => f32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+            SampleFormat::F64le => f64::from_le_bytes(b[..8].try_into().unwrap()) as f32,
+            SampleFormat::F64be => f64::from_be_bytes(b[..8].try_into().unwrap()) as f32,
+        }
+    }
+}
+
+/// Layout of a headerless PCM stream.
+#[derive(Clone, Copy, Debug)]
+pub struct RawFormat {
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub sample_format: SampleFormat,
+}
+
+pub fn load_raw<P: AsRef<Path>>(path: P, format: &RawFormat) -> Result<AudioData> {
+    let bytes = Input::parse(path.as_ref()).read_all()?;
+    decode_raw(&bytes, format)
+}
+
+/// Interprets interleaved PCM bytes, splitting them into per-channel buffers.
+pub fn decode_raw(bytes: &[u8], format: &RawFormat) -> Result<AudioData> {
+    if format.channels == 0 {
+        return Err(anyhow!("Raw input needs at least one channel"));
+    }
+    if format.sample_rate == 0 {
+        return Err(anyhow!("Raw input needs a non-zero sample rate"));
+    }
+
+    let sample_size = format.sample_format.bytes_per_sample();
+    let frame_size = sample_size * format.channels;
+    let trailing = bytes.len() % frame_size;
+    if trailing != 0 {
+        log::warn!("Ignoring {} trailing bytes of a partial frame", trailing);
+    }
+
+    let mut channel_samples: Vec<SampleWriter> = (0..format.channels).map(|_| SampleWriter::new()).collect();
+    for frame in bytes.chunks_exact(frame_size) {
+        for (out, sample) in channel_samples.iter_mut().zip(frame.chunks_exact(sample_size)) {
+            out.push(format.sample_format.decode(sample));
+        }
+    }
+
+    let mut channel_samples = channel_samples.into_iter().map(SampleWriter::finish).collect::<Result<Vec<_>>>()?;
+    let samples = channel_samples.remove(0);
+    Ok(AudioData {
+        samples,
+        extra_channels: channel_samples,
+        sample_rate: format.sample_rate,
+        channels: format.channels,
+        bits_per_sample: if format.sample_format.is_float() {
+            None
+        } else {
+            Some(sample_size as u32 * 8)
+        },
+        tags: AudioTags::default(),
+    })
+}
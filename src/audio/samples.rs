@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::{Deref, DerefMut};
+
+#[cfg(unix)]
+use memmap2::{Mmap, MmapMut, MmapOptions};
+#[cfg(unix)]
+use std::sync::Arc;
+
+/// Samples per channel kept in memory while decoding; longer channels (over 12 minutes at
+/// 44.1 kHz) spill to a memory-mapped temporary file the OS can page out under pressure.
+pub const SPILL_SAMPLES: usize = 1 << 25;
+
+/// One channel of decoded PCM: a plain vector, or for long recordings a memory-mapped
+/// temporary file. Either way it reads and writes as a `[f32]`.
+#[derive(Default)]
+pub struct Samples {
+    storage: Storage,
+}
+
+enum Storage {
+    Memory(Vec<f32>),
+    /// A read-only mapping, shared by every view of the samples
+    #[cfg(unix)]
+    Mapped { map: Arc<Mmap>, file: Arc<File> },
+    /// A copy-on-write mapping of the same file, owned by samples that were written to:
+    /// changed pages are private, so views taken before keep the decoded audio
+    #[cfg(unix)]
+    Private(MmapMut),
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Storage::Memory(Vec::new())
+    }
+}
+
+impl Samples {
+    /// Whether the samples live in a memory-mapped file rather than the heap.
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self.storage, Storage::Memory(_))
+    }
+
+    /// Another handle on the samples for reading elsewhere, such as a playback thread: a copy
+    /// when they're in memory or have been written to, and otherwise the same read-only
+    /// mapping, which neither handle can change.
+    pub fn view(&self) -> Samples {
+        match &self.storage {
+            #[cfg(unix)]
+            Storage::Mapped { map, file } => Samples { storage: Storage::Mapped { map: map.clone(), file: file.clone() } },
+            _ => self.to_vec().into(),
+        }
+    }
+}
+
+impl From<Vec<f32>> for Samples {
+    fn from(samples: Vec<f32>) -> Self {
+        Samples { storage: Storage::Memory(samples) }
+    }
+}
+
+impl Deref for Samples {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        match &self.storage {
+            Storage::Memory(samples) => samples,
+            #[cfg(unix)]
+            Storage::Mapped { map, .. } => as_samples(map),
+            #[cfg(unix)]
+            Storage::Private(map) => as_samples(map),
+        }
+    }
+}
+
+impl DerefMut for Samples {
+    /// Mapped samples are remapped copy-on-write first, as other views may be reading them.
+    fn deref_mut(&mut self) -> &mut [f32] {
+        #[cfg(unix)]
+        if let Storage::Mapped { file, .. } = &self.storage {
+            // SAFETY: the file is an unlinked temporary nothing writes to once mapped, and
+            // this mapping is private, so writes through it reach no other view
+            self.storage = match unsafe { MmapOptions::new().map_copy(file.as_ref()) } {
+                Ok(map) => Storage::Private(map),
+                Err(err) => {
+                    log::warn!("Copying mapped samples: {:#}", err);
+                    Storage::Memory(self.to_vec())
+                }
+            };
+        }
+        match &mut self.storage {
+            Storage::Memory(samples) => samples,
+            #[cfg(unix)]
+            Storage::Mapped { .. } => unreachable!("remapped above"),
+            #[cfg(unix)]
+            Storage::Private(map) => {
+                let len = map.len() / std::mem::size_of::<f32>();
+                // SAFETY: the mapping is page-aligned, holds `len` f32s, and `&mut self`
+                // borrows it exclusively
+                unsafe { std::slice::from_raw_parts_mut(map.as_mut_ptr().cast(), len) }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Samples {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Samples({} {})", self.len(), if self.is_mapped() { "mapped" } else { "in memory" })
+    }
+}
+
+/// The f32s in a mapping of samples written by `SampleWriter`.
+#[cfg(unix)]
+fn as_samples(map: &[u8]) -> &[f32] {
+    // SAFETY: mappings are page-aligned and hold whole native-endian f32s
+    unsafe { std::slice::from_raw_parts(map.as_ptr().cast(), map.len() / std::mem::size_of::<f32>()) }
+}
+
+/// Collects one channel while decoding, moving to a temporary file once it passes
+/// `SPILL_SAMPLES` (on Unix; elsewhere everything stays in memory).
+#[derive(Default)]
+pub struct SampleWriter {
+    buffer: Vec<f32>,
+    spilled: Option<(BufWriter<File>, usize)>,
+    /// Set when no temporary file could be created, so the rest stays in memory
+    in_memory: bool,
+    error: Option<std::io::Error>,
+}
+
+impl SampleWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        self.buffer.push(sample);
+        if self.buffer.len() >= SPILL_SAMPLES {
+            self.spill();
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, samples: &[f32]) {
+        self.buffer.extend_from_slice(samples);
+        if self.buffer.len() >= SPILL_SAMPLES {
+            self.spill();
+        }
+    }
+
+    pub fn extend(&mut self, samples: impl IntoIterator<Item = f32>) {
+        for sample in samples {
+            self.push(sample);
+        }
+    }
+
+    /// Writes the buffered samples out, opening the temporary file on first use.
+    fn spill(&mut self) {
+        if !cfg!(unix) || self.in_memory || self.error.is_some() {
+            return;
+        }
+        if self.spilled.is_none() {
+            match temporary_file() {
+                Ok(file) => self.spilled = Some((BufWriter::new(file), 0)),
+                Err(err) => {
+                    log::warn!("Keeping decoded samples in memory: {:#}", err);
+                    self.in_memory = true;
+                    return;
+                }
+            }
+        }
+        let (writer, written) = self.spilled.as_mut().expect("just opened");
+        for sample in &self.buffer {
+            if let Err(err) = writer.write_all(&sample.to_ne_bytes()) {
+                self.error = Some(err);
+                return;
+            }
+        }
+        *written += self.buffer.len();
+        self.buffer.clear();
+    }
+
+    pub fn finish(mut self) -> Result<Samples> {
+        if self.spilled.is_none() {
+            return Ok(self.buffer.into());
+        }
+        self.spill();
+        if let Some(err) = self.error {
+            return Err(err).context("Failed to write decoded samples to a temporary file");
+        }
+        let (writer, len) = self.spilled.take().expect("checked above");
+        let file = writer.into_inner().context("Failed to write decoded samples to a temporary file")?;
+        mapped(file, len)
+    }
+}
+
+#[cfg(unix)]
+fn mapped(file: File, len: usize) -> Result<Samples> {
+    // SAFETY: the file is an unlinked temporary holding `len` samples, which nothing
+    // writes to once mapped
+    let map = unsafe { MmapOptions::new().len(len * std::mem::size_of::<f32>()).map(&file) }
+        .context("Failed to map decoded samples")?;
+    Ok(Samples { storage: Storage::Mapped { map: Arc::new(map), file: Arc::new(file) } })
+}
+
+#[cfg(not(unix))]
+fn mapped(_file: File, _len: usize) -> Result<Samples> {
+    unreachable!("samples only spill on Unix")
+}
+
+/// A new file in the temporary directory, unlinked straight away so it disappears with the
+/// process.
+fn temporary_file() -> Result<File> {
+    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("fourrier-{}-{}.pcm", std::process::id(), count));
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    std::fs::remove_file(&path).with_context(|| format!("Failed to unlink {}", path.display()))?;
+    Ok(file)
+}
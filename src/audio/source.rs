@@ -0,0 +1,199 @@
+use anyhow::{anyhow, Context, Result};
+use std::fmt;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use symphonia::core::io::{MediaSource, ReadOnlySource};
+
+/// Bytes fetched per HTTP range request.
+const CHUNK_SIZE: u64 = 512 * 1024;
+
+/// Where audio bytes come from: a file, stdin (`-`), an http(s) URL or an encoded file
+/// already in memory.
+#[derive(Clone, Debug)]
+pub enum Input {
+    File(PathBuf),
+    Stdin,
+    Url(String),
+    Memory(Vec<u8>),
+}
+
+impl Input {
+    pub fn parse(input: &Path) -> Self {
+        match input.to_str() {
+            Some("-") => Input::Stdin,
+            Some(s) if s.starts_with("http://") || s.starts_with("https://") => Input::Url(s.to_string()),
+            _ => Input::File(input.to_path_buf()),
+        }
+    }
+
+    /// Opens the input as a symphonia media source. Stdin is buffered so it can be probed
+    /// and seeked like a file.
+    pub fn open(&self) -> Result<Box<dyn MediaSource>> {
+        match self {
+            Input::File(path) => Ok(Box::new(std::fs::File::open(path)?)),
+            Input::Stdin => {
+                let mut bytes = Vec::new();
+                io::stdin().lock().read_to_end(&mut bytes)?;
+                Ok(Box::new(Cursor::new(bytes)))
+            }
+            Input::Url(url) => open_url(url),
+            Input::Memory(bytes) => Ok(Box::new(Cursor::new(bytes.clone()))),
+        }
+    }
+
+    pub fn read_all(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.open()?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::File(path) => write!(f, "{}", path.display()),
+            Input::Stdin => f.write_str("-"),
+            Input::Url(url) => f.write_str(url),
+            Input::Memory(bytes) => write!(f, "<{} bytes>", bytes.len()),
+        }
+    }
+}
+
+fn open_url(url: &str) -> Result<Box<dyn MediaSource>> {
+    match HttpSource::new(url) {
+        Ok(source) => Ok(Box::new(source)),
+        Err(err) => {
+            log::info!("Range requests unavailable for {} ({}), streaming instead", url, err);
+            let child = Command::new("curl")
+                .args(["-sSfL", url])
+                .stdout(Stdio::piped())
+                .spawn()
+                .context("Failed to run curl")?;
+            let stdout = child.stdout.ok_or_else(|| anyhow!("curl produced no output"))?;
+            Ok(Box::new(ReadOnlySource::new(stdout)))
+        }
+    }
+}
+
+/// Seekable remote file read lazily through HTTP range requests (via curl).
+struct HttpSource {
+    url: String,
+    len: u64,
+    pos: u64,
+    chunk_start: u64,
+    chunk: Vec<u8>,
+}
+
+impl HttpSource {
+    fn new(url: &str) -> Result<Self> {
+        let output = Command::new("curl")
+            .args(["-sSfIL", url])
+            .output()
+            .context("Failed to run curl")?;
+        if !output.status.success() {
+            return Err(anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+
+        // With redirects curl prints every response's headers; only the last one counts
+        let headers = String::from_utf8_lossy(&output.stdout);
+        let last = headers.rsplit("\r\n\r\n").find(|h| !h.trim().is_empty()).unwrap_or("");
+        let mut len = None;
+        let mut ranges = false;
+        for line in last.lines() {
+            if let Some((name, value)) = line.split_once(':') {
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "content-length" => len = value.trim().parse().ok(),
+                    "accept-ranges" => ranges = value.trim() == "bytes",
+                    _ => {}
+                }
+            }
+        }
+        match (len, ranges) {
+            (Some(len), true) => Ok(Self {
+                url: url.to_string(),
+                len,
+                pos: 0,
+                chunk_start: 0,
+                chunk: Vec::new(),
+            }),
+            _ => Err(anyhow!("server does not advertise byte ranges")),
+        }
+    }
+
+    fn fetch(&mut self, start: u64) -> io::Result<()> {
+        let end = (start + CHUNK_SIZE).min(self.len) - 1;
+        // The status goes after the body, as its last three bytes
+        let output = Command::new("curl")
+            .args(["-sSfL", "-w", "%{http_code}", "-r", &format!("{}-{}", start, end), &self.url])
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+        let mut body = output.stdout;
+        let status = body.split_off(body.len().saturating_sub(3));
+        // A server that ignores the range answers 200 with the whole file, and a dropped
+        // connection leaves a short body; either would be decoded as the wrong bytes
+        if status != b"206" {
+            return Err(io::Error::other(format!(
+                "expected 206 Partial Content for bytes {}-{}, got status {}",
+                start,
+                end,
+                String::from_utf8_lossy(&status)
+            )));
+        }
+        let expected = end - start + 1;
+        if body.len() as u64 != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("expected {} bytes from {}, got {}", expected, start, body.len()),
+            ));
+        }
+        self.chunk_start = start;
+        self.chunk = body;
+        Ok(())
+    }
+}
+
+impl Read for HttpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len {
+            return Ok(0);
+        }
+        let chunk_end = self.chunk_start + self.chunk.len() as u64;
+        if self.pos < self.chunk_start || self.pos >= chunk_end {
+            self.fetch(self.pos)?;
+        }
+        let offset = (self.pos - self.chunk_start) as usize;
+        let available = &self.chunk[offset..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for HttpSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => self.len as i64 + offset,
+            SeekFrom::Current(offset) => self.pos as i64 + offset,
+        };
+        if target < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "seek before start"));
+        }
+        self.pos = target as u64;
+        Ok(self.pos)
+    }
+}
+
+impl MediaSource for HttpSource {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.len)
+    }
+}
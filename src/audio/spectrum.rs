@@ -0,0 +1,61 @@
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::WindowFunction;
+
+/// Long-term average power spectrum, in dB relative to a full-scale sine.
+#[derive(Clone, Debug, Default)]
+pub struct Spectrum {
+    pub frequencies: Vec<f32>,
+    pub levels: Vec<f32>,
+}
+
+impl Spectrum {
+    /// Index of the bin nearest `frequency`.
+    pub fn bin(&self, frequency: f32) -> usize {
+        let resolution = self.resolution();
+        if resolution <= 0.0 {
+            return 0;
+        }
+        ((frequency / resolution).round() as usize).min(self.levels.len().saturating_sub(1))
+    }
+
+    /// Width of a bin in Hz.
+    pub fn resolution(&self) -> f32 {
+        self.frequencies.get(1).copied().unwrap_or(0.0)
+    }
+}
+
+/// Averages the Hann-windowed power spectra of up to `max_frames` frames of `window_size`
+/// samples spread evenly over `samples` (zero-padded when shorter than one window).
+pub fn average_spectrum(samples: &[f32], sample_rate: u32, window_size: usize, max_frames: usize) -> Spectrum {
+    let window_size = window_size.max(2);
+    let fft = FftPlanner::new().plan_fft_forward(window_size);
+    let window = WindowFunction::Hann.coefficients(window_size);
+    // A full-scale sine then peaks at 0 dB
+    let scale = 2.0 / window.iter().sum::<f32>();
+
+    let available = samples.len().saturating_sub(window_size) + 1;
+    let frames = max_frames.clamp(1, available.max(1));
+    let mut power = vec![0.0f64; window_size / 2];
+    let mut buffer = vec![Complex::new(0.0f32, 0.0); window_size];
+    for frame in 0..frames {
+        let start = if frames > 1 { frame * (available - 1) / (frames - 1) } else { 0 };
+        for (i, (slot, &w)) in buffer.iter_mut().zip(&window).enumerate() {
+            *slot = Complex::new(samples.get(start + i).copied().unwrap_or(0.0) * w, 0.0);
+        }
+        fft.process(&mut buffer);
+        for (sum, c) in power.iter_mut().zip(&buffer) {
+            *sum += (c.norm() * scale).powi(2) as f64;
+        }
+    }
+
+    Spectrum {
+        frequencies: (0..window_size / 2)
+            .map(|i| i as f32 * sample_rate as f32 / window_size as f32)
+            .collect(),
+        levels: power
+            .iter()
+            .map(|&p| (10.0 * (p / frames as f64).max(1e-20).log10()) as f32)
+            .collect(),
+    }
+}
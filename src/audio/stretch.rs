@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use super::samples::Samples;
+
+/// Pitch-preserving time stretch (WSOLA: waveform-similarity overlap-add).
+///
+/// Output is produced in hops of half a frame. Each new frame is taken from around its nominal
+/// position in the source (advanced by `speed` per output hop), nudged within a small tolerance
+/// to the offset that best continues the previous frame, so speech keeps its pitch at 0.5×–2×.
+pub struct TimeStretch {
+    samples: Arc<Samples>,
+    speed: f64,
+    frame: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    start: usize,
+    end: usize,
+    hops: usize,
+    previous: Option<usize>,
+    tail: Vec<f32>,
+}
+
+impl TimeStretch {
+    pub fn new(samples: Arc<Samples>, sample_rate: u32, start: usize, speed: f64) -> Self {
+        // ~30 ms frames keep transients intact while spanning a couple of pitch periods
+        let frame = ((sample_rate as usize * 3 / 100) & !1).max(64);
+        let hop = frame / 2;
+        let window = (0..frame)
+            .map(|i| 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / frame as f32).cos()))
+            .collect();
+        Self {
+            samples,
+            speed,
+            frame,
+            tolerance: frame / 8,
+            window,
+            start,
+            end: usize::MAX,
+            hops: 0,
+            previous: None,
+            tail: vec![0.0; hop],
+        }
+    }
+
+    /// Stops output at source index `end` instead of the end of the samples.
+    pub fn with_end(mut self, end: usize) -> Self {
+        self.end = end;
+        self
+    }
+
+    fn source_len(&self) -> usize {
+        self.samples.len().min(self.end)
+    }
+
+    /// Source index at the start of the next block.
+    pub fn source_position(&self) -> usize {
+        self.start + (self.hops as f64 * (self.frame / 2) as f64 * self.speed) as usize
+    }
+
+    /// Returns the next block of output samples, or `None` once the source is exhausted.
+    pub fn next_block(&mut self, hops: usize) -> Option<Vec<f32>> {
+        let hop = self.frame / 2;
+        if (self.speed - 1.0).abs() < 1e-6 {
+            let from = self.source_position();
+            if from >= self.source_len() {
+                return None;
+            }
+            let to = (from + hop * hops).min(self.source_len());
+            self.hops += hops;
+            return Some(self.samples[from..to].to_vec());
+        }
+
+        let mut out = Vec::with_capacity(hop * hops);
+        for _ in 0..hops {
+            let nominal = self.source_position();
+            if nominal + self.frame + self.tolerance >= self.source_len() {
+                break;
+            }
+            let chosen = match self.previous {
+                Some(previous) => self.best_offset(nominal, previous + hop),
+                None => nominal,
+            };
+            let segment = &self.samples[chosen..chosen + self.frame];
+            for i in 0..hop {
+                out.push(self.tail[i] + segment[i] * self.window[i]);
+                self.tail[i] = segment[hop + i] * self.window[hop + i];
+            }
+            self.previous = Some(chosen);
+            self.hops += 1;
+        }
+        if out.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+
+    /// Finds the start near `nominal` whose first half best matches the natural continuation
+    /// of the previous frame.
+    fn best_offset(&self, nominal: usize, continuation: usize) -> usize {
+        let hop = self.frame / 2;
+        if continuation + hop > self.samples.len() {
+            return nominal;
+        }
+        let template = &self.samples[continuation..continuation + hop];
+        let low = nominal.saturating_sub(self.tolerance);
+        let high = nominal + self.tolerance;
+        (low..=high)
+            .step_by(2)
+            .map(|start| {
+                let score: f32 = self.samples[start..start + hop]
+                    .iter()
+                    .zip(template)
+                    .map(|(&x, &y)| x * y)
+                    .sum();
+                (start, score)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(nominal, |(start, _)| start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::generator::{generate, Signal, SignalParams};
+
+    /// Rising zero crossings per second over the middle half, clear of the fades.
+    fn frequency(samples: &[f32], sample_rate: u32) -> f32 {
+        let middle = &samples[samples.len() / 4..samples.len() * 3 / 4];
+        let crossings = middle.windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+        crossings as f32 * sample_rate as f32 / middle.len() as f32
+    }
+
+    #[test]
+    fn stretching_changes_the_length_but_not_the_pitch() {
+        let params = SignalParams { duration: 2.0, sample_rate: 16000, frequency: 220.0, ..Default::default() };
+        let samples = Arc::new(Samples::from(generate(Signal::Tone, &params).unwrap()));
+        for speed in [0.5, 0.75, 1.5, 2.0] {
+            let mut stretch = TimeStretch::new(samples.clone(), 16000, 0, speed);
+            let mut out = Vec::new();
+            while let Some(block) = stretch.next_block(8) {
+                out.extend(block);
+            }
+            let expected = samples.len() as f64 / speed;
+            // Short by at most the last frame and its search tolerance
+            let error = (out.len() as f64 - expected).abs();
+            assert!(error < 0.05 * expected, "{}x gave {} samples, not {}", speed, out.len(), expected);
+            let pitch = frequency(&out, 16000);
+            assert!((pitch - 220.0).abs() < 5.0, "{}x played at {} Hz", speed, pitch);
+        }
+    }
+}
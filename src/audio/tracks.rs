@@ -0,0 +1,126 @@
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use std::path::Path;
+
+/// Rate the coarse offset search decimates to, in Hz: plenty for the envelope of speech and
+/// music, and keeps the correlation's FFT small for hour-long recordings.
+const SEARCH_RATE: u32 = 2000;
+
+/// Longest stretch of each track the offset search compares, in seconds.
+const SEARCH_SPAN: f64 = 600.0;
+
+/// Stretch the offset is refined over at the full sample rate, in seconds.
+const REFINE_SPAN: f64 = 10.0;
+
+/// Offsets found with a lower correlation than this are likely chance matches between
+/// unrelated sounds.
+pub const MIN_CORRELATION: f32 = 0.3;
+
+/// Name a multi-track session shows for the track loaded from `path`: its file name
+/// without the extension.
+pub fn track_name(path: &Path) -> String {
+    path.file_stem().map_or(path.display().to_string(), |stem| stem.to_string_lossy().into_owned())
+}
+
+/// Sum of `tracks` as a mixing desk at unity gain would give it, as long as the longest.
+pub fn mix(tracks: &[&[f32]]) -> Vec<f32> {
+    let len = tracks.iter().map(|track| track.len()).max().unwrap_or(0);
+    let mut mixed = vec![0.0; len];
+    for track in tracks {
+        for (out, &sample) in mixed.iter_mut().zip(track.iter()) {
+            *out += sample;
+        }
+    }
+    mixed
+}
+
+/// How far one recording of an event is from another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Offset {
+    /// Samples the other recording started after the reference; negative when it started
+    /// before
+    pub samples: i64,
+    /// Normalized cross-correlation at the offset: near 1 for two recordings of the same
+    /// sound, near 0 when nothing matched
+    pub correlation: f32,
+}
+
+/// Finds where `other` lines up with `reference` (both at `sample_rate`) by cross-correlation:
+/// first over the decimated envelopes of up to `SEARCH_SPAN` seconds of each, then refined
+/// to the sample at the full rate.
+pub fn find_offset(reference: &[f32], other: &[f32], sample_rate: u32) -> Offset {
+    let factor = (sample_rate / SEARCH_RATE).max(1) as usize;
+    let span = (SEARCH_SPAN * sample_rate as f64) as usize;
+    let decimate = |samples: &[f32]| -> Vec<f32> {
+        samples[..samples.len().min(span)]
+            .chunks(factor)
+            .map(|block| block.iter().sum::<f32>() / block.len() as f32)
+            .collect()
+    };
+    let coarse = correlate(&decimate(reference), &decimate(other)) * factor as i64;
+    let window = (REFINE_SPAN * sample_rate as f64) as usize;
+    let dot = |lag: i64| {
+        let (a, b) = overlap(reference, other, lag, window);
+        a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+    };
+    let samples = (coarse - factor as i64..=coarse + factor as i64)
+        .map(|lag| (lag, dot(lag)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(coarse, |(lag, _)| lag);
+
+    let (a, b) = overlap(reference, other, samples, window);
+    let energy = |x: &[f32]| x.iter().map(|s| s * s).sum::<f32>();
+    let correlation = dot(samples) / (energy(a) * energy(b)).sqrt().max(f32::MIN_POSITIVE);
+    Offset { samples, correlation }
+}
+
+/// Lag of `other` against `reference` with the highest cross-correlation, computed with FFTs.
+fn correlate(reference: &[f32], other: &[f32]) -> i64 {
+    let size = (reference.len() + other.len()).next_power_of_two().max(1);
+    let mut planner = FftPlanner::new();
+    let forward = planner.plan_fft_forward(size);
+    let spectrum = |samples: &[f32]| {
+        let mut buffer: Vec<Complex<f32>> = samples.iter().map(|&s| Complex::new(s, 0.0)).collect();
+        buffer.resize(size, Complex::new(0.0, 0.0));
+        forward.process(&mut buffer);
+        buffer
+    };
+    let (a, b) = (spectrum(reference), spectrum(other));
+    let mut product: Vec<Complex<f32>> = a.iter().zip(&b).map(|(x, y)| x * y.conj()).collect();
+    planner.plan_fft_inverse(size).process(&mut product);
+    let peak = product
+        .iter()
+        .enumerate()
+        .max_by(|(_, x), (_, y)| x.re.total_cmp(&y.re))
+        .map_or(0, |(i, _)| i);
+    // The upper half of the result holds the negative lags
+    if peak > size / 2 {
+        peak as i64 - size as i64
+    } else {
+        peak as i64
+    }
+}
+
+/// The first `window` samples where `reference` from `lag` on and `other` from 0 overlap
+/// (or the other way round for a negative lag).
+fn overlap<'a>(reference: &'a [f32], other: &'a [f32], lag: i64, window: usize) -> (&'a [f32], &'a [f32]) {
+    let (a, b) = if lag >= 0 {
+        (reference.get(lag as usize..).unwrap_or_default(), other)
+    } else {
+        (reference, other.get((-lag) as usize..).unwrap_or_default())
+    };
+    let len = a.len().min(b.len()).min(window);
+    (&a[..len], &b[..len])
+}
+
+/// `samples` moved later by `offset` samples (earlier when negative), padded with silence,
+/// so a track that started `offset` samples after the reference lines up with it.
+pub fn shift(samples: &[f32], offset: i64) -> Vec<f32> {
+    if offset >= 0 {
+        let mut shifted = vec![0.0; offset as usize];
+        shifted.extend_from_slice(samples);
+        shifted
+    } else {
+        samples.get((-offset) as usize..).unwrap_or_default().to_vec()
+    }
+}
@@ -0,0 +1,130 @@
+use clap::ValueEnum;
+
+use super::SpectrogramParams;
+
+/// What spectrogram levels measure. All are stored in dB so the display and analyses can
+/// treat them alike; `amplitude` is shown as the linear value behind its dB level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Units {
+    /// Amplitude in dB relative to full scale: a full-scale sine peaks at 0 dBFS whatever
+    /// the window and its size
+    #[default]
+    Dbfs,
+    /// Power spectral density in dB re full scale²/Hz: white noise reads the same at any
+    /// window size, and matches `psd`
+    Power,
+    /// Linear peak amplitude, 1.0 for a full-scale sine
+    Amplitude,
+}
+
+/// How `dbfs` and `amplitude` levels compensate for the window, so they compare across
+/// window sizes and functions. `power` is a density and always energy-normalized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Normalization {
+    /// Divide by the window's coherent gain (its sum): a sine reads its amplitude at its
+    /// peak bin, while broadband noise reads lower in longer windows
+    #[default]
+    Peak,
+    /// Divide by the window's energy (the square root of its sum of squares times the size):
+    /// the bins of a frame add up to the frame's power at any size, so noise and other
+    /// broadband content compare across window functions, and tones read lower by the
+    /// window's noise bandwidth in bins. For noise levels independent of the size, use
+    /// `power`
+    Energy,
+    /// `|X| / N`, as older versions reported: depends on the window
+    None,
+}
+
+/// Converts STFT bin magnitudes to levels in `params.units`, calibration included.
+#[derive(Clone, Copy, Debug)]
+pub struct BinScale {
+    gain: f64,
+    /// DC has no negative-frequency image to fold in, so it gets half the amplitude (or
+    /// power) gain of the other bins
+    dc_gain: f64,
+    calibration: f64,
+    /// Level of a full-scale sine centred on a bin
+    full_scale: f64,
+}
+
+impl BinScale {
+    pub fn new(params: &SpectrogramParams, sample_rate: u32) -> Self {
+        let window = params.window.coefficients_in::<f64>(params.window_size);
+        let size = params.window_size.max(1) as f64;
+        let (sum, squares) = window.iter().fold((0.0, 0.0), |(s, q), w| (s + w, q + w * w));
+        let (gain, dc_gain) = match (params.units, params.normalization) {
+            (Units::Power, _) => {
+                let density = 1.0 / (sample_rate as f64 * squares);
+                ((2.0 * density).sqrt(), density.sqrt())
+            }
+            (_, Normalization::Peak) => (2.0 / sum, 1.0 / sum),
+            (_, Normalization::Energy) => {
+                let energy = 2.0 / (size * squares);
+                ((2.0 * energy).sqrt(), energy.sqrt())
+            }
+            (_, Normalization::None) => (1.0 / size, 1.0 / size),
+        };
+        // A full-scale sine's peak bin has a magnitude of half the window's sum
+        let full_scale = 20.0 * (gain * sum / 2.0).log10();
+        let calibration = params.calibration as f64;
+        Self { gain, dc_gain, calibration, full_scale: full_scale + calibration }
+    }
+
+    /// Multiplier from `|X[bin]|` to the linear quantity whose dB is the level.
+    pub fn gain(&self, bin: usize) -> f64 {
+        if bin == 0 {
+            self.dc_gain
+        } else {
+            self.gain
+        }
+    }
+
+    pub fn level(&self, magnitude: f64, bin: usize) -> f64 {
+        20.0 * (magnitude * self.gain(bin)).log10() + self.calibration
+    }
+}
+
+impl SpectrogramParams {
+    /// Level of a full-scale sine centred on a bin, calibration included: the top of the
+    /// display range.
+    pub fn full_scale(&self, sample_rate: u32) -> f32 {
+        BinScale::new(self, sample_rate).full_scale as f32
+    }
+
+    /// Unit label of levels, e.g. for a color scale.
+    pub fn unit_label(&self) -> &'static str {
+        match (self.units, self.calibration != 0.0) {
+            (Units::Dbfs, false) => "dBFS",
+            (Units::Dbfs, true) => "dB",
+            (Units::Power, _) => "dB/Hz",
+            (Units::Amplitude, false) => "FS",
+            (Units::Amplitude, true) => "",
+        }
+    }
+
+    /// A level as it is reported, without the unit: dB for `dbfs` and `power`, the linear
+    /// value for `amplitude`.
+    pub fn format_value(&self, level: f32) -> String {
+        match self.units {
+            Units::Amplitude => format_amplitude(10f32.powf(level / 20.0)),
+            _ => format!("{:.1}", level),
+        }
+    }
+
+    /// `format_value` with the unit.
+    pub fn format_level(&self, level: f32) -> String {
+        match self.unit_label() {
+            "" => self.format_value(level),
+            unit => format!("{} {}", self.format_value(level), unit),
+        }
+    }
+}
+
+/// Three significant digits, switching to exponent notation for small values.
+fn format_amplitude(value: f32) -> String {
+    if value >= 0.01 || value == 0.0 {
+        format!("{:.3}", value)
+    } else {
+        format!("{:.2e}", value)
+    }
+}
@@ -0,0 +1,122 @@
+use clap::ValueEnum;
+use std::fmt;
+
+use super::float::{Float, Precision};
+use super::units::{Normalization, Units};
+
+/// Tapers applied to each STFT frame. Wider main lobes (Blackman) trade frequency resolution
+/// for less leakage; the rectangular window has the narrowest lobe and the most leakage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum WindowFunction {
+    #[default]
+    Hann,
+    Hamming,
+    Blackman,
+    Rectangular,
+}
+
+impl WindowFunction {
+    pub const ALL: [WindowFunction; 4] = [
+        WindowFunction::Hann,
+        WindowFunction::Hamming,
+        WindowFunction::Blackman,
+        WindowFunction::Rectangular,
+    ];
+
+    pub fn coefficients(self, size: usize) -> Vec<f32> {
+        self.coefficients_in(size)
+    }
+
+    /// The window computed in `T`'s precision.
+    pub fn coefficients_in<T: Float>(self, size: usize) -> Vec<T> {
+        let constant = |value: f64| T::from_f64(value).expect("window constants fit any float");
+        let denominator = constant(size.saturating_sub(1).max(1) as f64);
+        let (two_pi, one) = (constant(2.0 * std::f64::consts::PI), constant(1.0));
+        (0..size)
+            .map(|i| {
+                let phase = two_pi * constant(i as f64) / denominator;
+                match self {
+                    WindowFunction::Hann => constant(0.5) * (one - phase.cos()),
+                    WindowFunction::Hamming => constant(0.54) - constant(0.46) * phase.cos(),
+                    WindowFunction::Blackman => {
+                        constant(0.42) - constant(0.5) * phase.cos() + constant(0.08) * (constant(2.0) * phase).cos()
+                    }
+                    WindowFunction::Rectangular => one,
+                }
+            })
+            .collect()
+    }
+
+    /// Peak-to-trough ripple in dB of the window summed at `hop`-sample offsets, away from
+    /// the ends: 0 when frames overlap-add to a constant (COLA), so every sample weighs the
+    /// same in the spectrogram and in resynthesis.
+    pub fn overlap_ripple(self, size: usize, hop: usize) -> f32 {
+        let window = self.coefficients_in::<f64>(size);
+        let hop = hop.clamp(1, size.max(1));
+        let sums: Vec<f64> =
+            (0..hop).map(|offset| window.iter().skip(offset).step_by(hop).sum::<f64>()).collect();
+        let (low, high) = sums.iter().fold((f64::INFINITY, 0.0f64), |(l, h), &s| (l.min(s), h.max(s)));
+        if low <= 0.0 {
+            return f32::INFINITY;
+        }
+        (20.0 * (high / low).log10()) as f32
+    }
+
+    /// The window's formula, for explanations.
+    pub fn formula(self) -> &'static str {
+        match self {
+            WindowFunction::Hann => "w[n] = 0.5(1 - cos(2πn/(N-1)))",
+            WindowFunction::Hamming => "w[n] = 0.54 - 0.46cos(2πn/(N-1))",
+            WindowFunction::Blackman => "w[n] = 0.42 - 0.5cos(2πn/(N-1)) + 0.08cos(4πn/(N-1))",
+            WindowFunction::Rectangular => "w[n] = 1",
+        }
+    }
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            WindowFunction::Hann => "Hann",
+            WindowFunction::Hamming => "Hamming",
+            WindowFunction::Blackman => "Blackman",
+            WindowFunction::Rectangular => "rectangular",
+        })
+    }
+}
+
+/// STFT parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpectrogramParams {
+    pub window_size: usize,
+    pub hop_size: usize,
+    pub window: WindowFunction,
+    pub precision: Precision,
+    pub units: Units,
+    pub normalization: Normalization,
+    /// Added to every level in dB, e.g. the dB SPL a full-scale sine measures at
+    pub calibration: f32,
+    /// The signal is low-passed and only every `decimation`th sample kept before the STFT,
+    /// so a window of a given size spans longer and resolves finer frequencies
+    pub decimation: usize,
+}
+
+impl SpectrogramParams {
+    /// Hann window with 50% overlap.
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            window_size,
+            hop_size: window_size / 2,
+            window: WindowFunction::Hann,
+            precision: Precision::F32,
+            units: Units::Dbfs,
+            normalization: Normalization::Peak,
+            calibration: 0.0,
+            decimation: 1,
+        }
+    }
+
+    /// Rate the STFT runs at for a signal at `sample_rate`, after decimation.
+    pub fn analysis_rate(&self, sample_rate: u32) -> u32 {
+        sample_rate / self.decimation.max(1) as u32
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::audio::{compute_spectrogram_with, load_audio, SpectrogramParams};
+use crate::speech::{prepare_audio_for_whisper, time_transcription, WHISPER_RATE};
+
+/// What `run` measures.
+#[derive(Clone, Debug)]
+pub struct BenchOptions {
+    /// Spectrogram window sizes to compare
+    pub window_sizes: Vec<usize>,
+    /// Models to transcribe with; none skips transcription
+    pub models: Vec<PathBuf>,
+    /// Whisper thread counts to compare for each model
+    pub threads: Vec<usize>,
+    /// Repetitions of the fast stages; the fastest counts
+    pub runs: usize,
+    /// Seconds from the start of the file to transcribe, so large models finish in
+    /// reasonable time
+    pub clip: f64,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+        // Powers of two up to the core count, then all cores
+        let mut threads: Vec<usize> = std::iter::successors(Some(1), |&n| Some(n * 2))
+            .take_while(|&n| n < cores)
+            .collect();
+        threads.push(cores);
+        Self {
+            window_sizes: vec![512, 1024, 2048, 4096, 8192],
+            models: Vec::new(),
+            threads,
+            runs: 3,
+            clip: 30.0,
+        }
+    }
+}
+
+/// One timed stage.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    /// Pipeline stage: decode, resample, spectrogram, model load or transcribe
+    pub stage: &'static str,
+    /// What differs between measurements of the stage, e.g. the window size
+    pub variant: String,
+    pub time: Duration,
+    /// Length of the audio the stage processed, in seconds; zero for the model load
+    pub audio_seconds: f64,
+}
+
+impl Measurement {
+    /// Processing time over audio time: below 1 is faster than real time.
+    pub fn realtime_factor(&self) -> Option<f64> {
+        (self.audio_seconds > 0.0).then(|| self.time.as_secs_f64() / self.audio_seconds)
+    }
+}
+
+/// Fastest of `runs` calls of `stage`.
+fn fastest<T>(runs: usize, mut stage: impl FnMut() -> Result<T>) -> Result<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..runs.max(1) {
+        let started = Instant::now();
+        stage()?;
+        best = best.min(started.elapsed());
+    }
+    Ok(best)
+}
+
+/// Times each stage of the pipeline on `input` as `options` asks, calling `progress` with
+/// a line before each.
+pub fn run(input: &Path, options: &BenchOptions, progress: impl Fn(&str)) -> Result<Vec<Measurement>> {
+    let mut measurements = Vec::new();
+    progress("Decoding...");
+    let decode = fastest(options.runs, || load_audio(input))?;
+    let audio_data = load_audio(input)?;
+    let duration = audio_data.samples.len() as f64 / audio_data.sample_rate as f64;
+    let channels = format!(
+        "{} Hz, {} channel{}",
+        audio_data.sample_rate,
+        audio_data.channels,
+        if audio_data.channels == 1 { "" } else { "s" }
+    );
+    measurements.push(Measurement { stage: "decode", variant: channels, time: decode, audio_seconds: duration });
+
+    progress("Resampling...");
+    let resample = fastest(options.runs, || Ok(prepare_audio_for_whisper(&audio_data)))?;
+    let variant = format!("{} -> {} Hz", audio_data.sample_rate, WHISPER_RATE);
+    measurements.push(Measurement { stage: "resample", variant, time: resample, audio_seconds: duration });
+
+    for &window_size in &options.window_sizes {
+        progress(&format!("Spectrogram, window {}...", window_size));
+        let params = SpectrogramParams::new(window_size);
+        let time = fastest(options.runs, || compute_spectrogram_with(&audio_data, &params, None))?;
+        measurements.push(Measurement {
+            stage: "spectrogram",
+            variant: format!("window {}", window_size),
+            time,
+            audio_seconds: duration,
+        });
+    }
+
+    if !options.models.is_empty() {
+        let mut samples = prepare_audio_for_whisper(&audio_data);
+        samples.truncate((options.clip * WHISPER_RATE as f64) as usize);
+        let clip = samples.len() as f64 / WHISPER_RATE as f64;
+        for model in &options.models {
+            let name = model.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned());
+            progress(&format!("Transcribing {:.0} s with {}...", clip, name));
+            let (load, runs) = time_transcription(&samples, model, &options.threads)?;
+            measurements.push(Measurement {
+                stage: "model load",
+                variant: name.clone(),
+                time: load,
+                audio_seconds: 0.0,
+            });
+            for (&threads, time) in options.threads.iter().zip(runs) {
+                measurements.push(Measurement {
+                    stage: "transcribe",
+                    variant: format!("{}, {} thread{}", name, threads, if threads == 1 { "" } else { "s" }),
+                    time,
+                    audio_seconds: clip,
+                });
+            }
+        }
+    }
+    Ok(measurements)
+}
+
+/// Comparison table: one row per measurement with its time, real-time factor and how much
+/// slower it is than the fastest variant of its stage.
+pub fn report_lines(measurements: &[Measurement]) -> Vec<String> {
+    let mut lines = vec![format!("{:<12} {:<32} {:>10} {:>8} {:>9}", "stage", "variant", "time", "RTF", "relative")];
+    for measurement in measurements {
+        let best = measurements
+            .iter()
+            .filter(|other| other.stage == measurement.stage)
+            .map(|other| other.time)
+            .min()
+            .unwrap_or(measurement.time);
+        let relative = measurement.time.as_secs_f64() / best.as_secs_f64().max(f64::MIN_POSITIVE);
+        lines.push(format!(
+            "{:<12} {:<32} {:>8.3} s {:>8} {:>8.2}x",
+            measurement.stage,
+            measurement.variant,
+            measurement.time.as_secs_f64(),
+            measurement.realtime_factor().map_or("-".to_string(), |rtf| format!("{:.4}", rtf)),
+            relative
+        ));
+    }
+    lines
+}
@@ -0,0 +1,288 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAY: u64 = 86_400;
+
+/// Seconds since the Unix epoch, 0 for times before it.
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Offset of local time from UTC at `time`, in seconds; 0 where the time zone isn't known.
+#[cfg(unix)]
+fn utc_offset(time: SystemTime) -> i64 {
+    let seconds = unix_seconds(time) as libc::time_t;
+    // SAFETY: localtime_r only writes the tm it is given
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::localtime_r(&seconds, &mut tm) };
+    if result.is_null() {
+        0
+    } else {
+        tm.tm_gmtoff as i64
+    }
+}
+
+#[cfg(not(unix))]
+fn utc_offset(_time: SystemTime) -> i64 {
+    0
+}
+
+/// Seconds since the epoch of `time` on the local clock.
+fn local_seconds(time: SystemTime) -> i64 {
+    unix_seconds(time) as i64 + utc_offset(time)
+}
+
+/// Year, month and day of the day `days` after 1970-01-01 (Howard Hinnant's
+/// `civil_from_days`).
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = (if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Date and time of `seconds` since the epoch, with `separator` between them.
+fn format_seconds(seconds: i64, date_separator: &str, separator: &str, time_separator: &str) -> String {
+    let (year, month, day) = civil_date(seconds.div_euclid(DAY as i64));
+    let of_day = seconds.rem_euclid(DAY as i64);
+    format!(
+        "{:04}{sep}{:02}{sep}{:02}{}{:02}{time}{:02}{time}{:02}",
+        year,
+        month,
+        day,
+        separator,
+        of_day / 3600,
+        of_day / 60 % 60,
+        of_day % 60,
+        sep = date_separator,
+        time = time_separator
+    )
+}
+
+/// `time` in UTC as ISO 8601 to the second, e.g. `2024-05-01T05:22:10Z`.
+pub fn utc_timestamp(time: SystemTime) -> String {
+    format!("{}Z", format_seconds(unix_seconds(time) as i64, "-", "T", ":"))
+}
+
+/// `time` on the local clock for file names, e.g. `20240501-052210`.
+pub fn local_file_stamp(time: SystemTime) -> String {
+    format_seconds(local_seconds(time), "", "-", "")
+}
+
+/// The next full hour on the local clock after `time`.
+pub fn next_hour(time: SystemTime) -> SystemTime {
+    let local = local_seconds(time);
+    let boundary = (local.div_euclid(3600) + 1) * 3600 - utc_offset(time);
+    let boundary = UNIX_EPOCH + Duration::from_secs(boundary.max(0) as u64);
+    // Where the offset changes by half an hour (Lord Howe Island), the clock shows half
+    // past at that instant; the full hour is the next one on the new offset
+    let past = local_seconds(boundary).rem_euclid(3600) as u64;
+    if past == 0 {
+        boundary
+    } else {
+        boundary + Duration::from_secs(3600 - past)
+    }
+}
+
+/// A time of day on the local clock, in seconds after midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeOfDay(u32);
+
+impl TimeOfDay {
+    /// Parses `HH:MM` or `HH:MM:SS`, 24-hour.
+    pub fn parse(text: &str) -> Result<Self> {
+        let parts: Vec<&str> = text.trim().split(':').collect();
+        let numbers: Option<Vec<u32>> = parts.iter().map(|part| part.parse().ok()).collect();
+        match numbers.as_deref() {
+            Some(&[hours, minutes]) if hours < 24 && minutes < 60 => Ok(Self(hours * 3600 + minutes * 60)),
+            Some(&[hours, minutes, seconds]) if hours < 24 && minutes < 60 && seconds < 60 => {
+                Ok(Self(hours * 3600 + minutes * 60 + seconds))
+            }
+            _ => Err(anyhow!("`{}` is not a time of day like 22:00", text.trim())),
+        }
+    }
+
+    /// The first time at or after `time` the local clock shows this time of day.
+    pub fn next_after(&self, time: SystemTime) -> SystemTime {
+        let now = local_seconds(time).rem_euclid(DAY as i64) as u64;
+        let wait = (self.0 as u64 + DAY - now) % DAY;
+        let whole = UNIX_EPOCH + Duration::from_secs(unix_seconds(time));
+        let guess = whole + Duration::from_secs(wait);
+        // Across a daylight saving change the clock shows this time earlier or later by
+        // the change, unless the change skipped it
+        let shift = utc_offset(time) - utc_offset(guess);
+        let shifted = if shift >= 0 {
+            guess + Duration::from_secs(shift as u64)
+        } else {
+            guess - Duration::from_secs(shift.unsigned_abs())
+        };
+        if shift != 0 && utc_offset(shifted) == utc_offset(guess) {
+            shifted
+        } else {
+            guess
+        }
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 3600, self.0 / 60 % 60)?;
+        if !self.0.is_multiple_of(60) {
+            write!(f, ":{:02}", self.0 % 60)?;
+        }
+        Ok(())
+    }
+}
+
+/// A daily window on the local clock, such as 22:00-06:00 for the night; it wraps past
+/// midnight when the end is before the start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl Schedule {
+    /// Parses `START-END`, e.g. `05:00-09:30`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let (start, end) = text
+            .split_once('-')
+            .ok_or_else(|| anyhow!("`{}` is not a window of times like 22:00-06:00", text.trim()))?;
+        let schedule = Self { start: TimeOfDay::parse(start)?, end: TimeOfDay::parse(end)? };
+        if schedule.start == schedule.end {
+            return Err(anyhow!("The window {} is empty", schedule));
+        }
+        Ok(schedule)
+    }
+
+    /// Whether `time` is inside the window.
+    pub fn contains(&self, time: SystemTime) -> bool {
+        let now = local_seconds(time).rem_euclid(DAY as i64) as u32;
+        if self.start.0 < self.end.0 {
+            (self.start.0..self.end.0).contains(&now)
+        } else {
+            now >= self.start.0 || now < self.end.0
+        }
+    }
+
+    /// When the window `time` is in (or the next one) starts and ends; the start is `time`
+    /// itself inside a window.
+    pub fn window(&self, time: SystemTime) -> (SystemTime, SystemTime) {
+        let start = if self.contains(time) { time } else { self.start.next_after(time) };
+        (start, self.end.next_after(start))
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    extern "C" {
+        fn tzset();
+    }
+
+    /// Tests share the process's time zone, so they take turns setting it.
+    static ZONE: Mutex<()> = Mutex::new(());
+
+    fn in_zone(zone: &str, test: impl FnOnce()) {
+        let _zone = ZONE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::env::set_var("TZ", zone);
+        // SAFETY: tzset only rereads TZ, which no other thread changes while ZONE is held
+        unsafe { tzset() };
+        test();
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(-1), (1969, 12, 31));
+        assert_eq!(civil_date(59), (1970, 3, 1));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(19_782), (2024, 2, 29));
+        // Centuries are leap years only every 400 years
+        assert_eq!(civil_date(47_541), (2100, 3, 1));
+        assert_eq!(civil_date(-25_508), (1900, 3, 1));
+        assert_eq!(civil_date(-719_162), (1, 1, 1));
+    }
+
+    #[test]
+    fn timestamps_at_the_epoch() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        // Times before the epoch count as the epoch
+        assert_eq!(utc_timestamp(UNIX_EPOCH - Duration::from_secs(1)), "1970-01-01T00:00:00Z");
+        in_zone("America/New_York", || {
+            // Local time before the epoch, on the last day of 1969
+            assert_eq!(local_file_stamp(UNIX_EPOCH), "19691231-190000");
+            assert_eq!(next_hour(UNIX_EPOCH), at(3600));
+        });
+        in_zone("Asia/Kolkata", || {
+            assert_eq!(local_file_stamp(UNIX_EPOCH), "19700101-053000");
+            assert_eq!(next_hour(UNIX_EPOCH), at(1800));
+        });
+    }
+
+    #[test]
+    fn next_hour_in_utc() {
+        in_zone("UTC", || {
+            assert_eq!(next_hour(at(1_710_043_199)), at(1_710_043_200));
+            assert_eq!(next_hour(at(1_710_043_200)), at(1_710_046_800));
+        });
+    }
+
+    #[test]
+    fn next_hour_across_daylight_saving() {
+        in_zone("America/New_York", || {
+            // 01:30 EST, before the clocks go forward at 02:00: next is 03:00 EDT
+            assert_eq!(next_hour(at(1_710_052_200)), at(1_710_054_000));
+            assert_eq!(local_file_stamp(at(1_710_054_000)), "20240310-030000");
+            // 01:30 EDT, before they go back at 02:00: next is the first 01:00 EST
+            assert_eq!(next_hour(at(1_730_611_800)), at(1_730_613_600));
+            assert_eq!(local_file_stamp(at(1_730_613_600)), "20241103-010000");
+            // 01:30 EST, the second time round: next is 02:00 EST
+            assert_eq!(next_hour(at(1_730_615_400)), at(1_730_617_200));
+        });
+        in_zone("Australia/Lord_Howe", || {
+            // 01:30 at +10:30; at 02:00 the clock goes forward half an hour to 02:30, so
+            // the next full hour is 03:00 at +11
+            assert_eq!(next_hour(at(1_728_140_400)), at(1_728_144_000));
+            assert_eq!(local_file_stamp(at(1_728_144_000)), "20241006-030000");
+            // 01:45 at +11; at 02:00 the clock goes back to 01:30, so the next full hour is
+            // 02:00 at +10:30
+            assert_eq!(next_hour(at(1_743_864_300)), at(1_743_867_000));
+            assert_eq!(local_file_stamp(at(1_743_867_000)), "20250406-020000");
+        });
+    }
+
+    #[test]
+    fn schedules_follow_the_local_clock_across_daylight_saving() {
+        in_zone("America/New_York", || {
+            let schedule = Schedule::parse("06:00-07:00").unwrap();
+            // 23:00 EST the night the clocks go forward
+            let (start, end) = schedule.window(at(1_710_043_200));
+            assert_eq!(local_file_stamp(start), "20240310-060000");
+            assert_eq!(end.duration_since(start).unwrap(), Duration::from_secs(3600));
+            assert!(schedule.contains(start));
+            // Noon EDT the day before the clocks go back
+            let (start, _) = Schedule::parse("03:00-04:00").unwrap().window(at(1_730_563_200));
+            assert_eq!(local_file_stamp(start), "20241103-030000");
+        });
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+
+pub mod toml;
+
+use crate::audio::Normalization;
+use self::toml::Table;
+
+const APP_NAME: &str = "fourrier";
+
+/// Settings read from `config.toml`. Every field is optional; command-line flags win over the
+/// file, and the file wins over built-in defaults.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub model: Option<PathBuf>,
+    pub window_size: Option<usize>,
+    /// Window compensation of spectrogram levels
+    pub normalization: Option<Normalization>,
+    /// Seconds between autosaves of the TUI session; 0 turns autosave off
+    pub autosave_interval: Option<u64>,
+    /// Everything in the file, for settings owned by other modules
+    pub table: Table,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let table = toml::parse(&text).with_context(|| format!("Invalid config {}", path.display()))?;
+        let normalization = match table.get("normalization").and_then(|v| v.as_str()) {
+            Some(name) => Some(Normalization::from_str(name, true).map_err(|_| {
+                anyhow!("Invalid config {}: normalization must be peak, energy or none, not `{}`", path.display(), name)
+            })?),
+            None => None,
+        };
+        let window_size = match table.get("window_size") {
+            Some(value) => Some(value.as_integer().and_then(window_size).ok_or_else(|| {
+                anyhow!("Invalid config {}: window_size must be a power of two of at least 2, not {}", path.display(), value)
+            })?),
+            None => None,
+        };
+        Ok(Self {
+            model: table.get("model").and_then(|v| v.as_str()).map(PathBuf::from),
+            window_size,
+            normalization,
+            autosave_interval: table
+                .get("autosave_interval")
+                .and_then(|v| v.as_integer())
+                .map(|v| v.max(0) as u64),
+            table,
+        })
+    }
+
+    /// Loads the config at `path`, or the default location when `None`. A missing default
+    /// config is not an error.
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => match default_config_path() {
+                Some(path) if path.exists() => Self::load(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+}
+
+/// `value` as an FFT window size, if it is a power of two of at least 2.
+pub fn window_size(value: i64) -> Option<usize> {
+    usize::try_from(value).ok().filter(|&size| size >= 2 && size.is_power_of_two())
+}
+
+/// Starter config written by the first-run wizard.
+pub fn starter_config(model: Option<&Path>) -> String {
+    let model_line = match model {
+        Some(path) => format!("model = \"{}\"", path.display().to_string().replace('\\', "\\\\")),
+        None => "# model = \"/path/to/ggml-base.bin\"".to_string(),
+    };
+    format!(
+        "# fourrier configuration\n\
+         \n\
+         # Whisper model used for transcription\n\
+         {}\n\
+         \n\
+         # FFT window size for the spectrogram (power of 2); by default the preset picks it\n\
+         # window_size = 1024\n\
+         \n\
+         # Window compensation of spectrogram levels: peak (tones compare across windows),\n\
+         # energy (each frame's bins sum to its power) or none\n\
+         # normalization = \"peak\"\n\
+         \n\
+         # Seconds between crash-recovery autosaves of the TUI session (0 disables)\n\
+         autosave_interval = 30\n",
+        model_line
+    )
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// Per-user configuration directory (`~/.config/fourrier`, `%APPDATA%\fourrier`, ...).
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".config")))
+    };
+    base.map(|dir| dir.join(APP_NAME))
+}
+
+/// Per-user data directory for downloaded models and caches.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".local/share")))
+    };
+    base.map(|dir| dir.join(APP_NAME))
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+pub fn models_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("models"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_sizes_must_be_powers_of_two() {
+        let path = std::env::temp_dir().join(format!("fourrier-config-{}.toml", std::process::id()));
+        let load = |value: &str| {
+            std::fs::write(&path, format!("window_size = {}\n", value)).unwrap();
+            Config::load(&path).map(|config| config.window_size)
+        };
+        assert_eq!(load("2048").unwrap(), Some(2048));
+        for value in ["0", "1", "1000", "-1024", "\"1024\""] {
+            let error = load(value).unwrap_err().to_string();
+            assert!(error.contains("window_size must be a power of two"), "{}: {}", value, error);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
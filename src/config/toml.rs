@@ -0,0 +1,231 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Values the config, theme, keymap and session files hold: TOML's, less tables.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            Value::Integer(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::Integer(i)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Value::Integer(i) => write!(f, "{}", i),
+            // Keep a decimal point so the value reads back as a float
+            Value::Float(x) if x.is_finite() && x.fract() == 0.0 => write!(f, "{:.1}", x),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// Flat table keyed by dotted path (`[theme]` + `waveform = ...` becomes `theme.waveform`).
+pub type Table = BTreeMap<String, Value>;
+
+/// Parses TOML into a flat table, nested tables becoming dotted paths. Arrays of tables,
+/// and tables inside arrays, aren't used by any of the files and are refused.
+pub fn parse(text: &str) -> Result<Table> {
+    let document: ::toml::Table = text.parse()?;
+    let mut table = Table::new();
+    flatten("", document, &mut table)?;
+    Ok(table)
+}
+
+fn flatten(prefix: &str, document: ::toml::Table, table: &mut Table) -> Result<()> {
+    for (key, value) in document {
+        let path = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+        match value {
+            ::toml::Value::Table(nested) => flatten(&path, nested, table)?,
+            value => {
+                let value = convert(value).map_err(|e| anyhow!("{}: {}", path, e))?;
+                table.insert(path, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn convert(value: ::toml::Value) -> std::result::Result<Value, &'static str> {
+    Ok(match value {
+        ::toml::Value::String(s) => Value::String(s),
+        ::toml::Value::Integer(i) => Value::Integer(i),
+        ::toml::Value::Float(f) => Value::Float(f),
+        ::toml::Value::Boolean(b) => Value::Bool(b),
+        ::toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        ::toml::Value::Array(items) => Value::Array(items.into_iter().map(convert).collect::<std::result::Result<_, _>>()?),
+        ::toml::Value::Table(_) => return Err("arrays of tables aren't supported"),
+    })
+}
+
+/// Writes a table back out: top-level keys first, then one `[section]` per dotted prefix.
+pub fn to_string(table: &Table) -> String {
+    let mut document = ::toml::Table::new();
+    for (path, value) in table {
+        match path.split_once('.') {
+            Some((section, key)) => {
+                let section = document
+                    .entry(section)
+                    .or_insert_with(|| ::toml::Value::Table(::toml::Table::new()));
+                if let ::toml::Value::Table(section) = section {
+                    section.insert(key.to_string(), to_toml(value));
+                }
+            }
+            None => {
+                document.insert(path.clone(), to_toml(value));
+            }
+        }
+    }
+    ::toml::to_string(&document).expect("tables of plain values always serialize")
+}
+
+fn to_toml(value: &Value) -> ::toml::Value {
+    match value {
+        Value::String(s) => ::toml::Value::String(s.clone()),
+        Value::Integer(i) => ::toml::Value::Integer(*i),
+        Value::Float(f) => ::toml::Value::Float(*f),
+        Value::Bool(b) => ::toml::Value::Boolean(*b),
+        Value::Array(items) => ::toml::Value::Array(items.iter().map(to_toml).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_in_strings_is_not_a_comment() {
+        let table = parse("a = 'C# minor' # comment\nb = \"#fff\"").unwrap();
+        assert_eq!(table["a"], Value::from("C# minor"));
+        assert_eq!(table["b"], Value::from("#fff"));
+    }
+
+    #[test]
+    fn multi_line_and_nested_arrays() {
+        let table = parse("[transcript]\nstart = [\n  0.0,\n  1.5, # first\n]\nranges = [[1, 2], [3, 4]]").unwrap();
+        assert_eq!(table["transcript.start"], Value::Array(vec![0.0.into(), 1.5.into()]));
+        assert_eq!(
+            table["transcript.ranges"],
+            Value::Array(vec![Value::Array(vec![1.into(), 2.into()]), Value::Array(vec![3.into(), 4.into()])])
+        );
+    }
+
+    #[test]
+    fn nested_tables_flatten_to_dotted_keys() {
+        let table = parse("[theme.dark]\nwaveform = \"cyan\"\n[keys]\n\"ctrl+s\" = \"save\"").unwrap();
+        assert_eq!(table["theme.dark.waveform"], Value::from("cyan"));
+        assert_eq!(table["keys.ctrl+s"], Value::from("save"));
+    }
+
+    #[test]
+    fn duplicate_keys_are_errors() {
+        assert!(parse("a = 1\na = 2").is_err());
+        assert!(parse("[x]\na = 1\n[x]\nb = 2").is_err());
+    }
+
+    #[test]
+    fn arrays_of_tables_are_refused() {
+        let error = parse("[[x]]\na = 1").unwrap_err();
+        assert!(error.to_string().contains("x"), "{}", error);
+    }
+
+    #[test]
+    fn written_tables_read_back() {
+        let mut table = Table::new();
+        table.insert("position".into(), 1.0.into());
+        table.insert("input".into(), "a \"b\" #c.wav".into());
+        table.insert("transcript.text".into(), Value::Array(vec!["hi\nthere".into(), "it's".into()]));
+        table.insert("keys.ctrl+s".into(), "save".into());
+        assert_eq!(parse(&to_string(&table)).unwrap(), table);
+    }
+}
@@ -0,0 +1,61 @@
+/// Most digipeaters an AX.25 frame's address field can list.
+const MAX_DIGIPEATERS: usize = 8;
+
+/// An AX.25 frame in the usual monitor form: `SOURCE>DESTINATION,DIGI1*,DIGI2:info`, where
+/// `*` marks digipeaters that have repeated it. Frames other than UI frames show their
+/// control byte instead of an information field. `None` if the address field is malformed.
+pub fn describe(frame: &[u8]) -> Option<String> {
+    let mut addresses = Vec::new();
+    let mut offset = 0;
+    loop {
+        let address = frame.get(offset..offset + 7)?;
+        offset += 7;
+        addresses.push(address);
+        // The low bit of the SSID byte marks the last address
+        if address[6] & 1 == 1 {
+            break;
+        }
+        if addresses.len() == 2 + MAX_DIGIPEATERS {
+            return None;
+        }
+    }
+    if addresses.len() < 2 {
+        return None;
+    }
+    let mut text = format!("{}>{}", callsign(addresses[1], false), callsign(addresses[0], false));
+    for digipeater in &addresses[2..] {
+        text.push(',');
+        text.push_str(&callsign(digipeater, true));
+    }
+    let control = *frame.get(offset)?;
+    // A UI frame (unnumbered information, with or without poll) is followed by a PID byte
+    // and the information
+    if control & 0xef == 0x03 {
+        let info = frame.get(offset + 2..).unwrap_or(&[]);
+        let info: String = String::from_utf8_lossy(info)
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        text.push(':');
+        text.push_str(info.trim_end());
+    } else {
+        text.push_str(&format!(" (control 0x{:02x})", control));
+    }
+    Some(text)
+}
+
+/// A callsign from its 7 address bytes: six characters shifted left one bit, padded with
+/// spaces, then the SSID, which is left off when 0.
+fn callsign(address: &[u8], digipeater: bool) -> String {
+    let name: String = address[..6].iter().map(|&b| (b >> 1) as char).collect();
+    let mut callsign = name.trim_end().to_string();
+    let ssid = (address[6] >> 1) & 0x0f;
+    if ssid != 0 {
+        callsign.push_str(&format!("-{}", ssid));
+    }
+    // A digipeater's top bit says it has repeated the frame
+    if digipeater && address[6] & 0x80 != 0 {
+        callsign.push('*');
+    }
+    callsign
+}
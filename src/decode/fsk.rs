@@ -0,0 +1,72 @@
+use rustfft::num_complex::Complex;
+
+/// How far each zero crossing of the discriminator pulls the bit clock toward it, as a
+/// share of the error: enough to lock within a preamble, little enough to ride out noise.
+const CLOCK_GAIN: f64 = 0.3;
+
+/// Frequency-shift keying: a carrier switching between a mark and a space tone at a fixed
+/// symbol rate. Digital modes are a keying and a framing on top.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fsk {
+    /// Frequency of a 1 (mark), in Hz
+    pub mark: f32,
+    /// Frequency of a 0 (space), in Hz
+    pub space: f32,
+    /// Symbols per second
+    pub baud: f32,
+}
+
+/// Bell 202: 1200 baud, 1200 Hz mark, 2200 Hz space, the AFSK of VHF packet radio and APRS.
+pub const BELL_202: Fsk = Fsk { mark: 1200.0, space: 2200.0, baud: 1200.0 };
+
+/// A demodulated symbol: whether it was a mark, and the sample its bit ended at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub mark: bool,
+    pub sample: usize,
+}
+
+/// The symbols `fsk` keyed into `samples` (at `sample_rate`). Each tone is measured by a
+/// correlator over the last symbol's length, and their difference sampled once per
+/// symbol by a clock that re-centers on each change of tone, so it follows a sender whose
+/// rate is slightly off.
+pub fn demodulate(samples: &[f32], sample_rate: u32, fsk: &Fsk) -> Vec<Symbol> {
+    let length = (sample_rate as f32 / fsk.baud).round().max(1.0) as usize;
+    let oscillator = |frequency: f32| {
+        let step = std::f64::consts::TAU * frequency as f64 / sample_rate as f64;
+        move |n: usize| Complex::from_polar(1.0, -step * n as f64)
+    };
+    let (mark_at, space_at) = (oscillator(fsk.mark), oscillator(fsk.space));
+    let (mut mark, mut space) = (Complex::new(0.0f64, 0.0), Complex::new(0.0f64, 0.0));
+
+    let mut symbols = Vec::new();
+    // Where the clock is within a symbol, from 0 to 1; a symbol is read as it wraps
+    let mut phase = 0.0f64;
+    let mut previous = 0.0f64;
+    for (n, &sample) in samples.iter().enumerate() {
+        let sample = sample as f64;
+        mark += mark_at(n) * sample;
+        space += space_at(n) * sample;
+        if n >= length {
+            let old = samples[n - length] as f64;
+            mark -= mark_at(n - length) * old;
+            space -= space_at(n - length) * old;
+        }
+        let (m, s) = (mark.norm_sqr(), space.norm_sqr());
+        // Normalized, so the clock doesn't depend on the level
+        let discriminator = (m - s) / (m + s + f64::MIN_POSITIVE);
+
+        // The correlators straddle a change of tone equally half a symbol after it, so
+        // the symbol ends half a symbol after each zero crossing
+        if (discriminator > 0.0) != (previous > 0.0) {
+            phase += (0.5 - phase) * CLOCK_GAIN;
+        }
+        previous = discriminator;
+        phase += 1.0 / length as f64;
+        if phase >= 1.0 {
+            phase -= 1.0;
+            symbols.push(Symbol { mark: discriminator > 0.0, sample: n });
+        }
+    }
+    symbols
+}
@@ -0,0 +1,179 @@
+use super::fsk::Symbol;
+
+/// Shortest frame kept, in bytes with its check sequence: an AX.25 frame has two
+/// addresses of 7 bytes, a control byte and the 2-byte FCS.
+const MIN_FRAME: usize = 17;
+
+/// Longest frame kept: AX.25's 256-byte information field and its headers.
+const MAX_FRAME: usize = 330;
+
+/// A frame whose check sequence matched, without it, and the samples it spans.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub bytes: Vec<u8>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// HDLC frames in NRZI-coded `symbols`, as AX.25 sends them: a change of tone is a 0 and
+/// no change a 1, frames sit between `01111110` flags, a 0 is stuffed after five 1s in a
+/// row inside a frame, bytes go least significant bit first, and a CRC-16 (X.25) ends each
+/// frame. Frames that fail the check, usually noise between flags, are dropped.
+pub fn deframe(symbols: &[Symbol]) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    let mut bits: Vec<bool> = Vec::new();
+    let mut ones = 0;
+    // Sample the frame began at, after its opening flag
+    let mut start = None;
+    for pair in symbols.windows(2) {
+        let bit = pair[0].mark == pair[1].mark;
+        let sample = pair[1].sample;
+        if bit {
+            ones += 1;
+            if ones > 6 {
+                // Seven 1s abort a frame (and fill the gaps between them)
+                bits.clear();
+                start = None;
+            } else {
+                bits.push(true);
+            }
+            continue;
+        }
+        match ones {
+            6 => {
+                // A flag: what came before its leading 0 and six 1s is a frame
+                bits.truncate(bits.len().saturating_sub(7));
+                if let Some(from) = start {
+                    if let Some(bytes) = check(&bits) {
+                        frames.push(Frame { bytes, start: from, end: sample });
+                    }
+                }
+                bits.clear();
+                start = Some(sample);
+            }
+            // A stuffed 0
+            5 => {}
+            _ => bits.push(false),
+        }
+        ones = 0;
+    }
+    frames
+}
+
+/// The bytes of a frame's `bits`, without the check sequence, if it's a whole number of
+/// bytes of a plausible length and the check sequence matches.
+fn check(bits: &[bool]) -> Option<Vec<u8>> {
+    if !bits.len().is_multiple_of(8) || !(MIN_FRAME..=MAX_FRAME).contains(&(bits.len() / 8)) {
+        return None;
+    }
+    let mut bytes: Vec<u8> = bits
+        .chunks_exact(8)
+        .map(|byte| byte.iter().rev().fold(0u8, |value, &bit| (value << 1) | bit as u8))
+        .collect();
+    let fcs = bytes.split_off(bytes.len() - 2);
+    (crc16_x25(&bytes) == u16::from_le_bytes([fcs[0], fcs[1]])).then_some(bytes)
+}
+
+/// CRC-16/X.25: the reflected CCITT polynomial, starting from and inverted with 0xFFFF.
+fn crc16_x25(bytes: &[u8]) -> u16 {
+    let mut crc = 0xffffu16;
+    for &byte in bytes {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x8408 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The tones (`true` for mark) that carry `frames` as `deframe` reads them, between
+/// `flags` flags of preamble and of tail, as a sender keys them: each frame with its check
+/// sequence, bit-stuffed and followed by a flag, NRZI-coded from a mark.
+#[cfg(test)]
+pub(crate) fn encode(frames: &[&[u8]], flags: usize) -> Vec<bool> {
+    const FLAG: u8 = 0x7e;
+    let byte_bits = |byte: u8| (0..8).map(move |i| byte >> i & 1 == 1);
+    let mut bits: Vec<bool> = (0..flags).flat_map(|_| byte_bits(FLAG)).collect();
+    for frame in frames {
+        let fcs = crc16_x25(frame).to_le_bytes();
+        let mut ones = 0;
+        for bit in frame.iter().chain(&fcs).flat_map(|&byte| byte_bits(byte)) {
+            bits.push(bit);
+            ones = if bit { ones + 1 } else { 0 };
+            if ones == 5 {
+                bits.push(false);
+                ones = 0;
+            }
+        }
+        bits.extend(byte_bits(FLAG));
+    }
+    bits.extend((1..flags).flat_map(|_| byte_bits(FLAG)));
+    let mut tone = true;
+    std::iter::once(tone)
+        .chain(bits.into_iter().map(|bit| {
+            tone ^= !bit;
+            tone
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(tones: &[bool]) -> Vec<Symbol> {
+        tones.iter().enumerate().map(|(i, &mark)| Symbol { mark, sample: i * 40 }).collect()
+    }
+
+    /// 15 bytes with runs of 1s that need stuffing (0xff, and 0x7e, which looks like a flag)
+    const FRAME: [u8; 15] = [0x82, 0xa0, 0xa4, 0xa6, 0x40, 0x40, 0x60, 0xff, 0xff, 0x7e, 0x7e, 0x03, 0xf0, 0x00, 0x1f];
+
+    #[test]
+    fn crc16_x25_check_value() {
+        // The catalogue check value of CRC-16/X.25 (also CRC-16/IBM-SDLC)
+        assert_eq!(crc16_x25(b"123456789"), 0x906e);
+        assert_eq!(crc16_x25(b""), 0x0000);
+    }
+
+    #[test]
+    fn deframes_stuffed_nrzi_frames() {
+        let second = [0x55u8; 20];
+        let frames = deframe(&symbols(&encode(&[&FRAME, &second], 4)));
+        let bytes: Vec<&[u8]> = frames.iter().map(|frame| &frame.bytes[..]).collect();
+        assert_eq!(bytes, [&FRAME[..], &second[..]]);
+        // The first frame ends where the second begins, at its closing flag
+        assert_eq!(frames[0].end, frames[1].start);
+        assert!(frames[0].start < frames[0].end);
+    }
+
+    #[test]
+    fn nrzi_ignores_the_starting_tone() {
+        let inverted: Vec<bool> = encode(&[&FRAME], 2).iter().map(|&tone| !tone).collect();
+        assert_eq!(deframe(&symbols(&inverted))[0].bytes, FRAME);
+    }
+
+    #[test]
+    fn drops_frames_that_fail_the_check() {
+        let mut tones = encode(&[&FRAME], 2);
+        // Flip one data bit well inside the frame: every tone after it inverts, which NRZI
+        // reads as a single changed bit
+        for tone in &mut tones[40..] {
+            *tone = !*tone;
+        }
+        assert!(deframe(&symbols(&tones)).is_empty());
+    }
+
+    #[test]
+    fn seven_ones_abort_a_frame() {
+        let second = [0x55u8; 20];
+        let mut tones = encode(&[&FRAME, &second], 2);
+        // Holding one tone for eight bits sends seven 1s in the middle of the first frame;
+        // the second, after the next flag, still decodes
+        let at = 60;
+        let held = tones[at];
+        tones.splice(at..at, std::iter::repeat_n(held, 8));
+        let frames = deframe(&symbols(&tones));
+        let bytes: Vec<&[u8]> = frames.iter().map(|frame| &frame.bytes[..]).collect();
+        assert_eq!(bytes, [&second[..]]);
+    }
+}
@@ -0,0 +1,138 @@
+use clap::ValueEnum;
+
+pub mod ax25;
+pub mod fsk;
+pub mod hdlc;
+pub mod morse;
+pub mod telephony;
+
+/// What `decode` looks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    /// DTMF digits and call-progress tones (dial tone, ringback, busy, reorder)
+    Dtmf,
+    /// Morse code keyed on a tone (CW), with the tone and speed of each transmission
+    Morse,
+    /// 1200 baud AFSK packet radio (Bell 202 tones, AX.25 frames), as APRS uses on VHF
+    Afsk1200,
+}
+
+/// Something decoded from the audio, with where it was heard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Detection {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Decodes `samples` (at `sample_rate`) in `mode`, in order of time.
+pub fn decode(samples: &[f32], sample_rate: u32, mode: Mode) -> Vec<Detection> {
+    let mut detections = match mode {
+        Mode::Dtmf => {
+            let mut detections = telephony::detect_dtmf(samples, sample_rate);
+            detections.extend(telephony::detect_call_progress(samples, sample_rate));
+            detections
+        }
+        Mode::Morse => morse::detect_morse(samples, sample_rate),
+        Mode::Afsk1200 => {
+            let symbols = fsk::demodulate(samples, sample_rate, &fsk::BELL_202);
+            let seconds = |sample: usize| sample as f64 / sample_rate as f64;
+            hdlc::deframe(&symbols)
+                .into_iter()
+                .filter_map(|frame| {
+                    let text = ax25::describe(&frame.bytes)?;
+                    Some(Detection { start: seconds(frame.start), end: seconds(frame.end), text })
+                })
+                .collect()
+        }
+    };
+    detections.sort_by(|a, b| a.start.total_cmp(&b.start));
+    detections
+}
+
+/// One line per detection.
+pub fn report_lines(detections: &[Detection]) -> Vec<String> {
+    if detections.is_empty() {
+        return vec!["Nothing decoded".to_string()];
+    }
+    detections
+        .iter()
+        .map(|detection| format!("{:>8.3}s-{:<8.3}s {}", detection.start, detection.end, detection.text))
+        .collect()
+}
+
+/// Amplitude of the sine at `frequency` in `frame`, by the Goertzel algorithm over the
+/// frame tapered by `window` (as long as the frame). Cheaper than an FFT for the handful of
+/// frequencies a decoder listens for, and not tied to bin centers.
+pub fn goertzel(frame: &[f32], window: &[f32], sample_rate: u32, frequency: f32) -> f32 {
+    let coefficient = 2.0 * (std::f32::consts::TAU * frequency / sample_rate as f32).cos();
+    let (mut previous, mut before) = (0.0f32, 0.0f32);
+    for (sample, w) in frame.iter().zip(window) {
+        let current = sample * w + coefficient * previous - before;
+        before = previous;
+        previous = current;
+    }
+    let power = previous * previous + before * before - coefficient * previous * before;
+    // A sine of amplitude A sums to A / 2 times the window's sum
+    2.0 * power.max(0.0).sqrt() / window.iter().sum::<f32>().max(f32::MIN_POSITIVE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An AX.25 address: the callsign shifted left one bit and padded with spaces, then the
+    /// SSID byte, marking the last address.
+    fn address(callsign: &str, ssid: u8, last: bool) -> Vec<u8> {
+        let mut bytes: Vec<u8> = format!("{:<6}", callsign).bytes().map(|b| b << 1).collect();
+        bytes.push(0x60 | ssid << 1 | last as u8);
+        bytes
+    }
+
+    /// `tones` keyed as Bell 202 at `baud`, phase-continuous, at 48 kHz.
+    fn afsk(tones: &[bool], baud: f64) -> Vec<f32> {
+        let rate = 48_000.0;
+        let mut phase = 0.0f64;
+        let mut samples = Vec::new();
+        for (i, &mark) in tones.iter().enumerate() {
+            let step = std::f64::consts::TAU * if mark { 1200.0 } else { 2200.0 } / rate;
+            let end = ((i + 1) as f64 * rate / baud).round() as usize;
+            while samples.len() < end {
+                samples.push(0.5 * phase.sin() as f32);
+                phase += step;
+            }
+        }
+        samples
+    }
+
+    fn ui_frame(info: &str) -> Vec<u8> {
+        let mut frame = address("APRS", 0, false);
+        frame.extend(address("N0CALL", 7, false));
+        frame.extend(address("WIDE1", 1, true));
+        frame.extend([0x03, 0xf0]);
+        frame.extend(info.bytes());
+        frame
+    }
+
+    #[test]
+    fn decodes_afsk1200_frames() {
+        let first = ui_frame("!4903.50N/07201.75W-Test ~~ 001");
+        let second = ui_frame(">status");
+        let samples = afsk(&hdlc::encode(&[&first, &second], 24), 1200.0);
+        let detections = decode(&samples, 48_000, Mode::Afsk1200);
+        let texts: Vec<&str> = detections.iter().map(|d| d.text.as_str()).collect();
+        assert_eq!(texts, ["N0CALL-7>APRS,WIDE1-1:!4903.50N/07201.75W-Test ~~ 001", "N0CALL-7>APRS,WIDE1-1:>status"]);
+        // The first frame starts after the 24 preamble flags, 160 ms
+        assert!((detections[0].start - 0.16).abs() < 0.002, "{}", detections[0].start);
+    }
+
+    #[test]
+    fn follows_a_sender_slightly_off_rate() {
+        let frame = ui_frame("clock drift");
+        for baud in [1188.0, 1212.0] {
+            let samples = afsk(&hdlc::encode(&[&frame], 24), baud);
+            let texts: Vec<String> = decode(&samples, 48_000, Mode::Afsk1200).into_iter().map(|d| d.text).collect();
+            assert_eq!(texts, ["N0CALL-7>APRS,WIDE1-1:clock drift"], "at {} baud", baud);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use super::{goertzel, Detection};
+use crate::audio::spectrum::average_spectrum;
+use crate::audio::WindowFunction;
+
+/// Range the keyed tone is looked for in: CW is usually listened to at 400-1000 Hz.
+const TONE_RANGE: (f32, f32) = (200.0, 2000.0);
+
+/// Envelope frames, short enough for a 50 WPM dit (24 ms), and the step between them, in
+/// seconds.
+const FRAME: f64 = 0.02;
+const HOP: f64 = 0.005;
+
+/// Silences longer than this many dits end a transmission.
+const END_OF_TRANSMISSION: f64 = 30.0;
+
+/// International Morse code (ITU-R M.1677).
+const CODE: &[(&str, char)] = &[
+    (".-", 'A'), ("-...", 'B'), ("-.-.", 'C'), ("-..", 'D'), (".", 'E'), ("..-.", 'F'),
+    ("--.", 'G'), ("....", 'H'), ("..", 'I'), (".---", 'J'), ("-.-", 'K'), (".-..", 'L'),
+    ("--", 'M'), ("-.", 'N'), ("---", 'O'), (".--.", 'P'), ("--.-", 'Q'), (".-.", 'R'),
+    ("...", 'S'), ("-", 'T'), ("..-", 'U'), ("...-", 'V'), (".--", 'W'), ("-..-", 'X'),
+    ("-.--", 'Y'), ("--..", 'Z'), ("-----", '0'), (".----", '1'), ("..---", '2'),
+    ("...--", '3'), ("....-", '4'), (".....", '5'), ("-....", '6'), ("--...", '7'),
+    ("---..", '8'), ("----.", '9'), (".-.-.-", '.'), ("--..--", ','), ("..--..", '?'),
+    (".----.", '\''), ("-.-.--", '!'), ("-..-.", '/'), ("-.--.", '('), ("-.--.-", ')'),
+    (".-...", '&'), ("---...", ':'), ("-.-.-.", ';'), ("-...-", '='), (".-.-.", '+'),
+    ("-....-", '-'), (".-..-.", '"'), (".--.-.", '@'),
+];
+
+/// Morse code keyed on a tone: a detection per word, after one for each transmission
+/// giving its tone and speed. The tone is the strongest in `TONE_RANGE`; the speed comes
+/// from the dits and dahs themselves, so it follows any sender.
+pub fn detect_morse(samples: &[f32], sample_rate: u32) -> Vec<Detection> {
+    let spectrum = average_spectrum(samples, sample_rate, 4096, 64);
+    let Some(tone) = (spectrum.bin(TONE_RANGE.0)..=spectrum.bin(TONE_RANGE.1))
+        .max_by(|&a, &b| spectrum.levels[a].total_cmp(&spectrum.levels[b]))
+        .map(|bin| spectrum.frequencies[bin])
+    else {
+        return Vec::new();
+    };
+
+    let size = (FRAME * sample_rate as f64) as usize;
+    let hop = ((HOP * sample_rate as f64) as usize).max(1);
+    let window = WindowFunction::Hann.coefficients(size);
+    let envelope: Vec<f32> = (0..samples.len().saturating_sub(size))
+        .step_by(hop)
+        .map(|start| goertzel(&samples[start..start + size], &window, sample_rate, tone))
+        .collect();
+    let keyed = key_states(&envelope);
+    // Runs of key down (true) or up, as (down, start, end) in seconds, each frame's time
+    // being its center
+    let time = |frame: usize| (frame * hop + size / 2) as f64 / sample_rate as f64;
+    let mut runs: Vec<(bool, f64, f64)> = Vec::new();
+    for (frame, &down) in keyed.iter().enumerate() {
+        match runs.last_mut() {
+            Some((state, _, end)) if *state == down => *end = time(frame + 1),
+            _ => runs.push((down, time(frame), time(frame + 1))),
+        }
+    }
+    // Leading and trailing silence say nothing about the timing
+    while runs.first().is_some_and(|run| !run.0) {
+        runs.remove(0);
+    }
+    while runs.last().is_some_and(|run| !run.0) {
+        runs.pop();
+    }
+    let marks: Vec<f64> = runs.iter().filter(|run| run.0).map(|run| run.2 - run.1).collect();
+    let Some(dit) = dit_length(&marks) else {
+        return Vec::new();
+    };
+
+    let mut detections = Vec::new();
+    // Index of the current transmission's summary detection
+    let mut summary: Option<usize> = None;
+    let (mut word, mut letter) = (String::new(), String::new());
+    let (mut word_start, mut last_mark) = (0.0, 0.0);
+    for &(down, start, end) in &runs {
+        let units = (end - start) / dit;
+        if down {
+            if summary.is_none() {
+                summary = Some(detections.len());
+                detections.push(Detection { start, end, text: String::new() });
+            }
+            if word.is_empty() && letter.is_empty() {
+                word_start = start;
+            }
+            letter.push(if units < 2.0 { '.' } else { '-' });
+            last_mark = end;
+            continue;
+        }
+        // Gaps of one dit are within a letter, three between letters and seven between words
+        if units >= 2.0 {
+            end_letter(&mut word, &mut letter);
+        }
+        if units >= 5.0 && !word.is_empty() {
+            detections.push(Detection { start: word_start, end: last_mark, text: std::mem::take(&mut word) });
+        }
+        if units >= END_OF_TRANSMISSION {
+            if let Some(index) = summary.take() {
+                close_transmission(&mut detections, index, last_mark, tone, dit);
+            }
+        }
+    }
+    end_letter(&mut word, &mut letter);
+    if !word.is_empty() {
+        detections.push(Detection { start: word_start, end: last_mark, text: word });
+    }
+    if let Some(index) = summary {
+        close_transmission(&mut detections, index, last_mark, tone, dit);
+    }
+    detections
+}
+
+/// Adds the character `letter` (dots and dashes) spells to `word`, `*` if it spells none.
+fn end_letter(word: &mut String, letter: &mut String) {
+    if !letter.is_empty() {
+        word.push(CODE.iter().find(|(code, _)| *code == letter.as_str()).map_or('*', |&(_, c)| c));
+        letter.clear();
+    }
+}
+
+/// Fills in the summary detection at `index` once its transmission has ended at `end`.
+fn close_transmission(detections: &mut [Detection], index: usize, end: f64, tone: f32, dit: f64) {
+    detections[index].end = end;
+    // PARIS, the standard word, is 50 dits long: WPM = 60 / (50 * dit)
+    detections[index].text = format!("Morse at {:.0} Hz, {:.0} WPM", tone, 1.2 / dit);
+}
+
+/// Whether the key is down in each envelope frame: above a threshold halfway between the
+/// noise and the tone levels, with hysteresis so noise on the edges doesn't chatter. A
+/// frame straddling an edge reads the share of the window the tone covers, so halfway is
+/// where the frame is centered on the edge and marks keep their length.
+fn key_states(envelope: &[f32]) -> Vec<bool> {
+    let mut sorted: Vec<f32> = envelope.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let percentile = |p: f64| sorted.get((p * (sorted.len().max(1) - 1) as f64) as usize).copied().unwrap_or(0.0);
+    let (noise, tone) = (percentile(0.1).max(1e-6), percentile(0.95).max(1e-6));
+    // No keying: the tone never rises 10 dB above the noise
+    if tone < noise * 3.0 {
+        return vec![false; envelope.len()];
+    }
+    let middle = (noise + tone) / 2.0;
+    let (on, off) = (middle * 1.2, middle / 1.2);
+    let mut down = false;
+    envelope
+        .iter()
+        .map(|&level| {
+            down = if down { level > off } else { level > on };
+            down
+        })
+        .collect()
+}
+
+/// Length of a dit in seconds, from the lengths of the marks: dits and dahs (three dits)
+/// split into two clusters by k-means.
+fn dit_length(marks: &[f64]) -> Option<f64> {
+    if marks.is_empty() {
+        return None;
+    }
+    let mut sorted = marks.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let (mut dit, mut dah) = (sorted[sorted.len() / 5], sorted[sorted.len() * 4 / 5]);
+    // All one length: taken for dits, which text has more of than dahs
+    if dah < dit * 2.0 {
+        return Some(dit);
+    }
+    for _ in 0..10 {
+        let split = (dit + dah) / 2.0;
+        let (short, long): (Vec<f64>, Vec<f64>) = marks.iter().copied().partition(|&mark| mark < split);
+        if short.is_empty() || long.is_empty() {
+            break;
+        }
+        dit = short.iter().sum::<f64>() / short.len() as f64;
+        dah = long.iter().sum::<f64>() / long.len() as f64;
+    }
+    // Both clusters measure the unit; dahs more precisely, being longer
+    Some((dit + dah / 3.0) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::generator::{generate, Signal, SignalParams};
+
+    /// `text` keyed on a 700 Hz tone at `wpm` (PARIS timing) and 8 kHz, with a second of
+    /// silence on each side.
+    fn keyed(text: &str, wpm: f64) -> Vec<f32> {
+        let dit = (1.2 / wpm * 8000.0) as usize;
+        // Every element is followed by a dit of silence, which the letter and word gaps
+        // (three and seven dits) include
+        let mut on = vec![false; 8000];
+        for (n, word) in text.split(' ').enumerate() {
+            if n > 0 {
+                on.extend(vec![false; 6 * dit]);
+            }
+            for (m, letter) in word.chars().enumerate() {
+                if m > 0 {
+                    on.extend(vec![false; 2 * dit]);
+                }
+                let code = CODE.iter().find(|&&(_, c)| c == letter).unwrap().0;
+                for element in code.chars() {
+                    on.extend(vec![true; if element == '-' { 3 * dit } else { dit }]);
+                    on.extend(vec![false; dit]);
+                }
+            }
+        }
+        on.extend(vec![false; 8000]);
+        on.iter()
+            .enumerate()
+            .map(|(i, &on)| if on { 0.3 * (std::f32::consts::TAU * 700.0 * i as f32 / 8000.0).sin() } else { 0.0 })
+            .collect()
+    }
+
+    #[test]
+    fn keyed_text_decodes_with_its_tone_and_speed() {
+        for wpm in [5.0, 12.0, 20.0, 30.0, 40.0] {
+            let detections = detect_morse(&keyed("CQ DE K1ABC", wpm), 8000);
+            let texts: Vec<&str> = detections.iter().map(|d| d.text.as_str()).collect();
+            assert_eq!(texts[1..], ["CQ", "DE", "K1ABC"]);
+            let (tone, speed) = texts[0].strip_prefix("Morse at ").unwrap().split_once(" Hz, ").unwrap();
+            let tone: f32 = tone.parse().unwrap();
+            let speed: f64 = speed.strip_suffix(" WPM").unwrap().parse().unwrap();
+            assert!((tone - 700.0).abs() < 5.0, "700 Hz read as {}", tone);
+            assert!((speed - wpm).abs() <= 1.0, "{} WPM read as {}", wpm, speed);
+            assert!((detections[1].start - 1.0).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn keyed_text_decodes_through_noise() {
+        let mut samples = keyed("SOS 73", 18.0);
+        let params = SignalParams { duration: 10.0, sample_rate: 8000, level_db: -20.0, ..Default::default() };
+        let noise = generate(Signal::White, &params).unwrap();
+        samples.iter_mut().zip(noise).for_each(|(s, n)| *s += n);
+        let detections = detect_morse(&samples, 8000);
+        let texts: Vec<&str> = detections.iter().map(|d| d.text.as_str()).collect();
+        assert_eq!(texts[1..], ["SOS", "73"]);
+        assert!(texts[0].ends_with(", 18 WPM"), "{}", texts[0]);
+    }
+
+    #[test]
+    fn silence_decodes_nothing() {
+        assert!(detect_morse(&vec![0.0; 16000], 8000).is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use super::{goertzel, Detection};
+use crate::audio::WindowFunction;
+
+/// DTMF's low (row) and high (column) group frequencies, in Hz.
+const ROWS: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+const COLUMNS: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+const KEYS: [[char; 4]; 4] = [['1', '2', '3', 'A'], ['4', '5', '6', 'B'], ['7', '8', '9', 'C'], ['*', '0', '#', 'D']];
+
+/// DTMF analysis frames, long enough to tell the group frequencies apart and short
+/// enough to catch the shortest valid digit, and the step between them, in seconds.
+const DTMF_FRAME: f64 = 0.025;
+const DTMF_HOP: f64 = 0.01;
+
+/// Shortest tone accepted as a digit (ITU-T Q.24 asks for 40 ms), in seconds.
+const MIN_DIGIT: f64 = 0.04;
+
+/// Call-progress frames are longer: 440 and 480 Hz, or 425 and 440 Hz, have to be told
+/// apart. In seconds.
+const PROGRESS_FRAME: f64 = 0.2;
+const PROGRESS_HOP: f64 = 0.05;
+
+/// Tones the call-progress signals are made of: the North American precise tone plan
+/// (350, 440, 480, 620 Hz) and the single 425 Hz of most of Europe.
+const PROGRESS_TONES: [f32; 5] = [350.0, 425.0, 440.0, 480.0, 620.0];
+
+/// Bursts of a call-progress signal further apart than this are separate signals, in
+/// seconds. Ringback is silent for 4 s between rings.
+const MAX_GAP: f64 = 4.5;
+
+/// Quietest tone detected, as a sine's amplitude (-40 dBFS).
+const MIN_AMPLITUDE: f32 = 0.01;
+
+/// Share of a frame's power the tones must hold, so speech and music with energy at the
+/// same frequencies aren't taken for signaling.
+const PURITY: f32 = 0.7;
+
+/// How far above the other frequencies of its group a DTMF tone must be, as an amplitude
+/// ratio (6 dB).
+const SEPARATION: f32 = 2.0;
+
+/// Largest level difference between a digit's two tones (twist), as an amplitude ratio
+/// (8 dB).
+const TWIST: f32 = 2.5;
+
+/// DTMF digits, one detection per key press.
+pub fn detect_dtmf(samples: &[f32], sample_rate: u32) -> Vec<Detection> {
+    let size = (DTMF_FRAME * sample_rate as f64) as usize;
+    let hop = ((DTMF_HOP * sample_rate as f64) as usize).max(1);
+    let window = WindowFunction::Hann.coefficients(size);
+    let seconds = |index: usize| index as f64 / sample_rate as f64;
+    let mut detections: Vec<Detection> = Vec::new();
+    // The key being held, and where it started and its last frame ended
+    let mut held: Option<(char, usize, usize)> = None;
+    let mut finish = |held: Option<(char, usize, usize)>| {
+        if let Some((key, start, end)) = held {
+            if seconds(end - start) >= MIN_DIGIT {
+                detections.push(Detection { start: seconds(start), end: seconds(end), text: key.to_string() });
+            }
+        }
+    };
+    for start in (0..samples.len().saturating_sub(size)).step_by(hop) {
+        let key = dtmf_key(&samples[start..start + size], &window, sample_rate);
+        held = match (held, key) {
+            (Some((held_key, from, _)), Some(key)) if held_key == key => Some((key, from, start + size)),
+            (previous, key) => {
+                finish(previous);
+                key.map(|key| (key, start, start + size))
+            }
+        };
+    }
+    finish(held);
+    detections
+}
+
+/// The key `frame` holds: one strong tone from each group, close in level, and little else.
+fn dtmf_key(frame: &[f32], window: &[f32], sample_rate: u32) -> Option<char> {
+    let strongest = |frequencies: &[f32; 4]| {
+        let mut levels: Vec<(usize, f32)> =
+            frequencies.iter().map(|&f| goertzel(frame, window, sample_rate, f)).enumerate().collect();
+        levels.sort_by(|a, b| b.1.total_cmp(&a.1));
+        (levels[0].0, levels[0].1, levels[1].1)
+    };
+    let (row, low, next_low) = strongest(&ROWS);
+    let (column, high, next_high) = strongest(&COLUMNS);
+    let distinct = low >= MIN_AMPLITUDE
+        && high >= MIN_AMPLITUDE
+        && low >= next_low * SEPARATION
+        && high >= next_high * SEPARATION
+        && (low / high).max(high / low) <= TWIST;
+    // A sine's power is half its amplitude squared
+    let power = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+    (distinct && (low * low + high * high) / 2.0 >= PURITY * power).then_some(KEYS[row][column])
+}
+
+/// The call-progress signals a frame can hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Signal {
+    /// 350 + 440 Hz
+    Dial,
+    /// 440 + 480 Hz
+    Ringback,
+    /// 480 + 620 Hz, slow for busy and fast for reorder
+    Busy,
+    /// 425 Hz alone, which is dial tone, ringback or busy by its cadence
+    Single,
+}
+
+/// Dial tone, ringback, busy and reorder, each with its cadence.
+pub fn detect_call_progress(samples: &[f32], sample_rate: u32) -> Vec<Detection> {
+    let size = (PROGRESS_FRAME * sample_rate as f64) as usize;
+    let hop = ((PROGRESS_HOP * sample_rate as f64) as usize).max(1);
+    let window = WindowFunction::Hann.coefficients(size);
+    let seconds = |index: usize| index as f64 / sample_rate as f64;
+
+    // Unbroken runs of one signal, as (signal, start, end) in seconds
+    let mut bursts: Vec<(Signal, f64, f64)> = Vec::new();
+    for start in (0..samples.len().saturating_sub(size)).step_by(hop) {
+        let Some(signal) = progress_signal(&samples[start..start + size], &window, sample_rate) else {
+            continue;
+        };
+        let (from, to) = (seconds(start), seconds(start + size));
+        match bursts.last_mut() {
+            Some((last, _, end)) if *last == signal && *end >= from => *end = to,
+            _ => bursts.push((signal, from, to)),
+        }
+    }
+
+    // Bursts of the same signal close together are one signal's cadence
+    let mut groups: Vec<(Signal, Vec<(f64, f64)>)> = Vec::new();
+    for (signal, start, end) in bursts {
+        match groups.last_mut() {
+            Some((last, group)) if *last == signal && start - group[group.len() - 1].1 <= MAX_GAP => {
+                group.push((start, end))
+            }
+            _ => groups.push((signal, vec![(start, end)])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(signal, bursts)| {
+            let on = bursts.iter().map(|(start, end)| end - start).sum::<f64>() / bursts.len() as f64;
+            let steady = bursts.len() == 1;
+            let name = match signal {
+                Signal::Dial => "dial tone",
+                Signal::Ringback => "ringback",
+                Signal::Busy if on < 0.35 => "reorder",
+                Signal::Busy => "busy",
+                Signal::Single if steady && on >= 2.0 => "dial tone (425 Hz)",
+                Signal::Single if on >= 0.8 => "ringback (425 Hz)",
+                Signal::Single => "busy (425 Hz)",
+            };
+            let text = if steady {
+                format!("{}, {:.1} s", name, on)
+            } else {
+                format!("{}, {} bursts of {:.2} s", name, bursts.len(), on)
+            };
+            Detection { start: bursts[0].0, end: bursts[bursts.len() - 1].1, text }
+        })
+        .collect()
+}
+
+fn progress_signal(frame: &[f32], window: &[f32], sample_rate: u32) -> Option<Signal> {
+    let levels: Vec<f32> = PROGRESS_TONES.iter().map(|&f| goertzel(frame, window, sample_rate, f)).collect();
+    let power = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+    // The tones at these indices of PROGRESS_TONES, if they're all present and make up
+    // the frame
+    let holds = |tones: &[usize]| {
+        tones.iter().all(|&i| levels[i] >= MIN_AMPLITUDE)
+            && tones.iter().map(|&i| levels[i] * levels[i] / 2.0).sum::<f32>() >= PURITY * power
+    };
+    let signals: [(Signal, &[usize]); 4] =
+        [(Signal::Dial, &[0, 2]), (Signal::Ringback, &[2, 3]), (Signal::Busy, &[3, 4]), (Signal::Single, &[1])];
+    signals
+        .into_iter()
+        .find(|(_, tones)| holds(tones))
+        .map(|(signal, _)| signal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sines at `frequencies`, each of amplitude `amplitude`, for `seconds` at 8 kHz.
+    fn tones(frequencies: &[f32], amplitude: f32, seconds: f64) -> Vec<f32> {
+        (0..(seconds * 8000.0) as usize)
+            .map(|i| {
+                let t = i as f32 / 8000.0;
+                frequencies.iter().map(|f| amplitude * (std::f32::consts::TAU * f * t).sin()).sum()
+            })
+            .collect()
+    }
+
+    fn key_tones(key: char) -> [f32; 2] {
+        let row = KEYS.iter().position(|keys| keys.contains(&key)).unwrap();
+        let column = KEYS[row].iter().position(|&k| k == key).unwrap();
+        [ROWS[row], COLUMNS[column]]
+    }
+
+    #[test]
+    fn dialed_digits_are_decoded_in_order() {
+        let digits = "159#0*D";
+        let mut samples = tones(&[], 0.0, 0.1);
+        for key in digits.chars() {
+            samples.extend(tones(&key_tones(key), 0.2, 0.07));
+            samples.extend(tones(&[], 0.0, 0.05));
+        }
+        let detections = detect_dtmf(&samples, 8000);
+        let decoded: String = detections.iter().map(|d| d.text.as_str()).collect();
+        assert_eq!(decoded, digits);
+        // Each digit is found where it was keyed, to within a frame
+        for (n, detection) in detections.iter().enumerate() {
+            let start = 0.1 + n as f64 * 0.12;
+            assert!((detection.start - start).abs() <= DTMF_FRAME, "{:?} should start at {}", detection, start);
+        }
+    }
+
+    #[test]
+    fn short_blips_and_single_tones_are_not_digits() {
+        let mut samples = tones(&key_tones('5'), 0.2, 0.02);
+        samples.extend(tones(&[], 0.0, 0.1));
+        samples.extend(tones(&[770.0], 0.3, 0.2));
+        assert!(detect_dtmf(&samples, 8000).is_empty());
+    }
+
+    #[test]
+    fn ringback_is_told_by_its_cadence() {
+        let mut samples = Vec::new();
+        for _ in 0..2 {
+            samples.extend(tones(&[440.0, 480.0], 0.1, 2.0));
+            samples.extend(tones(&[], 0.0, 4.0));
+        }
+        let detections = detect_call_progress(&samples, 8000);
+        assert_eq!(detections.len(), 1);
+        assert!(detections[0].text.starts_with("ringback, 2 bursts"), "{}", detections[0].text);
+    }
+}
@@ -0,0 +1,232 @@
+use std::io::IsTerminal;
+use std::path::Path;
+
+use crate::audio::capture;
+use crate::audio::playback::{backend, find_program};
+use crate::audio::{compute_spectrogram, load_audio_bytes};
+use crate::config::Config;
+use crate::export::wav::wav_bytes;
+use crate::models::{self, ggml::ModelHeader};
+use crate::visualization::console::{ConsoleCapabilities, MIN_FULL_SIZE};
+use crate::visualization::graphics::{detect, GraphicsProtocol};
+
+/// Frequency of the built-in test tone in Hz.
+const TEST_TONE: f32 = 1000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// Works, with reduced features
+    Warn,
+    /// Something won't work until fixed
+    Fail,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// The outcome of one check, with what to do about it when it isn't ok.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Ok, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Runs every check: the terminal, the Whisper model, the audio players and recorders and decoding.
+pub fn run(model: Option<&Path>, config: &Config) -> Vec<Check> {
+    let mut checks = terminal_checks(ConsoleCapabilities::detect(), |name| std::env::var(name).ok());
+    checks.push(model_check(model, config));
+    checks.push(player_check());
+    checks.push(recorder_check());
+    checks.push(ffmpeg_check());
+    checks.push(decode_check());
+    checks
+}
+
+/// Color depth, size and graphics protocol of the attached terminal.
+pub fn terminal_checks(capabilities: ConsoleCapabilities, var: impl Fn(&str) -> Option<String>) -> Vec<Check> {
+    let mut checks = Vec::new();
+    checks.push(if !std::io::stdout().is_terminal() {
+        Check::warn(
+            "terminal",
+            "stdout is not a terminal",
+            "Run fourrier in a terminal to open the visualizer; --stats, --json and --dump work anywhere",
+        )
+    } else if !capabilities.color {
+        Check::warn("color", "color is disabled (NO_COLOR or TERM=dumb)", "Unset NO_COLOR, or use --render braille")
+    } else if !capabilities.truecolor {
+        Check::warn(
+            "color",
+            "256 colors (COLORTERM is not truecolor)",
+            "Set COLORTERM=truecolor if the terminal supports 24-bit color, for smoother gradients",
+        )
+    } else {
+        Check::ok("color", "24-bit color")
+    });
+    if !capabilities.unicode {
+        checks.push(Check::warn(
+            "unicode",
+            "the locale is not UTF-8; braille and block glyphs are replaced",
+            "Set LANG to a UTF-8 locale, e.g. en_US.UTF-8",
+        ));
+    }
+    if let Ok((columns, rows)) = crossterm::terminal::size() {
+        let (min_columns, min_rows) = MIN_FULL_SIZE;
+        checks.push(if columns < min_columns || rows < min_rows {
+            Check::warn(
+                "size",
+                format!("{}×{}; the view is simplified", columns, rows),
+                format!("Enlarge the window to at least {}×{} for full detail", min_columns, min_rows),
+            )
+        } else {
+            Check::ok("size", format!("{}×{}", columns, rows))
+        });
+    }
+    if capabilities.limited() {
+        checks.push(Check::warn(
+            "link",
+            "remote session without truecolor; the view is simplified",
+            "Pass --display full to draw everything anyway",
+        ));
+    }
+    checks.push(match detect(&var) {
+        Some(GraphicsProtocol::Kitty) => Check::ok("graphics", "Kitty graphics protocol"),
+        Some(GraphicsProtocol::Sixel) => Check::ok("graphics", "Sixel"),
+        None if var("TMUX").is_some() || var("STY").is_some() => Check::warn(
+            "graphics",
+            "none inside tmux/screen",
+            "Run outside the multiplexer, or pass --graphics kitty|sixel if it passes images through",
+        ),
+        None => Check::warn(
+            "graphics",
+            "no known graphics protocol; the spectrogram is drawn with characters",
+            "Use Kitty, WezTerm, Ghostty or foot for an inline true-color spectrogram, or pass --graphics kitty|sixel",
+        ),
+    });
+    checks
+}
+
+/// Whether a Whisper model is found, readable and fits in free memory.
+pub fn model_check(model: Option<&Path>, config: &Config) -> Check {
+    let path = match models::resolve(model, config) {
+        Ok(path) => path,
+        Err(_) => {
+            return Check::fail(
+                "model",
+                "no Whisper model found",
+                format!("Run `fourrier models download {}`, or pass --model", models::DEFAULT_MODEL),
+            )
+        }
+    };
+    let header = match ModelHeader::read(&path) {
+        Ok(header) => header,
+        Err(e) => {
+            return Check::fail(
+                "model",
+                e.to_string(),
+                "Download it again with `fourrier models download`, or point --model at a ggml model",
+            )
+        }
+    };
+    let detail = format!("{} ({} {})", path.display(), header.class.name(), header.format);
+    match models::available_memory() {
+        Some(available) if header.memory_needed() > available => Check::warn(
+            "model",
+            format!("{}; needs about {} MB but {} MB is free", detail, header.memory_needed() >> 20, available >> 20),
+            "Use a smaller or quantized model, e.g. `fourrier models download base-q5_1`",
+        ),
+        _ => Check::ok("model", detail),
+    }
+}
+
+/// Whether a player for the visualizer's playback is installed.
+pub fn player_check() -> Check {
+    match backend() {
+        Some(program) => Check::ok("playback", program),
+        None => Check::warn(
+            "playback",
+            "no audio player found; the visualizer can't play audio",
+            "Install paplay (PulseAudio), aplay (ALSA), sox or ffplay",
+        ),
+    }
+}
+
+/// Whether a recorder for `fourrier record` is installed.
+pub fn recorder_check() -> Check {
+    match capture::backend() {
+        Some(program) => Check::ok("recording", program),
+        None => Check::warn(
+            "recording",
+            "no audio recorder found; `fourrier record` can't capture",
+            "Install parec (PulseAudio), arecord (ALSA), sox or ffmpeg",
+        ),
+    }
+}
+
+/// Whether ffmpeg is there for the formats symphonia can't decode.
+pub fn ffmpeg_check() -> Check {
+    if !cfg!(feature = "ffmpeg") {
+        return Check::ok("ffmpeg", "not used by this build (WAV and MP3 only)");
+    }
+    match (find_program("ffmpeg"), find_program("ffprobe")) {
+        (true, true) => Check::ok("ffmpeg", "found; formats besides WAV and MP3 decode through it"),
+        _ => Check::warn(
+            "ffmpeg",
+            "ffmpeg/ffprobe not found; only WAV and MP3 decode",
+            "Install ffmpeg for FLAC, Ogg, M4A and other formats",
+        ),
+    }
+}
+
+/// Decodes a generated one-second test tone and checks that its spectrogram peaks at the
+/// tone's frequency.
+pub fn decode_check() -> Check {
+    let sample_rate: u32 = 16_000;
+    let tone: Vec<f32> = (0..sample_rate)
+        .map(|i| 0.5 * (2.0 * std::f32::consts::PI * TEST_TONE * i as f32 / sample_rate as f32).sin())
+        .collect();
+    let fix = "Reinstall fourrier; decoding is built in, so this is a bug worth reporting";
+    let audio_data = match load_audio_bytes(wav_bytes(&[&tone], sample_rate)) {
+        Ok(audio_data) => audio_data,
+        Err(e) => return Check::fail("decoding", format!("the test tone didn't decode: {}", e), fix),
+    };
+    let peak = compute_spectrogram(&audio_data, 1024).ok().and_then(|spectrogram| {
+        let frame = spectrogram.magnitudes.get(spectrogram.magnitudes.len() / 2)?;
+        let bin = frame
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(bin, _)| bin)?;
+        spectrogram.frequencies.get(bin).copied()
+    });
+    match peak {
+        Some(peak) if (peak - TEST_TONE).abs() <= 2.0 * sample_rate as f32 / 1024.0 => {
+            Check::ok("decoding", format!("{:.0} Hz test tone decoded and analyzed", TEST_TONE))
+        }
+        Some(peak) => Check::fail("decoding", format!("the {:.0} Hz test tone peaked at {:.0} Hz", TEST_TONE, peak), fix),
+        None => Check::fail("decoding", "the test tone's spectrogram is empty", fix),
+    }
+}
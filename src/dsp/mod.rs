@@ -0,0 +1,321 @@
+use anyhow::{anyhow, bail, Result};
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::audio::filter::{apply_notches, de_ess, Biquad};
+use crate::audio::stft::{filter, frame_count, mask_bands, Analyzer};
+use crate::audio::{AudioData, SpectrogramParams};
+use crate::config::toml::{Table, Value};
+use crate::stats::{integrated_loudness, to_dbfs};
+
+/// Window size of `denoise`'s STFT (hop a quarter of it), as for `--mask-band`.
+const DENOISE_WINDOW: usize = 2048;
+
+/// Percentile of each bin's magnitudes over time taken as its noise level: most recordings
+/// have pauses for at least a fifth of their frames.
+const NOISE_PERCENTILE: f64 = 0.2;
+
+/// Most frames `denoise` looks at to estimate the noise, spread evenly over the recording.
+const NOISE_FRAMES: usize = 4096;
+
+/// How far above the noise estimate `denoise` subtracts, so noise peaks are caught too.
+const OVERSUBTRACTION: f32 = 2.0;
+
+/// One preprocessing step of a `--dsp` chain, run over the decoded audio before analysis.
+pub trait AudioEffect {
+    /// Processes every channel in place.
+    fn apply(&self, audio: &mut AudioData) -> Result<()>;
+
+    /// The step as it would be written in a chain, for progress messages.
+    fn describe(&self) -> String;
+}
+
+/// Channels of `audio`, first to last, for effects that treat each the same way.
+fn channels(audio: &mut AudioData) -> impl Iterator<Item = &mut [f32]> {
+    std::iter::once(&mut audio.samples)
+        .chain(audio.extra_channels.iter_mut())
+        .map(|channel| &mut channel[..])
+}
+
+/// Second-order Butterworth highpass (`true`) or lowpass at a cutoff in Hz.
+struct Pass {
+    high: bool,
+    cutoff: f32,
+}
+
+impl AudioEffect for Pass {
+    fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        let rate = audio.sample_rate;
+        if self.cutoff >= rate as f32 / 2.0 {
+            bail!("{} is above Nyquist ({} Hz)", self.describe(), rate / 2);
+        }
+        for channel in channels(audio) {
+            let mut filter = if self.high {
+                Biquad::highpass(rate, self.cutoff, FRAC_1_SQRT_2)
+            } else {
+                Biquad::lowpass(rate, self.cutoff, FRAC_1_SQRT_2)
+            };
+            for sample in channel.iter_mut() {
+                *sample = filter.process(*sample);
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("{}:{}", if self.high { "highpass" } else { "lowpass" }, self.cutoff)
+    }
+}
+
+struct Notch(f32);
+
+impl AudioEffect for Notch {
+    fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        apply_notches(audio, &[self.0]);
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("notch:{}", self.0)
+    }
+}
+
+struct Mask(f32, f32);
+
+impl AudioEffect for Mask {
+    fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        mask_bands(audio, &[(self.0, self.1)])
+    }
+
+    fn describe(&self) -> String {
+        format!("mask:{}-{}", self.0, self.1)
+    }
+}
+
+struct Gain(f32);
+
+impl AudioEffect for Gain {
+    fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        let gain = 10f32.powf(self.0 / 20.0);
+        for channel in channels(audio) {
+            channel.iter_mut().for_each(|sample| *sample *= gain);
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("gain:{}db", self.0)
+    }
+}
+
+/// Level `normalize` brings the audio to.
+#[derive(Clone, Copy)]
+enum Target {
+    /// Integrated loudness of all channels together
+    Lufs(f32),
+    /// Sample peak over all channels
+    Peak(f32),
+}
+
+struct Normalize(Target);
+
+impl AudioEffect for Normalize {
+    fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        let change = match self.0 {
+            Target::Lufs(target) => {
+                let all: Vec<&[f32]> = (0..=audio.extra_channels.len()).filter_map(|c| audio.channel(c)).collect();
+                match integrated_loudness(&all, audio.sample_rate) {
+                    Some(loudness) => target - loudness,
+                    None => {
+                        log::warn!("Audio too short or quiet to measure loudness; not normalizing");
+                        return Ok(());
+                    }
+                }
+            }
+            Target::Peak(target) => {
+                let peak = channels(audio).flat_map(|c| c.iter()).fold(0.0f32, |a, &b| a.max(b.abs()));
+                if peak == 0.0 {
+                    return Ok(());
+                }
+                target - to_dbfs(peak)
+            }
+        };
+        Gain(change).apply(audio)
+    }
+
+    fn describe(&self) -> String {
+        match self.0 {
+            Target::Lufs(target) => format!("normalize:{}lufs", target),
+            Target::Peak(target) => format!("normalize:{}dbfs", target),
+        }
+    }
+}
+
+/// Spectral subtraction: each bin is turned down by how close it is to that bin's noise
+/// level, estimated from its quieter frames, by at most `reduction_db`.
+struct Denoise {
+    reduction_db: f32,
+}
+
+impl AudioEffect for Denoise {
+    fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        let params = SpectrogramParams { hop_size: DENOISE_WINDOW / 4, ..SpectrogramParams::new(DENOISE_WINDOW) };
+        let floor = 10f32.powf(-self.reduction_db / 20.0);
+        for channel in channels(audio) {
+            // The noise level from an even spread of frames, so long recordings need not be held
+            let mut analyzer = Analyzer::new(&params)?;
+            let count = frame_count(channel.len(), params.hop_size);
+            let mut magnitudes = vec![Vec::new(); DENOISE_WINDOW / 2 + 1];
+            for m in (0..count).step_by(count.div_ceil(NOISE_FRAMES)) {
+                for (bin, values) in analyzer.frame(channel, m).iter().zip(&mut magnitudes) {
+                    values.push(bin.norm());
+                }
+            }
+            let noise: Vec<f32> = magnitudes
+                .iter_mut()
+                .map(|values| {
+                    let index = ((values.len() - 1) as f64 * NOISE_PERCENTILE) as usize;
+                    *values.select_nth_unstable_by(index, f32::total_cmp).1
+                })
+                .collect();
+            filter(channel, &params, |_, frame| {
+                for (bin, &noise) in frame.iter_mut().zip(&noise) {
+                    let magnitude = bin.norm();
+                    let gain = if magnitude > 0.0 {
+                        (1.0 - (OVERSUBTRACTION * noise / magnitude).powi(2)).max(0.0).sqrt().max(floor)
+                    } else {
+                        floor
+                    };
+                    *bin *= gain;
+                }
+            })?;
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("denoise:{}db", self.reduction_db)
+    }
+}
+
+struct DeEss(f32);
+
+impl AudioEffect for DeEss {
+    fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        *audio = de_ess(audio, self.0);
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("de-ess:{}db", self.0)
+    }
+}
+
+/// A number with an optional, case-insensitive unit suffix from `units`.
+fn number(text: &str, units: &[&str]) -> Option<f32> {
+    let lower = text.trim().to_ascii_lowercase();
+    let value = units.iter().find_map(|unit| lower.strip_suffix(unit)).unwrap_or(&lower);
+    value.trim().parse().ok().filter(|v: &f32| v.is_finite())
+}
+
+/// Parses one `name[:argument]` step.
+fn parse_effect(step: &str) -> Result<Box<dyn AudioEffect>> {
+    let (name, argument) = match step.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument.trim())),
+        None => (step.trim(), None),
+    };
+    let frequency = || {
+        let text = argument.ok_or_else(|| anyhow!("`{}` needs a frequency, e.g. {}:80", name, name))?;
+        number(text, &["khz", "hz"])
+            .map(|hz| if text.to_ascii_lowercase().ends_with("khz") { hz * 1000.0 } else { hz })
+            .filter(|&hz| hz > 0.0)
+            .ok_or_else(|| anyhow!("`{}` is not a frequency in Hz", text))
+    };
+    let decibels = |default: f32| match argument {
+        Some(text) => number(text, &["db"]).ok_or_else(|| anyhow!("`{}` is not a level in dB", text)),
+        None => Ok(default),
+    };
+    let effect: Box<dyn AudioEffect> = match name.to_ascii_lowercase().as_str() {
+        "highpass" => Box::new(Pass { high: true, cutoff: frequency()? }),
+        "lowpass" => Box::new(Pass { high: false, cutoff: frequency()? }),
+        "notch" => Box::new(Notch(frequency()?)),
+        "mask" => {
+            let text = argument.ok_or_else(|| anyhow!("`mask` needs a band, e.g. mask:2000-4000"))?;
+            let band = text
+                .split_once('-')
+                .and_then(|(low, high)| Some((number(low, &["hz"])?, number(high, &["hz"])?)))
+                .filter(|&(low, high)| low >= 0.0 && high > low)
+                .ok_or_else(|| anyhow!("`{}` is not a band of LOW-HIGH Hz", text))?;
+            Box::new(Mask(band.0, band.1))
+        }
+        "gain" => Box::new(Gain(decibels(0.0)?)),
+        "normalize" => {
+            let target = match argument {
+                None => Target::Lufs(-16.0),
+                Some(text) if text.to_ascii_lowercase().ends_with("lufs") => {
+                    Target::Lufs(number(text, &["lufs"]).ok_or_else(|| anyhow!("`{}` is not a loudness", text))?)
+                }
+                Some(text) => Target::Peak(
+                    number(text, &["dbfs", "db"]).ok_or_else(|| anyhow!("`{}` is not a level in dBFS", text))?,
+                ),
+            };
+            Box::new(Normalize(target))
+        }
+        "denoise" => Box::new(Denoise { reduction_db: decibels(12.0)?.abs() }),
+        "de-ess" | "deess" => Box::new(DeEss(decibels(6.0)?.abs())),
+        _ => bail!(
+            "Unknown effect `{}` (highpass, lowpass, notch, mask, gain, normalize, denoise, de-ess)",
+            name
+        ),
+    };
+    Ok(effect)
+}
+
+/// Effects run one after another, as given by `--dsp` or `dsp` in the config.
+#[derive(Default)]
+pub struct Chain {
+    effects: Vec<Box<dyn AudioEffect>>,
+}
+
+impl Chain {
+    /// Parses a comma-separated chain such as `highpass:80,normalize:-16lufs,denoise`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let effects = spec
+            .split(',')
+            .filter(|step| !step.trim().is_empty())
+            .map(parse_effect)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { effects })
+    }
+
+    /// The chain under `dsp` in the config: a string as for `--dsp`, or an array of steps.
+    pub fn from_config(table: &Table) -> Result<Self> {
+        match table.get("dsp") {
+            None => Ok(Self::default()),
+            Some(Value::String(spec)) => Self::parse(spec),
+            Some(Value::Array(steps)) => {
+                let steps = steps
+                    .iter()
+                    .map(|step| step.as_str().ok_or_else(|| anyhow!("dsp steps must be strings")))
+                    .collect::<Result<Vec<_>>>()?;
+                Self::parse(&steps.join(","))
+            }
+            Some(_) => bail!("dsp must be a chain string or an array of steps"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// The chain written back as a `--dsp` argument.
+    pub fn describe(&self) -> String {
+        self.effects.iter().map(|effect| effect.describe()).collect::<Vec<_>>().join(",")
+    }
+
+    pub fn apply(&self, audio: &mut AudioData) -> Result<()> {
+        for effect in &self.effects {
+            effect.apply(audio).map_err(|e| anyhow!("{}: {}", effect.describe(), e))?;
+        }
+        Ok(())
+    }
+}
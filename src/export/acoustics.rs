@@ -0,0 +1,14 @@
+use crate::stats::acoustics::BandDecay;
+
+/// Energy decay curves as CSV, one row per point: the band's center in Hz (empty for the
+/// full band), seconds after the direct sound, and the level in dB below the total energy.
+pub fn to_csv(decays: &[BandDecay]) -> String {
+    let mut csv = String::from("band_hz,time_s,level_db\n");
+    for decay in decays {
+        let band = decay.center.map_or(String::new(), |center| format!("{:.0}", center));
+        for &(time, level) in &decay.curve {
+            csv.push_str(&format!("{},{:.3},{:.2}\n", band, time, level));
+        }
+    }
+    csv
+}
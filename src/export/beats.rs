@@ -0,0 +1,15 @@
+use crate::stats::beats::Beats;
+
+/// Beats as CSV, one row per beat: its time, and the interval to the next beat and the
+/// rate that gives (empty for the last).
+pub fn to_csv(beats: &Beats) -> String {
+    let mut csv = String::from("time_s,interval_s,bpm\n");
+    let intervals = beats.intervals();
+    for (index, time) in beats.times.iter().enumerate() {
+        match intervals.get(index) {
+            Some(interval) => csv.push_str(&format!("{:.2},{:.2},{:.1}\n", time, interval, 60.0 / interval)),
+            None => csv.push_str(&format!("{:.2},,\n", time)),
+        }
+    }
+    csv
+}
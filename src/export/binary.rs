@@ -0,0 +1,165 @@
+use clap::ValueEnum;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+/// Encoding of `--json` output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// JSON text
+    #[default]
+    Json,
+    /// MessagePack
+    Msgpack,
+    /// CBOR (RFC 8949)
+    Cbor,
+}
+
+impl Format {
+    /// The value in this encoding; JSON gets a trailing newline.
+    pub fn encode(self, value: &Value) -> Vec<u8> {
+        match self {
+            Format::Json => format!("{}\n", value).into_bytes(),
+            Format::Msgpack => rmp_serde::to_vec(&Compact(value)).expect("encoding to memory can't fail"),
+            Format::Cbor => {
+                let mut out = Vec::new();
+                ciborium::into_writer(&Compact(value), &mut out).expect("encoding to memory can't fail");
+                out
+            }
+        }
+    }
+}
+
+/// Whole numbers JSON would print without a fraction, which both formats store as
+/// (shorter) integers.
+fn integer(n: f64) -> Option<i64> {
+    (n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0).then_some(n as i64)
+}
+
+/// A JSON value that serializes whole floats as integers.
+struct Compact<'a>(&'a Value);
+
+impl Serialize for Compact<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+                (Some(i), _, _) => serializer.serialize_i64(i),
+                (_, Some(u), _) => serializer.serialize_u64(u),
+                (_, _, Some(f)) => match integer(f) {
+                    Some(i) => serializer.serialize_i64(i),
+                    None => serializer.serialize_f64(f),
+                },
+                _ => serializer.serialize_unit(),
+            },
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&Compact(item))?;
+                }
+                seq.end()
+            }
+            Value::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, &Compact(value))?;
+                }
+                map.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::Value as Cbor;
+    use serde_json::json;
+
+    /// Both encodings of `value`, decoded again.
+    fn round_trip(value: &Value) -> (Cbor, Cbor) {
+        let msgpack = rmp_serde::from_slice(&Format::Msgpack.encode(value)).unwrap();
+        let cbor = ciborium::from_reader(Format::Cbor.encode(value).as_slice()).unwrap();
+        (msgpack, cbor)
+    }
+
+    #[test]
+    fn integers_round_trip_at_every_width() {
+        let boundaries: [i64; 20] = [
+            0,
+            23,
+            24,
+            127,
+            128,
+            255,
+            256,
+            65_535,
+            65_536,
+            4_294_967_295,
+            4_294_967_296,
+            9_007_199_254_740_991,
+            -1,
+            -24,
+            -25,
+            -32,
+            -33,
+            -129,
+            -32_769,
+            -9_007_199_254_740_991,
+        ];
+        for i in boundaries {
+            let (msgpack, cbor) = round_trip(&json!(i as f64));
+            assert_eq!(msgpack, Cbor::Integer(i.into()), "msgpack {}", i);
+            assert_eq!(cbor, Cbor::Integer(i.into()), "cbor {}", i);
+        }
+    }
+
+    #[test]
+    fn integers_use_the_shortest_encoding() {
+        let msgpack = |i: i64| Format::Msgpack.encode(&json!(i as f64));
+        assert_eq!(msgpack(127), [0x7f]);
+        assert_eq!(msgpack(128), [0xcc, 0x80]);
+        assert_eq!(msgpack(-32), [0xe0]);
+        assert_eq!(msgpack(-33), [0xd0, 0xdf]);
+        assert_eq!(msgpack(65_536), [0xce, 0, 1, 0, 0]);
+        let cbor = |i: i64| Format::Cbor.encode(&json!(i as f64));
+        assert_eq!(cbor(23), [0x17]);
+        assert_eq!(cbor(24), [0x18, 24]);
+        assert_eq!(cbor(-1), [0x20]);
+        assert_eq!(cbor(-25), [0x38, 24]);
+        assert_eq!(cbor(256), [0x19, 1, 0]);
+    }
+
+    #[test]
+    fn fractions_and_non_finite_numbers() {
+        let (msgpack, cbor) = round_trip(&json!(-0.5));
+        assert_eq!(msgpack, Cbor::Float(-0.5));
+        assert_eq!(cbor, Cbor::Float(-0.5));
+        // Past 2^53 a whole number is stored as a float, as JSON readers would take it
+        let (msgpack, _) = round_trip(&json!(1e20));
+        assert_eq!(msgpack, Cbor::Float(1e20));
+        for n in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(round_trip(&json!(n)), (Cbor::Null, Cbor::Null));
+        }
+    }
+
+    #[test]
+    fn nested_values_round_trip() {
+        let long = "x".repeat(300);
+        let value = json!({
+            "name": "täst",
+            "long": long,
+            "ok": true,
+            "none": null,
+            "list": (0..20).map(|i| i as f64).collect::<Vec<_>>(),
+        });
+        let expected = Cbor::Map(vec![
+            (Cbor::Text("name".into()), Cbor::Text("täst".into())),
+            (Cbor::Text("long".into()), Cbor::Text(long)),
+            (Cbor::Text("ok".into()), Cbor::Bool(true)),
+            (Cbor::Text("none".into()), Cbor::Null),
+            (Cbor::Text("list".into()), Cbor::Array((0..20).map(|i| Cbor::Integer(i.into())).collect())),
+        ]);
+        assert_eq!(round_trip(&value), (expected.clone(), expected));
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use super::srt::to_srt;
+use crate::speech::TranscriptionSegment;
+
+/// Writes `video` with `transcription` burned in as subtitles to `output`, re-encoding the
+/// picture and copying the audio. Runs `ffmpeg`, which needs libass for its `subtitles` filter.
+pub fn burn_in(video: &Path, transcription: &[TranscriptionSegment], output: &Path) -> Result<()> {
+    // ffmpeg runs in a scratch directory so the filter names the subtitles without escaping
+    let scratch = std::env::temp_dir().join(format!("fourrier-burn-in-{}", std::process::id()));
+    std::fs::create_dir_all(&scratch).with_context(|| format!("Failed to create {}", scratch.display()))?;
+    let result = run_ffmpeg(&scratch, video, transcription, output);
+    let _ = std::fs::remove_dir_all(&scratch);
+    result
+}
+
+fn run_ffmpeg(scratch: &Path, video: &Path, transcription: &[TranscriptionSegment], output: &Path) -> Result<()> {
+    std::fs::write(scratch.join("captions.srt"), to_srt(transcription))?;
+    let cwd = std::env::current_dir()?;
+    let result = Command::new("ffmpeg")
+        .current_dir(scratch)
+        .args(["-v", "error", "-nostdin", "-y", "-i"])
+        .arg(cwd.join(video))
+        .args(["-vf", "subtitles=captions.srt", "-c:a", "copy"])
+        .arg(cwd.join(output))
+        .output()
+        .context("Failed to run ffmpeg (is it installed?)")?;
+    if !result.status.success() {
+        return Err(anyhow!("ffmpeg failed: {}", String::from_utf8_lossy(&result.stderr).trim()));
+    }
+    Ok(())
+}
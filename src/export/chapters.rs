@@ -0,0 +1,130 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::audio::AudioTags;
+use crate::speech::chapters::Chapter;
+
+/// Podcasting 2.0 chapters (`podcast:chapters`) JSON.
+pub fn to_podcast_json(chapters: &[Chapter]) -> Value {
+    let chapters: Vec<Value> = chapters
+        .iter()
+        .map(|chapter| json!({ "startTime": chapter.start, "endTime": chapter.end, "title": chapter.title }))
+        .collect();
+    json!({ "version": "1.2.0", "chapters": chapters })
+}
+
+/// FFmpeg metadata with one `[CHAPTER]` per chapter, for muxing into other containers:
+/// `ffmpeg -i in.m4a -i chapters.txt -map_metadata 1 -map_chapters 1 -c copy out.m4a`.
+pub fn to_ffmetadata(chapters: &[Chapter]) -> String {
+    let escape = |s: &str| {
+        s.chars().fold(String::new(), |mut out, c| {
+            if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
+    };
+    let mut text = String::from(";FFMETADATA1\n");
+    for chapter in chapters {
+        text.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            (chapter.start * 1000.0).round() as u64,
+            (chapter.end * 1000.0).round() as u64,
+            escape(&chapter.title)
+        ));
+    }
+    text
+}
+
+fn id3_frame(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut frame = id.to_vec();
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(body);
+    frame
+}
+
+/// A text frame in UTF-16 with a byte order mark.
+fn id3_text(id: &[u8; 4], text: &str) -> Vec<u8> {
+    let mut body = vec![1, 0xFF, 0xFE];
+    body.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    body.extend_from_slice(&[0, 0]);
+    id3_frame(id, &body)
+}
+
+/// An ID3v2.3 tag carrying the chapters (`CHAP` frames under a `CTOC` table of contents)
+/// and the title, artist and album.
+pub fn to_id3(chapters: &[Chapter], tags: &AudioTags) -> Result<Vec<u8>> {
+    if chapters.len() > 255 {
+        return Err(anyhow!("ID3 tables of contents hold at most 255 chapters"));
+    }
+    let mut frames = Vec::new();
+    for (id, value) in [(b"TIT2", &tags.title), (b"TPE1", &tags.artist), (b"TALB", &tags.album)] {
+        if let Some(value) = value {
+            frames.extend(id3_text(id, value));
+        }
+    }
+    let mut toc = b"toc\0".to_vec();
+    // Top-level, ordered
+    toc.extend_from_slice(&[0x03, chapters.len() as u8]);
+    for index in 0..chapters.len() {
+        toc.extend_from_slice(format!("ch{}\0", index).as_bytes());
+    }
+    frames.extend(id3_frame(b"CTOC", &toc));
+    for (index, chapter) in chapters.iter().enumerate() {
+        let mut body = format!("ch{}\0", index).into_bytes();
+        body.extend_from_slice(&((chapter.start * 1000.0).round() as u32).to_be_bytes());
+        body.extend_from_slice(&((chapter.end * 1000.0).round() as u32).to_be_bytes());
+        // No byte offsets
+        body.extend_from_slice(&[0xFF; 8]);
+        body.extend(id3_text(b"TIT2", &chapter.title));
+        frames.extend(id3_frame(b"CHAP", &body));
+    }
+
+    let size = frames.len() as u32;
+    if size >= 1 << 28 {
+        return Err(anyhow!("ID3 tag too large"));
+    }
+    let mut tag = b"ID3\x03\x00\x00".to_vec();
+    // Sizes in the tag header are "syncsafe": 7 bits per byte
+    tag.extend((0..4).rev().map(|i| ((size >> (7 * i)) & 0x7F) as u8));
+    tag.extend(frames);
+    Ok(tag)
+}
+
+/// Length of the ID3v2 tag at the start of an MP3 file, or 0 without one.
+fn id3_length(bytes: &[u8]) -> usize {
+    if bytes.len() < 10 || &bytes[..3] != b"ID3" {
+        return 0;
+    }
+    let size = bytes[6..10].iter().fold(0usize, |size, &b| (size << 7) | (b & 0x7F) as usize);
+    let footer = if bytes[5] & 0x10 != 0 { 10 } else { 0 };
+    (10 + size + footer).min(bytes.len())
+}
+
+/// Writes the chapters as Podcasting 2.0 JSON for a `.json` path, as a copy of the MP3
+/// `input` with an ID3 chapter tag (replacing any existing ID3v2 tag) for `.mp3`, and as
+/// FFmpeg metadata otherwise.
+pub fn save_chapters(path: &Path, chapters: &[Chapter], input: &Path, tags: &AudioTags) -> Result<()> {
+    let extension = path.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+    let contents = match extension.as_deref() {
+        Some("json") => format!("{}\n", to_podcast_json(chapters)).into_bytes(),
+        Some("mp3") => {
+            let is_mp3 = input.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"));
+            if !is_mp3 || !input.is_file() {
+                return Err(anyhow!("ID3 chapters can only be written to a copy of a local MP3 input"));
+            }
+            if path.canonicalize().ok() == input.canonicalize().ok() {
+                return Err(anyhow!("Write the chapters to a new file, not over the input"));
+            }
+            let audio = std::fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+            let mut contents = to_id3(chapters, tags)?;
+            contents.extend_from_slice(&audio[id3_length(&audio)..]);
+            contents
+        }
+        _ => to_ffmetadata(chapters).into_bytes(),
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
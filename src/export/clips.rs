@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::wav::write_wav;
+use crate::clock::utc_timestamp;
+use crate::stats::trigger::TriggeredEvent;
+
+/// Log of the clips in a directory, appended to by every run that saves into it.
+pub const LOG_NAME: &str = "events.csv";
+
+/// A directory of event clips, one WAV per event, and a CSV log of them.
+pub struct ClipDirectory {
+    dir: PathBuf,
+    log: File,
+    sample_rate: u32,
+    /// When the recording's first sample was captured
+    started: SystemTime,
+    saved: usize,
+}
+
+impl ClipDirectory {
+    /// Creates `dir` if needed and opens its log, for clips of a recording at `sample_rate`
+    /// that started at `started`.
+    pub fn create(dir: &Path, sample_rate: u32, started: SystemTime) -> Result<Self> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(LOG_NAME);
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        if log.metadata().map_or(true, |metadata| metadata.len() == 0) {
+            writeln!(log, "file,start_utc,offset_s,duration_s,peak_dbfs")
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(Self { dir: dir.to_path_buf(), log, sample_rate, started, saved: 0 })
+    }
+
+    /// Clips saved so far.
+    pub fn saved(&self) -> usize {
+        self.saved
+    }
+
+    /// Writes `channels`, the audio of `event`, as the next clip, named after its number and
+    /// start time (e.g. `event-0001-20240501T052210Z.wav`), and logs it.
+    pub fn save(&mut self, event: &TriggeredEvent, channels: &[Vec<f32>]) -> Result<PathBuf> {
+        let offset = event.start as f64 / self.sample_rate as f64;
+        let duration = channels.first().map_or(0, Vec::len) as f64 / self.sample_rate as f64;
+        let start = utc_timestamp(self.started + Duration::from_secs_f64(offset));
+        let compact: String = start.chars().filter(|c| !matches!(c, '-' | ':')).collect();
+        let name = format!("event-{:04}-{}.wav", self.saved + 1, compact);
+        let path = self.dir.join(&name);
+        let slices: Vec<&[f32]> = channels.iter().map(Vec::as_slice).collect();
+        write_wav(&path, &slices, self.sample_rate)?;
+        writeln!(self.log, "{},{},{:.2},{:.2},{:.1}", name, start, offset, duration, event.peak)
+            .and_then(|_| self.log.flush())
+            .with_context(|| format!("Failed to write {}", self.dir.join(LOG_NAME).display()))?;
+        self.saved += 1;
+        Ok(path)
+    }
+}
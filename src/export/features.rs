@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use arrow_array::{ArrayRef, Float32Array, Float64Array, RecordBatch};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::audio::{AudioData, SpectrogramData};
+use crate::stats::pitch::pitch_track;
+
+/// Metadata key of the bins' centre frequencies.
+const BIN_FREQUENCIES: &str = "bin_frequencies";
+
+/// Share of a frame's power below the rolloff frequency.
+const ROLLOFF: f64 = 0.85;
+
+/// Power of each bin of a frame of dB levels.
+fn powers(frame: &[f32]) -> Vec<f64> {
+    frame.iter().map(|&db| if db.is_finite() { 10f64.powf(db as f64 / 10.0) } else { 0.0 }).collect()
+}
+
+/// Centroid and rolloff in Hz, and flatness (geometric over arithmetic mean power, 0 for a
+/// pure tone to 1 for white noise), of one frame.
+fn spectral_shape(frame: &[f32], frequencies: &[f32]) -> (f32, f32, f32) {
+    let power = powers(frame);
+    let total: f64 = power.iter().sum();
+    if total <= 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let centroid = power.iter().zip(frequencies).map(|(&p, &f)| p * f as f64).sum::<f64>() / total;
+    let mut cumulative = 0.0;
+    let rolloff_bin = power
+        .iter()
+        .position(|&p| {
+            cumulative += p;
+            cumulative >= ROLLOFF * total
+        })
+        .unwrap_or(power.len() - 1);
+    let mean_log = power.iter().map(|&p| (p + f64::MIN_POSITIVE).ln()).sum::<f64>() / power.len() as f64;
+    let flatness = mean_log.exp() / (total / power.len() as f64);
+    (centroid as f32, frequencies[rolloff_bin], flatness.min(1.0) as f32)
+}
+
+/// One row per spectrogram frame: `time` (frame start in seconds), `centroid_hz`,
+/// `rolloff_hz`, `flatness`, `f0_hz` (null when unvoiced) and the level of every bin in
+/// the spectrogram's units as `bin_<index>`. Frequencies would need dots in the names,
+/// which readers treat as nested fields; the schema's `bin_frequencies` metadata gives
+/// them instead, as a JSON array in Hz.
+pub fn frame_features(audio: &AudioData, spectrogram: &SpectrogramData, window_size: usize) -> Result<RecordBatch> {
+    let shapes: Vec<(f32, f32, f32)> = spectrogram
+        .magnitudes
+        .iter()
+        .map(|frame| spectral_shape(frame, &spectrogram.frequencies))
+        .collect();
+    let centre = window_size as f64 / 2.0 / audio.sample_rate as f64;
+    let times = spectrogram.time_points.iter().map(|&t| t as f64 + centre);
+    let f0 = pitch_track(&audio.samples, audio.sample_rate, times);
+
+    let float = |values: Vec<f32>| Arc::new(Float32Array::from(values)) as ArrayRef;
+    let mut fields = vec![
+        Field::new("time", DataType::Float64, false),
+        Field::new("centroid_hz", DataType::Float32, false),
+        Field::new("rolloff_hz", DataType::Float32, false),
+        Field::new("flatness", DataType::Float32, false),
+        Field::new("f0_hz", DataType::Float32, true),
+    ];
+    let mut columns = vec![
+        Arc::new(Float64Array::from_iter_values(spectrogram.time_points.iter().map(|&t| t as f64))) as ArrayRef,
+        float(shapes.iter().map(|s| s.0).collect()),
+        float(shapes.iter().map(|s| s.1).collect()),
+        float(shapes.iter().map(|s| s.2).collect()),
+        Arc::new(Float32Array::from(f0)),
+    ];
+    for bin in 0..spectrogram.frequencies.len() {
+        fields.push(Field::new(format!("bin_{}", bin), DataType::Float32, false));
+        columns.push(float(spectrogram.magnitudes.iter().map(|frame| frame[bin]).collect()));
+    }
+    let metadata = HashMap::from([(BIN_FREQUENCIES.to_string(), bin_frequencies(spectrogram))]);
+    let schema = Schema::new(fields).with_metadata(metadata);
+    RecordBatch::try_new(Arc::new(schema), columns).context("Failed to build the features table")
+}
+
+/// The centre frequency of each `bin_<index>` column in Hz, as a JSON array.
+fn bin_frequencies(spectrogram: &SpectrogramData) -> String {
+    let frequencies: Vec<String> = spectrogram.frequencies.iter().map(|f| f.to_string()).collect();
+    format!("[{}]", frequencies.join(","))
+}
+
+/// Writes `frame_features` as an Arrow IPC file for `.arrow`, `.feather` or `.ipc`, and as
+/// Parquet (Snappy-compressed) otherwise. Parquet files also carry `bin_frequencies` in
+/// their own key-value metadata, for readers that don't read the Arrow schema.
+pub fn save_features(path: &Path, audio: &AudioData, spectrogram: &SpectrogramData, window_size: usize) -> Result<()> {
+    let batch = frame_features(audio, spectrogram, window_size)?;
+    let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    let written = if matches!(extension.as_deref(), Some("arrow" | "feather" | "ipc")) {
+        write_ipc(file, &batch)
+    } else {
+        write_parquet(file, &batch, bin_frequencies(spectrogram))
+    };
+    written.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn write_ipc(file: File, batch: &RecordBatch) -> Result<()> {
+    let mut writer = FileWriter::try_new(BufWriter::new(file), &batch.schema())?;
+    writer.write(batch)?;
+    writer.finish()?;
+    Ok(())
+}
+
+fn write_parquet(file: File, batch: &RecordBatch, bin_frequencies: String) -> Result<()> {
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(vec![KeyValue::new(BIN_FREQUENCIES.to_string(), bin_frequencies)]))
+        .build();
+    let mut writer = ArrowWriter::try_new(BufWriter::new(file), batch.schema(), Some(properties))?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::generator::{generate, Signal, SignalParams};
+    use crate::audio::{compute_spectrogram, AudioTags};
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn tone() -> (AudioData, SpectrogramData) {
+        let params = SignalParams { duration: 0.5, sample_rate: 8000, frequency: 440.0, ..Default::default() };
+        let audio = AudioData {
+            samples: generate(Signal::Tone, &params).unwrap().into(),
+            extra_channels: Vec::new(),
+            sample_rate: 8000,
+            channels: 1,
+            bits_per_sample: None,
+            tags: AudioTags::default(),
+        };
+        let spectrogram = compute_spectrogram(&audio, 512).unwrap();
+        (audio, spectrogram)
+    }
+
+    fn temp_path(extension: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("fourrier-features-{}.{}", std::process::id(), extension))
+    }
+
+    /// The columns any reader of the table sees.
+    fn check(batch: &RecordBatch, spectrogram: &SpectrogramData) {
+        let schema = batch.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+        assert_eq!(names[..6], ["time", "centroid_hz", "rolloff_hz", "flatness", "f0_hz", "bin_0"]);
+        assert_eq!(names.len(), 5 + spectrogram.frequencies.len());
+        assert_eq!(batch.num_rows(), spectrogram.time_points.len());
+
+        let time = batch.column(0).as_any().downcast_ref::<Float64Array>().unwrap();
+        let expected: Vec<f64> = spectrogram.time_points.iter().map(|&t| t as f64).collect();
+        assert_eq!(time.values().to_vec(), expected);
+        // The tone is voiced away from the fades
+        let f0 = batch.column(4).as_any().downcast_ref::<Float32Array>().unwrap();
+        let middle = f0.value(f0.len() / 2);
+        assert!(f0.is_valid(f0.len() / 2) && (middle - 440.0).abs() < 5.0, "{}", middle);
+        let bin = batch.column(5 + 28).as_any().downcast_ref::<Float32Array>().unwrap();
+        let levels: Vec<f32> = spectrogram.magnitudes.iter().map(|frame| frame[28]).collect();
+        assert_eq!(bin.values().to_vec(), levels);
+    }
+
+    #[test]
+    fn parquet_readers_open_the_features() {
+        let (audio, spectrogram) = tone();
+        let path = temp_path("parquet");
+        save_features(&path, &audio, &spectrogram, 512).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let key_value = builder.metadata().file_metadata().key_value_metadata().unwrap();
+        let frequencies = key_value.iter().find(|entry| entry.key == BIN_FREQUENCIES).unwrap();
+        assert_eq!(frequencies.value.as_deref(), Some(bin_frequencies(&spectrogram).as_str()));
+        assert_eq!(builder.schema().metadata()[BIN_FREQUENCIES], bin_frequencies(&spectrogram));
+        let batches: Vec<RecordBatch> = builder.build().unwrap().map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 1);
+        check(&batches[0], &spectrogram);
+    }
+
+    #[test]
+    fn arrow_readers_open_the_features() {
+        let (audio, spectrogram) = tone();
+        let path = temp_path("arrow");
+        save_features(&path, &audio, &spectrogram, 512).unwrap();
+        let reader = arrow_ipc::reader::FileReader::try_new(File::open(&path).unwrap(), None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reader.schema().metadata()[BIN_FREQUENCIES], bin_frequencies(&spectrogram));
+        let batches: Vec<RecordBatch> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 1);
+        check(&batches[0], &spectrogram);
+    }
+}
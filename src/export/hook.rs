@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Longest a callback URL may take to answer, in seconds.
+const CALLBACK_TIMEOUT: u32 = 30;
+
+/// Times a failed callback is retried, a second apart and then doubling.
+const CALLBACK_RETRIES: u32 = 3;
+
+/// Where a finished job's JSON result is delivered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hook {
+    /// POSTed as the request body to an http(s) URL
+    Url(String),
+    /// Written to the standard input of a shell command
+    Command(String),
+}
+
+impl Hook {
+    /// An http:// or https:// URL, or otherwise a shell command. URLs may not contain
+    /// whitespace or control characters, which curl would otherwise read as more options.
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if !(value.starts_with("http://") || value.starts_with("https://")) {
+            return Ok(Hook::Command(value.to_string()));
+        }
+        if value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(anyhow!("A callback URL can't contain spaces or control characters"));
+        }
+        Ok(Hook::Url(value.to_string()))
+    }
+
+    /// The host a URL hook posts to, in lowercase and without any port or credentials.
+    pub fn host(&self) -> Option<String> {
+        let Hook::Url(url) = self else {
+            return None;
+        };
+        let rest = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let host = match host.strip_prefix('[') {
+            // An IPv6 address, whose colons aren't a port
+            Some(address) => address.split(']').next().unwrap_or_default(),
+            None => host.split(':').next().unwrap_or_default(),
+        };
+        Some(host.to_ascii_lowercase())
+    }
+
+    /// Delivers `payload`, failing if the URL doesn't answer with a success status after
+    /// the retries, or the command exits with an error.
+    pub fn deliver(&self, payload: &Value) -> Result<()> {
+        match self {
+            Hook::Url(url) => post(url, &payload.to_string()),
+            Hook::Command(command) => run(command, &payload.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hook::Url(url) => write!(f, "{}", url),
+            Hook::Command(command) => write!(f, "`{}`", command),
+        }
+    }
+}
+
+/// POSTs `body` as JSON with curl, the body on stdin so it can be any size and the URL as
+/// its own argument so nothing in it is read as an option.
+fn post(url: &str, body: &str) -> Result<()> {
+    let (timeout, retries) = (CALLBACK_TIMEOUT.to_string(), CALLBACK_RETRIES.to_string());
+    let mut child = Command::new("curl")
+        .args(["-sS", "--fail-with-body", "--max-time", &timeout, "--retry", &retries])
+        .args(["--proto", "=http,https", "-H", "Content-Type: application/json", "--data-binary", "@-"])
+        .args(["--url", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("curl has no stdin"))?
+        .write_all(body.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Callback to {} failed: {}", url, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// Runs `command` through the shell with `body` on its standard input.
+fn run(command: &str, body: &str) -> Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+    let mut child = shell
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run `{}`", command))?;
+    // A command that doesn't read its input closes the pipe early, which isn't an error
+    let _ = child.stdin.take().ok_or_else(|| anyhow!("`{}` has no stdin", command))?.write_all(body.as_bytes());
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("`{}` failed with {}", command, status));
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+use serde_json::{Number, Value};
+
+/// `value` as a JSON number at its shortest `f32` spelling, so 0.1 is written as 0.1 rather
+/// than widened to 0.10000000149011612; null when not finite, as serde_json does for `f64`.
+pub fn float(value: f32) -> Value {
+    value.to_string().parse().ok().and_then(Number::from_f64).map_or(Value::Null, Value::Number)
+}
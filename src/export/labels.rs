@@ -0,0 +1,24 @@
+use crate::decode::Detection;
+use crate::session::Marker;
+
+/// Audacity label track: `start<TAB>end<TAB>label` per line, point labels have start == end.
+pub fn to_labels(markers: &[Marker]) -> String {
+    markers
+        .iter()
+        .map(|marker| {
+            let name = marker.name.replace(['\t', '\n'], " ");
+            format!("{:.6}\t{:.6}\t{}\n", marker.time, marker.time, name)
+        })
+        .collect()
+}
+
+/// Audacity label track of decoded signals, one region label per detection.
+pub fn detection_labels(detections: &[Detection]) -> String {
+    detections
+        .iter()
+        .map(|detection| {
+            let text = detection.text.replace(['\t', '\n'], " ");
+            format!("{:.6}\t{:.6}\t{}\n", detection.start, detection.end, text)
+        })
+        .collect()
+}
@@ -0,0 +1,11 @@
+use crate::stats::level::LevelTrack;
+
+/// Leq of each `interval` seconds of `track` as CSV: the start of each interval and its
+/// level, the column named after the weighting (e.g. `laeq_db`).
+pub fn to_csv(track: &LevelTrack, interval: f64) -> String {
+    let mut csv = format!("start_s,{}_db\n", track.quantity("eq").to_lowercase());
+    for (start, level) in track.leq_series(interval) {
+        csv.push_str(&format!("{:.1},{:.1}\n", start, level));
+    }
+    csv
+}
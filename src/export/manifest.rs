@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+use super::segments::SegmentFile;
+
+/// Dataset manifest written next to split segments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ManifestFormat {
+    /// LJSpeech: audio in `wavs/`, `metadata.csv` with `id|text|normalized text`
+    Ljspeech,
+    /// `metadata.jsonl` with one `{"file_name", "text", "duration"}` object per line, as read
+    /// by HuggingFace `audiofolder` datasets
+    Jsonl,
+    /// `metadata.csv` with `file_name,text,duration` columns
+    Csv,
+}
+
+impl ManifestFormat {
+    /// Directory under the dataset root that the audio files go into.
+    pub fn audio_dir(self, root: &Path) -> PathBuf {
+        match self {
+            ManifestFormat::Ljspeech => root.join("wavs"),
+            ManifestFormat::Jsonl | ManifestFormat::Csv => root.to_path_buf(),
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            ManifestFormat::Ljspeech | ManifestFormat::Csv => "metadata.csv",
+            ManifestFormat::Jsonl => "metadata.jsonl",
+        }
+    }
+}
+
+/// Writes the manifest for `files` into `root` and returns its path. File paths are relative
+/// to `root`.
+pub fn write_manifest(root: &Path, files: &[SegmentFile], format: ManifestFormat) -> Result<PathBuf> {
+    let relative = |file: &SegmentFile| {
+        file.path
+            .strip_prefix(root)
+            .unwrap_or(&file.path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+    let mut contents = String::new();
+    match format {
+        ManifestFormat::Ljspeech => {
+            for file in files {
+                let id = file.path.file_stem().map_or(String::new(), |s| s.to_string_lossy().into_owned());
+                let text = file.text.replace(['|', '\n', '\r'], " ");
+                contents.push_str(&format!("{}|{}|{}\n", id, text, text));
+            }
+        }
+        ManifestFormat::Jsonl => {
+            for file in files {
+                let line = json!({ "file_name": relative(file), "text": file.text, "duration": file.duration() });
+                contents.push_str(&format!("{}\n", line));
+            }
+        }
+        ManifestFormat::Csv => {
+            contents.push_str("file_name,text,duration\n");
+            for file in files {
+                contents.push_str(&format!(
+                    "{},{},{:.3}\n",
+                    csv_field(&relative(file)),
+                    csv_field(&file.text),
+                    file.duration()
+                ));
+            }
+        }
+    }
+    let path = root.join(format.file_name());
+    std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
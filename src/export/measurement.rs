@@ -0,0 +1,9 @@
+/// Frequency response as CSV: third-octave center in Hz, then the level in dB relative to
+/// 1 kHz.
+pub fn response_csv(response: &[(f32, f32)]) -> String {
+    let mut csv = String::from("frequency_hz,level_db\n");
+    for (frequency, level) in response {
+        csv.push_str(&format!("{:.1},{:.2}\n", frequency, level));
+    }
+    csv
+}
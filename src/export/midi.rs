@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use crate::stats::live::{LiveFrame, LiveOutput};
+
+/// Controller of the first octave band; the others follow.
+pub const FIRST_BAND_CC: u8 = 20;
+
+/// Controller carrying f0 as a MIDI note number.
+pub const F0_CC: u8 = 16;
+
+/// Note triggered on each onset (a kick drum in General MIDI).
+pub const ONSET_NOTE: u8 = 36;
+
+/// Band levels from this many dB below full scale up to 0 dBFS span the controller range.
+const RANGE_DB: f32 = 60.0;
+
+/// Writes live features as MIDI messages on `channel` to a raw MIDI device, e.g. ALSA's
+/// `/dev/snd/midiC1D0` or a `snd-virmidi` port that other programs subscribe to: each
+/// octave band's level as a control change from `FIRST_BAND_CC` on, f0 as the nearest
+/// note number on `F0_CC` while voiced, and a note on/off of `ONSET_NOTE` at each onset,
+/// with its velocity from the level.
+pub struct MidiOut {
+    device: File,
+    /// Zero-based channel
+    channel: u8,
+    /// Last value sent per controller, so unchanged values aren't repeated
+    sent: [Option<u8>; 128],
+}
+
+impl MidiOut {
+    pub fn open(path: &Path, channel: u8) -> Result<Self> {
+        let device = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open MIDI device {}", path.display()))?;
+        Ok(Self { device, channel: channel.min(15), sent: [None; 128] })
+    }
+
+    fn control_change(&mut self, controller: u8, value: u8, out: &mut Vec<u8>) {
+        if self.sent[controller as usize] != Some(value) {
+            self.sent[controller as usize] = Some(value);
+            out.extend_from_slice(&[0xB0 | self.channel, controller, value]);
+        }
+    }
+}
+
+/// A level in dBFS as a 0-127 controller value.
+fn level_value(db: f32) -> u8 {
+    (((db + RANGE_DB) / RANGE_DB).clamp(0.0, 1.0) * 127.0).round() as u8
+}
+
+impl LiveOutput for MidiOut {
+    fn send(&mut self, frame: &LiveFrame) -> Result<()> {
+        let mut out = Vec::new();
+        for (i, &band) in frame.bands.iter().enumerate() {
+            self.control_change(FIRST_BAND_CC + i as u8, level_value(band), &mut out);
+        }
+        if let Some(f0) = frame.f0 {
+            let note = (69.0 + 12.0 * (f0 / 440.0).log2()).round().clamp(0.0, 127.0) as u8;
+            self.control_change(F0_CC, note, &mut out);
+        }
+        if frame.onset.is_some() {
+            let velocity = level_value(frame.level).max(1);
+            out.extend_from_slice(&[0x90 | self.channel, ONSET_NOTE, velocity]);
+            out.extend_from_slice(&[0x80 | self.channel, ONSET_NOTE, 0]);
+        }
+        if !out.is_empty() {
+            self.device.write_all(&out).context("Failed to write to the MIDI device")?;
+        }
+        Ok(())
+    }
+}
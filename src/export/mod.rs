@@ -0,0 +1,307 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use crate::audio::AudioData;
+use crate::decode::Detection;
+use crate::session::Marker;
+use crate::speech::align::Alignment;
+use crate::speech::TranscriptionSegment;
+use crate::stats::beats::Beats;
+use crate::stats::events::AcousticEvent;
+use crate::stats::level::LevelTrack;
+use crate::stats::notes::Note;
+use crate::stats::quality::QualityReport;
+use crate::stats::sibilance::Sibilance;
+use crate::stats::stereo::StereoStats;
+use crate::stats::timing::Timings;
+use crate::stats::AudioStats;
+
+pub mod acoustics;
+pub mod beats;
+pub mod binary;
+pub mod burn_in;
+pub mod chapters;
+pub mod clips;
+#[cfg(feature = "parquet")]
+pub mod features;
+pub mod hook;
+pub mod json;
+pub mod labels;
+pub mod leq;
+pub mod manifest;
+pub mod measurement;
+pub mod midi;
+pub mod musicxml;
+pub mod osc;
+pub mod png;
+pub mod psd;
+pub mod raven;
+pub mod rpm;
+pub mod segments;
+pub mod srt;
+pub mod textgrid;
+pub mod wav;
+
+use json::float;
+
+pub fn analysis_json(
+    input: &Path,
+    audio_data: &AudioData,
+    stats: &AudioStats,
+    transcription: &[TranscriptionSegment],
+    summary: Option<&str>,
+) -> Value {
+    let tags = &audio_data.tags;
+    let custom: Map<String, Value> = stats.custom.iter().map(|(name, value)| (name.clone(), json!(value))).collect();
+    json!({
+        "input": input.display().to_string(),
+        "metadata": {
+            "title": tags.title,
+            "artist": tags.artist,
+            "album": tags.album,
+            "duration": stats.duration,
+        },
+        "stats": {
+            "sample_rate": stats.sample_rate,
+            "bits_per_sample": stats.bits_per_sample,
+            "channels": stats.channels,
+            "peak_dbfs": float(stats.peak_dbfs),
+            "rms_dbfs": float(stats.rms_dbfs),
+            "lufs": stats.lufs.map(float),
+            "dominant_frequency": stats.dominant_frequency.map(float),
+            "bpm": stats.bpm.map(float),
+            "key": stats.key,
+            "segment_count": stats.segment_count,
+        },
+        "quality": quality_json(&stats.quality),
+        "sibilance": sibilance_json(&stats.sibilance),
+        "stereo": stats.stereo.as_ref().map(stereo_json),
+        "custom": custom,
+        "timings": timings_json(&stats.timings, stats.duration),
+        "transcription": transcription_json(transcription),
+        "summary": summary,
+    })
+}
+
+pub fn stereo_json(stereo: &StereoStats) -> Value {
+    let out_of_phase: Vec<Value> =
+        stereo.out_of_phase.iter().map(|&(start, end)| json!({ "start": start, "end": end })).collect();
+    json!({
+        "correlation": float(stereo.correlation),
+        "balance_db": float(stereo.balance_db),
+        "out_of_phase": out_of_phase,
+    })
+}
+
+pub fn timings_json(timings: &Timings, duration: f64) -> Value {
+    let stages: Map<String, Value> =
+        timings.stages.iter().map(|(stage, time)| (stage.to_string(), json!(time.as_secs_f64()))).collect();
+    json!({
+        "total_seconds": timings.total().as_secs_f64(),
+        "stages": stages,
+        "transcription_rtf": timings.realtime_factor(duration),
+    })
+}
+
+pub fn sibilance_json(bursts: &[Sibilance]) -> Value {
+    Value::Array(
+        bursts
+            .iter()
+            .map(|burst| json!({ "start": burst.start, "end": burst.end, "peak_dbfs": float(burst.peak_dbfs) }))
+            .collect(),
+    )
+}
+
+pub fn quality_json(quality: &QualityReport) -> Value {
+    let hum = quality.hum.as_ref().map(|hum| {
+        let lines: Vec<Value> = hum
+            .lines
+            .iter()
+            .map(|line| {
+                json!({
+                    "frequency": float(line.frequency),
+                    "level_dbfs": float(line.level_dbfs),
+                    "prominence_db": float(line.prominence),
+                })
+            })
+            .collect();
+        json!({ "mains": float(hum.mains), "lines": lines })
+    });
+    json!({
+        "snr_db": float(quality.snr_db),
+        "noise_floor_dbfs": float(quality.noise_floor_dbfs),
+        "signal_dbfs": float(quality.signal_dbfs),
+        "bandwidth_hz": float(quality.bandwidth_hz),
+        "hum": hum,
+    })
+}
+
+pub fn transcription_json(transcription: &[TranscriptionSegment]) -> Value {
+    Value::Array(
+        transcription.iter().map(|seg| json!({ "start": seg.start, "end": seg.end, "text": seg.text.trim() })).collect(),
+    )
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Writes the transcript as JSON for a `.json` path and as SRT otherwise.
+pub fn save_transcript(path: &Path, transcription: &[TranscriptionSegment]) -> Result<()> {
+    let contents = if is_json(path) {
+        format!("{}\n", transcription_json(transcription))
+    } else {
+        srt::to_srt(transcription)
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn markers_json(markers: &[Marker]) -> Value {
+    Value::Array(markers.iter().map(|marker| json!({ "time": marker.time, "name": marker.name })).collect())
+}
+/// Writes markers as JSON for a `.json` path and as an Audacity label track otherwise.
+pub fn save_markers(path: &Path, markers: &[Marker]) -> Result<()> {
+    let contents = if is_json(path) {
+        format!("{}\n", markers_json(markers))
+    } else {
+        labels::to_labels(markers)
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn detections_json(detections: &[Detection]) -> Value {
+    Value::Array(
+        detections
+            .iter()
+            .map(|detection| json!({ "start": detection.start, "end": detection.end, "text": detection.text }))
+            .collect(),
+    )
+}
+
+/// Writes decoded signals as JSON for a `.json` path and as an Audacity label track
+/// otherwise.
+pub fn save_detections(path: &Path, detections: &[Detection]) -> Result<()> {
+    let contents = if is_json(path) {
+        format!("{}\n", detections_json(detections))
+    } else {
+        labels::detection_labels(detections)
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn events_json(events: &[AcousticEvent]) -> Value {
+    Value::Array(
+        events
+            .iter()
+            .map(|event| {
+                json!({
+                    "start": event.start,
+                    "end": event.end,
+                    "low_frequency": float(event.low_frequency),
+                    "high_frequency": float(event.high_frequency),
+                    "peak_frequency": float(event.peak_frequency),
+                    "peak_level_db": float(event.peak_level_db),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Writes acoustic events as JSON for a `.json` path and as a Raven selection table
+/// otherwise.
+pub fn save_events(path: &Path, events: &[AcousticEvent]) -> Result<()> {
+    let contents = if is_json(path) {
+        format!("{}\n", events_json(events))
+    } else {
+        raven::selection_table(events)
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn beats_json(beats: &Beats) -> Value {
+    json!({ "bpm": float(beats.bpm), "beats": beats.times })
+}
+
+/// Writes beats as JSON for a `.json` path and as CSV otherwise.
+pub fn save_beats(path: &Path, beats: &Beats) -> Result<()> {
+    let contents = if is_json(path) { format!("{}\n", beats_json(beats)) } else { beats::to_csv(beats) };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn leq_json(track: &LevelTrack, interval: f64) -> Value {
+    let series = track.leq_series(interval);
+    let calibrated = track.calibration != 0.0;
+    json!({
+        "weighting": track.weighting.letter(),
+        "unit": track.unit(),
+        "interval_s": interval,
+        "leq": track.leq.map(float),
+        // Exposure and dose only mean something in dB SPL
+        "daily_exposure": track.daily_exposure().filter(|_| calibrated).map(float),
+        "dose_percent": track.dose().filter(|_| calibrated).map(float),
+        "start": series.iter().map(|&(start, _)| start).collect::<Vec<_>>(),
+        "levels": series.iter().map(|&(_, level)| float(level)).collect::<Vec<_>>(),
+    })
+}
+
+/// Writes the Leq of each `interval` seconds as JSON for a `.json` path and as CSV
+/// otherwise.
+pub fn save_leq(path: &Path, track: &LevelTrack, interval: f64) -> Result<()> {
+    let contents =
+        if is_json(path) { format!("{}\n", leq_json(track, interval)) } else { leq::to_csv(track, interval) };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Writes an alignment as a Praat TextGrid (segment and word tiers) for a `.TextGrid` path,
+/// as JSON for `.json` and as SRT of the segments otherwise.
+pub fn save_alignment(path: &Path, alignment: &Alignment, duration: f64) -> Result<()> {
+    let textgrid = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("textgrid"));
+    let contents = if textgrid {
+        let tiers = [("segments", alignment.segments.as_slice()), ("words", alignment.words.as_slice())];
+        textgrid::to_textgrid(&tiers, duration)
+    } else if is_json(path) {
+        let json = json!({
+            "segments": transcription_json(&alignment.segments),
+            "words": transcription_json(&alignment.words),
+        });
+        format!("{}\n", json)
+    } else {
+        srt::to_srt(&alignment.segments)
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn is_musicxml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("musicxml") || ext.eq_ignore_ascii_case("xml"))
+}
+
+/// Writes a melody as MusicXML at `bpm` for a `.musicxml` or `.xml` path, as JSON for
+/// `.json`, and otherwise as a tab-separated note list (start, duration, note, cents).
+pub fn save_notes(path: &Path, notes: &[Note], bpm: f32, title: &str) -> Result<()> {
+    let contents = if is_musicxml(path) {
+        musicxml::to_musicxml(notes, bpm, title)
+    } else if is_json(path) {
+        let json: Vec<Value> = notes
+            .iter()
+            .map(|note| {
+                json!({
+                    "start": note.start,
+                    "end": note.end,
+                    "note": note.name(),
+                    "midi": note.midi,
+                    "cents": float(note.cents),
+                })
+            })
+            .collect();
+        let json = Value::Array(json);
+        format!("{}\n", json)
+    } else {
+        let mut list = String::from("start\tduration\tnote\tcents\n");
+        for note in notes {
+            list.push_str(&format!("{:.3}\t{:.3}\t{}\t{:+.0}\n", note.start, note.duration(), note.name(), note.cents));
+        }
+        list
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
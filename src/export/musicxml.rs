@@ -0,0 +1,175 @@
+use std::fmt::Write;
+
+use crate::stats::notes::Note;
+
+/// Divisions of a quarter note: notes are quantized to sixteenths.
+const DIVISIONS: usize = 4;
+
+/// Length of a 4/4 measure in divisions.
+const MEASURE: usize = 4 * DIVISIONS;
+
+/// Durations with a single notehead (type and dots), longest first; others are written
+/// as tied pieces.
+const NOTE_VALUES: [(usize, &str, bool); 8] = [
+    (16, "whole", false),
+    (12, "half", true),
+    (8, "half", false),
+    (6, "quarter", true),
+    (4, "quarter", false),
+    (3, "eighth", true),
+    (2, "eighth", false),
+    (1, "16th", false),
+];
+
+const STEPS: [(&str, bool); 12] = [
+    ("C", false),
+    ("C", true),
+    ("D", false),
+    ("D", true),
+    ("E", false),
+    ("F", false),
+    ("F", true),
+    ("G", false),
+    ("G", true),
+    ("A", false),
+    ("A", true),
+    ("B", false),
+];
+
+/// The longest single note value that fits in `room` divisions (at least one).
+fn note_value(room: usize) -> (usize, &'static str, bool) {
+    NOTE_VALUES.iter().copied().find(|&(value, ..)| value <= room).unwrap_or(NOTE_VALUES[7])
+}
+
+/// A MIDI note (or `None` for a rest) lasting some divisions.
+type Event = (Option<i32>, usize);
+
+/// Notes and the rests between them, quantized at `bpm` from the first note on.
+fn events(notes: &[Note], bpm: f32) -> Vec<Event> {
+    let divisions_per_second = bpm as f64 / 60.0 * DIVISIONS as f64;
+    let Some(first) = notes.first() else {
+        return Vec::new();
+    };
+    let quantize = |time: f64| ((time - first.start) * divisions_per_second).round() as usize;
+    let mut events = Vec::new();
+    let mut position = 0;
+    for note in notes {
+        let (start, end) = (quantize(note.start).max(position), quantize(note.end));
+        if start > position {
+            events.push((None, start - position));
+        }
+        // Every note keeps at least a sixteenth, even if that shifts what follows
+        let length = end.saturating_sub(start).max(1);
+        events.push((Some(note.midi), length));
+        position = start + length;
+    }
+    events
+}
+
+fn write_note(xml: &mut String, midi: Option<i32>, value: (usize, &str, bool), tie_start: bool, tie_stop: bool) {
+    let (duration, kind, dotted) = value;
+    xml.push_str("      <note>\n");
+    match midi {
+        Some(midi) => {
+            let (step, sharp) = STEPS[midi.rem_euclid(12) as usize];
+            let _ = write!(xml, "        <pitch><step>{}</step>", step);
+            if sharp {
+                xml.push_str("<alter>1</alter>");
+            }
+            let _ = writeln!(xml, "<octave>{}</octave></pitch>", midi.div_euclid(12) - 1);
+        }
+        None => xml.push_str("        <rest/>\n"),
+    }
+    let _ = writeln!(xml, "        <duration>{}</duration>", duration);
+    if tie_stop {
+        xml.push_str("        <tie type=\"stop\"/>\n");
+    }
+    if tie_start {
+        xml.push_str("        <tie type=\"start\"/>\n");
+    }
+    let _ = writeln!(xml, "        <type>{}</type>", kind);
+    if dotted {
+        xml.push_str("        <dot/>\n");
+    }
+    if tie_start || tie_stop {
+        xml.push_str("        <notations>");
+        if tie_stop {
+            xml.push_str("<tied type=\"stop\"/>");
+        }
+        if tie_start {
+            xml.push_str("<tied type=\"start\"/>");
+        }
+        xml.push_str("</notations>\n");
+    }
+    xml.push_str("      </note>\n");
+}
+
+/// The melody as a one-part MusicXML 4.0 score in 4/4 at `bpm`, quantized to sixteenths;
+/// notes crossing a barline or of no single note value are tied.
+pub fn to_musicxml(notes: &[Note], bpm: f32, title: &str) -> String {
+    let bpm = bpm.round().max(1.0);
+    // Bass clef for low melodies, below G3 on average
+    let low = !notes.is_empty() && notes.iter().map(|n| n.midi as f32).sum::<f32>() / (notes.len() as f32) < 55.0;
+    let (sign, line) = if low { ("F", 4) } else { ("G", 2) };
+    let title = title.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    xml.push_str("<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \"http://www.musicxml.org/dtds/partwise.dtd\">\n");
+    xml.push_str("<score-partwise version=\"4.0\">\n");
+    let _ = writeln!(xml, "  <work><work-title>{}</work-title></work>", title);
+    xml.push_str("  <part-list><score-part id=\"P1\"><part-name>Melody</part-name></score-part></part-list>\n");
+    xml.push_str("  <part id=\"P1\">\n");
+
+    let mut measure = 0;
+    let mut filled = MEASURE;
+    let open_measure = |xml: &mut String, measure: &mut usize| {
+        if *measure > 0 {
+            xml.push_str("    </measure>\n");
+        }
+        *measure += 1;
+        let _ = writeln!(xml, "    <measure number=\"{}\">", measure);
+        if *measure == 1 {
+            let _ = writeln!(
+                xml,
+                "      <attributes><divisions>{}</divisions><key><fifths>0</fifths></key><time><beats>4</beats><beat-type>4</beat-type></time><clef><sign>{}</sign><line>{}</line></clef></attributes>",
+                DIVISIONS, sign, line
+            );
+            let _ = writeln!(
+                xml,
+                "      <direction placement=\"above\"><direction-type><metronome><beat-unit>quarter</beat-unit><per-minute>{}</per-minute></metronome></direction-type><sound tempo=\"{}\"/></direction>",
+                bpm, bpm
+            );
+        }
+    };
+    for (midi, duration) in events(notes, bpm) {
+        let mut left = duration;
+        let mut tied = false;
+        while left > 0 {
+            if filled == MEASURE {
+                open_measure(&mut xml, &mut measure);
+                filled = 0;
+            }
+            let room = (MEASURE - filled).min(left);
+            let value = note_value(room);
+            left -= value.0;
+            filled += value.0;
+            // Rests are simply written one after another
+            let tie_start = midi.is_some() && left > 0;
+            write_note(&mut xml, midi, value, tie_start, midi.is_some() && tied);
+            tied = tie_start;
+        }
+    }
+    if measure == 0 {
+        open_measure(&mut xml, &mut measure);
+        filled = 0;
+    }
+    // Rest out the last measure
+    while filled < MEASURE {
+        let value = note_value(MEASURE - filled);
+        write_note(&mut xml, None, value, false, false);
+        filled += value.0;
+    }
+    xml.push_str("    </measure>\n  </part>\n</score-partwise>\n");
+    xml
+}
@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use std::net::{SocketAddr, UdpSocket};
+
+use crate::stats::live::{LiveFrame, LiveOutput};
+
+/// Address prefix of every message.
+const PREFIX: &str = "/fourrier";
+
+/// An OSC string: the bytes, a terminating NUL and padding to a multiple of four.
+fn push_string(out: &mut Vec<u8>, text: &str) {
+    out.extend_from_slice(text.as_bytes());
+    out.resize((out.len() / 4 + 1) * 4, 0);
+}
+
+/// An OSC 1.0 message with float arguments.
+pub fn message(address: &str, args: &[f32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_string(&mut out, address);
+    push_string(&mut out, &format!(",{}", "f".repeat(args.len())));
+    for arg in args {
+        out.extend_from_slice(&arg.to_be_bytes());
+    }
+    out
+}
+
+/// Sends live features as OSC messages over UDP: `/fourrier/bands` with the level of each
+/// octave band in dBFS, `/fourrier/level` with the overall level, `/fourrier/f0` in Hz
+/// while voiced, and `/fourrier/onset` with the onset strength on each onset.
+pub struct OscSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+}
+
+impl OscSender {
+    pub fn new(target: SocketAddr) -> Result<Self> {
+        let local: SocketAddr = if target.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { (std::net::Ipv6Addr::UNSPECIFIED, 0).into() };
+        let socket = UdpSocket::bind(local).context("Failed to open a UDP socket for OSC")?;
+        Ok(Self { socket, target })
+    }
+
+    fn send_message(&self, name: &str, args: &[f32]) -> Result<()> {
+        let packet = message(&format!("{}/{}", PREFIX, name), args);
+        self.socket
+            .send_to(&packet, self.target)
+            .with_context(|| format!("Failed to send OSC to {}", self.target))?;
+        Ok(())
+    }
+}
+
+impl LiveOutput for OscSender {
+    fn send(&mut self, frame: &LiveFrame) -> Result<()> {
+        self.send_message("bands", &frame.bands)?;
+        self.send_message("level", &[frame.level])?;
+        if let Some(f0) = frame.f0 {
+            self.send_message("f0", &[f0])?;
+        }
+        if let Some(strength) = frame.onset {
+            self.send_message("onset", &[strength])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+use crate::audio::psd::{density_db, Psd};
+
+/// PSD as CSV: frequency in Hz, then the density and its confidence bounds in dB re full
+/// scale²/Hz.
+pub fn to_csv(psd: &Psd) -> String {
+    let mut csv = String::from("frequency_hz,psd_db_per_hz,lower_db_per_hz,upper_db_per_hz\n");
+    for (((frequency, &density), &lower), &upper) in psd.frequencies.iter().zip(&psd.density).zip(&psd.lower).zip(&psd.upper) {
+        csv.push_str(&format!(
+            "{:.3},{:.3},{:.3},{:.3}\n",
+            frequency,
+            density_db(density),
+            density_db(lower),
+            density_db(upper)
+        ));
+    }
+    csv
+}
@@ -0,0 +1,22 @@
+use crate::stats::events::AcousticEvent;
+
+/// Raven selection table: tab-separated, one selection per event on the first channel's
+/// spectrogram view, which Raven Pro and Lite open over the recording (File > Open
+/// Selection Table).
+pub fn selection_table(events: &[AcousticEvent]) -> String {
+    let mut table = String::from(
+        "Selection\tView\tChannel\tBegin Time (s)\tEnd Time (s)\tLow Freq (Hz)\tHigh Freq (Hz)\tPeak Freq (Hz)\n",
+    );
+    for (index, event) in events.iter().enumerate() {
+        table.push_str(&format!(
+            "{}\tSpectrogram 1\t1\t{:.6}\t{:.6}\t{:.1}\t{:.1}\t{:.1}\n",
+            index + 1,
+            event.start,
+            event.end,
+            event.low_frequency,
+            event.high_frequency,
+            event.peak_frequency
+        ));
+    }
+    table
+}
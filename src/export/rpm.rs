@@ -0,0 +1,16 @@
+use crate::stats::engine::RpmTrack;
+
+/// An RPM track as CSV, one row per frame: seconds, the firing frequency in Hz and the
+/// engine speed (both empty where no engine is heard).
+pub fn to_csv(track: &RpmTrack) -> String {
+    let mut csv = String::from("time_s,firing_hz,rpm\n");
+    for &(time, frequency) in &track.points {
+        match frequency {
+            Some(frequency) => {
+                csv.push_str(&format!("{:.3},{:.2},{:.0}\n", time, frequency, track.engine.rpm(frequency)))
+            }
+            None => csv.push_str(&format!("{:.3},,\n", time)),
+        }
+    }
+    csv
+}
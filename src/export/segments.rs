@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::wav::write_wav;
+use crate::audio::AudioData;
+use crate::speech::TranscriptionSegment;
+
+/// Longest text slug in a segment file name.
+const MAX_SLUG: usize = 40;
+
+/// One audio file written by `split_segments`.
+#[derive(Clone, Debug)]
+pub struct SegmentFile {
+    pub path: PathBuf,
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl SegmentFile {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Writes each transcript segment's audio (all channels) to `dir` as
+/// `<HH-MM-SS.mmm>_<slug of the text>.wav`. Segments outside the audio are skipped.
+pub fn split_segments(
+    dir: &Path,
+    audio: &AudioData,
+    transcription: &[TranscriptionSegment],
+) -> Result<Vec<SegmentFile>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let rate = audio.sample_rate as f64;
+    let channels: Vec<&[f32]> = (0..audio.channels.max(1)).filter_map(|c| audio.channel(c)).collect();
+
+    let mut files = Vec::new();
+    for segment in transcription {
+        let start = ((segment.start.max(0.0) * rate) as usize).min(audio.samples.len());
+        let end = ((segment.end * rate) as usize).min(audio.samples.len());
+        if end <= start {
+            continue;
+        }
+        let text = segment.text.trim().to_string();
+        let path = dir.join(format!("{}_{}.wav", timestamp(segment.start), slug(&text)));
+        let slices: Vec<&[f32]> = channels.iter().map(|c| &c[start..end.min(c.len())]).collect();
+        write_wav(&path, &slices, audio.sample_rate)?;
+        files.push(SegmentFile {
+            path,
+            text,
+            start: start as f64 / rate,
+            end: end as f64 / rate,
+        });
+    }
+    Ok(files)
+}
+
+/// `HH-MM-SS.mmm`, which sorts in time order and is valid in file names everywhere.
+fn timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}-{:02}-{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Lowercase ASCII letters and digits joined by dashes, e.g. "Hello, world!" -> "hello-world".
+fn slug(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let mut slug = String::new();
+    for word in words {
+        if slug.len() + word.len() + 1 > MAX_SLUG {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+    if slug.is_empty() {
+        "segment".to_string()
+    } else {
+        slug
+    }
+}
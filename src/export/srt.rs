@@ -0,0 +1,27 @@
+use crate::speech::TranscriptionSegment;
+
+/// `HH:MM:SS,mmm`, the SubRip timestamp format.
+fn timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+pub fn to_srt(transcription: &[TranscriptionSegment]) -> String {
+    let mut out = String::new();
+    for (i, seg) in transcription.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(seg.start),
+            timestamp(seg.end),
+            seg.text.trim()
+        ));
+    }
+    out
+}
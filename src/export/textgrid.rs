@@ -0,0 +1,54 @@
+use crate::speech::TranscriptionSegment;
+
+/// Praat TextGrid (long text format) with one interval tier per entry of `tiers`. Gaps
+/// between segments become empty intervals, as Praat requires tiers to cover the whole range.
+pub fn to_textgrid(tiers: &[(&str, &[TranscriptionSegment])], duration: f64) -> String {
+    let end = tiers
+        .iter()
+        .flat_map(|(_, segments)| segments.iter().map(|s| s.end))
+        .fold(duration, f64::max);
+    let mut out = format!(
+        "File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n\nxmin = 0\nxmax = {}\ntiers? <exists>\nsize = {}\nitem []:\n",
+        end,
+        tiers.len()
+    );
+    for (number, (name, segments)) in tiers.iter().enumerate() {
+        let mut intervals = Vec::new();
+        let mut time = 0.0;
+        for segment in *segments {
+            let start = segment.start.max(time);
+            if start > time {
+                intervals.push((time, start, String::new()));
+            }
+            let stop = segment.end.max(start);
+            intervals.push((start, stop, segment.text.trim().to_string()));
+            time = stop;
+        }
+        if end > time {
+            intervals.push((time, end, String::new()));
+        }
+
+        out.push_str(&format!(
+            "    item [{}]:\n        class = \"IntervalTier\"\n        name = \"{}\"\n        xmin = 0\n        xmax = {}\n        intervals: size = {}\n",
+            number + 1,
+            quote(name),
+            end,
+            intervals.len()
+        ));
+        for (i, (start, stop, text)) in intervals.iter().enumerate() {
+            out.push_str(&format!(
+                "        intervals [{}]:\n            xmin = {}\n            xmax = {}\n            text = \"{}\"\n",
+                i + 1,
+                start,
+                stop,
+                quote(text)
+            ));
+        }
+    }
+    out
+}
+
+/// Praat escapes a quote inside a string by doubling it.
+fn quote(text: &str) -> String {
+    text.replace('"', "\"\"")
+}
@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+/// Writes 16-bit PCM WAV with one entry of `channels` per channel, interleaved.
+pub fn write_wav(path: &Path, channels: &[&[f32]], sample_rate: u32) -> Result<()> {
+    let bytes = wav_bytes(channels, sample_rate);
+    let mut file = std::fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(&bytes)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The 16-bit PCM WAV file `write_wav` writes, in memory.
+pub fn wav_bytes(channels: &[&[f32]], sample_rate: u32) -> Vec<u8> {
+    let frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    let channel_count = channels.len().max(1) as u16;
+    let block_align = channel_count * 2;
+    let data_len = (frames * block_align as usize) as u32;
+
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&channel_count.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for i in 0..frames {
+        for channel in channels {
+            let sample = (channel[i].clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+    bytes
+}
@@ -0,0 +1,213 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::Status;
+
+use super::proto;
+use super::proto::job::State;
+use super::proto::job_result::Output;
+use crate::metrics::Metrics;
+
+/// How long a finished job's result is kept for `GetJobResult`.
+const RETENTION: Duration = Duration::from_secs(3600);
+
+/// The analyses and transcriptions running at once, at most one per permit, and their
+/// metrics.
+pub(super) struct Workers {
+    pub metrics: Arc<Metrics>,
+    permits: Arc<Semaphore>,
+}
+
+impl Workers {
+    pub fn new(metrics: Arc<Metrics>, max_jobs: usize) -> Self {
+        Self { metrics, permits: Arc::new(Semaphore::new(max_jobs.max(1))) }
+    }
+
+    /// Waits for a worker to be free.
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit, Status> {
+        self.permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| Status::unavailable("The server is shutting down"))
+    }
+
+    /// Waits for a worker, then runs `work` on it, off the async workers, counted in the
+    /// metrics under `rpc`.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        rpc: &str,
+        work: impl FnOnce() -> Result<(T, f64), Status> + Send + 'static,
+    ) -> Result<T, Status> {
+        let _queued = self.metrics.queued();
+        let permit = self.acquire().await?;
+        self.execute(rpc, permit, None, work).await
+    }
+
+    /// Runs `work` on the worker `permit` holds, keeping it until the work returns its
+    /// result and the seconds of audio it processed. Audio is decoded as part of the work,
+    /// so only requests holding a worker have decoded samples in memory. After `timeout`, if
+    /// given, stops waiting and sets its flag; the worker stays taken until the work sees
+    /// the flag and returns, so work must check it between its stages.
+    pub async fn execute<T: Send + 'static>(
+        &self,
+        rpc: &str,
+        permit: OwnedSemaphorePermit,
+        timeout: Option<(Duration, Arc<AtomicBool>)>,
+        work: impl FnOnce() -> Result<(T, f64), Status> + Send + 'static,
+    ) -> Result<T, Status> {
+        let started = Instant::now();
+        let task = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            work()
+        });
+        let joined = match timeout {
+            Some((limit, cancel)) => match tokio::time::timeout(limit, task).await {
+                Ok(joined) => joined,
+                Err(_) => {
+                    cancel.store(true, Ordering::Relaxed);
+                    self.metrics.finished(rpc, 0.0, started.elapsed(), false);
+                    return Err(Status::deadline_exceeded(format!("Timed out after {} s", limit.as_secs())));
+                }
+            },
+            None => task.await,
+        };
+        let result = joined.map_err(|e| Status::internal(e.to_string()))?;
+        let seconds = result.as_ref().map_or(0.0, |(_, seconds)| *seconds);
+        self.metrics.finished(rpc, seconds, started.elapsed(), result.is_ok());
+        result.map(|(output, _)| output)
+    }
+}
+
+/// An analysis or transcription error, as the status a client gets.
+pub(super) fn internal(error: anyhow::Error) -> Status {
+    Status::internal(format!("{:#}", error))
+}
+
+struct Entry {
+    /// Order of submission, for the queue position
+    number: u64,
+    state: State,
+    error: String,
+    output: Option<Output>,
+    finished: Option<Instant>,
+}
+
+/// Jobs submitted with `SubmitJob`, by id, until `RETENTION` after they finish.
+pub(super) struct Jobs {
+    entries: Mutex<HashMap<String, Entry>>,
+    submitted: Mutex<u64>,
+    /// Most jobs waiting for a worker, each holding its undecoded audio
+    max_queue: usize,
+}
+
+impl Jobs {
+    pub fn new(max_queue: usize) -> Self {
+        Self { entries: Mutex::new(HashMap::new()), submitted: Mutex::new(0), max_queue: max_queue.max(1) }
+    }
+
+    /// Adds a queued job and returns its id: 128 random bits, unguessable so clients can't
+    /// read each other's results. Expired jobs are dropped, and the job is refused if
+    /// `max_queue` are already waiting.
+    pub fn submit(&self) -> Result<String, Status> {
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes).map_err(|e| Status::internal(format!("Failed to make a job id: {}", e)))?;
+        let id: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let mut number = self.submitted.lock().expect("jobs are never poisoned");
+        let mut entries = self.entries.lock().expect("jobs are never poisoned");
+        entries.retain(|_, entry| entry.finished.is_none_or(|finished| finished.elapsed() < RETENTION));
+        if entries.values().filter(|entry| entry.state == State::Queued).count() >= self.max_queue {
+            return Err(Status::resource_exhausted(format!(
+                "{} jobs are already queued; try again later",
+                self.max_queue
+            )));
+        }
+        *number += 1;
+        let entry = Entry { number: *number, state: State::Queued, error: String::new(), output: None, finished: None };
+        entries.insert(id.clone(), entry);
+        Ok(id)
+    }
+
+    pub fn start(&self, id: &str) {
+        if let Some(entry) = self.entries.lock().expect("jobs are never poisoned").get_mut(id) {
+            entry.state = State::Running;
+        }
+    }
+
+    /// Stores a job's output, or its error.
+    pub fn finish(&self, id: &str, outcome: Result<Output, Status>) {
+        if let Some(entry) = self.entries.lock().expect("jobs are never poisoned").get_mut(id) {
+            match outcome {
+                Ok(output) => {
+                    entry.state = State::Done;
+                    entry.output = Some(output);
+                }
+                Err(status) => {
+                    let timed_out = status.code() == tonic::Code::DeadlineExceeded;
+                    entry.state = if timed_out { State::TimedOut } else { State::Failed };
+                    entry.error = status.message().to_string();
+                }
+            }
+            entry.finished = Some(Instant::now());
+        }
+    }
+
+    pub fn status(&self, id: &str) -> Result<proto::Job, Status> {
+        let entries = self.entries.lock().expect("jobs are never poisoned");
+        let entry = entries.get(id).ok_or_else(|| Status::not_found(format!("No job {}", id)))?;
+        let position = if entry.state == State::Queued {
+            entries
+                .values()
+                .filter(|other| other.state == State::Queued && other.number < entry.number)
+                .count()
+        } else {
+            0
+        };
+        Ok(proto::Job {
+            id: id.to_string(),
+            state: entry.state as i32,
+            position: position as u32,
+            error: entry.error.clone(),
+        })
+    }
+
+    /// A finished job as the JSON hooks are sent: its id, state (`done`, `failed` or
+    /// `timed_out`), error and result, as `--json` writes an analysis or as a list of
+    /// segments.
+    pub fn payload(&self, id: &str) -> Option<Value> {
+        let entries = self.entries.lock().expect("jobs are never poisoned");
+        let entry = entries.get(id)?;
+        let result = match &entry.output {
+            Some(Output::Analyze(response)) => serde_json::from_str(&response.json).unwrap_or(Value::Null),
+            Some(Output::Transcribe(response)) => {
+                let segments: Vec<Value> = response
+                    .segments
+                    .iter()
+                    .map(|segment| json!({ "start": segment.start, "end": segment.end, "text": segment.text }))
+                    .collect();
+                json!({ "segments": segments })
+            }
+            None => Value::Null,
+        };
+        Some(json!({
+            "id": id,
+            "state": entry.state.as_str_name().to_lowercase(),
+            "error": (!entry.error.is_empty()).then_some(&entry.error),
+            "result": result,
+        }))
+    }
+
+    pub fn result(&self, id: &str) -> Result<proto::JobResult, Status> {
+        let entries = self.entries.lock().expect("jobs are never poisoned");
+        let entry = entries.get(id).ok_or_else(|| Status::not_found(format!("No job {}", id)))?;
+        match entry.state {
+            State::Queued | State::Running => Err(Status::failed_precondition(format!("Job {} hasn't finished", id))),
+            State::Done => Ok(proto::JobResult { output: entry.output.clone() }),
+            State::TimedOut => Err(Status::deadline_exceeded(entry.error.clone())),
+            State::Failed => Err(Status::internal(entry.error.clone())),
+        }
+    }
+}
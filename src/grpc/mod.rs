@@ -0,0 +1,503 @@
+// Handlers answer with tonic's `Status`, large as errors go
+#![allow(clippy::result_large_err)]
+
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::audio::{compute_spectrogram, load_audio, load_audio_bytes, AudioData, AudioTags};
+use crate::export::analysis_json;
+use crate::export::hook::Hook;
+use crate::metrics::Metrics;
+use crate::speech::{transcribe_audio, transcribe_chunked, TranscriptionSegment};
+use crate::stats::compute_stats;
+
+mod jobs;
+
+/// Types and the server trait generated from `proto/fourrier.proto`.
+pub mod proto {
+    tonic::include_proto!("fourrier.v1");
+}
+
+use jobs::{internal, Jobs, Workers};
+use proto::analyzer_server::{Analyzer, AnalyzerServer};
+use proto::audio::Source;
+use proto::job_result::Output;
+use proto::submit_job_request::Task;
+
+/// Length of the windows `StreamTranscribe` transcribes, in seconds: Whisper's own context.
+const STREAM_WINDOW: f64 = 30.0;
+
+/// Segments `StreamTranscribe` buffers before a slow client holds up transcription.
+const STREAM_BUFFER: usize = 64;
+
+/// Largest spectrogram window a request may ask for: 1.4 s at 48 kHz, and far short of
+/// what would let one request allocate gigabytes.
+const MAX_WINDOW: usize = 65_536;
+
+/// What a job does, decoding its audio once it has a worker and stopping early once the
+/// flag is set where it can; returns the output and the seconds of audio processed.
+type JobWork = Box<dyn FnOnce(&AtomicBool) -> Result<(Output, f64), Status> + Send>;
+
+struct Service {
+    /// Whisper model for the transcription RPCs, if one was found at startup
+    model: Option<PathBuf>,
+    workers: Arc<Workers>,
+    jobs: Arc<Jobs>,
+    /// How long a job may run unless it asks for less or more
+    job_timeout: Duration,
+    /// Told of every job as it finishes
+    hook: Option<Hook>,
+    /// Hosts a job's own callback URL may name
+    callback_hosts: Vec<String>,
+    access: Arc<AudioAccess>,
+}
+
+impl Service {
+    fn model(&self) -> Result<PathBuf, Status> {
+        self.model
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("The server has no Whisper model (start it with --model)"))
+    }
+}
+
+/// Where a request's audio `path` may point, so clients can't have the server read its
+/// own files or fetch from its own network: files under `root`, and URLs on `hosts`.
+/// Without either, audio has to be sent as data.
+#[derive(Clone, Debug, Default)]
+pub struct AudioAccess {
+    /// Directory, canonical, whose files requests may name
+    pub root: Option<PathBuf>,
+    /// Hosts, lowercase, whose http(s) URLs requests may name
+    pub hosts: Vec<String>,
+}
+
+impl AudioAccess {
+    /// What the server opens for a request's `path`: a URL on an allowed host, or a file
+    /// under the root (relative paths are taken from it), symlinks resolved.
+    fn resolve(&self, path: &str) -> Result<String, Status> {
+        if path.trim() == "-" {
+            return Err(Status::invalid_argument("`-` (the server's standard input) isn't an audio path"));
+        }
+        if is_url(path) {
+            let host = match Hook::parse(path) {
+                Ok(url @ Hook::Url(_)) => url.host().unwrap_or_default(),
+                _ => return Err(Status::invalid_argument(format!("Invalid URL {}", path))),
+            };
+            if !self.hosts.contains(&host) {
+                return Err(Status::permission_denied(format!(
+                    "Audio URL host `{}` isn't allowed (the server must be started with --allow-audio-host {})",
+                    host, host
+                )));
+            }
+            return Ok(path.to_string());
+        }
+        let root = self.root.as_ref().ok_or_else(|| {
+            Status::permission_denied("Audio paths aren't allowed (the server must be started with --audio-root DIR)")
+        })?;
+        let file = root
+            .join(path)
+            .canonicalize()
+            .ok()
+            .filter(|file| file.is_file())
+            .ok_or_else(|| Status::invalid_argument(format!("No file {}", path)))?;
+        if !file.starts_with(root) {
+            return Err(Status::permission_denied(format!("{} is outside the server's --audio-root", path)));
+        }
+        file.into_os_string()
+            .into_string()
+            .map_err(|_| Status::invalid_argument(format!("{} isn't a UTF-8 path", path)))
+    }
+}
+
+/// Decodes a request's audio, which is slow and memory-hungry enough that it's only done
+/// in work holding a worker.
+fn decode(audio: Option<proto::Audio>, access: &AudioAccess) -> Result<(AudioData, String), Status> {
+    let decoded = match audio.and_then(|audio| audio.source) {
+        Some(Source::Data(bytes)) => load_audio_bytes(bytes).map(|audio| (audio, String::new())),
+        Some(Source::Path(path)) => load_audio(access.resolve(&path)?).map(|audio| (audio, path)),
+        None => return Err(Status::invalid_argument("No audio given")),
+    };
+    decoded.map_err(|e| Status::invalid_argument(format!("Failed to load audio: {:#}", e)))
+}
+
+/// A job's audio, if it has any: a path must be one `access` allows, naming a file or a
+/// URL, which is only fetched once the job runs.
+fn check_audio(audio: Option<proto::Audio>, access: &AudioAccess) -> Result<proto::Audio, Status> {
+    match audio.as_ref().and_then(|audio| audio.source.as_ref()) {
+        Some(Source::Data(_)) => {}
+        Some(Source::Path(path)) => {
+            access.resolve(path)?;
+        }
+        None => return Err(Status::invalid_argument("No audio given")),
+    }
+    Ok(audio.expect("checked above"))
+}
+
+fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Stops work between its stages once a job's timeout has set `cancel`, so the job frees
+/// its worker rather than running on unseen.
+fn check_cancelled(cancel: &AtomicBool) -> Result<(), Status> {
+    if cancel.load(Ordering::Relaxed) {
+        Err(Status::deadline_exceeded("Stopped after the job's timeout"))
+    } else {
+        Ok(())
+    }
+}
+
+fn segment(segment: TranscriptionSegment, offset: f64) -> proto::Segment {
+    proto::Segment {
+        start: segment.start + offset,
+        end: segment.end + offset,
+        text: segment.text.trim().to_string(),
+    }
+}
+
+fn window_size(request: &proto::AnalyzeRequest) -> Result<usize, Status> {
+    match request.window_size as usize {
+        0 => Ok(1024),
+        size if (2..=MAX_WINDOW).contains(&size) => Ok(size),
+        size => Err(Status::invalid_argument(format!(
+            "window_size must be from 2 to {}, got {}",
+            MAX_WINDOW, size
+        ))),
+    }
+}
+
+fn seconds(audio: &AudioData) -> f64 {
+    audio.samples.len() as f64 / audio.sample_rate as f64
+}
+
+/// The analysis of `audio`, checking `cancel` between its stages.
+fn analyze(
+    audio: &AudioData,
+    input: &str,
+    window_size: usize,
+    cancel: &AtomicBool,
+) -> Result<proto::AnalyzeResponse, Status> {
+    check_cancelled(cancel)?;
+    let spectrogram = compute_spectrogram(audio, window_size).map_err(internal)?;
+    check_cancelled(cancel)?;
+    let stats = compute_stats(audio, &spectrogram, &[]);
+    check_cancelled(cancel)?;
+    Ok(proto::AnalyzeResponse {
+        duration: stats.duration,
+        sample_rate: stats.sample_rate,
+        channels: stats.channels as u32,
+        peak_dbfs: stats.peak_dbfs,
+        rms_dbfs: stats.rms_dbfs,
+        lufs: stats.lufs,
+        dominant_frequency: stats.dominant_frequency,
+        bpm: stats.bpm,
+        key: stats.key.clone(),
+        snr_db: stats.quality.snr_db,
+        noise_floor_dbfs: stats.quality.noise_floor_dbfs,
+        bandwidth_hz: stats.quality.bandwidth_hz,
+        json: analysis_json(Path::new(input), audio, &stats, &[], None).to_string(),
+    })
+}
+
+#[tonic::async_trait]
+impl Analyzer for Service {
+    async fn analyze(
+        &self,
+        request: Request<proto::AnalyzeRequest>,
+    ) -> Result<Response<proto::AnalyzeResponse>, Status> {
+        let request = request.into_inner();
+        let window_size = window_size(&request)?;
+        let access = self.access.clone();
+        let response = self
+            .workers
+            .run("analyze", move || {
+                let (audio, input) = decode(request.audio, &access)?;
+                let never = AtomicBool::new(false);
+                Ok((analyze(&audio, &input, window_size, &never)?, seconds(&audio)))
+            })
+            .await?;
+        Ok(Response::new(response))
+    }
+
+    async fn transcribe(
+        &self,
+        request: Request<proto::TranscribeRequest>,
+    ) -> Result<Response<proto::TranscribeResponse>, Status> {
+        let model = self.model()?;
+        let audio = request.into_inner().audio;
+        let access = self.access.clone();
+        let segments = self
+            .workers
+            .run("transcribe", move || {
+                let (audio, _) = decode(audio, &access)?;
+                Ok((transcribe_audio(&audio, &model).map_err(internal)?, seconds(&audio)))
+            })
+            .await?;
+        let segments = segments.into_iter().map(|s| segment(s, 0.0)).collect();
+        Ok(Response::new(proto::TranscribeResponse { segments }))
+    }
+
+    type StreamTranscribeStream = Pin<Box<dyn Stream<Item = Result<proto::Segment, Status>> + Send>>;
+
+    async fn stream_transcribe(
+        &self,
+        request: Request<Streaming<proto::AudioChunk>>,
+    ) -> Result<Response<Self::StreamTranscribeStream>, Status> {
+        let model = self.model()?;
+        let mut chunks = request.into_inner();
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let workers = self.workers.clone();
+        tokio::spawn(async move {
+            if let Err(status) = stream_windows(&mut chunks, model, &workers, &sender).await {
+                let _ = sender.send(Err(status)).await;
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    async fn submit_job(&self, request: Request<proto::SubmitJobRequest>) -> Result<Response<proto::Job>, Status> {
+        let request = request.into_inner();
+        let timeout = match request.timeout_seconds {
+            0 => self.job_timeout,
+            seconds => Duration::from_secs(seconds as u64),
+        };
+        // Clients may only name URLs, a command would run whatever they sent, and only to
+        // allowed hosts, so they can't have the server reach into its own network
+        let callback = match Hook::parse(&request.callback_url) {
+            _ if request.callback_url.trim().is_empty() => None,
+            Ok(url @ Hook::Url(_)) => {
+                let host = url.host().unwrap_or_default();
+                if !self.callback_hosts.contains(&host) {
+                    return Err(Status::permission_denied(format!(
+                        "callback_url host `{}` isn't allowed (the server must be started with --allow-callback {})",
+                        host, host
+                    )));
+                }
+                Some(url)
+            }
+            Ok(Hook::Command(_)) => return Err(Status::invalid_argument("callback_url must be an http(s) URL")),
+            Err(e) => return Err(Status::invalid_argument(e.to_string())),
+        };
+        let hooks: Vec<Hook> = self.hook.iter().cloned().chain(callback).collect();
+        // Queued jobs keep their audio as sent and are decoded once they have a worker, so
+        // only missing audio is refused now and a file that won't decode fails the job
+        let (rpc, work): (&'static str, JobWork) = match request.task {
+            Some(Task::Analyze(analyze_request)) => {
+                let window_size = window_size(&analyze_request)?;
+                let audio = check_audio(analyze_request.audio, &self.access)?;
+                let access = self.access.clone();
+                let work: JobWork = Box::new(move |cancel: &AtomicBool| {
+                    let (audio, input) = decode(Some(audio), &access)?;
+                    let response = analyze(&audio, &input, window_size, cancel)?;
+                    Ok((Output::Analyze(response), seconds(&audio)))
+                });
+                ("analyze_job", work)
+            }
+            Some(Task::Transcribe(transcribe_request)) => {
+                let model = self.model()?;
+                let audio = check_audio(transcribe_request.audio, &self.access)?;
+                let access = self.access.clone();
+                let work: JobWork = Box::new(move |cancel: &AtomicBool| {
+                    let (audio, _) = decode(Some(audio), &access)?;
+                    let transcript = transcribe_chunked(&audio, &model, 1, Some(cancel)).map_err(internal)?;
+                    let segments = transcript.segments.into_iter().map(|s| segment(s, 0.0)).collect();
+                    Ok((Output::Transcribe(proto::TranscribeResponse { segments }), seconds(&audio)))
+                });
+                ("transcribe_job", work)
+            }
+            None => return Err(Status::invalid_argument("No task given")),
+        };
+        let id = self.jobs.submit()?;
+        let (workers, jobs, job) = (self.workers.clone(), self.jobs.clone(), id.clone());
+        tokio::spawn(async move {
+            let _queued = workers.metrics.queued();
+            let permit = match workers.acquire().await {
+                Ok(permit) => permit,
+                Err(status) => return jobs.finish(&job, Err(status)),
+            };
+            jobs.start(&job);
+            let cancel = Arc::new(AtomicBool::new(false));
+            let flag = cancel.clone();
+            let outcome = workers.execute(rpc, permit, Some((timeout, cancel)), move || work(flag.as_ref())).await;
+            jobs.finish(&job, outcome);
+            if let Some(payload) = jobs.payload(&job).filter(|_| !hooks.is_empty()) {
+                deliver(hooks, payload).await;
+            }
+        });
+        Ok(Response::new(self.jobs.status(&id)?))
+    }
+
+    async fn get_job(&self, request: Request<proto::JobRequest>) -> Result<Response<proto::Job>, Status> {
+        Ok(Response::new(self.jobs.status(&request.into_inner().id)?))
+    }
+
+    async fn get_job_result(&self, request: Request<proto::JobRequest>) -> Result<Response<proto::JobResult>, Status> {
+        Ok(Response::new(self.jobs.result(&request.into_inner().id)?))
+    }
+}
+
+/// Delivers a finished job's `payload` to each of `hooks` in turn, logging failures: the
+/// result is still there for `GetJobResult`.
+async fn deliver(hooks: Vec<Hook>, payload: serde_json::Value) {
+    let delivered = tokio::task::spawn_blocking(move || {
+        for hook in &hooks {
+            if let Err(e) = hook.deliver(&payload) {
+                eprintln!("Warning: failed to deliver a job to {}: {:#}", hook, e);
+            }
+        }
+    });
+    let _ = delivered.await;
+}
+
+/// Collects the chunks into windows and sends each window's segments, timed from the
+/// start of the stream.
+async fn stream_windows(
+    chunks: &mut Streaming<proto::AudioChunk>,
+    model: PathBuf,
+    workers: &Workers,
+    sender: &mpsc::Sender<Result<proto::Segment, Status>>,
+) -> Result<(), Status> {
+    let mut sample_rate = 0;
+    let mut pending: Vec<f32> = Vec::new();
+    let mut offset = 0.0;
+    loop {
+        let chunk = chunks.message().await?;
+        let done = chunk.is_none();
+        if let Some(chunk) = chunk {
+            if sample_rate == 0 {
+                sample_rate = chunk.sample_rate;
+                if sample_rate == 0 {
+                    return Err(Status::invalid_argument("The first chunk needs a sample_rate"));
+                }
+            }
+            pending.extend(chunk.samples);
+        }
+        let window = (STREAM_WINDOW * sample_rate as f64) as usize;
+        while !pending.is_empty() && (done || pending.len() >= window) {
+            let samples: Vec<f32> = pending.drain(..pending.len().min(window)).collect();
+            let length = samples.len() as f64 / sample_rate as f64;
+            let audio = AudioData {
+                samples: samples.into(),
+                extra_channels: Vec::new(),
+                sample_rate,
+                channels: 1,
+                bits_per_sample: None,
+                tags: AudioTags::default(),
+            };
+            let model = model.clone();
+            let transcribed = workers.run("stream_transcribe", move || {
+                Ok((transcribe_audio(&audio, &model).map_err(internal)?, length))
+            });
+            for recognized in transcribed.await? {
+                if sender.send(Ok(segment(recognized, offset))).await.is_err() {
+                    // The client went away
+                    return Ok(());
+                }
+            }
+            offset += length;
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// How much work the server takes on.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// Analyses and transcriptions running at once, the rest waiting their turn
+    pub max_jobs: usize,
+    /// Jobs waiting for a worker before `SubmitJob` is refused
+    pub max_queue: usize,
+    /// How long a job runs unless it asks otherwise
+    pub job_timeout: Duration,
+    /// Largest request in bytes, which bounds the audio sent as data
+    pub max_message: usize,
+}
+
+/// Serves the `Analyzer` service on `address` until the process is stopped. `model` is the
+/// Whisper model for the transcription RPCs, which fail without one. `hook` is told of each
+/// job as it finishes, as is a job's own callback URL if its host is one of
+/// `callback_hosts`. Audio paths are opened as `access` allows. Each RPC's work is counted
+/// in `metrics`.
+pub fn serve(
+    address: SocketAddr,
+    model: Option<PathBuf>,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    hook: Option<Hook>,
+    callback_hosts: Vec<String>,
+    access: AudioAccess,
+) -> Result<()> {
+    let service = Service {
+        model,
+        workers: Arc::new(Workers::new(metrics, limits.max_jobs)),
+        jobs: Arc::new(Jobs::new(limits.max_queue)),
+        job_timeout: limits.job_timeout,
+        hook,
+        callback_hosts,
+        access: Arc::new(access),
+    };
+    let runtime = tokio::runtime::Runtime::new().context("Failed to start the async runtime")?;
+    runtime.block_on(async {
+        tonic::transport::Server::builder()
+            .add_service(AnalyzerServer::new(service).max_decoding_message_size(limits.max_message))
+            .serve(address)
+            .await
+            .with_context(|| format!("gRPC server on {} failed", address))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn audio_paths_stay_inside_what_the_server_allows() {
+        let dir = std::env::temp_dir().join(format!("fourrier-grpc-{}", std::process::id()));
+        let root = dir.join("audio");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("take.wav"), b"").unwrap();
+        std::fs::write(dir.join("secret.wav"), b"").unwrap();
+        let root = root.canonicalize().unwrap();
+        let access = AudioAccess { root: Some(root.clone()), hosts: vec!["audio.example.com".to_string()] };
+        let code = |path: &str| access.resolve(path).unwrap_err().code();
+
+        let inside = root.join("take.wav").to_string_lossy().into_owned();
+        assert_eq!(access.resolve("take.wav").unwrap(), inside);
+        assert_eq!(access.resolve(&inside).unwrap(), inside);
+        assert_eq!(code("../secret.wav"), Code::PermissionDenied);
+        assert_eq!(code(&dir.join("secret.wav").to_string_lossy()), Code::PermissionDenied);
+        assert_eq!(code("missing.wav"), Code::InvalidArgument);
+        assert_eq!(code("-"), Code::InvalidArgument);
+        assert_eq!(access.resolve("https://Audio.Example.com:8443/a.mp3").unwrap(), "https://Audio.Example.com:8443/a.mp3");
+        assert_eq!(code("http://127.0.0.1/a.mp3"), Code::PermissionDenied);
+        assert_eq!(code("http://audio.example.com@169.254.169.254/"), Code::PermissionDenied);
+
+        let closed = AudioAccess::default();
+        assert_eq!(closed.resolve("take.wav").unwrap_err().code(), Code::PermissionDenied);
+        assert_eq!(closed.resolve("https://audio.example.com/a.mp3").unwrap_err().code(), Code::PermissionDenied);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn window_sizes_are_bounded() {
+        let request = |window_size| proto::AnalyzeRequest { audio: None, window_size };
+        assert_eq!(window_size(&request(0)).unwrap(), 1024);
+        assert_eq!(window_size(&request(2)).unwrap(), 2);
+        assert_eq!(window_size(&request(65_536)).unwrap(), 65_536);
+        for size in [1, 65_537, 1 << 31] {
+            assert_eq!(window_size(&request(size)).unwrap_err().code(), Code::InvalidArgument);
+        }
+    }
+}
@@ -1,17 +1,29 @@
-use ctor::ctor;
+/// Process-wide setup for embedders and the CLI. Nothing runs implicitly; callers that want
+/// logging or quieter Whisper output call [`init`] once at startup.
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub log_level: log::LevelFilter,
+    /// Ask whisper.cpp not to print debug and progress output
+    pub quiet_whisper: bool,
+}
 
-#[ctor]
-fn init() {
-    // Suppress Whisper output by redirecting stdout during initialization
-    let stdout = std::io::stdout();
-    let _handle = stdout.lock();
-    
-    // Set environment variables
-    std::env::set_var("WHISPER_PRINT_DEBUG", "0");
-    std::env::set_var("WHISPER_PRINT_PROGRESS", "0");
-    
-    // Configure logging
-    env_logger::Builder::new()
-        .filter_level(log::LevelFilter::Error)
-        .init();
-} 
\ No newline at end of file
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            log_level: log::LevelFilter::Error,
+            quiet_whisper: true,
+        }
+    }
+}
+
+pub fn init(options: &Options) {
+    if options.quiet_whisper {
+        std::env::set_var("WHISPER_PRINT_DEBUG", "0");
+        std::env::set_var("WHISPER_PRINT_PROGRESS", "0");
+    }
+
+    // An embedding application may already have installed its own logger
+    let _ = env_logger::Builder::new()
+        .filter_level(options.log_level)
+        .try_init();
+}
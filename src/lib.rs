@@ -0,0 +1,25 @@
+pub mod audio;
+pub mod bench;
+pub mod clock;
+pub mod config;
+pub mod decode;
+pub mod doctor;
+pub mod dsp;
+pub mod export;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod metrics;
+pub mod models;
+pub mod presets;
+pub mod redact;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod session;
+pub mod setup;
+pub mod speech;
+pub mod stats;
+pub mod summarize;
+pub mod visualization;
+mod init;
+
+pub use init::{init, Options};
@@ -1,42 +1,2590 @@
-use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+use anyhow::{anyhow, Context, Result};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use std::io::{IsTerminal, Write};
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-mod audio;
-mod speech;
-mod visualization;
-mod init;
-
-use audio::{load_audio, compute_spectrogram};
-use speech::transcribe_audio;
-use visualization::Visualizer;
+use fourrier::audio::{compute_spectrogram_with, decimation_factor, load_audio, AudioData, Normalization, Precision, SpectrogramParams, Units, WindowFunction};
+use fourrier::audio::capture::{self, play_and_record, Recorder};
+use fourrier::audio::playback::{find_program, Player};
+use fourrier::audio::tracks::{find_offset, mix, shift, track_name, MIN_CORRELATION};
+use fourrier::audio::generator::{generate, Signal, SignalParams};
+use fourrier::audio::iq::{demodulate, Demodulation};
+use fourrier::audio::psd::{density_db, welch, WelchParams};
+use fourrier::audio::stft::mask_bands;
+use fourrier::audio::filter::{apply_notches, de_ess};
+use fourrier::audio::raw::{load_raw, RawFormat, SampleFormat};
+use fourrier::audio::weighting::Weighting;
+use fourrier::clock::{self, Schedule, TimeOfDay};
+use fourrier::metrics::Metrics;
+use fourrier::config::toml::{Table, Value};
+use fourrier::bench::BenchOptions;
+use fourrier::config::Config;
+use fourrier::decode::{self, decode, Mode};
+use fourrier::dsp::Chain;
+use fourrier::export::{analysis_json, save_alignment, save_beats, save_detections, save_events, save_leq, save_notes, save_transcript};
+use fourrier::export::acoustics::to_csv as decay_csv;
+use fourrier::export::rpm::to_csv as rpm_csv;
+use fourrier::export::binary::Format;
+use fourrier::export::burn_in::burn_in;
+use fourrier::export::chapters::save_chapters;
+use fourrier::export::clips::ClipDirectory;
+#[cfg(feature = "parquet")]
+use fourrier::export::features::save_features;
+use fourrier::export::hook::Hook;
+use fourrier::export::midi::MidiOut;
+use fourrier::export::osc::OscSender;
+use fourrier::export::manifest::{write_manifest, ManifestFormat};
+use fourrier::export::measurement::response_csv;
+use fourrier::export::psd::to_csv as psd_csv;
+use fourrier::export::segments::split_segments;
+use fourrier::export::wav::write_wav;
+use fourrier::models::{self, ggml::ModelHeader};
+use fourrier::redact::{bleep, Bleep, Redactor};
+#[cfg(feature = "scripting")]
+use fourrier::script::Script;
+use fourrier::presets::{classify, ContentType, Preset, PresetChoice};
+use fourrier::session::autosave::{self, Autosave};
+use fourrier::session::load_session;
+use fourrier::setup;
+use fourrier::speech::align::align;
+use fourrier::speech::chapters::{chapters, Chapter, MIN_CHAPTER};
+use fourrier::speech::live::{caption, CaptionEvent};
+use fourrier::speech::{estimate_words, merge_tracks, transcribe_chunked, transcribe_words, Transcript, TranscriptionSegment, WHISPER_RATE};
+use fourrier::stats::acoustics::{self as room, impulse_response};
+use fourrier::stats::beats::{self, detect_beats};
+use fourrier::stats::level::{self, level_track, LEVEL_STEP};
+use fourrier::stats::bleed::{self, detect_bleed};
+use fourrier::stats::{compute_stats, AudioStats};
+use fourrier::stats::engine::{self, track_rpm, Engine, RpmTrack};
+use fourrier::stats::events::{self, detect_events};
+use fourrier::stats::live::{self, LiveOutput};
+use fourrier::stats::measurement::{self, distortion, frequency_response};
+use fourrier::stats::notes::melody;
+use fourrier::stats::quality::{detect_hum, detect_tones, tone_spectrum, QualityReport};
+use fourrier::stats::speech::speech_stats;
+use fourrier::stats::stereo::{stereo_stats, StereoStats};
+use fourrier::stats::timing::{self, Timings};
+use fourrier::stats::trigger::{self, TriggerSettings};
+use fourrier::stats::tuning::estimate_tuning;
+use fourrier::summarize::Summarizer;
+use fourrier::visualization::acoustics;
+use fourrier::visualization::captions;
+use fourrier::visualization::console::{DisplayMode, SpectrogramRender};
+use fourrier::visualization::dump::DumpFormat;
+use fourrier::visualization::graphics::GraphicsMode;
+use fourrier::visualization::keymap::Keymap;
+use fourrier::visualization::levels::Contrast;
+use fourrier::visualization::loading::LoadingScreen;
+use fourrier::visualization::recording;
+use fourrier::visualization::theme::Theme;
+use fourrier::visualization::Visualizer;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("redaction").multiple(true).args(["redact_profanity", "redact_pii"])))]
 struct Cli {
-    /// Path to the audio file to analyze
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the audio file to analyze. Repeat for a multi-track session (e.g. one file per
+    /// microphone of a podcast), analyzed as the mix and shown as stacked tracks
+    #[arg(short, long, required_unless_present = "session")]
+    input: Vec<PathBuf>,
+
+    /// With several inputs recording the same event (a backup recorder, a phone and the
+    /// mixer), find each one's offset from the first by cross-correlation and shift it into
+    /// alignment
+    #[arg(long)]
+    align_tracks: bool,
+
+    /// With several inputs, print where each track's sound turns up delayed and attenuated
+    /// in another (microphone bleed, or the same content twice) instead of opening the
+    /// visualizer
+    #[arg(long, conflicts_with_all = ["stats", "quality", "tuning", "json"])]
+    bleed: bool,
+
+    /// Print acoustic events (calls and other bursts standing out of the background) with
+    /// their times and frequency ranges instead of opening the visualizer
+    #[arg(long, conflicts_with_all = ["stats", "quality", "tuning", "json", "bleed"])]
+    events: bool,
+
+    /// Write the acoustic events: JSON for `.json`, otherwise a Raven selection table
+    #[arg(long, value_name = "PATH")]
+    events_out: Option<PathBuf>,
+
+    /// Band events are detected in, e.g. `2000-10000` in Hz [default: the preset's range,
+    /// else the whole spectrum]
+    #[arg(long, value_name = "LOW-HIGH", value_parser = parse_band)]
+    event_band: Option<(f32, f32)>,
+
+    /// Print the heart rate and the time of each beat (for stethoscope recordings) instead
+    /// of opening the visualizer
+    #[arg(long, conflicts_with_all = ["stats", "quality", "tuning", "json", "bleed", "events"])]
+    beats: bool,
+
+    /// Write the beats: JSON for `.json`, otherwise CSV
+    #[arg(long, value_name = "PATH")]
+    beats_out: Option<PathBuf>,
+
+    /// Print the frequency-weighted sound level (Leq, Fast max and min, L10, L50, L90)
+    /// instead of opening the visualizer; dB SPL with --calibration-offset
+    #[arg(long, conflicts_with_all = ["stats", "quality", "tuning", "json", "bleed", "events", "beats"])]
+    levels: bool,
+
+    /// Frequency weighting of sound levels (--levels, --leq and the level meter)
+    #[arg(long, value_enum, default_value = "a")]
+    weighting: Weighting,
+
+    /// Print the Leq of each --leq-interval as a chart, with the noise dose when calibrated,
+    /// instead of opening the visualizer
+    #[arg(long, conflicts_with_all = ["stats", "quality", "tuning", "json", "bleed", "events", "beats", "levels"])]
+    leq: bool,
+
+    /// Interval of --leq and --leq-out, e.g. `1s`, `1min`, `15min` or `1h`
+    #[arg(long, value_name = "DURATION", default_value = "1s", value_parser = parse_duration)]
+    leq_interval: f64,
+
+    /// Write the Leq of each --leq-interval: JSON for `.json`, otherwise CSV
+    #[arg(long, value_name = "PATH")]
+    leq_out: Option<PathBuf>,
+
+    /// Track an engine's speed from its harmonics and draw its firing frequency over the
+    /// spectrogram, with the RPM at the playhead
+    #[arg(long)]
+    rpm: bool,
+
+    /// Write the engine's speed over time as CSV (implies --rpm)
+    #[arg(long, value_name = "PATH")]
+    rpm_out: Option<PathBuf>,
+
+    /// Cylinders of the engine --rpm tracks, which sets how firing frequency maps to RPM
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..=16))]
+    cylinders: u32,
+
+    /// The engine --rpm tracks is a two-stroke, each cylinder firing every revolution
+    #[arg(long)]
+    two_stroke: bool,
+
+    /// Session file: restored on startup when it exists (input, parameters, edits, markers,
+    /// playhead), and saved on `w` and on quit
+    #[arg(long)]
+    session: Option<PathBuf>,
+
+    /// Window size for FFT (must be a power of 2) [default: from the config or preset, else 1024]
+    #[arg(short, long)]
+    window_size: Option<usize>,
+
+    /// Window function applied to each FFT frame
+    #[arg(long, value_enum)]
+    window_function: Option<WindowFunction>,
+
+    /// Hop between FFT frames in samples [default: half the window size]
+    #[arg(long)]
+    hop: Option<usize>,
+
+    /// Precision of windowing and FFTs; `f64` for quantitative measurements that need lower
+    /// numerical noise [default: f32]
+    #[arg(long, value_enum)]
+    precision: Option<Precision>,
+
+    /// Spectrogram level units: `dbfs` (a full-scale sine at 0 dB), `power` (spectral
+    /// density in dB/Hz) or linear `amplitude` [default: dbfs]
+    #[arg(long, value_enum)]
+    units: Option<Units>,
+
+    /// Window compensation of `dbfs` and `amplitude` levels: `peak` so tones compare across
+    /// window sizes and functions, `energy` so each frame's bins sum to its power (noise
+    /// compares across window functions), `none` for the raw |X|/N
+    /// [default: from the config, else peak]
+    #[arg(long, value_enum)]
+    normalization: Option<Normalization>,
+
+    /// Calibration offset in dB added to every spectrogram and sound level, e.g. the dB SPL
+    /// a full-scale sine measures at [default: 0]
+    #[arg(long, visible_alias = "calibration-offset", value_name = "DB", allow_hyphen_values = true)]
+    calibration: Option<f32>,
+
+    /// Whisper model file (ggml format) used for transcription
+    #[arg(long, global = true)]
+    model: Option<PathBuf>,
+
+    /// Transcribe with this many Whisper states in parallel, each on its own chunk of the
+    /// audio split at quiet points (at least a minute each), sharing the cores; capped by
+    /// free memory
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    transcribe_jobs: u32,
+
+    /// Where `w` in the visualizer saves the transcript (`.json` for JSON, otherwise SRT)
+    /// [default: <input name>.srt]
+    #[arg(long)]
+    transcript_out: Option<PathBuf>,
+
+    /// Analysis preset (window size, spectrogram range, whether to transcribe). `auto` picks
+    /// one by classifying the audio; `none` keeps the plain defaults and always transcribes
+    /// [default: auto]
+    #[arg(long, value_enum)]
+    preset: Option<PresetChoice>,
+
+    /// Highest frequency the spectrogram shows, in Hz, up to half the sample rate
+    /// [default: the preset's range, else the lowest 100 bins]
+    #[arg(long, value_name = "HZ")]
+    max_frequency: Option<f32>,
+
+    /// Device to play to, as `fourrier devices` lists it [default: the system's default
+    /// output]
+    #[arg(long)]
+    output_device: Option<String>,
+
+    /// Play N times slower than recorded, bringing ultrasound down N times into hearing, as
+    /// a bat detector's time expansion does [default: 10 for sample rates of 192 kHz and
+    /// up, else 1]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=100))]
+    time_expansion: Option<u32>,
+
+    /// Rendering detail: `auto` simplifies the view on small, remote or slow terminals
+    #[arg(long, value_enum, default_value = "auto")]
+    display: DisplayMode,
+
+    /// Spectrogram rendering: `heatmap` colored cells, `blocks` colored half blocks at twice
+    /// the vertical resolution, `braille` monochrome dots for terminals with poor color support
+    #[arg(long, value_enum, default_value = "heatmap")]
+    render: SpectrogramRender,
+
+    /// Spectrogram contrast: `auto` stretches the colormap from the 5th to the 99th
+    /// percentile of the levels shown, `fixed` over the 100 dB below full scale
+    #[arg(long, value_enum, default_value = "auto")]
+    contrast: Contrast,
+
+    /// Draw the spectrogram as a true-color inline image with the Kitty graphics protocol or
+    /// Sixel. `auto` uses one when the terminal is known to support it
+    #[arg(long, value_enum, default_value = "auto")]
+    graphics: GraphicsMode,
+
+    /// Start with the spectrogram scrolling in step with playback, showing the last SECONDS
+    /// up to the playhead
+    #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "10")]
+    scroll: Option<f64>,
+
+    /// Where `w` in the visualizer saves markers (`.json` for JSON, otherwise an Audacity
+    /// label track) [default: <input name>.labels.txt]
+    #[arg(long)]
+    markers_out: Option<PathBuf>,
+
+    /// Write each transcript segment's audio to this directory as a WAV named with its start
+    /// time and text, then exit (transcribes even when the preset wouldn't)
+    #[arg(long, value_name = "DIR")]
+    split_segments: Option<PathBuf>,
+
+    /// Also write a dataset manifest (path, text, duration) for the split segments; with
+    /// `ljspeech` the audio goes into `<DIR>/wavs`
+    #[arg(long, value_enum, requires = "split_segments")]
+    manifest: Option<ManifestFormat>,
+
+    /// Mask profanity in the transcript (`f***`); `[redact] profanity` in the config adds words
+    #[arg(long)]
+    redact_profanity: bool,
+
+    /// Mask personal data in the transcript: email addresses, card-like numbers and phone
+    /// numbers (`[EMAIL]`, `[CARD]`, `[PHONE]`), plus `[redact] patterns` from the config
+    #[arg(long)]
+    redact_pii: bool,
+
+    /// Also replace redacted words in the audio (played, split and written with
+    /// --audio-out) with a tone or silence
+    #[arg(long, value_enum, requires = "redaction")]
+    bleep: Option<Bleep>,
+
+    /// Preprocessing chain run on the decoded audio before anything else, e.g.
+    /// `highpass:80,normalize:-16lufs,denoise` [default: `dsp` from the config]
+    #[arg(long, value_name = "CHAIN", allow_hyphen_values = true)]
+    dsp: Option<String>,
+
+    /// Rhai script with hooks: `transcript(segments)` to edit the transcript, `frame(time,
+    /// levels, bin_hz)` and `metrics(stats)` for custom metrics in the stats and --json
+    /// (needs a build with `--features scripting`)
+    #[arg(long, value_name = "FILE")]
+    script: Option<PathBuf>,
+
+    /// Remove steady tones with notch filters before analysis, playback and export: `auto`
+    /// for the hum and tones the quality report finds, or a comma-separated list of Hz
+    #[arg(long, value_name = "auto|HZ,...", value_parser = parse_notch)]
+    notch: Option<Notch>,
+
+    /// Remove frequency bands, e.g. `2000-4000` or `50-70,100-130` in Hz, by zeroing them in
+    /// the STFT and resynthesizing, before analysis, playback and export
+    #[arg(long, value_name = "LOW-HIGH,...", value_delimiter = ',', value_parser = parse_band)]
+    mask_band: Vec<(f32, f32)>,
+
+    /// Turn down harsh sibilance in exported audio (--audio-out, --split-segments) by up to
+    /// this many dB, with a dynamic filter above 4 kHz
+    #[arg(long, value_name = "DB", num_args = 0..=1, default_missing_value = "6")]
+    de_ess: Option<f32>,
+
+    /// Write frame-level features (time, spectral centroid, rolloff and flatness, f0, and
+    /// the level of every bin) as a Parquet table, or an Arrow IPC file for `.arrow`,
+    /// `.feather` or `.ipc`, e.g. for pandas or polars
+    #[arg(long, value_name = "PATH")]
+    features_out: Option<PathBuf>,
+
+    /// Segment a monophonic melody into notes and write them: MusicXML for `.musicxml` or
+    /// `.xml`, JSON for `.json`, otherwise a note list (start, duration, note, cents off)
+    #[arg(long, value_name = "PATH")]
+    notes_out: Option<PathBuf>,
+
+    /// Tempo of the --notes-out MusicXML in BPM [default: the estimated tempo, else 120]
+    #[arg(long, value_name = "BPM", requires = "notes_out")]
+    tempo: Option<f32>,
+
+    /// Write the processed (notched, masked, bleeped, de-essed) audio to this WAV file
+    #[arg(long, value_name = "PATH", alias = "redacted-audio")]
+    audio_out: Option<PathBuf>,
+
+    /// Split the transcript into topic chapters, drawn on the waveform (transcribes even when
+    /// the preset wouldn't)
+    #[arg(long)]
+    chapters: bool,
+
+    /// Write the chapters: Podcasting 2.0 JSON for `.json`, a copy of an MP3 input with ID3
+    /// chapters for `.mp3`, otherwise FFmpeg metadata (implies --chapters)
+    #[arg(long, value_name = "PATH")]
+    chapters_out: Option<PathBuf>,
+
+    /// Shortest chapter in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = MIN_CHAPTER)]
+    min_chapter: f64,
+
+    /// Summarize the transcript with an LLM behind an OpenAI-compatible chat endpoint
+    /// (`[summarize] endpoint` in the config, default a local llama.cpp server), shown in a
+    /// pane and included in --json
+    #[arg(long)]
+    summarize: bool,
+
+    /// When the analysis finishes, POST what --json writes to this http(s) URL, or run this
+    /// shell command with it on its standard input, e.g. to hand the result on to a pipeline
+    #[arg(long, value_name = "URL|COMMAND", value_parser = parse_hook)]
+    on_done: Option<Hook>,
+
+    /// Config file to read instead of the per-user config.toml
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Treat the input as headerless PCM described by --rate, --channels and --sample-format
+    #[arg(long)]
+    raw: bool,
+
+    /// Sample rate of raw PCM input in Hz
+    #[arg(long, default_value = "48000", requires = "raw")]
+    rate: u32,
+
+    /// Number of interleaved channels in raw PCM input
+    #[arg(long, default_value = "1", requires = "raw")]
+    channels: usize,
+
+    /// Sample encoding of raw PCM input
+    #[arg(long, value_enum, default_value = "s16le", requires = "raw")]
+    sample_format: SampleFormat,
+
+    /// Treat the input as an IQ recording (I = first channel, Q = second) and demodulate it
+    #[arg(long, value_enum)]
+    iq: Option<Demodulation>,
+
+    /// Analyze mid ((L+R)/2) and side ((L-R)/2) instead of left and right; `c` in the
+    /// visualizer switches the waveform and spectrogram between them
+    #[arg(long)]
+    mid_side: bool,
+
+    /// Increase log verbosity (-v warnings, -vv info, -vvv debug)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Print a summary of the audio statistics instead of opening the visualizer
+    #[arg(long)]
+    stats: bool,
+
+    /// Print a recording quality report (SNR, noise floor, bandwidth, mains hum) instead of
+    /// opening the visualizer
+    #[arg(long, conflicts_with = "stats")]
+    quality: bool,
+
+    /// Print an intonation report (A4 reference the melody is tuned to, and each note's
+    /// deviation from equal temperament in cents) instead of opening the visualizer
+    #[arg(long, conflicts_with_all = ["stats", "quality"])]
+    tuning: bool,
+
+    /// Print metadata, stats and transcription as JSON instead of opening the visualizer
+    #[arg(long, conflicts_with = "stats")]
+    json: bool,
+
+    /// Encoding of --json: JSON text, or MessagePack or CBOR for large outputs
+    #[arg(long, value_enum, default_value = "json", requires = "json")]
+    format: Format,
+
+    /// Render the view once to stdout (`ansi` with color escapes, or plain `text`) instead of
+    /// opening the visualizer; sized to the terminal, else $COLUMNS×$LINES, else 120×40
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "ansi", conflicts_with_all = ["stats", "quality", "tuning", "json"])]
+    dump: Option<DumpFormat>,
+
+    /// Render the scrolling spectrogram of the whole file, with the transcript as captions,
+    /// into a video (e.g. `.mp4`, with the audio) or `.gif` through ffmpeg instead of opening
+    /// the visualizer. --scroll sets the seconds shown
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["stats", "quality", "tuning", "json", "dump"])]
+    export_video: Option<PathBuf>,
+
+    /// Write a standalone HTML report (waveform, spectrogram, transcript and stats, playable
+    /// from the page) to DIR instead of opening the visualizer
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["stats", "quality", "tuning", "json", "dump"])]
+    export_report: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+enum Notch {
+    Auto,
+    Frequencies(Vec<f32>),
+}
+
+fn parse_notch(value: &str) -> Result<Notch, String> {
+    if value == "auto" {
+        return Ok(Notch::Auto);
+    }
+    value
+        .split(',')
+        .map(|f| match f.trim().parse::<f32>() {
+            Ok(f) if f > 0.0 => Ok(f),
+            _ => Err(format!("`{}` is not a frequency in Hz", f.trim())),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Notch::Frequencies)
+}
+
+fn parse_band(value: &str) -> Result<(f32, f32), String> {
+    let band = value.trim();
+    let parsed = band.split_once('-').map(|(low, high)| (low.trim().parse::<f32>(), high.trim().parse::<f32>()));
+    match parsed {
+        Some((Ok(low), Ok(high))) if low >= 0.0 && high > low => Ok((low, high)),
+        _ => Err(format!("`{}` is not a band of LOW-HIGH Hz", band)),
+    }
+}
+
+/// Parses a duration in seconds, e.g. `1s`, `0.5`, `1min`, `15m` or `2h`.
+fn parse_duration(value: &str) -> Result<f64, String> {
+    let text = value.trim().to_ascii_lowercase();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let scale = match unit.trim() {
+        "" | "s" | "sec" => 1.0,
+        "m" | "min" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("`{}` is not a duration like 1s, 1min or 1h", value)),
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number * scale >= LEVEL_STEP => Ok(number * scale),
+        Ok(_) => Err(format!("Durations must be at least {} s", LEVEL_STEP)),
+        Err(_) => Err(format!("`{}` is not a duration like 1s, 1min or 1h", value)),
+    }
+}
+
+fn parse_hook(value: &str) -> Result<Hook, String> {
+    match value.trim() {
+        "" => Err("A hook needs a URL or a command".to_string()),
+        value => Hook::parse(value).map_err(|e| e.to_string()),
+    }
+}
+
+fn parse_schedule(value: &str) -> Result<Schedule, String> {
+    Schedule::parse(value).map_err(|e| e.to_string())
+}
+
+/// Parses when to stop: a time of day (the next time the clock shows it) or a duration
+/// from now.
+fn parse_until(value: &str) -> Result<SystemTime, String> {
+    if value.contains(':') {
+        let time = TimeOfDay::parse(value).map_err(|e| e.to_string())?;
+        return Ok(time.next_after(SystemTime::now()));
+    }
+    let seconds = parse_duration(value).map_err(|_| format!("`{}` is not a time of day like 18:30 or a duration like 2h", value))?;
+    Ok(SystemTime::now() + std::time::Duration::from_secs_f64(seconds))
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Time an existing transcript against the audio (forced alignment), writing segment
+    /// and word timings
+    Align(AlignArgs),
+    /// Estimate the power spectral density (Welch's method) with a confidence interval per
+    /// bin, written as CSV
+    Psd(PsdArgs),
+    /// Estimate a room's reverberation time (RT60) and early decay time per octave band
+    /// from a recorded sweep or clap, and plot the energy decay curves
+    Acoustics(AcousticsArgs),
+    /// Generate a measurement signal (a log sweep, pink or white noise, or a tone) and
+    /// play it or write it to a WAV file
+    Generate(GenerateArgs),
+    /// Measure the frequency response and THD+N of an audio interface or device chain by
+    /// playing test signals out and recording them back through a loopback
+    Measure(MeasureArgs),
+    /// Decode signaling in a recording (DTMF digits and call-progress tones, Morse code,
+    /// or AFSK1200 packet radio), with times
+    Decode(DecodeArgs),
+    /// Run a Rhai script's `main(args)`, with `analyze(path)`, `transcribe(path)` and
+    /// `write_file(path, text)` to call (needs a build with `--features scripting`)
+    Script(ScriptArgs),
+    /// Serve the analysis pipeline over gRPC (`proto/fourrier.proto`: Analyze, Transcribe,
+    /// StreamTranscribe; needs a build with `--features grpc`)
+    Grpc(GrpcArgs),
+    /// Manage the Whisper models in the per-user cache
+    Models(ModelsArgs),
+    /// Time decoding, resampling, spectrograms and transcription on this machine, to pick
+    /// window sizes, models and thread counts
+    Bench(BenchArgs),
+    /// Check the terminal, Whisper model, audio players and decoding, and suggest fixes
+    Doctor,
+    /// Record from an input device to a WAV file, showing live levels
+    Record(RecordArgs),
+    /// List capture and playback devices, with the names --device and --output-device take
+    Devices,
+    /// Caption speech from a microphone or the system's output live, full-screen
+    Captions(CaptionsArgs),
+    /// Transcribe a video's audio and burn the transcript into a copy as subtitles (needs
+    /// ffmpeg)
+    BurnIn(BurnInArgs),
+}
+
+#[derive(Args)]
+struct BurnInArgs {
+    /// Video file to subtitle
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Video file to write [default: <input name>.subtitled.<input extension>]
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Also save the transcript (`.json` for JSON, otherwise SRT)
+    #[arg(long)]
+    transcript: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct CaptionsArgs {
+    /// Input device, as `fourrier devices` lists it [default: the system's default input]
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Caption what the system plays (the monitor of the default output, or a loopback
+    /// device), e.g. a meeting or video
+    #[arg(long, conflicts_with = "device")]
+    loopback: bool,
+
+    /// Finished phrases kept on screen above the one in progress
+    #[arg(long, value_name = "PHRASES", default_value_t = 6)]
+    history: usize,
+
+    /// Save the captions when stopped (`.json` for JSON, otherwise SRT)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct RecordArgs {
+    /// WAV file to write (with --schedule or --until, the name of the hourly files, e.g.
+    /// take-20240501-050000.wav for take.wav), or with --trigger the directory to save
+    /// event clips in
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Seconds to record [default: until stopped with q, Esc or Enter]
+    #[arg(long, value_name = "SECONDS")]
+    duration: Option<f64>,
+
+    /// Input device, as `fourrier devices` lists it (a PulseAudio source, an ALSA device
+    /// such as `hw:1,0`, ...) [default: the system's default input]
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Record what the system plays (the monitor of the default output, or a loopback
+    /// device) instead of an input
+    #[arg(long, conflicts_with = "device")]
+    loopback: bool,
+
+    /// Sample rate in Hz
+    #[arg(long, default_value_t = 48000)]
+    rate: u32,
+
+    /// Number of channels
+    #[arg(long, default_value_t = 1)]
+    channels: usize,
+
+    /// Analyze the recording when it is saved, as with `fourrier -i <output>`
+    #[arg(long, conflicts_with_all = ["schedule", "until"])]
+    analyze: bool,
+
+    /// Record every day between two times on the local clock, START-END (e.g. 22:00-06:00),
+    /// waiting for the next window outside one; the audio goes to hourly files
+    #[arg(long, value_name = "START-END", value_parser = parse_schedule)]
+    schedule: Option<Schedule>,
+
+    /// Stop at this time of day (e.g. 18:30) or after this long (e.g. 90min, 8h); the
+    /// audio goes to hourly files
+    #[arg(long, value_name = "TIME", value_parser = parse_until)]
+    until: Option<SystemTime>,
+
+    /// While recording, send octave band levels, level, f0 and onsets as OSC messages over
+    /// UDP to this address, e.g. `127.0.0.1:9000`
+    #[arg(long, value_name = "HOST:PORT")]
+    osc: Option<String>,
+
+    /// While recording, send band levels and f0 as MIDI control changes and onsets as
+    /// notes to this raw MIDI device, e.g. `/dev/snd/midiC1D0`
+    #[arg(long, value_name = "DEVICE")]
+    midi: Option<PathBuf>,
+
+    /// MIDI channel for --midi
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=16), requires = "midi")]
+    midi_channel: u8,
+
+    /// Only save events: clips where the level of the first channel (over 100 ms) reaches
+    /// this many dBFS, into the --output directory with a log, events.csv
+    #[arg(long, value_name = "DBFS", allow_hyphen_values = true, conflicts_with = "analyze")]
+    trigger: Option<f32>,
+
+    /// Measure the --trigger level in this band only, LOW-HIGH in Hz, e.g. 2000-8000 for
+    /// birdsong without the wind and traffic
+    #[arg(long, value_name = "LOW-HIGH", value_parser = parse_band, requires = "trigger")]
+    trigger_band: Option<(f32, f32)>,
+
+    /// Seconds of audio kept before each event
+    #[arg(long, value_name = "SECONDS", default_value_t = 2.0, requires = "trigger")]
+    pre_roll: f64,
+
+    /// Seconds of audio kept after each event; an event ends once the level stays under
+    /// the trigger this long
+    #[arg(long, value_name = "SECONDS", default_value_t = 3.0, requires = "trigger")]
+    post_roll: f64,
+
+    /// Serve Prometheus metrics (live levels, seconds recorded, files saved) at
+    /// http://ADDRESS/metrics while recording
+    #[arg(long, value_name = "ADDRESS")]
+    metrics: Option<std::net::SocketAddr>,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Audio file to benchmark with
     #[arg(short, long)]
     input: PathBuf,
 
-    /// Window size for FFT (must be a power of 2)
-    #[arg(short, long, default_value = "1024")]
-    window_size: usize,
+    /// Spectrogram window sizes to compare [default: 512,1024,2048,4096,8192]
+    #[arg(long, value_delimiter = ',')]
+    window_sizes: Vec<usize>,
+
+    /// Models to compare, as catalog names of cached models or paths [default: the model
+    /// transcription would use]
+    #[arg(long, value_delimiter = ',')]
+    models: Vec<String>,
+
+    /// Whisper thread counts to compare [default: powers of two up to the core count]
+    #[arg(long, value_delimiter = ',')]
+    threads: Vec<usize>,
+
+    /// Repetitions of decoding, resampling and each spectrogram; the fastest counts
+    #[arg(long, default_value_t = 3)]
+    runs: usize,
+
+    /// Seconds from the start of the file to transcribe
+    #[arg(long, default_value_t = 30.0)]
+    clip: f64,
+
+    /// Leave out transcription
+    #[arg(long, conflicts_with = "models")]
+    no_transcription: bool,
+}
+
+#[derive(Args)]
+struct ModelsArgs {
+    #[command(subcommand)]
+    action: ModelsAction,
+}
+
+#[derive(Subcommand)]
+enum ModelsAction {
+    /// List the cached models with their size, format and checksum
+    List,
+    /// Download a model from the catalog into the cache
+    Download {
+        /// Catalog name, e.g. `base` or `small.en-q5_1`
+        name: String,
+    },
+    /// Delete a cached model
+    Remove {
+        /// Catalog name or file name
+        name: String,
+    },
+    /// Check cached models for truncated or corrupted files
+    Verify {
+        /// Catalog name or file name [default: every cached model]
+        name: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct AlignArgs {
+    /// Audio file the transcript belongs to
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Transcript as plain text; each non-empty line becomes a segment
+    #[arg(long)]
+    text: PathBuf,
+
+    /// Output file: `.TextGrid` for Praat (segment and word tiers), `.json` for JSON,
+    /// otherwise SRT of the segments [default: <input name>.srt]
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ScriptArgs {
+    /// Script to run
+    script: PathBuf,
+
+    /// Arguments passed to `main(args)`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[derive(Args)]
+struct GrpcArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: std::net::SocketAddr,
+
+    /// Serve Prometheus metrics (jobs, audio processed, real-time factor, queue depth) at
+    /// http://ADDRESS/metrics
+    #[arg(long, value_name = "ADDRESS")]
+    metrics: Option<std::net::SocketAddr>,
+
+    /// Most analyses and transcriptions run at once; further requests and jobs wait their
+    /// turn, so a burst of uploads doesn't exhaust memory
+    #[arg(long, value_name = "N", default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..))]
+    max_jobs: u32,
+
+    /// Most jobs from SubmitJob waiting for a worker; further jobs are refused with
+    /// RESOURCE_EXHAUSTED until the queue drains
+    #[arg(long, value_name = "N", default_value_t = 32, value_parser = clap::value_parser!(u32).range(1..))]
+    max_queue: u32,
+
+    /// How long a job from SubmitJob may run before it's stopped, e.g. 30min or 2h (a job
+    /// can ask for its own)
+    #[arg(long, value_name = "DURATION", default_value = "1h", value_parser = parse_duration)]
+    job_timeout: f64,
+
+    /// When a job from SubmitJob finishes, POST its id, state and result as JSON to this
+    /// http(s) URL, or run this shell command with the JSON on its standard input
+    #[arg(long, value_name = "URL|COMMAND", value_parser = parse_hook)]
+    on_job_done: Option<Hook>,
+
+    /// Host a job's own callback_url may name (repeatable); without any, clients can't ask
+    /// the server to POST to hosts of their choosing
+    #[arg(long, value_name = "HOST")]
+    allow_callback: Vec<String>,
+
+    /// Directory whose files requests may name as their audio path; without it, audio has
+    /// to be sent as data or by an allowed URL
+    #[arg(long, value_name = "DIR")]
+    audio_root: Option<PathBuf>,
+
+    /// Host whose http(s) URLs requests may name as their audio path (repeatable)
+    #[arg(long, value_name = "HOST")]
+    allow_audio_host: Vec<String>,
+
+    /// Largest request the server accepts, in MiB, which bounds the audio sent as data
+    #[arg(long, value_name = "MIB", default_value_t = 256, value_parser = clap::value_parser!(u32).range(1..=4095))]
+    max_message_size: u32,
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("segmenting").args(["segment_size", "segments"])))]
+struct PsdArgs {
+    /// Audio file to analyze (the first channel)
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Samples per segment: longer segments resolve finer frequencies, with fewer segments
+    /// to average [default: 4096]
+    #[arg(long)]
+    segment_size: Option<usize>,
+
+    /// Number of segments to average instead of a segment size; the segment size follows
+    /// from the file length and overlap
+    #[arg(long)]
+    segments: Option<usize>,
+
+    /// Fraction each segment overlaps the next
+    #[arg(long, default_value_t = 0.5)]
+    overlap: f64,
+
+    /// Window applied to each segment
+    #[arg(long, value_enum, default_value = "hann")]
+    window: WindowFunction,
+
+    /// Coverage of the confidence interval
+    #[arg(long, default_value_t = 0.95)]
+    confidence: f64,
+
+    /// CSV file to write [default: stdout]
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct AcousticsArgs {
+    /// Recording of the room's response to a sweep or an impulse (a clap, balloon pop or
+    /// starter pistol)
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// The sweep played while recording, to deconvolve the recording by; without it the
+    /// recording is taken as the impulse response itself
+    #[arg(long)]
+    sweep: Option<PathBuf>,
+
+    /// Write the impulse response to this WAV file
+    #[arg(long)]
+    ir: Option<PathBuf>,
+
+    /// Write the energy decay curves to this CSV file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+    /// Signal to generate
+    #[arg(long, value_enum, default_value = "sweep")]
+    signal: Signal,
+
+    /// Seconds of signal
+    #[arg(long, default_value_t = 10.0)]
+    duration: f64,
+
+    /// Level in dBFS: the peak of a sweep or tone, the RMS of noise
+    #[arg(long, value_name = "DB", default_value_t = -12.0, allow_hyphen_values = true)]
+    level: f32,
+
+    /// Frequency a sweep starts at, in Hz
+    #[arg(long, default_value_t = 20.0)]
+    from: f32,
+
+    /// Frequency a sweep ends at, in Hz
+    #[arg(long, default_value_t = 20000.0)]
+    to: f32,
+
+    /// Frequency of a tone, in Hz
+    #[arg(long, default_value_t = 1000.0)]
+    frequency: f32,
+
+    /// Sample rate in Hz
+    #[arg(long, default_value_t = 48000)]
+    rate: u32,
+
+    /// WAV file to write
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Play the signal
+    #[arg(long)]
+    play: bool,
+
+    /// Device to play to, as `fourrier devices` lists it [default: the system's default
+    /// output]
+    #[arg(long, requires = "play")]
+    device: Option<String>,
+}
+
+#[derive(Args)]
+struct MeasureArgs {
+    /// Input device the signal comes back on, as `fourrier devices` lists it [default:
+    /// the system's default input]
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Device to play to [default: the system's default output]
+    #[arg(long)]
+    output_device: Option<String>,
+
+    /// Level of the test signals, in dBFS peak
+    #[arg(long, value_name = "DB", default_value_t = -12.0, allow_hyphen_values = true)]
+    level: f32,
+
+    /// Frequency of the distortion test tone, in Hz
+    #[arg(long, default_value_t = 1000.0)]
+    frequency: f32,
+
+    /// Sample rate in Hz
+    #[arg(long, default_value_t = 48000)]
+    rate: u32,
+
+    /// Write the frequency response to this CSV file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DecodeArgs {
+    /// Audio file to decode (the first channel)
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// What to decode
+    #[arg(long, value_enum)]
+    mode: Mode,
+
+    /// Also save what was decoded (`.json` for JSON, otherwise an Audacity label track)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    fourrier::init(&fourrier::Options {
+        log_level: match cli.verbose {
+            0 => log::LevelFilter::Error,
+            1 => log::LevelFilter::Warn,
+            2 => log::LevelFilter::Info,
+            _ => log::LevelFilter::Debug,
+        },
+        ..Default::default()
+    });
+
+    if let Some(Command::Models(args)) = &cli.command {
+        return models_command(&args.action);
+    }
+    if let Some(Command::Devices) = &cli.command {
+        return devices_command();
+    }
+    if let Some(Command::Doctor) = &cli.command {
+        return doctor_command(cli.model.as_deref(), cli.config.as_deref());
+    }
+    if cli.config.is_none() && setup::needs_first_run() {
+        let existing = models::resolve(cli.model.as_deref(), &Config::default()).ok();
+        setup::run_first_run_wizard(existing.as_deref())?;
+    }
+    let config = Config::load_or_default(cli.config.as_deref())?;
+    if let Some(Command::Align(args)) = &cli.command {
+        return align_command(args, cli.model.as_deref(), &config);
+    }
+    if let Some(Command::BurnIn(args)) = &cli.command {
+        return burn_in_command(args, cli.model.as_deref(), &config);
+    }
+    if let Some(Command::Psd(args)) = &cli.command {
+        return psd_command(args);
+    }
+    if let Some(Command::Generate(args)) = &cli.command {
+        return generate_command(args);
+    }
+    if let Some(Command::Measure(args)) = &cli.command {
+        return measure_command(args);
+    }
+    if let Some(Command::Decode(args)) = &cli.command {
+        return decode_command(args);
+    }
+    if let Some(Command::Script(args)) = &cli.command {
+        return script_command(args, cli.model.as_deref(), &config);
+    }
+    if let Some(Command::Grpc(args)) = &cli.command {
+        return grpc_command(args, cli.model.as_deref(), &config);
+    }
+    if let Some(Command::Bench(args)) = &cli.command {
+        return bench_command(args, cli.model.as_deref(), &config);
+    }
+    let theme = Theme::from_config(&config.table)?;
+    if let Some(Command::Captions(args)) = &cli.command {
+        return captions_command(args, cli.model.as_deref(), &config, &theme);
+    }
+    if let Some(Command::Acoustics(args)) = &cli.command {
+        return acoustics_command(args, &theme);
+    }
+    if let Some(Command::Record(args)) = &cli.command {
+        let analyze = args.analyze;
+        let output = record_command(args, &theme)?;
+        if !analyze {
+            return Ok(());
+        }
+        cli.input = vec![output];
+    }
+    let keymap = Keymap::from_config(&config.table)?;
+
+    let mut restored = match cli.session.clone() {
+        Some(path) if path.exists() => {
+            eprintln!("Restoring session {}...", path.display());
+            let (params, state) = load_session(&path)?;
+            apply_session_params(&mut cli, &params).with_context(|| format!("Invalid session {}", path.display()))?;
+            Some(state)
+        }
+        _ => None,
+    };
+    let input = cli
+        .input
+        .first()
+        .cloned()
+        .ok_or_else(|| anyhow!("The session file has no input; pass -i"))?;
+    if (cli.bleed || cli.align_tracks) && cli.input.len() < 2 {
+        return Err(anyhow!("--bleed and --align-tracks compare tracks; pass two or more -i inputs"));
+    }
+
+    let chain = match &cli.dsp {
+        Some(spec) => Chain::parse(spec)?,
+        None => Chain::from_config(&config.table)?,
+    };
+    #[cfg(not(feature = "scripting"))]
+    if cli.script.is_some() {
+        return Err(anyhow!("--script needs a build with `--features scripting`"));
+    }
+    #[cfg(not(feature = "parquet"))]
+    if cli.features_out.is_some() {
+        return Err(anyhow!("--features-out needs a build with `--features parquet`"));
+    }
+    #[cfg(feature = "scripting")]
+    let mut script = cli
+        .script
+        .as_deref()
+        .map(|path| Script::load(path, models::resolve(cli.model.as_deref(), &config).ok()))
+        .transpose()?;
+
+    // Everything before the viewer opens runs behind a loading screen, when it will open
+    let interactive = !(cli.stats || cli.quality || cli.tuning || cli.json || cli.bleed || cli.events || cli.beats || cli.levels || cli.leq)
+        && cli.dump.is_none()
+        && cli.export_video.is_none()
+        && cli.export_report.is_none()
+        && cli.split_segments.is_none()
+        && std::io::stdout().is_terminal();
+    let loading = if interactive {
+        let name = input.file_name().map_or(input.display().to_string(), |n| n.to_string_lossy().into_owned());
+        Some(LoadingScreen::start(format!("Loading {}", name), theme.clone())?)
+    } else {
+        None
+    };
+    let say = |text: &str| match &loading {
+        Some(loading) => loading.message(text),
+        None => eprintln!("{}", text),
+    };
+
+    let mut timings = Timings::default();
+    let mut audio_data = load_input(&cli, &input, &mut timings, &say)?;
+    // Further inputs make a multi-track session, analyzed and played as the mix of the tracks
+    let tracks = if cli.input.len() > 1 {
+        load_tracks(&cli, &input, &audio_data, &say)?
+    } else {
+        Vec::new()
+    };
+    if !tracks.is_empty() {
+        if cli.bleed {
+            print_bleed(&tracks, audio_data.sample_rate);
+            return Ok(());
+        }
+        let channels: Vec<&[f32]> = tracks.iter().map(|(_, track)| &track.samples[..]).collect();
+        audio_data = AudioData {
+            samples: mix(&channels).into(),
+            extra_channels: Vec::new(),
+            channels: 1,
+            ..audio_data
+        };
+        timings.lap("tracks");
+    }
+    if !chain.is_empty() || cli.notch.is_some() || !cli.mask_band.is_empty() {
+        preprocess(&cli, &chain, &mut audio_data, &say)?;
+        timings.lap("preprocess");
+    }
 
-    println!("Loading audio file...");
-    let audio_data = load_audio(&cli.input)?;
+    let preset = choose_preset(cli.preset.unwrap_or(PresetChoice::Auto), &audio_data, &mut timings, &say);
+    let window_size = cli
+        .window_size
+        .or(config.window_size)
+        .or(preset.map(|p| p.window_size))
+        .unwrap_or(1024);
+    if let Some(preset) = &preset {
+        say(&format!(
+            "Using {} preset: window {}, {}-{} Hz, transcription {}",
+            preset.content,
+            window_size,
+            preset.min_frequency,
+            preset.max_frequency.min(audio_data.sample_rate as f32 / 2.0),
+            if preset.transcribe { "on" } else { "off" }
+        ));
+    }
 
-    println!("Computing spectrogram...");
-    let spectrogram = compute_spectrogram(&audio_data, cli.window_size)?;
+    if (cli.events || cli.events_out.is_some()) && report_events(&cli, &audio_data, preset, &mut timings, &say)? {
+        return Ok(());
+    }
+    if (cli.beats || cli.beats_out.is_some()) && report_beats(&cli, &audio_data, &mut timings, &say)? {
+        return Ok(());
+    }
+    if (cli.levels || cli.leq || cli.leq_out.is_some()) && report_levels(&cli, &audio_data, &mut timings, &say)? {
+        return Ok(());
+    }
+    let rpm = if cli.rpm || cli.rpm_out.is_some() {
+        Some(report_rpm(&cli, &audio_data, &mut timings, &say)?)
+    } else {
+        None
+    };
 
-    println!("Transcribing audio...");
-    let transcription = transcribe_audio(&cli.input)?;
+    let want_chapters = cli.chapters || cli.chapters_out.is_some();
+    // A restored session carries its (possibly edited) transcript
+    let mut transcript = if let Some(state) = &restored {
+        Transcript {
+            words: estimate_words(&state.transcription),
+            segments: state.transcription.clone(),
+        }
+    } else if preset.is_none_or(|p| p.transcribe) || cli.split_segments.is_some() || want_chapters {
+        let transcript = transcribe_input(&cli, &config, &audio_data, &tracks, loading.as_ref(), &say)?;
+        timings.lap(timing::TRANSCRIBE);
+        transcript
+    } else {
+        Transcript::default()
+    };
+    #[cfg(feature = "scripting")]
+    if let Some(script) = &mut script {
+        let recognized = transcript.segments.clone();
+        script.transcript(&mut transcript.segments)?;
+        // Word timings no longer match edited text
+        if transcript.segments != recognized {
+            transcript.words = estimate_words(&transcript.segments);
+        }
+    }
+    if cli.redact_profanity || cli.redact_pii {
+        redact(&cli, &config, &mut transcript, &mut audio_data, &say)?;
+    }
+    let duration = audio_data.samples.len() as f64 / audio_data.sample_rate as f64;
+    let speech = speech_stats(&transcript.words, duration);
+    let transcription = transcript.segments;
+    if let Some(state) = &mut restored {
+        state.transcription = transcription.clone();
+    }
+    let de_essed = cli.de_ess.map(|depth| de_ess(&audio_data, depth.abs()));
+    if let Some(path) = &cli.audio_out {
+        let audio = de_essed.as_ref().unwrap_or(&audio_data);
+        let channels: Vec<&[f32]> = (0..audio.channels.max(1)).filter_map(|c| audio.channel(c)).collect();
+        write_wav(path, &channels, audio.sample_rate)?;
+        say(&format!("Wrote {}", path.display()));
+    }
 
-    let visualizer = Visualizer::new(audio_data, spectrogram, transcription);
+    // Correlation and balance describe the original left and right
+    let stereo = if cli.mid_side {
+        let stereo = stereo_stats(&audio_data);
+        audio_data.to_mid_side()?;
+        Some(stereo)
+    } else {
+        None
+    };
+
+    if cli.redact_profanity || cli.redact_pii || cli.de_ess.is_some() || cli.audio_out.is_some() {
+        timings.lap("redact and export");
+    }
+
+    say("Computing spectrogram...");
+    let params = spectrogram_params(&cli, &config, preset, window_size, audio_data.sample_rate);
+    if params.precision == Precision::F64 && audio_data.wider_than_f32() {
+        say(&format!(
+            "Warning: the input has {} bits per sample but is decoded to f32 (24 bits), so \
+             --precision f64 keeps the FFT's rounding noise out, not the source's lower bits",
+            audio_data.bits_per_sample.unwrap_or_default()
+        ));
+    }
+    let spectrogram = compute_spectrogram_with(&audio_data, &params, None)?;
+    let side_spectrogram = match audio_data.mono(1) {
+        Some(side) if cli.mid_side => Some(compute_spectrogram_with(&side, &params, None)?),
+        _ => None,
+    };
+    timings.lap("spectrogram");
+    #[cfg(feature = "parquet")]
+    if let Some(path) = &cli.features_out {
+        save_features(path, &audio_data, &spectrogram, window_size)?;
+        say(&format!("Wrote {}", path.display()));
+    }
+
+    if let Some(root) = &cli.split_segments {
+        return write_segments(root, cli.manifest, de_essed.as_ref().unwrap_or(&audio_data), &transcription, &say);
+    }
+
+    let chapters = if want_chapters {
+        find_chapters(&cli, &input, &audio_data, &transcription, duration, &say)?
+    } else {
+        Vec::new()
+    };
+
+    let summary = if cli.summarize {
+        let summarizer = Summarizer::from_config(&config.table)?;
+        say(&format!("Summarizing transcript via {}...", summarizer.endpoint));
+        let summary = summarizer.summarize(&transcription)?;
+        timings.lap("summarize");
+        Some(summary)
+    } else {
+        None
+    };
+
+    let mut stats = compute_stats(&audio_data, &spectrogram, &transcription);
+    timings.lap("analysis");
+    if let Some(stereo) = stereo {
+        stats.stereo = stereo;
+    }
+    if let Some(path) = &cli.notes_out {
+        write_notes(path, cli.tempo.or(stats.bpm), &input, &audio_data, &say)?;
+        timings.lap("notes");
+    }
+    #[cfg(feature = "scripting")]
+    if let Some(script) = &mut script {
+        stats.custom = script.metrics(&spectrogram, &stats)?;
+        timings.lap("script");
+    }
+    stats.timings = timings;
+    if let Some(warning) = stats.stereo.as_ref().and_then(StereoStats::warning) {
+        say(&format!("Warning: {}", warning));
+    }
+    if let Some(hook) = &cli.on_done {
+        let json = analysis_json(&input, &audio_data, &stats, &transcription, summary.as_deref());
+        deliver_result(hook, &json, &say);
+    }
+    if cli.stats || cli.quality || cli.tuning || cli.json {
+        return print_analysis(&cli, &input, &audio_data, &stats, &transcription, summary.as_deref());
+    }
+
+    let sample_count = audio_data.samples.len();
+    let sample_rate = audio_data.sample_rate;
+    let mut visualizer = with_view_options(Visualizer::new(audio_data, spectrogram, transcription, stats), &cli, &input, sample_rate)
+        .with_spectrogram_params(params)
+        .with_speech_stats(speech)
+        .with_theme(theme)
+        .with_keymap(keymap);
+    if let Some(preset) = &preset {
+        visualizer = visualizer.with_preset(preset);
+    }
+    if let Some(max_frequency) = cli.max_frequency {
+        visualizer = visualizer.with_max_frequency(max_frequency);
+    }
+    if let Some(track) = rpm {
+        visualizer = visualizer.with_rpm(track);
+    }
+    if let Some(side) = side_spectrogram {
+        visualizer = visualizer.with_mid_side(side, params);
+    }
+    if !tracks.is_empty() {
+        visualizer = visualizer.with_tracks(tracks.into_iter().map(|(name, track)| (name, track.samples.to_vec())).collect());
+    }
+    if let Some(summary) = summary {
+        visualizer = visualizer.with_summary(summary);
+    }
+    if !chapters.is_empty() {
+        visualizer = visualizer.with_chapters(chapters);
+    }
+    if let Some(state) = &restored {
+        visualizer.restore_session(state)?;
+    }
+    if let Some(path) = &cli.session {
+        let session = session_params(&cli, &params, preset.as_ref());
+        visualizer = visualizer.with_session_file(path.clone(), session);
+    }
+    if let Some(path) = &cli.export_video {
+        say("Rendering video...");
+        visualizer.export_video(path)?;
+        say(&format!("Wrote {}", path.display()));
+        return Ok(());
+    }
+    if let Some(dir) = &cli.export_report {
+        let name = input.file_name().map_or(input.display().to_string(), |n| n.to_string_lossy().into_owned());
+        visualizer.export_report(dir, &name)?;
+        say(&format!("Wrote {}", dir.join("index.html").display()));
+        return Ok(());
+    }
+    if let Some(loading) = loading {
+        loading.finish();
+    }
+    if let Some(format) = cli.dump {
+        let (width, height) = dump_size();
+        print!("{}", visualizer.dump(width, height, format)?);
+        return Ok(());
+    }
+    let interval = config
+        .autosave_interval
+        .map_or(autosave::DEFAULT_INTERVAL, std::time::Duration::from_secs);
+    if !interval.is_zero() {
+        visualizer = with_autosave(visualizer, &input, sample_count, interval)?;
+    }
     visualizer.run()?;
 
     Ok(())
 }
+
+/// Loads the first input, as raw samples if `--raw` says so, demodulated if `--iq` asks.
+fn load_input(cli: &Cli, input: &Path, timings: &mut Timings, say: &dyn Fn(&str)) -> Result<AudioData> {
+    say("Loading audio file...");
+    let audio_data = if cli.raw {
+        let format = RawFormat {
+            sample_rate: cli.rate,
+            channels: cli.channels,
+            sample_format: cli.sample_format,
+        };
+        load_raw(input, &format)?
+    } else {
+        load_audio(input)?
+    };
+    timings.lap("decode");
+    let Some(mode) = cli.iq else {
+        return Ok(audio_data);
+    };
+    say(&format!("Demodulating IQ ({:?})...", mode));
+    let demodulated = demodulate(&audio_data, mode)?;
+    timings.lap("demodulate");
+    Ok(demodulated)
+}
+
+/// Loads the further `-i` inputs of a multi-track session, after the first one's
+/// `audio_data`, as named mono tracks, aligned if `--align-tracks` asks.
+fn load_tracks(cli: &Cli, input: &Path, audio_data: &AudioData, say: &dyn Fn(&str)) -> Result<Vec<(String, AudioData)>> {
+    let mut tracks = vec![(track_name(input), audio_data.mono(0).expect("the first channel always exists"))];
+    for path in &cli.input[1..] {
+        say(&format!("Loading {}...", path.display()));
+        let track = load_audio(path)?;
+        if track.sample_rate != audio_data.sample_rate {
+            return Err(anyhow!(
+                "{} is at {} Hz but {} is at {} Hz; the tracks of a session need one sample rate",
+                path.display(),
+                track.sample_rate,
+                input.display(),
+                audio_data.sample_rate
+            ));
+        }
+        tracks.push((track_name(path), track.mono(0).expect("the first channel always exists")));
+    }
+    if cli.align_tracks {
+        align_tracks(&mut tracks, audio_data.sample_rate, say);
+    }
+    Ok(tracks)
+}
+
+/// Shifts every track after the first to line up with it, leaving those that don't
+/// resemble it where they are.
+fn align_tracks(tracks: &mut [(String, AudioData)], sample_rate: u32, say: &dyn Fn(&str)) {
+    let (first, rest) = tracks.split_at_mut(1);
+    let (reference, reference_audio) = &first[0];
+    for (name, track) in rest {
+        let offset = find_offset(&reference_audio.samples, &track.samples, sample_rate);
+        let seconds = offset.samples as f64 / sample_rate as f64;
+        if offset.correlation < MIN_CORRELATION {
+            say(&format!(
+                "{} doesn't match {} (correlation {:.2}); left unshifted",
+                name, reference, offset.correlation
+            ));
+            continue;
+        }
+        say(&format!(
+            "{} starts {:.3}s {} {} (correlation {:.2}); shifted into alignment",
+            name,
+            seconds.abs(),
+            if seconds >= 0.0 { "after" } else { "before" },
+            reference,
+            offset.correlation
+        ));
+        track.samples = shift(&track.samples, offset.samples).into();
+    }
+}
+
+/// Prints the `--bleed` report: how much of each track leaks into the others.
+fn print_bleed(tracks: &[(String, AudioData)], sample_rate: u32) {
+    let channels: Vec<&[f32]> = tracks.iter().map(|(_, track)| &track.samples[..]).collect();
+    let names: Vec<String> = tracks.iter().map(|(name, _)| name.clone()).collect();
+    for line in bleed::report_lines(&detect_bleed(&channels, sample_rate), &names) {
+        println!("{}", line);
+    }
+}
+
+/// Runs the `--dsp` chain, then `--notch` and `--mask-band`.
+fn preprocess(cli: &Cli, chain: &Chain, audio_data: &mut AudioData, say: &dyn Fn(&str)) -> Result<()> {
+    if !chain.is_empty() {
+        say(&format!("Processing {}...", chain.describe()));
+        chain.apply(audio_data)?;
+    }
+    if let Some(notch) = &cli.notch {
+        apply_notch(notch, audio_data, say);
+    }
+    if !cli.mask_band.is_empty() {
+        let bands = &cli.mask_band;
+        let list: Vec<String> = bands.iter().map(|(low, high)| format!("{:.0}-{:.0}", low, high)).collect();
+        say(&format!("Masking {} Hz...", list.join(", ")));
+        mask_bands(audio_data, bands)?;
+    }
+    Ok(())
+}
+
+/// Notches out the `--notch` frequencies, or with `auto` the hum and steady tones found.
+fn apply_notch(notch: &Notch, audio_data: &mut AudioData, say: &dyn Fn(&str)) {
+    let frequencies = match notch {
+        Notch::Auto => {
+            let spectrum = tone_spectrum(audio_data);
+            let report = QualityReport {
+                hum: detect_hum(&spectrum),
+                tones: detect_tones(&spectrum),
+                ..Default::default()
+            };
+            report.tonal_frequencies()
+        }
+        Notch::Frequencies(frequencies) => frequencies.clone(),
+    };
+    if frequencies.is_empty() {
+        say("No steady tones found to notch");
+    } else {
+        let list: Vec<String> = frequencies.iter().map(|f| format!("{:.0}", f)).collect();
+        say(&format!("Notching {} Hz...", list.join(", ")));
+        apply_notches(audio_data, &frequencies);
+    }
+}
+
+/// The preset `--preset` picks, classifying the audio for `auto`.
+fn choose_preset(choice: PresetChoice, audio_data: &AudioData, timings: &mut Timings, say: &dyn Fn(&str)) -> Option<Preset> {
+    let content = match choice {
+        PresetChoice::None => return None,
+        PresetChoice::Auto => {
+            let classification = classify(audio_data);
+            timings.lap("classify");
+            say(&format!("Detected {}; use --preset to override", classification));
+            classification.content
+        }
+        PresetChoice::Speech => ContentType::Speech,
+        PresetChoice::Music => ContentType::Music,
+        PresetChoice::Environmental => ContentType::Environmental,
+        PresetChoice::Birdsong => ContentType::Birdsong,
+        PresetChoice::Ultrasonic => ContentType::Ultrasonic,
+        PresetChoice::Infrasound => ContentType::Infrasound,
+        PresetChoice::Auscultation => ContentType::Auscultation,
+    };
+    Some(Preset::for_content(content))
+}
+
+/// The spectrogram the flags, the config and the preset ask for, in that order of
+/// precedence.
+fn spectrogram_params(cli: &Cli, config: &Config, preset: Option<Preset>, window_size: usize, sample_rate: u32) -> SpectrogramParams {
+    SpectrogramParams {
+        window_size,
+        hop_size: cli.hop.unwrap_or(window_size / 2),
+        window: cli.window_function.unwrap_or_default(),
+        precision: cli.precision.unwrap_or_default(),
+        units: cli.units.unwrap_or_default(),
+        normalization: cli.normalization.or(config.normalization).unwrap_or_default(),
+        calibration: cli.calibration.unwrap_or(0.0),
+        decimation: preset
+            .and_then(|p| p.analysis_rate)
+            .map_or(1, |rate| decimation_factor(sample_rate, rate)),
+    }
+}
+
+/// Detects events for `--events` and `--events-out`, in `--event-band` or the preset's
+/// band. True when `--events` printed its report, which ends the run.
+fn report_events(
+    cli: &Cli,
+    audio_data: &AudioData,
+    preset: Option<Preset>,
+    timings: &mut Timings,
+    say: &dyn Fn(&str),
+) -> Result<bool> {
+    let nyquist = audio_data.sample_rate as f32 / 2.0;
+    let band = cli
+        .event_band
+        .or(preset.map(|p| (p.min_frequency, p.max_frequency)))
+        .map_or((0.0, nyquist), |(low, high)| (low, high.min(nyquist)));
+    let events = detect_events(audio_data, band)?;
+    timings.lap("events");
+    if cli.events {
+        for line in events::report_lines(&events) {
+            println!("{}", line);
+        }
+        return Ok(true);
+    }
+    if let Some(path) = &cli.events_out {
+        save_events(path, &events)?;
+        say(&format!("Wrote {} events to {}", events.len(), path.display()));
+    }
+    Ok(false)
+}
+
+/// Detects the beat for `--beats` and `--beats-out`. True when `--beats` printed its
+/// report, which ends the run.
+fn report_beats(cli: &Cli, audio_data: &AudioData, timings: &mut Timings, say: &dyn Fn(&str)) -> Result<bool> {
+    let beats = detect_beats(&audio_data.samples, audio_data.sample_rate);
+    timings.lap("beats");
+    if cli.beats {
+        for line in beats::report_lines(beats.as_ref()) {
+            println!("{}", line);
+        }
+        return Ok(true);
+    }
+    if let Some(path) = &cli.beats_out {
+        let beats = beats.ok_or_else(|| anyhow!("No regular beat detected"))?;
+        save_beats(path, &beats)?;
+        say(&format!("Wrote {} beats ({:.0} BPM) to {}", beats.times.len(), beats.bpm, path.display()));
+    }
+    Ok(false)
+}
+
+/// Measures levels for `--levels`, `--leq` and `--leq-out`. True when `--levels` or
+/// `--leq` printed its report, which ends the run.
+fn report_levels(cli: &Cli, audio_data: &AudioData, timings: &mut Timings, say: &dyn Fn(&str)) -> Result<bool> {
+    let calibration = cli.calibration.unwrap_or(0.0);
+    let track = level_track(&audio_data.samples, audio_data.sample_rate, cli.weighting, calibration);
+    timings.lap("levels");
+    if let Some(path) = &cli.leq_out {
+        save_leq(path, &track, cli.leq_interval)?;
+        say(&format!("Wrote {}", path.display()));
+    }
+    if !(cli.levels || cli.leq) {
+        return Ok(false);
+    }
+    let lines = if cli.leq {
+        level::leq_report_lines(&track, cli.leq_interval)
+    } else {
+        level::report_lines(&track)
+    };
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(true)
+}
+
+/// Tracks engine speed for `--rpm` and `--rpm-out`, for the viewer to plot.
+fn report_rpm(cli: &Cli, audio_data: &AudioData, timings: &mut Timings, say: &dyn Fn(&str)) -> Result<RpmTrack> {
+    let track = track_rpm(audio_data, Engine { cylinders: cli.cylinders, two_stroke: cli.two_stroke })?;
+    timings.lap("rpm");
+    for line in engine::report_lines(&track) {
+        say(&line);
+    }
+    if let Some(path) = &cli.rpm_out {
+        std::fs::write(path, rpm_csv(&track)).with_context(|| format!("Failed to write {}", path.display()))?;
+        say(&format!("Wrote {}", path.display()));
+    }
+    Ok(track)
+}
+
+/// Transcribes the audio, or each of the `tracks` of a multi-track session, with as many
+/// jobs as memory allows. On the loading screen the transcription can be cancelled,
+/// giving an empty transcript.
+fn transcribe_input(
+    cli: &Cli,
+    config: &Config,
+    audio_data: &AudioData,
+    tracks: &[(String, AudioData)],
+    loading: Option<&LoadingScreen>,
+    say: &dyn Fn(&str),
+) -> Result<Transcript> {
+    let (model_path, fit) = models::fit_to_memory(models::resolve(cli.model.as_deref(), config)?, cli.model.is_some());
+    if let Some(message) = fit {
+        say(&message);
+    }
+    let mut jobs = cli.transcribe_jobs as usize;
+    if let Some(limit) = models::parallel_limit(&model_path).filter(|&limit| limit < jobs) {
+        say(&format!("Only enough free memory for {} of {} transcription jobs", limit, jobs));
+        jobs = limit;
+    }
+    let cancel = Arc::new(AtomicBool::new(false));
+    match loading {
+        Some(loading) => loading.cancellable("Transcribing audio...", cancel.clone()),
+        None => say("Transcribing audio..."),
+    }
+    let result = if tracks.is_empty() {
+        transcribe_chunked(audio_data, &model_path, jobs, Some(&cancel))
+    } else {
+        // Track by track, so each line says whose microphone it came from
+        tracks
+            .iter()
+            .map(|(name, track)| Ok((name.clone(), transcribe_chunked(track, &model_path, jobs, Some(&cancel))?)))
+            .collect::<Result<Vec<_>>>()
+            .map(merge_tracks)
+    };
+    match result {
+        Err(_) if cancel.load(Ordering::Relaxed) => {
+            say("Transcription cancelled");
+            Ok(Transcript::default())
+        }
+        result => result,
+    }
+}
+
+/// Redacts the transcript for `--redact-profanity` and `--redact-pii`, bleeping the
+/// audio under the redacted words if `--bleep` asks.
+fn redact(cli: &Cli, config: &Config, transcript: &mut Transcript, audio_data: &mut AudioData, say: &dyn Fn(&str)) -> Result<()> {
+    let mut redactor = Redactor::default();
+    if cli.redact_profanity {
+        redactor = redactor.with_profanity(&config.table)?;
+    }
+    if cli.redact_pii {
+        redactor = redactor.with_pii(&config.table)?;
+    }
+    let ranges = redactor.redact(transcript);
+    if let Some(mode) = cli.bleep {
+        bleep(audio_data, &ranges, mode);
+    }
+    say(&format!("Redacted {} passages", ranges.len()));
+    Ok(())
+}
+
+/// Writes a clip per transcript segment under `root` for `--split-segments`, with a
+/// manifest of them if `--manifest` asks.
+fn write_segments(
+    root: &Path,
+    manifest: Option<ManifestFormat>,
+    audio_data: &AudioData,
+    transcription: &[TranscriptionSegment],
+    say: &dyn Fn(&str),
+) -> Result<()> {
+    let dir = manifest.map_or(root.to_path_buf(), |format| format.audio_dir(root));
+    let files = split_segments(&dir, audio_data, transcription)?;
+    say(&format!("Wrote {} segments to {}", files.len(), dir.display()));
+    if let Some(format) = manifest {
+        let path = write_manifest(root, &files, format)?;
+        say(&format!("Wrote {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Splits the transcript into chapters for `--chapters`, writing them to
+/// `--chapters-out` if given.
+fn find_chapters(
+    cli: &Cli,
+    input: &Path,
+    audio_data: &AudioData,
+    transcription: &[TranscriptionSegment],
+    duration: f64,
+    say: &dyn Fn(&str),
+) -> Result<Vec<Chapter>> {
+    let chapters = chapters(transcription, duration, cli.min_chapter);
+    say(&format!("Found {} chapters", chapters.len()));
+    if let Some(path) = &cli.chapters_out {
+        save_chapters(path, &chapters, input, &audio_data.tags)?;
+        say(&format!("Wrote {}", path.display()));
+    }
+    Ok(chapters)
+}
+
+/// Writes the melody to `--notes-out` at `tempo`, or 120 BPM, titled with the file's
+/// title tag or name.
+fn write_notes(path: &Path, tempo: Option<f32>, input: &Path, audio_data: &AudioData, say: &dyn Fn(&str)) -> Result<()> {
+    let notes = melody(audio_data);
+    let stem = input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    save_notes(path, &notes, tempo.unwrap_or(120.0), audio_data.tags.title.as_deref().unwrap_or(&stem))?;
+    say(&format!("Wrote {} notes to {}", notes.len(), path.display()));
+    Ok(())
+}
+
+/// Sends the analysis to the `--on-done` hook. A failed delivery is only a warning: the
+/// analysis itself succeeded.
+fn deliver_result(hook: &Hook, json: &serde_json::Value, say: &dyn Fn(&str)) {
+    say(&format!("Delivering the result to {}...", hook));
+    if let Err(e) = hook.deliver(json) {
+        say(&format!("Warning: {:#}", e));
+    }
+}
+
+/// Prints the analysis as `--stats`, `--quality`, `--tuning` or `--json` asks.
+fn print_analysis(
+    cli: &Cli,
+    input: &Path,
+    audio_data: &AudioData,
+    stats: &AudioStats,
+    transcription: &[TranscriptionSegment],
+    summary: Option<&str>,
+) -> Result<()> {
+    let lines = if cli.stats {
+        stats.summary_lines()
+    } else if cli.quality {
+        stats.quality.report_lines()
+    } else if cli.tuning {
+        match estimate_tuning(&melody(audio_data)) {
+            Some(tuning) => tuning.report_lines(),
+            None => vec!["No notes detected".to_string()],
+        }
+    } else {
+        let json = analysis_json(input, audio_data, stats, transcription, summary);
+        std::io::stdout().write_all(&cli.format.encode(&json))?;
+        return Ok(());
+    };
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// The viewer's display and output options from the command line.
+fn with_view_options(visualizer: Visualizer, cli: &Cli, input: &Path, sample_rate: u32) -> Visualizer {
+    let time_expansion = cli.time_expansion.unwrap_or(if sample_rate >= 192000 { 10 } else { 1 });
+    let transcript_out = cli.transcript_out.clone().unwrap_or_else(|| default_output_path(input, "srt"));
+    let markers_out = cli.markers_out.clone().unwrap_or_else(|| default_output_path(input, "labels.txt"));
+    let audio_out = cli.audio_out.clone().unwrap_or_else(|| default_output_path(input, "edited.wav"));
+    visualizer
+        .with_transcript_path(transcript_out)
+        .with_markers_path(markers_out)
+        .with_audio_path(audio_out)
+        // Frame rate doesn't matter for a one-off dump, so it gets full detail unless asked
+        .with_display_mode(match (cli.dump, cli.display) {
+            (Some(_), DisplayMode::Auto) => DisplayMode::Full,
+            (_, mode) => mode,
+        })
+        .with_render(cli.render)
+        .with_contrast(cli.contrast)
+        .with_weighting(cli.weighting)
+        .with_leq_interval(cli.leq_interval)
+        .with_scroll(cli.scroll)
+        .with_output_device(cli.output_device.clone())
+        .with_time_expansion(time_expansion as f64)
+        .with_graphics(cli.graphics.resolve().filter(|_| cli.dump.is_none()))
+}
+
+/// Adds autosave to the viewer, first offering to recover a session a crash left unsaved.
+fn with_autosave(mut visualizer: Visualizer, input: &Path, sample_count: usize, interval: Duration) -> Result<Visualizer> {
+    let input = input.display().to_string();
+    if let Some(recovery) = autosave::find_recovery(&input, sample_count) {
+        let age = recovery
+            .saved_at
+            .and_then(|t| t.elapsed().ok())
+            .map_or(String::new(), |age| format!(" from {} min ago", age.as_secs() / 60));
+        if setup::confirm(&format!("Recover the unsaved session{}?", age), true)? {
+            visualizer.restore_session(&recovery.state)?;
+        } else {
+            recovery.discard();
+        }
+    }
+    Ok(visualizer.with_autosave(Autosave::new(&input, sample_count, interval)))
+}
+
+fn burn_in_command(args: &BurnInArgs, model: Option<&Path>, config: &Config) -> Result<()> {
+    // Checked first, rather than after a long transcription
+    if !find_program("ffmpeg") {
+        return Err(anyhow!("burn-in needs ffmpeg on the PATH"));
+    }
+    eprintln!("Loading audio...");
+    let audio_data = load_audio(&args.input)?;
+    let model_path = models::resolve(model, config)?;
+    eprintln!("Transcribing audio...");
+    let transcription = transcribe_chunked(&audio_data, &model_path, 1, None)?.segments;
+    if let Some(path) = &args.transcript {
+        save_transcript(path, &transcription)?;
+        eprintln!("Wrote {}", path.display());
+    }
+    let extension = args.input.extension().map_or("mp4".to_string(), |ext| ext.to_string_lossy().into_owned());
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| default_output_path(&args.input, &format!("subtitled.{}", extension)));
+    eprintln!("Burning in {} segments...", transcription.len());
+    burn_in(&args.input, &transcription, &output)?;
+    eprintln!("Wrote {}", output.display());
+    Ok(())
+}
+
+fn align_command(args: &AlignArgs, model: Option<&Path>, config: &Config) -> Result<()> {
+    let text = std::fs::read_to_string(&args.text)
+        .with_context(|| format!("Failed to read {}", args.text.display()))?;
+    eprintln!("Loading audio file...");
+    let audio_data = load_audio(&args.input)?;
+    let model_path = models::resolve(model, config)?;
+    eprintln!("Recognizing words...");
+    let words = transcribe_words(&audio_data, &model_path)?;
+    let duration = audio_data.samples.len() as f64 / audio_data.sample_rate as f64;
+    let alignment = align(&text, &words, duration);
+    let output = args.output.clone().unwrap_or_else(|| default_output_path(&args.input, "srt"));
+    save_alignment(&output, &alignment, duration)?;
+    eprintln!(
+        "Aligned {} segments ({} words) to {}",
+        alignment.segments.len(),
+        alignment.words.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+#[cfg(feature = "scripting")]
+fn script_command(args: &ScriptArgs, model: Option<&Path>, config: &Config) -> Result<()> {
+    let mut script = Script::load(&args.script, models::resolve(model, config).ok())?;
+    script.run_main(&args.args)
+}
+
+#[cfg(not(feature = "scripting"))]
+fn script_command(_args: &ScriptArgs, _model: Option<&Path>, _config: &Config) -> Result<()> {
+    Err(anyhow!("`fourrier script` needs a build with `--features scripting`"))
+}
+
+#[cfg(feature = "grpc")]
+fn grpc_command(args: &GrpcArgs, model: Option<&Path>, config: &Config) -> Result<()> {
+    let model = models::resolve(model, config).ok();
+    if model.is_none() {
+        eprintln!("Warning: no Whisper model found; Transcribe and StreamTranscribe will fail");
+    }
+    let metrics = Arc::new(Metrics::new());
+    if let Some(address) = args.metrics {
+        fourrier::metrics::serve(address, metrics.clone())?;
+        eprintln!("Serving metrics on http://{}/metrics", address);
+    }
+    eprintln!("Serving gRPC on {} ({} jobs at once)...", args.listen, args.max_jobs);
+    let limits = fourrier::grpc::Limits {
+        max_jobs: args.max_jobs as usize,
+        max_queue: args.max_queue as usize,
+        job_timeout: std::time::Duration::from_secs_f64(args.job_timeout),
+        max_message: (args.max_message_size as usize) << 20,
+    };
+    let lowercase = |hosts: &[String]| hosts.iter().map(|host| host.trim().to_ascii_lowercase()).collect();
+    let root = args
+        .audio_root
+        .as_ref()
+        .map(|root| root.canonicalize().with_context(|| format!("No directory {}", root.display())))
+        .transpose()?;
+    let access = fourrier::grpc::AudioAccess { root, hosts: lowercase(&args.allow_audio_host) };
+    let callback_hosts = lowercase(&args.allow_callback);
+    fourrier::grpc::serve(args.listen, model, metrics, limits, args.on_job_done.clone(), callback_hosts, access)
+}
+
+#[cfg(not(feature = "grpc"))]
+fn grpc_command(_args: &GrpcArgs, _model: Option<&Path>, _config: &Config) -> Result<()> {
+    Err(anyhow!("`fourrier grpc` needs a build with `--features grpc`"))
+}
+
+fn models_command(action: &ModelsAction) -> Result<()> {
+    let file_name = |path: &Path| path.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned());
+    match action {
+        ModelsAction::List => {
+            let files = models::cached_files()?;
+            if files.is_empty() {
+                eprintln!("No cached models");
+            }
+            let mut total = 0;
+            for path in &files {
+                let size = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+                total += size;
+                let name = models::catalog_entry(path).map_or_else(|| file_name(path), |model| model.name.to_string());
+                let (format, checksum) = if path.extension().is_some_and(|extension| extension == "part") {
+                    ("incomplete".to_string(), "-".to_string())
+                } else {
+                    let format = ModelHeader::read(path).map_or("unreadable".to_string(), |header| {
+                        format!("{} {}", header.class.name(), header.format)
+                    });
+                    (format, models::recorded_checksum(path).unwrap_or_else(|| "-".to_string()))
+                };
+                println!(
+                    "{:<16} {:>8.1} MB  {:<12} {}",
+                    name,
+                    size as f64 / (1024.0 * 1024.0),
+                    format,
+                    checksum
+                );
+            }
+            if let Some(dir) = fourrier::config::models_dir() {
+                eprintln!("{:.1} MB in {}", total as f64 / (1024.0 * 1024.0), dir.display());
+            }
+        }
+        ModelsAction::Download { name } => {
+            let model = models::find(name).ok_or_else(|| {
+                let names: Vec<&str> = models::MODELS.iter().map(|model| model.name).collect();
+                anyhow!("Unknown model `{}`; the catalog has {}", name, names.join(", "))
+            })?;
+            eprintln!("Downloading {} (~{} MB)...", model.file_name(), model.size_mb);
+            let path = models::download(model)?;
+            eprintln!("Saved model to {}", path.display());
+        }
+        ModelsAction::Remove { name } => {
+            for file in models::remove(&models::cached_file(name)?)? {
+                eprintln!("Removed {}", file.display());
+            }
+        }
+        ModelsAction::Verify { name } => {
+            let files = match name {
+                Some(name) => vec![models::cached_file(name)?],
+                None => models::cached_files()?,
+            };
+            let mut failed = 0;
+            for path in &files {
+                let problems = models::verify(path)?;
+                if problems.is_empty() {
+                    println!("{}: ok", file_name(path));
+                } else {
+                    failed += 1;
+                    println!("{}: {}", file_name(path), problems.join("; "));
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow!(
+                    "{} of {} models failed; `fourrier models remove` and download them again",
+                    failed,
+                    files.len()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn bench_command(args: &BenchArgs, model: Option<&Path>, config: &Config) -> Result<()> {
+    let mut options = BenchOptions { runs: args.runs, clip: args.clip, ..Default::default() };
+    if !args.window_sizes.is_empty() {
+        options.window_sizes = args.window_sizes.clone();
+    }
+    if !args.threads.is_empty() {
+        options.threads = args.threads.clone();
+    }
+    if !args.no_transcription {
+        options.models = if args.models.is_empty() {
+            vec![models::resolve(model, config)?]
+        } else {
+            args.models
+                .iter()
+                .map(|name| match models::find(name) {
+                    Some(_) => models::cached_file(name),
+                    None => Ok(PathBuf::from(name)),
+                })
+                .collect::<Result<_>>()?
+        };
+    }
+    let measurements = fourrier::bench::run(&args.input, &options, |step| eprintln!("{}", step))?;
+    for line in fourrier::bench::report_lines(&measurements) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn record_command(args: &RecordArgs, theme: &Theme) -> Result<PathBuf> {
+    let device = if args.loopback { Some(capture::loopback_device()?) } else { args.device.clone() };
+    let mut outputs: Vec<Box<dyn LiveOutput>> = Vec::new();
+    if let Some(address) = &args.osc {
+        let target = address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or_else(|| anyhow!("`{}` is not a HOST:PORT address", address))?;
+        outputs.push(Box::new(OscSender::new(target)?));
+    }
+    if let Some(device) = &args.midi {
+        outputs.push(Box::new(MidiOut::open(device, args.midi_channel - 1)?));
+    }
+    let metrics = match args.metrics {
+        Some(address) => {
+            let metrics = Arc::new(Metrics::new());
+            fourrier::metrics::serve(address, metrics.clone())?;
+            eprintln!("Serving metrics on http://{}/metrics", address);
+            Some(metrics)
+        }
+        None => None,
+    };
+    // Timed recordings go to hourly files, except events, which have their own clips
+    let rotate = (args.schedule.is_some() || args.until.is_some()) && args.trigger.is_none();
+    let mut recorded = None;
+    loop {
+        let now = SystemTime::now();
+        let mut deadline = args.until;
+        if let Some(schedule) = args.schedule {
+            let (start, end) = schedule.window(now);
+            if args.until.is_some_and(|until| start >= until) {
+                break;
+            }
+            if start > now && !wait_for_window(args, start, &schedule, theme)? {
+                return recorded.ok_or_else(|| anyhow!("Stopped before the schedule's window opened; nothing recorded"));
+            }
+            deadline = Some(deadline.map_or(end, |until| until.min(end)));
+        }
+        let remaining = deadline.map(|deadline| deadline.duration_since(SystemTime::now()).unwrap_or_default().as_secs_f64());
+        let duration = match (args.duration, remaining) {
+            (Some(duration), Some(remaining)) => Some(duration.min(remaining)),
+            (duration, remaining) => duration.or(remaining),
+        };
+        if duration.is_some_and(|duration| duration <= 0.0) {
+            break;
+        }
+        let (output, completed) =
+            record_window(args, device.as_deref(), &mut outputs, duration, rotate, metrics.as_deref(), theme)?;
+        recorded = Some(output);
+        // Stopped by hand, or the device went away
+        if args.schedule.is_none() || !completed {
+            break;
+        }
+    }
+    recorded.ok_or_else(|| anyhow!("--until passes before the schedule's next window; nothing to record"))
+}
+
+/// Waits for the next window of `schedule`, which opens at `start`: on a countdown screen
+/// that can be stopped with q or Esc in a terminal, otherwise in steps of a second so a
+/// clock change or suspend doesn't oversleep. Returns false if the wait was stopped.
+fn wait_for_window(args: &RecordArgs, start: SystemTime, schedule: &Schedule, theme: &Theme) -> Result<bool> {
+    if std::io::stdout().is_terminal() {
+        let title = format!("Recording to {}", args.output.display());
+        return recording::wait(start, &title, &format!("{} daily", schedule), theme);
+    }
+    eprintln!("Waiting until {} to record ({} daily)...", schedule.start, schedule);
+    while let Ok(left) = start.duration_since(SystemTime::now()) {
+        std::thread::sleep(left.min(Duration::from_secs(1)));
+    }
+    Ok(true)
+}
+
+/// Records for `duration` seconds, or until stopped, to `args.output`: one WAV, a file for
+/// each hour on the clock when `rotate`, or with `--trigger` clips of the events. Returns
+/// the file or directory written and whether the whole `duration` was recorded. Levels and
+/// files go to `metrics` as they change.
+fn record_window(
+    args: &RecordArgs,
+    device: Option<&str>,
+    outputs: &mut [Box<dyn LiveOutput>],
+    duration: Option<f64>,
+    rotate: bool,
+    metrics: Option<&Metrics>,
+    theme: &Theme,
+) -> Result<(PathBuf, bool)> {
+    let recorder = Recorder::start(device, args.rate, args.channels)?;
+    let started = SystemTime::now();
+    let stop = AtomicBool::new(false);
+    let mut clips = match args.trigger {
+        Some(_) => Some(ClipDirectory::create(&args.output, recorder.sample_rate(), started)?),
+        None => None,
+    };
+    // Events or hourly files saved so far
+    let saved = AtomicUsize::new(0);
+    let quiet = std::io::stdout().is_terminal();
+    let completed = std::thread::scope(|scope| -> Result<bool> {
+        let streaming = (!outputs.is_empty()).then(|| {
+            let (recorder, stop) = (&recorder, &stop);
+            scope.spawn(move || live::stream(recorder.sample_rate(), |from| recorder.samples_since(from), outputs, stop))
+        });
+        let triggering = args.trigger.zip(clips.as_mut()).map(|(threshold, clips)| {
+            let settings = TriggerSettings {
+                threshold,
+                band: args.trigger_band,
+                pre_roll: args.pre_roll.max(0.0),
+                post_roll: args.post_roll.max(0.0),
+            };
+            let (recorder, stop, saved) = (&recorder, &stop, &saved);
+            scope.spawn(move || {
+                let audio = |from| recorder.samples_since(from);
+                let release = |before| recorder.discard_before(before);
+                trigger::watch(recorder.sample_rate(), &settings, audio, release, stop, |event| {
+                    let path = clips.save(event, &recorder.channels_between(event.start, event.end))?;
+                    saved.store(clips.saved(), Ordering::Relaxed);
+                    if let Some(metrics) = metrics {
+                        metrics.add("fourrier_files_saved_total", &[], 1.0);
+                    }
+                    if !quiet {
+                        eprintln!("Event at {:.1} dBFS saved to {}", event.peak, path.display());
+                    }
+                    Ok(())
+                })
+            })
+        });
+        let rotating = rotate.then(|| {
+            let (recorder, stop, saved) = (&recorder, &stop, &saved);
+            scope.spawn(move || {
+                rotate_hourly(recorder, &args.output, started, duration, stop, |path, seconds| {
+                    saved.fetch_add(1, Ordering::Relaxed);
+                    if let Some(metrics) = metrics {
+                        metrics.add("fourrier_files_saved_total", &[], 1.0);
+                    }
+                    if !quiet {
+                        eprintln!("Saved {:.1} s to {}", seconds, path.display());
+                    }
+                })
+            })
+        });
+        let metering = metrics.map(|metrics| {
+            let (recorder, stop) = (&recorder, &stop);
+            scope.spawn(move || {
+                let mut counted = 0.0;
+                loop {
+                    let elapsed = recorder.elapsed();
+                    metrics.add("fourrier_recorded_seconds_total", &[], elapsed - counted);
+                    counted = elapsed;
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    metrics.set_levels(&recorder.levels());
+                    // Several times per scrape at the shortest usual interval
+                    std::thread::sleep(std::time::Duration::from_millis(250));
+                }
+            })
+        });
+        let recorded = if std::io::stdout().is_terminal() {
+            let (title, note): (String, Box<dyn Fn() -> Option<String> + '_>) = match args.trigger {
+                Some(threshold) => (
+                    format!("Recording events over {:.0} dBFS to {}", threshold, args.output.display()),
+                    Box::new(|| Some(format!("{} events saved", saved.load(Ordering::Relaxed)))),
+                ),
+                None if rotate => (
+                    format!("Recording hourly files to {}", stamped_path(&args.output, "*").display()),
+                    Box::new(|| Some(format!("{} files saved, next at {}", saved.load(Ordering::Relaxed), next_hour_label()))),
+                ),
+                None => (format!("Recording to {}", args.output.display()), Box::new(|| None)),
+            };
+            recording::run(&recorder, duration, &title, note, theme)
+        } else {
+            match duration {
+                Some(duration) => {
+                    eprintln!("Recording {:.0} s to {}...", duration, args.output.display());
+                    while recorder.elapsed() < duration && recorder.is_running() {
+                        std::thread::sleep(std::time::Duration::from_millis(50));
+                    }
+                    Ok(())
+                }
+                None => Err(anyhow!("Recording without a terminal needs --duration or --until")),
+            }
+        };
+        let completed = duration.is_some_and(|duration| recorder.elapsed() >= duration);
+        stop.store(true, Ordering::Relaxed);
+        if let Some(streaming) = streaming {
+            streaming.join().unwrap_or_else(|_| Err(anyhow!("The live output thread panicked")))?;
+        }
+        if let Some(triggering) = triggering {
+            triggering.join().unwrap_or_else(|_| Err(anyhow!("The trigger thread panicked")))?;
+        }
+        if let Some(rotating) = rotating {
+            rotating.join().unwrap_or_else(|_| Err(anyhow!("The file rotation thread panicked")))?;
+        }
+        if let Some(metering) = metering {
+            let _ = metering.join();
+        }
+        recorded.map(|_| completed)
+    })?;
+    if let Some(clips) = clips {
+        eprintln!(
+            "Saved {} events from {:.1} s to {}",
+            clips.saved(),
+            recorder.elapsed(),
+            args.output.display()
+        );
+        return Ok((args.output.clone(), completed));
+    }
+    if rotate {
+        return Ok((args.output.clone(), completed));
+    }
+    let sample_rate = recorder.sample_rate();
+    let channels = recorder.stop(duration);
+    let slices: Vec<&[f32]> = channels.iter().map(Vec::as_slice).collect();
+    if slices.first().is_none_or(|samples| samples.is_empty()) {
+        return Err(anyhow!("Nothing was recorded; check the input device with `fourrier doctor`"));
+    }
+    write_wav(&args.output, &slices, sample_rate)?;
+    if let Some(metrics) = metrics {
+        metrics.add("fourrier_files_saved_total", &[], 1.0);
+    }
+    eprintln!(
+        "Saved {:.1} s to {}",
+        slices[0].len() as f64 / sample_rate as f64,
+        args.output.display()
+    );
+    Ok((args.output.clone(), completed))
+}
+
+/// `output` with `stamp` added to its name, e.g. `take-20240501-050000.wav`.
+fn stamped_path(output: &Path, stamp: &str) -> PathBuf {
+    let stem = output.file_stem().map_or("recording".into(), |stem| stem.to_string_lossy());
+    let extension = output.extension().map_or("wav".into(), |extension| extension.to_string_lossy());
+    output.with_file_name(format!("{}-{}.{}", stem, stamp, extension))
+}
+
+/// The next full hour on the local clock, as `HH:00`.
+fn next_hour_label() -> String {
+    let stamp = clock::local_file_stamp(clock::next_hour(SystemTime::now()));
+    format!("{}:00", &stamp[9..11])
+}
+
+/// Writes what `recorder` records to a file per hour on the clock, named after the local
+/// time its audio starts (see `stamped_path`), freeing each hour's audio once it's written.
+/// The last file ends when `stop` is set, cut to `duration` seconds from `started`.
+/// `saved(path, seconds)` is told of each file.
+fn rotate_hourly(
+    recorder: &Recorder,
+    output: &Path,
+    started: SystemTime,
+    duration: Option<f64>,
+    stop: &AtomicBool,
+    mut saved: impl FnMut(&Path, f64),
+) -> Result<()> {
+    let rate = recorder.sample_rate() as f64;
+    let index = |time: SystemTime| (time.duration_since(started).unwrap_or_default().as_secs_f64() * rate) as usize;
+    let mut write = |from: usize, to: usize, time: SystemTime| -> Result<()> {
+        let channels = recorder.channels_between(from, to);
+        let slices: Vec<&[f32]> = channels.iter().map(Vec::as_slice).collect();
+        if slices.first().is_none_or(|samples| samples.is_empty()) {
+            return Ok(());
+        }
+        let path = stamped_path(output, &clock::local_file_stamp(time));
+        write_wav(&path, &slices, recorder.sample_rate())?;
+        saved(&path, slices[0].len() as f64 / rate);
+        recorder.discard_before(to);
+        Ok(())
+    };
+    let (mut from, mut from_time) = (0, started);
+    loop {
+        let hour = clock::next_hour(from_time);
+        let to = index(hour);
+        while (recorder.elapsed() * rate) < to as f64 && !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+        }
+        if stop.load(Ordering::Relaxed) {
+            let end = duration.map_or(usize::MAX, |duration| (duration * rate) as usize);
+            return write(from, end, from_time);
+        }
+        write(from, to, from_time)?;
+        (from, from_time) = (to, hour);
+    }
+}
+
+fn captions_command(args: &CaptionsArgs, model: Option<&Path>, config: &Config, theme: &Theme) -> Result<()> {
+    let (model_path, fit) = models::fit_to_memory(models::resolve(model, config)?, model.is_some());
+    if let Some(message) = fit {
+        eprintln!("{}", message);
+    }
+    let device = if args.loopback { Some(capture::loopback_device()?) } else { args.device.clone() };
+    let recorder = Recorder::start(device.as_deref(), WHISPER_RATE as u32, 1)?;
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let (recorder, stop_flag, model_path) = (&recorder, &stop, &model_path);
+    let (captions, result) = std::thread::scope(|scope| {
+        let worker = scope.spawn(move || {
+            caption(model_path, |from| recorder.samples_since(from), stop_flag, |event| {
+                let _ = sender.send(event);
+            })
+        });
+        let captions = if std::io::stdout().is_terminal() {
+            let title = if args.loopback { "Captions: system audio" } else { "Captions" };
+            captions::run(&receiver, args.history, title, theme)
+        } else {
+            // One finished phrase per line, until interrupted
+            let mut captions = Vec::new();
+            for event in &receiver {
+                if let CaptionEvent::Final(segment) = event {
+                    println!("{}", segment.text.trim());
+                    captions.push(segment);
+                }
+            }
+            Ok(captions)
+        };
+        stop_flag.store(true, Ordering::Relaxed);
+        let result = worker.join().unwrap_or_else(|_| Err(anyhow!("The captioning thread panicked")));
+        (captions, result)
+    });
+    result?;
+    let captions = captions?;
+    if let Some(path) = &args.output {
+        save_transcript(path, &captions)?;
+        eprintln!("Saved {} captions to {}", captions.len(), path.display());
+    }
+    Ok(())
+}
+
+fn devices_command() -> Result<()> {
+    let devices = fourrier::audio::devices::list()?;
+    if devices.is_empty() {
+        eprintln!("No audio devices found");
+    }
+    for device in &devices {
+        let format = match (device.sample_rate, device.channels) {
+            (Some(rate), Some(channels)) => format!("{} Hz, {} ch", rate, channels),
+            (Some(rate), None) => format!("{} Hz", rate),
+            (None, Some(channels)) => format!("{} ch", channels),
+            (None, None) => "-".to_string(),
+        };
+        println!(
+            "{:<8} {:<48} {:<16} {}",
+            device.direction.name(),
+            device.name,
+            format,
+            device.description.as_deref().unwrap_or("")
+        );
+    }
+    Ok(())
+}
+
+fn doctor_command(model: Option<&Path>, config: Option<&Path>) -> Result<()> {
+    // A broken config is one of the things to report, not a reason to stop
+    let (config, config_check) = match Config::load_or_default(config) {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(format!("{:#}", e))),
+    };
+    let mut failed = 0;
+    if let Some(error) = config_check {
+        failed += 1;
+        println!("{:<5} {:<10} {}", "FAIL", "config", error);
+        println!("{:<16} fix: correct the file, or delete it to start over", "");
+    }
+    for check in fourrier::doctor::run(model, &config) {
+        println!("{:<5} {:<10} {}", check.status.label(), check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("{:<16} fix: {}", "", fix);
+        }
+        if check.status == fourrier::doctor::Status::Fail {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow!("{} check{} failed", failed, if failed == 1 { "" } else { "s" }));
+    }
+    Ok(())
+}
+
+fn psd_command(args: &PsdArgs) -> Result<()> {
+    eprintln!("Loading audio file...");
+    let audio_data = load_audio(&args.input)?;
+    let samples = &audio_data.samples;
+    let mut params = WelchParams {
+        overlap: args.overlap,
+        window: args.window,
+        confidence: args.confidence,
+        ..Default::default()
+    };
+    params.segment_size = match (args.segment_size, args.segments) {
+        (Some(size), _) => size,
+        (None, Some(count)) => WelchParams::segment_size_for(samples.len(), count, args.overlap),
+        (None, None) => params.segment_size,
+    };
+    let psd = welch(samples, audio_data.sample_rate, params)?;
+    let (lower, upper) = psd.interval();
+    eprintln!(
+        "{} segment{} of {} samples ({:.2} Hz bins), {:.0} degrees of freedom: {:.0}% interval {:+.1} / {:+.1} dB",
+        psd.segments,
+        if psd.segments == 1 { "" } else { "s" },
+        params.segment_size,
+        psd.resolution(),
+        psd.degrees_of_freedom,
+        params.confidence * 100.0,
+        density_db(lower),
+        density_db(upper),
+    );
+    let csv = psd_csv(&psd);
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, csv).with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => print!("{}", csv),
+    }
+    Ok(())
+}
+
+fn generate_command(args: &GenerateArgs) -> Result<()> {
+    if args.output.is_none() && !args.play {
+        return Err(anyhow!("Pass --output, --play or both"));
+    }
+    let params = SignalParams {
+        duration: args.duration,
+        sample_rate: args.rate,
+        level_db: args.level,
+        from: args.from,
+        to: args.to,
+        frequency: args.frequency,
+    };
+    let samples = generate(args.signal, &params)?;
+    if let Some(path) = &args.output {
+        write_wav(path, &[&samples], args.rate)?;
+        eprintln!("Wrote {}", path.display());
+    }
+    if args.play {
+        let mut player = Player::new(&samples.into(), args.rate).with_device(args.device.clone());
+        eprintln!("Playing {:.1} s...", args.duration);
+        player.play()?;
+        while player.is_playing() {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    }
+    Ok(())
+}
+
+fn measure_command(args: &MeasureArgs) -> Result<()> {
+    let base = SignalParams { sample_rate: args.rate, level_db: args.level, ..Default::default() };
+    let sweep_params = SignalParams { duration: 5.0, to: base.to.min(args.rate as f32 / 2.0), ..base };
+    let sweep = generate(Signal::Sweep, &sweep_params)?;
+    eprintln!("Playing a {:.0}-{:.0} Hz sweep...", sweep_params.from, sweep_params.to);
+    let recorded = play_and_record(&sweep, args.rate, args.device.as_deref(), args.output_device.clone())?;
+    let response = frequency_response(&impulse_response(&recorded, Some(&sweep), args.rate), args.rate);
+
+    let tone = generate(Signal::Tone, &SignalParams { duration: 4.0, frequency: args.frequency, ..base })?;
+    eprintln!("Playing a {:.0} Hz tone...", args.frequency);
+    let recorded = play_and_record(&tone, args.rate, args.device.as_deref(), args.output_device.clone())?;
+    let thd = distortion(&recorded, args.rate, args.frequency)?;
+
+    println!("Frequency response (dB re 1 kHz):");
+    for (frequency, level) in &response {
+        println!("  {:>7.0} Hz  {:+6.1}", frequency, level);
+    }
+    for line in measurement::report_lines(&thd) {
+        println!("{}", line);
+    }
+    if let Some(path) = &args.output {
+        std::fs::write(path, response_csv(&response)).with_context(|| format!("Failed to write {}", path.display()))?;
+        eprintln!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn decode_command(args: &DecodeArgs) -> Result<()> {
+    eprintln!("Loading audio file...");
+    let audio_data = load_audio(&args.input)?;
+    let detections = decode(&audio_data.samples, audio_data.sample_rate, args.mode);
+    for line in decode::report_lines(&detections) {
+        println!("{}", line);
+    }
+    if let Some(path) = &args.output {
+        save_detections(path, &detections)?;
+        eprintln!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn acoustics_command(args: &AcousticsArgs, theme: &Theme) -> Result<()> {
+    eprintln!("Loading audio file...");
+    let audio_data = load_audio(&args.input)?;
+    let sweep = match &args.sweep {
+        Some(path) => {
+            let sweep = load_audio(path)?;
+            if sweep.sample_rate != audio_data.sample_rate {
+                return Err(anyhow!(
+                    "The sweep is at {} Hz and the recording at {} Hz; they must match",
+                    sweep.sample_rate,
+                    audio_data.sample_rate
+                ));
+            }
+            Some(sweep.samples)
+        }
+        None => None,
+    };
+    let ir = impulse_response(&audio_data.samples, sweep.as_deref(), audio_data.sample_rate);
+    if let Some(path) = &args.ir {
+        write_wav(path, &[&ir], audio_data.sample_rate)?;
+        eprintln!("Wrote {}", path.display());
+    }
+    let decays = room::analyze(&ir, audio_data.sample_rate);
+    if let Some(path) = &args.output {
+        std::fs::write(path, decay_csv(&decays)).with_context(|| format!("Failed to write {}", path.display()))?;
+        eprintln!("Wrote {}", path.display());
+    }
+    let lines = room::report_lines(&decays);
+    if std::io::stdout().is_terminal() {
+        let title = format!("Room acoustics of {}", args.input.display());
+        acoustics::run(&decays, &lines, &title, theme)?;
+    }
+    for line in &lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// `<input stem>.<extension>` next to a local input, or in the working directory for stdin
+/// and URLs.
+fn default_output_path(input: &Path, extension: &str) -> PathBuf {
+    let local = input.exists();
+    let stem = input
+        .file_stem()
+        .filter(|_| local || input.to_str().is_some_and(|s| s.contains("://")))
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "transcript".to_string());
+    let name = format!("{}.{}", stem, extension);
+    match input.parent().filter(|_| local) {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+fn dump_size() -> (u16, u16) {
+    if std::io::stdout().is_terminal() {
+        if let Ok(size) = crossterm::terminal::size() {
+            return size;
+        }
+    }
+    let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok()).filter(|&v| v > 0);
+    (var("COLUMNS").unwrap_or(120), var("LINES").unwrap_or(40))
+}
+
+fn value_name<T: ValueEnum>(value: &T) -> Value {
+    value.to_possible_value().map_or(Value::String(String::new()), |v| v.get_name().into())
+}
+
+/// Parameters stored in a `--session` file. The preset is stored as resolved, so a restored
+/// auto-detected session doesn't depend on detection again.
+fn session_params(cli: &Cli, spectrogram: &SpectrogramParams, preset: Option<&Preset>) -> Table {
+    let mut params = Table::new();
+    let inputs = cli.input.iter().map(|input| input.display().to_string().as_str().into()).collect();
+    params.insert("input".into(), Value::Array(inputs));
+    params.insert("window_size".into(), (spectrogram.window_size as i64).into());
+    params.insert("window_function".into(), value_name(&spectrogram.window));
+    params.insert("hop".into(), (spectrogram.hop_size as i64).into());
+    params.insert("precision".into(), value_name(&spectrogram.precision));
+    params.insert("units".into(), value_name(&spectrogram.units));
+    params.insert("normalization".into(), value_name(&spectrogram.normalization));
+    if spectrogram.calibration != 0.0 {
+        params.insert("calibration".into(), (spectrogram.calibration as f64).into());
+    }
+    let preset = preset.map_or("none".to_string(), |p| p.content.to_string());
+    params.insert("preset".into(), preset.as_str().into());
+    if let Some(mode) = &cli.iq {
+        params.insert("iq".into(), value_name(mode));
+    }
+    if cli.mid_side {
+        params.insert("mid_side".into(), true.into());
+    }
+    if let Some(chain) = &cli.dsp {
+        params.insert("dsp".into(), chain.as_str().into());
+    }
+    if cli.raw {
+        params.insert("raw.rate".into(), (cli.rate as i64).into());
+        params.insert("raw.channels".into(), (cli.channels as i64).into());
+        params.insert("raw.sample_format".into(), value_name(&cli.sample_format));
+    }
+    params
+}
+
+/// Fills in options not given on the command line from a session file.
+fn apply_session_params(cli: &mut Cli, params: &Table) -> Result<()> {
+    let text = |key: &str| params.get(key).and_then(|v| v.as_str());
+    let integer = |key: &str| params.get(key).and_then(|v| v.as_integer());
+    if cli.input.is_empty() {
+        // Every track of a multi-track session; sessions from before those hold one path
+        cli.input = match params.get("input") {
+            Some(Value::Array(inputs)) => inputs.iter().filter_map(Value::as_str).map(PathBuf::from).collect(),
+            _ => text("input").map(PathBuf::from).into_iter().collect(),
+        };
+    }
+    if let (None, Some(value)) = (cli.window_size, params.get("window_size")) {
+        let size = value.as_integer().and_then(fourrier::config::window_size);
+        cli.window_size = Some(size.ok_or_else(|| anyhow!("window_size must be a power of two of at least 2, not {}", value))?);
+    }
+    if cli.window_function.is_none() {
+        cli.window_function = text("window_function").and_then(|v| WindowFunction::from_str(v, true).ok());
+    }
+    if cli.hop.is_none() {
+        cli.hop = integer("hop").map(|v| v as usize);
+    }
+    if cli.precision.is_none() {
+        cli.precision = text("precision").and_then(|v| Precision::from_str(v, true).ok());
+    }
+    if cli.units.is_none() {
+        cli.units = text("units").and_then(|v| Units::from_str(v, true).ok());
+    }
+    if cli.normalization.is_none() {
+        cli.normalization = text("normalization").and_then(|v| Normalization::from_str(v, true).ok());
+    }
+    if cli.calibration.is_none() {
+        cli.calibration = params.get("calibration").and_then(|v| v.as_float()).map(|v| v as f32);
+    }
+    if cli.preset.is_none() {
+        cli.preset = text("preset").and_then(|v| PresetChoice::from_str(v, true).ok());
+    }
+    if cli.iq.is_none() {
+        cli.iq = text("iq").and_then(|v| Demodulation::from_str(v, true).ok());
+    }
+    if cli.dsp.is_none() {
+        cli.dsp = text("dsp").map(String::from);
+    }
+    cli.mid_side |= params.get("mid_side").and_then(|v| v.as_bool()).unwrap_or(false);
+    if !cli.raw && params.contains_key("raw.rate") {
+        cli.raw = true;
+        cli.rate = integer("raw.rate").map_or(cli.rate, |v| v as u32);
+        cli.channels = integer("raw.channels").map_or(cli.channels, |v| v as usize);
+        if let Some(format) = text("raw.sample_format").and_then(|v| SampleFormat::from_str(v, true).ok()) {
+            cli.sample_format = format;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a scrape may take to send its request, all told, before the connection is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most bytes of request line and headers read from a scrape; the rest is ignored.
+const MAX_REQUEST: u64 = 8192;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+}
+
+struct Family {
+    kind: Kind,
+    help: &'static str,
+    /// Value of each series, by its rendered labels (empty for none)
+    series: BTreeMap<String, f64>,
+}
+
+/// Counters and gauges of a long-running server or recorder, exposed in the Prometheus
+/// text format by `serve`.
+pub struct Metrics {
+    started: Instant,
+    families: Mutex<BTreeMap<&'static str, Family>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let metrics = Self { started: Instant::now(), families: Mutex::new(BTreeMap::new()) };
+        metrics.describe("fourrier_jobs_total", Kind::Counter, "Files or streamed windows processed, by RPC and outcome");
+        metrics.describe("fourrier_audio_seconds_total", Kind::Counter, "Seconds of audio processed, by RPC");
+        metrics.describe("fourrier_processing_seconds_total", Kind::Counter, "Seconds spent processing, by RPC");
+        metrics.describe(
+            "fourrier_real_time_factor",
+            Kind::Gauge,
+            "Processing time over audio length of the last job, by RPC; under 1 is faster than real time",
+        );
+        metrics.describe("fourrier_queue_depth", Kind::Gauge, "Requests and jobs waiting or running");
+        metrics.set("fourrier_queue_depth", &[], 0.0);
+        metrics.describe("fourrier_audio_level_dbfs", Kind::Gauge, "Live level over the last 100 ms, by channel and RMS or peak");
+        metrics.describe("fourrier_recorded_seconds_total", Kind::Counter, "Seconds of audio recorded");
+        metrics.describe("fourrier_files_saved_total", Kind::Counter, "Recordings, hourly files and event clips saved");
+        metrics
+    }
+
+    fn describe(&self, name: &'static str, kind: Kind, help: &'static str) {
+        let mut families = self.families.lock().expect("metrics are never poisoned");
+        families.insert(name, Family { kind, help, series: BTreeMap::new() });
+    }
+
+    fn update(&self, name: &str, labels: &[(&str, &str)], change: impl FnOnce(&mut f64)) {
+        let mut families = self.families.lock().expect("metrics are never poisoned");
+        if let Some(family) = families.get_mut(name) {
+            change(family.series.entry(render_labels(labels)).or_insert(0.0));
+        }
+    }
+
+    /// Adds `amount` to a counter, or to a gauge when it goes down as well as up.
+    pub fn add(&self, name: &str, labels: &[(&str, &str)], amount: f64) {
+        self.update(name, labels, |value| *value += amount);
+    }
+
+    /// Sets a gauge.
+    pub fn set(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, labels, |current| *current = value);
+    }
+
+    /// Counts a job in the queue depth until the guard returned is dropped.
+    pub fn queued(&self) -> Queued<'_> {
+        self.add("fourrier_queue_depth", &[], 1.0);
+        Queued { metrics: self }
+    }
+
+    /// Records a job of `rpc` over `audio_seconds` of audio that took `elapsed`.
+    pub fn finished(&self, rpc: &str, audio_seconds: f64, elapsed: Duration, succeeded: bool) {
+        let outcome = if succeeded { "ok" } else { "error" };
+        self.add("fourrier_jobs_total", &[("rpc", rpc), ("outcome", outcome)], 1.0);
+        if !succeeded {
+            return;
+        }
+        self.add("fourrier_audio_seconds_total", &[("rpc", rpc)], audio_seconds);
+        self.add("fourrier_processing_seconds_total", &[("rpc", rpc)], elapsed.as_secs_f64());
+        if audio_seconds > 0.0 {
+            self.set("fourrier_real_time_factor", &[("rpc", rpc)], elapsed.as_secs_f64() / audio_seconds);
+        }
+    }
+
+    /// Sets the live RMS and peak level of each channel, in dBFS, as `Recorder::levels`
+    /// gives them.
+    pub fn set_levels(&self, levels: &[(f32, f32)]) {
+        for (channel, &(rms, peak)) in levels.iter().enumerate() {
+            let channel = (channel + 1).to_string();
+            // Silence is -inf, which Prometheus takes but graphs poorly
+            self.set("fourrier_audio_level_dbfs", &[("channel", &channel), ("kind", "rms")], rms.max(-200.0) as f64);
+            self.set("fourrier_audio_level_dbfs", &[("channel", &channel), ("kind", "peak")], peak.max(-200.0) as f64);
+        }
+    }
+
+    /// All series in the Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        text.push_str("# HELP fourrier_uptime_seconds Seconds since the process started\n");
+        text.push_str("# TYPE fourrier_uptime_seconds gauge\n");
+        text.push_str(&format!("fourrier_uptime_seconds {}\n", self.started.elapsed().as_secs_f64()));
+        let families = self.families.lock().expect("metrics are never poisoned");
+        for (name, family) in families.iter() {
+            let kind = if family.kind == Kind::Counter { "counter" } else { "gauge" };
+            text.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, family.help, name, kind));
+            for (labels, value) in &family.series {
+                text.push_str(&format!("{}{} {}\n", name, labels, value));
+            }
+        }
+        text
+    }
+}
+
+/// A job counted in the queue depth while it lives.
+pub struct Queued<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.metrics.add("fourrier_queue_depth", &[], -1.0);
+    }
+}
+
+/// `{name="value",...}`, or nothing without labels.
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{}=\"{}\"", name, escaped)
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Answers `GET /metrics` on `address` with `metrics` from a thread of its own, for
+/// Prometheus to scrape, until the process exits.
+pub fn serve(address: SocketAddr, metrics: Arc<Metrics>) -> Result<()> {
+    let listener = TcpListener::bind(address).with_context(|| format!("Failed to listen on {} for metrics", address))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // Each connection on its own thread, so a slow client only holds up its own scrape
+            let metrics = Arc::clone(&metrics);
+            std::thread::spawn(move || {
+                let _ = respond(stream, &metrics);
+            });
+        }
+    });
+    Ok(())
+}
+
+/// Reads from a connection until `until`, however slowly the bytes trickle in.
+struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.until.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "Request took too long"));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buffer)
+    }
+}
+
+fn respond(stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let deadline = Deadline { stream: stream.try_clone()?, until: Instant::now() + REQUEST_TIMEOUT };
+    let mut reader = BufReader::new(deadline.take(MAX_REQUEST));
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // The headers, unused
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or("");
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") => ("200 OK", "text/plain; version=0.0.4", metrics.render()),
+        ("GET", _) => ("404 Not Found", "text/plain", "Metrics are at /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,113 @@
+use anyhow::{anyhow, Context, Result};
+use std::io::Read;
+use std::path::Path;
+
+/// "ggml" as the little-endian magic number whisper.cpp model files start with.
+const MAGIC: u32 = 0x6767_6d6c;
+
+/// whisper.cpp stores the quantization format version in `ftype`, times this.
+const QNT_VERSION_FACTOR: i32 = 1000;
+
+const MB: u64 = 1024 * 1024;
+
+/// Whisper architecture size, told apart by encoder layer count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelClass {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    Large,
+}
+
+impl ModelClass {
+    fn from_layers(layers: i32) -> Self {
+        match layers {
+            ..=4 => ModelClass::Tiny,
+            5..=6 => ModelClass::Base,
+            7..=12 => ModelClass::Small,
+            13..=24 => ModelClass::Medium,
+            _ => ModelClass::Large,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ModelClass::Tiny => "tiny",
+            ModelClass::Base => "base",
+            ModelClass::Small => "small",
+            ModelClass::Medium => "medium",
+            ModelClass::Large => "large",
+        }
+    }
+
+    /// Memory of one Whisper state besides the weights: whisper.cpp's scratch buffers, key/value
+    /// caches and encode/decode buffers for the size.
+    fn state_memory(self) -> u64 {
+        MB * match self {
+            ModelClass::Tiny => 133,
+            ModelClass::Base => 179,
+            ModelClass::Small => 303,
+            ModelClass::Medium => 496,
+            ModelClass::Large => 703,
+        }
+    }
+}
+
+/// What a model file's header says about it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelHeader {
+    pub class: ModelClass,
+    /// Weight format, e.g. `f16` or `q5_1`
+    pub format: &'static str,
+    /// Multilingual models have a larger vocabulary than the `.en` ones
+    pub multilingual: bool,
+    /// Size of the file, which is about what the weights take in memory
+    pub file_size: u64,
+}
+
+impl ModelHeader {
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let file_size = file.metadata()?.len();
+        // Magic, then n_vocab, n_audio_ctx, n_audio_state, n_audio_head, n_audio_layer,
+        // n_text_ctx, n_text_state, n_text_head, n_text_layer, n_mels and ftype
+        let mut bytes = [0u8; 48];
+        file.read_exact(&mut bytes)
+            .map_err(|_| anyhow!("{} is too short to be a Whisper model", path.display()))?;
+        let field = |i: usize| i32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().expect("four bytes"));
+        if field(0) as u32 != MAGIC {
+            return Err(anyhow!("{} is not a ggml Whisper model", path.display()));
+        }
+        let format = match field(11) % QNT_VERSION_FACTOR {
+            0 => "f32",
+            1 => "f16",
+            2 => "q4_0",
+            3 => "q4_1",
+            7 => "q8_0",
+            8 => "q5_0",
+            9 => "q5_1",
+            other => return Err(anyhow!("{} has an unsupported weight format ({})", path.display(), other)),
+        };
+        Ok(Self {
+            class: ModelClass::from_layers(field(5)),
+            format,
+            multilingual: field(1) >= 51865,
+            file_size,
+        })
+    }
+
+    pub fn quantized(&self) -> bool {
+        self.format.starts_with('q')
+    }
+
+    /// Memory a transcription needs: the weights plus one state.
+    pub fn memory_needed(&self) -> u64 {
+        self.file_size + self.class.state_memory()
+    }
+
+    /// Memory each additional parallel state needs.
+    pub fn state_memory(&self) -> u64 {
+        self.class.state_memory()
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{self, Config};
+
+pub mod ggml;
+
+use ggml::ModelHeader;
+
+const BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// The repository's Git LFS pointers, which carry each file's SHA-256.
+const POINTER_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/raw/main";
+
+/// Model used when nothing else is configured.
+pub const DEFAULT_MODEL: &str = "base";
+
+/// Legacy location: a model next to the working directory, as in the quickstart.
+const LOCAL_MODEL: &str = "whisper-base.bin";
+
+pub struct ModelInfo {
+    pub name: &'static str,
+    pub size_mb: u32,
+}
+
+pub const MODELS: &[ModelInfo] = &[
+    ModelInfo { name: "tiny", size_mb: 75 },
+    ModelInfo { name: "tiny.en", size_mb: 75 },
+    ModelInfo { name: "base", size_mb: 142 },
+    ModelInfo { name: "base.en", size_mb: 142 },
+    ModelInfo { name: "small", size_mb: 466 },
+    ModelInfo { name: "small.en", size_mb: 466 },
+    ModelInfo { name: "medium", size_mb: 1500 },
+    ModelInfo { name: "large-v3", size_mb: 2900 },
+    // Quantized weights: a third of the size or less, at a small cost in accuracy
+    ModelInfo { name: "tiny-q5_1", size_mb: 31 },
+    ModelInfo { name: "tiny.en-q5_1", size_mb: 31 },
+    ModelInfo { name: "base-q5_1", size_mb: 57 },
+    ModelInfo { name: "base.en-q5_1", size_mb: 57 },
+    ModelInfo { name: "small-q5_1", size_mb: 181 },
+    ModelInfo { name: "small.en-q5_1", size_mb: 181 },
+    ModelInfo { name: "medium-q5_0", size_mb: 514 },
+    ModelInfo { name: "large-v3-q5_0", size_mb: 1080 },
+];
+
+pub fn find(name: &str) -> Option<&'static ModelInfo> {
+    MODELS.iter().find(|m| m.name == name)
+}
+
+impl ModelInfo {
+    pub fn file_name(&self) -> String {
+        format!("ggml-{}.bin", self.name)
+    }
+
+    pub fn url(&self) -> String {
+        format!("{}/{}", BASE_URL, self.file_name())
+    }
+
+    /// The SHA-256 Hugging Face publishes for the model, read from its Git LFS pointer.
+    pub fn upstream_checksum(&self) -> Result<String> {
+        let url = format!("{}/{}", POINTER_URL, self.file_name());
+        let output = Command::new("curl")
+            .args(["-fsSL", "--max-time", "30", &url])
+            .output()
+            .context("Failed to run curl")?;
+        if !output.status.success() {
+            return Err(anyhow!("Fetching {} failed: {}", url, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        let pointer = String::from_utf8_lossy(&output.stdout);
+        pointer
+            .lines()
+            .find_map(|line| line.strip_prefix("oid sha256:"))
+            .map(|hex| hex.trim().to_ascii_lowercase())
+            .filter(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| anyhow!("{} has no SHA-256", url))
+    }
+
+    /// Where the model lives in the per-user cache.
+    pub fn cached_path(&self) -> Option<PathBuf> {
+        config::models_dir().map(|dir| dir.join(self.file_name()))
+    }
+}
+
+/// Picks the model file: explicit flag, then config, then `./whisper-base.bin`, then the
+/// default model in the per-user cache.
+pub fn resolve(explicit: Option<&Path>, config: &Config) -> Result<PathBuf> {
+    if let Some(path) = explicit.or(config.model.as_deref()) {
+        return Ok(path.to_path_buf());
+    }
+    let local = PathBuf::from(LOCAL_MODEL);
+    if local.exists() {
+        return Ok(local);
+    }
+    let cached = find(DEFAULT_MODEL).and_then(ModelInfo::cached_path);
+    match cached {
+        Some(path) if path.exists() => Ok(path),
+        _ => Err(anyhow!(
+            "No Whisper model found. Pass --model, set `model` in {}, or run again interactively to download one",
+            config::default_config_path().map_or("the config file".to_string(), |p| p.display().to_string())
+        )),
+    }
+}
+
+/// Downloads a model into the per-user cache with curl and returns its path. The file is
+/// checked against the SHA-256 Hugging Face publishes, which is recorded for `verify`; if
+/// that can't be fetched the model is kept with no checksum, with a warning.
+pub fn download(model: &ModelInfo) -> Result<PathBuf> {
+    let dest = model
+        .cached_path()
+        .ok_or_else(|| anyhow!("Could not determine a data directory for models"))?;
+    if let Some(dir) = dest.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let expected = model
+        .upstream_checksum()
+        .map_err(|e| eprintln!("Warning: can't check the download: {:#}", e))
+        .ok();
+    let partial = dest.with_extension("bin.part");
+    let status = Command::new("curl")
+        .args(["-fL", "--progress-bar", "-o"])
+        .arg(&partial)
+        .arg(model.url())
+        .status()
+        .context("Failed to run curl")?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&partial);
+        return Err(anyhow!("Downloading {} failed", model.url()));
+    }
+    if let Some(expected) = &expected {
+        let actual = sha256_file(&partial)?;
+        if &actual != expected {
+            let _ = std::fs::remove_file(&partial);
+            return Err(anyhow!("{} has SHA-256 {}, but Hugging Face publishes {}", model.url(), actual, expected));
+        }
+    }
+    std::fs::rename(&partial, &dest)?;
+    let _ = std::fs::remove_file(checksum_path(&dest));
+    if let Some(expected) = &expected {
+        record_checksum(&dest, expected)?;
+    }
+    Ok(dest)
+}
+
+/// SHA-256 of a file's contents, in lowercase hex.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Sidecar holding the published checksum of a cached model, for `verify` to compare
+/// against.
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sha256");
+    path.with_file_name(name)
+}
+
+/// The checksum recorded for `path`, if any.
+pub fn recorded_checksum(path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(checksum_path(path)).ok()?;
+    text.split_whitespace().next().map(str::to_string)
+}
+
+/// Records `checksum` for `path` next to the file, in `sha256sum` format.
+fn record_checksum(path: &Path, checksum: &str) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let sidecar = checksum_path(path);
+    std::fs::write(&sidecar, format!("{}  {}\n", checksum, name))
+        .with_context(|| format!("Failed to write {}", sidecar.display()))
+}
+
+/// Model files in the per-user cache, including interrupted downloads (`.bin.part`),
+/// sorted by name.
+pub fn cached_files() -> Result<Vec<PathBuf>> {
+    let Some(dir) = config::models_dir().filter(|dir| dir.exists()) else {
+        return Ok(Vec::new());
+    };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.ends_with(".bin") || name.ends_with(".bin.part")
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The catalog entry a cached file was downloaded as, by file name.
+pub fn catalog_entry(path: &Path) -> Option<&'static ModelInfo> {
+    let name = path.file_name()?.to_str()?;
+    MODELS.iter().find(|model| model.file_name() == name)
+}
+
+/// A cached model by catalog name (`base`) or file name (`ggml-base.bin`).
+pub fn cached_file(name: &str) -> Result<PathBuf> {
+    let dir = config::models_dir().ok_or_else(|| anyhow!("Could not determine a data directory for models"))?;
+    let path = match find(name) {
+        Some(model) => dir.join(model.file_name()),
+        None => dir.join(name),
+    };
+    if path.exists() && path.parent() == Some(dir.as_path()) {
+        Ok(path)
+    } else {
+        Err(anyhow!("No cached model `{}` in {}", name, dir.display()))
+    }
+}
+
+/// Deletes a cached model with its checksum and any interrupted download of it; returns
+/// the files removed.
+pub fn remove(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for file in [path.to_path_buf(), checksum_path(path), path.with_extension("bin.part")] {
+        if file.exists() {
+            std::fs::remove_file(&file).with_context(|| format!("Failed to remove {}", file.display()))?;
+            removed.push(file);
+        }
+    }
+    Ok(removed)
+}
+
+/// Checks a cached model: that it is a complete download, that its header parses, that
+/// its size is close to the catalog's and that it matches the checksum Hugging Face
+/// publishes. That is the one recorded at download, or for catalog models downloaded
+/// without one, fetched now and recorded if the file matches. Returns the problems found,
+/// none if it is fine.
+pub fn verify(path: &Path) -> Result<Vec<String>> {
+    if path.extension().is_some_and(|extension| extension == "part") {
+        return Ok(vec!["incomplete download".to_string()]);
+    }
+    let mut problems = Vec::new();
+    match ModelHeader::read(path) {
+        Ok(header) => {
+            let catalog = catalog_entry(path).map(|model| model.size_mb as f64);
+            let size = header.file_size as f64 / (1024.0 * 1024.0);
+            if let Some(expected) = catalog.filter(|expected| (size - expected).abs() > expected * 0.1) {
+                problems.push(format!("{:.0} MB, but the catalog has about {:.0} MB", size, expected));
+            }
+        }
+        Err(e) => problems.push(e.to_string()),
+    }
+    let recorded = recorded_checksum(path);
+    let expected = recorded.clone().or_else(|| catalog_entry(path)?.upstream_checksum().ok());
+    match expected {
+        Some(expected) => {
+            let actual = sha256_file(path)?;
+            if actual != expected {
+                problems.push(format!("checksum {} doesn't match the published {}", actual, expected));
+            } else if recorded.is_none() {
+                record_checksum(path, &expected)?;
+            }
+        }
+        None => problems.push("no checksum recorded".to_string()),
+    }
+    Ok(problems)
+}
+
+/// Memory available to new allocations without swapping, in bytes: `MemAvailable` from
+/// `/proc/meminfo`. `None` where that isn't available.
+pub fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// How many Whisper states of `model` fit in memory next to the loaded model, at least one.
+/// `None` when the free memory or the model's size is unknown.
+pub fn parallel_limit(model: &Path) -> Option<usize> {
+    let header = ModelHeader::read(model).ok()?;
+    let available = available_memory()?;
+    Some((available.saturating_sub(header.file_size) / header.state_memory()).max(1) as usize)
+}
+
+/// Checks that `model` fits in free memory. When it doesn't and `fixed` is false (the model
+/// wasn't asked for by path), switches to the cached model that needs the most memory that
+/// still fits, typically a quantized variant or the next size down. Returns the model to
+/// use, and a message when it is a different one or won't fit.
+pub fn fit_to_memory(model: PathBuf, fixed: bool) -> (PathBuf, Option<String>) {
+    let (Some(available), Ok(header)) = (available_memory(), ModelHeader::read(&model)) else {
+        return (model, None);
+    };
+    let needed = header.memory_needed();
+    if needed <= available {
+        return (model, None);
+    }
+    let name = model.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned());
+    let fitting = MODELS
+        .iter()
+        .filter_map(|info| info.cached_path().filter(|path| path.exists()))
+        .filter_map(|path| Some((ModelHeader::read(&path).ok()?.memory_needed(), path)))
+        .filter(|&(memory, _)| memory <= available)
+        .max_by_key(|&(memory, _)| memory);
+    match fitting {
+        Some((_, smaller)) if !fixed => {
+            let message = format!(
+                "{} needs about {} MB but {} MB is free; using {} instead",
+                name,
+                needed / (1024 * 1024),
+                available / (1024 * 1024),
+                smaller.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned())
+            );
+            (smaller, Some(message))
+        }
+        _ => {
+            let message = format!(
+                "Warning: {} needs about {} MB but only {} MB is free; transcription may swap or fail (quantized models such as base-q5_1 need a third as much)",
+                name,
+                needed / (1024 * 1024),
+                available / (1024 * 1024)
+            );
+            (model, Some(message))
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use clap::ValueEnum;
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::fmt;
+
+use crate::audio::filter::Biquad;
+use crate::audio::AudioData;
+
+/// Only the start of long files is classified; it's enough to tell content types apart.
+const MAX_ANALYSIS_SECONDS: usize = 60;
+
+/// Where hearing ends and ultrasound begins, in Hz.
+const ULTRASONIC: f32 = 20000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ContentType {
+    Speech,
+    Music,
+    Environmental,
+    /// Bird song and other high-pitched animal calls
+    Birdsong,
+    /// Bat echolocation and other sound above hearing, in recordings at 192 kHz or more
+    Ultrasonic,
+    /// Infrasound and machinery vibration, from a fraction of a hertz up
+    Infrasound,
+    /// Heart and lung sounds from a (digital) stethoscope
+    Auscultation,
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ContentType::Speech => "speech",
+            ContentType::Music => "music",
+            ContentType::Environmental => "environmental",
+            ContentType::Birdsong => "birdsong",
+            ContentType::Ultrasonic => "ultrasonic",
+            ContentType::Infrasound => "infrasound",
+            ContentType::Auscultation => "auscultation",
+        })
+    }
+}
+
+/// `--preset` choices: detect from the audio, force a content type, or keep the plain defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PresetChoice {
+    Auto,
+    Speech,
+    Music,
+    Environmental,
+    Birdsong,
+    Ultrasonic,
+    Infrasound,
+    Auscultation,
+    None,
+}
+
+/// Analysis settings suited to a content type.
+#[derive(Clone, Copy, Debug)]
+pub struct Preset {
+    pub content: ContentType,
+    pub window_size: usize,
+    /// Lowest frequency of interest: event detection leaves out energy below it
+    pub min_frequency: f32,
+    /// Highest frequency shown in the spectrogram
+    pub max_frequency: f32,
+    /// Rate the spectrogram is computed at, decimating down to it; the file's own when
+    /// `None`
+    pub analysis_rate: Option<u32>,
+    pub transcribe: bool,
+}
+
+impl Preset {
+    pub fn for_content(content: ContentType) -> Self {
+        match content {
+            // Short windows follow syllables; formants sit below ~4 kHz
+            ContentType::Speech => Self {
+                content,
+                window_size: 512,
+                min_frequency: 80.0,
+                max_frequency: 4000.0,
+                analysis_rate: None,
+                transcribe: true,
+            },
+            // Long windows resolve individual notes
+            ContentType::Music => Self {
+                content,
+                window_size: 4096,
+                min_frequency: 20.0,
+                max_frequency: 5000.0,
+                analysis_rate: None,
+                transcribe: false,
+            },
+            ContentType::Environmental => Self {
+                content,
+                window_size: 1024,
+                min_frequency: 20.0,
+                max_frequency: 12000.0,
+                analysis_rate: None,
+                transcribe: false,
+            },
+            // Calls are fast and high: short windows follow trills, and wind and traffic
+            // rumble sit below the band
+            ContentType::Birdsong => Self {
+                content,
+                window_size: 256,
+                min_frequency: 1000.0,
+                max_frequency: 16000.0,
+                analysis_rate: None,
+                transcribe: false,
+            },
+            // Bat calls sweep from over 100 kHz down to about 15 kHz in a few milliseconds:
+            // 1024 samples are 2.7 ms at 384 kHz. The range stops at the file's Nyquist
+            // frequency, and nothing there is for Whisper.
+            ContentType::Ultrasonic => Self {
+                content,
+                window_size: 1024,
+                min_frequency: 15000.0,
+                max_frequency: 192000.0,
+                analysis_rate: None,
+                transcribe: false,
+            },
+            // Decimated to 1 kHz, 65536-sample windows span 65 s and resolve 0.015 Hz: the
+            // full rate would need millions of samples per window for the same
+            ContentType::Infrasound => Self {
+                content,
+                window_size: 65536,
+                min_frequency: 0.1,
+                max_frequency: 100.0,
+                analysis_rate: Some(1000),
+                transcribe: false,
+            },
+            // Heart sounds lie at 20-400 Hz and lung sounds up to 2 kHz; decimated to 4 kHz,
+            // 1024 samples are a quarter of a second, resolving 4 Hz
+            ContentType::Auscultation => Self {
+                content,
+                window_size: 1024,
+                min_frequency: 20.0,
+                max_frequency: 2000.0,
+                analysis_rate: Some(4000),
+                transcribe: false,
+            },
+        }
+    }
+}
+
+/// The features behind a classification, shown so the decision can be judged.
+#[derive(Clone, Copy, Debug)]
+pub struct Classification {
+    pub content: ContentType,
+    /// Fraction of 20 ms frames quieter than half the mean frame energy (pauses between words)
+    pub low_energy_ratio: f32,
+    /// Mean spectral flatness, 0 for pure tones up to 1 for white noise
+    pub flatness: f32,
+    /// Share of the energy above `ULTRASONIC`, 0 when the sample rate can't carry any
+    pub ultrasonic_ratio: f32,
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (low-energy frames {:.0}%, spectral flatness {:.2}, ultrasonic energy {:.0}%)",
+            self.content,
+            self.low_energy_ratio * 100.0,
+            self.flatness,
+            self.ultrasonic_ratio * 100.0
+        )
+    }
+}
+
+pub fn classify(audio_data: &AudioData) -> Classification {
+    let rate = audio_data.sample_rate as usize;
+    let samples = &audio_data.samples[..audio_data.samples.len().min(rate * MAX_ANALYSIS_SECONDS)];
+
+    let frame = (rate / 50).max(1);
+    let energies: Vec<f32> = samples
+        .chunks_exact(frame)
+        .map(|chunk| chunk.iter().map(|x| x * x).sum::<f32>() / frame as f32)
+        .collect();
+    let mean_energy = energies.iter().sum::<f32>() / energies.len().max(1) as f32;
+    let low_energy_ratio = if mean_energy > 0.0 {
+        energies.iter().filter(|&&e| e < 0.5 * mean_energy).count() as f32 / energies.len() as f32
+    } else {
+        1.0
+    };
+
+    let flatness = mean_flatness(samples, mean_energy);
+    let ultrasonic_ratio = ultrasonic_ratio(samples, audio_data.sample_rate);
+
+    // Speech alternates syllables and pauses; music is sustained and tonal; environmental
+    // sound (rain, traffic, wind) is sustained but noise-like. A recording mostly above
+    // hearing is ultrasonic whatever its rhythm (bat calls come in pulses like syllables).
+    let content = if ultrasonic_ratio > 0.5 {
+        ContentType::Ultrasonic
+    } else if mean_energy == 0.0 || (flatness > 0.3 && low_energy_ratio < 0.3) {
+        ContentType::Environmental
+    } else if low_energy_ratio >= 0.3 {
+        ContentType::Speech
+    } else {
+        ContentType::Music
+    };
+
+    Classification {
+        content,
+        low_energy_ratio,
+        flatness,
+        ultrasonic_ratio,
+    }
+}
+
+/// Share of the energy of `samples` above `ULTRASONIC`, through a fourth-order
+/// Butterworth high-pass (two sections).
+fn ultrasonic_ratio(samples: &[f32], sample_rate: u32) -> f32 {
+    // Some headroom over the cutoff, for the filter to work in
+    if (sample_rate as f32) < ULTRASONIC * 2.2 {
+        return 0.0;
+    }
+    let mut sections = [0.5412, 1.3066].map(|q| Biquad::highpass(sample_rate, ULTRASONIC, q));
+    let (mut total, mut high) = (0.0f64, 0.0f64);
+    for &x in samples {
+        let y = sections.iter_mut().fold(x, |y, section| section.process(y));
+        total += (x * x) as f64;
+        high += (y * y) as f64;
+    }
+    if total > 0.0 {
+        (high / total) as f32
+    } else {
+        0.0
+    }
+}
+
+/// Geometric over arithmetic mean of the power spectrum, averaged over non-silent frames.
+fn mean_flatness(samples: &[f32], mean_energy: f32) -> f32 {
+    const SIZE: usize = 1024;
+    let fft = FftPlanner::new().plan_fft_forward(SIZE);
+    let mut total = 0.0;
+    let mut count = 0;
+    for chunk in samples.chunks_exact(SIZE).step_by(4) {
+        let energy = chunk.iter().map(|x| x * x).sum::<f32>() / SIZE as f32;
+        if energy < 0.5 * mean_energy {
+            continue;
+        }
+        let mut buffer: Vec<Complex<f32>> = chunk.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        fft.process(&mut buffer);
+        let power: Vec<f32> = buffer[1..SIZE / 2].iter().map(|c| c.norm_sqr() + 1e-12).collect();
+        let log_mean = power.iter().map(|p| p.ln()).sum::<f32>() / power.len() as f32;
+        let mean = power.iter().sum::<f32>() / power.len() as f32;
+        total += log_mean.exp() / mean;
+        count += 1;
+    }
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f32
+    }
+}
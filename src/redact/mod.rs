@@ -0,0 +1,305 @@
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use regex::Regex;
+use std::collections::BTreeSet;
+
+use crate::audio::samples::Samples;
+use crate::audio::AudioData;
+use crate::config::toml::Table;
+use crate::speech::{estimate_words, Transcript, TranscriptionSegment};
+
+/// Words masked by `--redact-profanity`. Matching is whole-word and case-insensitive, so
+/// inflections are listed explicitly; `[redact] profanity` in the config adds more.
+const PROFANITY: &[&str] = &[
+    "arse", "arsehole", "ass", "asshole", "assholes", "bastard", "bastards", "bitch", "bitches",
+    "bitching", "bollocks", "bullshit", "cock", "cocks", "crap", "cunt", "cunts", "damn",
+    "damned", "dick", "dicks", "dickhead", "fuck", "fucked", "fucker", "fuckers", "fucking",
+    "fucks", "goddamn", "motherfucker", "motherfuckers", "motherfucking", "piss", "pissed",
+    "prick", "shit", "shits", "shitty", "shitting", "slut", "twat", "wanker", "whore",
+];
+
+/// Padding around a redacted word's audio, since word timestamps are approximate.
+const PADDING: f64 = 0.02;
+
+/// How redacted words sound in exported audio.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Bleep {
+    /// A 1 kHz tone
+    #[default]
+    Tone,
+    /// Silence
+    Mute,
+}
+
+/// Built-in PII entities for `--redact-pii`, tried in order (cards before phone numbers,
+/// which can look alike). Whisper writes most numbers as digits, but spelled-out digit
+/// runs and spoken addresses are caught too.
+const ENTITIES: &[(&str, &str)] = &[
+    ("email", r"[\w.+-]+@[\w-]+(\.[\w-]+)+"),
+    ("email", r"(?i)\b[\w.]+ at [\w-]+ dot (com|org|net|edu|gov|io|co|uk)\b"),
+    ("card", r"\b\d([ -]?\d){12,18}\b"),
+    ("phone", r"(\+\d{1,3}[ .-]?)?\(?\b\d{3}\)?[ .-]?\d{3}[ .-]?\d{4}\b"),
+    (
+        "phone",
+        r"(?i)\b(zero|oh|one|two|three|four|five|six|seven|eight|nine)([ ,-]+(zero|oh|one|two|three|four|five|six|seven|eight|nine)){6,}\b",
+    ),
+];
+
+/// A pattern whose matches are replaced by `[LABEL]`.
+#[derive(Clone, Debug)]
+struct Rule {
+    label: String,
+    regex: Regex,
+}
+
+/// Flags words and patterns in transcripts and masks them.
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    profanity: BTreeSet<String>,
+    rules: Vec<Rule>,
+}
+
+/// Letters, digits and apostrophes, lowercased: the form words are compared in.
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '\'')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn strings<'a>(table: &'a Table, key: &str) -> Result<Vec<&'a str>> {
+    let Some(value) = table.get(key) else {
+        return Ok(Vec::new());
+    };
+    let items = value
+        .as_array()
+        .ok_or_else(|| anyhow!("{} must be an array of strings", key))?;
+    items
+        .iter()
+        .map(|item| item.as_str().ok_or_else(|| anyhow!("Invalid entry in {}: {}", key, item)))
+        .collect()
+}
+
+impl Redactor {
+    /// Adds the built-in profanity list plus `[redact] profanity` from the config.
+    pub fn with_profanity(mut self, table: &Table) -> Result<Self> {
+        self.profanity.extend(PROFANITY.iter().map(|w| w.to_string()));
+        for word in strings(table, "redact.profanity")? {
+            self.profanity.insert(normalize(word));
+        }
+        Ok(self)
+    }
+
+    /// Adds the PII entities named in `[redact] pii` (default: all of email, card, phone)
+    /// and the regular expressions in `[redact] patterns`.
+    pub fn with_pii(mut self, table: &Table) -> Result<Self> {
+        let names = match table.get("redact.pii") {
+            Some(_) => strings(table, "redact.pii")?,
+            None => vec!["email", "card", "phone"],
+        };
+        for name in &names {
+            if !ENTITIES.iter().any(|(entity, _)| entity == name) {
+                return Err(anyhow!("Unknown PII entity `{}` in redact.pii (expected email, card or phone)", name));
+            }
+        }
+        for (entity, pattern) in ENTITIES.iter().filter(|(entity, _)| names.contains(entity)) {
+            self.rules.push(Rule {
+                label: entity.to_uppercase(),
+                regex: Regex::new(pattern)?,
+            });
+        }
+        for pattern in strings(table, "redact.patterns")? {
+            self.rules.push(Rule {
+                label: "REDACTED".to_string(),
+                regex: Regex::new(pattern).with_context(|| format!("Invalid pattern `{}` in redact.patterns", pattern))?,
+            });
+        }
+        Ok(self)
+    }
+
+    pub fn is_flagged(&self, word: &str) -> bool {
+        self.profanity.contains(&normalize(word))
+    }
+
+    /// Byte ranges of `text` to redact with their replacements: profane words as their
+    /// first letter and asterisks (`f***`), pattern matches as `[LABEL]`. Earlier rules win
+    /// where matches overlap.
+    fn spans(&self, text: &str) -> Vec<(usize, usize, String)> {
+        let mut spans: Vec<(usize, usize, String)> = Vec::new();
+        for rule in &self.rules {
+            for (start, end) in rule.regex.find_iter(text).map(|m| (m.start(), m.end())) {
+                if spans.iter().all(|&(s, e, _)| end <= s || start >= e) {
+                    spans.push((start, end, format!("[{}]", rule.label)));
+                }
+            }
+        }
+        let mut word_start = None;
+        for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+            if c.is_alphanumeric() || c == '\'' {
+                word_start.get_or_insert(i);
+                continue;
+            }
+            let Some(start) = word_start.take() else {
+                continue;
+            };
+            let word = &text[start..i];
+            if self.is_flagged(word) && spans.iter().all(|&(s, e, _)| i <= s || start >= e) {
+                let mut chars = word.chars();
+                let masked: String = chars.next().into_iter().chain(chars.map(|_| '*')).collect();
+                spans.push((start, i, masked));
+            }
+        }
+        spans.sort_by_key(|&(start, _, _)| start);
+        spans
+    }
+
+    /// `text` with flagged words and pattern matches replaced, keeping everything else.
+    pub fn mask(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end, replacement) in self.spans(text) {
+            out.push_str(&text[last..start]);
+            out.push_str(&replacement);
+            last = end;
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    /// Masks the transcript's segments and returns the (start, end) time ranges the
+    /// redactions cover, padded and merged.
+    pub fn redact(&self, transcript: &mut Transcript) -> Vec<(f64, f64)> {
+        let mut ranges: Vec<(f64, f64)> = Vec::new();
+        for segment in &mut transcript.segments {
+            let spans = self.spans(&segment.text);
+            if spans.is_empty() {
+                continue;
+            }
+            let words = timed_words(segment, &transcript.words);
+            for (start, end, _) in &spans {
+                let covered = words.iter().filter(|(range, _)| range.0 < *end && range.1 > *start);
+                let (from, to) = covered.fold((f64::INFINITY, f64::NEG_INFINITY), |(from, to), (_, word)| {
+                    (from.min(word.start), to.max(word.end))
+                });
+                if from <= to {
+                    ranges.push(((from - PADDING).max(0.0), to + PADDING));
+                }
+            }
+            segment.text = self.mask(&segment.text);
+        }
+
+        ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut merged: Vec<(f64, f64)> = Vec::new();
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+}
+
+/// The whitespace-separated words of `segment` as byte ranges, with times from `words`
+/// (Whisper's word timings) when the words centered in the segment line up one to one with
+/// its text, and estimated from the segment's timing otherwise.
+fn timed_words(segment: &TranscriptionSegment, words: &[TranscriptionSegment]) -> Vec<((usize, usize), TranscriptionSegment)> {
+    let text = &segment.text;
+    let ranges: Vec<(usize, usize)> = text
+        .split_whitespace()
+        .map(|word| {
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            (start, start + word.len())
+        })
+        .collect();
+    let inside: Vec<TranscriptionSegment> = words
+        .iter()
+        .filter(|word| (segment.start..=segment.end).contains(&((word.start + word.end) / 2.0)))
+        .cloned()
+        .collect();
+    let timed = if inside.len() == ranges.len() {
+        inside
+    } else {
+        estimate_words(std::slice::from_ref(segment))
+    };
+    ranges.into_iter().zip(timed).collect()
+}
+
+/// Replaces the audio in `ranges` (seconds) on every channel with a tone or silence.
+pub fn bleep(audio: &mut AudioData, ranges: &[(f64, f64)], bleep: Bleep) {
+    let rate = audio.sample_rate as f64;
+    let mut channels: Vec<&mut Samples> = std::iter::once(&mut audio.samples)
+        .chain(audio.extra_channels.iter_mut())
+        .collect();
+    for &(start, end) in ranges {
+        for channel in channels.iter_mut() {
+            let from = ((start * rate) as usize).min(channel.len());
+            let to = ((end * rate) as usize).min(channel.len());
+            for (i, sample) in channel[from..to].iter_mut().enumerate() {
+                *sample = match bleep {
+                    Bleep::Tone => 0.25 * (2.0 * std::f64::consts::PI * 1000.0 * i as f64 / rate).sin() as f32,
+                    Bleep::Mute => 0.0,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::toml;
+
+    fn pii(config: &str) -> Redactor {
+        Redactor::default().with_pii(&toml::parse(config).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn emails_written_and_spoken() {
+        let redactor = pii("");
+        assert_eq!(redactor.mask("mail jane.doe+x@mail.example.co.uk now"), "mail [EMAIL] now");
+        assert_eq!(redactor.mask("It's Jane at example dot com."), "It's [EMAIL].");
+        assert_eq!(redactor.mask("meet me at noon dot"), "meet me at noon dot");
+    }
+
+    #[test]
+    fn cards_before_phone_numbers() {
+        let redactor = pii("");
+        assert_eq!(redactor.mask("card 4111 1111 1111 1111 ok"), "card [CARD] ok");
+        assert_eq!(redactor.mask("card 4111-1111-1111-1111"), "card [CARD]");
+        assert_eq!(redactor.mask("call (555) 123-4567 or +1 555.123.4567"), "call [PHONE] or [PHONE]");
+        assert_eq!(redactor.mask("room 1234, 12 people"), "room 1234, 12 people");
+    }
+
+    #[test]
+    fn spelled_out_phone_numbers() {
+        let redactor = pii("");
+        assert_eq!(redactor.mask("it's five five five, one two three, four"), "it's [PHONE]");
+        assert_eq!(redactor.mask("one two three go"), "one two three go");
+    }
+
+    #[test]
+    fn word_boundaries_follow_unicode_words() {
+        // `é` is a word character, so the digits in `café5551234567` don't start at a word boundary
+        let redactor = pii("");
+        assert_eq!(redactor.mask("café5551234567"), "café5551234567");
+        assert_eq!(redactor.mask("café 555 123 4567"), "café [PHONE]");
+    }
+
+    #[test]
+    fn entities_can_be_chosen() {
+        let redactor = pii("[redact]\npii = [\"email\"]");
+        assert_eq!(redactor.mask("a@b.io 555-123-4567"), "[EMAIL] 555-123-4567");
+        assert!(Redactor::default().with_pii(&toml::parse("[redact]\npii = [\"ssn\"]").unwrap()).is_err());
+    }
+
+    #[test]
+    fn user_patterns_run_in_linear_time() {
+        let redactor = pii("[redact]\npii = []\npatterns = [\"(a+)+$\", \"(?i)account \\\\w+\"]");
+        let text = format!("{}b Account X1", "a".repeat(10_000));
+        let started = std::time::Instant::now();
+        assert_eq!(redactor.mask(&text), format!("{}b [REDACTED]", "a".repeat(10_000)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(Redactor::default().with_pii(&toml::parse("[redact]\npatterns = [\"(\"]").unwrap()).is_err());
+    }
+}
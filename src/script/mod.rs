@@ -0,0 +1,186 @@
+use anyhow::{anyhow, Context, Result};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::path::{Path, PathBuf};
+
+use crate::audio::{compute_spectrogram, load_audio, SpectrogramData};
+use crate::speech::{transcribe_audio, TranscriptionSegment};
+use crate::stats::{compute_stats, AudioStats};
+
+/// Window of the spectrogram `analyze()` computes for its stats.
+const ANALYZE_WINDOW: usize = 1024;
+
+/// A Rhai script with optional hooks, all plain functions:
+///
+/// - `fn transcript(segments)`: gets the segments as an array of `#{start, end, text}` maps
+///   and returns the (edited) array
+/// - `fn frame(time, levels, bin_hz)`: called per spectrogram frame with its levels; returns
+///   a number or a map of named numbers, averaged (and maxed) into custom metrics
+/// - `fn metrics(stats)`: gets the stats as a map and returns a map of extra metrics
+/// - `fn main(args)`: entry point of `fourrier script`, with `analyze(path)`,
+///   `transcribe(path)` and `write_file(path, text)` to call
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+}
+
+fn script_error(error: Box<EvalAltResult>) -> anyhow::Error {
+    anyhow!("Script error: {}", error)
+}
+
+fn segments_to_array(segments: &[TranscriptionSegment]) -> Array {
+    segments
+        .iter()
+        .map(|segment| {
+            let mut map = Map::new();
+            map.insert("start".into(), segment.start.into());
+            map.insert("end".into(), segment.end.into());
+            map.insert("text".into(), segment.text.trim().into());
+            map.into()
+        })
+        .collect()
+}
+
+/// A number from an int or float value.
+fn as_number(value: &Dynamic) -> Option<f64> {
+    value.as_float().ok().or_else(|| value.as_int().ok().map(|v| v as f64))
+}
+
+fn array_to_segments(array: Array) -> Result<Vec<TranscriptionSegment>> {
+    array
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let map = item
+                .try_cast::<Map>()
+                .ok_or_else(|| anyhow!("transcript() must return maps, item {} isn't one", i))?;
+            let number = |key: &str| {
+                map.get(key)
+                    .and_then(as_number)
+                    .ok_or_else(|| anyhow!("Segment {} returned by transcript() has no numeric `{}`", i, key))
+            };
+            Ok(TranscriptionSegment {
+                start: number("start")?,
+                end: number("end")?,
+                text: map.get("text").map(|text| text.to_string()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn stats_map(stats: &AudioStats) -> Map {
+    let mut map = Map::new();
+    let option = |value: Option<f32>| value.map_or(Dynamic::UNIT, |v| (v as f64).into());
+    map.insert("duration".into(), stats.duration.into());
+    map.insert("sample_rate".into(), (stats.sample_rate as i64).into());
+    map.insert("channels".into(), (stats.channels as i64).into());
+    map.insert("peak_dbfs".into(), (stats.peak_dbfs as f64).into());
+    map.insert("rms_dbfs".into(), (stats.rms_dbfs as f64).into());
+    map.insert("lufs".into(), option(stats.lufs));
+    map.insert("dominant_frequency".into(), option(stats.dominant_frequency));
+    map.insert("bpm".into(), option(stats.bpm));
+    map.insert("key".into(), stats.key.clone().map_or(Dynamic::UNIT, Into::into));
+    map.insert("segment_count".into(), (stats.segment_count as i64).into());
+    map.insert("snr_db".into(), (stats.quality.snr_db as f64).into());
+    map.insert("noise_floor_dbfs".into(), (stats.quality.noise_floor_dbfs as f64).into());
+    map.insert("bandwidth_hz".into(), (stats.quality.bandwidth_hz as f64).into());
+    map.insert("sibilant_bursts".into(), (stats.sibilance.len() as i64).into());
+    map
+}
+
+impl Script {
+    /// Compiles the script and runs its top-level statements once. `model` is the Whisper
+    /// model `transcribe()` uses.
+    pub fn load(path: &Path, model: Option<PathBuf>) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.register_fn("analyze", |path: &str| -> Result<Map, Box<EvalAltResult>> {
+            let audio = load_audio(path).map_err(|e| format!("analyze({}): {}", path, e))?;
+            let spectrogram = compute_spectrogram(&audio, ANALYZE_WINDOW).map_err(|e| e.to_string())?;
+            Ok(stats_map(&compute_stats(&audio, &spectrogram, &[])))
+        });
+        engine.register_fn("transcribe", move |path: &str| -> Result<Array, Box<EvalAltResult>> {
+            let model = model.as_ref().ok_or("transcribe() needs a Whisper model (--model)")?;
+            let audio = load_audio(path).map_err(|e| format!("transcribe({}): {}", path, e))?;
+            let segments = transcribe_audio(&audio, model).map_err(|e| e.to_string())?;
+            Ok(segments_to_array(&segments))
+        });
+        engine.register_fn("write_file", |path: &str, text: &str| -> Result<(), Box<EvalAltResult>> {
+            std::fs::write(path, text).map_err(|e| format!("write_file({}): {}", path, e).into())
+        });
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| anyhow!("{}", e))
+            .with_context(|| format!("Failed to compile script {}", path.display()))?;
+        let mut scope = Scope::new();
+        engine.run_ast_with_scope(&mut scope, &ast).map_err(script_error)?;
+        Ok(Self { engine, ast, scope })
+    }
+
+    fn defines(&self, name: &str, arity: usize) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name && f.params.len() == arity)
+    }
+
+    fn call<T: Clone + 'static>(&mut self, name: &str, args: impl rhai::FuncArgs) -> Result<T> {
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options(options, &mut self.scope, &self.ast, name, args)
+            .map_err(script_error)
+    }
+
+    /// Runs `transcript(segments)` over the segments, when the script defines it.
+    pub fn transcript(&mut self, segments: &mut Vec<TranscriptionSegment>) -> Result<()> {
+        if self.defines("transcript", 1) {
+            let array: Array = self.call("transcript", (segments_to_array(segments),))?;
+            *segments = array_to_segments(array)?;
+        }
+        Ok(())
+    }
+
+    /// Custom metrics from `frame()` (mean and max of each value over the frames) and
+    /// `metrics(stats)`, in that order.
+    pub fn metrics(&mut self, spectrogram: &SpectrogramData, stats: &AudioStats) -> Result<Vec<(String, f64)>> {
+        let mut metrics = Vec::new();
+        if self.defines("frame", 3) {
+            let bin_hz = spectrogram.frequencies.get(1).copied().unwrap_or(0.0) as f64;
+            // Per name: sum, max and count
+            let mut totals: Vec<(String, f64, f64, usize)> = Vec::new();
+            for (&time, levels) in spectrogram.time_points.iter().zip(&spectrogram.magnitudes) {
+                let levels: Array = levels.iter().map(|&level| Dynamic::from(level as f64)).collect();
+                let result: Dynamic = self.call("frame", (time as f64, levels, bin_hz))?;
+                let values: Vec<(String, f64)> = match result.clone().try_cast::<Map>() {
+                    Some(map) => map.iter().filter_map(|(k, v)| Some((k.to_string(), as_number(v)?))).collect(),
+                    None => as_number(&result).map(|v| ("frame".to_string(), v)).into_iter().collect(),
+                };
+                for (name, value) in values {
+                    match totals.iter_mut().find(|(n, ..)| *n == name) {
+                        Some((_, sum, max, count)) => {
+                            *sum += value;
+                            *max = max.max(value);
+                            *count += 1;
+                        }
+                        None => totals.push((name, value, value, 1)),
+                    }
+                }
+            }
+            for (name, sum, max, count) in totals {
+                metrics.push((format!("{} mean", name), sum / count as f64));
+                metrics.push((format!("{} max", name), max));
+            }
+        }
+        if self.defines("metrics", 1) {
+            let map: Map = self.call("metrics", (stats_map(stats),))?;
+            metrics.extend(map.iter().filter_map(|(k, v)| Some((k.to_string(), as_number(v)?))));
+        }
+        Ok(metrics)
+    }
+
+    /// Runs `main(args)` for `fourrier script`.
+    pub fn run_main(&mut self, args: &[String]) -> Result<()> {
+        if !self.defines("main", 1) {
+            return Err(anyhow!("The script has no `fn main(args)`"));
+        }
+        let args: Array = args.iter().map(|arg| Dynamic::from(arg.clone())).collect();
+        let _: Dynamic = self.call("main", (args,))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::SessionState;
+use crate::config::{self, toml};
+
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Periodically writes the session to a recovery file so a crash or a dropped terminal loses
+/// at most one interval of work. The file is removed when the session ends normally.
+pub struct Autosave {
+    path: PathBuf,
+    input: String,
+    sample_count: usize,
+    interval: Duration,
+    last_check: Instant,
+    saved: Option<SessionState>,
+}
+
+/// Recovery file for an input: one per input path, under the data directory.
+fn recovery_path(input: &str) -> PathBuf {
+    // FNV-1a keeps the name stable across runs and toolchains
+    let hash = input
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+    config::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("autosave")
+        .join(format!("{:016x}.toml", hash))
+}
+
+impl Autosave {
+    /// `sample_count` ties the recovery file to the decoded audio, so a file that changed on
+    /// disk isn't paired with stale edits.
+    pub fn new(input: &str, sample_count: usize, interval: Duration) -> Self {
+        Self {
+            path: recovery_path(input),
+            input: input.to_string(),
+            sample_count,
+            interval,
+            last_check: Instant::now(),
+            saved: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records the state the session started from; nothing is written until it changes.
+    pub fn set_baseline(&mut self, state: SessionState) {
+        self.saved = Some(state);
+    }
+
+    pub fn due(&self) -> bool {
+        self.last_check.elapsed() >= self.interval
+    }
+
+    /// Writes `state` if it differs from what was last saved.
+    pub fn save(&mut self, state: &SessionState) -> Result<()> {
+        self.last_check = Instant::now();
+        if self.saved.as_ref() == Some(state) {
+            return Ok(());
+        }
+        let mut table = state.to_table();
+        table.insert("input".into(), self.input.as_str().into());
+        table.insert("samples".into(), (self.sample_count as i64).into());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        table.insert("saved_at".into(), (now.as_secs() as i64).into());
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Write then rename so a crash mid-write never leaves a truncated recovery file
+        let partial = self.path.with_extension("toml.part");
+        std::fs::write(&partial, toml::to_string(&table))
+            .with_context(|| format!("Failed to autosave to {}", partial.display()))?;
+        std::fs::rename(&partial, &self.path)?;
+        self.saved = Some(state.clone());
+        Ok(())
+    }
+
+    /// Ends the session cleanly: the recovery file is no longer needed.
+    pub fn discard(&self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A session left behind by a run that didn't exit normally.
+pub struct Recovery {
+    pub state: SessionState,
+    pub saved_at: Option<SystemTime>,
+    path: PathBuf,
+}
+
+impl Recovery {
+    pub fn discard(self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Looks for a recovery file matching this input and its decoded length.
+pub fn find_recovery(input: &str, sample_count: usize) -> Option<Recovery> {
+    let path = recovery_path(input);
+    let text = std::fs::read_to_string(&path).ok()?;
+    let table = match toml::parse(&text) {
+        Ok(table) => table,
+        Err(e) => {
+            log::warn!("Ignoring unreadable autosave {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let matches = table.get("input").and_then(|v| v.as_str()) == Some(input)
+        && table.get("samples").and_then(|v| v.as_integer()) == Some(sample_count as i64);
+    if !matches {
+        return None;
+    }
+    let state = SessionState::from_table(&table).ok()?;
+    let saved_at = table
+        .get("saved_at")
+        .and_then(|v| v.as_integer())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64));
+    Some(Recovery { state, saved_at, path })
+}
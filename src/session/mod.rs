@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+
+use crate::config::toml::{self, Table, Value};
+use crate::speech::TranscriptionSegment;
+
+pub mod autosave;
+
+/// A named bookmark at a point in the audio.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Marker {
+    pub time: f64,
+    pub name: String,
+}
+
+/// Everything the user can change in the visualizer, in a form that can be written to disk
+/// and applied again later.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionState {
+    pub position: f64,
+    pub speed: f64,
+    pub selection: Option<(f64, f64)>,
+    pub loop_region: Option<(f64, f64)>,
+    pub show_stats: bool,
+    pub transcription: Vec<TranscriptionSegment>,
+    pub markers: Vec<Marker>,
+    /// Horizontal lines across the spectrogram, in Hz
+    pub frequency_markers: Vec<f64>,
+    pub note_grid: bool,
+    /// Muted tracks of a multi-track session, counting from 0
+    pub muted_tracks: Vec<usize>,
+}
+
+fn range_value((start, end): (f64, f64)) -> Value {
+    Value::Array(vec![start.into(), end.into()])
+}
+
+fn range(table: &Table, key: &str) -> Option<(f64, f64)> {
+    match table.get(key)?.as_array()? {
+        [start, end] => Some((start.as_float()?, end.as_float()?)),
+        _ => None,
+    }
+}
+
+fn float_array(table: &Table, key: &str) -> Vec<f64> {
+    table
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_float).collect())
+        .unwrap_or_default()
+}
+
+fn string_array(table: &Table, key: &str) -> Vec<String> {
+    table
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+impl SessionState {
+    /// Session keys as a TOML table; the transcript and markers are stored as parallel arrays
+    /// under `[transcript]` and `[markers]`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("position".into(), self.position.into());
+        table.insert("speed".into(), self.speed.into());
+        table.insert("show_stats".into(), self.show_stats.into());
+        table.insert("note_grid".into(), self.note_grid.into());
+        table.insert(
+            "frequency_markers".into(),
+            Value::Array(self.frequency_markers.iter().map(|&f| f.into()).collect()),
+        );
+        table.insert(
+            "muted_tracks".into(),
+            Value::Array(self.muted_tracks.iter().map(|&track| (track as i64).into()).collect()),
+        );
+        if let Some(selection) = self.selection {
+            table.insert("selection".into(), range_value(selection));
+        }
+        if let Some(region) = self.loop_region {
+            table.insert("loop".into(), range_value(region));
+        }
+        let segments = &self.transcription;
+        table.insert(
+            "transcript.start".into(),
+            Value::Array(segments.iter().map(|s| s.start.into()).collect()),
+        );
+        table.insert(
+            "transcript.end".into(),
+            Value::Array(segments.iter().map(|s| s.end.into()).collect()),
+        );
+        table.insert(
+            "transcript.text".into(),
+            Value::Array(segments.iter().map(|s| s.text.as_str().into()).collect()),
+        );
+        table.insert(
+            "markers.time".into(),
+            Value::Array(self.markers.iter().map(|m| m.time.into()).collect()),
+        );
+        table.insert(
+            "markers.name".into(),
+            Value::Array(self.markers.iter().map(|m| m.name.as_str().into()).collect()),
+        );
+        table
+    }
+
+    pub fn from_table(table: &Table) -> Result<Self> {
+        let starts = float_array(table, "transcript.start");
+        let ends = float_array(table, "transcript.end");
+        let texts = string_array(table, "transcript.text");
+        if starts.len() != texts.len() || ends.len() != texts.len() {
+            return Err(anyhow!("Transcript start, end and text arrays differ in length"));
+        }
+        let transcription = texts
+            .into_iter()
+            .zip(starts.into_iter().zip(ends))
+            .map(|(text, (start, end))| TranscriptionSegment { text, start, end })
+            .collect();
+
+        let times = float_array(table, "markers.time");
+        let names = string_array(table, "markers.name");
+        if times.len() != names.len() {
+            return Err(anyhow!("Marker time and name arrays differ in length"));
+        }
+        let markers = times
+            .into_iter()
+            .zip(names)
+            .map(|(time, name)| Marker { time, name })
+            .collect();
+
+        Ok(Self {
+            position: table.get("position").and_then(Value::as_float).unwrap_or(0.0),
+            speed: table.get("speed").and_then(Value::as_float).unwrap_or(1.0),
+            selection: range(table, "selection"),
+            loop_region: range(table, "loop"),
+            show_stats: table.get("show_stats").and_then(Value::as_bool).unwrap_or(false),
+            transcription,
+            markers,
+            frequency_markers: float_array(table, "frequency_markers"),
+            note_grid: table.get("note_grid").and_then(Value::as_bool).unwrap_or(false),
+            muted_tracks: table
+                .get("muted_tracks")
+                .and_then(Value::as_array)
+                .map(|items| items.iter().filter_map(Value::as_integer).filter_map(|i| usize::try_from(i).ok()).collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Reads a `--session` file: the analysis parameters it was saved with (everything that isn't
+/// session state, e.g. `input` and `window_size`) and the state itself.
+pub fn load_session(path: &Path) -> Result<(Table, SessionState)> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read session {}", path.display()))?;
+    let table = toml::parse(&text).with_context(|| format!("Invalid session {}", path.display()))?;
+    let state = SessionState::from_table(&table)?;
+    Ok((table, state))
+}
+
+/// Writes `params` and `state` to a session file, replacing it atomically.
+pub fn save_session(path: &Path, params: &Table, state: &SessionState) -> Result<()> {
+    let mut table = params.clone();
+    table.extend(state.to_table());
+    let partial = path.with_extension("part");
+    std::fs::write(&partial, toml::to_string(&table))
+        .with_context(|| format!("Failed to write session {}", path.display()))?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips_through_the_table() {
+        let state = SessionState {
+            position: 12.5,
+            speed: 0.75,
+            selection: Some((1.0, 2.0)),
+            loop_region: None,
+            show_stats: true,
+            transcription: vec![TranscriptionSegment { text: "hello".into(), start: 0.0, end: 1.5 }],
+            markers: vec![Marker { time: 3.0, name: "intro".into() }],
+            frequency_markers: vec![440.0],
+            note_grid: false,
+            muted_tracks: vec![0, 2],
+        };
+        assert_eq!(SessionState::from_table(&state.to_table()).unwrap(), state);
+    }
+}
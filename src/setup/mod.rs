@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use crate::config::{self, starter_config};
+use crate::models::{self, DEFAULT_MODEL};
+
+/// Whether the first-run wizard should be offered: no config yet and someone at the keyboard.
+pub fn needs_first_run() -> bool {
+    let interactive = io::stdin().is_terminal() && io::stderr().is_terminal();
+    interactive && config::default_config_path().is_some_and(|path| !path.exists())
+}
+
+/// Asks a yes/no question on stderr and reads the answer from stdin.
+pub fn confirm(question: &str, default_yes: bool) -> Result<bool> {
+    let hint = if default_yes { "[Y/n]" } else { "[y/N]" };
+    eprint!("{} {} ", question, hint);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(match answer.trim().to_ascii_lowercase().as_str() {
+        "" => default_yes,
+        a => a.starts_with('y'),
+    })
+}
+
+/// Offers to download the default model and writes a starter config.
+pub fn run_first_run_wizard(model_found: Option<&Path>) -> Result<()> {
+    let config_path = config::default_config_path()
+        .ok_or_else(|| anyhow!("Could not determine a config directory"))?;
+    eprintln!("Welcome to fourrier! Let's get you set up.");
+
+    let mut model = model_found.map(Path::to_path_buf);
+    if model.is_none() {
+        let info = models::find(DEFAULT_MODEL).expect("default model is in the catalog");
+        let question = format!(
+            "No Whisper model found. Download ggml-{} (~{} MB)?",
+            info.name, info.size_mb
+        );
+        if confirm(&question, true)? {
+            let path = models::download(info)?;
+            eprintln!("Saved model to {}", path.display());
+            model = Some(path);
+        }
+    }
+
+    if confirm(&format!("Write a starter config to {}?", config_path.display()), true)? {
+        if let Some(dir) = config_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&config_path, starter_config(model.as_deref()))?;
+        eprintln!("Wrote {}", config_path.display());
+    }
+    Ok(())
+}
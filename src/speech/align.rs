@@ -0,0 +1,115 @@
+use super::{estimate_words, TranscriptionSegment};
+
+/// Timings for a known transcript: one segment per non-empty line and one per word.
+#[derive(Clone, Debug, Default)]
+pub struct Alignment {
+    pub segments: Vec<TranscriptionSegment>,
+    pub words: Vec<TranscriptionSegment>,
+}
+
+/// Lowercase letters and digits only, so "Hello," matches "hello".
+fn normalize(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Aligns the words of `text` to recognized (ideally word-level) segments by edit distance,
+/// taking the timing of each matched or substituted word from its recognized counterpart.
+/// Words Whisper missed share the gap between their aligned neighbours by length; with
+/// nothing recognized, the transcript is spread over `duration`.
+pub fn align(text: &str, recognized: &[TranscriptionSegment], duration: f64) -> Alignment {
+    let lines: Vec<Vec<&str>> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|words| !words.is_empty())
+        .collect();
+    let words: Vec<&str> = lines.iter().flatten().copied().collect();
+    let heard = estimate_words(recognized);
+
+    // Edit distance with backpointers: 0 = diagonal (match or substitution), 1 = transcript
+    // word unheard, 2 = recognized word not in the transcript
+    let (n, m) = (words.len(), heard.len());
+    let expected: Vec<String> = words.iter().map(|w| normalize(w)).collect();
+    let actual: Vec<String> = heard.iter().map(|w| normalize(&w.text)).collect();
+    let mut steps = vec![0u8; (n + 1) * (m + 1)];
+    let mut previous: Vec<u32> = (0..=m as u32).collect();
+    steps[1..=m].fill(2);
+    for i in 1..=n {
+        let mut current = vec![i as u32; m + 1];
+        steps[i * (m + 1)] = 1;
+        for j in 1..=m {
+            let substitution = previous[j - 1] + (expected[i - 1] != actual[j - 1]) as u32;
+            let skip_word = previous[j] + 1;
+            let skip_heard = current[j - 1] + 1;
+            let (cost, step) = if substitution <= skip_word && substitution <= skip_heard {
+                (substitution, 0)
+            } else if skip_word <= skip_heard {
+                (skip_word, 1)
+            } else {
+                (skip_heard, 2)
+            };
+            current[j] = cost;
+            steps[i * (m + 1) + j] = step;
+        }
+        previous = current;
+    }
+
+    let mut matched: Vec<Option<usize>> = vec![None; n];
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        match steps[i * (m + 1) + j] {
+            0 => {
+                matched[i - 1] = Some(j - 1);
+                i -= 1;
+                j -= 1;
+            }
+            1 => i -= 1,
+            _ => j -= 1,
+        }
+    }
+
+    // Timings, interpolating runs of unmatched words between their neighbours
+    let mut timed: Vec<(f64, f64)> = matched
+        .iter()
+        .map(|m| m.map_or((f64::NAN, f64::NAN), |j| (heard[j].start, heard[j].end)))
+        .collect();
+    let mut k = 0;
+    while k < n {
+        if !timed[k].0.is_nan() {
+            k += 1;
+            continue;
+        }
+        let run_start = k;
+        while k < n && timed[k].0.is_nan() {
+            k += 1;
+        }
+        let from = if run_start == 0 { 0.0 } else { timed[run_start - 1].1 };
+        let to = if k == n { duration.max(from) } else { timed[k].0.max(from) };
+        let total: usize = words[run_start..k].iter().map(|w| w.len()).sum();
+        let mut start = from;
+        for index in run_start..k {
+            let share = (to - from) * words[index].len() as f64 / total.max(1) as f64;
+            timed[index] = (start, start + share);
+            start += share;
+        }
+    }
+
+    let mut alignment = Alignment::default();
+    let mut index = 0;
+    for line in &lines {
+        let first = index;
+        for word in line {
+            alignment.words.push(TranscriptionSegment {
+                text: word.to_string(),
+                start: timed[index].0,
+                end: timed[index].1,
+            });
+            index += 1;
+        }
+        alignment.segments.push(TranscriptionSegment {
+            text: line.join(" "),
+            start: timed[first].0,
+            end: timed[index - 1].1,
+        });
+    }
+    alignment
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use super::TranscriptionSegment;
+
+/// A stretch of the recording about one topic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    /// The chapter's most distinctive words
+    pub title: String,
+}
+
+/// Shortest chapter `--chapters` produces by default, in seconds.
+pub const MIN_CHAPTER: f64 = 60.0;
+
+/// Content words per TextTiling pseudo-sentence.
+const PSEUDO_SENTENCE: usize = 20;
+
+/// Pseudo-sentences compared on each side of a candidate boundary.
+const BLOCK: usize = 6;
+
+/// Words in a title.
+const TITLE_WORDS: usize = 3;
+
+const STOPWORDS: &[&str] = &[
+    "about", "above", "actually", "after", "again", "all", "also", "and", "any", "are", "around",
+    "because", "been", "before", "being", "but", "can", "could", "did", "does", "doing", "don't",
+    "down", "each", "even", "for", "from", "get", "gets", "getting", "going", "gonna", "got",
+    "had", "has", "have", "having", "her", "here", "hers", "him", "his", "how", "i'm", "into",
+    "it's", "its", "just", "know", "like", "little", "lot", "make", "many", "maybe", "mean",
+    "more", "most", "much", "need", "not", "now", "off", "one", "only", "other", "our", "out",
+    "over", "really", "right", "said", "same", "say", "see", "she", "should", "some", "something",
+    "still", "such", "than", "that", "that's", "the", "their", "them", "then", "there", "there's",
+    "these", "they", "thing", "things", "think", "this", "those", "through", "too", "two", "under",
+    "until", "very", "want", "was", "way", "well", "were", "what", "when", "where", "which",
+    "while", "who", "why", "will", "with", "would", "yeah", "yes", "you", "your", "you're",
+];
+
+/// Lowercased letters, digits and apostrophes, with a possessive `'s` dropped.
+fn normalize(word: &str) -> String {
+    let word: String = word
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '\'')
+        .flat_map(char::to_lowercase)
+        .collect();
+    match word.strip_suffix("'s") {
+        Some(stem) if !STOPWORDS.contains(&word.as_str()) => stem.to_string(),
+        _ => word,
+    }
+}
+
+fn cosine(a: &HashMap<&str, f64>, b: &HashMap<&str, f64>) -> f64 {
+    let dot: f64 = a.iter().filter_map(|(word, x)| b.get(word).map(|y| x * y)).sum();
+    let norm = |v: &HashMap<&str, f64>| v.values().map(|x| x * x).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator > 0.0 { dot / denominator } else { 0.0 }
+}
+
+/// Splits the transcript into chapters with TextTiling: vocabulary overlap between the
+/// blocks of text on either side of each gap, with boundaries at the deepest dips in
+/// similarity that leave every chapter at least `min_length` seconds long. Boundaries fall
+/// on segment starts; chapters are titled by their most frequent distinctive words.
+pub fn chapters(transcription: &[TranscriptionSegment], duration: f64, min_length: f64) -> Vec<Chapter> {
+    if transcription.is_empty() {
+        return Vec::new();
+    }
+    // Content words with the segment each came from
+    let mut tokens: Vec<(String, usize)> = Vec::new();
+    for (index, segment) in transcription.iter().enumerate() {
+        for word in segment.text.split_whitespace() {
+            let word = normalize(word);
+            if word.chars().count() > 2 && !STOPWORDS.contains(&word.as_str()) {
+                tokens.push((word, index));
+            }
+        }
+    }
+    let sentences: Vec<&[(String, usize)]> = tokens.chunks(PSEUDO_SENTENCE).collect();
+
+    // Similarity across each gap between pseudo-sentences, then how deep a dip each gap is
+    // relative to the highest similarity reached climbing away on both sides
+    let block = |range: std::ops::Range<usize>| {
+        let mut counts: HashMap<&str, f64> = HashMap::new();
+        for (word, _) in sentences[range].iter().copied().flatten() {
+            *counts.entry(word.as_str()).or_default() += 1.0;
+        }
+        counts
+    };
+    let gaps: Vec<usize> = (1..sentences.len()).collect();
+    let similarity: Vec<f64> = gaps
+        .iter()
+        .map(|&g| cosine(&block(g.saturating_sub(BLOCK)..g), &block(g..(g + BLOCK).min(sentences.len()))))
+        .collect();
+    let depth: Vec<f64> = (0..similarity.len())
+        .map(|i| {
+            let mut left = similarity[i];
+            for &s in similarity[..i].iter().rev() {
+                if s < left {
+                    break;
+                }
+                left = s;
+            }
+            let mut right = similarity[i];
+            for &s in &similarity[i + 1..] {
+                if s < right {
+                    break;
+                }
+                right = s;
+            }
+            (left - similarity[i]) + (right - similarity[i])
+        })
+        .collect();
+    let mean = depth.iter().sum::<f64>() / depth.len().max(1) as f64;
+    let deviation = (depth.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / depth.len().max(1) as f64).sqrt();
+
+    // Deepest dips first, each moved to the start of the segment it falls in (or the next
+    // one when the pseudo-sentence starts mid-segment)
+    let mut candidates: Vec<(f64, f64)> = Vec::new();
+    for (i, &g) in gaps.iter().enumerate() {
+        if depth[i] <= mean - deviation / 2.0 || depth[i] <= 0.0 {
+            continue;
+        }
+        let (_, segment) = sentences[g][0];
+        let (_, previous) = sentences[g - 1][PSEUDO_SENTENCE - 1];
+        let segment = if previous == segment { segment + 1 } else { segment };
+        if let Some(start) = transcription.get(segment).map(|s| s.start) {
+            candidates.push((depth[i], start));
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut boundaries: Vec<f64> = Vec::new();
+    for (_, time) in candidates {
+        if time >= min_length
+            && duration - time >= min_length
+            && boundaries.iter().all(|b| (b - time).abs() >= min_length)
+        {
+            boundaries.push(time);
+        }
+    }
+    boundaries.sort_by(f64::total_cmp);
+
+    let edges: Vec<f64> = std::iter::once(0.0)
+        .chain(boundaries)
+        .chain(std::iter::once(duration))
+        .collect();
+    let words_in = |start: f64, end: f64| {
+        tokens
+            .iter()
+            .filter(move |(_, segment)| {
+                let time = transcription[*segment].start;
+                time >= start && (time < end || end >= duration)
+            })
+            .map(|(word, _)| word.as_str())
+    };
+
+    // Title words: frequent in the chapter, weighted down when common to many chapters
+    let count = edges.len() - 1;
+    let mut spread: HashMap<&str, usize> = HashMap::new();
+    for pair in edges.windows(2) {
+        let mut seen: Vec<&str> = words_in(pair[0], pair[1]).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        for word in seen {
+            *spread.entry(word).or_default() += 1;
+        }
+    }
+    edges
+        .windows(2)
+        .enumerate()
+        .map(|(index, pair)| {
+            // Frequency with first appearance, so ties keep the order words were spoken in
+            let mut frequency: Vec<(&str, usize, usize)> = Vec::new();
+            for (position, word) in words_in(pair[0], pair[1]).enumerate() {
+                match frequency.iter_mut().find(|(w, _, _)| *w == word) {
+                    Some(entry) => entry.1 += 1,
+                    None => frequency.push((word, 1, position)),
+                }
+            }
+            let score = |&(word, n, _): &(&str, usize, usize)| {
+                n as f64 * (1.0 + count as f64 / spread[word] as f64).ln()
+            };
+            frequency.sort_by(|a, b| score(b).total_cmp(&score(a)).then(a.2.cmp(&b.2)));
+            let title = frequency
+                .iter()
+                .take(TITLE_WORDS)
+                .map(|(word, _, _)| {
+                    let mut chars = word.chars();
+                    chars.next().into_iter().flat_map(char::to_uppercase).chain(chars).collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            Chapter {
+                start: pair[0],
+                end: pair[1],
+                title: if title.is_empty() { format!("Chapter {}", index + 1) } else { title },
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use whisper_rs::WhisperContext;
+
+use super::{read_transcript, whisper_params, TranscriptionSegment, WHISPER_RATE};
+
+/// How often the pending audio is transcribed again.
+const STEP: Duration = Duration::from_millis(700);
+
+/// Least pending audio worth transcribing, in seconds.
+const MIN_WINDOW: f64 = 0.5;
+
+/// Longest the pending audio grows before its text is committed without a pause, in
+/// seconds: short enough that a retranscription keeps up, long enough for whole phrases.
+const MAX_WINDOW: f64 = 8.0;
+
+/// Quiet at the end of the pending audio that counts as a pause between phrases, in seconds.
+const PAUSE: f64 = 0.4;
+
+/// Pending audio quieter than this throughout is skipped rather than transcribed, which
+/// keeps Whisper from hallucinating text into silence, in dBFS.
+const SILENCE: f32 = -50.0;
+
+/// A pause is this much quieter than the pending audio, in dB.
+const PAUSE_DROP: f32 = 15.0;
+
+/// What the captioner reports as the audio comes in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptionEvent {
+    /// The text of the phrase in progress, revised as it goes on
+    Partial(String),
+    /// A finished phrase, which won't change any more
+    Final(TranscriptionSegment),
+}
+
+fn rms_db(samples: &[f32]) -> f32 {
+    let power = samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32;
+    10.0 * power.max(1e-12).log10()
+}
+
+/// Transcribes audio as it arrives, for live captions: every `STEP` the audio since the
+/// last finished phrase is transcribed again and reported as `Partial`, and at a pause, or
+/// once it reaches `MAX_WINDOW`, its text is reported as `Final`. `audio(from)` returns the
+/// mono `WHISPER_RATE` samples from index `from` on. Runs until `stop` is set.
+pub fn caption(
+    model_path: &Path,
+    audio: impl Fn(usize) -> Vec<f32>,
+    stop: &AtomicBool,
+    mut emit: impl FnMut(CaptionEvent),
+) -> Result<()> {
+    let model = model_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Model path {} is not valid UTF-8", model_path.display()))?;
+    let ctx = WhisperContext::new(model)
+        .map_err(|e| anyhow!("Failed to load Whisper model {}: {}", model_path.display(), e))?;
+    let mut state = ctx.create_state()?;
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get()).min(4);
+    let to_samples = |seconds: f64| (seconds * WHISPER_RATE as f64) as usize;
+
+    // Start of the audio not yet in a finished phrase
+    let mut committed = 0;
+    let mut partial = String::new();
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(STEP);
+        let pending = audio(committed);
+        if pending.len() < to_samples(MIN_WINDOW) {
+            continue;
+        }
+        let level = rms_db(&pending);
+        if level < SILENCE {
+            // Nothing said since the last phrase; keep a little in case speech is starting
+            committed += pending.len().saturating_sub(to_samples(PAUSE));
+            continue;
+        }
+        let tail = rms_db(&pending[pending.len().saturating_sub(to_samples(PAUSE))..]);
+        let paused = tail < SILENCE.max(level - PAUSE_DROP);
+        let full = pending.len() >= to_samples(MAX_WINDOW);
+
+        state
+            .full(whisper_params(0, threads, None), &pending)
+            .map_err(|e| anyhow!("Failed to process audio: {}", e))?;
+        let offset = committed as f64 / WHISPER_RATE as f64;
+        let mut segments = read_transcript(&state, offset)?.segments;
+        segments.retain(|segment| !segment.text.trim().is_empty());
+
+        if paused || full {
+            // Without a pause, the last segment may be cut off mid-word; it stays pending
+            let keep = if paused || segments.len() < 2 { 0 } else { 1 };
+            let finished = segments.len() - keep;
+            committed = match segments.get(finished) {
+                Some(next) => to_samples(next.start).max(committed),
+                None => committed + pending.len(),
+            };
+            for segment in segments.drain(..finished) {
+                emit(CaptionEvent::Final(segment));
+            }
+        }
+        let text = segments.iter().map(|segment| segment.text.trim()).collect::<Vec<_>>().join(" ");
+        if text != partial {
+            emit(CaptionEvent::Partial(text.clone()));
+            partial = text;
+        }
+    }
+    Ok(())
+}
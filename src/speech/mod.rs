@@ -1,80 +1,58 @@
 use anyhow::{Result, anyhow};
 use std::path::Path;
-use whisper_rs::{WhisperContext, FullParams, SamplingStrategy};
-use symphonia::core::codecs::DecoderOptions;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
-use symphonia::core::audio::Signal;
-use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use whisper_rs::{WhisperContext, WhisperState, FullParams, SamplingStrategy};
 
+use crate::audio::filter::Biquad;
+use crate::audio::AudioData;
+
+pub mod align;
+pub mod chapters;
+pub mod live;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct TranscriptionSegment {
     pub text: String,
     pub start: f64,
     pub end: f64,
 }
 
-fn load_audio_for_whisper<P: AsRef<Path>>(path: P) -> Result<Vec<f32>> {
-    println!("Loading audio file for Whisper...");
-    let file = File::open(&path)?;
-    let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-    let hint = Hint::new();
-    let format_opts = FormatOptions::default();
-    let metadata_opts = MetadataOptions::default();
-    let decoder_opts = DecoderOptions::default();
-
-    let probed = symphonia::default::get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
-    let mut format = probed.format;
-    
-    // Get sample rate before processing packets
-    let track = format.default_track().unwrap();
-    let sample_rate = track.codec_params.sample_rate.unwrap_or(16000);
-    println!("Audio format: {:?}", track.codec_params.codec);
-    println!("Sample rate: {} Hz", sample_rate);
-    println!("Channels: {:?}", track.codec_params.channels);
-
-    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &decoder_opts)?;
-    let mut samples = Vec::new();
-
-    println!("Decoding audio...");
-    while let Ok(packet) = format.next_packet() {
-        let decoded = decoder.decode(&packet)?;
-        match decoded {
-            symphonia::core::audio::AudioBufferRef::F32(buf) => {
-                samples.extend_from_slice(buf.chan(0));
-            },
-            symphonia::core::audio::AudioBufferRef::U8(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| (x as f32 / 128.0) - 1.0));
-            },
-            symphonia::core::audio::AudioBufferRef::U16(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| (x as f32 / 32768.0) - 1.0));
-            },
-            symphonia::core::audio::AudioBufferRef::U32(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| (x as f32 / 2147483648.0) - 1.0));
-            },
-            symphonia::core::audio::AudioBufferRef::S8(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| x as f32 / 128.0));
-            },
-            symphonia::core::audio::AudioBufferRef::S16(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| x as f32 / 32768.0));
-            },
-            symphonia::core::audio::AudioBufferRef::S32(buf) => {
-                samples.extend(buf.chan(0).iter().map(|&x| x as f32 / 2147483648.0));
-            },
-            _ => {
-                println!("Unsupported audio format, skipping packet");
-                continue;
-            }
+/// Splits segments into words, dividing each segment's time by word length; an estimate
+/// for when Whisper's word timings aren't available.
+pub fn estimate_words(recognized: &[TranscriptionSegment]) -> Vec<TranscriptionSegment> {
+    let mut words = Vec::new();
+    for segment in recognized {
+        let parts: Vec<&str> = segment.text.split_whitespace().collect();
+        let total: usize = parts.iter().map(|p| p.len()).sum();
+        let mut start = segment.start;
+        for part in parts {
+            let share = (segment.end - segment.start) * part.len() as f64 / total.max(1) as f64;
+            words.push(TranscriptionSegment {
+                text: part.to_string(),
+                start,
+                end: start + share,
+            });
+            start += share;
         }
     }
+    words
+}
+
+/// The first channel as Whisper takes it: peak-normalized to [-1, 1], low-passed and
+/// resampled to 16 kHz.
+pub fn prepare_audio_for_whisper(audio_data: &AudioData) -> Vec<f32> {
+    log::debug!("Preparing audio for Whisper...");
+    let sample_rate = audio_data.sample_rate;
+    log::debug!("Sample rate: {} Hz", sample_rate);
+    log::debug!("Channels: {}", audio_data.channels);
+    let mut samples = audio_data.samples.to_vec();
 
-    println!("Loaded {} samples", samples.len());
+    log::debug!("Loaded {} samples", samples.len());
     // Debug: Check sample values
     if !samples.is_empty() {
-        println!("First few samples: {:?}", &samples[..5.min(samples.len())]);
-        println!("Sample range: [{}, {}]", 
+        log::debug!("First few samples: {:?}", &samples[..5.min(samples.len())]);
+        log::debug!("Sample range: [{}, {}]", 
             samples.iter().fold(f32::INFINITY, |a, &b| a.min(b)),
             samples.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b))
         );
@@ -83,15 +61,25 @@ fn load_audio_for_whisper<P: AsRef<Path>>(path: P) -> Result<Vec<f32>> {
     // Normalize samples to [-1, 1] range if needed
     let max_abs = samples.iter().fold(0.0f32, |a, &b| a.max(b.abs()));
     if max_abs > 1.0 {
-        println!("Normalizing samples...");
+        log::debug!("Normalizing samples...");
         for sample in &mut samples {
             *sample /= max_abs;
         }
     }
 
+    // Low-pass under 8 kHz first (fourth-order Butterworth), so what lies above the new
+    // Nyquist frequency, such as the ultrasound of a bat recording, doesn't fold down into
+    // the speech band
+    if sample_rate > 16000 {
+        let mut sections = [0.5412, 1.3066].map(|q| Biquad::lowpass(sample_rate, 7000.0, q));
+        for sample in &mut samples {
+            *sample = sections.iter_mut().fold(*sample, |y, section| section.process(y));
+        }
+    }
+
     // Resample to 16kHz if needed
     if sample_rate != 16000 {
-        println!("Resampling from {}Hz to 16kHz...", sample_rate);
+        log::debug!("Resampling from {}Hz to 16kHz...", sample_rate);
         let ratio = 16000.0 / sample_rate as f32;
         let new_len = (samples.len() as f32 * ratio) as usize;
         let mut resampled = Vec::with_capacity(new_len);
@@ -103,26 +91,132 @@ fn load_audio_for_whisper<P: AsRef<Path>>(path: P) -> Result<Vec<f32>> {
             }
         }
         samples = resampled;
-        println!("Resampled to {} samples", samples.len());
+        log::debug!("Resampled to {} samples", samples.len());
     }
 
-    Ok(samples)
+    samples
 }
 
-pub fn transcribe_audio<P: AsRef<Path>>(path: P) -> Result<Vec<TranscriptionSegment>> {
-    println!("Starting transcription process...");
-    
-    // Load the audio
-    let audio_samples = load_audio_for_whisper(&path)?;
-    
-    // Load the model
-    println!("Loading Whisper model...");
-    let ctx = WhisperContext::new("whisper-base.bin")
-        .map_err(|e| anyhow!("Failed to load Whisper model: {}", e))?;
-    
-    // Configure parameters
-    println!("Configuring Whisper parameters...");
+/// Segments plus the words inside them, timed from Whisper's token timestamps.
+#[derive(Clone, Debug, Default)]
+pub struct Transcript {
+    pub segments: Vec<TranscriptionSegment>,
+    pub words: Vec<TranscriptionSegment>,
+}
+
+/// Merges the transcripts of a multi-track session's tracks, given with their names, into
+/// one in time order, each segment starting with its track's name.
+pub fn merge_tracks(transcripts: Vec<(String, Transcript)>) -> Transcript {
+    let mut merged = Transcript::default();
+    for (name, transcript) in transcripts {
+        merged.segments.extend(transcript.segments.into_iter().map(|segment| TranscriptionSegment {
+            text: format!("{}: {}", name, segment.text.trim()),
+            ..segment
+        }));
+        merged.words.extend(transcript.words);
+    }
+    merged.segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+    merged.words.sort_by(|a, b| a.start.total_cmp(&b.start));
+    merged
+}
+
+pub fn transcribe_audio(audio_data: &AudioData, model_path: &Path) -> Result<Vec<TranscriptionSegment>> {
+    Ok(run_whisper(audio_data, model_path, 0, 1, None)?.segments)
+}
+
+/// Transcribes into segments along with word-level timings.
+pub fn transcribe_with_words(audio_data: &AudioData, model_path: &Path) -> Result<Transcript> {
+    run_whisper(audio_data, model_path, 0, 1, None)
+}
+
+/// Like `transcribe_with_words`, but split into `jobs` chunks transcribed in parallel
+/// (see `run_whisper`), and giving up with an error once `cancel` is set. Whisper checks
+/// it before encoding each 30-second window, so a cancelled job stops within one window's
+/// work.
+pub fn transcribe_chunked(
+    audio_data: &AudioData,
+    model_path: &Path,
+    jobs: usize,
+    cancel: Option<&AtomicBool>,
+) -> Result<Transcript> {
+    run_whisper(audio_data, model_path, 0, jobs, cancel)
+}
+
+/// Transcribes with one segment per word, for word-level timings.
+pub fn transcribe_words(audio_data: &AudioData, model_path: &Path) -> Result<Vec<TranscriptionSegment>> {
+    Ok(run_whisper(audio_data, model_path, 1, 1, None)?.segments)
+}
+
+/// Times Whisper transcribing `samples` (as from `prepare_audio_for_whisper`) once per
+/// entry of `threads`, with that many threads, loading the model only once. Returns how
+/// long loading took, then each transcription.
+pub fn time_transcription(samples: &[f32], model_path: &Path, threads: &[usize]) -> Result<(Duration, Vec<Duration>)> {
+    let started = Instant::now();
+    let model = model_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Model path {} is not valid UTF-8", model_path.display()))?;
+    let ctx = WhisperContext::new(model)
+        .map_err(|e| anyhow!("Failed to load Whisper model {}: {}", model_path.display(), e))?;
+    let loading = started.elapsed();
+    let runs = threads
+        .iter()
+        .map(|&threads| {
+            let mut state = ctx.create_state()?;
+            let started = Instant::now();
+            state
+                .full(whisper_params(0, threads, None), samples)
+                .map_err(|e| anyhow!("Failed to process audio: {}", e))?;
+            Ok(started.elapsed())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((loading, runs))
+}
+
+/// Whisper's encoder callback: returning false aborts the transcription.
+unsafe extern "C" fn keep_encoding(
+    _ctx: *mut whisper_rs_sys::whisper_context,
+    _state: *mut whisper_rs_sys::whisper_state,
+    cancel: *mut std::ffi::c_void,
+) -> bool {
+    !(*(cancel as *const AtomicBool)).load(Ordering::Relaxed)
+}
+
+/// Sample rate Whisper transcribes at.
+pub const WHISPER_RATE: usize = 16000;
+
+/// Shortest chunk for parallel transcription, in seconds: Whisper decodes 30 s windows, so
+/// shorter chunks leave cores idle inside a window.
+const MIN_CHUNK: usize = 60;
+
+/// How far a chunk boundary may move from where an even split puts it, to land on the
+/// quietest 20 ms around it rather than mid-word, in seconds.
+const BOUNDARY_SEARCH: usize = 3;
+
+/// Ends of `jobs` roughly equal chunks of `samples` (at `WHISPER_RATE`), each boundary
+/// moved to the quietest 20 ms frame near it. Fewer chunks for short audio.
+fn chunk_bounds(samples: &[f32], jobs: usize) -> Vec<usize> {
+    let chunks = jobs.min(samples.len() / (MIN_CHUNK * WHISPER_RATE)).max(1);
+    let frame = WHISPER_RATE / 50;
+    let energy = |start: usize| samples[start..start + frame].iter().map(|x| x * x).sum::<f32>();
+    let mut bounds: Vec<usize> = (1..chunks)
+        .map(|i| {
+            let target = i * samples.len() / chunks;
+            let from = target.saturating_sub(BOUNDARY_SEARCH * WHISPER_RATE);
+            let to = (target + BOUNDARY_SEARCH * WHISPER_RATE).min(samples.len() - frame);
+            (from..to)
+                .step_by(frame)
+                .min_by(|&a, &b| energy(a).total_cmp(&energy(b)))
+                .unwrap_or(target)
+        })
+        .collect();
+    bounds.push(samples.len());
+    bounds
+}
+
+/// Whisper's decoding parameters; see `run_whisper`.
+fn whisper_params(max_len: i32, threads: usize, cancel: Option<&AtomicBool>) -> FullParams<'static, 'static> {
     let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_n_threads(threads as i32);
     params.set_language(Some("en"));
     params.set_print_special(false);
     params.set_print_progress(false);
@@ -133,68 +227,164 @@ pub fn transcribe_audio<P: AsRef<Path>>(path: P) -> Result<Vec<TranscriptionSegm
     params.set_no_context(true);
     params.set_single_segment(false);
     params.set_max_initial_ts(1.0);
-    params.set_max_len(0);
+    params.set_max_len(max_len);
     params.set_split_on_word(true);
+    if let Some(cancel) = cancel {
+        // SAFETY: the callback only reads the flag, which outlives the transcription the
+        // parameters are used for
+        unsafe {
+            params.set_start_encoder_callback(Some(keep_encoding));
+            params.set_start_encoder_callback_user_data(cancel as *const AtomicBool as *mut std::ffi::c_void);
+        }
+    }
+    params
+}
+
+/// Runs Whisper over the audio; `max_len` caps segment length in characters (0 = no limit),
+/// split at word boundaries. With `jobs` above one, the audio is split into that many
+/// chunks at quiet points, and each is transcribed by its own Whisper state on its own
+/// thread, sharing the loaded model. Stops early, with an error, once `cancel` is set.
+fn run_whisper(
+    audio_data: &AudioData,
+    model_path: &Path,
+    max_len: i32,
+    jobs: usize,
+    cancel: Option<&AtomicBool>,
+) -> Result<Transcript> {
+    log::debug!("Starting transcription process...");
     
-    // Create state
-    println!("Creating Whisper state...");
-    let mut state = ctx.create_state()?;
+    // Prepare the audio
+    let audio_samples = prepare_audio_for_whisper(audio_data);
     
-    // Process the audio
-    println!("Processing audio with Whisper ({} samples)...", audio_samples.len());
-    match state.full(params, &audio_samples) {
-        Ok(_) => println!("Successfully processed audio"),
-        Err(e) => {
-            println!("Error processing audio: {}", e);
-            return Err(anyhow!("Failed to process audio: {}", e));
+    // Load the model
+    log::debug!("Loading Whisper model from {}...", model_path.display());
+    let model = model_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Model path {} is not valid UTF-8", model_path.display()))?;
+    let ctx = WhisperContext::new(model)
+        .map_err(|e| anyhow!("Failed to load Whisper model {}: {}", model_path.display(), e))?;
+
+    let bounds = chunk_bounds(&audio_samples, jobs);
+    // Cores are shared between the states; Whisper's own default is up to four threads
+    let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let threads = if bounds.len() == 1 { cores.min(4) } else { (cores / bounds.len()).max(1) };
+    log::debug!("Transcribing {} chunks with {} threads each", bounds.len(), threads);
+    let transcribe = |from: usize, to: usize| -> Result<Transcript> {
+        log::debug!("Creating Whisper state...");
+        let mut state = ctx.create_state()?;
+        log::debug!("Processing audio with Whisper ({} samples)...", to - from);
+        match state.full(whisper_params(max_len, threads, cancel), &audio_samples[from..to]) {
+            Ok(_) => log::debug!("Successfully processed audio"),
+            Err(e) => {
+                log::error!("Error processing audio: {}", e);
+                return Err(anyhow!("Failed to process audio: {}", e));
+            }
         }
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(anyhow!("Transcription cancelled"));
+        }
+        read_transcript(&state, from as f64 / WHISPER_RATE as f64)
+    };
+
+    let chunks: Vec<Result<Transcript>> = if bounds.len() == 1 {
+        vec![transcribe(0, audio_samples.len())]
+    } else {
+        let starts = std::iter::once(0).chain(bounds.iter().copied());
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = starts
+                .zip(&bounds)
+                .map(|(from, &to)| scope.spawn(move || transcribe(from, to)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(anyhow!("A transcription thread panicked"))))
+                .collect()
+        })
+    };
+    let mut transcript = Transcript::default();
+    for chunk in chunks {
+        let chunk = chunk?;
+        transcript.segments.extend(chunk.segments);
+        transcript.words.extend(chunk.words);
+    }
+    
+    if transcript.segments.is_empty() {
+        log::warn!("No transcription segments were generated!");
+    } else {
+        log::debug!("Successfully generated {} transcription segments", transcript.segments.len());
     }
     
+    Ok(transcript)
+}
+
+/// The segments and words of a finished transcription, `offset` seconds into the audio.
+fn read_transcript(state: &WhisperState, offset: f64) -> Result<Transcript> {
     // Get the number of segments
     let num_segments = match state.full_n_segments() {
         Ok(n) => {
-            println!("Found {} segments", n);
+            log::debug!("Found {} segments", n);
             n
         },
         Err(e) => {
-            println!("Error getting segments: {}", e);
+            log::error!("Error getting segments: {}", e);
             return Err(anyhow!("Failed to get segments: {}", e));
         }
     };
     
     let mut segments = Vec::new();
+    let mut words: Vec<TranscriptionSegment> = Vec::new();
     
     // Process each segment
     for i in 0..num_segments {
-        println!("Processing segment {}", i);
+        log::debug!("Processing segment {}", i);
         
         let segment_text = state.full_get_segment_text(i)
             .map_err(|e| anyhow!("Failed to get segment text: {}", e))?;
         
         if segment_text.trim().is_empty() {
-            println!("Segment {} is empty, skipping", i);
+            log::debug!("Segment {} is empty, skipping", i);
             continue;
         }
         
-        let start = state.full_get_segment_t0(i)
+        let start = offset + state.full_get_segment_t0(i)
             .map_err(|e| anyhow!("Failed to get segment start time: {}", e))? as f64 / 100.0;
-        let end = state.full_get_segment_t1(i)
+        let end = offset + state.full_get_segment_t1(i)
             .map_err(|e| anyhow!("Failed to get segment end time: {}", e))? as f64 / 100.0;
         
-        println!("Segment {}: [{:.2}-{:.2}] {}", i, start, end, segment_text);
+        log::debug!("Segment {}: [{:.2}-{:.2}] {}", i, start, end, segment_text);
         
         segments.push(TranscriptionSegment {
             text: segment_text,
             start,
             end,
         });
+
+        // Tokens are word pieces; a leading space starts a new word
+        let num_tokens = state.full_n_tokens(i)
+            .map_err(|e| anyhow!("Failed to get token count: {}", e))?;
+        let mut new_word = true;
+        for t in 0..num_tokens {
+            let text = state.full_get_token_text(i, t)
+                .map_err(|e| anyhow!("Failed to get token text: {}", e))?;
+            if text.starts_with("[_") || text.starts_with("<|") {
+                continue;
+            }
+            let data = state.full_get_token_data(i, t)
+                .map_err(|e| anyhow!("Failed to get token data: {}", e))?;
+            let (t0, t1) = (offset + data.t0 as f64 / 100.0, offset + data.t1 as f64 / 100.0);
+            match words.last_mut() {
+                Some(word) if !new_word && !text.starts_with(' ') => {
+                    word.text.push_str(&text);
+                    word.end = t1.max(word.end);
+                }
+                _ => words.push(TranscriptionSegment {
+                    text: text.trim_start().to_string(),
+                    start: t0,
+                    end: t1,
+                }),
+            }
+            new_word = false;
+        }
     }
-    
-    if segments.is_empty() {
-        println!("Warning: No transcription segments were generated!");
-    } else {
-        println!("Successfully generated {} transcription segments", segments.len());
-    }
-    
-    Ok(segments)
-} 
\ No newline at end of file
+    Ok(Transcript { segments, words })
+}
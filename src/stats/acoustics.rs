@@ -0,0 +1,187 @@
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+/// Octave bands reverberation is measured in, by center frequency (ISO 3382).
+pub const BANDS: [f32; 7] = [125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0];
+
+/// Impulse response kept before its peak, so the filters' rise isn't cut off, in seconds.
+const PRE_PEAK: f64 = 0.001;
+
+/// Blocks the decay is smoothed over when looking for the noise floor, in seconds.
+const BLOCK: f64 = 0.01;
+
+/// Energy decay curve points kept for plotting and CSV, one per this many seconds.
+const CURVE_STEP: f64 = 0.01;
+
+/// Regularization of the sweep deconvolution, relative to the sweep's peak power: keeps
+/// frequencies the sweep didn't cover from being amplified into noise.
+const REGULARIZATION: f32 = 1e-3;
+
+/// Reverberation of one octave band (or the full band, with no center).
+#[derive(Clone, Debug, PartialEq)]
+pub struct BandDecay {
+    pub center: Option<f32>,
+    /// Early decay time: 0 to -10 dB, extrapolated to 60 dB, in seconds
+    pub edt: Option<f64>,
+    /// -5 to -25 dB, extrapolated to 60 dB
+    pub t20: Option<f64>,
+    /// -5 to -35 dB, extrapolated to 60 dB
+    pub t30: Option<f64>,
+    /// Schroeder energy decay curve: seconds after the direct sound and dB below the total
+    pub curve: Vec<(f64, f32)>,
+}
+
+impl BandDecay {
+    /// The reverberation time reported as RT60: T30 when the decay was measured far enough
+    /// above the noise, else T20.
+    pub fn rt60(&self) -> Option<f64> {
+        self.t30.or(self.t20)
+    }
+
+    /// `125 Hz`, `2 kHz` or `Full band`.
+    pub fn band_name(&self) -> String {
+        match self.center {
+            Some(center) if center >= 1000.0 => format!("{} kHz", center / 1000.0),
+            Some(center) => format!("{} Hz", center),
+            None => "Full band".to_string(),
+        }
+    }
+}
+
+/// The room's impulse response from a recording: with the `sweep` that was played, the
+/// recording deconvolved by it; otherwise the recording of an impulse (a clap, balloon pop
+/// or starter pistol) itself. Either way it starts just before the direct sound.
+pub fn impulse_response(recording: &[f32], sweep: Option<&[f32]>, sample_rate: u32) -> Vec<f32> {
+    let response = match sweep {
+        Some(sweep) => deconvolve(recording, sweep),
+        None => recording.to_vec(),
+    };
+    let peak = response
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+        .map_or(0, |(i, _)| i);
+    let start = peak.saturating_sub((PRE_PEAK * sample_rate as f64) as usize);
+    response[start..].to_vec()
+}
+
+fn deconvolve(recording: &[f32], sweep: &[f32]) -> Vec<f32> {
+    let size = (recording.len() + sweep.len()).next_power_of_two();
+    let mut planner = FftPlanner::new();
+    let forward = planner.plan_fft_forward(size);
+    let spectrum = |samples: &[f32]| {
+        let mut buffer: Vec<Complex<f32>> = samples.iter().map(|&s| Complex::new(s, 0.0)).collect();
+        buffer.resize(size, Complex::new(0.0, 0.0));
+        forward.process(&mut buffer);
+        buffer
+    };
+    let (recorded, played) = (spectrum(recording), spectrum(sweep));
+    let floor = played.iter().fold(0.0f32, |a, c| a.max(c.norm_sqr())) * REGULARIZATION;
+    let mut response: Vec<Complex<f32>> =
+        recorded.iter().zip(&played).map(|(r, s)| r * s.conj() / (s.norm_sqr() + floor)).collect();
+    planner.plan_fft_inverse(size).process(&mut response);
+    // Only the causal part, as long as the recording
+    response.iter().take(recording.len()).map(|c| c.re / size as f32).collect()
+}
+
+/// Energy decay and reverberation times of `ir` (at `sample_rate`) over the full band and
+/// in each octave band of `BANDS` below Nyquist.
+pub fn analyze(ir: &[f32], sample_rate: u32) -> Vec<BandDecay> {
+    let mut decays = vec![band_decay(None, ir, sample_rate)];
+    for &center in BANDS.iter().filter(|&&center| center * std::f32::consts::SQRT_2 < sample_rate as f32 / 2.0) {
+        let filtered = octave(ir, sample_rate, center);
+        decays.push(band_decay(Some(center), &filtered, sample_rate));
+    }
+    decays
+}
+
+/// `samples` with everything outside the octave around `center` removed in the frequency
+/// domain.
+fn octave(samples: &[f32], sample_rate: u32, center: f32) -> Vec<f32> {
+    let size = samples.len().next_power_of_two();
+    let mut planner = FftPlanner::new();
+    let mut buffer: Vec<Complex<f32>> = samples.iter().map(|&s| Complex::new(s, 0.0)).collect();
+    buffer.resize(size, Complex::new(0.0, 0.0));
+    planner.plan_fft_forward(size).process(&mut buffer);
+    let (low, high) = (center / std::f32::consts::SQRT_2, center * std::f32::consts::SQRT_2);
+    for (bin, value) in buffer.iter_mut().enumerate() {
+        // Bins above Nyquist mirror those below
+        let frequency = bin.min(size - bin) as f32 * sample_rate as f32 / size as f32;
+        if !(low..high).contains(&frequency) {
+            *value = Complex::new(0.0, 0.0);
+        }
+    }
+    planner.plan_fft_inverse(size).process(&mut buffer);
+    buffer.iter().take(samples.len()).map(|c| c.re / size as f32).collect()
+}
+
+fn band_decay(center: Option<f32>, ir: &[f32], sample_rate: u32) -> BandDecay {
+    let energy: Vec<f64> = ir[..truncation(ir, sample_rate)].iter().map(|&s| (s as f64).powi(2)).collect();
+    // Schroeder backward integration
+    let mut remaining = energy.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+    let total = remaining;
+    let mut decay = Vec::with_capacity(energy.len());
+    for e in &energy {
+        decay.push((10.0 * (remaining / total).max(1e-30).log10()) as f32);
+        remaining -= e;
+    }
+    let seconds = |i: usize| i as f64 / sample_rate as f64;
+    let step = ((CURVE_STEP * sample_rate as f64) as usize).max(1);
+    BandDecay {
+        center,
+        edt: decay_time(&decay, sample_rate, 0.0, -10.0),
+        t20: decay_time(&decay, sample_rate, -5.0, -25.0),
+        t30: decay_time(&decay, sample_rate, -5.0, -35.0),
+        curve: decay.iter().enumerate().step_by(step).map(|(i, &db)| (seconds(i), db)).collect(),
+    }
+}
+
+/// Where the decay of `ir` meets the noise floor, which is taken from its last tenth:
+/// integrating the noise past it would flatten the end of the curve.
+fn truncation(ir: &[f32], sample_rate: u32) -> usize {
+    let block = ((BLOCK * sample_rate as f64) as usize).max(1);
+    let power = |samples: &[f32]| samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32;
+    let noise = power(&ir[ir.len() - ir.len() / 10..]);
+    ir.chunks(block)
+        .position(|chunk| power(chunk) <= noise * 2.0)
+        .map_or(ir.len(), |index| (index * block).max(block).min(ir.len()))
+}
+
+/// Time the decay takes to fall 60 dB at the rate it falls from `from` to `to` dB, by a
+/// least-squares line through that part of the curve; `None` if it never gets to `to`.
+fn decay_time(decay: &[f32], sample_rate: u32, from: f32, to: f32) -> Option<f64> {
+    let start = decay.iter().position(|&db| db <= from)?;
+    let end = decay.iter().position(|&db| db <= to)?;
+    if end <= start + 1 {
+        return None;
+    }
+    let n = (end - start) as f64;
+    let (mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0);
+    for (i, &db) in decay[start..end].iter().enumerate() {
+        let (x, y) = ((start + i) as f64 / sample_rate as f64, db as f64);
+        sx += x;
+        sy += y;
+        sxx += x * x;
+        sxy += x * y;
+    }
+    let slope = (n * sxy - sx * sy) / (n * sxx - sx * sx);
+    (slope < 0.0).then(|| -60.0 / slope)
+}
+
+/// A table of `decays`: a header, then each band's EDT, T20, T30 and RT60 in seconds, with
+/// `-` where the decay didn't reach far enough above the noise to measure.
+pub fn report_lines(decays: &[BandDecay]) -> Vec<String> {
+    let seconds = |time: Option<f64>| time.map_or("-".to_string(), |time| format!("{:.2}", time));
+    let mut lines = vec![format!("{:<10} {:>6} {:>6} {:>6} {:>6}", "Band", "EDT", "T20", "T30", "RT60")];
+    for decay in decays {
+        lines.push(format!(
+            "{:<10} {:>6} {:>6} {:>6} {:>6}",
+            decay.band_name(),
+            seconds(decay.edt),
+            seconds(decay.t20),
+            seconds(decay.t30),
+            seconds(decay.rt60())
+        ));
+    }
+    lines
+}
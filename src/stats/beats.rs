@@ -0,0 +1,156 @@
+use crate::audio::filter::Biquad;
+
+/// Band heart sounds (S1, S2) put their energy in, in Hz: murmurs and lung sounds above it
+/// and the stethoscope's handling rumble below stay out of the envelope.
+pub const HEART_BAND: (f32, f32) = (25.0, 400.0);
+
+/// Rate the envelope is computed at, in Hz: 10 ms steps, fine enough for beat timing.
+pub const ENVELOPE_RATE: u32 = 100;
+
+/// Heart rates looked for, in beats per minute.
+const RATE_RANGE: (f32, f32) = (30.0, 200.0);
+
+/// Beats closer together than this share of the period are one beat: S2 after S1, or a
+/// split sound.
+const MIN_SPACING: f64 = 0.6;
+
+/// Envelope peaks below this share of the 95th percentile aren't beats.
+const THRESHOLD: f32 = 0.3;
+
+/// Beats found in a recording: their times and the rate they give.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Beats {
+    /// Seconds of each beat, in order
+    pub times: Vec<f64>,
+    /// Rate from the envelope's autocorrelation, in beats per minute
+    pub bpm: f32,
+}
+
+impl Beats {
+    /// Seconds between each beat and the next.
+    pub fn intervals(&self) -> Vec<f64> {
+        self.times.windows(2).map(|pair| pair[1] - pair[0]).collect()
+    }
+
+    /// Lowest and highest rate between consecutive beats, in beats per minute.
+    pub fn rate_range(&self) -> Option<(f32, f32)> {
+        let intervals = self.intervals();
+        let shortest = intervals.iter().copied().reduce(f64::min)?;
+        let longest = intervals.iter().copied().reduce(f64::max)?;
+        Some(((60.0 / longest) as f32, (60.0 / shortest) as f32))
+    }
+
+    /// Root mean square of the differences between successive intervals, in seconds: how
+    /// irregular the rhythm is from beat to beat.
+    pub fn rmssd(&self) -> Option<f64> {
+        let intervals = self.intervals();
+        let differences: Vec<f64> = intervals.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        (!differences.is_empty())
+            .then(|| (differences.iter().map(|d| d * d).sum::<f64>() / differences.len() as f64).sqrt())
+    }
+}
+
+/// Shannon energy envelope of `samples` (at `sample_rate`) in `band`, at `ENVELOPE_RATE`:
+/// the mean of -x² ln x² over each step, after normalizing to the peak. It stresses the
+/// medium-intensity heart sounds over both noise and the odd loud knock.
+pub fn envelope(samples: &[f32], sample_rate: u32, band: (f32, f32)) -> Vec<f32> {
+    let mut filters = [
+        Biquad::highpass(sample_rate, band.0, std::f32::consts::FRAC_1_SQRT_2),
+        Biquad::lowpass(sample_rate, band.1.min(sample_rate as f32 * 0.45), std::f32::consts::FRAC_1_SQRT_2),
+    ];
+    let filtered: Vec<f32> =
+        samples.iter().map(|&x| filters.iter_mut().fold(x, |y, filter| filter.process(y))).collect();
+    let peak = filtered.iter().fold(0.0f32, |a, &b| a.max(b.abs()));
+    if peak == 0.0 {
+        return Vec::new();
+    }
+    let step = (sample_rate / ENVELOPE_RATE).max(1) as usize;
+    filtered
+        .chunks(step)
+        .map(|chunk| {
+            let energy: f32 = chunk
+                .iter()
+                .map(|&x| {
+                    let power = (x / peak).powi(2);
+                    if power > 0.0 {
+                        -power * power.ln()
+                    } else {
+                        0.0
+                    }
+                })
+                .sum();
+            energy / chunk.len() as f32
+        })
+        .collect()
+}
+
+/// Beats of a heart recording: the rate from the strongest autocorrelation of the envelope
+/// in `RATE_RANGE`, then the strongest envelope peak of each period. With S1 and S2 both
+/// in the envelope, the full cycle correlates best, as the gaps between them differ.
+pub fn detect_beats(samples: &[f32], sample_rate: u32) -> Option<Beats> {
+    let envelope = envelope(samples, sample_rate, HEART_BAND);
+    let rate = ENVELOPE_RATE as f32;
+    let min_lag = (rate * 60.0 / RATE_RANGE.1).round() as usize;
+    let max_lag = (rate * 60.0 / RATE_RANGE.0).round() as usize;
+    // Two cycles at the slowest rate, so there's something to correlate
+    if envelope.len() <= max_lag * 2 {
+        return None;
+    }
+    let mean = envelope.iter().sum::<f32>() / envelope.len() as f32;
+    let centered: Vec<f32> = envelope.iter().map(|&e| e - mean).collect();
+    let (lag, score) = (min_lag..=max_lag)
+        .map(|lag| {
+            let score: f32 = centered.iter().zip(&centered[lag..]).map(|(&a, &b)| a * b).sum();
+            // Normalized by the overlap, so short lags don't win for having more terms
+            (lag, score / (centered.len() - lag) as f32)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if score <= 0.0 {
+        return None;
+    }
+
+    let mut sorted = envelope.clone();
+    sorted.sort_by(f32::total_cmp);
+    let threshold = sorted[sorted.len() * 95 / 100] * THRESHOLD;
+    let mut peaks: Vec<usize> = (1..envelope.len() - 1)
+        .filter(|&i| envelope[i] >= threshold && envelope[i] > envelope[i - 1] && envelope[i] >= envelope[i + 1])
+        .collect();
+    // Strongest first, each keeping weaker peaks within the spacing out
+    peaks.sort_by(|&a, &b| envelope[b].total_cmp(&envelope[a]));
+    let spacing = (lag as f64 * MIN_SPACING) as usize;
+    let mut beats: Vec<usize> = Vec::new();
+    for peak in peaks {
+        if beats.iter().all(|&beat| beat.abs_diff(peak) >= spacing) {
+            beats.push(peak);
+        }
+    }
+    beats.sort_unstable();
+    Some(Beats {
+        times: beats.iter().map(|&i| i as f64 / ENVELOPE_RATE as f64).collect(),
+        bpm: 60.0 * rate / lag as f32,
+    })
+}
+
+/// Summary of `beats` for the terminal, then one line per beat.
+pub fn report_lines(beats: Option<&Beats>) -> Vec<String> {
+    let Some(beats) = beats else {
+        return vec!["No regular beat detected".to_string()];
+    };
+    let mut lines = vec![format!("Rate:        {:.0} BPM", beats.bpm)];
+    lines.push(format!("Beats:       {}", beats.times.len()));
+    if let Some((low, high)) = beats.rate_range() {
+        lines.push(format!("Range:       {:.0}-{:.0} BPM", low, high));
+    }
+    if let Some(rmssd) = beats.rmssd() {
+        lines.push(format!("RMSSD:       {:.0} ms", rmssd * 1000.0));
+    }
+    lines.push(String::new());
+    let intervals = beats.intervals();
+    for (index, time) in beats.times.iter().enumerate() {
+        match intervals.get(index) {
+            Some(interval) => lines.push(format!("{:>9.2}s  next in {:.2}s ({:.0} BPM)", time, interval, 60.0 / interval)),
+            None => lines.push(format!("{:>9.2}s", time)),
+        }
+    }
+    lines
+}
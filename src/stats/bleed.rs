@@ -0,0 +1,129 @@
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use super::to_dbfs;
+
+/// Length of the windows compared, in seconds.
+const WINDOW: f64 = 0.5;
+
+/// Longest delay searched for: sound travels about 17 m in this time, more than any room
+/// puts between two microphones, in seconds.
+const MAX_DELAY: f64 = 0.05;
+
+/// Normalized cross-correlation above which a window of one track counts as containing
+/// the other.
+const THRESHOLD: f32 = 0.5;
+
+/// Windows where the source is quieter than this have nothing to bleed, in dBFS.
+const ACTIVE: f32 = -45.0;
+
+/// One track's sound turning up in another: microphone bleed, or the same content recorded twice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bleed {
+    /// Index of the track the sound comes from
+    pub source: usize,
+    /// Index of the track it turns up in
+    pub target: usize,
+    pub start: f64,
+    pub end: f64,
+    /// How much later the sound arrives in the target, in seconds
+    pub delay: f64,
+    /// Level of the copy relative to the source, in dB
+    pub level_db: f32,
+}
+
+/// Finds where each of `tracks` (at `sample_rate`) appears, delayed and attenuated, in the
+/// others. Windows are compared by cross-correlation over delays up to `MAX_DELAY`; only
+/// the louder track of a pair counts as the source, so each bleed is reported once.
+pub fn detect_bleed(tracks: &[&[f32]], sample_rate: u32) -> Vec<Bleed> {
+    let window = (WINDOW * sample_rate as f64) as usize;
+    let max_delay = (MAX_DELAY * sample_rate as f64) as usize;
+    let size = (window + max_delay).next_power_of_two();
+    let mut planner = FftPlanner::new();
+    let (forward, inverse) = (planner.plan_fft_forward(size), planner.plan_fft_inverse(size));
+    let len = tracks.iter().map(|track| track.len()).min().unwrap_or(0);
+
+    let mut found: Vec<Bleed> = Vec::new();
+    // Half-overlapping windows, so a burst isn't missed by falling across a boundary
+    for start in (0..len.saturating_sub(window + max_delay)).step_by(window / 2) {
+        let energies: Vec<f32> =
+            tracks.iter().map(|track| track[start..start + window].iter().map(|s| s * s).sum()).collect();
+        // A source's window zero-padded; a target's with the delays after it, so the
+        // correlation is linear over 0..=max_delay
+        let spectrum = |samples: &[f32]| {
+            let mut buffer: Vec<Complex<f32>> = samples.iter().map(|&s| Complex::new(s, 0.0)).collect();
+            buffer.resize(size, Complex::new(0.0, 0.0));
+            forward.process(&mut buffer);
+            buffer
+        };
+        let sources: Vec<_> = tracks.iter().map(|track| spectrum(&track[start..start + window])).collect();
+        let targets: Vec<_> = tracks.iter().map(|track| spectrum(&track[start..start + window + max_delay])).collect();
+
+        for source in 0..tracks.len() {
+            let level = to_dbfs((energies[source] / window as f32).sqrt());
+            if level < ACTIVE {
+                continue;
+            }
+            for target in (0..tracks.len()).filter(|&t| t != source && energies[t] < energies[source]) {
+                let mut product: Vec<Complex<f32>> =
+                    targets[target].iter().zip(&sources[source]).map(|(t, s)| t * s.conj()).collect();
+                inverse.process(&mut product);
+                // rustfft leaves the inverse unscaled
+                let (delay, peak) = product[..=max_delay]
+                    .iter()
+                    .enumerate()
+                    .map(|(delay, c)| (delay, c.re / size as f32))
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .unwrap_or((0, 0.0));
+                let correlation = peak / (energies[source] * energies[target]).sqrt().max(f32::MIN_POSITIVE);
+                if correlation < THRESHOLD {
+                    continue;
+                }
+                let time = start as f64 / sample_rate as f64;
+                let bleed = Bleed {
+                    source,
+                    target,
+                    start: time,
+                    end: time + WINDOW,
+                    delay: delay as f64 / sample_rate as f64,
+                    // The copy's gain: how much of the source the target's peak holds
+                    level_db: 20.0 * (peak / energies[source]).max(1e-6).log10(),
+                };
+                match found.iter_mut().rev().find(|b| b.source == source && b.target == target) {
+                    Some(last) if last.end >= time => {
+                        last.end = bleed.end;
+                        // Keep the strongest window's delay and level for the whole range
+                        if bleed.level_db > last.level_db {
+                            last.delay = bleed.delay;
+                            last.level_db = bleed.level_db;
+                        }
+                    }
+                    _ => found.push(bleed),
+                }
+            }
+        }
+    }
+    found.sort_by(|a, b| a.start.total_cmp(&b.start));
+    found
+}
+
+/// One line per bleed, naming tracks by `names`.
+pub fn report_lines(bleeds: &[Bleed], names: &[String]) -> Vec<String> {
+    if bleeds.is_empty() {
+        return vec!["No bleed between the tracks".to_string()];
+    }
+    bleeds
+        .iter()
+        .map(|bleed| {
+            format!(
+                "{} in {}: {:.1}s-{:.1}s, {:.1} ms later, {:.1} dB",
+                names[bleed.source],
+                names[bleed.target],
+                bleed.start,
+                bleed.end,
+                bleed.delay * 1000.0,
+                bleed.level_db
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,198 @@
+use crate::audio::SpectrogramData;
+
+/// Speed of sound in air at 20 °C, in m/s.
+pub const SPEED_OF_SOUND: f64 = 343.0;
+
+/// How far the tone is followed from its average frequency, as a ratio: a source passing
+/// at 60 m/s shifts by about 19% each way.
+const SEARCH_RATIO: f32 = 1.25;
+
+/// Frames where the tone stands less than this over the frame's median, in dB, are left out.
+const MIN_PROMINENCE: f32 = 10.0;
+
+/// Fewest tracked frames a fit is attempted on.
+const MIN_POINTS: usize = 8;
+
+/// Nelder-Mead iterations of the fit.
+const ITERATIONS: usize = 600;
+
+/// A source moving in a straight line at constant speed past a stationary listener, and the
+/// tone it emits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DopplerFit {
+    /// Frequency the source emits, in Hz, heard as it passes closest
+    pub source_frequency: f64,
+    /// Speed of the source, in m/s
+    pub speed: f64,
+    /// When the source is closest, in seconds
+    pub closest_time: f64,
+    /// How close it comes, in meters
+    pub closest_distance: f64,
+    /// Root mean square difference between the tracked tone and the curve, in Hz
+    pub rms_error: f64,
+}
+
+impl DopplerFit {
+    /// Frequency heard at `time`: the source frequency scaled by c / (c + v_r), v_r being
+    /// the speed at which the source moves away from the listener.
+    pub fn frequency_at(&self, time: f64) -> f64 {
+        model(&[self.source_frequency, self.speed, self.closest_time, self.closest_distance], time)
+    }
+
+    /// A line for the status bar.
+    pub fn describe(&self) -> String {
+        format!(
+            "Doppler: {:.1} m/s ({:.0} km/h), tone {:.1} Hz, closest {:.0} m at {:.2}s (RMS {:.1} Hz)",
+            self.speed,
+            self.speed * 3.6,
+            self.source_frequency,
+            self.closest_distance,
+            self.closest_time,
+            self.rms_error
+        )
+    }
+}
+
+/// Frequency heard at `time` for parameters `[f0, v, t0, d]`. Only the magnitudes of the
+/// speed and the distance matter, which keeps the fit free of constraints.
+fn model(parameters: &[f64; 4], time: f64) -> f64 {
+    let [frequency, speed, closest_time, distance] = *parameters;
+    let along = speed.abs() * (time - closest_time);
+    let range = (along * along + distance * distance).sqrt().max(f64::MIN_POSITIVE);
+    let receding = speed.abs() * along / range;
+    frequency * SPEED_OF_SOUND / (SPEED_OF_SOUND + receding)
+}
+
+/// The tone of `spectrogram` between `times` (seconds), as (seconds, Hz) per frame: the bin
+/// strongest on average up to `max_frequency`, then in each frame the peak within
+/// `SEARCH_RATIO` of it, interpolated between bins.
+pub fn track_tone(spectrogram: &SpectrogramData, times: (f64, f64), max_frequency: f32) -> Vec<(f64, f64)> {
+    let frames: Vec<usize> = (0..spectrogram.time_points.len())
+        .filter(|&frame| (times.0..=times.1).contains(&(spectrogram.time_points[frame] as f64)))
+        .collect();
+    let bins = spectrogram.frequencies.iter().take_while(|&&f| f <= max_frequency).count();
+    if frames.is_empty() || bins < 3 {
+        return Vec::new();
+    }
+    let Some(center) = (1..bins).max_by(|&a, &b| {
+        let mean = |bin: usize| frames.iter().map(|&frame| spectrogram.magnitudes[frame][bin]).sum::<f32>();
+        mean(a).total_cmp(&mean(b))
+    }) else {
+        return Vec::new();
+    };
+    let frequency = spectrogram.frequencies[center];
+    let bin_width = spectrogram.frequencies[1] - spectrogram.frequencies[0];
+    let low = ((frequency / SEARCH_RATIO / bin_width) as usize).max(1);
+    let high = ((frequency * SEARCH_RATIO / bin_width).ceil() as usize).min(bins - 2);
+
+    frames
+        .iter()
+        .filter_map(|&frame| {
+            let levels = &spectrogram.magnitudes[frame];
+            let peak = (low..=high).max_by(|&a, &b| levels[a].total_cmp(&levels[b]))?;
+            let mut sorted = levels.clone();
+            sorted.sort_by(f32::total_cmp);
+            if levels[peak] < sorted[sorted.len() / 2] + MIN_PROMINENCE {
+                return None;
+            }
+            // Vertex of the parabola through the peak and its neighbours
+            let (left, middle, right) = (levels[peak - 1], levels[peak], levels[peak + 1]);
+            let curvature = left - 2.0 * middle + right;
+            let offset = if curvature < 0.0 { 0.5 * (left - right) / curvature } else { 0.0 };
+            let time = spectrogram.time_points[frame] as f64;
+            Some((time, ((peak as f32 + offset) * bin_width) as f64))
+        })
+        .collect()
+}
+
+/// Fits the Doppler curve of a straight, constant-speed pass to `track` (seconds, Hz), by
+/// least squares from a start read off the curve: the tone heard approaching and receding
+/// gives the source frequency and speed, where it crosses the source frequency the time of
+/// closest approach, and how steeply it does the distance.
+pub fn fit_doppler(track: &[(f64, f64)]) -> Option<DopplerFit> {
+    if track.len() < MIN_POINTS {
+        return None;
+    }
+    let fifth = track.len() / 5;
+    let mean = |points: &[(f64, f64)]| points.iter().map(|p| p.1).sum::<f64>() / points.len() as f64;
+    let (approaching, receding) = (mean(&track[..fifth.max(1)]), mean(&track[track.len() - fifth.max(1)..]));
+    if approaching <= receding {
+        return None;
+    }
+    // Approaching f0 c / (c - v), receding f0 c / (c + v)
+    let speed = SPEED_OF_SOUND * (approaching - receding) / (approaching + receding);
+    let frequency = approaching * (SPEED_OF_SOUND - speed) / SPEED_OF_SOUND;
+    let crossing = track.iter().position(|p| p.1 < frequency).unwrap_or(track.len() / 2);
+    let closest_time = track[crossing].0;
+    // Near closest approach the tone falls at f0 v² / (c d) Hz per second
+    let span = (track[track.len() - 1].0 - track[0].0) / 10.0;
+    let near: Vec<&(f64, f64)> = track.iter().filter(|p| (p.0 - closest_time).abs() <= span).collect();
+    let slope = {
+        let n = near.len() as f64;
+        let (mt, mf) = (near.iter().map(|p| p.0).sum::<f64>() / n, near.iter().map(|p| p.1).sum::<f64>() / n);
+        let covariance: f64 = near.iter().map(|p| (p.0 - mt) * (p.1 - mf)).sum();
+        let variance: f64 = near.iter().map(|p| (p.0 - mt).powi(2)).sum();
+        if variance > 0.0 { covariance / variance } else { 0.0 }
+    };
+    let distance = if slope < 0.0 { frequency * speed * speed / (SPEED_OF_SOUND * -slope) } else { speed };
+
+    let error = |parameters: &[f64; 4]| -> f64 {
+        track.iter().map(|&(time, heard)| (model(parameters, time) - heard).powi(2)).sum()
+    };
+    let start = [frequency, speed, closest_time, distance.max(1.0)];
+    let steps = [frequency * 0.01, speed * 0.2 + 1.0, span.max(0.1), distance * 0.5 + 1.0];
+    let best = nelder_mead(error, start, steps);
+    Some(DopplerFit {
+        source_frequency: best[0],
+        speed: best[1].abs(),
+        closest_time: best[2],
+        closest_distance: best[3].abs(),
+        rms_error: (error(&best) / track.len() as f64).sqrt(),
+    })
+}
+
+/// Minimizes `f` from `start` with the Nelder-Mead simplex method, the first simplex
+/// spanning `steps` along each axis.
+fn nelder_mead(f: impl Fn(&[f64; 4]) -> f64, start: [f64; 4], steps: [f64; 4]) -> [f64; 4] {
+    let mut simplex: Vec<([f64; 4], f64)> = vec![(start, f(&start))];
+    for axis in 0..4 {
+        let mut point = start;
+        point[axis] += steps[axis];
+        simplex.push((point, f(&point)));
+    }
+    let along = |from: &[f64; 4], to: &[f64; 4], t: f64| -> [f64; 4] {
+        std::array::from_fn(|i| from[i] + t * (to[i] - from[i]))
+    };
+    for _ in 0..ITERATIONS {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let centroid: [f64; 4] = std::array::from_fn(|i| simplex[..4].iter().map(|(p, _)| p[i]).sum::<f64>() / 4.0);
+        let (worst, worst_value) = simplex[4];
+        let reflected = along(&centroid, &worst, -1.0);
+        let reflected_value = f(&reflected);
+        if reflected_value < simplex[0].1 {
+            let expanded = along(&centroid, &worst, -2.0);
+            let expanded_value = f(&expanded);
+            simplex[4] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+        } else if reflected_value < simplex[3].1 {
+            simplex[4] = (reflected, reflected_value);
+        } else {
+            let contracted = along(&centroid, &worst, 0.5);
+            let contracted_value = f(&contracted);
+            if contracted_value < worst_value {
+                simplex[4] = (contracted, contracted_value);
+            } else {
+                // Shrink everything toward the best point
+                let best = simplex[0].0;
+                for vertex in simplex.iter_mut().skip(1) {
+                    vertex.0 = along(&best, &vertex.0, 0.5);
+                    vertex.1 = f(&vertex.0);
+                }
+            }
+        }
+    }
+    simplex.into_iter().min_by(|a, b| a.1.total_cmp(&b.1)).map_or(start, |(point, _)| point)
+}
@@ -0,0 +1,209 @@
+use anyhow::Result;
+
+use crate::audio::{compute_spectrogram_with, decimation_factor, AudioData, SpectrogramParams};
+
+/// Engine speeds looked for, in RPM: from idle to a superbike's redline.
+pub const RPM_RANGE: (f32, f32) = (600.0, 16000.0);
+
+/// Rate the spectrum is analyzed at, in Hz: the first harmonics of the firing frequency
+/// all lie below its Nyquist frequency.
+const ANALYSIS_RATE: u32 = 4000;
+
+/// Window at `ANALYSIS_RATE`, a quarter of a second, and the step between frames in
+/// seconds.
+const WINDOW: usize = 1024;
+const HOP: f64 = 0.05;
+
+/// Harmonics of the firing frequency scored per candidate.
+const HARMONICS: usize = 8;
+
+/// Candidates are spaced this ratio apart: 1%, about 100 RPM at 10000 RPM.
+const STEP: f32 = 1.01;
+
+/// Furthest the track moves between frames, in candidate steps (8% in 50 ms), and what
+/// each step costs it, in dB of harmonic score.
+const MAX_JUMP: usize = 8;
+const JUMP_COST: f32 = 0.5;
+
+/// Frames whose harmonics stand less than this far over the spectrum's median, in dB, have
+/// no engine in them.
+const MIN_SCORE: f32 = 6.0;
+
+/// The engine whose sound is analyzed: how its firing frequency relates to its speed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Engine {
+    pub cylinders: u32,
+    /// Each cylinder fires every revolution instead of every other one
+    pub two_stroke: bool,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self { cylinders: 4, two_stroke: false }
+    }
+}
+
+impl Engine {
+    /// Ignition pulses per crankshaft revolution.
+    fn firings_per_revolution(&self) -> f32 {
+        self.cylinders.max(1) as f32 / if self.two_stroke { 1.0 } else { 2.0 }
+    }
+
+    /// Crankshaft speed in RPM at a firing frequency in Hz.
+    pub fn rpm(&self, firing_frequency: f32) -> f32 {
+        firing_frequency * 60.0 / self.firings_per_revolution()
+    }
+
+    /// Firing frequency in Hz at a crankshaft speed in RPM.
+    pub fn firing_frequency(&self, rpm: f32) -> f32 {
+        rpm * self.firings_per_revolution() / 60.0
+    }
+}
+
+/// The firing frequency of an engine over time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RpmTrack {
+    pub engine: Engine,
+    /// Seconds (the center of each frame) and the firing frequency in Hz, `None` where no
+    /// engine is heard
+    pub points: Vec<(f64, Option<f32>)>,
+}
+
+impl RpmTrack {
+    /// Engine speed at the frame nearest `time`, in RPM.
+    pub fn rpm_at(&self, time: f64) -> Option<f32> {
+        let index = self.points.partition_point(|&(t, _)| t < time);
+        let nearest = [index.checked_sub(1), Some(index)]
+            .into_iter()
+            .flatten()
+            .filter_map(|i| self.points.get(i))
+            .min_by(|a, b| (a.0 - time).abs().total_cmp(&(b.0 - time).abs()))?;
+        nearest.1.map(|frequency| self.engine.rpm(frequency))
+    }
+
+    /// Engine speeds of the frames it's heard in, in RPM.
+    pub fn rpms(&self) -> Vec<f32> {
+        self.points.iter().filter_map(|&(_, frequency)| frequency.map(|f| self.engine.rpm(f))).collect()
+    }
+}
+
+/// Tracks the dominant harmonic series of the first channel and reads it as `engine`'s
+/// firing frequency. Each frame scores every candidate in `RPM_RANGE` by how far its
+/// harmonics stand over the frame's median level; the track is the path through the frames
+/// with the best total score, less a cost for each step it moves, so it follows the engine
+/// through gear changes without jumping to other noise.
+pub fn track_rpm(audio: &AudioData, engine: Engine) -> Result<RpmTrack> {
+    let decimation = decimation_factor(audio.sample_rate, ANALYSIS_RATE);
+    let rate = audio.sample_rate as f32 / decimation as f32;
+    let params = SpectrogramParams {
+        hop_size: ((HOP * rate as f64) as usize).max(1),
+        decimation,
+        ..SpectrogramParams::new(WINDOW)
+    };
+    let spectrogram = compute_spectrogram_with(audio, &params, None)?;
+    let bin_width = rate / WINDOW as f32;
+
+    let low = engine.firing_frequency(RPM_RANGE.0);
+    // The second harmonic at least must be below Nyquist
+    let high = engine.firing_frequency(RPM_RANGE.1).min(rate / 4.0);
+    let candidates: Vec<f32> = std::iter::successors(Some(low), |&f| Some(f * STEP))
+        .take_while(|&f| f <= high)
+        .collect();
+    if candidates.is_empty() {
+        return Ok(RpmTrack { engine, points: Vec::new() });
+    }
+
+    let scores: Vec<Vec<f32>> = spectrogram
+        .magnitudes
+        .iter()
+        .map(|frame| {
+            let mut sorted = frame.clone();
+            sorted.sort_by(f32::total_cmp);
+            let median = sorted[sorted.len() / 2];
+            // Level over the median at a frequency, between bins
+            let level = |frequency: f32| {
+                let position = frequency / bin_width;
+                let bin = position as usize;
+                let fraction = position - bin as f32;
+                let at = |i: usize| frame.get(i).map_or(0.0, |&db| (db - median).max(0.0));
+                at(bin) * (1.0 - fraction) + at(bin + 1) * fraction
+            };
+            candidates
+                .iter()
+                .map(|&f0| {
+                    let harmonics: Vec<f32> = (1..=HARMONICS)
+                        .map(|h| f0 * h as f32)
+                        .take_while(|&f| f < rate / 2.0)
+                        .map(&level)
+                        .collect();
+                    harmonics.iter().sum::<f32>() / harmonics.len().max(1) as f32
+                })
+                .collect()
+        })
+        .collect();
+
+    // Best path (Viterbi) over the frames, steps between neighbouring frames limited to
+    // `MAX_JUMP`
+    let mut total = scores.first().cloned().unwrap_or_default();
+    let mut from: Vec<Vec<u16>> = Vec::with_capacity(scores.len());
+    for frame in scores.iter().skip(1) {
+        let mut next = vec![f32::NEG_INFINITY; candidates.len()];
+        let mut back = vec![0u16; candidates.len()];
+        for (j, &score) in frame.iter().enumerate() {
+            let (best, value) = (j.saturating_sub(MAX_JUMP)..(j + MAX_JUMP + 1).min(candidates.len()))
+                .map(|k| (k, total[k] - JUMP_COST * k.abs_diff(j) as f32))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap_or((j, 0.0));
+            next[j] = value + score;
+            back[j] = best as u16;
+        }
+        total = next;
+        from.push(back);
+    }
+    let Some(mut index) = (0..total.len()).max_by(|&a, &b| total[a].total_cmp(&total[b])) else {
+        return Ok(RpmTrack { engine, points: Vec::new() });
+    };
+    let mut path = vec![index; scores.len()];
+    for (frame, back) in from.iter().enumerate().rev() {
+        index = back[index] as usize;
+        path[frame] = index;
+    }
+
+    let center = WINDOW as f64 / 2.0 / rate as f64;
+    let points = spectrogram
+        .time_points
+        .iter()
+        .zip(path.iter().zip(&scores))
+        .map(|(&time, (&index, frame))| {
+            let heard = frame[index] >= MIN_SCORE;
+            (time as f64 + center, heard.then_some(candidates[index]))
+        })
+        .collect();
+    Ok(RpmTrack { engine, points })
+}
+
+/// Summary of `track` for the terminal.
+pub fn report_lines(track: &RpmTrack) -> Vec<String> {
+    let engine = track.engine;
+    let name = format!(
+        "{} cylinder{}, {}",
+        engine.cylinders,
+        if engine.cylinders == 1 { "" } else { "s" },
+        if engine.two_stroke { "two-stroke" } else { "four-stroke" }
+    );
+    let rpms = track.rpms();
+    if rpms.is_empty() {
+        return vec![format!("No engine heard ({})", name)];
+    }
+    let low = rpms.iter().copied().fold(f32::INFINITY, f32::min);
+    let high = rpms.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let mean = rpms.iter().sum::<f32>() / rpms.len() as f32;
+    vec![format!(
+        "Engine ({}): {:.0}-{:.0} RPM, mean {:.0} RPM, heard {:.0}% of the time",
+        name,
+        low,
+        high,
+        mean,
+        100.0 * rpms.len() as f32 / track.points.len() as f32
+    )]
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+
+use crate::audio::{compute_spectrogram_with, AudioData, SpectrogramParams};
+
+/// Analysis window: about 5 ms at 48 kHz, short enough for the trills and chips of bird
+/// calls.
+const WINDOW: usize = 256;
+
+/// How far above the background (the median frame) the band must rise to start an event,
+/// in dB.
+const THRESHOLD_DB: f32 = 10.0;
+
+/// Events closer together than this are one call, in seconds.
+const MIN_GAP: f64 = 0.03;
+
+/// Events shorter than this are clicks rather than calls, in seconds.
+const MIN_DURATION: f64 = 0.02;
+
+/// An event's frequency range reaches out from its peak as far as the spectrum stays
+/// within this of the peak, in dB.
+const RANGE_DB: f32 = 20.0;
+
+/// A burst of sound standing out of the background: a call, song phrase or other
+/// acoustic event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AcousticEvent {
+    pub start: f64,
+    pub end: f64,
+    pub low_frequency: f32,
+    pub high_frequency: f32,
+    /// Frequency with the most energy over the event
+    pub peak_frequency: f32,
+    /// Loudest bin of the event, in dBFS
+    pub peak_level_db: f32,
+}
+
+/// Events in `band` (Hz) of the first channel: runs of frames whose energy in the band is
+/// `THRESHOLD_DB` over the median frame, merged across gaps under `MIN_GAP`.
+pub fn detect_events(audio: &AudioData, band: (f32, f32)) -> Result<Vec<AcousticEvent>> {
+    let spectrogram = compute_spectrogram_with(audio, &SpectrogramParams::new(WINDOW), None)?;
+    let bins: Vec<usize> = (0..spectrogram.frequencies.len())
+        .filter(|&bin| (band.0..=band.1).contains(&spectrogram.frequencies[bin]))
+        .collect();
+    let (Some(&first), Some(&last)) = (bins.first(), bins.last()) else {
+        return Ok(Vec::new());
+    };
+    let power = |db: f32| 10f32.powf(db / 10.0);
+    let levels: Vec<f32> = spectrogram
+        .magnitudes
+        .iter()
+        .map(|frame| 10.0 * frame[first..=last].iter().map(|&db| power(db)).sum::<f32>().max(1e-20).log10())
+        .collect();
+    let mut sorted = levels.clone();
+    sorted.sort_by(f32::total_cmp);
+    let Some(&background) = sorted.get(sorted.len() / 2) else {
+        return Ok(Vec::new());
+    };
+
+    // Frame ranges above the threshold, merged across short gaps
+    let frame_time = |frame: usize| spectrogram.time_points[frame] as f64;
+    let frame_length = WINDOW as f64 / audio.sample_rate as f64;
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (frame, &level) in levels.iter().enumerate() {
+        if level < background + THRESHOLD_DB {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((_, end)) if frame_time(frame) - frame_time(*end) <= MIN_GAP => *end = frame,
+            _ => runs.push((frame, frame)),
+        }
+    }
+
+    Ok(runs
+        .into_iter()
+        .map(|(from, to)| (frame_time(from), frame_time(to) + frame_length, from, to))
+        .filter(|&(start, end, _, _)| end - start >= MIN_DURATION)
+        .map(|(start, end, from, to)| {
+            let frames = &spectrogram.magnitudes[from..=to];
+            // Energy of each bin over the event
+            let totals: Vec<f32> =
+                (first..=last).map(|bin| frames.iter().map(|frame| power(frame[bin])).sum()).collect();
+            let peak = (0..totals.len()).max_by(|&a, &b| totals[a].total_cmp(&totals[b])).unwrap_or(0);
+            let floor = totals[peak] / power(RANGE_DB);
+            let low = (0..peak).rev().take_while(|&i| totals[i] >= floor).last().unwrap_or(peak);
+            let high = (peak + 1..totals.len()).take_while(|&i| totals[i] >= floor).last().unwrap_or(peak);
+            let frequency = |i: usize| spectrogram.frequencies[first + i];
+            AcousticEvent {
+                start,
+                end,
+                low_frequency: frequency(low),
+                high_frequency: frequency(high),
+                peak_frequency: frequency(peak),
+                peak_level_db: frames.iter().flat_map(|frame| &frame[first..=last]).fold(f32::NEG_INFINITY, |a, &b| a.max(b)),
+            }
+        })
+        .collect())
+}
+
+/// One line per event, for the terminal.
+pub fn report_lines(events: &[AcousticEvent]) -> Vec<String> {
+    if events.is_empty() {
+        return vec!["No events detected".to_string()];
+    }
+    events
+        .iter()
+        .map(|event| {
+            format!(
+                "{:.3}s-{:.3}s: {:.0}-{:.0} Hz, peak {:.0} Hz at {:.1} dBFS",
+                event.start, event.end, event.low_frequency, event.high_frequency, event.peak_frequency, event.peak_level_db
+            )
+        })
+        .collect()
+}
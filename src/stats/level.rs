@@ -0,0 +1,191 @@
+use crate::audio::weighting::{weight, Weighting};
+
+/// Time constant of the Fast time weighting, in seconds.
+pub const FAST: f64 = 0.125;
+
+/// Spacing of the level track, in seconds.
+pub const LEVEL_STEP: f64 = 0.1;
+
+/// Noise dose criterion (NIOSH): 85 dB(A) over 8 hours is 100%, and every 3 dB more halves
+/// the time allowed.
+pub const DOSE_CRITERION: f32 = 85.0;
+pub const DOSE_HOURS: f64 = 8.0;
+pub const EXCHANGE_RATE: f32 = 3.0;
+
+/// Widest bar of the Leq chart, in characters.
+const BAR_WIDTH: usize = 40;
+
+/// Sound level over time, as a sound level meter shows it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelTrack {
+    pub weighting: Weighting,
+    /// Offset in dB making the levels dB SPL (the level a full-scale sine measures at), 0
+    /// for levels in dBFS
+    pub calibration: f32,
+    /// Fast-weighted level at the end of each `LEVEL_STEP`, in dB
+    pub levels: Vec<f32>,
+    /// Equivalent continuous level of each `LEVEL_STEP`, in dB
+    pub steps: Vec<f32>,
+    /// Equivalent continuous level over the whole recording, in dB
+    pub leq: Option<f32>,
+}
+
+impl LevelTrack {
+    /// Unit of the levels, e.g. `dB(A)`, or `dBFS(A)` without calibration.
+    pub fn unit(&self) -> String {
+        let scale = if self.calibration != 0.0 { "dB" } else { "dBFS" };
+        format!("{}({})", scale, self.weighting.letter())
+    }
+
+    /// Name of a measured quantity with the weighting in it, e.g. `LAeq` for `eq`.
+    pub fn quantity(&self, name: &str) -> String {
+        format!("L{}{}", self.weighting.letter(), name)
+    }
+
+    /// Level at the step `time` falls in.
+    pub fn level_at(&self, time: f64) -> Option<f32> {
+        self.levels.get((time.max(0.0) / LEVEL_STEP) as usize).copied()
+    }
+
+    /// Leq of each `interval` seconds (whole steps, at least one), as (start in seconds,
+    /// dB); the last may be shorter.
+    pub fn leq_series(&self, interval: f64) -> Vec<(f64, f32)> {
+        let steps = ((interval / LEVEL_STEP).round() as usize).max(1);
+        self.steps
+            .chunks(steps)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let energy = chunk.iter().map(|&db| 10f64.powf(db as f64 / 10.0)).sum::<f64>() / chunk.len() as f64;
+                ((index * steps) as f64 * LEVEL_STEP, (10.0 * energy.log10()) as f32)
+            })
+            .collect()
+    }
+
+    /// Length of the recording, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.steps.len() as f64 * LEVEL_STEP
+    }
+
+    /// Leq normalized to an 8-hour day (LEX,8h): the level that, held for 8 hours, carries
+    /// the recording's energy.
+    pub fn daily_exposure(&self) -> Option<f32> {
+        let duration = self.duration();
+        let leq = self.leq.filter(|_| duration > 0.0)?;
+        Some(leq + (10.0 * (duration / (DOSE_HOURS * 3600.0)).log10()) as f32)
+    }
+
+    /// Noise dose of the recording, in percent of the daily `DOSE_CRITERION`: each
+    /// `EXCHANGE_RATE` over it halves the time allowed.
+    pub fn dose(&self) -> Option<f32> {
+        let duration = self.duration();
+        let leq = self.leq.filter(|_| duration > 0.0)?;
+        let allowed = DOSE_HOURS * 3600.0 / 2f64.powf(((leq - DOSE_CRITERION) / EXCHANGE_RATE) as f64);
+        Some((100.0 * duration / allowed) as f32)
+    }
+
+    /// Level exceeded `percent` of the time: L10 is the level of the louder events, L90 the
+    /// background.
+    pub fn exceeded(&self, percent: f32) -> Option<f32> {
+        let mut sorted: Vec<f32> = self.levels.iter().copied().filter(|l| l.is_finite()).collect();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+        let index = ((sorted.len() as f32 * percent / 100.0) as usize).min(sorted.len().checked_sub(1)?);
+        Some(sorted[index])
+    }
+}
+
+/// Level in dB of a mean square, a full-scale sine (mean square 1/2) being 0 dBFS.
+pub fn mean_square_db(mean_square: f64, calibration: f32) -> f32 {
+    (10.0 * (2.0 * mean_square).max(1e-20).log10()) as f32 + calibration
+}
+
+/// Fast-weighted level of `samples` at `sample_rate` through `weighting`, every
+/// `LEVEL_STEP`, and the Leq over all of them.
+pub fn level_track(samples: &[f32], sample_rate: u32, weighting: Weighting, calibration: f32) -> LevelTrack {
+    let weighted = weight(samples, sample_rate, weighting);
+    let step = ((LEVEL_STEP * sample_rate as f64) as usize).max(1);
+    let decay = 1.0 - (-1.0 / (FAST * sample_rate as f64)).exp();
+    let mut fast = 0.0f64;
+    let mut total = 0.0f64;
+    let (levels, steps) = weighted
+        .chunks(step)
+        .map(|chunk| {
+            let mut energy = 0.0f64;
+            for &x in chunk {
+                let square = x as f64 * x as f64;
+                fast += (square - fast) * decay;
+                energy += square;
+            }
+            total += energy;
+            (mean_square_db(fast, calibration), mean_square_db(energy / chunk.len() as f64, calibration))
+        })
+        .unzip();
+    let leq = (!weighted.is_empty()).then(|| mean_square_db(total / weighted.len() as f64, calibration));
+    LevelTrack { weighting, calibration, levels, steps, leq }
+}
+
+/// Summary of `track` for the terminal.
+pub fn report_lines(track: &LevelTrack) -> Vec<String> {
+    let Some(leq) = track.leq else {
+        return vec!["No audio to measure".to_string()];
+    };
+    let unit = track.unit();
+    let max = track.levels.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let min = track.levels.iter().copied().fold(f32::INFINITY, f32::min);
+    let mut lines = vec![
+        format!("{:<13}{:.1} {}", track.quantity("eq") + ":", leq, unit),
+        format!("{:<13}{:.1} {}", track.quantity("Fmax") + ":", max, unit),
+        format!("{:<13}{:.1} {}", track.quantity("Fmin") + ":", min, unit),
+    ];
+    for percent in [10, 50, 90] {
+        if let Some(level) = track.exceeded(percent as f32) {
+            lines.push(format!("{:<13}{:.1} {}", track.quantity(&percent.to_string()) + ":", level, unit));
+        }
+    }
+    if track.calibration == 0.0 {
+        lines.push("Uncalibrated: pass --calibration-offset for dB SPL".to_string());
+    }
+    lines
+}
+
+/// Leq of each `interval` seconds of `track` for the terminal, as a bar chart over the
+/// series' range, after the overall Leq and, when calibrated, the daily exposure and noise
+/// dose.
+pub fn leq_report_lines(track: &LevelTrack, interval: f64) -> Vec<String> {
+    let Some(leq) = track.leq else {
+        return vec!["No audio to measure".to_string()];
+    };
+    let unit = track.unit();
+    let duration = clock(track.duration());
+    let mut lines = vec![format!("{:<13}{:.1} {} over {}", track.quantity("eq") + ":", leq, unit, duration)];
+    if track.calibration != 0.0 {
+        if let (Some(exposure), Some(dose)) = (track.daily_exposure(), track.dose()) {
+            lines.push(format!("{:<13}{:.1} {}", track.quantity("EX,8h") + ":", exposure, unit));
+            lines.push(format!(
+                "{:<13}{:.1}% ({:.0} {} for {:.0} h, {:.0} dB exchange rate)",
+                "Dose:", dose, DOSE_CRITERION, unit, DOSE_HOURS, EXCHANGE_RATE
+            ));
+        }
+    }
+    let series = track.leq_series(interval);
+    let finite = || series.iter().map(|p| p.1).filter(|l| l.is_finite());
+    let low = finite().fold(f32::INFINITY, f32::min);
+    let high = finite().fold(f32::NEG_INFINITY, f32::max);
+    lines.push(String::new());
+    for (start, level) in series {
+        let fraction = if high > low { ((level - low) / (high - low)).clamp(0.0, 1.0) } else { 1.0 };
+        let bar = "█".repeat(1 + (fraction * (BAR_WIDTH - 1) as f32).round() as usize);
+        lines.push(format!("{:>10}  {:>6.1}  {}", clock(start), level, bar));
+    }
+    lines
+}
+
+/// `h:mm:ss`, or `m:ss` under an hour.
+fn clock(seconds: f64) -> String {
+    let whole = seconds.round() as u64;
+    let (hours, minutes, seconds) = (whole / 3600, whole / 60 % 60, whole % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
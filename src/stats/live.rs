@@ -0,0 +1,167 @@
+use anyhow::Result;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::pitch::PitchTracker;
+use super::to_dbfs;
+use crate::audio::WindowFunction;
+
+/// Samples per analysis window.
+const WINDOW: usize = 2048;
+
+/// Samples between analyses: about 10 ms at 48 kHz, quick enough for lights to follow beats.
+const HOP: usize = 512;
+
+/// Centers of the octave bands reported, in Hz.
+pub const BANDS: [f32; 8] = [63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0];
+
+/// Spectral flux this many times its recent average marks an onset.
+const ONSET_RATIO: f32 = 1.5;
+
+/// Frames of flux history the onset threshold averages.
+const FLUX_HISTORY: usize = 20;
+
+/// Shortest time between onsets, in seconds.
+const REFRACTORY: f64 = 0.1;
+
+/// Frames quieter than this have no onsets, in dBFS.
+const ONSET_FLOOR: f32 = -50.0;
+
+/// Features of one analysis window, as sent to lighting rigs and synths.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiveFrame {
+    /// Level of each of `BANDS` in dBFS
+    pub bands: Vec<f32>,
+    /// Overall RMS level in dBFS
+    pub level: f32,
+    /// Strength of an onset starting in this frame (the flux over its threshold), if any
+    pub onset: Option<f32>,
+    pub f0: Option<f32>,
+}
+
+/// Band energies, onsets and pitch of audio arriving in successive windows.
+pub struct LiveAnalyzer {
+    sample_rate: u32,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    pitch: PitchTracker,
+    previous: Vec<f32>,
+    flux: Vec<f32>,
+    /// Seconds since the last onset
+    since_onset: f64,
+}
+
+impl LiveAnalyzer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            fft: FftPlanner::new().plan_fft_forward(WINDOW),
+            window: WindowFunction::Hann.coefficients(WINDOW),
+            pitch: PitchTracker::new(sample_rate),
+            previous: Vec::new(),
+            flux: Vec::new(),
+            since_onset: f64::INFINITY,
+        }
+    }
+
+    /// Samples `analyze` needs: the FFT window, or the pitch tracker's frame if longer.
+    pub fn frame_len(&self) -> usize {
+        WINDOW.max(self.pitch.frame_len())
+    }
+
+    /// Analyzes the latest `frame_len()` samples of `recent`, `hop` samples after the
+    /// previous call.
+    pub fn analyze(&mut self, recent: &[f32], hop: usize) -> LiveFrame {
+        self.since_onset += hop as f64 / self.sample_rate as f64;
+        let frame = &recent[recent.len().saturating_sub(self.frame_len())..];
+        let newest = &frame[frame.len().saturating_sub(WINDOW)..];
+
+        let mut buffer: Vec<Complex<f32>> = (0..WINDOW)
+            .map(|i| Complex::new(newest.get(i).copied().unwrap_or(0.0) * self.window[i], 0.0))
+            .collect();
+        self.fft.process(&mut buffer);
+        // A full-scale sine then peaks at 0 dB
+        let scale = 2.0 / self.window.iter().sum::<f32>();
+        let magnitudes: Vec<f32> = buffer[..WINDOW / 2].iter().map(|c| c.norm() * scale).collect();
+        let resolution = self.sample_rate as f32 / WINDOW as f32;
+        let bands = BANDS
+            .iter()
+            .map(|&center| {
+                let (low, high) = (center / std::f32::consts::SQRT_2, center * std::f32::consts::SQRT_2);
+                let power: f32 = magnitudes
+                    .iter()
+                    .enumerate()
+                    .filter(|&(bin, _)| (low..high).contains(&(bin as f32 * resolution)))
+                    .map(|(_, m)| m * m)
+                    .sum();
+                // Half the sum: a sine's peak magnitude squared is twice its power
+                10.0 * (power / 2.0).max(1e-12).log10()
+            })
+            .collect();
+        let level = to_dbfs((newest.iter().map(|x| x * x).sum::<f32>() / newest.len().max(1) as f32).sqrt());
+
+        let flux: f32 = magnitudes
+            .iter()
+            .zip(&self.previous)
+            .map(|(now, before)| (now - before).max(0.0))
+            .sum();
+        let average = self.flux.iter().sum::<f32>() / self.flux.len().max(1) as f32;
+        let onset = (self.flux.len() == FLUX_HISTORY
+            && level > ONSET_FLOOR
+            && flux > ONSET_RATIO * average
+            && self.since_onset >= REFRACTORY)
+            .then(|| flux / average.max(f32::MIN_POSITIVE));
+        if onset.is_some() {
+            self.since_onset = 0.0;
+        }
+        self.flux.push(flux);
+        if self.flux.len() > FLUX_HISTORY {
+            self.flux.remove(0);
+        }
+        self.previous = magnitudes;
+
+        LiveFrame {
+            bands,
+            level,
+            onset,
+            f0: self.pitch.estimate(frame),
+        }
+    }
+}
+
+/// Somewhere live features are sent, e.g. an OSC or MIDI destination.
+pub trait LiveOutput: Send {
+    fn send(&mut self, frame: &LiveFrame) -> Result<()>;
+}
+
+/// Analyzes audio as it arrives, every `HOP` samples, and sends each frame to `outputs`.
+/// `audio(from)` returns the mono samples from index `from` on. Runs until `stop` is set.
+pub fn stream(
+    sample_rate: u32,
+    audio: impl Fn(usize) -> Vec<f32>,
+    outputs: &mut [Box<dyn LiveOutput>],
+    stop: &AtomicBool,
+) -> Result<()> {
+    let mut analyzer = LiveAnalyzer::new(sample_rate);
+    let poll = Duration::from_secs_f64(HOP as f64 / sample_rate as f64 / 2.0);
+    // End of the last window analyzed
+    let mut analyzed: usize = 0;
+    while !stop.load(Ordering::Relaxed) {
+        let from = analyzed.saturating_sub(analyzer.frame_len());
+        let recent = audio(from);
+        if from + recent.len() < analyzed + HOP {
+            std::thread::sleep(poll);
+            continue;
+        }
+        // Falling behind skips frames rather than sending stale ones
+        analyzed = (from + recent.len()) / HOP * HOP;
+        let frame = analyzer.analyze(&recent[..analyzed - from], HOP);
+        for output in outputs.iter_mut() {
+            output.send(&frame)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use anyhow::{anyhow, Result};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use super::to_dbfs;
+use crate::audio::WindowFunction;
+
+/// Captures whose fundamental is quieter than this hold nothing to measure, in dBFS.
+const SILENT: f32 = -80.0;
+
+/// Harmonics counted in THD, from the second up.
+const HARMONICS: usize = 10;
+
+/// Bins either side of a tone's peak that hold its power under the Blackman window's main
+/// lobe.
+const LOBE: usize = 4;
+
+/// Lowest frequency left in THD+N, below which hum and drift aren't the device's
+/// distortion, in Hz.
+const LOW_CUT: f32 = 20.0;
+
+/// Length of the impulse response the frequency response is taken from, in seconds: long
+/// enough for a device chain's filters, short enough to leave out echoes and noise.
+const RESPONSE_WINDOW: f64 = 0.2;
+
+/// Harmonic distortion of a device chain playing a pure tone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distortion {
+    /// Frequency of the fundamental as captured, in Hz
+    pub fundamental: f32,
+    /// Peak level of the fundamental, in dBFS
+    pub level_db: f32,
+    /// Total harmonic distortion: harmonics' RMS over the fundamental's, as a ratio
+    pub thd: f32,
+    /// Total harmonic distortion plus noise: everything but the fundamental, over it
+    pub thd_n: f32,
+    /// Each harmonic from the second, with its frequency and level relative to the
+    /// fundamental in dB
+    pub harmonics: Vec<(f32, f32)>,
+}
+
+/// THD and THD+N of the capture of a tone at `frequency`, from the middle half of
+/// `samples` so the silence of playback latency at the start and of the tail at the end is
+/// left out.
+pub fn distortion(samples: &[f32], sample_rate: u32, frequency: f32) -> Result<Distortion> {
+    let middle = &samples[samples.len() / 4..samples.len() * 3 / 4];
+    let size = middle.len().next_power_of_two() / 2;
+    if size < 1024 {
+        return Err(anyhow!("The capture is too short to measure distortion"));
+    }
+    let window = WindowFunction::Blackman.coefficients(size);
+    let mut buffer: Vec<Complex<f32>> = middle.iter().zip(&window).map(|(s, w)| Complex::new(s * w, 0.0)).collect();
+    FftPlanner::new().plan_fft_forward(size).process(&mut buffer);
+    let power: Vec<f32> = buffer[..size / 2].iter().map(|c| c.norm_sqr()).collect();
+    let resolution = sample_rate as f32 / size as f32;
+
+    // The strongest bin within 5% of the tone, as the device may not run at exactly the
+    // nominal rate
+    let bin_of = |frequency: f32| (frequency / resolution).round() as usize;
+    let search = bin_of(frequency * 0.95)..bin_of(frequency * 1.05).min(power.len() - 1) + 1;
+    let peak = search
+        .max_by(|&a, &b| power[a].total_cmp(&power[b]))
+        .ok_or_else(|| anyhow!("{} Hz is above the capture's Nyquist frequency", frequency))?;
+    let lobe = |center: usize| &power[center.saturating_sub(LOBE)..(center + LOBE + 1).min(power.len())];
+    let fundamental_power: f32 = lobe(peak).iter().sum();
+    // By Parseval, a sine of amplitude A puts N * A² / 4 * sum(w²) in the positive bins
+    let window_power: f32 = window.iter().map(|w| w * w).sum();
+    let level_db = to_dbfs(2.0 * (fundamental_power / (size as f32 * window_power)).sqrt());
+    if level_db < SILENT {
+        return Err(anyhow!("No {} Hz tone came back; check the loopback connection", frequency));
+    }
+    // Interpolated between the peak and its neighbours, for a finer frequency than the bins
+    let weighted: f32 = lobe(peak).iter().enumerate().map(|(i, p)| (peak.saturating_sub(LOBE) + i) as f32 * p).sum();
+    let fundamental = weighted / fundamental_power * resolution;
+
+    let mut harmonics = Vec::new();
+    let mut harmonic_power = 0.0;
+    for order in 2..=HARMONICS {
+        let center = bin_of(fundamental * order as f32);
+        if center + LOBE >= power.len() {
+            break;
+        }
+        let energy: f32 = lobe(center).iter().sum();
+        harmonic_power += energy;
+        harmonics.push((fundamental * order as f32, 10.0 * (energy / fundamental_power).max(1e-20).log10()));
+    }
+    let residual: f32 = power[bin_of(LOW_CUT).max(1)..].iter().sum::<f32>() - fundamental_power;
+    Ok(Distortion {
+        fundamental,
+        level_db,
+        thd: (harmonic_power / fundamental_power).sqrt(),
+        thd_n: (residual.max(0.0) / fundamental_power).sqrt(),
+        harmonics,
+    })
+}
+
+/// Magnitude response of `ir` at the ISO third-octave centers from 20 Hz to Nyquist, each
+/// the average over its third of an octave, in dB relative to 1 kHz.
+pub fn frequency_response(ir: &[f32], sample_rate: u32) -> Vec<(f32, f32)> {
+    let len = ir.len().min((RESPONSE_WINDOW * sample_rate as f64) as usize);
+    let size = len.next_power_of_two().max(2);
+    let mut buffer: Vec<Complex<f32>> = ir[..len].iter().map(|&s| Complex::new(s, 0.0)).collect();
+    buffer.resize(size, Complex::new(0.0, 0.0));
+    FftPlanner::new().plan_fft_forward(size).process(&mut buffer);
+    let resolution = sample_rate as f32 / size as f32;
+    let nyquist = sample_rate as f32 / 2.0;
+
+    let band_power = |center: f32| {
+        let edge = 2f32.powf(1.0 / 6.0);
+        let from = ((center / edge / resolution).floor() as usize).max(1);
+        let to = ((center * edge / resolution).ceil() as usize).clamp(from + 1, size / 2);
+        buffer[from..to].iter().map(|c| c.norm_sqr()).sum::<f32>() / (to - from) as f32
+    };
+    let reference = band_power(1000.0).max(f32::MIN_POSITIVE);
+    // Third-octave centers are 1 kHz times powers of 2^(1/3); -17 is 20 Hz
+    (-17..)
+        .map(|n| 1000.0 * 2f32.powf(n as f32 / 3.0))
+        .take_while(|&center| center * 2f32.powf(1.0 / 6.0) <= nyquist)
+        .map(|center| (center, 10.0 * (band_power(center) / reference).max(1e-20).log10()))
+        .collect()
+}
+
+/// Lines summarizing `distortion`, for the terminal.
+pub fn report_lines(distortion: &Distortion) -> Vec<String> {
+    let percent_and_db = |ratio: f32| format!("{:.4}% ({:.1} dB)", ratio * 100.0, 20.0 * ratio.max(1e-10).log10());
+    let mut lines = vec![
+        format!("Fundamental: {:.1} Hz at {:.1} dBFS", distortion.fundamental, distortion.level_db),
+        format!("THD: {}", percent_and_db(distortion.thd)),
+        format!("THD+N: {}", percent_and_db(distortion.thd_n)),
+    ];
+    for (order, (frequency, level)) in distortion.harmonics.iter().enumerate() {
+        lines.push(format!("  H{}: {:.0} Hz, {:.1} dB", order + 2, frequency, level));
+    }
+    lines
+}
@@ -0,0 +1,333 @@
+use crate::audio::filter::Biquad;
+use crate::audio::{AudioData, SpectrogramData};
+use crate::speech::TranscriptionSegment;
+
+pub mod acoustics;
+pub mod beats;
+pub mod bleed;
+pub mod doppler;
+pub mod engine;
+pub mod events;
+pub mod level;
+pub mod live;
+pub mod measurement;
+pub mod notes;
+pub mod pitch;
+pub mod quality;
+pub mod sibilance;
+pub mod speech;
+pub mod stereo;
+pub mod timing;
+pub mod trigger;
+pub mod tuning;
+
+use quality::{assess_quality, QualityReport};
+use sibilance::{detect_sibilance, Sibilance};
+use stereo::{stereo_stats, StereoStats};
+use timing::Timings;
+
+pub const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+// Krumhansl-Kessler key profiles, starting at the tonic
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+pub struct AudioStats {
+    pub duration: f64,
+    pub sample_rate: u32,
+    pub bits_per_sample: Option<u32>,
+    pub channels: usize,
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    pub lufs: Option<f32>,
+    pub dominant_frequency: Option<f32>,
+    pub bpm: Option<f32>,
+    pub key: Option<String>,
+    pub segment_count: usize,
+    pub quality: QualityReport,
+    /// Harsh sibilant bursts, in order
+    pub sibilance: Vec<Sibilance>,
+    /// Correlation and balance, for stereo input
+    pub stereo: Option<StereoStats>,
+    /// Named values computed by a `--script`
+    pub custom: Vec<(String, f64)>,
+    /// How long each stage of the analysis took
+    pub timings: Timings,
+}
+
+impl AudioStats {
+    pub fn summary_lines(&self) -> Vec<String> {
+        let or_na = |value: Option<String>| value.unwrap_or_else(|| "n/a".to_string());
+        vec![
+            format!("Duration:      {:.2}s", self.duration),
+            format!("Sample rate:   {} Hz", self.sample_rate),
+            format!("Bit depth:     {}", or_na(self.bits_per_sample.map(|b| format!("{} bit", b)))),
+            format!("Channels:      {}", self.channels),
+            format!("Peak:          {:.1} dBFS", self.peak_dbfs),
+            format!("RMS:           {:.1} dBFS", self.rms_dbfs),
+            format!("Loudness:      {}", or_na(self.lufs.map(|l| format!("{:.1} LUFS", l)))),
+            format!("Dominant freq: {}", or_na(self.dominant_frequency.map(|f| format!("{:.0} Hz", f)))),
+            format!("Tempo:         {}", or_na(self.bpm.map(|b| format!("{:.0} BPM", b)))),
+            format!("Key:           {}", or_na(self.key.clone())),
+            format!("Segments:      {}", self.segment_count),
+            match self.sibilance.as_slice() {
+                [] => "Sibilance:     none".to_string(),
+                bursts => {
+                    let total: f64 = bursts.iter().map(|b| b.end - b.start).sum();
+                    let peak = bursts.iter().fold(f32::NEG_INFINITY, |a, b| a.max(b.peak_dbfs));
+                    format!("Sibilance:     {} bursts ({:.1}s), peak {:.1} dBFS", bursts.len(), total, peak)
+                }
+            },
+        ]
+        .into_iter()
+        .chain(self.stereo.iter().flat_map(StereoStats::summary_lines))
+        .chain(self.custom.iter().map(|(name, value)| format!("{:<15}{:.3}", format!("{}:", name), value)))
+        .chain(self.timings.report_lines(self.duration))
+        .collect()
+    }
+}
+
+pub fn compute_stats(
+    audio_data: &AudioData,
+    spectrogram: &SpectrogramData,
+    transcription: &[TranscriptionSegment],
+) -> AudioStats {
+    let samples = &audio_data.samples;
+    let channels: Vec<&[f32]> =
+        (0..=audio_data.extra_channels.len()).filter_map(|c| audio_data.channel(c)).collect();
+    // Peak and RMS over every channel, as meters show the loudest
+    let peak = channels.iter().flat_map(|c| c.iter()).fold(0.0f32, |a, &b| a.max(b.abs()));
+    let count: usize = channels.iter().map(|c| c.len()).sum();
+    let rms = if count == 0 {
+        0.0
+    } else {
+        (channels.iter().flat_map(|c| c.iter()).map(|&x| x as f64 * x as f64).sum::<f64>() / count as f64).sqrt() as f32
+    };
+
+    AudioStats {
+        duration: samples.len() as f64 / audio_data.sample_rate as f64,
+        sample_rate: audio_data.sample_rate,
+        bits_per_sample: audio_data.bits_per_sample,
+        channels: audio_data.channels,
+        peak_dbfs: to_dbfs(peak),
+        rms_dbfs: to_dbfs(rms),
+        lufs: integrated_loudness(&channels, audio_data.sample_rate),
+        dominant_frequency: dominant_frequency(spectrogram),
+        bpm: estimate_bpm(spectrogram),
+        key: estimate_key(spectrogram),
+        segment_count: transcription.len(),
+        quality: assess_quality(audio_data),
+        sibilance: detect_sibilance(audio_data),
+        stereo: stereo_stats(audio_data),
+        custom: Vec::new(),
+        timings: Timings::default(),
+    }
+}
+
+pub fn to_dbfs(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-10).log10()
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    if db.is_finite() {
+        10f32.powf(db / 20.0)
+    } else {
+        0.0
+    }
+}
+
+/// Weight of each channel in `integrated_loudness`: 5.1 in the usual order (L, R, C, LFE,
+/// Ls, Rs) leaves out the LFE and lifts the surrounds by 1.5 dB, as BS.1770 does; any other
+/// layout counts every channel once.
+fn channel_weights(count: usize) -> Vec<f32> {
+    match count {
+        6 => vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41],
+        _ => vec![1.0; count],
+    }
+}
+
+/// Integrated loudness following ITU-R BS.1770 (K-weighting, 400 ms gated blocks): the
+/// K-weighted power of each block is summed over the channels before gating.
+pub fn integrated_loudness(channels: &[&[f32]], sample_rate: u32) -> Option<f32> {
+    let len = channels.iter().map(|c| c.len()).min()?;
+    let block_size = (sample_rate as f32 * 0.4) as usize;
+    let step = (block_size / 4).max(1);
+    if block_size == 0 || len < block_size {
+        return None;
+    }
+
+    let mut block_powers = vec![0.0f32; (len - block_size) / step + 1];
+    for (channel, weight) in channels.iter().zip(channel_weights(channels.len())) {
+        if weight == 0.0 {
+            continue;
+        }
+        let [mut shelf, mut highpass] = Biquad::k_weighting(sample_rate);
+        let weighted: Vec<f32> = channel[..len].iter().map(|&x| highpass.process(shelf.process(x))).collect();
+        for (i, power) in block_powers.iter_mut().enumerate() {
+            let block = &weighted[i * step..i * step + block_size];
+            *power += weight * block.iter().map(|&x| x * x).sum::<f32>() / block_size as f32;
+        }
+    }
+
+    let loudness = |power: f32| -0.691 + 10.0 * power.max(1e-20).log10();
+    let gated_mean = |threshold: f32| {
+        let gated: Vec<f32> = block_powers
+            .iter()
+            .cloned()
+            .filter(|&p| loudness(p) > threshold)
+            .collect();
+        if gated.is_empty() {
+            None
+        } else {
+            Some(gated.iter().sum::<f32>() / gated.len() as f32)
+        }
+    };
+
+    let absolute = gated_mean(-70.0)?;
+    let relative = gated_mean(loudness(absolute) - 10.0)?;
+    Some(loudness(relative))
+}
+
+fn average_spectrum(spectrogram: &SpectrogramData) -> Vec<f32> {
+    let mut average = vec![0.0f32; spectrogram.frequencies.len()];
+    for frame in &spectrogram.magnitudes {
+        for (sum, &db) in average.iter_mut().zip(frame.iter()) {
+            *sum += db_to_linear(db);
+        }
+    }
+    let frames = spectrogram.magnitudes.len().max(1) as f32;
+    average.iter_mut().for_each(|v| *v /= frames);
+    average
+}
+
+fn dominant_frequency(spectrogram: &SpectrogramData) -> Option<f32> {
+    average_spectrum(spectrogram)
+        .iter()
+        .enumerate()
+        .skip(1) // ignore DC
+        .filter(|(_, &v)| v > 0.0)
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(i, _)| spectrogram.frequencies[i])
+}
+
+/// Tempo estimate from the autocorrelation of the spectral-flux onset envelope.
+fn estimate_bpm(spectrogram: &SpectrogramData) -> Option<f32> {
+    if spectrogram.time_points.len() < 2 {
+        return None;
+    }
+    let frame_rate = 1.0 / (spectrogram.time_points[1] - spectrogram.time_points[0]);
+
+    let linear: Vec<Vec<f32>> = spectrogram
+        .magnitudes
+        .iter()
+        .map(|frame| frame.iter().map(|&db| db_to_linear(db)).collect())
+        .collect();
+    let flux: Vec<f32> = linear
+        .windows(2)
+        .map(|w| {
+            w[1].iter()
+                .zip(w[0].iter())
+                .map(|(&cur, &prev)| (cur - prev).max(0.0))
+                .sum()
+        })
+        .collect();
+
+    let mean = flux.iter().sum::<f32>() / flux.len().max(1) as f32;
+    let onsets: Vec<f32> = flux.iter().map(|&f| f - mean).collect();
+
+    let min_lag = (frame_rate * 60.0 / 200.0).round().max(1.0) as usize;
+    let max_lag = (frame_rate * 60.0 / 60.0).round() as usize;
+    if onsets.len() <= max_lag * 2 {
+        return None;
+    }
+
+    let (best_lag, best_score) = (min_lag..=max_lag)
+        .map(|lag| {
+            let score: f32 = onsets
+                .iter()
+                .zip(onsets.iter().skip(lag))
+                .map(|(&a, &b)| a * b)
+                .sum();
+            (lag, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    if best_score <= 0.0 {
+        return None;
+    }
+    Some(60.0 * frame_rate / best_lag as f32)
+}
+
+/// Key estimate by correlating an averaged chroma vector against major/minor profiles.
+fn estimate_key(spectrogram: &SpectrogramData) -> Option<String> {
+    let mut chroma = [0.0f32; 12];
+    for (i, &energy) in average_spectrum(spectrogram).iter().enumerate() {
+        let freq = spectrogram.frequencies[i];
+        if !(55.0..=5000.0).contains(&freq) {
+            continue;
+        }
+        let midi = (69.0 + 12.0 * (freq / 440.0).log2()).round() as i32;
+        chroma[midi.rem_euclid(12) as usize] += energy;
+    }
+    if chroma.iter().all(|&c| c == 0.0) {
+        return None;
+    }
+
+    let mut best: Option<(f32, String)> = None;
+    for tonic in 0..12 {
+        for (profile, mode) in [(&MAJOR_PROFILE, "major"), (&MINOR_PROFILE, "minor")] {
+            let rotated: Vec<f32> = (0..12).map(|i| profile[(i + 12 - tonic) % 12]).collect();
+            let score = correlation(&chroma, &rotated);
+            if best.as_ref().is_none_or(|(s, _)| score > *s) {
+                best = Some((score, format!("{} {}", NOTE_NAMES[tonic], mode)));
+            }
+        }
+    }
+    best.map(|(_, key)| key)
+}
+
+fn correlation(a: &[f32], b: &[f32]) -> f32 {
+    let mean_a = a.iter().sum::<f32>() / a.len() as f32;
+    let mean_b = b.iter().sum::<f32>() / b.len() as f32;
+    let (mut num, mut den_a, mut den_b) = (0.0, 0.0, 0.0);
+    for (&x, &y) in a.iter().zip(b.iter()) {
+        num += (x - mean_a) * (y - mean_b);
+        den_a += (x - mean_a) * (x - mean_a);
+        den_b += (y - mean_b) * (y - mean_b);
+    }
+    num / (den_a * den_b).sqrt().max(1e-12)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(level_db: f32, sample_rate: u32) -> Vec<f32> {
+        let amplitude = 10f32.powf(level_db / 20.0);
+        let step = 2.0 * std::f32::consts::PI * 1000.0 / sample_rate as f32;
+        (0..sample_rate * 5).map(|n| amplitude * (step * n as f32).sin()).collect()
+    }
+
+    #[test]
+    fn loudness_sums_the_channels() {
+        // EBU Tech 3341 case 1: a 1 kHz sine at -23 dBFS in both channels reads -23 LUFS
+        for rate in [44_100, 48_000] {
+            let tone = sine(-23.0, rate);
+            let stereo = integrated_loudness(&[&tone, &tone], rate).unwrap();
+            assert!((stereo + 23.0).abs() < 0.1, "{} Hz: {}", rate, stereo);
+            let mono = integrated_loudness(&[&tone], rate).unwrap();
+            assert!((mono - (stereo - 3.01)).abs() < 0.05, "{} Hz: {}", rate, mono);
+        }
+        let silence = vec![0.0; 48_000];
+        assert_eq!(integrated_loudness(&[&silence, &silence], 48_000), None);
+    }
+
+    #[test]
+    fn the_lfe_is_left_out_of_5_1() {
+        let tone = sine(-23.0, 48_000);
+        let silence = vec![0.0; tone.len()];
+        let front = integrated_loudness(&[&tone, &tone, &silence, &silence, &silence, &silence], 48_000).unwrap();
+        let with_lfe = integrated_loudness(&[&tone, &tone, &silence, &tone, &silence, &silence], 48_000).unwrap();
+        assert!((front - with_lfe).abs() < 1e-3);
+    }
+}
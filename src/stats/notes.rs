@@ -0,0 +1,97 @@
+use crate::audio::AudioData;
+
+use super::pitch::pitch_track;
+use super::NOTE_NAMES;
+
+/// Spacing of the pitch track notes are read from, in seconds.
+pub const NOTE_HOP: f64 = 0.01;
+
+/// Frames of the median filter over note numbers, which smooths over octave errors and
+/// vibrato crossing a semitone boundary for a frame or two.
+const MEDIAN_FRAMES: usize = 5;
+
+/// Shortest note reported, in seconds; shorter runs are glides or glitches.
+const MIN_NOTE: f64 = 0.06;
+
+/// A note of a monophonic melody.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub start: f64,
+    pub end: f64,
+    /// MIDI note number (60 is middle C)
+    pub midi: i32,
+    /// Mean deviation of the sung or played pitch from the equal-tempered note (A4 = 440
+    /// Hz), in cents
+    pub cents: f32,
+}
+
+impl Note {
+    /// Name with octave, e.g. `C#4`.
+    pub fn name(&self) -> String {
+        format!("{}{}", NOTE_NAMES[self.midi.rem_euclid(12) as usize], self.octave())
+    }
+
+    pub fn octave(&self) -> i32 {
+        self.midi.div_euclid(12) - 1
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Fractional MIDI note number of a frequency.
+pub fn midi_number(frequency: f32) -> f32 {
+    69.0 + 12.0 * (frequency / 440.0).log2()
+}
+
+/// The first channel's pitch as fractional MIDI note numbers every `NOTE_HOP` seconds,
+/// `None` where unvoiced.
+pub fn pitch_frames(audio: &AudioData) -> Vec<Option<f32>> {
+    let duration = audio.samples.len() as f64 / audio.sample_rate as f64;
+    let frames = (duration / NOTE_HOP) as usize;
+    let track = pitch_track(&audio.samples, audio.sample_rate, (0..frames).map(|i| i as f64 * NOTE_HOP));
+    track.iter().map(|f0| f0.map(midi_number)).collect()
+}
+
+/// Splits the first channel's pitch track into notes: runs of voiced frames on the same
+/// (median-filtered) semitone.
+pub fn melody(audio: &AudioData) -> Vec<Note> {
+    segment_notes(&pitch_frames(audio))
+}
+
+/// Notes in `pitch_frames` output.
+pub fn segment_notes(pitches: &[Option<f32>]) -> Vec<Note> {
+    // Median of the voiced note numbers around each voiced frame
+    let half = MEDIAN_FRAMES / 2;
+    let notes: Vec<Option<i32>> = (0..pitches.len())
+        .map(|i| {
+            pitches[i]?;
+            let mut around: Vec<i32> = pitches[i.saturating_sub(half)..(i + half + 1).min(pitches.len())]
+                .iter()
+                .flatten()
+                .map(|m| m.round() as i32)
+                .collect();
+            around.sort_unstable();
+            Some(around[around.len() / 2])
+        })
+        .collect();
+
+    let mut melody = Vec::new();
+    let mut i = 0;
+    while i < notes.len() {
+        let Some(midi) = notes[i] else {
+            i += 1;
+            continue;
+        };
+        let run = notes[i..].iter().take_while(|&&n| n == Some(midi)).count();
+        let start = i as f64 * NOTE_HOP;
+        let end = (i + run) as f64 * NOTE_HOP;
+        if end - start >= MIN_NOTE {
+            let cents = pitches[i..i + run].iter().flatten().map(|m| (m - midi as f32) * 100.0).sum::<f32>() / run as f32;
+            melody.push(Note { start, end, midi, cents });
+        }
+        i += run;
+    }
+    melody
+}
@@ -0,0 +1,115 @@
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::sync::Arc;
+
+use super::to_dbfs;
+
+/// Lowest and highest fundamental tracked, in Hz: A1 to A6, bass voice to piccolo.
+pub const MIN_F0: f32 = 55.0;
+pub const MAX_F0: f32 = 1760.0;
+
+/// Largest normalized difference at a period for a stretch to count as voiced (YIN's
+/// absolute threshold).
+const VOICING_THRESHOLD: f32 = 0.15;
+
+/// Stretches quieter than this have no pitch.
+const SILENCE_DBFS: f32 = -50.0;
+
+/// YIN fundamental frequency estimator for one sample rate.
+pub struct PitchTracker {
+    sample_rate: u32,
+    min_lag: usize,
+    /// Longest period, which is also the integration window
+    max_lag: usize,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+}
+
+impl PitchTracker {
+    pub fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f32;
+        let min_lag = ((rate / MAX_F0).floor() as usize).max(2);
+        let max_lag = ((rate / MIN_F0).ceil() as usize).max(min_lag + 2);
+        let size = (2 * max_lag).next_power_of_two();
+        let mut planner = FftPlanner::new();
+        Self {
+            sample_rate,
+            min_lag,
+            max_lag,
+            forward: planner.plan_fft_forward(size),
+            inverse: planner.plan_fft_inverse(size),
+        }
+    }
+
+    /// Samples `estimate` looks at: two of the longest periods.
+    pub fn frame_len(&self) -> usize {
+        2 * self.max_lag
+    }
+
+    /// Fundamental of the first `frame_len()` samples of `frame` in Hz, or `None` when
+    /// unvoiced, silent or too short.
+    pub fn estimate(&self, frame: &[f32]) -> Option<f32> {
+        let w = self.max_lag;
+        let frame = frame.get(..2 * w)?;
+        let power = frame.iter().map(|x| x * x).sum::<f32>() / frame.len() as f32;
+        if to_dbfs(power.sqrt()) < SILENCE_DBFS {
+            return None;
+        }
+
+        // Cross-correlation of the first window with the whole frame, by FFT
+        let size = self.forward.len();
+        let pad = |samples: &[f32]| -> Vec<Complex<f32>> {
+            let mut buffer: Vec<Complex<f32>> = samples.iter().map(|&x| Complex::new(x, 0.0)).collect();
+            buffer.resize(size, Complex::new(0.0, 0.0));
+            buffer
+        };
+        let (mut head, mut whole) = (pad(&frame[..w]), pad(frame));
+        self.forward.process(&mut head);
+        self.forward.process(&mut whole);
+        let mut correlation: Vec<Complex<f32>> = head.iter().zip(&whole).map(|(a, b)| a.conj() * b).collect();
+        self.inverse.process(&mut correlation);
+
+        // Difference function d(τ) = Σ x[j]² + Σ x[j+τ]² - 2 Σ x[j] x[j+τ] over j < w
+        let mut prefix = vec![0.0f32; frame.len() + 1];
+        for (i, &x) in frame.iter().enumerate() {
+            prefix[i + 1] = prefix[i] + x * x;
+        }
+        let difference = |lag: usize| {
+            let energy = prefix[w] + prefix[lag + w] - prefix[lag];
+            (energy - 2.0 * correlation[lag].re / size as f32).max(0.0)
+        };
+        // Cumulative mean normalized difference
+        let mut normalized = vec![1.0f32; w + 1];
+        let mut running = 0.0;
+        for (lag, value) in normalized.iter_mut().enumerate().skip(1) {
+            let d = difference(lag);
+            running += d;
+            *value = if running > 0.0 { d * lag as f32 / running } else { 1.0 };
+        }
+
+        let mut lag = (self.min_lag..w).find(|&lag| normalized[lag] < VOICING_THRESHOLD)?;
+        while lag + 1 < w && normalized[lag + 1] < normalized[lag] {
+            lag += 1;
+        }
+        // Parabolic interpolation around the dip
+        let (a, b, c) = (normalized[lag - 1], normalized[lag], normalized[lag + 1]);
+        let curvature = a - 2.0 * b + c;
+        let shift = if curvature > 0.0 { (0.5 * (a - c) / curvature).clamp(-0.5, 0.5) } else { 0.0 };
+        Some(self.sample_rate as f32 / (lag as f32 + shift))
+    }
+}
+
+/// Fundamental at each of `times` (seconds, the centre of the stretch analysed), `None`
+/// where unvoiced or too close to the ends.
+pub fn pitch_track(samples: &[f32], sample_rate: u32, times: impl IntoIterator<Item = f64>) -> Vec<Option<f32>> {
+    let tracker = PitchTracker::new(sample_rate);
+    let half = tracker.frame_len() / 2;
+    times
+        .into_iter()
+        .map(|time| {
+            let centre = (time * sample_rate as f64).round() as usize;
+            let start = centre.checked_sub(half)?;
+            tracker.estimate(samples.get(start..)?)
+        })
+        .collect()
+}
@@ -0,0 +1,243 @@
+use crate::audio::spectrum::{average_spectrum, Spectrum};
+use crate::audio::AudioData;
+
+use super::to_dbfs;
+
+/// Length of the frames levels are measured over, in seconds.
+const FRAME: f64 = 0.05;
+
+/// Level treated as digital silence, so an all-zero stretch doesn't give an infinite SNR.
+const SILENCE_DBFS: f32 = -120.0;
+
+/// How far the spectrum may fall below its peak and still count as in band, in dB.
+const BANDWIDTH_RANGE: f32 = 60.0;
+
+/// How far a hum line must stand above the spectrum around it, in dB.
+const HUM_PROMINENCE: f32 = 10.0;
+
+/// How far any other steady tone must stand above the spectrum around it, in dB. Higher
+/// than for hum, where the frequencies are known in advance.
+const TONE_PROMINENCE: f32 = 15.0;
+
+/// Most tones reported, strongest first.
+const MAX_TONES: usize = 16;
+
+/// Quietest hum line or tone reported, below which a line is more likely numerical noise in a
+/// synthetic or heavily processed signal.
+const TONE_MIN_DBFS: f32 = -90.0;
+
+/// Hum harmonics checked, including the fundamental.
+const HUM_HARMONICS: usize = 5;
+
+/// A steady tone: a narrow peak in the long-term average spectrum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tone {
+    pub frequency: f32,
+    pub level_dbfs: f32,
+    /// Height above the surrounding spectrum in dB
+    pub prominence: f32,
+}
+
+/// Mains hum at 50 or 60 Hz: the fundamental and harmonics that stand out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hum {
+    pub mains: f32,
+    pub lines: Vec<Tone>,
+}
+
+/// Recording quality estimates for checking a recording before editing.
+#[derive(Clone, Debug, Default)]
+pub struct QualityReport {
+    /// Level of the quietest stretches (10th percentile of 50 ms frames)
+    pub noise_floor_dbfs: f32,
+    /// Level of the loudest stretches (95th percentile of 50 ms frames)
+    pub signal_dbfs: f32,
+    pub snr_db: f32,
+    /// Highest frequency within 60 dB of the spectrum's peak
+    pub bandwidth_hz: f32,
+    pub hum: Option<Hum>,
+    /// Steady tones anywhere in the spectrum (hum included), by frequency
+    pub tones: Vec<Tone>,
+}
+
+impl QualityReport {
+    /// Frequencies of the hum lines and other tones, for flagging and `--notch auto`.
+    pub fn tonal_frequencies(&self) -> Vec<f32> {
+        let hum = self.hum.iter().flat_map(|hum| &hum.lines);
+        merge(hum.chain(&self.tones).map(|tone| tone.frequency).collect())
+    }
+
+    pub fn report_lines(&self) -> Vec<String> {
+        let snr = match self.snr_db {
+            s if s >= 40.0 => "good",
+            s if s >= 25.0 => "fair",
+            _ => "poor",
+        };
+        let floor = match self.noise_floor_dbfs {
+            f if f <= -60.0 => "good",
+            f if f <= -45.0 => "fair",
+            _ => "noisy",
+        };
+        let bandwidth = match self.bandwidth_hz {
+            b if b < 7000.0 => "narrowband",
+            b if b < 15000.0 => "reduced",
+            _ => "full",
+        };
+        let hum = match &self.hum {
+            Some(hum) => {
+                let lines: Vec<String> = hum.lines.iter().map(|line| format!("{:.0}", line.frequency)).collect();
+                let loudest = hum.lines.iter().fold(f32::NEG_INFINITY, |a, line| a.max(line.level_dbfs));
+                format!("{:.0} Hz mains ({} Hz), {:.0} dBFS", hum.mains, lines.join(", "), loudest)
+            }
+            None => "none".to_string(),
+        };
+        vec![
+            format!("SNR:           {:.1} dB ({})", self.snr_db, snr),
+            format!("Noise floor:   {:.1} dBFS ({})", self.noise_floor_dbfs, floor),
+            format!("Bandwidth:     {:.1} kHz ({})", self.bandwidth_hz / 1000.0, bandwidth),
+            format!("Hum:           {}", hum),
+            match self.tonal_frequencies() {
+                frequencies if frequencies.is_empty() => "Tones:         none".to_string(),
+                frequencies => {
+                    let list: Vec<String> = frequencies.iter().map(|f| format!("{:.0}", f)).collect();
+                    format!("Tones:         {} Hz (remove with --notch auto)", list.join(", "))
+                }
+            },
+        ]
+    }
+}
+
+fn percentile(sorted: &[f32], fraction: f64) -> f32 {
+    if sorted.is_empty() {
+        return SILENCE_DBFS;
+    }
+    sorted[((sorted.len() - 1) as f64 * fraction).round() as usize]
+}
+
+/// Level of `spectrum` at `frequency` relative to the median of the spectrum 5–20 Hz to
+/// either side, with the line's level.
+fn prominence(spectrum: &Spectrum, frequency: f32) -> (f32, f32) {
+    let resolution = spectrum.resolution().max(f32::EPSILON);
+    let center = spectrum.bin(frequency);
+    let peak_bins = (1.5 / resolution).ceil() as usize;
+    let level = spectrum.levels[center.saturating_sub(peak_bins)..=(center + peak_bins).min(spectrum.levels.len() - 1)]
+        .iter()
+        .fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    let mut around: Vec<f32> = spectrum
+        .frequencies
+        .iter()
+        .zip(&spectrum.levels)
+        .filter(|(&f, _)| (5.0..=20.0).contains(&(f - frequency).abs()))
+        .map(|(_, &level)| level)
+        .collect();
+    around.sort_by(f32::total_cmp);
+    (level, level - percentile(&around, 0.5))
+}
+
+/// 50 or 60 Hz hum, whichever stands out more, when its fundamental or second harmonic
+/// does.
+pub fn detect_hum(spectrum: &Spectrum) -> Option<Hum> {
+    let nyquist = spectrum.frequencies.last().copied().unwrap_or(0.0);
+    [50.0, 60.0]
+        .into_iter()
+        .filter_map(|mains: f32| {
+            let lines: Vec<Tone> = (1..=HUM_HARMONICS)
+                .map(|k| mains * k as f32)
+                .filter(|&f| f + 20.0 < nyquist)
+                .map(|frequency| {
+                    let (level_dbfs, prominence) = prominence(spectrum, frequency);
+                    Tone { frequency, level_dbfs, prominence }
+                })
+                .collect();
+            let lines: Vec<Tone> = lines
+                .into_iter()
+                .filter(|l| l.prominence >= HUM_PROMINENCE && l.level_dbfs >= TONE_MIN_DBFS)
+                .collect();
+            let strong = lines.iter().any(|l| l.frequency == mains || l.frequency == 2.0 * mains);
+            strong.then_some(Hum { mains, lines })
+        })
+        .max_by(|a, b| {
+            let total = |hum: &Hum| hum.lines.iter().map(|l| l.prominence).sum::<f32>();
+            total(a).total_cmp(&total(b))
+        })
+}
+
+/// Sorted frequencies with near-duplicates (within 3 Hz) dropped.
+fn merge(mut frequencies: Vec<f32>) -> Vec<f32> {
+    frequencies.sort_by(f32::total_cmp);
+    frequencies.dedup_by(|b, a| *b - *a < 3.0);
+    frequencies
+}
+
+/// Narrow peaks standing well above the spectrum around them: the most prominent local
+/// maxima, one per 3 Hz.
+pub fn detect_tones(spectrum: &Spectrum) -> Vec<Tone> {
+    let nyquist = spectrum.frequencies.last().copied().unwrap_or(0.0);
+    let levels = &spectrum.levels;
+    let mut tones: Vec<Tone> = (1..levels.len().saturating_sub(1))
+        .filter(|&i| {
+            let frequency = spectrum.frequencies[i];
+            frequency >= 20.0 && frequency + 20.0 < nyquist && levels[i] >= levels[i - 1] && levels[i] > levels[i + 1]
+        })
+        .filter_map(|i| {
+            let (level_dbfs, prominence) = prominence(spectrum, spectrum.frequencies[i]);
+            // Parabolic interpolation between bins for the exact frequency
+            let curvature = levels[i - 1] - 2.0 * levels[i] + levels[i + 1];
+            let offset = if curvature < 0.0 { 0.5 * (levels[i - 1] - levels[i + 1]) / curvature } else { 0.0 };
+            let frequency = (i as f32 + offset) * spectrum.resolution();
+            (prominence >= TONE_PROMINENCE && level_dbfs >= TONE_MIN_DBFS).then_some(Tone {
+                frequency,
+                level_dbfs,
+                prominence,
+            })
+        })
+        .collect();
+    tones.sort_by(|a, b| b.prominence.total_cmp(&a.prominence));
+    let mut kept: Vec<Tone> = Vec::new();
+    for tone in tones {
+        if kept.len() < MAX_TONES && kept.iter().all(|k| (k.frequency - tone.frequency).abs() >= 3.0) {
+            kept.push(tone);
+        }
+    }
+    kept.sort_by(|a, b| a.frequency.total_cmp(&b.frequency));
+    kept
+}
+
+/// Spectrum fine enough (2 Hz or finer per bin) to tell tones from their neighbours.
+pub fn tone_spectrum(audio: &AudioData) -> Spectrum {
+    let window = (audio.sample_rate as usize / 2).next_power_of_two().max(2048);
+    average_spectrum(&audio.samples, audio.sample_rate, window, 64)
+}
+
+pub fn assess_quality(audio: &AudioData) -> QualityReport {
+    let samples = &audio.samples;
+    let frame = ((audio.sample_rate as f64 * FRAME) as usize).max(1);
+    let mut levels: Vec<f32> = samples
+        .chunks(frame)
+        .map(|chunk| {
+            let rms = (chunk.iter().map(|&x| x * x).sum::<f32>() / chunk.len() as f32).sqrt();
+            to_dbfs(rms).max(SILENCE_DBFS)
+        })
+        .collect();
+    levels.sort_by(f32::total_cmp);
+    let noise_floor_dbfs = percentile(&levels, 0.10);
+    let signal_dbfs = percentile(&levels, 0.95);
+
+    let spectrum = average_spectrum(samples, audio.sample_rate, 4096, 256);
+    let peak = spectrum.levels.iter().skip(1).fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    let bandwidth_hz = spectrum
+        .levels
+        .iter()
+        .rposition(|&level| level >= peak - BANDWIDTH_RANGE)
+        .map_or(0.0, |bin| spectrum.frequencies[bin]);
+
+    let tones = tone_spectrum(audio);
+    QualityReport {
+        noise_floor_dbfs,
+        signal_dbfs,
+        snr_db: signal_dbfs - noise_floor_dbfs,
+        bandwidth_hz,
+        hum: detect_hum(&tones),
+        tones: detect_tones(&tones),
+    }
+}
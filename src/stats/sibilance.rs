@@ -0,0 +1,73 @@
+use crate::audio::filter::{sibilant_filter, SIBILANCE_THRESHOLD_DBFS, SIBILANT_FRACTION};
+use crate::audio::AudioData;
+
+use super::to_dbfs;
+
+/// Length of the frames the sibilant band is measured over, in seconds.
+const FRAME: f64 = 0.01;
+
+/// Shortest and longest burst reported, in seconds. Sibilants last tens to a few hundred
+/// milliseconds; longer bright stretches are cymbals, hiss or noise rather than speech.
+const MIN_BURST: f64 = 0.03;
+const MAX_BURST: f64 = 0.5;
+
+/// Sibilant frames this close together are one burst, in seconds.
+const MERGE_GAP: f64 = 0.02;
+
+/// A stretch where the sibilant band is loud and dominates the signal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sibilance {
+    pub start: f64,
+    pub end: f64,
+    /// Loudest 10 ms of the sibilant band in the burst
+    pub peak_dbfs: f32,
+}
+
+/// Harsh sibilant bursts in the first channel, in order. Empty for sample rates too low to
+/// carry sibilance.
+pub fn detect_sibilance(audio: &AudioData) -> Vec<Sibilance> {
+    let Some((mut high, mut low)) = sibilant_filter(audio.sample_rate) else {
+        return Vec::new();
+    };
+    let rate = audio.sample_rate as f64;
+    let frame = ((rate * FRAME) as usize).max(1);
+    // Sibilant-band level of each frame that counts, None for the rest
+    let levels: Vec<Option<f32>> = audio
+        .samples
+        .chunks(frame)
+        .map(|chunk| {
+            let (band, total) = chunk.iter().fold((0.0f32, 0.0f32), |(band, total), &x| {
+                let y = low.process(high.process(x));
+                (band + y * y, total + x * x)
+            });
+            let level = to_dbfs((band / chunk.len() as f32).sqrt());
+            (level >= SIBILANCE_THRESHOLD_DBFS && band > SIBILANT_FRACTION * total).then_some(level)
+        })
+        .collect();
+
+    let seconds = |frames: usize| (frames * frame) as f64 / rate;
+    let gap = (MERGE_GAP / FRAME).round() as usize;
+    let mut bursts: Vec<(usize, usize, f32)> = Vec::new();
+    for (i, level) in levels.iter().enumerate() {
+        let Some(level) = *level else {
+            continue;
+        };
+        match bursts.last_mut() {
+            Some((_, end, peak)) if i <= *end + gap => {
+                *end = i + 1;
+                *peak = peak.max(level);
+            }
+            _ => bursts.push((i, i + 1, level)),
+        }
+    }
+    let duration = audio.samples.len() as f64 / rate;
+    bursts
+        .into_iter()
+        .map(|(start, end, peak_dbfs)| Sibilance {
+            start: seconds(start),
+            end: seconds(end).min(duration),
+            peak_dbfs,
+        })
+        .filter(|burst| (MIN_BURST..=MAX_BURST).contains(&(burst.end - burst.start)))
+        .collect()
+}
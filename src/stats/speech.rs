@@ -0,0 +1,135 @@
+use crate::speech::TranscriptionSegment;
+
+/// Shortest silence between words counted as a pause, in seconds.
+pub const MIN_PAUSE: f64 = 0.3;
+
+/// Span of each speaking-rate measurement, in seconds.
+pub const RATE_WINDOW: f64 = 15.0;
+
+/// Filler words and phrases counted, in the order they're listed.
+const FILLERS: &[&str] = &["um", "uh", "er", "ah", "hmm", "like", "you know", "i mean", "basically", "literally"];
+
+/// Speaking rate, pauses and filler words, from word timings.
+#[derive(Clone, Debug, Default)]
+pub struct SpeechStats {
+    pub word_count: usize,
+    /// Words per minute from the first word to the last
+    pub words_per_minute: Option<f64>,
+    /// Words per minute in each `RATE_WINDOW` from the start
+    pub rate: Vec<f64>,
+    pub pause_count: usize,
+    pub mean_pause: Option<f64>,
+    /// Start and length of the longest pause
+    pub longest_pause: Option<(f64, f64)>,
+    /// Filler counts, most frequent first
+    pub fillers: Vec<(&'static str, usize)>,
+}
+
+/// Lowercased letters and apostrophes, with drawn-out hesitations ("ummm", "uhh", "erm",
+/// "hmmm") folded into one spelling.
+fn normalize(word: &str) -> String {
+    let word: String = word
+        .chars()
+        .filter(|c| c.is_alphabetic() || *c == '\'')
+        .flat_map(char::to_lowercase)
+        .collect();
+    let runs = |pattern: &[char]| {
+        let mut chars = word.chars().peekable();
+        for (i, &c) in pattern.iter().enumerate() {
+            // Optional trailing letters, e.g. the m in "erm"
+            let optional = i > 1;
+            if chars.next_if_eq(&c).is_none() && !optional {
+                return false;
+            }
+            while chars.next_if_eq(&c).is_some() {}
+        }
+        chars.next().is_none()
+    };
+    let folded = [("um", &['u', 'm'][..]), ("uh", &['u', 'h']), ("er", &['e', 'r', 'm']), ("ah", &['a', 'h']), ("hmm", &['h', 'm'])]
+        .iter()
+        .find(|(_, pattern)| runs(pattern))
+        .map(|(filler, _)| filler.to_string());
+    folded.unwrap_or(word)
+}
+
+pub fn speech_stats(words: &[TranscriptionSegment], duration: f64) -> SpeechStats {
+    let spoken: Vec<(&TranscriptionSegment, String)> = words
+        .iter()
+        .map(|word| (word, normalize(&word.text)))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+    let (Some(first), Some(last)) = (spoken.first(), spoken.last()) else {
+        return SpeechStats::default();
+    };
+    let span = last.0.end - first.0.start;
+    let words_per_minute = (span > 0.0).then(|| spoken.len() as f64 * 60.0 / span);
+
+    let windows = (duration / RATE_WINDOW).ceil().max(1.0) as usize;
+    let mut counts = vec![0usize; windows];
+    for (word, _) in &spoken {
+        counts[((word.start / RATE_WINDOW) as usize).min(windows - 1)] += 1;
+    }
+    let rate = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            // The last window may be short
+            let length = (duration - i as f64 * RATE_WINDOW).clamp(1.0, RATE_WINDOW);
+            count as f64 * 60.0 / length
+        })
+        .collect();
+
+    let pauses: Vec<(f64, f64)> = spoken
+        .windows(2)
+        .map(|pair| (pair[0].0.end, pair[1].0.start - pair[0].0.end))
+        .filter(|&(_, length)| length >= MIN_PAUSE)
+        .collect();
+    let mean_pause = (!pauses.is_empty()).then(|| pauses.iter().map(|p| p.1).sum::<f64>() / pauses.len() as f64);
+    let longest_pause = pauses.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1));
+
+    let texts: Vec<&str> = spoken.iter().map(|(_, text)| text.as_str()).collect();
+    let mut fillers: Vec<(&'static str, usize)> = FILLERS
+        .iter()
+        .map(|&filler| {
+            let parts: Vec<&str> = filler.split(' ').collect();
+            (filler, texts.windows(parts.len()).filter(|window| *window == parts.as_slice()).count())
+        })
+        .filter(|&(_, count)| count > 0)
+        .collect();
+    fillers.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    SpeechStats {
+        word_count: spoken.len(),
+        words_per_minute,
+        rate,
+        pause_count: pauses.len(),
+        mean_pause,
+        longest_pause,
+        fillers,
+    }
+}
+
+impl SpeechStats {
+    /// Speaking rate in the window containing `time`.
+    pub fn rate_at(&self, time: f64) -> Option<f64> {
+        self.rate.get((time.max(0.0) / RATE_WINDOW) as usize).copied()
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        let filler_total: usize = self.fillers.iter().map(|f| f.1).sum();
+        let filler_list: Vec<String> = self.fillers.iter().map(|(f, n)| format!("{} {}", f, n)).collect();
+        let mut pauses = self.mean_pause.map(|m| format!("mean {:.1}s", m)).into_iter().collect::<Vec<_>>();
+        if let Some((start, length)) = self.longest_pause {
+            pauses.push(format!("longest {:.1}s at {:.1}s", length, start));
+        }
+        let detail = |parts: Vec<String>| {
+            if parts.is_empty() { String::new() } else { format!(" ({})", parts.join(", ")) }
+        };
+        vec![
+            format!("Words:   {}", self.word_count),
+            format!("Rate:    {}", self.words_per_minute.map_or("n/a".to_string(), |r| format!("{:.0} wpm", r))),
+            format!("Pauses:  {}{}", self.pause_count, detail(pauses)),
+            format!("Fillers: {}{}", filler_total, detail(filler_list)),
+        ]
+    }
+}
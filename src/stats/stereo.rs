@@ -0,0 +1,143 @@
+use crate::audio::AudioData;
+
+use super::to_dbfs;
+
+/// Span of each correlation and balance measurement, in seconds: about the integration time
+/// of a hardware phase meter.
+pub const STEREO_WINDOW: f64 = 0.3;
+
+/// Correlation below which a window counts as out of phase.
+pub const OUT_OF_PHASE: f32 = -0.3;
+
+/// Windows quieter than this in both channels are skipped: correlation is meaningless in
+/// silence.
+const SILENCE_DBFS: f32 = -60.0;
+
+/// Correlation and balance over one window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StereoWindow {
+    /// Pearson correlation of left and right: +1 mono, 0 unrelated, -1 polarity inverted
+    pub correlation: f32,
+    /// Right level minus left level in dB: positive leans right
+    pub balance_db: f32,
+}
+
+/// Inter-channel correlation and L/R balance of the first two channels.
+#[derive(Clone, Debug, Default)]
+pub struct StereoStats {
+    /// Over the whole file, silent windows excluded
+    pub correlation: f32,
+    pub balance_db: f32,
+    /// One per `STEREO_WINDOW` from the start
+    pub windows: Vec<Option<StereoWindow>>,
+    /// Start and end of runs of out-of-phase windows
+    pub out_of_phase: Vec<(f64, f64)>,
+}
+
+/// Sums of squares and of the product of `left` and `right`.
+fn moments(left: &[f32], right: &[f32]) -> (f64, f64, f64) {
+    left.iter().zip(right).fold((0.0, 0.0, 0.0), |(ll, rr, lr), (&l, &r)| {
+        let (l, r) = (l as f64, r as f64);
+        (ll + l * l, rr + r * r, lr + l * r)
+    })
+}
+
+/// Correlation and balance from the moments of `n` samples.
+fn measure((ll, rr, lr): (f64, f64, f64), n: usize) -> Option<StereoWindow> {
+    let level = |power: f64| to_dbfs((power / n.max(1) as f64).sqrt() as f32);
+    let (left, right) = (level(ll), level(rr));
+    if left.max(right) < SILENCE_DBFS {
+        return None;
+    }
+    // One silent channel is as uncorrelated as it gets
+    let correlation = if ll > 0.0 && rr > 0.0 { (lr / (ll * rr).sqrt()) as f32 } else { 0.0 };
+    Some(StereoWindow { correlation, balance_db: right - left })
+}
+
+/// Stereo measurements for audio with two or more channels, `None` for mono.
+pub fn stereo_stats(audio: &AudioData) -> Option<StereoStats> {
+    let (left, right) = (audio.channel(0)?, audio.channel(1)?);
+    let window = ((audio.sample_rate as f64 * STEREO_WINDOW) as usize).max(1);
+    let mut total = (0.0, 0.0, 0.0);
+    let mut counted = 0;
+    let windows: Vec<Option<StereoWindow>> = left
+        .chunks(window)
+        .zip(right.chunks(window))
+        .map(|(l, r)| {
+            let m = moments(l, r);
+            let measured = measure(m, l.len());
+            if measured.is_some() {
+                total = (total.0 + m.0, total.1 + m.1, total.2 + m.2);
+                counted += l.len();
+            }
+            measured
+        })
+        .collect();
+    let overall = measure(total, counted).unwrap_or(StereoWindow { correlation: 1.0, balance_db: 0.0 });
+
+    let mut out_of_phase: Vec<(f64, f64)> = Vec::new();
+    for (i, measured) in windows.iter().enumerate() {
+        if !measured.is_some_and(|w| w.correlation < OUT_OF_PHASE) {
+            continue;
+        }
+        let (start, end) = (i as f64 * STEREO_WINDOW, (i + 1) as f64 * STEREO_WINDOW);
+        match out_of_phase.last_mut() {
+            Some(run) if run.1 >= start => run.1 = end,
+            _ => out_of_phase.push((start, end)),
+        }
+    }
+    let duration = left.len() as f64 / audio.sample_rate as f64;
+    if let Some(run) = out_of_phase.last_mut() {
+        run.1 = run.1.min(duration);
+    }
+
+    Some(StereoStats {
+        correlation: overall.correlation,
+        balance_db: overall.balance_db,
+        windows,
+        out_of_phase,
+    })
+}
+
+/// "centre", or how far and which way the balance leans.
+pub fn balance_label(balance_db: f32) -> String {
+    match balance_db {
+        b if b.abs() < 0.5 => "centre".to_string(),
+        b if b > 0.0 => format!("{:.1} dB right", b),
+        b => format!("{:.1} dB left", -b),
+    }
+}
+
+impl StereoStats {
+    /// Measurements in the window containing `time`.
+    pub fn at(&self, time: f64) -> Option<StereoWindow> {
+        self.windows.get((time.max(0.0) / STEREO_WINDOW) as usize).copied().flatten()
+    }
+
+    /// "Out of phase" warning naming the first few stretches, or `None` when there are none.
+    pub fn warning(&self) -> Option<String> {
+        if self.out_of_phase.is_empty() {
+            return None;
+        }
+        let mut stretches: Vec<String> =
+            self.out_of_phase.iter().take(3).map(|(start, end)| format!("{:.1}–{:.1}s", start, end)).collect();
+        if self.out_of_phase.len() > 3 {
+            stretches.push(format!("{} more", self.out_of_phase.len() - 3));
+        }
+        Some(format!("out-of-phase content at {} (mono playback will cancel it)", stretches.join(", ")))
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!("Correlation:   {:+.2}", self.correlation),
+            format!("Balance:       {}", balance_label(self.balance_db)),
+            format!("Out of phase:  {}", match self.out_of_phase.len() {
+                0 => "none".to_string(),
+                n => {
+                    let total: f64 = self.out_of_phase.iter().map(|(start, end)| end - start).sum();
+                    format!("{} stretch{}, {:.1}s", n, if n == 1 { "" } else { "es" }, total)
+                }
+            }),
+        ]
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+/// Stage whose time is compared against the audio's duration.
+pub const TRANSCRIBE: &str = "transcribe";
+
+/// Wall-clock time of each stage of the analysis, so it shows where the time goes.
+#[derive(Clone, Debug)]
+pub struct Timings {
+    /// Each stage and how long it took, in order
+    pub stages: Vec<(String, Duration)>,
+    /// End of the last stage
+    last: Instant,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self { stages: Vec::new(), last: Instant::now() }
+    }
+}
+
+impl Timings {
+    /// Ends `stage`: it took the time since the previous one ended, or since the timings
+    /// were created.
+    pub fn lap(&mut self, stage: &str) {
+        let now = Instant::now();
+        self.stages.push((stage.to_string(), now - self.last));
+        self.last = now;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, time)| *time).sum()
+    }
+
+    /// Transcription time over the audio's `duration` in seconds: below 1 is faster than
+    /// real time. `None` when nothing was transcribed.
+    pub fn realtime_factor(&self, duration: f64) -> Option<f64> {
+        let (_, time) = self.stages.iter().find(|(stage, _)| stage == TRANSCRIBE)?;
+        (duration > 0.0).then(|| time.as_secs_f64() / duration)
+    }
+
+    /// Lines for the stats pane: the total, then each stage, with the real-time factor on
+    /// the transcription. None when no stage was timed.
+    pub fn report_lines(&self, duration: f64) -> Vec<String> {
+        if self.stages.is_empty() {
+            return Vec::new();
+        }
+        let mut lines = vec![format!("Timing:        {:.2}s total", self.total().as_secs_f64())];
+        lines.extend(self.stages.iter().map(|(stage, time)| {
+            let rtf = match self.realtime_factor(duration) {
+                Some(rtf) if stage == TRANSCRIBE => format!(" (RTF {:.3})", rtf),
+                _ => String::new(),
+            };
+            format!("  {:<12} {:.2}s{}", stage, time.as_secs_f64(), rtf)
+        }));
+        lines
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use super::to_dbfs;
+use crate::audio::filter::Biquad;
+
+/// Length of the blocks whose level is compared with the threshold, in seconds.
+const BLOCK: f64 = 0.1;
+
+/// Longest clip saved, in seconds: a noise that doesn't stop is cut into clips this long.
+pub const MAX_CLIP: f64 = 600.0;
+
+/// When to save audio: where the level, over the whole spectrum or in one band, reaches a
+/// threshold, with some audio either side.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriggerSettings {
+    /// Level in dBFS a `BLOCK`'s RMS must reach
+    pub threshold: f32,
+    /// Band the level is measured in, as (low, high) Hz, e.g. to keep wind out of birdsong
+    pub band: Option<(f32, f32)>,
+    /// Seconds kept before the first block over the threshold
+    pub pre_roll: f64,
+    /// Seconds kept after the last block over the threshold; the event ends once this long
+    /// passes under it
+    pub post_roll: f64,
+}
+
+/// A stretch of audio that set off the trigger, in samples from the start of the recording,
+/// with the pre and post roll.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriggeredEvent {
+    pub start: usize,
+    pub end: usize,
+    /// Loudest block's level in dBFS
+    pub peak: f32,
+}
+
+/// Level of successive blocks, through the band-pass of `TriggerSettings::band` if any.
+struct BlockLevel {
+    band: Option<(Biquad, Biquad)>,
+}
+
+impl BlockLevel {
+    fn new(sample_rate: u32, band: Option<(f32, f32)>) -> Self {
+        let nyquist = sample_rate as f32 / 2.0;
+        let band = band.map(|(low, high)| {
+            let high = high.min(nyquist * 0.95);
+            (
+                Biquad::highpass(sample_rate, low.max(1.0), std::f32::consts::FRAC_1_SQRT_2),
+                Biquad::lowpass(sample_rate, high, std::f32::consts::FRAC_1_SQRT_2),
+            )
+        });
+        Self { band }
+    }
+
+    fn level(&mut self, block: &[f32]) -> f32 {
+        let power = match &mut self.band {
+            Some((highpass, lowpass)) => block
+                .iter()
+                .map(|&x| lowpass.process(highpass.process(x)).powi(2))
+                .sum::<f32>(),
+            None => block.iter().map(|x| x * x).sum::<f32>(),
+        };
+        to_dbfs((power / block.len().max(1) as f32).sqrt())
+    }
+}
+
+/// Watches audio as it arrives for blocks at or over the threshold, and hands each event to
+/// `save` once it ends. `audio(from)` returns the mono samples from index `from` on;
+/// `release(before)` is told when the samples before an index are no longer needed. Runs
+/// until `stop` is set, saving any event still going on then.
+pub fn watch(
+    sample_rate: u32,
+    settings: &TriggerSettings,
+    audio: impl Fn(usize) -> Vec<f32>,
+    release: impl Fn(usize),
+    stop: &AtomicBool,
+    mut save: impl FnMut(&TriggeredEvent) -> Result<()>,
+) -> Result<()> {
+    let rate = sample_rate as f64;
+    let block = ((BLOCK * rate) as usize).max(1);
+    let (pre_roll, post_roll) = ((settings.pre_roll * rate) as usize, (settings.post_roll * rate) as usize);
+    let max_clip = (MAX_CLIP * rate) as usize;
+    let mut levels = BlockLevel::new(sample_rate, settings.band);
+    // End of the last block measured
+    let mut measured = 0;
+    // The event going on, and the end of its last block over the threshold
+    let mut active: Option<(TriggeredEvent, usize)> = None;
+    while !stop.load(Ordering::Relaxed) {
+        let fresh = audio(measured);
+        if fresh.len() < block {
+            std::thread::sleep(Duration::from_secs_f64(BLOCK / 2.0));
+            continue;
+        }
+        for samples in fresh.chunks_exact(block) {
+            let level = levels.level(samples);
+            let end = measured + block;
+            let loud = level >= settings.threshold;
+            match &mut active {
+                None if loud => {
+                    let start = measured.saturating_sub(pre_roll);
+                    active = Some((TriggeredEvent { start, end, peak: level }, end));
+                }
+                None => {}
+                Some((event, last_loud)) => {
+                    if loud {
+                        *last_loud = end;
+                        event.peak = event.peak.max(level);
+                    }
+                    event.end = end;
+                    if end >= *last_loud + post_roll || end - event.start >= max_clip {
+                        save(event)?;
+                        active = None;
+                    }
+                }
+            }
+            measured = end;
+        }
+        // Only the pre roll is kept between events
+        if active.is_none() {
+            release(measured.saturating_sub(pre_roll));
+        }
+    }
+    if let Some((event, _)) = active {
+        save(&event)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use super::notes::Note;
+
+/// How a performance sits against equal temperament.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tuning {
+    /// A4 the notes are most consistent with, in Hz
+    pub reference: f32,
+    /// `reference` as cents from A4 = 440 Hz
+    pub offset_cents: f32,
+    /// Duration-weighted spread of the notes around `reference`, in cents
+    pub spread_cents: f32,
+    /// Each note with its deviation from `reference` (rather than 440 Hz) in cents
+    pub notes: Vec<(Note, f32)>,
+}
+
+/// Wraps cents into -50..50: the deviation from the nearest semitone.
+pub fn wrap_cents(cents: f32) -> f32 {
+    cents - 100.0 * (cents / 100.0).round()
+}
+
+/// Estimates the reference from the notes' deviations from 440 Hz. Deviations wrap at a
+/// semitone, so they are averaged as angles: a performance 45 cents sharp and one 55 cents
+/// flat are the same tuning, a semitone apart. `None` without notes.
+pub fn estimate_tuning(notes: &[Note]) -> Option<Tuning> {
+    let total: f64 = notes.iter().map(Note::duration).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let (sin, cos) = notes.iter().fold((0.0f64, 0.0f64), |(sin, cos), note| {
+        let angle = note.cents as f64 / 100.0 * std::f64::consts::TAU;
+        (sin + angle.sin() * note.duration(), cos + angle.cos() * note.duration())
+    });
+    let offset_cents = (sin.atan2(cos) / std::f64::consts::TAU * 100.0) as f32;
+    let notes: Vec<(Note, f32)> = notes
+        .iter()
+        .map(|note| (note.clone(), wrap_cents(note.cents - offset_cents)))
+        .collect();
+    let variance = notes.iter().map(|(note, cents)| (cents * cents) as f64 * note.duration()).sum::<f64>() / total;
+    Some(Tuning {
+        reference: 440.0 * 2f32.powf(offset_cents / 1200.0),
+        offset_cents,
+        spread_cents: variance.sqrt() as f32,
+        notes,
+    })
+}
+
+impl Tuning {
+    /// Report for `--tuning`: the reference and spread, then one line per note.
+    pub fn report_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Reference:     A4 = {:.1} Hz ({:+.0} cents from 440)", self.reference, self.offset_cents),
+            format!("Spread:        {:.0} cents ({} notes)", self.spread_cents, self.notes.len()),
+            String::new(),
+            format!("{:>9}  {:>8}  {:<5} {:>8}  {:>8}", "start", "duration", "note", "vs 440", "vs ref"),
+        ];
+        lines.extend(self.notes.iter().map(|(note, cents)| {
+            format!(
+                "{:>9.3}  {:>8.3}  {:<5} {:>+8.0}  {:>+8.0}",
+                note.start,
+                note.duration(),
+                note.name(),
+                note.cents,
+                cents
+            )
+        }));
+        lines
+    }
+}
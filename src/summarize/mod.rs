@@ -0,0 +1,106 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::config::toml::Table;
+use crate::speech::TranscriptionSegment;
+
+/// A local llama.cpp server (`llama-server`) speaks the OpenAI chat API on this address.
+const DEFAULT_ENDPOINT: &str = "http://localhost:8080/v1/chat/completions";
+
+const DEFAULT_PROMPT: &str = "Summarize this transcript in a few sentences, then list the main \
+points as short bullets. Reply with the summary only.";
+
+/// Where and how `--summarize` sends the transcript, from `[summarize]` in the config:
+/// `endpoint` (any OpenAI-compatible chat completions URL), `model`, `api_key_env` (the
+/// environment variable holding the API key, if the endpoint needs one) and `prompt`.
+#[derive(Clone, Debug)]
+pub struct Summarizer {
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    pub prompt: String,
+}
+
+impl Summarizer {
+    pub fn from_config(table: &Table) -> Result<Self> {
+        let text = |key: &str| -> Result<Option<String>> {
+            match table.get(key) {
+                Some(value) => value
+                    .as_str()
+                    .map(|s| Some(s.to_string()))
+                    .ok_or_else(|| anyhow!("{} must be a string", key)),
+                None => Ok(None),
+            }
+        };
+        let api_key = match text("summarize.api_key_env")? {
+            Some(var) => Some(std::env::var(&var).with_context(|| format!("{} is not set", var))?),
+            None => None,
+        };
+        Ok(Self {
+            endpoint: text("summarize.endpoint")?.unwrap_or_else(|| DEFAULT_ENDPOINT.to_string()),
+            model: text("summarize.model")?.unwrap_or_else(|| "default".to_string()),
+            api_key,
+            prompt: text("summarize.prompt")?.unwrap_or_else(|| DEFAULT_PROMPT.to_string()),
+        })
+    }
+
+    /// Sends the transcript to the endpoint and returns the model's reply.
+    pub fn summarize(&self, transcription: &[TranscriptionSegment]) -> Result<String> {
+        let transcript: Vec<&str> = transcription.iter().map(|s| s.text.trim()).collect();
+        if transcript.iter().all(|t| t.is_empty()) {
+            return Err(anyhow!("There is no transcript to summarize"));
+        }
+        let request = json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": self.prompt },
+                { "role": "user", "content": transcript.join("\n") },
+            ],
+            "temperature": 0.2,
+        });
+
+        // Everything goes to curl as a config on stdin, keeping the API key out of the
+        // process list
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut config = format!(
+            "url = {}\nheader = \"Content-Type: application/json\"\ndata-binary = {}\n",
+            quote(&self.endpoint),
+            quote(&request.to_string())
+        );
+        if let Some(key) = &self.api_key {
+            config.push_str(&format!("header = {}\n", quote(&format!("Authorization: Bearer {}", key))));
+        }
+        let mut child = Command::new("curl")
+            .args(["-sS", "--fail-with-body", "--max-time", "300", "-K", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run curl")?;
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("curl has no stdin"))?
+            .write_all(config.as_bytes())?;
+        let output = child.wait_with_output()?;
+        let body = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Summarization request to {} failed: {} {}",
+                self.endpoint,
+                String::from_utf8_lossy(&output.stderr).trim(),
+                body.trim()
+            ));
+        }
+
+        let response: Value =
+            serde_json::from_str(&body).context("Unexpected response from the summarization endpoint")?;
+        response
+            .pointer("/choices/0/message/content")
+            .and_then(Value::as_str)
+            .map(|content| content.trim().to_string())
+            .ok_or_else(|| anyhow!("The summarization endpoint returned no message: {}", body.trim()))
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph};
+
+use super::console::{ConsoleCapabilities, TerminalSession};
+use super::theme::Theme;
+use crate::stats::acoustics::BandDecay;
+
+/// Lowest level of the decay axis, in dB.
+const FLOOR: f64 = -60.0;
+
+/// Plots the energy decay curves of `decays` (the full band first), with `lines` (the
+/// table the subcommand prints) under them. Left and right pick the band drawn over the
+/// full band; `q` or `Esc` closes the plot.
+pub fn run(decays: &[BandDecay], lines: &[String], title: &str, theme: &Theme) -> Result<()> {
+    let mut session = TerminalSession::start()?;
+    let capabilities = ConsoleCapabilities::detect();
+    let fg = |color| if capabilities.color { Style::default().fg(color) } else { Style::default() };
+    let curve = |decay: &BandDecay| -> Vec<(f64, f64)> {
+        decay.curve.iter().map(|&(time, db)| (time, (db as f64).max(FLOOR))).collect()
+    };
+    let full = curve(&decays[0]);
+    let length = decays.iter().filter_map(|decay| decay.curve.last()).fold(0.0f64, |a, &(time, _)| a.max(time));
+    let mut selected = 1.min(decays.len() - 1);
+    loop {
+        let band = curve(&decays[selected]);
+        let name = decays[selected].band_name();
+        session.terminal.draw(|frame| {
+            let block = Block::default()
+                .title(format!(" {} ", title))
+                .borders(Borders::ALL)
+                .border_style(fg(theme.border));
+            let inner = block.inner(frame.size());
+            frame.render_widget(block, frame.size());
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(lines.len() as u16), Constraint::Length(1)])
+                .split(inner);
+
+            let line = |data| Dataset::default().marker(capabilities.line_marker()).graph_type(GraphType::Line).data(data);
+            let mut datasets = vec![line(&full).name("Full band").style(fg(theme.muted))];
+            if selected > 0 {
+                datasets.push(line(&band).name(name.clone()).style(fg(theme.waveform)));
+            }
+            let chart = Chart::new(datasets)
+                .block(Block::default().title(" Energy decay ").borders(Borders::ALL).border_style(fg(theme.border)))
+                .x_axis(
+                    Axis::default()
+                        .bounds([0.0, length])
+                        .labels(vec![Span::raw("0"), Span::raw(format!("{:.2}s", length))]),
+                )
+                .y_axis(
+                    Axis::default()
+                        .bounds([FLOOR, 0.0])
+                        .labels(vec![Span::raw(format!("{:.0}", FLOOR)), Span::raw("0dB")]),
+                );
+            frame.render_widget(chart, rows[0]);
+
+            let table: Vec<Line> = lines
+                .iter()
+                .enumerate()
+                // The table's first line is its header, so band `i` is on line `i + 1`
+                .map(|(i, text)| {
+                    if i == selected + 1 {
+                        Line::styled(text.as_str(), fg(theme.highlight))
+                    } else {
+                        Line::raw(text.as_str())
+                    }
+                })
+                .collect();
+            frame.render_widget(Paragraph::new(table), rows[1]);
+            let keys = Line::styled("Left/Right to pick a band, q or Esc to close", fg(theme.muted));
+            frame.render_widget(Paragraph::new(keys), rows[2]);
+        })?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q' | 'c') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left | KeyCode::Char('h') => selected = selected.saturating_sub(1),
+                KeyCode::Right | KeyCode::Char('l') => selected = (selected + 1).min(decays.len() - 1),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+use super::console::{ConsoleCapabilities, TerminalSession};
+use super::theme::Theme;
+use crate::speech::live::CaptionEvent;
+use crate::speech::TranscriptionSegment;
+
+/// Redraw interval, which is also how often keys are checked.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Shows captions from `events` full-screen: the phrase in progress at the bottom, with the
+/// last `history` finished phrases above it. Runs until `q`, `Esc` or Ctrl-C, or until the
+/// captioner stops sending; returns every finished phrase.
+pub fn run(events: &Receiver<CaptionEvent>, history: usize, title: &str, theme: &Theme) -> Result<Vec<TranscriptionSegment>> {
+    let mut session = TerminalSession::start()?;
+    let capabilities = ConsoleCapabilities::detect();
+    let fg = |color| if capabilities.color { Style::default().fg(color) } else { Style::default() };
+    let mut finished = Vec::new();
+    let mut shown: VecDeque<String> = VecDeque::new();
+    let mut partial = String::new();
+    loop {
+        loop {
+            match events.try_recv() {
+                Ok(CaptionEvent::Partial(text)) => partial = text,
+                Ok(CaptionEvent::Final(segment)) => {
+                    shown.push_back(segment.text.trim().to_string());
+                    while shown.len() > history {
+                        shown.pop_front();
+                    }
+                    finished.push(segment);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(finished),
+            }
+        }
+
+        // A blank line between phrases makes each one easier to pick out at a glance
+        let mut lines: Vec<Line> = Vec::new();
+        for text in &shown {
+            lines.push(Line::styled(text.clone(), fg(theme.muted)));
+            lines.push(Line::raw(""));
+        }
+        let current = if partial.is_empty() && shown.is_empty() { "Listening..." } else { partial.as_str() };
+        lines.push(Line::styled(current.to_string(), fg(theme.highlight).add_modifier(Modifier::BOLD)));
+
+        session.terminal.draw(|frame| {
+            let block = Block::default()
+                .title(format!(" {} (q to stop) ", title))
+                .borders(Borders::ALL)
+                .border_style(fg(theme.border));
+            let inner = block.inner(frame.size());
+            frame.render_widget(block, frame.size());
+            // Keep the newest line at the bottom, scrolling older ones off the top
+            let width = inner.width.max(1) as usize;
+            let rows: usize = lines.iter().map(|line| line.width().div_ceil(width).max(1)).sum();
+            let height = inner.height as usize;
+            let top = Rect {
+                y: inner.y + height.saturating_sub(rows) as u16,
+                height: rows.min(height) as u16,
+                ..inner
+            };
+            let paragraph = Paragraph::new(Text::from(lines.clone()))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .scroll((rows.saturating_sub(height) as u16, 0));
+            frame.render_widget(paragraph, top);
+        })?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                let stop = matches!(key.code, KeyCode::Char('q' | 'c') | KeyCode::Esc);
+                if key.kind == KeyEventKind::Press && stop {
+                    return Ok(finished);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use crossterm::cursor::{Hide, Show};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::symbols::Marker;
+use ratatui::Terminal;
+use std::io::{stdout, Stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+/// What the attached console can render. Detection is environment-based so it can be
+/// constructed directly in tests or overridden by callers.
+#[derive(Clone, Copy, Debug)]
+pub struct ConsoleCapabilities {
+    /// Non-ASCII glyphs (braille, dashes, ×) render correctly
+    pub unicode: bool,
+    /// Colored output is wanted
+    pub color: bool,
+    /// 24-bit color is advertised (`COLORTERM=truecolor`)
+    pub truecolor: bool,
+    /// Running over SSH or inside tmux/screen, where output bandwidth is often limited
+    pub remote: bool,
+}
+
+impl Default for ConsoleCapabilities {
+    fn default() -> Self {
+        Self {
+            unicode: true,
+            color: true,
+            truecolor: true,
+            remote: false,
+        }
+    }
+}
+
+impl ConsoleCapabilities {
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok(), cfg!(windows))
+    }
+
+    /// Capabilities from an environment lookup, split out so it is testable without a console.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>, windows: bool) -> Self {
+        let dumb = var("TERM").is_some_and(|term| term == "dumb");
+        let color = var("NO_COLOR").is_none_or(|v| v.is_empty()) && !dumb;
+
+        let unicode = if windows {
+            // Legacy conhost uses an OEM code page and raster fonts without braille; Windows
+            // Terminal, ConEmu and VS Code's terminal are UTF-8 capable.
+            var("WT_SESSION").is_some()
+                || var("ConEmuANSI").is_some_and(|v| v == "ON")
+                || var("TERM_PROGRAM").is_some()
+        } else {
+            let locale = var("LC_ALL")
+                .filter(|v| !v.is_empty())
+                .or_else(|| var("LC_CTYPE").filter(|v| !v.is_empty()))
+                .or_else(|| var("LANG"))
+                .unwrap_or_default()
+                .to_ascii_uppercase();
+            !dumb && (locale.is_empty() || locale.contains("UTF-8") || locale.contains("UTF8"))
+        };
+
+        let truecolor = var("COLORTERM").is_some_and(|v| v == "truecolor" || v == "24bit");
+        let multiplexer = var("TMUX").is_some()
+            || var("STY").is_some()
+            || var("TERM").is_some_and(|term| term.starts_with("screen") || term.starts_with("tmux"));
+        let ssh = ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"].iter().any(|name| var(name).is_some());
+
+        Self {
+            unicode,
+            color,
+            truecolor,
+            remote: ssh || multiplexer,
+        }
+    }
+
+    /// Remote sessions without truecolor are usually slow links or old multiplexers; simple
+    /// rendering keeps them responsive.
+    pub fn limited(&self) -> bool {
+        self.remote && !self.truecolor
+    }
+
+    /// Marker for line charts: braille when available, plain dots otherwise.
+    pub fn line_marker(&self) -> Marker {
+        if self.unicode {
+            Marker::Braille
+        } else {
+            Marker::Dot
+        }
+    }
+
+    pub fn dash(&self) -> &'static str {
+        if self.unicode {
+            "—"
+        } else {
+            "-"
+        }
+    }
+
+    pub fn times(&self) -> &'static str {
+        if self.unicode {
+            "×"
+        } else {
+            "x"
+        }
+    }
+}
+
+/// How much detail the visualizer draws.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum DisplayMode {
+    /// Simple rendering on limited, small or slow terminals; full detail otherwise
+    #[default]
+    Auto,
+    Full,
+    /// Lower refresh rate and a coarser spectrogram
+    Simple,
+}
+
+/// How spectrogram cells are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SpectrogramRender {
+    /// One colored block per cell, four intensity levels
+    #[default]
+    Heatmap,
+    /// Colored half blocks, doubling the vertical resolution
+    Blocks,
+    /// Monochrome braille dots (2×4 per cell), dithered by intensity
+    Braille,
+}
+
+/// Terminals smaller than this get simple rendering in auto mode.
+pub const MIN_FULL_SIZE: (u16, u16) = (80, 24);
+
+/// Frames slower than this to draw (on average) suggest a high-latency link.
+pub const SLOW_FRAME_MS: f64 = 40.0;
+
+/// Whether a `TerminalSession` has the console, so the panic hook knows to restore it.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Raw mode + alternate screen, restored on drop and on panic so a crash never leaves the
+/// console in raw mode (which on conhost also swallows subsequent input echo).
+pub struct TerminalSession {
+    pub terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalSession {
+    pub fn start() -> Result<Self> {
+        // Installed once and left in place, chaining to whatever hook was there before
+        static HOOK: Once = Once::new();
+        HOOK.call_once(|| {
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                if ACTIVE.swap(false, Ordering::SeqCst) {
+                    restore();
+                }
+                previous(info);
+            }));
+        });
+
+        enable_raw_mode()?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        let setup = || -> Result<Terminal<CrosstermBackend<Stdout>>> {
+            execute!(stdout(), EnterAlternateScreen, Hide)?;
+            let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+            terminal.clear()?;
+            Ok(terminal)
+        };
+        match setup() {
+            Ok(terminal) => Ok(Self { terminal }),
+            // Not left in raw mode, where the error message would print without newlines
+            Err(err) => {
+                ACTIVE.store(false, Ordering::SeqCst);
+                restore();
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        if ACTIVE.swap(false, Ordering::SeqCst) {
+            restore();
+        }
+    }
+}
+
+fn restore() {
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{compute_spectrogram, AudioData};
+    use crate::stats::compute_stats;
+    use crate::visualization::dump::DumpFormat;
+    use crate::visualization::Visualizer;
+
+    fn detect(vars: &[(&str, &str)], windows: bool) -> ConsoleCapabilities {
+        ConsoleCapabilities::from_env(
+            |name| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string()),
+            windows,
+        )
+    }
+
+    #[test]
+    fn utf8_locale_has_unicode_and_color() {
+        let caps = detect(&[("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")], false);
+        assert!(caps.unicode && caps.color);
+        assert!(!caps.truecolor && !caps.remote);
+    }
+
+    #[test]
+    fn dumb_terminal_has_neither() {
+        let caps = detect(&[("TERM", "dumb"), ("LANG", "en_US.UTF-8")], false);
+        assert!(!caps.unicode && !caps.color);
+    }
+
+    #[test]
+    fn no_color_turns_color_off_unless_empty() {
+        assert!(!detect(&[("NO_COLOR", "1")], false).color);
+        assert!(detect(&[("NO_COLOR", "")], false).color);
+    }
+
+    #[test]
+    fn lc_all_overrides_lang() {
+        assert!(!detect(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")], false).unicode);
+        assert!(detect(&[("LC_ALL", ""), ("LC_CTYPE", "de_DE.utf8"), ("LANG", "C")], false).unicode);
+        assert!(detect(&[], false).unicode);
+    }
+
+    #[test]
+    fn windows_needs_a_modern_terminal_for_unicode() {
+        assert!(!detect(&[], true).unicode);
+        assert!(detect(&[("WT_SESSION", "1")], true).unicode);
+        assert!(detect(&[("ConEmuANSI", "ON")], true).unicode);
+        assert!(!detect(&[("ConEmuANSI", "OFF")], true).unicode);
+    }
+
+    #[test]
+    fn ssh_and_multiplexers_are_remote() {
+        assert!(detect(&[("SSH_CONNECTION", "10.0.0.1 22 10.0.0.2 5000")], false).remote);
+        assert!(detect(&[("TMUX", "/tmp/tmux-1000/default,1,0")], false).remote);
+        assert!(detect(&[("TERM", "screen-256color")], false).remote);
+        let truecolor = detect(&[("SSH_TTY", "/dev/pts/0"), ("COLORTERM", "truecolor")], false);
+        assert!(truecolor.remote && !truecolor.limited());
+        assert!(detect(&[("SSH_TTY", "/dev/pts/0")], false).limited());
+    }
+
+    #[test]
+    fn braille_only_where_unicode_renders() {
+        let sample_rate = 8000;
+        let render = |unicode: bool| {
+            let samples: Vec<f32> = (0..sample_rate)
+                .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin() * 0.5)
+                .collect();
+            let audio = AudioData {
+                samples: samples.into(),
+                extra_channels: Vec::new(),
+                sample_rate: sample_rate as u32,
+                channels: 1,
+                bits_per_sample: Some(16),
+                tags: Default::default(),
+            };
+            let spectrogram = compute_spectrogram(&audio, 512).unwrap();
+            let stats = compute_stats(&audio, &spectrogram, &[]);
+            let capabilities = ConsoleCapabilities { unicode, color: false, truecolor: false, remote: false };
+            Visualizer::new(audio, spectrogram, Vec::new(), stats)
+                .with_capabilities(capabilities)
+                .dump(100, 30, DumpFormat::Text)
+                .unwrap()
+        };
+        let braille = |text: &str| text.chars().any(|c| ('\u{2800}'..='\u{28ff}').contains(&c));
+
+        let plain = render(false);
+        assert!(plain.contains("Waveform") && plain.contains("Spectrogram"), "{}", plain);
+        assert!(!braille(&plain), "{}", plain);
+        assert!(braille(&render(true)));
+    }
+}
@@ -0,0 +1,231 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::Visualizer;
+use crate::stats::notes::midi_number;
+use crate::stats::NOTE_NAMES;
+
+/// Steps across the file the left and right keys take, at most.
+const FRAME_STEPS: usize = 200;
+
+/// Highest harmonic number the cursor is read as when looking for the fundamental.
+const MAX_HARMONIC: u32 = 8;
+
+/// Harmonics scored per candidate fundamental.
+const SCORED_HARMONICS: u32 = 12;
+
+/// Lowest fundamental considered, in Hz.
+const MIN_FUNDAMENTAL: f32 = 20.0;
+
+/// Width of the comb's teeth, as a share of the time shown, and the most teeth drawn.
+const TOOTH: f64 = 0.02;
+const MAX_TEETH: usize = 64;
+
+/// A crosshair on the spectrogram, on a frame and a bin.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Cursor {
+    pub(super) frame: usize,
+    pub(super) bin: usize,
+    /// Whether the cursor locks onto spectral peaks
+    pub(super) snap: bool,
+    /// Whether the harmonics of the fundamental under the cursor are drawn
+    pub(super) comb: bool,
+    /// Harmonic number the cursor is on, when set by hand rather than found
+    pub(super) harmonic: Option<u32>,
+}
+
+/// The harmonic series the cursor is on.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Series {
+    pub(super) fundamental: f32,
+    /// Which harmonic of it the cursor is on, 1 being the fundamental itself
+    pub(super) harmonic: u32,
+}
+
+impl Series {
+    /// Nearest equal-tempered note to the fundamental (A4 = 440 Hz) and how far off it is,
+    /// e.g. `A3 +4¢`.
+    pub(super) fn note(&self) -> String {
+        let midi = midi_number(self.fundamental);
+        let nearest = midi.round() as i32;
+        format!(
+            "{}{} {:+.0}¢",
+            NOTE_NAMES[nearest.rem_euclid(12) as usize],
+            nearest.div_euclid(12) - 1,
+            (midi - nearest as f32) * 100.0
+        )
+    }
+}
+
+/// What lies under the cursor.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Readout {
+    pub(super) time: f64,
+    pub(super) frequency: f32,
+    pub(super) level: f32,
+}
+
+impl Visualizer {
+    /// Puts the cursor on the frame at the playhead, on its strongest bin.
+    pub(super) fn start_cursor(&mut self) {
+        let frames = self.spectrogram.time_points.len();
+        if frames == 0 {
+            self.status = Some("No spectrogram frames to read".to_string());
+            return;
+        }
+        let playhead = self.player.position();
+        let frame = self.spectrogram.time_points.partition_point(|&t| (t as f64) < playhead).min(frames - 1);
+        let levels = &self.spectrogram.magnitudes[frame];
+        let bin = (1..self.displayed_bins()).max_by(|&a, &b| levels[a].total_cmp(&levels[b])).unwrap_or(0);
+        self.cursor = Some(Cursor { frame, bin, snap: false, comb: false, harmonic: None });
+        self.info = Some(
+            "Cursor: arrows move, PgUp/PgDn jump, s snaps to peaks, h harmonics, Enter marks, Esc closes".to_string(),
+        );
+    }
+
+    pub(super) fn handle_cursor_key(&mut self, key: KeyEvent) {
+        let Some(mut cursor) = self.cursor else {
+            return;
+        };
+        let frames = self.spectrogram.time_points.len();
+        let bins = self.displayed_bins();
+        let frame_step = (frames / FRAME_STEPS).max(1);
+        let bin_step = (bins / 10).max(1);
+        // Up or down, for the snap to look for the next peak that way
+        let mut direction = 0;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('k') => {
+                self.cursor = None;
+                return;
+            }
+            KeyCode::Left => cursor.frame = cursor.frame.saturating_sub(frame_step),
+            KeyCode::Right => cursor.frame = (cursor.frame + frame_step).min(frames.saturating_sub(1)),
+            KeyCode::Up if cursor.snap => direction = 1,
+            KeyCode::Down if cursor.snap => direction = -1,
+            KeyCode::Up => cursor.bin = (cursor.bin + 1).min(bins - 1),
+            KeyCode::Down => cursor.bin = cursor.bin.saturating_sub(1),
+            KeyCode::PageUp => cursor.bin = (cursor.bin + bin_step).min(bins - 1),
+            KeyCode::PageDown => cursor.bin = cursor.bin.saturating_sub(bin_step),
+            KeyCode::Char('s') => cursor.snap = !cursor.snap,
+            KeyCode::Char('h') => cursor.comb = !cursor.comb,
+            KeyCode::Char('0') if cursor.comb => cursor.harmonic = None,
+            KeyCode::Char(digit @ '1'..='9') if cursor.comb => cursor.harmonic = digit.to_digit(10),
+            KeyCode::Enter => {
+                self.annotate_cursor();
+                return;
+            }
+            _ => return,
+        }
+        if cursor.snap {
+            cursor.bin = self.nearest_peak(cursor.frame, cursor.bin, direction);
+        }
+        self.cursor = Some(cursor);
+    }
+
+    /// The local maximum of `frame`'s spectrum nearest `bin` among the displayed bins,
+    /// or with `direction` the first one above (1) or below (-1) it; `bin` if there's none.
+    fn nearest_peak(&self, frame: usize, bin: usize, direction: i32) -> usize {
+        let levels = &self.spectrogram.magnitudes[frame];
+        let top = self.displayed_bins().min(levels.len() - 1);
+        let is_peak = |i: usize| i >= 1 && i < top && levels[i] > levels[i - 1] && levels[i] >= levels[i + 1];
+        let above = (bin + 1..top).find(|&i| is_peak(i));
+        let below = (1..bin.min(top)).rev().find(|&i| is_peak(i));
+        match direction {
+            1 => above.unwrap_or(bin),
+            -1 => below.unwrap_or(bin),
+            _ if is_peak(bin) => bin,
+            _ => match (above, below) {
+                (Some(a), Some(b)) => if a - bin < bin - b { a } else { b },
+                (a, b) => a.or(b).unwrap_or(bin),
+            },
+        }
+    }
+
+    /// Time, frequency and level under the cursor. On a peak, the frequency and level are
+    /// the vertex of the parabola through it and its neighbours (in dB), which is much
+    /// closer to a tone's own than the bin's center.
+    pub(super) fn cursor_readout(&self) -> Option<Readout> {
+        let cursor = self.cursor?;
+        let frame = cursor.frame.min(self.spectrogram.time_points.len().checked_sub(1)?);
+        let levels = &self.spectrogram.magnitudes[frame];
+        let bin = cursor.bin.min(levels.len().checked_sub(1)?);
+        let frequencies = &self.spectrogram.frequencies;
+        let bin_width = frequencies.get(1).map_or(0.0, |f| f - frequencies[0]);
+        let (mut frequency, mut level) = (frequencies[bin], levels[bin]);
+        if bin >= 1 && bin + 1 < levels.len() && levels[bin] > levels[bin - 1] && levels[bin] >= levels[bin + 1] {
+            let (left, middle, right) = (levels[bin - 1], levels[bin], levels[bin + 1]);
+            let curvature = left - 2.0 * middle + right;
+            if curvature < 0.0 {
+                let offset = 0.5 * (left - right) / curvature;
+                frequency += offset * bin_width;
+                level = middle - 0.25 * (left - right) * offset;
+            }
+        }
+        Some(Readout { time: self.spectrogram.time_points[frame] as f64, frequency, level })
+    }
+
+    /// The harmonic series through the cursor, while the comb is shown. Unless the harmonic
+    /// number was given, each of the first `MAX_HARMONIC` is tried, and the one whose
+    /// fundamental's harmonics stand highest over the frame's median on average wins: a
+    /// fundamental an octave too low puts half its teeth between the partials.
+    pub(super) fn cursor_series(&self, readout: &Readout) -> Option<Series> {
+        let cursor = self.cursor.filter(|cursor| cursor.comb)?;
+        if let Some(harmonic) = cursor.harmonic {
+            return Some(Series { fundamental: readout.frequency / harmonic as f32, harmonic });
+        }
+        let levels = &self.spectrogram.magnitudes[cursor.frame.min(self.spectrogram.magnitudes.len().checked_sub(1)?)];
+        let frequencies = &self.spectrogram.frequencies;
+        let bin_width = frequencies.get(1).map_or(0.0, |f| f - frequencies[0]);
+        let top = frequencies[self.displayed_bins() - 1];
+        let mut sorted = levels.clone();
+        sorted.sort_by(f32::total_cmp);
+        let median = sorted.get(sorted.len() / 2).copied()?;
+        // Strongest bin within half a bin of a frequency, over the median
+        let level = |frequency: f32| {
+            let bin = (frequency / bin_width).round() as usize;
+            let around = levels.get(bin.saturating_sub(1)..(bin + 2).min(levels.len()))?;
+            Some(around.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b)) - median)
+        };
+        (1..=MAX_HARMONIC)
+            .map(|harmonic| readout.frequency / harmonic as f32)
+            .filter(|&fundamental| fundamental >= MIN_FUNDAMENTAL.max(bin_width))
+            .filter_map(|fundamental| {
+                let scores: Vec<f32> = (1..=SCORED_HARMONICS)
+                    .map(|k| fundamental * k as f32)
+                    .take_while(|&f| f <= top)
+                    .filter_map(|f| level(f).map(|db| db.max(0.0)))
+                    .collect();
+                (!scores.is_empty()).then(|| (fundamental, scores.iter().sum::<f32>() / scores.len() as f32))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(fundamental, _)| Series {
+                fundamental,
+                harmonic: (readout.frequency / fundamental).round() as u32,
+            })
+    }
+
+    /// Teeth of the comb at the cursor: a short line at each harmonic up to `max_frequency`,
+    /// `span` being the seconds shown.
+    pub(super) fn comb_lines(series: &Series, time: f64, span: f64, max_frequency: f64) -> Vec<[(f64, f64); 2]> {
+        let half = span * TOOTH / 2.0;
+        (1..)
+            .map(|k| series.fundamental as f64 * k as f64)
+            .take_while(|&f| f <= max_frequency)
+            .take(MAX_TEETH)
+            .map(|f| [(time - half, f), (time + half, f)])
+            .collect()
+    }
+
+    /// Adds a marker at the cursor's time, named with its frequency and level, and the
+    /// fundamental while the comb is shown.
+    fn annotate_cursor(&mut self) {
+        let Some(readout) = self.cursor_readout() else {
+            return;
+        };
+        let mut name = format!("{:.1} Hz, {}", readout.frequency, self.spectrogram_params.format_level(readout.level));
+        if let Some(series) = self.cursor_series(&readout) {
+            name = format!("{}, f0 {:.1} Hz {}", name, series.fundamental, series.note());
+        }
+        self.info = Some(format!("Marked {} at {:.3}s", name, readout.time));
+        self.add_marker(readout.time, &name);
+    }
+}
@@ -0,0 +1,27 @@
+use anyhow::{anyhow, Result};
+
+use super::Visualizer;
+use crate::stats::doppler::{fit_doppler, track_tone};
+
+/// Points the fitted curve is drawn with across the selection.
+const CURVE_POINTS: usize = 200;
+
+impl Visualizer {
+    /// Follows the strongest tone through the selection and fits a passing source's
+    /// Doppler curve to it, reporting the fit and drawing the curve.
+    pub(super) fn measure_doppler(&mut self) -> Result<()> {
+        let selection = self.selection.ok_or_else(|| anyhow!("Select a pass with 'v' first"))?;
+        let max_frequency = self.spectrogram.frequencies[self.displayed_bins() - 1];
+        let track = track_tone(&self.spectrogram, selection, max_frequency);
+        let fit = fit_doppler(&track).ok_or_else(|| anyhow!("No falling tone to fit in the selection"))?;
+        self.info = Some(fit.describe());
+        let (start, end) = selection;
+        self.doppler_curve = (0..=CURVE_POINTS)
+            .map(|i| {
+                let time = start + (end - start) * i as f64 / CURVE_POINTS as f64;
+                (time, fit.frequency_at(time))
+            })
+            .collect();
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+use ratatui::Terminal;
+use std::fmt::Write;
+
+use super::Visualizer;
+
+/// Output of `--dump`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum DumpFormat {
+    /// Text with ANSI color escapes (for `less -R` or terminals)
+    #[default]
+    Ansi,
+    /// Plain text
+    Text,
+}
+
+impl Visualizer {
+    /// Renders the view once at `width`×`height` cells, for output outside the terminal UI.
+    pub fn dump(&self, width: u16, height: u16, format: DumpFormat) -> Result<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+        self.draw(&mut terminal)?;
+        let buffer = terminal.backend().buffer();
+        Ok(match format {
+            DumpFormat::Ansi => to_ansi(buffer),
+            DumpFormat::Text => to_text(buffer),
+        })
+    }
+}
+
+fn to_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let line: String = (area.left()..area.right()).map(|x| buffer.get(x, y).symbol.as_str()).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+fn to_ansi(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut current = None;
+        for x in area.left()..area.right() {
+            let cell = buffer.get(x, y);
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if current != Some(style) {
+                text.push_str("\x1b[0");
+                if cell.fg != Color::Reset {
+                    let _ = write!(text, ";{}", sgr(cell.fg, false));
+                }
+                if cell.bg != Color::Reset {
+                    let _ = write!(text, ";{}", sgr(cell.bg, true));
+                }
+                for (modifier, code) in [
+                    (Modifier::BOLD, 1),
+                    (Modifier::DIM, 2),
+                    (Modifier::ITALIC, 3),
+                    (Modifier::UNDERLINED, 4),
+                    (Modifier::REVERSED, 7),
+                ] {
+                    if cell.modifier.contains(modifier) {
+                        let _ = write!(text, ";{}", code);
+                    }
+                }
+                text.push('m');
+                current = Some(style);
+            }
+            text.push_str(&cell.symbol);
+        }
+        text.push_str("\x1b[0m\n");
+    }
+    text
+}
+
+/// SGR parameters selecting `color` as the foreground, or the background with `background`.
+fn sgr(color: Color, background: bool) -> String {
+    let base = if background { 40 } else { 30 };
+    let named = |index: u8| {
+        if index < 8 {
+            (base + index as u16).to_string()
+        } else {
+            (base + 60 + index as u16 - 8).to_string()
+        }
+    };
+    match color {
+        Color::Reset => (base + 9).to_string(),
+        Color::Black => named(0),
+        Color::Red => named(1),
+        Color::Green => named(2),
+        Color::Yellow => named(3),
+        Color::Blue => named(4),
+        Color::Magenta => named(5),
+        Color::Cyan => named(6),
+        Color::Gray => named(7),
+        Color::DarkGray => named(8),
+        Color::LightRed => named(9),
+        Color::LightGreen => named(10),
+        Color::LightYellow => named(11),
+        Color::LightBlue => named(12),
+        Color::LightMagenta => named(13),
+        Color::LightCyan => named(14),
+        Color::White => named(15),
+        Color::Indexed(n) => format!("{};5;{}", base + 8, n),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+    }
+}
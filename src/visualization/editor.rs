@@ -0,0 +1,117 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::speech::TranscriptionSegment;
+
+/// Seconds a segment boundary moves per key press.
+pub const NUDGE_STEP: f64 = 0.1;
+
+/// Shortest segment the timing keys will produce.
+pub const MIN_SEGMENT: f64 = 0.05;
+
+pub enum InputOutcome {
+    Editing,
+    Commit(String),
+    Cancel,
+}
+
+/// Single-line text field with a cursor, for inline edits in a pane.
+pub struct LineInput {
+    text: Vec<char>,
+    cursor: usize,
+}
+
+impl LineInput {
+    pub fn new(text: &str) -> Self {
+        let text: Vec<char> = text.chars().collect();
+        let cursor = text.len();
+        Self { text, cursor }
+    }
+
+    pub fn handle(&mut self, key: KeyEvent) -> InputOutcome {
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return InputOutcome::Editing;
+        }
+        match key.code {
+            KeyCode::Enter => return InputOutcome::Commit(self.text.iter().collect()),
+            KeyCode::Esc => return InputOutcome::Cancel,
+            KeyCode::Char(c) => {
+                self.text.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => {}
+        }
+        InputOutcome::Editing
+    }
+
+    /// The text split around the cursor: before, the character under it, after.
+    pub fn parts(&self) -> (String, Option<char>, String) {
+        let before = self.text[..self.cursor].iter().collect();
+        let at = self.text.get(self.cursor).copied();
+        let after = self.text.get(self.cursor + 1..).unwrap_or_default().iter().collect();
+        (before, at, after)
+    }
+}
+
+/// Transcript edit mode: a selected segment, and a text field while its text is being edited.
+pub struct TranscriptEditor {
+    pub selected: usize,
+    pub input: Option<LineInput>,
+}
+
+impl TranscriptEditor {
+    pub fn new(selected: usize) -> Self {
+        Self {
+            selected,
+            input: None,
+        }
+    }
+}
+
+/// One change to a segment, with enough to apply it in either direction.
+struct Edit {
+    index: usize,
+    before: TranscriptionSegment,
+    after: TranscriptionSegment,
+}
+
+/// Undo/redo stacks for transcript edits. A new edit clears the redo stack.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    pub fn record(&mut self, index: usize, before: TranscriptionSegment, after: TranscriptionSegment) {
+        self.undo.push(Edit { index, before, after });
+        self.redo.clear();
+    }
+
+    /// Reverts the latest edit in `transcription`, returning the segment index it touched.
+    pub fn undo(&mut self, transcription: &mut [TranscriptionSegment]) -> Option<usize> {
+        let edit = self.undo.pop()?;
+        let index = edit.index;
+        *transcription.get_mut(index)? = edit.before.clone();
+        self.redo.push(edit);
+        Some(index)
+    }
+
+    pub fn redo(&mut self, transcription: &mut [TranscriptionSegment]) -> Option<usize> {
+        let edit = self.redo.pop()?;
+        let index = edit.index;
+        *transcription.get_mut(index)? = edit.after.clone();
+        self.undo.push(edit);
+        Some(index)
+    }
+}
@@ -0,0 +1,221 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Chart, Clear, Dataset, GraphType, Paragraph, Wrap};
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::Visualizer;
+use crate::audio::units::{BinScale, Normalization, Units};
+
+/// Strongest bins listed from the frame under the playhead.
+const PEAKS: usize = 3;
+
+impl Visualizer {
+    fn window_size(&self) -> usize {
+        self.spectrogram.frequencies.len() * 2
+    }
+
+    /// Spectrum of the windowed frame starting at the playhead, in the spectrogram's units.
+    fn frame_spectrum(&self, playhead: f64) -> Vec<(f64, f64)> {
+        let size = self.window_size();
+        let samples = &self.audio_data.samples;
+        if size == 0 || samples.len() < size {
+            return Vec::new();
+        }
+        let rate = self.audio_data.sample_rate as f64;
+        let start = ((playhead * rate) as usize).min(samples.len() - size);
+        let window = self.spectrogram_params.window.coefficients(size);
+        let mut buffer: Vec<Complex<f32>> = samples[start..start + size]
+            .iter()
+            .zip(&window)
+            .map(|(&x, &w)| Complex::new(x * w, 0.0))
+            .collect();
+        FftPlanner::new().plan_fft_forward(size).process(&mut buffer);
+        let scale = BinScale::new(&self.spectrogram_params, self.audio_data.sample_rate);
+        buffer[..size / 2]
+            .iter()
+            .enumerate()
+            .map(|(k, c)| (k as f64 * rate / size as f64, scale.level((c.norm() as f64).max(1e-12), k)))
+            .collect()
+    }
+
+    /// Overlay explaining the analysis parameters behind the current view.
+    pub(super) fn draw_explain(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let area = centered(area, 90, 80);
+        frame.render_widget(Clear, area);
+        let block = self.pane("Explain (x to close)");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(inner);
+        let charts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(columns[1]);
+
+        let size = self.window_size();
+        let rate = self.audio_data.sample_rate as f64;
+        let spectrum = self.frame_spectrum(playhead);
+        frame.render_widget(
+            Paragraph::new(self.explain_lines(size, rate, playhead, &spectrum)).wrap(Wrap { trim: true }),
+            columns[0],
+        );
+
+        let function = self.spectrogram_params.window;
+        let coefficients = function.coefficients(size);
+        let window: Vec<(f64, f64)> = coefficients
+            .iter()
+            .enumerate()
+            .step_by((size / 64).max(1))
+            .map(|(i, &w)| (i as f64, w as f64))
+            .collect();
+        let window_chart = Chart::new(vec![Dataset::default()
+            .marker(self.capabilities.line_marker())
+            .graph_type(GraphType::Line)
+            .style(self.fg(self.theme.waveform))
+            .data(&window)])
+        .block(self.pane(format!("{} window w[n]", function)))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, size as f64])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{}", size))]),
+        )
+        .y_axis(Axis::default().bounds([0.0, 1.0]).labels(vec![Span::raw("0"), Span::raw("1")]));
+        frame.render_widget(window_chart, charts[0]);
+
+        let max_frequency = self.max_frequency.map_or(rate / 2.0, |f| f as f64);
+        let top = self.spectrogram_params.full_scale(self.audio_data.sample_rate) as f64;
+        let bottom = top - 120.0;
+        let shown: Vec<(f64, f64)> = spectrum
+            .iter()
+            .copied()
+            .filter(|&(f, _)| f <= max_frequency)
+            .map(|(f, level)| (f, level.max(bottom)))
+            .collect();
+        let dft_chart = Chart::new(vec![Dataset::default()
+            .marker(self.capabilities.line_marker())
+            .graph_type(GraphType::Line)
+            .style(self.fg(self.theme.highlight))
+            .data(&shown)])
+        .block(self.pane(format!("DFT of the frame at {:.2}s", playhead)))
+        .x_axis(
+            Axis::default()
+                .title("Hz")
+                .bounds([0.0, max_frequency])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}", max_frequency))]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(self.spectrogram_params.unit_label())
+                .bounds([bottom, top])
+                .labels(vec![
+                    Span::raw(self.spectrogram_params.format_value(bottom as f32)),
+                    Span::raw(self.spectrogram_params.format_value(top as f32)),
+                ]),
+        );
+        frame.render_widget(dft_chart, charts[1]);
+    }
+
+    fn explain_lines(&self, size: usize, rate: f64, playhead: f64, spectrum: &[(f64, f64)]) -> Vec<Line<'static>> {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let resolution = rate / size.max(1) as f64;
+        let duration_ms = size as f64 / rate * 1000.0;
+        let hop = self.spectrogram_params.hop_size;
+        let function = self.spectrogram_params.window;
+        let overlap = 100.0 * (1.0 - hop as f64 / size.max(1) as f64);
+        let mut lines = vec![
+            Line::styled("Short-time Fourier transform", bold),
+            Line::raw(format!("Sample rate fs = {:.0} Hz, window N = {} samples", rate, size)),
+            Line::raw(format!(
+                "Each column is |DFT| of N samples weighted by a {} window, {}, which shapes the frame edges to trade leakage against resolution.",
+                function,
+                function.formula()
+            )),
+            Line::raw(format!(
+                "Hop = {} samples ({:.1} ms), so frames overlap by {:.0}%.",
+                hop,
+                hop as f64 / rate * 1000.0,
+                overlap.max(0.0)
+            )),
+            Line::raw(match function.overlap_ripple(size, hop) {
+                ripple if ripple < 0.1 => "The overlapping windows sum to a constant (COLA), so every sample carries the same weight.".to_string(),
+                ripple if ripple.is_finite() => format!("The overlapping windows don't sum to a constant: their total ripples by {:.2} dB, so some samples weigh more than others.", ripple),
+                _ => "The hop skips samples the windows never cover.".to_string(),
+            }),
+            Line::raw(format!(
+                "Levels are {}, window-compensated by {}.",
+                match self.spectrogram_params.units {
+                    Units::Dbfs => "amplitudes in dB",
+                    Units::Power => "power spectral density",
+                    Units::Amplitude => "linear amplitudes",
+                },
+                match (self.spectrogram_params.units, self.spectrogram_params.normalization) {
+                    (Units::Power, _) | (_, Normalization::Energy) => "the window's energy",
+                    (_, Normalization::Peak) => "its coherent gain",
+                    (_, Normalization::None) => "its size alone (|X|/N)",
+                }
+            )),
+            Line::raw(""),
+            Line::styled("Resolution", bold),
+            Line::raw(format!("Frequency: Δf = fs/N = {:.2} Hz per bin ({} bins up to {:.0} Hz)", resolution, size / 2, rate / 2.0)),
+            Line::raw(format!("Time: Δt = N/fs = {:.1} ms per window", duration_ms)),
+            Line::raw(format!(
+                "Δt·Δf = 1: doubling N to {} gives {:.2} Hz bins but {:.1} ms windows; halving it to {} gives {:.2} Hz and {:.1} ms.",
+                size * 2,
+                resolution / 2.0,
+                duration_ms * 2.0,
+                size / 2,
+                resolution * 2.0,
+                duration_ms / 2.0
+            )),
+            Line::raw(""),
+            Line::styled(format!("Frame at {:.2}s: strongest bins", playhead), bold),
+        ];
+        let mut peaks: Vec<(usize, f64, f64)> = spectrum
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|&(k, &(_, db))| {
+                let left = spectrum[k - 1].1;
+                let right = spectrum.get(k + 1).map_or(f64::NEG_INFINITY, |p| p.1);
+                db >= left && db > right
+            })
+            .map(|(k, &(f, db))| (k, f, db))
+            .collect();
+        peaks.sort_by(|a, b| b.2.total_cmp(&a.2));
+        if peaks.is_empty() {
+            lines.push(Line::raw("(no frame here)"));
+        }
+        for (k, f, level) in peaks.into_iter().take(PEAKS) {
+            let level = self.spectrogram_params.format_level(level as f32);
+            lines.push(Line::raw(format!("k = {:4}  f = k·Δf = {:7.1} Hz  {}", k, f, level)));
+        }
+        if !self.capabilities.unicode {
+            lines = lines.into_iter().map(ascii_line).collect();
+        }
+        lines
+    }
+}
+
+/// Spells out the math symbols for consoles without Unicode.
+fn ascii_line(line: Line<'static>) -> Line<'static> {
+    let style = line.spans.first().map(|span| span.style).unwrap_or_default();
+    let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+    let text = text.replace('Δ', "d").replace('π', "pi").replace('·', "*");
+    Line::styled(text, style)
+}
+
+/// A rectangle of the given percentage size centered in `area`.
+fn centered(area: Rect, width_percent: u16, height_percent: u16) -> Rect {
+    let width = area.width * width_percent / 100;
+    let height = area.height * height_percent / 100;
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,377 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use std::io::Write;
+
+use super::Visualizer;
+
+/// Whether the spectrogram is drawn as an inline bitmap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GraphicsMode {
+    /// Use a graphics protocol when the terminal is known to support one
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    /// Always draw with characters
+    None,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Sixel,
+}
+
+impl GraphicsMode {
+    pub fn resolve(self) -> Option<GraphicsProtocol> {
+        match self {
+            GraphicsMode::Auto => detect(|name| std::env::var(name).ok()),
+            GraphicsMode::Kitty => Some(GraphicsProtocol::Kitty),
+            GraphicsMode::Sixel => Some(GraphicsProtocol::Sixel),
+            GraphicsMode::None => None,
+        }
+    }
+}
+
+/// The protocol a terminal supports, judged from its environment. Querying the terminal
+/// would need a round trip before the UI starts, so only terminals known to support a
+/// protocol are recognized.
+pub fn detect(var: impl Fn(&str) -> Option<String>) -> Option<GraphicsProtocol> {
+    // Multiplexers drop or garble image escapes unless configured to pass them through
+    if var("TMUX").is_some() || var("STY").is_some() {
+        return None;
+    }
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || program == "WezTerm"
+        || program == "ghostty"
+    {
+        Some(GraphicsProtocol::Kitty)
+    } else if term.starts_with("foot")
+        || term.starts_with("mlterm")
+        || term.starts_with("contour")
+        || program == "mintty"
+    {
+        Some(GraphicsProtocol::Sixel)
+    } else {
+        None
+    }
+}
+
+/// What the last emitted image showed; it is only sent again when this changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct ImageKey {
+    area: Rect,
+    playhead_column: u16,
+    frames: usize,
+    window_size: usize,
+    hop_size: usize,
+    display_levels: (f32, f32),
+    colormap: super::theme::Colormap,
+}
+
+/// RGB pixels, row-major.
+pub(super) struct Bitmap {
+    pub(super) width: usize,
+    pub(super) height: usize,
+    pub(super) rgb: Vec<u8>,
+}
+
+impl Visualizer {
+    /// Frame and labels of the spectrogram pane when it is drawn as a bitmap; the image
+    /// itself is written after the frame by `draw_graphics`.
+    pub(super) fn draw_spectrogram_frame(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let mut title = vec![Span::raw(format!("Spectrogram{} ", self.signal_label()))];
+        title.extend(self.color_scale());
+        let block = self.pane(Line::from(title));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        if inner.height < 2 || inner.width == 0 {
+            return;
+        }
+        let duration = *self.spectrogram.time_points.last().unwrap_or(&0.0) as f64;
+        let max_freq = self.spectrogram.frequencies[self.displayed_bins() - 1];
+        let label = Line::styled(
+            format!("0-{:.0} Hz (bottom to top), 0.0-{:.1}s", max_freq, duration),
+            self.fg(self.theme.muted),
+        );
+        let label_area = Rect { y: inner.bottom() - 1, height: 1, ..inner };
+        frame.render_widget(Paragraph::new(label), label_area);
+        self.image_area.set(Some(Rect { height: inner.height - 1, ..inner }));
+    }
+
+    /// Writes the spectrogram image into the area reserved by the last draw, if it changed.
+    pub(super) fn draw_graphics(&mut self, out: &mut impl Write, playhead: f64) -> Result<()> {
+        let Some(protocol) = self.graphics else {
+            return Ok(());
+        };
+        let key = self.image_area.get().map(|area| {
+            let duration = self.player.duration().max(f64::EPSILON);
+            ImageKey {
+                area,
+                playhead_column: (playhead / duration * area.width as f64) as u16,
+                frames: self.spectrogram.time_points.len(),
+                window_size: self.spectrogram_params.window_size,
+                hop_size: self.spectrogram_params.hop_size,
+                display_levels: self.level_range(),
+                colormap: self.colormap,
+            }
+        });
+        if key == self.image_key {
+            return Ok(());
+        }
+        if protocol == GraphicsProtocol::Kitty {
+            write!(out, "\x1b_Ga=d,d=i,i=1,q=2\x1b\\")?;
+        }
+        self.image_key = key;
+        let Some(key) = key else {
+            out.flush()?;
+            return Ok(());
+        };
+
+        // Cell size in pixels, when the terminal reports its pixel size
+        let (cell_width, cell_height) = match crossterm::terminal::window_size() {
+            Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+                ((size.width / size.columns).max(1), (size.height / size.rows).max(1))
+            }
+            _ => (8, 16),
+        };
+        let area = key.area;
+        let width = (area.width as usize * cell_width as usize).min(4096);
+        let height = (area.height as usize * cell_height as usize).min(4096);
+        let bitmap = self.spectrogram_bitmap(width, height, playhead);
+
+        queue!(out, MoveTo(area.x, area.y))?;
+        match protocol {
+            GraphicsProtocol::Kitty => write_kitty(out, &bitmap, area)?,
+            GraphicsProtocol::Sixel => write_sixel(out, &bitmap)?,
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Forgets the emitted image, so the next `draw_graphics` sends it again.
+    pub(super) fn invalidate_graphics(&mut self) {
+        self.image_key = None;
+    }
+
+    pub(super) fn spectrogram_bitmap(&self, width: usize, height: usize, playhead: f64) -> Bitmap {
+        let frames = self.spectrogram.time_points.len();
+        let duration = self.player.duration().max(f64::EPSILON);
+        let playhead_x = (playhead > 0.0).then(|| (playhead / duration * width as f64) as usize);
+        let playhead_rgb = rgb(self.theme.playhead);
+
+        let mut pixels = vec![0; width * height * 3];
+        for x in 0..width {
+            if frames == 0 {
+                break;
+            }
+            let column = if playhead_x == Some(x) {
+                vec![playhead_rgb; height]
+            } else {
+                self.bitmap_column(x * frames / width, (x + 1) * frames / width, height)
+            };
+            for (y, color) in column.iter().enumerate() {
+                let offset = (y * width + x) * 3;
+                pixels[offset..offset + 3].copy_from_slice(color);
+            }
+        }
+        Bitmap { width, height, rgb: pixels }
+    }
+
+    /// Colors of one bitmap column, top to bottom, covering spectrogram frames `from..to`.
+    pub(super) fn bitmap_column(&self, from: usize, to: usize, height: usize) -> Vec<[u8; 3]> {
+        let bins = self.displayed_bins();
+        let palette = self.colormap.colors(&self.theme).map(rgb);
+        let (floor, top) = self.level_range();
+        let merged = self.spectrogram_pyramid.frames(&self.spectrogram.magnitudes, from, to);
+        (0..height)
+            .map(|y| {
+                // Low frequencies at the bottom
+                let bin = (height - 1 - y) * bins / height;
+                let magnitude = merged.iter().fold(f32::NEG_INFINITY, |a, frame| a.max(frame[bin]));
+                gradient(&palette, ((magnitude - floor) / (top - floor)).clamp(0.0, 1.0))
+            })
+            .collect()
+    }
+}
+
+/// Black below the display floor, then through the four palette colors.
+fn gradient(palette: &[[u8; 3]; 4], intensity: f32) -> [u8; 3] {
+    const FLOOR: f32 = 0.1;
+    let (from, to, fraction) = if intensity < FLOOR {
+        ([0, 0, 0], palette[0], intensity / FLOOR)
+    } else {
+        let position = (intensity - FLOOR) / (1.0 - FLOOR) * 3.0;
+        let index = (position as usize).min(2);
+        (palette[index], palette[index + 1], position - index as f32)
+    };
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    [mix(from[0], to[0]), mix(from[1], to[1]), mix(from[2], to[2])]
+}
+
+/// Approximate RGB for a terminal color (xterm's defaults for the named and indexed ones).
+fn rgb(color: Color) -> [u8; 3] {
+    const ANSI: [[u8; 3]; 16] = [
+        [0, 0, 0],
+        [205, 0, 0],
+        [0, 205, 0],
+        [205, 205, 0],
+        [0, 0, 238],
+        [205, 0, 205],
+        [0, 205, 205],
+        [229, 229, 229],
+        [127, 127, 127],
+        [255, 0, 0],
+        [0, 255, 0],
+        [255, 255, 0],
+        [92, 92, 255],
+        [255, 0, 255],
+        [0, 255, 255],
+        [255, 255, 255],
+    ];
+    let index = match color {
+        Color::Rgb(r, g, b) => return [r, g, b],
+        Color::Indexed(n) if n >= 232 => {
+            let level = 8 + (n - 232) * 10;
+            return [level, level, level];
+        }
+        Color::Indexed(n) if n >= 16 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            return [level(n / 36), level(n / 6 % 6), level(n % 6)];
+        }
+        Color::Indexed(n) => n as usize,
+        Color::Reset | Color::Gray => 7,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+    ANSI[index]
+}
+
+/// Kitty graphics protocol: raw RGB, base64-encoded in chunks, scaled to the cell area.
+fn write_kitty(out: &mut impl Write, bitmap: &Bitmap, area: Rect) -> Result<()> {
+    let data = base64(&bitmap.rgb);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,i=1,f=24,s={},v={},c={},r={},C=1,q=2,m={};",
+                bitmap.width, bitmap.height, area.width, area.height, more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// Sixel: colors quantized to a 6×6×6 cube, one run-length encoded pass per color and
+/// six-pixel band.
+fn write_sixel(out: &mut impl Write, bitmap: &Bitmap) -> Result<()> {
+    let quantize = |p: usize| {
+        let level = |v: u8| (v as usize * 5 + 127) / 255;
+        let rgb = &bitmap.rgb[p * 3..p * 3 + 3];
+        level(rgb[0]) * 36 + level(rgb[1]) * 6 + level(rgb[2])
+    };
+    let indices: Vec<usize> = (0..bitmap.width * bitmap.height).map(quantize).collect();
+
+    let mut sixel = String::from("\x1bPq");
+    sixel.push_str(&format!("\"1;1;{};{}", bitmap.width, bitmap.height));
+    let mut used = [false; 216];
+    for &index in &indices {
+        used[index] = true;
+    }
+    for (index, _) in used.iter().enumerate().filter(|(_, &used)| used) {
+        let percent = |v: usize| v * 100 / 5;
+        sixel.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            percent(index / 36),
+            percent(index / 6 % 6),
+            percent(index % 6)
+        ));
+    }
+
+    for band in (0..bitmap.height).step_by(6) {
+        let rows = (bitmap.height - band).min(6);
+        let mut colors: Vec<usize> = (band..band + rows)
+            .flat_map(|y| indices[y * bitmap.width..(y + 1) * bitmap.width].iter().copied())
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for (n, &color) in colors.iter().enumerate() {
+            if n > 0 {
+                sixel.push('$');
+            }
+            sixel.push_str(&format!("#{}", color));
+            let column = |x: usize| {
+                let bits = (0..rows)
+                    .filter(|&dy| indices[(band + dy) * bitmap.width + x] == color)
+                    .fold(0u8, |bits, dy| bits | (1 << dy));
+                (b'?' + bits) as char
+            };
+            let mut x = 0;
+            while x < bitmap.width {
+                let symbol = column(x);
+                let mut run = 1;
+                while x + run < bitmap.width && column(x + run) == symbol {
+                    run += 1;
+                }
+                if run > 3 {
+                    sixel.push_str(&format!("!{}{}", run, symbol));
+                } else {
+                    sixel.extend(std::iter::repeat_n(symbol, run));
+                }
+                x += run;
+            }
+        }
+        sixel.push('-');
+    }
+    sixel.push_str("\x1b\\");
+    out.write_all(sixel.as_bytes())?;
+    Ok(())
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
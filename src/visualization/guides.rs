@@ -0,0 +1,103 @@
+use anyhow::{anyhow, Result};
+use crossterm::event::KeyEvent;
+
+use super::editor::{InputOutcome, LineInput};
+use super::Visualizer;
+use crate::stats::NOTE_NAMES;
+
+/// Frequency lines closer than this to a typed frequency are that line, in Hz.
+const SAME_LINE: f64 = 0.05;
+
+/// Frequency of MIDI note `midi` in equal temperament with A4 = 440 Hz.
+pub(super) fn note_frequency(midi: i32) -> f64 {
+    440.0 * 2f64.powf((midi - 69) as f64 / 12.0)
+}
+
+/// Parses a frequency in Hz (`1000`, `1000Hz`, `1.5k`) or a note name with its octave
+/// (`A4`, `C#3`, `Bb2`).
+fn parse_frequency(text: &str) -> Result<f64> {
+    let text = text.trim();
+    let lower = text.to_ascii_lowercase();
+    let number = lower.trim_end_matches("hz").trim();
+    let kilohertz = || Some(number.strip_suffix('k')?.parse::<f64>().ok()? * 1000.0);
+    if let Some(hz) = number.parse::<f64>().ok().or_else(kilohertz) {
+        return (hz > 0.0).then_some(hz).ok_or_else(|| anyhow!("Frequency must be above 0 Hz"));
+    }
+    let split = text.find(|c: char| c.is_ascii_digit() || c == '-').unwrap_or(text.len());
+    let (name, octave) = text.split_at(split);
+    let octave: i32 = octave.parse().map_err(|_| anyhow!("`{}` is not a frequency in Hz or a note like A4", text))?;
+    let (letter, accidental) = name.split_at(name.len().min(1));
+    let pitch = NOTE_NAMES
+        .iter()
+        .position(|note| note.eq_ignore_ascii_case(letter))
+        .ok_or_else(|| anyhow!("`{}` is not a frequency in Hz or a note like A4", text))? as i32;
+    let shift = match accidental {
+        "" => 0,
+        "#" => 1,
+        "b" => -1,
+        _ => return Err(anyhow!("`{}` is not a frequency in Hz or a note like A4", text)),
+    };
+    Ok(note_frequency((octave + 1) * 12 + pitch + shift))
+}
+
+impl Visualizer {
+    /// Opens the prompt for a frequency line.
+    pub(super) fn start_frequency_input(&mut self) {
+        self.frequency_input = Some(LineInput::new(""));
+    }
+
+    pub(super) fn handle_frequency_key(&mut self, key: KeyEvent) {
+        let Some(input) = &mut self.frequency_input else {
+            return;
+        };
+        match input.handle(key) {
+            InputOutcome::Editing => {}
+            InputOutcome::Cancel => self.frequency_input = None,
+            InputOutcome::Commit(text) => {
+                self.frequency_input = None;
+                let result = self.toggle_frequency_line(&text);
+                self.report(result);
+            }
+        }
+    }
+
+    /// Adds a line at the typed frequency, or removes the one already there; `clear`
+    /// removes them all.
+    fn toggle_frequency_line(&mut self, text: &str) -> Result<()> {
+        if text.trim().eq_ignore_ascii_case("clear") {
+            self.info = Some(format!("Removed {} frequency lines", self.frequency_markers.len()));
+            self.unsaved_edits |= !self.frequency_markers.is_empty();
+            self.frequency_markers.clear();
+            return Ok(());
+        }
+        let frequency = parse_frequency(text)?;
+        match self.frequency_markers.iter().position(|&f| (f - frequency).abs() < SAME_LINE) {
+            Some(index) => {
+                self.frequency_markers.remove(index);
+                self.info = Some(format!("Removed the line at {:.1} Hz", frequency));
+            }
+            None => {
+                let index = self.frequency_markers.partition_point(|&f| f < frequency);
+                self.frequency_markers.insert(index, frequency);
+                self.info = Some(format!("Line at {:.1} Hz", frequency));
+            }
+        }
+        self.unsaved_edits = true;
+        Ok(())
+    }
+
+    /// Equal-tempered notes (A4 = 440 Hz) up to `max_frequency` for the note grid, as
+    /// (Hz, whether it's an A). Notes closer together than a row of `rows` are left out,
+    /// except the A's, so the low end stays readable.
+    pub(super) fn note_grid_lines(&self, max_frequency: f64, rows: usize) -> Vec<(f64, bool)> {
+        if !self.note_grid {
+            return Vec::new();
+        }
+        let row = max_frequency / rows.max(1) as f64;
+        let semitone = 2f64.powf(1.0 / 12.0) - 1.0;
+        (0..=127)
+            .map(|midi| (note_frequency(midi), midi % 12 == 9))
+            .filter(|&(frequency, a)| frequency <= max_frequency && (a || frequency * semitone >= row))
+            .collect()
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+use crate::config::toml::{Table, Value};
+
+/// Viewer actions that can be bound to keys under `[keys]` in the config, e.g.
+/// `play = "p"` or `quit = ["q", "ctrl-c"]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    TogglePlay,
+    SpeedUp,
+    SpeedDown,
+    SeekBack,
+    SeekForward,
+    Select,
+    Loop,
+    SpectralEdit,
+    Doppler,
+    Cursor,
+    FrequencyLine,
+    NoteGrid,
+    FloorDown,
+    FloorUp,
+    CeilingDown,
+    CeilingUp,
+    AutoLevel,
+    ToggleStats,
+    ToggleSpeech,
+    ToggleStereo,
+    MidSide,
+    ToggleSpectrum,
+    TogglePsd,
+    ToggleTuner,
+    ToggleMeter,
+    ToggleRta,
+    ToggleScope,
+    ToggleScroll,
+    /// Mute or unmute a track of a multi-track session, counting from 0
+    MuteTrack(usize),
+    Explain,
+    Playground,
+    Edit,
+    Save,
+    AddMarker,
+    RemoveMarker,
+    NextMarker,
+    PreviousMarker,
+    Undo,
+    Redo,
+}
+
+const ACTIONS: &[(&str, Action, &[&str])] = &[
+    ("quit", Action::Quit, &["q", "esc"]),
+    ("play", Action::TogglePlay, &["space"]),
+    ("speed_up", Action::SpeedUp, &["+", "="]),
+    ("speed_down", Action::SpeedDown, &["-"]),
+    ("seek_back", Action::SeekBack, &["left"]),
+    ("seek_forward", Action::SeekForward, &["right"]),
+    ("select", Action::Select, &["v"]),
+    ("loop", Action::Loop, &["l", "L"]),
+    ("spectral_edit", Action::SpectralEdit, &["b"]),
+    ("doppler", Action::Doppler, &["D"]),
+    ("cursor", Action::Cursor, &["k"]),
+    ("frequency_line", Action::FrequencyLine, &["h"]),
+    ("note_grid", Action::NoteGrid, &["g"]),
+    ("floor_down", Action::FloorDown, &["["]),
+    ("floor_up", Action::FloorUp, &["]"]),
+    ("ceiling_down", Action::CeilingDown, &["{"]),
+    ("ceiling_up", Action::CeilingUp, &["}"]),
+    ("auto_level", Action::AutoLevel, &["A"]),
+    ("stats", Action::ToggleStats, &["i"]),
+    ("speech", Action::ToggleSpeech, &["r"]),
+    ("stereo", Action::ToggleStereo, &["s"]),
+    ("mid_side", Action::MidSide, &["c"]),
+    ("spectrum", Action::ToggleSpectrum, &["f"]),
+    ("psd", Action::TogglePsd, &["d"]),
+    ("tuner", Action::ToggleTuner, &["u"]),
+    ("meter", Action::ToggleMeter, &["y"]),
+    ("rta", Action::ToggleRta, &["t"]),
+    ("scope", Action::ToggleScope, &["o"]),
+    ("scroll", Action::ToggleScroll, &["a"]),
+    ("mute_1", Action::MuteTrack(0), &["1"]),
+    ("mute_2", Action::MuteTrack(1), &["2"]),
+    ("mute_3", Action::MuteTrack(2), &["3"]),
+    ("mute_4", Action::MuteTrack(3), &["4"]),
+    ("mute_5", Action::MuteTrack(4), &["5"]),
+    ("mute_6", Action::MuteTrack(5), &["6"]),
+    ("mute_7", Action::MuteTrack(6), &["7"]),
+    ("mute_8", Action::MuteTrack(7), &["8"]),
+    ("mute_9", Action::MuteTrack(8), &["9"]),
+    ("explain", Action::Explain, &["x"]),
+    ("playground", Action::Playground, &["p"]),
+    ("edit", Action::Edit, &["e"]),
+    ("save", Action::Save, &["w"]),
+    ("add_marker", Action::AddMarker, &["m"]),
+    ("remove_marker", Action::RemoveMarker, &["M"]),
+    ("next_marker", Action::NextMarker, &["n"]),
+    ("previous_marker", Action::PreviousMarker, &["N"]),
+    ("undo", Action::Undo, &["ctrl-z"]),
+    ("redo", Action::Redo, &["ctrl-r"]),
+];
+
+/// A key plus whether Ctrl/Alt were held. Shift is folded into the character.
+type Binding = (KeyCode, bool, bool);
+
+pub struct Keymap {
+    bindings: HashMap<Binding, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        for (_, action, keys) in ACTIONS {
+            for key in *keys {
+                bindings.insert(parse_key(key).expect("default bindings parse"), *action);
+            }
+        }
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Defaults with `[keys]` overrides. Binding an action replaces all of its default keys.
+    pub fn from_config(table: &Table) -> Result<Self> {
+        let mut keymap = Self::default();
+        for (name, action, _) in ACTIONS {
+            let Some(value) = table.get(&format!("keys.{}", name)) else {
+                continue;
+            };
+            let keys: Vec<&str> = match value {
+                Value::String(key) => vec![key],
+                Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                _ => return Err(anyhow!("keys.{} must be a key or an array of keys", name)),
+            };
+            keymap.bindings.retain(|_, bound| bound != action);
+            for key in keys {
+                let binding = parse_key(key).ok_or_else(|| anyhow!("Unknown key `{}` for keys.{}", key, name))?;
+                keymap.bindings.insert(binding, *action);
+            }
+        }
+        for name in table.keys().filter_map(|key| key.strip_prefix("keys.")) {
+            if !ACTIONS.iter().any(|(action, _, _)| *action == name) {
+                return Err(anyhow!("Unknown action keys.{}", name));
+            }
+        }
+        Ok(keymap)
+    }
+
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        self.bindings.get(&(key.code, ctrl, alt)).copied()
+    }
+}
+
+/// Parses `q`, `M`, `space`, `esc`, `left`, `f5`, `ctrl-z`, `alt-x` and the like.
+fn parse_key(text: &str) -> Option<Binding> {
+    let mut ctrl = false;
+    let mut alt = false;
+    let mut rest = text;
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        if let Some(stripped) = lower.strip_prefix("ctrl-").filter(|s| !s.is_empty()) {
+            ctrl = true;
+            rest = &rest[rest.len() - stripped.len()..];
+        } else if let Some(stripped) = lower.strip_prefix("alt-").filter(|s| !s.is_empty()) {
+            alt = true;
+            rest = &rest[rest.len() - stripped.len()..];
+        } else {
+            break;
+        }
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(if ctrl { c.to_ascii_lowercase() } else { c }),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+        },
+    };
+    Some((code, ctrl, alt))
+}
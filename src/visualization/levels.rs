@@ -0,0 +1,87 @@
+use clap::ValueEnum;
+
+use super::Visualizer;
+use crate::audio::histogram::LevelHistogram;
+
+/// Step of the floor and ceiling keys, in dB.
+const LEVEL_STEP: f32 = 5.0;
+
+/// Narrowest span of levels the colormap is stretched over, in dB.
+pub(super) const MIN_SPAN: f32 = 10.0;
+
+/// Lowest the floor goes below full scale, in dB.
+const MAX_RANGE: f32 = 200.0;
+
+/// Percentiles of the spectrogram's levels auto-level maps to the bottom and the top of
+/// the colormap: the noise floor shows as the darkest color, and only the strongest
+/// components reach the brightest.
+pub(super) const AUTO_PERCENTILES: (f32, f32) = (5.0, 99.0);
+
+/// How spectrogram levels are mapped onto the colormap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Contrast {
+    /// From the `AUTO_PERCENTILES` of the spectrogram's levels, refitted whenever it's
+    /// recomputed
+    #[default]
+    Auto,
+    /// From a range below full scale (100 dB to start with), changed only by hand
+    Fixed,
+}
+
+impl Visualizer {
+    /// Floor and top of the `AUTO_PERCENTILES` of the displayed bins' levels, from their
+    /// histogram over the whole spectrogram, computed on first use.
+    pub(super) fn auto_levels(&self) -> Option<(f32, f32)> {
+        if self.auto_levels.get().is_none() {
+            let histogram = LevelHistogram::new(&self.spectrogram.magnitudes, self.displayed_bins());
+            let levels = histogram.percentile(AUTO_PERCENTILES.0).zip(histogram.percentile(AUTO_PERCENTILES.1));
+            self.auto_levels.set(levels.map(|(low, high)| (low.min(high - MIN_SPAN), high)));
+        }
+        self.auto_levels.get()
+    }
+
+    /// Switches to fixed contrast at the levels shown, so they can be changed by hand.
+    pub(super) fn fix_levels(&mut self) {
+        if self.contrast == Contrast::Fixed {
+            return;
+        }
+        let (floor, top) = self.level_range();
+        let full_scale = self.full_scale();
+        self.display_headroom = (full_scale - top).clamp(0.0, MAX_RANGE - MIN_SPAN);
+        self.display_range = (full_scale - floor).clamp(self.display_headroom + MIN_SPAN, MAX_RANGE);
+        self.contrast = Contrast::Fixed;
+    }
+
+    /// Moves the display floor up (positive `delta`) or down, in dB.
+    pub(super) fn adjust_floor(&mut self, delta: f32) {
+        self.fix_levels();
+        let range = self.display_range - delta * LEVEL_STEP;
+        self.display_range = range.clamp(self.display_headroom + MIN_SPAN, MAX_RANGE);
+        self.describe_levels();
+    }
+
+    /// Moves the display ceiling up (positive `delta`) or down, in dB.
+    pub(super) fn adjust_ceiling(&mut self, delta: f32) {
+        self.fix_levels();
+        let headroom = self.display_headroom - delta * LEVEL_STEP;
+        self.display_headroom = headroom.clamp(0.0, self.display_range - MIN_SPAN);
+        self.describe_levels();
+    }
+
+    /// Goes back to auto contrast, fitted afresh.
+    pub(super) fn auto_level(&mut self) {
+        self.contrast = Contrast::Auto;
+        self.auto_levels.set(None);
+        if self.auto_levels().is_none() {
+            self.status = Some("No levels to fit the display to".to_string());
+            return;
+        }
+        self.describe_levels();
+    }
+
+    fn describe_levels(&mut self) {
+        let (floor, ceiling) = self.level_range();
+        let params = &self.spectrogram_params;
+        self.info = Some(format!("Display {} to {}", params.format_value(floor), params.format_level(ceiling)));
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::console::{ConsoleCapabilities, TerminalSession};
+use super::theme::Theme;
+
+const SPINNER_UNICODE: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_ASCII: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Redraw interval, which is also how often keys are checked.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Progress shown while the file is decoded, analyzed and transcribed before the viewer
+/// opens, drawn on its own thread so it keeps moving while the work blocks. Messages are
+/// lines like the ones otherwise printed to stderr: the newest is the step in progress,
+/// the ones before it are done. Closing the screen prints them all to stderr, so they are
+/// still there after the viewer exits.
+pub struct LoadingScreen {
+    sender: Sender<(String, Option<Arc<AtomicBool>>)>,
+    thread: Option<JoinHandle<Vec<String>>>,
+}
+
+impl LoadingScreen {
+    /// Opens the screen; `title` names what is loading.
+    pub fn start(title: String, theme: Theme) -> Result<Self> {
+        let session = TerminalSession::start()?;
+        let (sender, receiver) = mpsc::channel();
+        let capabilities = ConsoleCapabilities::detect();
+        let thread = std::thread::spawn(move || draw_loop(session, &receiver, &title, &theme, &capabilities));
+        Ok(Self { sender, thread: Some(thread) })
+    }
+
+    pub fn message(&self, text: impl Into<String>) {
+        // The drawing thread only stops when the screen closes
+        let _ = self.sender.send((text.into(), None));
+    }
+
+    /// A step that `Esc` or `c` cancel, by setting `cancel`, until the next message.
+    pub fn cancellable(&self, text: impl Into<String>, cancel: Arc<AtomicBool>) {
+        let _ = self.sender.send((text.into(), Some(cancel)));
+    }
+
+    /// Closes the screen, restoring the terminal, and prints the messages to stderr; the
+    /// same as dropping it.
+    pub fn finish(self) {}
+}
+
+impl Drop for LoadingScreen {
+    fn drop(&mut self) {
+        // Disconnecting the channel stops the drawing thread
+        let (sender, _) = mpsc::channel();
+        drop(std::mem::replace(&mut self.sender, sender));
+        if let Some(lines) = self.thread.take().and_then(|thread| thread.join().ok()) {
+            for line in lines {
+                eprintln!("{}", line);
+            }
+        }
+    }
+}
+
+/// Draws until the channel disconnects; returns the messages received. `q`, `Esc` or
+/// Ctrl-C (which raw mode doesn't turn into a signal) exit the program, except that `Esc`
+/// cancels a cancellable step instead.
+fn draw_loop(
+    mut session: TerminalSession,
+    receiver: &Receiver<(String, Option<Arc<AtomicBool>>)>,
+    title: &str,
+    theme: &Theme,
+    capabilities: &ConsoleCapabilities,
+) -> Vec<String> {
+    let started = Instant::now();
+    // Each message and when it arrived
+    let mut steps: Vec<(String, Instant)> = Vec::new();
+    // Cancellation flag of the step in progress
+    let mut cancel: Option<Arc<AtomicBool>> = None;
+    let mut tick = 0usize;
+    loop {
+        loop {
+            match receiver.try_recv() {
+                Ok((text, flag)) => {
+                    steps.push((text, Instant::now()));
+                    cancel = flag;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return steps.into_iter().map(|(text, _)| text).collect(),
+            }
+        }
+
+        let fg = |color| if capabilities.color { Style::default().fg(color) } else { Style::default() };
+        let spinner = if capabilities.unicode { &SPINNER_UNICODE[..] } else { &SPINNER_ASCII[..] };
+        let done = if capabilities.unicode { "✓" } else { "*" };
+        let now = Instant::now();
+        let mut lines: Vec<Line> = steps
+            .iter()
+            .enumerate()
+            .map(|(i, (text, at))| {
+                let (glyph, until) = match steps.get(i + 1) {
+                    Some((_, next)) => (Span::styled(done, fg(theme.muted)), *next),
+                    None => (Span::styled(spinner[tick % spinner.len()], fg(theme.highlight)), now),
+                };
+                Line::from(vec![
+                    glyph,
+                    Span::raw(format!(" {} ", text)),
+                    Span::styled(format!("{:.1}s", (until - *at).as_secs_f64()), fg(theme.muted)),
+                ])
+            })
+            .collect();
+        let cancelling = cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        let keys = match &cancel {
+            Some(_) if cancelling => "cancelling, q to quit",
+            Some(_) => "Esc or c to cancel, q to quit",
+            None => "q to quit",
+        };
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            format!("{:.0}s elapsed, {}", started.elapsed().as_secs_f64(), keys),
+            fg(theme.muted),
+        ));
+
+        let _ = session.terminal.draw(|frame| {
+            let area = centered(frame.size(), 70, lines.len() as u16 + 2);
+            let block = Block::default()
+                .title(format!(" {} ", title))
+                .borders(Borders::ALL)
+                .border_style(fg(theme.border));
+            frame.render_widget(Paragraph::new(lines.clone()).block(block), area);
+        });
+        tick += 1;
+
+        if let Ok(true) = event::poll(TICK) {
+            if let Ok(Event::Key(key)) = event::read() {
+                let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                let cancel_key = !ctrl_c && matches!(key.code, KeyCode::Char('c') | KeyCode::Esc);
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if let (Some(flag), true) = (&cancel, cancel_key) {
+                    flag.store(true, Ordering::Relaxed);
+                } else if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    // The work on the main thread can't be interrupted, so leave outright
+                    drop(session);
+                    std::process::exit(130);
+                }
+            }
+        }
+    }
+}
+
+/// A `width`% wide, `height` rows high area in the middle of `area`.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let height = height.min(area.height);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(area.height.saturating_sub(height) / 2),
+            Constraint::Length(height),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - width) / 2),
+            Constraint::Percentage(width),
+            Constraint::Min(0),
+        ])
+        .split(rows[1])[1]
+}
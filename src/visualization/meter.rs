@@ -0,0 +1,79 @@
+use ratatui::layout::Rect;
+use ratatui::text::Span;
+use ratatui::widgets::{Axis, Chart};
+
+use super::Visualizer;
+use crate::stats::level::{level_track, LevelTrack, LEVEL_STEP};
+
+/// Levels below this are drawn at it, in dB below the loudest step: digital silence would
+/// otherwise stretch the scale to -200 dB.
+const METER_RANGE: f32 = 80.0;
+
+impl Visualizer {
+    /// Weighted level of the first channel, computed on first use.
+    fn level_track(&self) -> std::cell::Ref<'_, LevelTrack> {
+        if self.level_track.borrow().is_none() {
+            let calibration = self.spectrogram_params.calibration;
+            let track = level_track(&self.audio_data.samples, self.audio_data.sample_rate, self.weighting, calibration);
+            *self.level_track.borrow_mut() = Some(track);
+        }
+        std::cell::Ref::map(self.level_track.borrow(), |cached| cached.as_ref().expect("just computed"))
+    }
+
+    /// Sound level meter: the Fast-weighted level over the whole file with the Leq of each
+    /// interval as steps and the playhead, the level at the playhead and the overall Leq in
+    /// the title.
+    pub(super) fn draw_meter(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let track = self.level_track();
+        let unit = track.unit();
+        let loudest = track.levels.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let (top, bottom) = if loudest.is_finite() {
+            let top = (loudest / 10.0).ceil() * 10.0;
+            (top as f64, (top - METER_RANGE) as f64)
+        } else {
+            (0.0, -(METER_RANGE as f64))
+        };
+        let points: Vec<(f64, f64)> = track
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, &level)| ((i + 1) as f64 * LEVEL_STEP, (level as f64).max(bottom)))
+            .collect();
+        let duration = points.last().map_or(LEVEL_STEP, |p| p.0);
+        // Leq of each interval as steps across it
+        let interval = self.leq_interval;
+        let leq_steps: Vec<(f64, f64)> = track
+            .leq_series(interval)
+            .into_iter()
+            .flat_map(|(start, level)| {
+                let level = (level as f64).max(bottom);
+                [(start, level), ((start + interval).min(duration), level)]
+            })
+            .collect();
+        let playhead_line = [(playhead, bottom), (playhead, top)];
+        let datasets = vec![
+            self.line_dataset(&points, self.theme.waveform),
+            self.line_dataset(&leq_steps, self.theme.highlight),
+            self.line_dataset(&playhead_line, self.theme.playhead),
+        ];
+
+        let mut title = format!("Level {}", track.weighting.letter());
+        if let Some(level) = track.level_at(playhead) {
+            title = format!("{} {:.1} {}", title, level, unit);
+        }
+        if let Some(leq) = track.leq {
+            title = format!("{} ({} {:.1})", title, track.quantity("eq"), leq);
+        }
+        let chart = Chart::new(datasets)
+            .block(self.pane(title))
+            .x_axis(Axis::default().bounds([0.0, duration]).labels(vec![
+                Span::raw("0s"),
+                Span::raw(format!("{:.0}s", duration)),
+            ]))
+            .y_axis(Axis::default().bounds([bottom, top]).labels(vec![
+                Span::raw(format!("{:.0}", bottom)),
+                Span::raw(format!("{:.0}", top)),
+            ]));
+        frame.render_widget(chart, area);
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+
+use super::Visualizer;
+use crate::audio::pyramid::{EnvelopePyramid, SpectrogramPyramid};
+use crate::audio::{SpectrogramData, SpectrogramParams};
+
+/// `--mid-side` display state: which signal is shown and the other one's spectrogram. The
+/// samples themselves swap places between the first two channels.
+pub(super) struct MidSide {
+    pub(super) showing_side: bool,
+    spectrogram: SpectrogramData,
+    params: SpectrogramParams,
+}
+
+impl MidSide {
+    pub(super) fn new(side: SpectrogramData, params: SpectrogramParams) -> Self {
+        Self { showing_side: false, spectrogram: side, params }
+    }
+}
+
+impl Visualizer {
+    /// " (mid)" or " (side)" for pane titles in mid/side mode, empty otherwise.
+    pub(super) fn signal_label(&self) -> &'static str {
+        match &self.mid_side {
+            Some(mid_side) if mid_side.showing_side => " (side)",
+            Some(_) => " (mid)",
+            None => "",
+        }
+    }
+
+    /// Switches the waveform, spectrogram and analysis views between mid and side.
+    pub(super) fn toggle_mid_side(&mut self) -> Result<()> {
+        let Some(mid_side) = &mut self.mid_side else {
+            self.status = Some("Mid/side view needs --mid-side".to_string());
+            return Ok(());
+        };
+        let Some(other) = self.audio_data.extra_channels.first_mut() else {
+            return Ok(());
+        };
+        std::mem::swap(&mut self.audio_data.samples, other);
+        std::mem::swap(&mut self.spectrogram, &mut mid_side.spectrogram);
+        mid_side.showing_side = !mid_side.showing_side;
+        self.envelope = EnvelopePyramid::new(&self.audio_data.samples);
+        self.spectrogram_pyramid = SpectrogramPyramid::new(&self.spectrogram.magnitudes);
+        self.auto_levels.set(None);
+        // The average spectrum, PSD, pitch track, level track and any bitmap belong to the other signal
+        *self.average_spectrum.borrow_mut() = None;
+        *self.psd.borrow_mut() = None;
+        *self.tuner.borrow_mut() = None;
+        *self.level_track.borrow_mut() = None;
+        // A playground change still computing is of the signal switched away from: redo it
+        let pending = self.recompute.take().map(|recompute| recompute.params);
+        self.image_key = None;
+        // The playground may have changed the parameters since this one was computed
+        let params = std::mem::replace(&mut mid_side.params, self.spectrogram_params);
+        if params != self.spectrogram_params {
+            self.recompute_spectrogram(self.spectrogram_params)?;
+        }
+        if let Some(params) = pending {
+            self.start_recompute(params)?;
+        }
+        Ok(())
+    }
+}
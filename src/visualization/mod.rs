@@ -1,22 +1,184 @@
-use anyhow::Result;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-use ratatui::backend::CrosstermBackend;
+use anyhow::{anyhow, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::backend::Backend;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::symbols;
-use ratatui::widgets::{Block, Borders, Dataset, GraphType, Chart, Paragraph, Wrap};
-use ratatui::text::Span;
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, Borders, Dataset, GraphType, Chart, Paragraph, Sparkline, Wrap};
+use ratatui::text::{Line, Span};
 use ratatui::Terminal;
-use std::io::stdout;
-use std::time::Duration;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-use crate::audio::{AudioData, SpectrogramData};
+use crate::audio::playback::Player;
+use crate::export::wav::write_wav;
+use crate::export::{save_markers, save_transcript};
+use crate::presets::Preset;
+use crate::audio::pyramid::{EnvelopePyramid, SpectrogramPyramid};
+use crate::audio::weighting::Weighting;
+use crate::audio::{AudioData, SpectrogramData, SpectrogramParams};
+use crate::session::autosave::Autosave;
+use crate::config::toml::Table;
+use crate::session::{save_session, Marker, SessionState};
+use crate::speech::chapters::Chapter;
+use crate::stats::engine::RpmTrack;
+use crate::stats::level::LevelTrack;
+use crate::stats::speech::SpeechStats;
 use crate::speech::TranscriptionSegment;
+use crate::stats::AudioStats;
+
+pub mod acoustics;
+pub mod captions;
+pub mod console;
+pub mod dump;
+mod cursor;
+mod doppler;
+mod editor;
+mod explain;
+mod guides;
+pub mod levels;
+mod meter;
+mod mid_side;
+pub mod graphics;
+pub mod keymap;
+pub mod loading;
+mod playground;
+mod psd;
+pub mod recording;
+mod report;
+mod rta;
+mod scope;
+mod spectral_edit;
+mod spectrum;
+mod stereo;
+pub mod theme;
+mod tracks;
+mod tuner;
+mod video;
+
+use graphics::{GraphicsProtocol, ImageKey};
+use console::{ConsoleCapabilities, DisplayMode, SpectrogramRender, TerminalSession, MIN_FULL_SIZE, SLOW_FRAME_MS};
+use keymap::{Action, Keymap};
+use levels::Contrast;
+use mid_side::MidSide;
+use tracks::Track;
+use playground::Recompute;
+use psd::PsdCache;
+use rta::PeakHold;
+use spectral_edit::SpectralEdit;
+use spectrum::SpectrumCache;
+use theme::{Colormap, Theme};
+use tuner::TunerCache;
+use editor::{History, InputOutcome, LineInput, TranscriptEditor, MIN_SEGMENT, NUDGE_STEP};
 
 pub struct Visualizer {
     audio_data: AudioData,
     spectrogram: SpectrogramData,
     transcription: Vec<TranscriptionSegment>,
+    stats: AudioStats,
+    show_stats: bool,
+    speech_stats: SpeechStats,
+    show_speech: bool,
+    player: Player,
+    status: Option<String>,
+    capabilities: ConsoleCapabilities,
+    selection_anchor: Option<f64>,
+    selection: Option<(f64, f64)>,
+    autosave: Option<Autosave>,
+    editor: Option<TranscriptEditor>,
+    markers: Vec<Marker>,
+    /// Name being typed for a new marker at the given time
+    marker_input: Option<(f64, LineInput)>,
+    markers_path: Option<PathBuf>,
+    /// Band being typed for a spectral edit of the given time range
+    box_input: Option<((f64, f64), LineInput)>,
+    /// Frequency being typed for a line across the spectrogram
+    frequency_input: Option<LineInput>,
+    /// Horizontal lines across the spectrogram, in Hz
+    frequency_markers: Vec<f64>,
+    /// Equal-tempered note lines (A4 = 440 Hz) across the spectrogram
+    note_grid: bool,
+    spectral_edits: Vec<SpectralEdit>,
+    /// Where `w` writes the audio once it has spectral edits
+    audio_path: Option<PathBuf>,
+    history: History,
+    transcript_path: Option<PathBuf>,
+    unsaved_edits: bool,
+    quit_armed: bool,
+    info: Option<String>,
+    display_mode: DisplayMode,
+    render: SpectrogramRender,
+    graphics: Option<GraphicsProtocol>,
+    /// Where the last draw left room for the spectrogram bitmap
+    image_area: Cell<Option<Rect>>,
+    image_key: Option<ImageKey>,
+    /// Moving average of frame draw time in milliseconds
+    frame_ms: f64,
+    /// Set once frames have been slow, so the view doesn't flip back and forth
+    slow_terminal: bool,
+    /// Highest frequency shown in the spectrogram
+    max_frequency: Option<f32>,
+    /// How many times slower than recorded playback runs (time expansion for ultrasound)
+    time_expansion: f64,
+    /// Whether the spectrogram scrolls with playback instead of showing the whole file
+    scrolling: bool,
+    /// Seconds of audio shown while scrolling
+    scroll_span: f64,
+    /// Content type the analysis preset was chosen for, shown in the header
+    preset_label: Option<String>,
+    show_explain: bool,
+    /// `--session` file and the analysis parameters written with the state
+    session_file: Option<(PathBuf, Table)>,
+    theme: Theme,
+    keymap: Keymap,
+    /// Parameters the spectrogram was computed with, changed live in the playground
+    spectrogram_params: SpectrogramParams,
+    colormap: Colormap,
+    /// Selected row while the parameter playground is open
+    playground: Option<usize>,
+    /// Playground change still being computed
+    recompute: Option<Recompute>,
+    /// Spectrogram levels shown below full scale, in dB
+    display_range: f32,
+    /// How far below full scale the colormap tops out, in dB
+    display_headroom: f32,
+    contrast: Contrast,
+    /// Floor and top of auto contrast, until the spectrogram changes
+    auto_levels: Cell<Option<(f32, f32)>>,
+    /// `--summarize` result, shown in its own pane
+    summary: Option<String>,
+    chapters: Vec<Chapter>,
+    /// Engine firing frequency drawn over the spectrogram, with the RPM in its title
+    rpm: Option<RpmTrack>,
+    /// Doppler curve last fitted with `D`, as (seconds, Hz)
+    doppler_curve: Vec<(f64, f64)>,
+    /// Crosshair while the cursor readout is open
+    cursor: Option<cursor::Cursor>,
+    show_spectrum: bool,
+    average_spectrum: RefCell<Option<SpectrumCache>>,
+    show_psd: bool,
+    psd: RefCell<Option<PsdCache>>,
+    show_tuner: bool,
+    tuner: RefCell<Option<TunerCache>>,
+    show_meter: bool,
+    /// Frequency weighting of the level meter
+    weighting: Weighting,
+    level_track: RefCell<Option<LevelTrack>>,
+    /// Interval of the Leq steps drawn in the level meter, in seconds
+    leq_interval: f64,
+    show_rta: bool,
+    show_scope: bool,
+    show_stereo: bool,
+    /// `--mid-side`: the signal not shown
+    mid_side: Option<MidSide>,
+    /// Display summaries of the samples and spectrogram, rebuilt when either changes
+    envelope: EnvelopePyramid,
+    spectrogram_pyramid: SpectrogramPyramid,
+    peak_hold: RefCell<PeakHold>,
+    /// Input files of a multi-track session, whose mix `audio_data` holds
+    tracks: Vec<Track>,
 }
 
 impl Visualizer {
@@ -24,23 +186,754 @@ impl Visualizer {
         audio_data: AudioData,
         spectrogram: SpectrogramData,
         transcription: Vec<TranscriptionSegment>,
+        stats: AudioStats,
     ) -> Self {
+        let player = Player::new(&audio_data.samples, audio_data.sample_rate);
+        let envelope = EnvelopePyramid::new(&audio_data.samples);
+        let spectrogram_pyramid = SpectrogramPyramid::new(&spectrogram.magnitudes);
+        let spectrogram_params = SpectrogramParams::new(spectrogram.frequencies.len() * 2);
         Self {
             audio_data,
             spectrogram,
             transcription,
+            stats,
+            show_stats: false,
+            speech_stats: SpeechStats::default(),
+            show_speech: false,
+            player,
+            status: None,
+            capabilities: ConsoleCapabilities::detect(),
+            selection_anchor: None,
+            selection: None,
+            autosave: None,
+            editor: None,
+            markers: Vec::new(),
+            marker_input: None,
+            markers_path: None,
+            box_input: None,
+            frequency_input: None,
+            frequency_markers: Vec::new(),
+            note_grid: false,
+            spectral_edits: Vec::new(),
+            audio_path: None,
+            history: History::default(),
+            transcript_path: None,
+            unsaved_edits: false,
+            quit_armed: false,
+            info: None,
+            display_mode: DisplayMode::Auto,
+            render: SpectrogramRender::default(),
+            graphics: None,
+            image_area: Cell::new(None),
+            image_key: None,
+            frame_ms: 0.0,
+            slow_terminal: false,
+            max_frequency: None,
+            time_expansion: 1.0,
+            scrolling: false,
+            scroll_span: SCROLL_SPAN,
+            preset_label: None,
+            show_explain: false,
+            session_file: None,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            spectrogram_params,
+            colormap: Colormap::default(),
+            playground: None,
+            recompute: None,
+            display_range: DISPLAY_RANGE,
+            display_headroom: 0.0,
+            contrast: Contrast::default(),
+            auto_levels: Cell::new(None),
+            summary: None,
+            chapters: Vec::new(),
+            rpm: None,
+            doppler_curve: Vec::new(),
+            cursor: None,
+            show_spectrum: false,
+            average_spectrum: RefCell::new(None),
+            show_psd: false,
+            psd: RefCell::new(None),
+            show_tuner: false,
+            tuner: RefCell::new(None),
+            show_meter: false,
+            weighting: Weighting::default(),
+            level_track: RefCell::new(None),
+            leq_interval: 1.0,
+            show_rta: false,
+            show_scope: false,
+            show_stereo: false,
+            mid_side: None,
+            envelope,
+            spectrogram_pyramid,
+            peak_hold: RefCell::new(Vec::new()),
+            tracks: Vec::new(),
+        }
+    }
+
+    pub fn with_capabilities(mut self, capabilities: ConsoleCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// The parameters `spectrogram` was computed with, as a starting point for the playground.
+    pub fn with_spectrogram_params(mut self, params: SpectrogramParams) -> Self {
+        self.spectrogram_params = params;
+        self
+    }
+
+    /// Mid/side mode: `audio_data` holds mid and side as its first two channels, `spectrogram`
+    /// is of the mid and `side` of the side, both computed with `params`.
+    pub fn with_mid_side(mut self, side: SpectrogramData, params: SpectrogramParams) -> Self {
+        self.mid_side = Some(MidSide::new(side, params));
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    pub fn with_display_mode(mut self, mode: DisplayMode) -> Self {
+        self.display_mode = mode;
+        self
+    }
+
+    pub fn with_render(mut self, render: SpectrogramRender) -> Self {
+        self.render = render;
+        self
+    }
+
+    /// Draws the spectrogram as an inline bitmap with `protocol` (outside the simple view).
+    pub fn with_graphics(mut self, protocol: Option<GraphicsProtocol>) -> Self {
+        self.graphics = protocol;
+        self
+    }
+
+    /// Starts with the spectrogram scrolling, showing the last `span` seconds up to the
+    /// playhead; `None` starts with the whole file, still scrolling the default span on `a`.
+    pub fn with_scroll(mut self, span: Option<f64>) -> Self {
+        self.scrolling = span.is_some();
+        self.scroll_span = span.unwrap_or(SCROLL_SPAN);
+        self
+    }
+
+    /// Multi-track session: `audio_data` is the mix of `tracks` (the name and first channel of
+    /// each input, at its sample rate), which are drawn as stacked waveforms and can be muted.
+    pub fn with_tracks(mut self, tracks: Vec<(String, Vec<f32>)>) -> Self {
+        self.tracks = tracks.into_iter().map(|(name, samples)| Track::new(name, samples)).collect();
+        self
+    }
+
+    /// Plays to `device` instead of the system's default output.
+    pub fn with_output_device(mut self, device: Option<String>) -> Self {
+        self.player = self.new_player().with_device(device);
+        self
+    }
+
+    /// Plays `factor` times slower than recorded, bringing ultrasound down into hearing.
+    pub fn with_time_expansion(mut self, factor: f64) -> Self {
+        self.time_expansion = factor;
+        let device = self.player.device().map(str::to_string);
+        self.player = self.new_player().with_device(device);
+        self
+    }
+
+    pub fn with_summary(mut self, summary: String) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Speaking rate, pauses and fillers for the pane toggled with `r`.
+    pub fn with_speech_stats(mut self, speech_stats: SpeechStats) -> Self {
+        self.speech_stats = speech_stats;
+        self
+    }
+
+    /// Chapters to draw on the waveform, with the current one named in its title.
+    pub fn with_chapters(mut self, chapters: Vec<Chapter>) -> Self {
+        self.chapters = chapters;
+        self
+    }
+
+    /// An engine's firing frequency to draw over the spectrogram.
+    pub fn with_rpm(mut self, track: RpmTrack) -> Self {
+        self.rpm = Some(track);
+        self
+    }
+
+    pub fn with_preset(mut self, preset: &Preset) -> Self {
+        self.max_frequency = Some(preset.max_frequency);
+        self.preset_label = Some(preset.content.to_string());
+        self
+    }
+
+    /// Shows the spectrogram up to `max_frequency` (Hz), overriding the preset's range.
+    pub fn with_max_frequency(mut self, max_frequency: f32) -> Self {
+        self.max_frequency = Some(max_frequency);
+        self.auto_levels.set(None);
+        self
+    }
+
+    /// Frequency weighting of the level meter.
+    pub fn with_weighting(mut self, weighting: Weighting) -> Self {
+        self.weighting = weighting;
+        self
+    }
+
+    /// Interval the level meter draws the Leq over, in seconds.
+    pub fn with_leq_interval(mut self, interval: f64) -> Self {
+        self.leq_interval = interval;
+        self
+    }
+
+    /// How spectrogram levels map onto the colormap: fitted to their distribution, or a
+    /// fixed range below full scale.
+    pub fn with_contrast(mut self, contrast: Contrast) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    /// Whether to draw the simplified view on a terminal of this size.
+    fn simple_rendering(&self, size: Rect) -> bool {
+        match self.display_mode {
+            DisplayMode::Full => false,
+            DisplayMode::Simple => true,
+            DisplayMode::Auto => {
+                self.capabilities.limited()
+                    || size.width < MIN_FULL_SIZE.0
+                    || size.height < MIN_FULL_SIZE.1
+                    || self.slow_terminal
+            }
+        }
+    }
+
+    /// Where `w` saves the (edited) transcript: JSON for `.json`, SRT otherwise.
+    pub fn with_transcript_path(mut self, path: PathBuf) -> Self {
+        self.transcript_path = Some(path);
+        self
+    }
+
+    /// Where `w` saves markers: JSON for `.json`, an Audacity label track otherwise.
+    pub fn with_markers_path(mut self, path: PathBuf) -> Self {
+        self.markers_path = Some(path);
+        self
+    }
+
+    /// Where `w` writes the edited audio as a WAV after spectral edits.
+    pub fn with_audio_path(mut self, path: PathBuf) -> Self {
+        self.audio_path = Some(path);
+        self
+    }
+
+    /// Saves the session to `path` on `w` and on quit, along with `params`.
+    pub fn with_session_file(mut self, path: PathBuf, params: Table) -> Self {
+        self.session_file = Some((path, params));
+        self
+    }
+
+    pub fn with_autosave(mut self, autosave: Autosave) -> Self {
+        self.autosave = Some(autosave);
+        self
+    }
+
+    pub fn session_state(&self) -> SessionState {
+        SessionState {
+            position: self.player.position(),
+            speed: self.player.speed(),
+            selection: self.selection,
+            loop_region: self.player.loop_region(),
+            show_stats: self.show_stats,
+            transcription: self.transcription.clone(),
+            markers: self.markers.clone(),
+            frequency_markers: self.frequency_markers.clone(),
+            note_grid: self.note_grid,
+            muted_tracks: self.muted_tracks(),
         }
     }
 
-    pub fn run(&self) -> Result<()> {
-        enable_raw_mode()?;
-        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-        terminal.clear()?;
+    pub fn restore_session(&mut self, state: &SessionState) -> Result<()> {
+        self.player.pause();
+        if self.muted_tracks() != state.muted_tracks {
+            self.set_muted(&state.muted_tracks);
+            self.reload_player()?;
+        }
+        self.player.set_speed(state.speed)?;
+        self.player.set_loop(state.loop_region)?;
+        self.player.seek(state.position)?;
+        self.selection = state.selection;
+        self.selection_anchor = None;
+        self.show_stats = state.show_stats;
+        self.transcription = state.transcription.clone();
+        self.markers = state.markers.clone();
+        self.frequency_markers = state.frequency_markers.clone();
+        self.note_grid = state.note_grid;
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        let mut session = TerminalSession::start()?;
+        let terminal = &mut session.terminal;
+
+        let baseline = self.session_state();
+        if let Some(autosave) = &mut self.autosave {
+            autosave.set_baseline(baseline);
+        }
+
+        let mut redraw = true;
+        loop {
+            if self.autosave.as_ref().is_some_and(Autosave::due) {
+                let state = self.session_state();
+                if let Some(Err(e)) = self.autosave.as_mut().map(|autosave| autosave.save(&state)) {
+                    self.status = Some(e.to_string());
+                    redraw = true;
+                }
+            }
+
+            redraw |= self.poll_recompute();
+            let playing = self.player.is_playing();
+            if redraw || playing {
+                let started = Instant::now();
+                self.draw(terminal)?;
+                let had_image = self.image_key.is_some();
+                self.draw_graphics(terminal.backend_mut(), self.player.position())?;
+                if had_image && self.image_key.is_none() && self.graphics == Some(GraphicsProtocol::Sixel) {
+                    // Sixel pixels stay until the cells under them are rewritten
+                    terminal.clear()?;
+                    self.draw(terminal)?;
+                }
+                let elapsed = started.elapsed().as_secs_f64() * 1000.0;
+                self.frame_ms = 0.8 * self.frame_ms + 0.2 * elapsed;
+                self.slow_terminal |= self.frame_ms > SLOW_FRAME_MS;
+                redraw = false;
+            }
+
+            let simple = self.simple_rendering(terminal.size()?);
+            let timeout = match (playing || self.recompute.is_some(), simple) {
+                (true, false) => 50,
+                (true, true) => 200,
+                (false, _) => 250,
+            };
+            if !event::poll(Duration::from_millis(timeout))? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    redraw = true;
+                    if self.handle_key(key) {
+                        break;
+                    }
+                }
+                Event::Resize(_, _) => {
+                    // The resize clears the screen, images included
+                    self.invalidate_graphics();
+                    redraw = true;
+                }
+                _ => {}
+            }
+        }
 
+        self.player.pause();
+        if let Some((path, params)) = &self.session_file {
+            save_session(path, params, &self.session_state())?;
+        }
+        if let Some(autosave) = &self.autosave {
+            autosave.discard();
+        }
+        Ok(())
+    }
+
+    /// Handles a key press; returns true when the viewer should quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.info = None;
+        let quit_armed = std::mem::take(&mut self.quit_armed);
+        let editing_text = self.marker_input.is_some()
+            || self.box_input.is_some()
+            || self.frequency_input.is_some()
+            || self.editor.as_ref().is_some_and(|editor| editor.input.is_some());
+        let action = self.keymap.action(key);
+        if !editing_text {
+            match action {
+                Some(Action::Undo) => {
+                    self.undo();
+                    return false;
+                }
+                Some(Action::Redo) => {
+                    self.redo();
+                    return false;
+                }
+                _ if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+                _ => {}
+            }
+        }
+        if let Some((time, input)) = &mut self.marker_input {
+            match input.handle(key) {
+                InputOutcome::Editing => {}
+                InputOutcome::Cancel => self.marker_input = None,
+                InputOutcome::Commit(name) => {
+                    let time = *time;
+                    self.marker_input = None;
+                    self.add_marker(time, name.trim());
+                }
+            }
+            return false;
+        }
+        if self.box_input.is_some() {
+            self.handle_box_key(key);
+            return false;
+        }
+        if self.frequency_input.is_some() {
+            self.handle_frequency_key(key);
+            return false;
+        }
+        if self.editor.is_some() {
+            self.handle_edit_key(key);
+            return false;
+        }
+        if self.playground.is_some() {
+            self.handle_playground_key(key);
+            return false;
+        }
+        if self.cursor.is_some() {
+            self.handle_cursor_key(key);
+            return false;
+        }
+        let Some(action) = action else {
+            return false;
+        };
+        match action {
+            Action::Quit => {
+                if self.unsaved_edits && !quit_armed {
+                    self.quit_armed = true;
+                    self.status = Some("Unsaved changes: save first, or quit again to discard".to_string());
+                    return false;
+                }
+                return true;
+            }
+            Action::ToggleStats => self.show_stats = !self.show_stats,
+            Action::ToggleSpeech => self.show_speech = !self.show_speech,
+            Action::ToggleSpectrum => self.show_spectrum = !self.show_spectrum,
+            Action::TogglePsd => self.show_psd = !self.show_psd,
+            Action::ToggleTuner => self.show_tuner = !self.show_tuner,
+            Action::ToggleMeter => self.show_meter = !self.show_meter,
+            Action::ToggleRta => self.show_rta = !self.show_rta,
+            Action::ToggleScope => self.show_scope = !self.show_scope,
+            Action::ToggleScroll => self.scrolling = !self.scrolling,
+            Action::MuteTrack(index) => {
+                let result = self.toggle_mute(index);
+                self.report(result);
+            }
+            Action::ToggleStereo => self.show_stereo = !self.show_stereo,
+            Action::MidSide => {
+                let result = self.toggle_mid_side();
+                self.report(result);
+            }
+            Action::Explain => self.show_explain = !self.show_explain,
+            Action::Playground => self.playground = Some(0),
+            Action::Edit => self.enter_edit_mode(),
+            Action::Save => {
+                let result = self.save();
+                self.report(result);
+            }
+            Action::AddMarker => {
+                self.marker_input = Some((self.player.position(), LineInput::new("")));
+            }
+            Action::RemoveMarker => self.remove_nearest_marker(),
+            Action::NextMarker => {
+                let result = self.jump_to_marker(true);
+                self.report(result);
+            }
+            Action::PreviousMarker => {
+                let result = self.jump_to_marker(false);
+                self.report(result);
+            }
+            Action::TogglePlay => {
+                let result = self.player.toggle();
+                self.report(result);
+            }
+            Action::SpeedUp => {
+                let result = self.player.set_speed(self.player.speed() + 0.25);
+                self.report(result);
+            }
+            Action::SpeedDown => {
+                let result = self.player.set_speed(self.player.speed() - 0.25);
+                self.report(result);
+            }
+            Action::SeekBack => {
+                let result = self.player.seek(self.player.position() - 5.0);
+                self.report(result);
+            }
+            Action::SeekForward => {
+                let result = self.player.seek(self.player.position() + 5.0);
+                self.report(result);
+            }
+            Action::Select => {
+                let result = self.mark_selection();
+                self.report(result);
+            }
+            Action::Loop => {
+                let result = self.toggle_loop();
+                self.report(result);
+            }
+            Action::SpectralEdit => {
+                let result = self.start_spectral_edit();
+                self.report(result);
+            }
+            Action::Doppler => {
+                let result = self.measure_doppler();
+                self.report(result);
+            }
+            Action::Cursor => self.start_cursor(),
+            Action::FrequencyLine => self.start_frequency_input(),
+            Action::FloorDown => self.adjust_floor(-1.0),
+            Action::FloorUp => self.adjust_floor(1.0),
+            Action::CeilingDown => self.adjust_ceiling(-1.0),
+            Action::CeilingUp => self.adjust_ceiling(1.0),
+            Action::AutoLevel => self.auto_level(),
+            Action::NoteGrid => {
+                self.note_grid = !self.note_grid;
+                self.unsaved_edits = true;
+            }
+            Action::Undo | Action::Redo => {}
+        }
+        false
+    }
+
+    /// Starts editing at the segment under the playhead, or the first one.
+    fn enter_edit_mode(&mut self) {
+        if self.transcription.is_empty() {
+            self.status = Some("No transcript segments to edit".to_string());
+            return;
+        }
+        let position = self.player.position();
+        let selected = self
+            .transcription
+            .iter()
+            .position(|seg| position < seg.end)
+            .unwrap_or(self.transcription.len() - 1);
+        self.editor = Some(TranscriptEditor::new(selected));
+    }
+
+    fn handle_edit_key(&mut self, key: KeyEvent) {
+        let Some(editor) = &mut self.editor else {
+            return;
+        };
+        let selected = editor.selected;
+        if let Some(input) = &mut editor.input {
+            match input.handle(key) {
+                InputOutcome::Editing => {}
+                InputOutcome::Cancel => editor.input = None,
+                InputOutcome::Commit(text) => {
+                    editor.input = None;
+                    let mut segment = self.transcription[selected].clone();
+                    segment.text = text.trim().to_string();
+                    self.replace_segment(selected, segment);
+                }
+            }
+            return;
+        }
+        let last = self.transcription.len() - 1;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('e') => self.editor = None,
+            KeyCode::Up | KeyCode::Char('k') => editor.selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => editor.selected = (selected + 1).min(last),
+            KeyCode::Enter => {
+                editor.input = Some(LineInput::new(self.transcription[selected].text.trim()));
+            }
+            KeyCode::Char('[') => self.nudge_segment(selected, -NUDGE_STEP, 0.0),
+            KeyCode::Char(']') => self.nudge_segment(selected, NUDGE_STEP, 0.0),
+            KeyCode::Char('{') => self.nudge_segment(selected, 0.0, -NUDGE_STEP),
+            KeyCode::Char('}') => self.nudge_segment(selected, 0.0, NUDGE_STEP),
+            KeyCode::Char('g') => {
+                let result = self.player.seek(self.transcription[selected].start);
+                self.report(result);
+            }
+            KeyCode::Char(' ') => {
+                let result = self.player.toggle();
+                self.report(result);
+            }
+            KeyCode::Char('w') => {
+                let result = self.save();
+                self.report(result);
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves a segment's start and/or end, keeping it inside the audio and at least
+    /// `MIN_SEGMENT` long.
+    fn nudge_segment(&mut self, index: usize, start_delta: f64, end_delta: f64) {
+        let mut segment = self.transcription[index].clone();
+        let duration = self.stats.duration;
+        let shortest_end = segment.start + MIN_SEGMENT;
+        segment.end = (segment.end + end_delta).clamp(shortest_end, duration.max(shortest_end));
+        segment.start = (segment.start + start_delta).clamp(0.0, (segment.end - MIN_SEGMENT).max(0.0));
+        self.replace_segment(index, segment);
+    }
+
+    fn replace_segment(&mut self, index: usize, segment: TranscriptionSegment) {
+        if self.transcription[index] != segment {
+            let before = std::mem::replace(&mut self.transcription[index], segment.clone());
+            self.history.record(index, before, segment);
+            self.unsaved_edits = true;
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.history.undo(&mut self.transcription) {
+            Some(index) => self.after_history_step(index, "Undid"),
+            None => self.status = Some("Nothing to undo".to_string()),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.history.redo(&mut self.transcription) {
+            Some(index) => self.after_history_step(index, "Redid"),
+            None => self.status = Some("Nothing to redo".to_string()),
+        }
+    }
+
+    /// Selects the segment an undo/redo touched so the change is visible.
+    fn after_history_step(&mut self, index: usize, verb: &str) {
+        self.unsaved_edits = true;
+        self.status = None;
+        if let Some(editor) = &mut self.editor {
+            editor.selected = index;
+        }
+        self.info = Some(format!("{} edit to segment {}", verb, index + 1));
+    }
+
+    /// Saves the transcript, plus the markers and spectrally edited audio when there are any.
+    fn save(&mut self) -> Result<()> {
+        let path = self
+            .transcript_path
+            .clone()
+            .ok_or_else(|| anyhow!("No transcript output path (use --transcript-out)"))?;
+        save_transcript(&path, &self.transcription)?;
+        let mut saved = path.display().to_string();
+        // Written even without markers, so deleting the last one doesn't leave stale labels
+        if let Some(markers_path) = &self.markers_path {
+            save_markers(markers_path, &self.markers)?;
+            saved = format!("{} and {}", saved, markers_path.display());
+        }
+        if let (Some(audio_path), false) = (&self.audio_path, self.spectral_edits.is_empty()) {
+            let audio = &self.audio_data;
+            let channels: Vec<&[f32]> = (0..audio.channels.max(1)).filter_map(|c| audio.channel(c)).collect();
+            write_wav(audio_path, &channels, audio.sample_rate)?;
+            saved = format!("{} and {}", saved, audio_path.display());
+        }
+        if let Some((session_path, params)) = &self.session_file {
+            save_session(session_path, params, &self.session_state())?;
+            saved = format!("{} and {}", saved, session_path.display());
+        }
+        self.unsaved_edits = false;
+        self.info = Some(format!("Saved {}", saved));
+        Ok(())
+    }
+
+    fn add_marker(&mut self, time: f64, name: &str) {
+        let name = if name.is_empty() {
+            format!("Marker {}", self.markers.len() + 1)
+        } else {
+            name.to_string()
+        };
+        let index = self.markers.partition_point(|marker| marker.time <= time);
+        self.markers.insert(index, Marker { time, name });
+        self.unsaved_edits = true;
+    }
+
+    fn remove_nearest_marker(&mut self) {
+        let position = self.player.position();
+        let nearest = self
+            .markers
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (a.time - position).abs().total_cmp(&(b.time - position).abs()))
+            .map(|(i, _)| i);
+        match nearest {
+            Some(index) => {
+                let marker = self.markers.remove(index);
+                self.info = Some(format!("Removed marker {}", marker.name));
+                self.unsaved_edits = true;
+            }
+            None => self.status = Some("No markers".to_string()),
+        }
+    }
+
+    /// Seeks to the next (or previous) marker relative to the playhead.
+    fn jump_to_marker(&mut self, forward: bool) -> Result<()> {
+        // A small margin so repeated presses move past a marker the playhead sits on
+        let position = self.player.position();
+        let target = if forward {
+            self.markers.iter().find(|marker| marker.time > position + 0.01)
+        } else {
+            self.markers.iter().rev().find(|marker| marker.time < position - 0.25)
+        };
+        let time = target.map(|marker| marker.time).ok_or_else(|| anyhow!("No more markers"))?;
+        self.player.seek(time)
+    }
+
+    /// First press anchors the selection at the playhead, the second closes it, the third
+    /// clears it (and any loop over it).
+    fn mark_selection(&mut self) -> Result<()> {
+        let position = self.player.position();
+        match (self.selection_anchor.take(), self.selection) {
+            (Some(anchor), _) => {
+                self.selection = Some((anchor.min(position), anchor.max(position)));
+                Ok(())
+            }
+            (None, Some(_)) => {
+                self.selection = None;
+                self.player.set_loop(None)
+            }
+            (None, None) => {
+                self.selection_anchor = Some(position);
+                Ok(())
+            }
+        }
+    }
+
+    fn toggle_loop(&mut self) -> Result<()> {
+        if self.player.loop_region().is_some() {
+            self.player.set_loop(None)
+        } else {
+            let region = self.selection.ok_or_else(|| anyhow!("Select a region with 'v' first"))?;
+            self.player.set_loop(Some(region))?;
+            self.player.play()
+        }
+    }
+
+    /// Rebuilds playback after the samples it plays change, keeping the position, speed and
+    /// loop. Playback stays paused.
+    fn reload_player(&mut self) -> Result<()> {
+        let (position, speed, region) = (self.player.position(), self.player.speed(), self.player.loop_region());
+        self.player.pause();
+        let device = self.player.device().map(str::to_string);
+        self.player = self.new_player().with_device(device);
+        self.player.set_speed(speed)?;
+        self.player.set_loop(region)?;
+        self.player.seek(position)
+    }
+
+    fn report(&mut self, result: Result<()>) {
+        self.status = result.err().map(|e| e.to_string());
+    }
+
+    /// Renders one frame onto any ratatui backend (e.g. `TestBackend` in CI).
+    pub fn draw<B: Backend>(&self, terminal: &mut Terminal<B>) -> Result<()> {
+        let playhead = self.player.position();
+        self.image_area.set(None);
         terminal.draw(|frame| {
+            let simple = self.simple_rendering(frame.size());
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
+                    Constraint::Length(1),
                     Constraint::Percentage(30),
                     Constraint::Percentage(35),
                     Constraint::Percentage(35),
@@ -48,59 +941,403 @@ impl Visualizer {
                 .margin(1)
                 .split(frame.size());
 
-            self.draw_transcription(frame, chunks[0]);
-            self.draw_waveform(frame, chunks[1]);
-            self.draw_spectrogram(frame, chunks[2]);
-        })?;
+            self.draw_header(frame, chunks[0], playhead, simple);
+            let chunks = &chunks[1..];
 
-        // Wait briefly to show the visualization
-        std::thread::sleep(Duration::from_secs(5));
-        
-        disable_raw_mode()?;
-        terminal.clear()?;
+            // Side panes share the top row with the transcript
+            #[derive(Clone, Copy)]
+            enum Side {
+                Markers,
+                Summary,
+                Stats,
+                Speech,
+                Stereo,
+                Spectrum,
+                Psd,
+                Tuner,
+                Meter,
+                Scope,
+                Playground,
+            }
+            let mut sides = Vec::new();
+            if !self.markers.is_empty() {
+                sides.push((Side::Markers, 20));
+            }
+            if self.summary.is_some() {
+                sides.push((Side::Summary, 30));
+            }
+            if self.show_stats {
+                sides.push((Side::Stats, 30));
+            }
+            if self.show_speech {
+                sides.push((Side::Speech, 30));
+            }
+            if self.show_stereo {
+                sides.push((Side::Stereo, 30));
+            }
+            if self.show_spectrum {
+                sides.push((Side::Spectrum, 40));
+            }
+            if self.show_psd {
+                sides.push((Side::Psd, 40));
+            }
+            if self.show_tuner {
+                sides.push((Side::Tuner, 30));
+            }
+            if self.show_meter {
+                sides.push((Side::Meter, 30));
+            }
+            if self.show_scope {
+                sides.push((Side::Scope, 30));
+            }
+            if self.playground.is_some() {
+                sides.push((Side::Playground, 30));
+            }
+            let side_width: u16 = sides.iter().map(|&(_, w)| w).sum();
+            let mut constraints = vec![Constraint::Percentage(100 - side_width.min(70))];
+            constraints.extend(sides.iter().map(|&(_, w)| Constraint::Percentage(w)));
+            let top = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(constraints)
+                .split(chunks[0]);
+            self.draw_transcription(frame, top[0], playhead);
+            for (&(side, _), &area) in sides.iter().zip(&top[1..]) {
+                match side {
+                    Side::Markers => self.draw_markers(frame, area, playhead),
+                    Side::Summary => self.draw_summary(frame, area),
+                    Side::Stats => self.draw_stats(frame, area),
+                    Side::Speech => self.draw_speech(frame, area, playhead),
+                    Side::Stereo => self.draw_stereo(frame, area, playhead),
+                    Side::Spectrum => self.draw_average_spectrum(frame, area),
+                    Side::Psd => self.draw_psd(frame, area),
+                    Side::Tuner => self.draw_tuner(frame, area, playhead),
+                    Side::Meter => self.draw_meter(frame, area, playhead),
+                    Side::Scope => self.draw_scope(frame, area, playhead),
+                    Side::Playground => self.draw_playground(frame, area),
+                }
+            }
+            if self.tracks.is_empty() {
+                self.draw_waveform(frame, chunks[1], playhead, simple);
+            } else {
+                self.draw_tracks(frame, chunks[1], playhead, simple);
+            }
+            if self.show_rta {
+                let bottom = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+                    .split(chunks[2]);
+                self.draw_spectrogram(frame, bottom[0], playhead, simple);
+                self.draw_rta(frame, bottom[1], playhead);
+            } else {
+                self.draw_spectrogram(frame, chunks[2], playhead, simple);
+            }
+            if self.show_explain {
+                self.draw_explain(frame, frame.size(), playhead);
+                // A bitmap would cover the overlay
+                self.image_area.set(None);
+            }
+        })?;
         Ok(())
     }
 
-    fn draw_transcription(&self, frame: &mut ratatui::Frame, area: Rect) {
-        let text = self
+    fn fg(&self, color: Color) -> Style {
+        if self.capabilities.color {
+            Style::default().fg(color)
+        } else {
+            Style::default()
+        }
+    }
+
+    /// A bordered pane in the theme's border color.
+    fn pane<'a>(&self, title: impl Into<Title<'a>>) -> Block<'a> {
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(self.fg(self.theme.border))
+    }
+
+    /// Without color, intensity levels are told apart by glyph instead.
+    fn intensity_marker(&self, level: usize) -> symbols::Marker {
+        if self.capabilities.color || level >= 2 {
+            symbols::Marker::Block
+        } else {
+            symbols::Marker::Dot
+        }
+    }
+
+    /// Level of a full-scale sine in the spectrogram.
+    fn full_scale(&self) -> f32 {
+        let params = &self.spectrogram_params;
+        params.full_scale(params.analysis_rate(self.audio_data.sample_rate))
+    }
+
+    /// Display floor and top of spectrogram levels: the auto contrast percentiles, or from
+    /// the display range below full scale up to the headroom below it (a full-scale sine by
+    /// default).
+    fn level_range(&self) -> (f32, f32) {
+        if let Some(levels) = self.auto_levels().filter(|_| self.contrast == Contrast::Auto) {
+            return levels;
+        }
+        let full_scale = self.full_scale();
+        let top = full_scale - self.display_headroom;
+        ((full_scale - self.display_range).min(top - levels::MIN_SPAN), top)
+    }
+
+    /// Color scale for spectrogram titles: the floor, the colormap, and the top with its
+    /// unit.
+    fn color_scale(&self) -> Vec<Span<'static>> {
+        let (floor, top) = self.level_range();
+        let params = &self.spectrogram_params;
+        let mut spans = vec![Span::styled(format!("{} ", params.format_value(floor)), self.fg(self.theme.muted))];
+        let glyph = if self.capabilities.unicode { "█" } else { "#" };
+        spans.extend(self.colormap.colors(&self.theme).map(|color| Span::styled(glyph, self.fg(color))));
+        spans.push(Span::styled(format!(" {}", params.format_level(top)), self.fg(self.theme.muted)));
+        spans
+    }
+
+    fn line_dataset<'a>(&self, line: &'a [(f64, f64)], color: Color) -> Dataset<'a> {
+        Dataset::default()
+            .marker(self.capabilities.line_marker())
+            .graph_type(GraphType::Line)
+            .style(self.fg(color))
+            .data(line)
+    }
+
+    /// Vertical lines at the selection bounds (or the pending anchor), spanning 0..top.
+    fn selection_lines(&self, top: f64) -> Vec<[(f64, f64); 2]> {
+        let bounds = match (self.selection, self.selection_anchor) {
+            (Some((start, end)), _) => vec![start, end],
+            (None, Some(anchor)) => vec![anchor],
+            (None, None) => Vec::new(),
+        };
+        bounds.into_iter().map(|t| [(t, 0.0), (t, top)]).collect()
+    }
+
+    fn draw_header(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64, simple: bool) {
+        let tags = &self.audio_data.tags;
+        let mut spans = Vec::new();
+        for (i, tag) in [&tags.title, &tags.artist, &tags.album].into_iter().flatten().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(format!(" {} ", self.capabilities.dash())));
+            }
+            let style = if i == 0 {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(tag.clone(), style));
+        }
+        if !spans.is_empty() {
+            spans.push(Span::raw("  "));
+        }
+        spans.push(Span::styled(
+            format!("[{} / {}]", format_clock(playhead), format_clock(self.stats.duration)),
+            self.fg(self.theme.muted),
+        ));
+        if let Some(label) = &self.preset_label {
+            spans.push(Span::styled(format!(" {}", label), self.fg(self.theme.muted)));
+        }
+        if (self.player.speed() - 1.0).abs() > 1e-6 {
+            spans.push(Span::styled(
+                format!(" {:.2}{}", self.player.speed(), self.capabilities.times()),
+                self.fg(self.theme.highlight),
+            ));
+        }
+        if self.time_expansion > 1.0 {
+            spans.push(Span::styled(
+                format!(" TE {}{}", self.time_expansion, self.capabilities.times()),
+                self.fg(self.theme.highlight),
+            ));
+        }
+        if let Some((start, end)) = self.selection {
+            let label = if self.player.loop_region().is_some() { "loop" } else { "sel" };
+            spans.push(Span::styled(
+                format!("  {} {}-{}", label, format_clock(start), format_clock(end)),
+                self.fg(self.theme.selection),
+            ));
+        } else if let Some(anchor) = self.selection_anchor {
+            spans.push(Span::styled(format!("  sel {}-...", format_clock(anchor)), self.fg(self.theme.selection)));
+        }
+        if let Some((time, input)) = &self.marker_input {
+            let (before, at, after) = input.parts();
+            spans.push(Span::styled(format!("  Marker at {}: ", format_clock(*time)), self.fg(self.theme.marker)));
+            spans.push(Span::raw(before));
+            spans.push(Span::styled(
+                at.map_or(" ".to_string(), String::from),
+                Style::default().add_modifier(Modifier::REVERSED),
+            ));
+            spans.push(Span::raw(after));
+        }
+        if let Some(((start, end), input)) = &self.box_input {
+            let (before, at, after) = input.parts();
+            spans.push(Span::styled(
+                format!("  Cut {}-{}, LOW-HIGH Hz [dB]: ", format_clock(*start), format_clock(*end)),
+                self.fg(self.theme.selection),
+            ));
+            spans.push(Span::raw(before));
+            spans.push(Span::styled(
+                at.map_or(" ".to_string(), String::from),
+                Style::default().add_modifier(Modifier::REVERSED),
+            ));
+            spans.push(Span::raw(after));
+        }
+        if let Some(input) = &self.frequency_input {
+            let (before, at, after) = input.parts();
+            spans.push(Span::styled("  Line at Hz or note (again to remove, `clear`): ", self.fg(self.theme.marker)));
+            spans.push(Span::raw(before));
+            spans.push(Span::styled(
+                at.map_or(" ".to_string(), String::from),
+                Style::default().add_modifier(Modifier::REVERSED),
+            ));
+            spans.push(Span::raw(after));
+        }
+        if simple {
+            spans.push(Span::styled("  simple view", self.fg(self.theme.muted)));
+        }
+        if self.editor.is_some() {
+            spans.push(Span::styled("  EDIT", self.fg(self.theme.info).add_modifier(Modifier::BOLD)));
+        }
+        if self.unsaved_edits {
+            spans.push(Span::styled(" *", self.fg(self.theme.highlight)));
+        }
+        if let Some(info) = &self.info {
+            spans.push(Span::styled(format!("  {}", info), self.fg(self.theme.info)));
+        }
+        if let Some(status) = &self.status {
+            spans.push(Span::styled(format!("  {}", status), self.fg(self.theme.error)));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
+    fn draw_markers(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        // The marker most recently passed by the playhead is highlighted
+        let current = self.markers.iter().rposition(|marker| marker.time <= playhead);
+        let lines: Vec<Line> = self
+            .markers
+            .iter()
+            .enumerate()
+            .map(|(i, marker)| {
+                let line = format!("{} {}", format_clock(marker.time), marker.name);
+                if Some(i) == current {
+                    Line::styled(line, self.fg(self.theme.marker).add_modifier(Modifier::BOLD))
+                } else {
+                    Line::raw(line)
+                }
+            })
+            .collect();
+        let paragraph = Paragraph::new(lines)
+            .block(self.pane("Markers"));
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_summary(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let paragraph = Paragraph::new(self.summary.clone().unwrap_or_default())
+            .wrap(Wrap { trim: true })
+            .block(self.pane("Summary"));
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_stats(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let mut lines = self.stats.summary_lines();
+        lines.extend(self.stats.quality.report_lines());
+        let paragraph = Paragraph::new(lines.join("\n"))
+            .block(self.pane("Stats"));
+        frame.render_widget(paragraph, area);
+    }
+
+    /// Speech analytics above a sparkline of the speaking rate over time.
+    fn draw_speech(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let block = self.pane("Speech");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let stats = &self.speech_stats;
+        if stats.word_count == 0 {
+            frame.render_widget(Paragraph::new("No transcribed words"), inner);
+            return;
+        }
+        let mut lines = stats.summary_lines();
+        if let (Some(rate), Some(_)) = (stats.rate_at(playhead), stats.words_per_minute) {
+            lines[1].push_str(&format!(", now {:.0}", rate));
+        }
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(inner);
+        frame.render_widget(Paragraph::new(lines.join("\n")).wrap(Wrap { trim: true }), rows[0]);
+        // One bar per column, each the fastest window under it
+        let windows = stats.rate.len();
+        let bars = (rows[1].width as usize).min(windows).max(1);
+        let data: Vec<u64> = (0..bars)
+            .map(|c| {
+                let (from, to) = (c * windows / bars, (c + 1) * windows / bars);
+                stats.rate[from..to.max(from + 1)].iter().fold(0.0f64, |a, &b| a.max(b)) as u64
+            })
+            .collect();
+        let sparkline = Sparkline::default().data(&data).style(self.fg(self.theme.waveform));
+        frame.render_widget(sparkline, rows[1]);
+    }
+
+    fn draw_transcription(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let selected = self.editor.as_ref().map(|editor| editor.selected);
+        let text: Vec<Line> = self
             .transcription
             .iter()
-            .map(|seg| {
-                format!(
-                    "[{:.2}s - {:.2}s] {}",
-                    seg.start, seg.end, seg.text
-                )
+            .enumerate()
+            .map(|(i, seg)| {
+                let timing = format!("[{:.2}s - {:.2}s] ", seg.start, seg.end);
+                let editing = self.editor.as_ref().and_then(|editor| editor.input.as_ref());
+                match editing {
+                    Some(input) if selected == Some(i) => {
+                        let (before, at, after) = input.parts();
+                        let cursor = Style::default().add_modifier(Modifier::REVERSED);
+                        Line::from(vec![
+                            Span::raw(timing),
+                            Span::raw(before),
+                            Span::styled(at.map_or(" ".to_string(), String::from), cursor),
+                            Span::raw(after),
+                        ])
+                    }
+                    _ if selected == Some(i) => Line::styled(
+                        timing + seg.text.as_str(),
+                        Style::default().add_modifier(Modifier::REVERSED),
+                    ),
+                    // Highlight the segment under the playhead
+                    _ if playhead > 0.0 && (seg.start..seg.end).contains(&playhead) => Line::styled(
+                        timing + seg.text.as_str(),
+                        self.fg(self.theme.highlight).add_modifier(Modifier::BOLD),
+                    ),
+                    _ => Line::raw(timing + seg.text.as_str()),
+                }
             })
-            .collect::<Vec<_>>()
-            .join("\n");
+            .collect();
 
+        let title = if self.editor.is_some() {
+            "Transcription (Enter edit, [ ] start, { } end, g go, w save, Esc done)"
+        } else {
+            "Transcription"
+        };
+        // Keep the selected segment in view; wrapped lines make this approximate
+        let visible = area.height.saturating_sub(2) as usize;
+        let scroll = selected.map_or(0, |i| i.saturating_sub(visible / 2)) as u16;
         let paragraph = Paragraph::new(text)
-            .block(Block::default().title("Transcription").borders(Borders::ALL))
-            .wrap(Wrap { trim: true });
+            .block(self.pane(title))
+            .wrap(Wrap { trim: true })
+            .scroll((scroll, 0));
         frame.render_widget(paragraph, area);
     }
 
-    fn draw_waveform(&self, frame: &mut ratatui::Frame, area: Rect) {
-        // Find the maximum amplitude for proper scaling
-        let max_amplitude = self.audio_data.samples
+    fn draw_waveform(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64, simple: bool) {
+        // Scale to the loudest sample, with RMS per column (half resolution in the simple view)
+        let max_amplitude = self.envelope.total().peak;
+        let columns = if simple { area.width / 2 } else { area.width }.max(1) as usize;
+        let samples = &self.audio_data.samples;
+        let seconds_per_column = samples.len() as f64 / columns as f64 / self.audio_data.sample_rate as f64;
+        let waveform_data: Vec<(f64, f64)> = self
+            .envelope
+            .columns(samples, 0, samples.len(), columns)
             .iter()
-            .cloned()
-            .fold(0.0f32, f32::max);
-
-        // Calculate step size based on available width
-        let points_per_column = (self.audio_data.samples.len() / area.width as usize).max(1);
-        
-        // Create data points with RMS values for better visualization
-        let waveform_data: Vec<(f64, f64)> = self.audio_data.samples
-            .chunks(points_per_column)
             .enumerate()
-            .map(|(i, chunk)| {
-                let rms = (chunk.iter().map(|&x| x * x).sum::<f32>() / chunk.len() as f32).sqrt();
-                (
-                    i as f64 * points_per_column as f64 / self.audio_data.sample_rate as f64,
-                    (rms / max_amplitude) as f64, // Scale to fit the y-axis
-                )
-            })
+            .map(|(i, column)| (i as f64 * seconds_per_column, (column.rms() / max_amplitude) as f64))
             .collect();
 
         let duration = self.audio_data.samples.len() as f64 / self.audio_data.sample_rate as f64;
@@ -117,15 +1354,44 @@ impl Visualizer {
             "1.0".to_string(),
         ];
 
-        let datasets = vec![Dataset::default()
+        let playhead_line = [(playhead, 0.0), (playhead, 1.0)];
+        let selection_lines = self.selection_lines(1.0);
+        let marker_lines: Vec<[(f64, f64); 2]> =
+            self.markers.iter().map(|m| [(m.time, 0.0), (m.time, 1.0)]).collect();
+        // The first chapter starts at 0, where a line would hide the axis
+        let chapter_lines: Vec<[(f64, f64); 2]> =
+            self.chapters.iter().skip(1).map(|c| [(c.start, 0.0), (c.start, 1.0)]).collect();
+        let mut datasets = vec![Dataset::default()
             .name("Waveform")
-            .marker(symbols::Marker::Braille)
+            .marker(self.capabilities.line_marker())
             .graph_type(GraphType::Line)
-            .style(Style::default().fg(Color::Cyan))
+            .style(self.fg(self.theme.waveform))
             .data(&waveform_data)];
+        for line in &chapter_lines {
+            datasets.push(self.line_dataset(line, self.theme.chapter));
+        }
+        for line in &marker_lines {
+            datasets.push(self.line_dataset(line, self.theme.marker));
+        }
+        for line in &selection_lines {
+            datasets.push(self.line_dataset(line, self.theme.selection));
+        }
+        if playhead > 0.0 {
+            datasets.push(self.line_dataset(&playhead_line, self.theme.playhead));
+        }
 
+        let title = match self.chapters.iter().rposition(|c| c.start <= playhead) {
+            Some(index) => Line::from(vec![
+                Span::raw(format!("Waveform{} ", self.signal_label())),
+                Span::styled(
+                    format!("{}/{} {}", index + 1, self.chapters.len(), self.chapters[index].title),
+                    self.fg(self.theme.chapter),
+                ),
+            ]),
+            None => Line::raw(format!("Waveform{}", self.signal_label())),
+        };
         let chart = Chart::new(datasets)
-            .block(Block::default().title("Waveform").borders(Borders::ALL))
+            .block(self.pane(title))
             .x_axis(
                 ratatui::widgets::Axis::default()
                     .title("Time (s)")
@@ -142,61 +1408,257 @@ impl Visualizer {
         frame.render_widget(chart, area);
     }
 
-    fn draw_spectrogram(&self, frame: &mut ratatui::Frame, area: Rect) {
-        let max_freq_idx = self.spectrogram.frequencies.len().min(100);
-        let time_step = (self.spectrogram.time_points.len() / area.width as usize).max(1);
-        
-        // Create intensity-based points
+    /// Number of bins from 0 Hz the spectrogram shows: up to the preset's (or
+    /// `--max-frequency`'s) frequency, or the lowest 100 bins.
+    fn displayed_bins(&self) -> usize {
+        let frequencies = &self.spectrogram.frequencies;
+        match self.max_frequency {
+            Some(max) => frequencies.iter().take_while(|&&f| f <= max).count().max(1),
+            None => 100,
+        }
+        .min(frequencies.len())
+    }
+
+    fn draw_spectrogram(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64, simple: bool) {
+        // The bitmap is of the whole file, so scrolling always draws with glyphs
+        if self.graphics.is_some() && !simple && !self.scrolling && self.cursor.is_none() {
+            self.draw_spectrogram_frame(frame, area);
+            return;
+        }
+        // Rows and columns of spectrogram points: fixed for the heatmap, matched to the
+        // sub-cell resolution of the glyphs otherwise. The simple view halves both.
+        let (rows, columns) = match self.render {
+            SpectrogramRender::Heatmap => (100, area.width as usize),
+            SpectrogramRender::Blocks => (area.height as usize * 2, area.width as usize),
+            SpectrogramRender::Braille => (area.height as usize * 4, area.width as usize * 2),
+        };
+        let (bins, columns) = if simple { (rows / 2, columns / 2) } else { (rows, columns) };
+        let bins = bins.max(1);
+        let frequencies = &self.spectrogram.frequencies;
+        let max_freq_idx = self.displayed_bins();
+        let frames = self.spectrogram.time_points.len();
+        let duration = *self.spectrogram.time_points.last().unwrap_or(&0.0) as f64;
+        let (floor, top) = self.level_range();
+        // Time range shown: the whole file, or while scrolling the span ending at the playhead,
+        // so the newest frames come in at the right edge
+        let (start, end) = if self.scrolling { (playhead - self.scroll_span, playhead) } else { (0.0, duration) };
+        let frame_at = |time: f64| {
+            let index = if duration > 0.0 { time / duration * frames as f64 } else { 0.0 };
+            (index.max(0.0) as usize).min(frames)
+        };
+
+        // Create intensity-based points (braille uses only the first level). Each point takes
+        // the maximum of the frames and bins under it (from the pyramid for long files), so
+        // the grid is filled whether there are more or fewer frames and bins than points.
         let mut points_by_intensity = vec![Vec::new(); 4]; // 4 intensity levels
-        
-        for t in (0..self.spectrogram.time_points.len()).step_by(time_step) {
-            let time = self.spectrogram.time_points[t];
-            for f in 0..max_freq_idx {
-                let magnitude = self.spectrogram.magnitudes[t][f];
-                let intensity = ((magnitude + 100.0) / 100.0).max(0.0).min(1.0);
-                
-                if intensity > 0.1 {
+
+        let columns = if frames == 0 { 0 } else { columns.max(1) };
+        for column in 0..columns {
+            let time = start + (end - start) * column as f64 / columns as f64;
+            let next = start + (end - start) * (column + 1) as f64 / columns as f64;
+            if time < 0.0 || time >= duration {
+                // Before the start of the file while scrolling
+                continue;
+            }
+            let merged = self.spectrogram_pyramid.frames(&self.spectrogram.magnitudes, frame_at(time), frame_at(next));
+            let rows = bins.min(max_freq_idx);
+            for row in 0..rows {
+                let low = row * max_freq_idx / rows;
+                let high = ((row + 1) * max_freq_idx / rows).max(low + 1);
+                let magnitude = merged
+                    .iter()
+                    .flat_map(|frame| &frame[low..high])
+                    .fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+                let intensity = ((magnitude - floor) / (top - floor)).clamp(0.0, 1.0);
+                let point = (time, frequencies[low] as f64);
+
+                if self.render == SpectrogramRender::Braille {
+                    // Ordered dithering: the dot density follows the intensity
+                    let threshold = (BAYER[row % 4][column % 4] as f32 + 0.5) / 16.0;
+                    if intensity > 0.1 + 0.9 * threshold {
+                        points_by_intensity[0].push(point);
+                    }
+                } else if intensity > 0.1 {
                     let intensity_level = (intensity * 3.99) as usize;
-                    points_by_intensity[intensity_level].push((
-                        time as f64,
-                        self.spectrogram.frequencies[f] as f64,
-                    ));
+                    points_by_intensity[intensity_level].push(point);
                 }
             }
         }
 
-        let colors = [Color::Blue, Color::Green, Color::Yellow, Color::Red];
+        let colors = self.colormap.colors(&self.theme);
         let mut datasets = Vec::new();
-        
+
         for (intensity_level, points) in points_by_intensity.iter().enumerate() {
             if !points.is_empty() {
+                let (marker, style) = match self.render {
+                    SpectrogramRender::Heatmap => {
+                        (self.intensity_marker(intensity_level), self.fg(colors[intensity_level]))
+                    }
+                    SpectrogramRender::Blocks if self.capabilities.unicode => {
+                        (symbols::Marker::HalfBlock, self.fg(colors[intensity_level]))
+                    }
+                    SpectrogramRender::Blocks => {
+                        (self.intensity_marker(intensity_level), self.fg(colors[intensity_level]))
+                    }
+                    SpectrogramRender::Braille => (self.capabilities.line_marker(), Style::default()),
+                };
                 datasets.push(
                     Dataset::default()
-                        .marker(symbols::Marker::Block)
+                        .marker(marker)
                         .graph_type(GraphType::Scatter)
-                        .style(Style::default().fg(colors[intensity_level]))
+                        .style(style)
                         .data(points)
                 );
             }
         }
 
-        let duration = *self.spectrogram.time_points.last().unwrap_or(&0.0) as f64;
         let max_freq = self.spectrogram.frequencies[max_freq_idx - 1];
+
+        let playhead_line = [(playhead, 0.0), (playhead, max_freq as f64)];
+        let selection_lines = self.selection_lines(max_freq as f64);
+        // Steady tones (hum and the like) found by the quality report
+        let tones: Vec<f32> = self
+            .stats
+            .quality
+            .tonal_frequencies()
+            .into_iter()
+            .filter(|&f| f <= max_freq)
+            .collect();
+        let tone_lines: Vec<[(f64, f64); 2]> =
+            tones.iter().map(|&f| [(start.max(0.0), f as f64), (end, f as f64)]).collect();
+        let across = |frequency: f64| [(start.max(0.0), frequency), (end, frequency)];
+        let grid = self.note_grid_lines(max_freq as f64, area.height as usize);
+        let grid_lines: Vec<[(f64, f64); 2]> = grid.iter().map(|&(f, _)| across(f)).collect();
+        for (line, &(_, a)) in grid_lines.iter().zip(&grid) {
+            datasets.push(self.line_dataset(line, if a { self.theme.selection } else { self.theme.muted }));
+        }
+        let frequency_lines: Vec<[(f64, f64); 2]> =
+            self.frequency_markers.iter().filter(|&&f| f <= max_freq as f64).map(|&f| across(f)).collect();
+        for line in &frequency_lines {
+            datasets.push(self.line_dataset(line, self.theme.marker));
+        }
+        for line in &tone_lines {
+            datasets.push(self.line_dataset(line, self.theme.error));
+        }
+        // The engine's firing frequency, broken where it isn't heard
+        let mut rpm_lines: Vec<Vec<(f64, f64)>> = Vec::new();
+        if let Some(track) = &self.rpm {
+            let mut line = Vec::new();
+            for &(time, frequency) in &track.points {
+                match frequency.filter(|&f| f <= max_freq && (start..=end).contains(&time)) {
+                    Some(frequency) => line.push((time, frequency as f64)),
+                    None if !line.is_empty() => rpm_lines.push(std::mem::take(&mut line)),
+                    None => {}
+                }
+            }
+            if !line.is_empty() {
+                rpm_lines.push(line);
+            }
+        }
+        for line in &rpm_lines {
+            datasets.push(self.line_dataset(line, self.theme.highlight));
+        }
+        let doppler_line: Vec<(f64, f64)> = self
+            .doppler_curve
+            .iter()
+            .copied()
+            .filter(|&(time, frequency)| (start..=end).contains(&time) && frequency <= max_freq as f64)
+            .collect();
+        if !doppler_line.is_empty() {
+            datasets.push(self.line_dataset(&doppler_line, self.theme.marker));
+        }
+        // Sibilant bursts are flagged along the top edge, as the band is often out of view
+        let sibilance_lines: Vec<[(f64, f64); 2]> = self
+            .stats
+            .sibilance
+            .iter()
+            .map(|burst| [(burst.start, max_freq as f64), (burst.end, max_freq as f64)])
+            .collect();
+        for line in &sibilance_lines {
+            datasets.push(self.line_dataset(line, self.theme.highlight));
+        }
+        let edit_outlines: Vec<[(f64, f64); 5]> =
+            self.spectral_edits.iter().filter_map(|edit| edit.outline(max_freq)).collect();
+        for outline in &edit_outlines {
+            datasets.push(self.line_dataset(outline, self.theme.marker));
+        }
+        for line in &selection_lines {
+            datasets.push(self.line_dataset(line, self.theme.selection));
+        }
+        let readout = self.cursor_readout();
+        let crosshair: Vec<[(f64, f64); 2]> = readout
+            .map(|r| {
+                vec![
+                    [(r.time, 0.0), (r.time, max_freq as f64)],
+                    [(start.max(0.0), r.frequency as f64), (end, r.frequency as f64)],
+                ]
+            })
+            .unwrap_or_default();
+        for line in &crosshair {
+            datasets.push(self.line_dataset(line, self.theme.highlight));
+        }
+        let series = readout.as_ref().and_then(|r| self.cursor_series(r));
+        let comb: Vec<[(f64, f64); 2]> = match (&readout, &series) {
+            (Some(r), Some(series)) => Self::comb_lines(series, r.time, end - start, max_freq as f64),
+            _ => Vec::new(),
+        };
+        for (k, line) in comb.iter().enumerate() {
+            datasets.push(self.line_dataset(line, if k == 0 { self.theme.selection } else { self.theme.marker }));
+        }
+        if playhead > 0.0 {
+            datasets.push(self.line_dataset(&playhead_line, self.theme.playhead));
+        }
         
         let time_labels: Vec<Span> = (0..=5)
-            .map(|i| Span::raw(format!("{:.1}s", duration * i as f64 / 5.0)))
+            .map(|i| Span::raw(format!("{:.1}s", start + (end - start) * i as f64 / 5.0)))
             .collect();
             
         let freq_labels: Vec<Span> = (0..=4)
-            .map(|i| Span::raw(format!("{:.0}Hz", max_freq * i as f32 / 4.0)))
+            .map(|i| Span::raw(frequency_label(max_freq * i as f32 / 4.0)))
             .collect();
 
+        let mut title = vec![Span::raw(format!("Spectrogram{} ", self.signal_label()))];
+        if !tones.is_empty() {
+            let list: Vec<String> = tones.iter().map(|f| format!("{:.0}", f)).collect();
+            title.push(Span::styled(format!("tones: {} Hz ", list.join(", ")), self.fg(self.theme.error)));
+        }
+        if self.note_grid {
+            title.push(Span::styled("A440 grid ", self.fg(self.theme.selection)));
+        }
+        if let Some(rpm) = self.rpm.as_ref().and_then(|track| track.rpm_at(playhead)) {
+            title.push(Span::styled(format!("{:.0} RPM ", rpm), self.fg(self.theme.highlight)));
+        }
+        if let Some(readout) = readout {
+            let snap = if self.cursor.is_some_and(|cursor| cursor.snap) { " (snap)" } else { "" };
+            title.push(Span::styled(
+                format!(
+                    "{:.3}s {:.1} Hz {}{} ",
+                    readout.time,
+                    readout.frequency,
+                    self.spectrogram_params.format_level(readout.level),
+                    snap
+                ),
+                self.fg(self.theme.highlight),
+            ));
+        }
+        if let Some(series) = &series {
+            title.push(Span::styled(
+                format!("f0 {:.1} Hz {} (h{}) ", series.fundamental, series.note(), series.harmonic),
+                self.fg(self.theme.marker),
+            ));
+        }
+        if !self.stats.sibilance.is_empty() {
+            let count = self.stats.sibilance.len();
+            title.push(Span::styled(format!("sibilance: {} ", count), self.fg(self.theme.highlight)));
+        }
+        title.extend(self.color_scale());
+        let title = Line::from(title);
         let chart = Chart::new(datasets)
-            .block(Block::default().title("Spectrogram").borders(Borders::ALL))
+            .block(self.pane(title))
             .x_axis(
                 ratatui::widgets::Axis::default()
                     .title("Time (s)")
-                    .bounds([0.0, duration])
+                    .bounds([start, end])
                     .labels(time_labels)
             )
             .y_axis(
@@ -208,4 +1670,32 @@ impl Visualizer {
 
         frame.render_widget(chart, area);
     }
-} 
\ No newline at end of file
+}
+
+
+/// Seconds of audio the scrolling spectrogram shows by default.
+pub const SCROLL_SPAN: f64 = 10.0;
+
+/// Spectrogram levels shown below full scale with fixed contrast, until changed, in dB.
+const DISPLAY_RANGE: f32 = 100.0;
+
+/// 4×4 Bayer matrix for ordered dithering.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+fn format_clock(seconds: f64) -> String {
+    format!("{}:{:05.2}", (seconds / 60.0) as u64, seconds % 60.0)
+}
+
+/// An axis label for `hz`, in kHz from 10 kHz up so ultrasonic ranges stay narrow, and
+/// with decimals below 10 Hz so infrasonic ones don't all round to the same label.
+fn frequency_label(hz: f32) -> String {
+    if hz >= 10_000.0 {
+        format!("{:.0}kHz", hz / 1000.0)
+    } else if hz >= 10.0 || hz == 0.0 {
+        format!("{:.0}Hz", hz)
+    } else if hz >= 1.0 {
+        format!("{:.1}Hz", hz)
+    } else {
+        format!("{:.2}Hz", hz)
+    }
+}
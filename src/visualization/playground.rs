@@ -0,0 +1,232 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::Paragraph;
+
+use super::levels::Contrast;
+use super::theme::Colormap;
+use super::Visualizer;
+use crate::audio::pyramid::SpectrogramPyramid;
+use crate::audio::{compute_spectrogram_with, SpectrogramData, SpectrogramJob, SpectrogramParams, WindowFunction};
+
+/// Frames computed per re-render; a few per terminal column is all the display can show.
+const MAX_FRAMES: usize = 1024;
+
+const WINDOW_SIZES: [usize; 7] = [256, 512, 1024, 2048, 4096, 8192, 16384];
+
+/// Hop as a fraction of the window.
+const HOP_FRACTIONS: [f64; 5] = [0.125, 0.25, 0.5, 0.75, 1.0];
+
+/// Spectrogram levels shown below full scale, in dB.
+const RANGES: [f32; 6] = [40.0, 60.0, 80.0, 100.0, 120.0, 140.0];
+
+const ROWS: usize = 5;
+
+/// A spectrogram being recomputed on another thread with new parameters. Dropping it
+/// cancels the computation.
+pub(super) struct Recompute {
+    pub(super) params: SpectrogramParams,
+    frames: usize,
+    progress: Arc<AtomicUsize>,
+    cancelled: Arc<AtomicBool>,
+    result: Receiver<SpectrogramData>,
+}
+
+impl Drop for Recompute {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Steps `current` by `delta` positions through `options`, clamping at the ends.
+fn step<T: Copy + PartialEq>(options: &[T], current: T, delta: isize) -> T {
+    let index = options.iter().position(|&o| o == current).unwrap_or(0) as isize;
+    options[(index + delta).clamp(0, options.len() as isize - 1) as usize]
+}
+
+/// Like `step`, but wrapping around; for unordered choices.
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, delta: isize) -> T {
+    let index = options.iter().position(|&o| o == current).unwrap_or(0) as isize;
+    options[(index + delta).rem_euclid(options.len() as isize) as usize]
+}
+
+fn nearest<T: Copy>(options: &[T], key: impl Fn(T) -> f64) -> T {
+    *options
+        .iter()
+        .min_by(|&&a, &&b| key(a).total_cmp(&key(b)))
+        .expect("options are not empty")
+}
+
+impl Visualizer {
+    pub(super) fn handle_playground_key(&mut self, key: KeyEvent) {
+        let Some(row) = self.playground else {
+            return;
+        };
+        let delta = match key.code {
+            KeyCode::Esc | KeyCode::Char('p') => {
+                self.playground = None;
+                return;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.playground = Some(row.saturating_sub(1));
+                return;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.playground = Some((row + 1).min(ROWS - 1));
+                return;
+            }
+            KeyCode::Left | KeyCode::Char('-') => -1,
+            KeyCode::Right | KeyCode::Char('+') | KeyCode::Char('=') => 1,
+            _ => return,
+        };
+
+        // Steps continue from a change still being computed
+        let mut params = self.recompute.as_ref().map_or(self.spectrogram_params, |r| r.params);
+        let hop_fraction = params.hop_size as f64 / params.window_size as f64;
+        match row {
+            0 => {
+                let size = nearest(&WINDOW_SIZES, |s| (s as f64 - params.window_size as f64).abs());
+                params.window_size = step(&WINDOW_SIZES, size, delta);
+                params.hop_size = ((params.window_size as f64 * hop_fraction) as usize).max(1);
+            }
+            1 => params.window = cycle(&WindowFunction::ALL, params.window, delta),
+            2 => {
+                let fraction = nearest(&HOP_FRACTIONS, |f| (f - hop_fraction).abs());
+                let fraction = step(&HOP_FRACTIONS, fraction, delta);
+                params.hop_size = ((params.window_size as f64 * fraction) as usize).max(1);
+            }
+            3 => {
+                self.fix_levels();
+                let range = nearest(&RANGES, |r| (r - self.display_range).abs() as f64);
+                self.display_range = step(&RANGES, range, delta);
+                return;
+            }
+            _ => {
+                self.colormap = cycle(&Colormap::ALL, self.colormap, delta);
+                return;
+            }
+        }
+        let result = self.start_recompute(params);
+        self.report(result);
+    }
+
+    /// Starts recomputing the frames the spectrogram pane can show on another thread,
+    /// replacing (and cancelling) any recomputation still running. The current spectrogram
+    /// stays on screen until `poll_recompute` swaps the new one in.
+    pub(super) fn start_recompute(&mut self, params: SpectrogramParams) -> Result<()> {
+        let job = SpectrogramJob::new(&self.audio_data, &params, MAX_FRAMES)?;
+        let progress = Arc::new(AtomicUsize::new(0));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, result) = mpsc::channel();
+        let frames = job.frames();
+        let (thread_progress, thread_cancelled) = (progress.clone(), cancelled.clone());
+        std::thread::spawn(move || {
+            let spectrogram = job.run(&thread_progress, &thread_cancelled);
+            // The receiver is gone if the recomputation was replaced or cancelled
+            let _ = sender.send(spectrogram);
+        });
+        self.recompute = Some(Recompute { params, frames, progress, cancelled, result });
+        Ok(())
+    }
+
+    /// Swaps in a finished background recomputation. Returns true while one is running or
+    /// has just finished, so the progress is redrawn.
+    pub(super) fn poll_recompute(&mut self) -> bool {
+        let Some(recompute) = &self.recompute else {
+            return false;
+        };
+        match recompute.result.try_recv() {
+            Ok(spectrogram) => {
+                let params = recompute.params;
+                self.recompute = None;
+                self.apply_spectrogram(spectrogram, params);
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {
+                self.recompute = None;
+                self.status = Some("Spectrogram recomputation failed".to_string());
+            }
+        }
+        true
+    }
+
+    /// Recomputes only the frames the spectrogram pane can show, on this thread; cancels
+    /// any background recomputation, which would be of the old signal.
+    pub(super) fn recompute_spectrogram(&mut self, params: SpectrogramParams) -> Result<()> {
+        self.recompute = None;
+        let spectrogram = compute_spectrogram_with(&self.audio_data, &params, Some(MAX_FRAMES))?;
+        self.apply_spectrogram(spectrogram, params);
+        Ok(())
+    }
+
+    fn apply_spectrogram(&mut self, spectrogram: SpectrogramData, params: SpectrogramParams) {
+        self.spectrogram = spectrogram;
+        self.spectrogram_pyramid = SpectrogramPyramid::new(&self.spectrogram.magnitudes);
+        self.auto_levels.set(None);
+        self.spectrogram_params = params;
+        // A session file reopens with the parameters last chosen here
+        if let Some((_, table)) = &mut self.session_file {
+            table.insert("window_size".into(), (params.window_size as i64).into());
+            table.insert("hop".into(), (params.hop_size as i64).into());
+            if let Some(name) = params.window.to_possible_value() {
+                table.insert("window_function".into(), name.get_name().into());
+            }
+        }
+    }
+
+    pub(super) fn draw_playground(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let params = &self.recompute.as_ref().map_or(self.spectrogram_params, |r| r.params);
+        let rate = params.analysis_rate(self.audio_data.sample_rate) as f64;
+        let overlap = 100.0 * (1.0 - params.hop_size as f64 / params.window_size as f64);
+        let values = [
+            format!("Window:   {}", params.window_size),
+            format!("Function: {}", params.window),
+            format!("Hop:      {} ({:.0}% overlap)", params.hop_size, overlap.max(0.0)),
+            match self.contrast {
+                Contrast::Auto => {
+                    let (floor, top) = self.level_range();
+                    format!("Range:    auto ({:.0} dB)", top - floor)
+                }
+                Contrast::Fixed => format!("Range:    {:.0} dB", self.display_range),
+            },
+            format!("Colors:   {}", self.colormap.name()),
+        ];
+        let row = self.playground.unwrap_or(0);
+        let mut lines: Vec<Line> = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| {
+                if i == row {
+                    Line::styled(format!("> {}", text), Style::default().add_modifier(Modifier::REVERSED))
+                } else {
+                    Line::raw(format!("  {}", text))
+                }
+            })
+            .collect();
+        lines.push(Line::raw(""));
+        if let Some(recompute) = &self.recompute {
+            let done = recompute.progress.load(Ordering::Relaxed).min(recompute.frames);
+            lines.push(Line::styled(
+                format!("Computing... {:.0}%", 100.0 * done as f64 / recompute.frames.max(1) as f64),
+                self.fg(self.theme.highlight),
+            ));
+        }
+        lines.push(Line::styled(
+            format!(
+                "{:.1} Hz/bin, {:.1} ms/window",
+                rate / params.window_size as f64,
+                params.window_size as f64 / rate * 1000.0
+            ),
+            self.fg(self.theme.muted),
+        ));
+        lines.push(Line::styled("j/k select  -/+ change  p close", self.fg(self.theme.muted)));
+        let paragraph = Paragraph::new(lines).block(self.pane("Playground"));
+        frame.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,95 @@
+use ratatui::layout::Rect;
+use ratatui::text::Span;
+use ratatui::widgets::{Axis, Chart, Dataset, GraphType, Paragraph};
+
+use super::Visualizer;
+use crate::audio::psd::{density_db, welch, Psd, WelchParams};
+
+/// Range of the density axis below the highest upper bound, in dB.
+const RANGE: f64 = 100.0;
+
+/// Welch PSD and the selection it was computed for.
+pub(super) type PsdCache = (Option<(f64, f64)>, Psd);
+
+impl Visualizer {
+    /// Welch PSD of the selection, or of the whole file without one, with the `psd`
+    /// subcommand's defaults. Kept until the selection changes.
+    fn psd(&self) -> std::cell::Ref<'_, Psd> {
+        let stale = self.psd.borrow().as_ref().is_none_or(|(range, _)| *range != self.selection);
+        if stale {
+            let samples = &self.audio_data.samples;
+            let rate = self.audio_data.sample_rate as f64;
+            let (from, to) = match self.selection {
+                Some((start, end)) => (
+                    ((start * rate) as usize).min(samples.len()),
+                    ((end * rate) as usize).min(samples.len()),
+                ),
+                None => (0, samples.len()),
+            };
+            let psd = welch(&samples[from..to], self.audio_data.sample_rate, WelchParams::default())
+                .expect("default Welch parameters are valid");
+            *self.psd.borrow_mut() = Some((self.selection, psd));
+        }
+        std::cell::Ref::map(self.psd.borrow(), |cached| &cached.as_ref().expect("just computed").1)
+    }
+
+    /// The PSD as a line in dB/Hz between its confidence bounds.
+    pub(super) fn draw_psd(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let psd = self.psd();
+        let title = format!(
+            "PSD{}, {:.0}% interval",
+            if self.selection.is_some() { " (selection)" } else { "" },
+            psd.params.confidence * 100.0
+        );
+        let block = self.pane(title);
+        let max_frequency = self
+            .max_frequency
+            .unwrap_or(self.audio_data.sample_rate as f32 / 2.0)
+            .min(psd.frequencies.last().copied().unwrap_or(0.0));
+        let bins = psd.frequencies.iter().take_while(|&&f| f <= max_frequency).count();
+        if bins < 3 {
+            frame.render_widget(Paragraph::new("Too short").block(block), area);
+            return;
+        }
+
+        // One point per braille column, the highest bin under it; DC is left out
+        let points = (area.width as usize * 2).clamp(1, bins - 1);
+        let curve = |values: &[f64]| -> Vec<(f64, f64)> {
+            (0..points)
+                .map(|p| {
+                    let from = 1 + p * (bins - 1) / points;
+                    let to = (1 + (p + 1) * (bins - 1) / points).max(from + 1);
+                    let peak = values[from..to].iter().fold(0.0f64, |a, &b| a.max(b));
+                    (psd.frequencies[from] as f64, density_db(peak))
+                })
+                .collect()
+        };
+        let (density, lower, upper) = (curve(&psd.density), curve(&psd.lower), curve(&psd.upper));
+        let peak = upper.iter().fold(f64::NEG_INFINITY, |a, &(_, level)| a.max(level));
+        let top = (peak / 10.0).ceil() * 10.0;
+        let bottom = top - RANGE;
+        let clamp = |data: Vec<(f64, f64)>| -> Vec<(f64, f64)> {
+            data.into_iter().map(|(f, level)| (f, level.max(bottom))).collect()
+        };
+        let (density, lower, upper) = (clamp(density), clamp(lower), clamp(upper));
+
+        let line = |data| Dataset::default().marker(self.capabilities.line_marker()).graph_type(GraphType::Line).data(data);
+        let chart = Chart::new(vec![
+            line(&lower).style(self.fg(self.theme.muted)),
+            line(&upper).style(self.fg(self.theme.muted)),
+            line(&density).style(self.fg(self.theme.waveform)),
+        ])
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, max_frequency as f64])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}Hz", max_frequency))]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([bottom, top])
+                .labels(vec![Span::raw(format!("{:.0}", bottom)), Span::raw(format!("{:.0}dB/Hz", top))]),
+        );
+        frame.render_widget(chart, area);
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph};
+use std::time::{Duration, SystemTime};
+
+use super::console::{ConsoleCapabilities, TerminalSession};
+use super::theme::Theme;
+use crate::audio::capture::Recorder;
+
+/// Redraw interval, which is also how often keys are checked.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Lowest level the meters show, in dBFS.
+const FLOOR: f32 = -60.0;
+
+/// Peaks above this are flagged as clipping.
+const CLIP: f32 = -0.1;
+
+/// Shows live level meters while `recorder` runs, until `duration` seconds are recorded,
+/// the recorder stops, or `q`, `Esc`, `Enter` or Ctrl-C end the recording early. `note`
+/// gives a line to show under the clock, such as how many events were saved.
+pub fn run(
+    recorder: &Recorder,
+    duration: Option<f64>,
+    title: &str,
+    note: impl Fn() -> Option<String>,
+    theme: &Theme,
+) -> Result<()> {
+    let mut session = TerminalSession::start()?;
+    let capabilities = ConsoleCapabilities::detect();
+    let fg = |color| if capabilities.color { Style::default().fg(color) } else { Style::default() };
+    // Highest peak of each channel so far
+    let mut held: Vec<f32> = Vec::new();
+    loop {
+        let elapsed = recorder.elapsed();
+        if duration.is_some_and(|duration| elapsed >= duration) || !recorder.is_running() {
+            return Ok(());
+        }
+        let levels = recorder.levels();
+        held.resize(levels.len(), f32::NEG_INFINITY);
+        for (held, &(_, peak)) in held.iter_mut().zip(&levels) {
+            *held = held.max(peak);
+        }
+        let note = note();
+        let clock = match duration {
+            Some(duration) => format!("{:.1}s of {:.0}s", elapsed, duration),
+            None => format!("{:.1}s", elapsed),
+        };
+
+        session.terminal.draw(|frame| {
+            let block = Block::default()
+                .title(format!(" {} ", title))
+                .borders(Borders::ALL)
+                .border_style(fg(theme.border));
+            let inner = block.inner(frame.size());
+            frame.render_widget(block, frame.size());
+            let mut constraints = vec![Constraint::Length(1), Constraint::Length(1)];
+            constraints.extend(levels.iter().map(|_| Constraint::Length(2)));
+            constraints.push(Constraint::Min(0));
+            constraints.push(Constraint::Length(1));
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(constraints)
+                .split(inner);
+            frame.render_widget(Paragraph::new(Line::styled(format!("Recording {}", clock), fg(theme.highlight))), rows[0]);
+            if let Some(note) = &note {
+                frame.render_widget(Paragraph::new(Line::styled(note.as_str(), fg(theme.muted))), rows[1]);
+            }
+            for (channel, (&(rms, peak), &held)) in levels.iter().zip(&held).enumerate() {
+                let ratio = ((rms.max(FLOOR) - FLOOR) / -FLOOR).clamp(0.0, 1.0);
+                let color = if held > CLIP { theme.error } else { theme.waveform };
+                let label = format!(
+                    "ch {}: {:.1} dB RMS, peak {:.1} dB, max {:.1} dB{}",
+                    channel + 1,
+                    rms.max(FLOOR),
+                    peak.max(FLOOR),
+                    held.max(FLOOR),
+                    if held > CLIP { ", clipped" } else { "" }
+                );
+                let gauge = Gauge::default().gauge_style(fg(color)).ratio(ratio as f64).label(label);
+                frame.render_widget(gauge, rows[2 + channel]);
+            }
+            let keys = Line::styled("q, Esc or Enter to stop and save", fg(theme.muted));
+            frame.render_widget(Paragraph::new(keys), rows[rows.len() - 1]);
+        })?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                let stop = matches!(key.code, KeyCode::Char('q' | 'c') | KeyCode::Esc | KeyCode::Enter);
+                if key.kind == KeyEventKind::Press && stop {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Shows a countdown while a scheduled recording waits for `start`, with `note` (such as
+/// the schedule) under it. Returns false if `q`, `Esc` or Ctrl-C stopped the wait.
+pub fn wait(start: SystemTime, title: &str, note: &str, theme: &Theme) -> Result<bool> {
+    let mut session = TerminalSession::start()?;
+    let capabilities = ConsoleCapabilities::detect();
+    let fg = |color| if capabilities.color { Style::default().fg(color) } else { Style::default() };
+    loop {
+        let Ok(left) = start.duration_since(SystemTime::now()) else {
+            return Ok(true);
+        };
+        let left = left.as_secs();
+        let countdown = format!("Waiting {}:{:02}:{:02} to record", left / 3600, left / 60 % 60, left % 60);
+
+        session.terminal.draw(|frame| {
+            let block = Block::default()
+                .title(format!(" {} ", title))
+                .borders(Borders::ALL)
+                .border_style(fg(theme.border));
+            let inner = block.inner(frame.size());
+            frame.render_widget(block, frame.size());
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)])
+                .split(inner);
+            frame.render_widget(Paragraph::new(Line::styled(countdown.as_str(), fg(theme.highlight))), rows[0]);
+            frame.render_widget(Paragraph::new(Line::styled(note, fg(theme.muted))), rows[1]);
+            let keys = Line::styled("q or Esc to stop waiting", fg(theme.muted));
+            frame.render_widget(Paragraph::new(keys), rows[3]);
+        })?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                let stop = matches!(key.code, KeyCode::Char('q' | 'c') | KeyCode::Esc);
+                if key.kind == KeyEventKind::Press && stop {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+
+use super::Visualizer;
+use crate::export::json::float;
+use crate::export::png::png_bytes;
+use crate::export::transcription_json;
+use crate::export::wav::write_wav;
+
+/// Min/max pairs in the report's waveform, enough for a full-width view on a wide screen.
+const PEAKS: usize = 2000;
+
+/// Size of the report's spectrogram image in pixels.
+const IMAGE: (usize, usize) = (1600, 400);
+
+/// Page of `--export-report`; `{{title}}` and `{{data}}` are filled in.
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 1100px; padding: 0 1em; color: #222; }
+h2 { font-size: 1.1em; margin-top: 2em; }
+.view { position: relative; cursor: pointer; }
+.view canvas, .view img { display: block; width: 100%; }
+.playhead { position: absolute; top: 0; bottom: 0; width: 2px; background: #e33; pointer-events: none; }
+audio { width: 100%; margin-top: 1em; }
+.muted { color: #777; font-size: 0.9em; }
+#transcript p { margin: 0.2em 0; padding: 0.2em 0.4em; cursor: pointer; border-radius: 3px; }
+#transcript p:hover { background: #eee; }
+#transcript p.current { background: #ffe9a8; }
+#transcript span { color: #777; font-variant-numeric: tabular-nums; margin-right: 0.8em; }
+pre { background: #f5f5f5; padding: 1em; overflow-x: auto; }
+</style>
+</head>
+<body>
+<h1>{{title}}</h1>
+<audio id="audio" controls src="audio.wav"></audio>
+<h2>Waveform</h2>
+<div class="view" id="waveform"><canvas height="160"></canvas><div class="playhead"></div></div>
+<h2>Spectrogram</h2>
+<div class="view" id="spectrogram"><img src="spectrogram.png" alt="Spectrogram"><div class="playhead"></div></div>
+<p class="muted" id="range"></p>
+<h2>Transcript</h2>
+<div id="transcript"></div>
+<h2>Stats</h2>
+<pre id="stats"></pre>
+<p class="muted">Generated by fourrier</p>
+<script>
+const data = {{data}};
+const audio = document.getElementById("audio");
+const clock = t => Math.floor(t / 60) + ":" + (t % 60).toFixed(1).padStart(4, "0");
+
+const canvas = document.querySelector("#waveform canvas");
+function drawWaveform() {
+  canvas.width = canvas.clientWidth * devicePixelRatio;
+  canvas.height = 160 * devicePixelRatio;
+  const ctx = canvas.getContext("2d");
+  const middle = canvas.height / 2;
+  ctx.fillStyle = "#3a7bd5";
+  for (let x = 0; x < canvas.width; x++) {
+    const from = Math.floor(x * data.peaks.length / canvas.width);
+    const to = Math.max(from + 1, Math.floor((x + 1) * data.peaks.length / canvas.width));
+    let low = 0, high = 0;
+    for (const [min, max] of data.peaks.slice(from, to)) {
+      low = Math.min(low, min);
+      high = Math.max(high, max);
+    }
+    ctx.fillRect(x, middle - high * middle, 1, Math.max(1, (high - low) * middle));
+  }
+}
+drawWaveform();
+addEventListener("resize", drawWaveform);
+
+for (const view of document.querySelectorAll(".view")) {
+  view.addEventListener("click", event => {
+    const box = view.getBoundingClientRect();
+    audio.currentTime = (event.clientX - box.left) / box.width * data.duration;
+    audio.play();
+  });
+}
+
+const transcript = document.getElementById("transcript");
+const lines = data.transcription.map(segment => {
+  const line = document.createElement("p");
+  const time = document.createElement("span");
+  time.textContent = clock(segment.start);
+  line.append(time, segment.text);
+  line.addEventListener("click", () => { audio.currentTime = segment.start; audio.play(); });
+  transcript.append(line);
+  return line;
+});
+if (!lines.length) transcript.textContent = "No transcript.";
+document.getElementById("stats").textContent = data.stats.join("\n");
+document.getElementById("range").textContent =
+  "0 to " + Math.round(data.max_frequency) + " Hz, bottom to top; click the waveform, spectrogram or a line of the transcript to play from there.";
+
+function update() {
+  const position = audio.currentTime / data.duration * 100;
+  for (const playhead of document.querySelectorAll(".playhead")) playhead.style.left = position + "%";
+  data.transcription.forEach((segment, i) => {
+    lines[i].classList.toggle("current", audio.currentTime >= segment.start && audio.currentTime < segment.end);
+  });
+  requestAnimationFrame(update);
+}
+update();
+</script>
+</body>
+</html>
+"##;
+
+impl Visualizer {
+    /// Writes a self-contained HTML report to `dir`: `index.html` with the waveform, the
+    /// spectrogram (`spectrogram.png`), the transcript and the stats, playing `audio.wav`
+    /// from wherever they are clicked. It opens from disk without a server.
+    pub fn export_report(&self, dir: &Path, title: &str) -> Result<()> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let (width, height) = IMAGE;
+        let bitmap = self.spectrogram_bitmap(width, height, 0.0);
+        let image = dir.join("spectrogram.png");
+        std::fs::write(&image, png_bytes(bitmap.width, bitmap.height, &bitmap.rgb))
+            .with_context(|| format!("Failed to write {}", image.display()))?;
+
+        let audio = &self.audio_data;
+        let channels: Vec<&[f32]> = (0..audio.channels.max(1)).filter_map(|c| audio.channel(c)).collect();
+        write_wav(&dir.join("audio.wav"), &channels, audio.sample_rate)?;
+
+        let samples: &[f32] = &audio.samples;
+        let count = PEAKS.min(samples.len());
+        let peaks: Vec<[f64; 2]> = (0..count)
+            .map(|i| {
+                let bucket = &samples[i * samples.len() / count..(i + 1) * samples.len() / count];
+                let (min, max) = bucket.iter().fold((0.0f32, 0.0f32), |(min, max), &s| (min.min(s), max.max(s)));
+                // Three decimals are finer than the waveform's pixels and keep the page small
+                let round = |value: f32| (value as f64 * 1000.0).round() / 1000.0;
+                [round(min), round(max)]
+            })
+            .collect();
+        let stats = self.stats.summary_lines().into_iter().chain(self.stats.quality.report_lines());
+        let data = json!({
+            "duration": self.player.duration(),
+            "max_frequency": float(self.spectrogram.frequencies[self.displayed_bins() - 1]),
+            "peaks": peaks,
+            "transcription": transcription_json(&self.transcription),
+            "stats": stats.collect::<Vec<_>>(),
+        });
+        let page = TEMPLATE
+            .replace("{{title}}", &escape_html(title))
+            // `</script>` in a transcript would otherwise end the script
+            .replace("{{data}}", &data.to_string().replace("</", "<\\/"));
+        let index = dir.join("index.html");
+        std::fs::write(&index, page).with_context(|| format!("Failed to write {}", index.display()))
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
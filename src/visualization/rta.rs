@@ -0,0 +1,162 @@
+use std::time::Instant;
+
+use ratatui::layout::Rect;
+use ratatui::widgets::{Paragraph, Widget};
+
+use super::Visualizer;
+use crate::audio::spectrum::average_spectrum;
+
+/// Lowest level shown, in dBFS; bars start here.
+const FLOOR: f32 = -90.0;
+
+/// How long a peak is held before it starts to fall, in seconds.
+const HOLD: f32 = 1.0;
+
+/// How fast a released peak falls, in dB per second.
+const FALL: f32 = 20.0;
+
+/// Equivalent noise bandwidth of the Hann window in bins, so summing a band's bins doesn't
+/// overstate a tone's level.
+const HANN_ENBW: f32 = 1.5;
+
+/// Held band peaks: the level and when it was reached.
+pub(super) type PeakHold = Vec<(f32, Instant)>;
+
+/// Centers of the 1/3-octave bands (or octave bands with `step` 3) from 25 Hz up to
+/// `max_frequency`, on the base-two series around 1 kHz.
+fn band_centers(max_frequency: f32, step: usize) -> Vec<f32> {
+    (-16..=13)
+        .step_by(step)
+        .map(|n| 1000.0 * 2f32.powf(n as f32 / 3.0))
+        .take_while(|&center| center <= max_frequency)
+        .collect()
+}
+
+fn frequency_label(frequency: f32) -> String {
+    if frequency >= 1000.0 {
+        format!("{}k", (frequency / 1000.0 * 10.0).round() / 10.0)
+    } else {
+        format!("{:.0}", frequency)
+    }
+}
+
+impl Visualizer {
+    /// Level in dBFS of each band in the window centred on the playhead.
+    fn band_levels(&self, playhead: f64, centers: &[f32], half_band: f32) -> Vec<f32> {
+        let samples = &self.audio_data.samples;
+        let rate = self.audio_data.sample_rate;
+        // About 125 ms, so the bars follow the audio closely
+        let size = (rate as usize / 8).next_power_of_two().max(1024);
+        let center = (playhead * rate as f64) as usize;
+        let start = center.saturating_sub(size / 2).min(samples.len());
+        let end = (start + size).min(samples.len());
+        let spectrum = average_spectrum(&samples[start..end], rate, size, 1);
+        let power: Vec<f32> = spectrum.levels.iter().map(|&level| 10f32.powf(level / 10.0)).collect();
+
+        centers
+            .iter()
+            .map(|&center| {
+                let (low, high) = (center / half_band, center * half_band);
+                let in_band: f32 = spectrum
+                    .frequencies
+                    .iter()
+                    .zip(&power)
+                    .filter(|(&f, _)| f >= low && f < high)
+                    .map(|(_, &p)| p)
+                    .sum();
+                // Bands narrower than a bin take the nearest bin instead
+                let total = if in_band > 0.0 {
+                    in_band / HANN_ENBW
+                } else {
+                    power.get(spectrum.bin(center)).copied().unwrap_or(0.0)
+                };
+                10.0 * total.max(1e-12).log10()
+            })
+            .collect()
+    }
+
+    /// Updates the held peaks with this frame's levels and returns the peaks to draw.
+    fn hold_peaks(&self, levels: &[f32]) -> Vec<f32> {
+        let now = Instant::now();
+        let mut held = self.peak_hold.borrow_mut();
+        if held.len() != levels.len() {
+            *held = vec![(FLOOR, now); levels.len()];
+        }
+        held.iter_mut()
+            .zip(levels)
+            .map(|((peak, since), &level)| {
+                let released = now.duration_since(*since).as_secs_f32() - HOLD;
+                let shown = if released > 0.0 { *peak - FALL * released } else { *peak };
+                if level >= shown {
+                    (*peak, *since) = (level, now);
+                    level
+                } else {
+                    shown
+                }
+            })
+            .collect()
+    }
+
+    /// Real-time analyzer: the instantaneous spectrum at the playhead as 1/3-octave bars
+    /// with peak hold, redrawn with every frame while playing.
+    pub(super) fn draw_rta(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let block = self.pane(format!("RTA {:.0}–0 dBFS", FLOOR));
+        let inner = block.inner(area);
+        let max_frequency = self.max_frequency.unwrap_or(f32::MAX).min(self.audio_data.sample_rate as f32 / 2.0);
+        // Octave bands when there isn't a column for every 1/3-octave band
+        let step = if inner.width as usize >= band_centers(max_frequency, 1).len() { 1 } else { 3 };
+        let centers = band_centers(max_frequency, step);
+        if inner.height < 2 || centers.is_empty() || inner.width < centers.len() as u16 {
+            frame.render_widget(Paragraph::new("Too small").block(block), area);
+            return;
+        }
+        frame.render_widget(block, area);
+
+        let half_band = 2f32.powf(step as f32 / 6.0);
+        let levels = self.band_levels(playhead, &centers, half_band);
+        let peaks = self.hold_peaks(&levels);
+
+        // The bottom row holds the frequency labels
+        let rows = inner.height - 1;
+        let width = inner.width / centers.len() as u16;
+        let gap = u16::from(width > 1);
+        let fraction = |level: f32| ((level - FLOOR) / -FLOOR).clamp(0.0, 1.0);
+        let partial = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+        let bar_style = self.fg(self.theme.waveform);
+        let peak_style = self.fg(self.theme.highlight);
+        let buffer = frame.buffer_mut();
+        for (band, (&level, &peak)) in levels.iter().zip(&peaks).enumerate() {
+            let x = inner.x + band as u16 * width;
+            // Height in eighths of a row
+            let eighths = (fraction(level) * rows as f32 * 8.0).round() as u16;
+            let peak_row = ((fraction(peak) * rows as f32) as u16).min(rows - 1);
+            for row in 0..rows {
+                let y = inner.y + rows - 1 - row;
+                let filled = eighths.saturating_sub(row * 8).min(8);
+                let (symbol, style) = if filled > 0 {
+                    let symbol = match self.capabilities.unicode {
+                        true => partial[filled as usize],
+                        false if filled >= 4 => "#",
+                        false => " ",
+                    };
+                    (symbol, bar_style)
+                } else if row == peak_row && peak > FLOOR {
+                    (if self.capabilities.unicode { "▔" } else { "-" }, peak_style)
+                } else {
+                    continue;
+                };
+                for column in x..x + width - gap {
+                    buffer.get_mut(column, y).set_symbol(symbol).set_style(style);
+                }
+            }
+        }
+
+        let labels = Rect { y: inner.y + rows, height: 1, ..inner };
+        let first = frequency_label(centers[0]);
+        let last = frequency_label(*centers.last().expect("checked non-empty"));
+        let padding = (labels.width as usize).saturating_sub(first.len() + last.len());
+        Paragraph::new(format!("{}{}{}", first, " ".repeat(padding), last))
+            .style(self.fg(self.theme.muted))
+            .render(labels, buffer);
+    }
+}
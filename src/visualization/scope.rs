@@ -0,0 +1,55 @@
+use ratatui::layout::Rect;
+use ratatui::text::Span;
+use ratatui::widgets::{Axis, Chart};
+
+use super::Visualizer;
+
+/// Span of the oscilloscope, centred on the playhead, in milliseconds.
+const SPAN_MS: f64 = 20.0;
+
+/// Sample magnitude treated as clipped.
+const CLIP: f32 = 0.999;
+
+impl Visualizer {
+    /// Oscilloscope: the raw samples of each channel (up to two) in the few milliseconds
+    /// around the playhead, one point per sample, on a fixed ±1 scale so clipping and
+    /// asymmetry show as they are.
+    pub(super) fn draw_scope(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let rate = self.audio_data.sample_rate as f64;
+        let center = (playhead * rate) as i64;
+        let half = (SPAN_MS / 2000.0 * rate).ceil() as i64;
+        let traces: Vec<Vec<(f64, f64)>> = (0..self.audio_data.channels.min(2))
+            .filter_map(|index| self.audio_data.channel(index))
+            .map(|samples| {
+                (center - half..=center + half)
+                    .filter(|&i| i >= 0 && (i as usize) < samples.len())
+                    .map(|i| ((i - center) as f64 * 1000.0 / rate, samples[i as usize] as f64))
+                    .collect()
+            })
+            .collect();
+        let clipped = traces.iter().flatten().any(|&(_, x)| x.abs() >= CLIP as f64);
+
+        let limit = SPAN_MS / 2.0;
+        let clip_lines = [[(-limit, 1.0), (limit, 1.0)], [(-limit, -1.0), (limit, -1.0)]];
+        let zero_line = [(-limit, 0.0), (limit, 0.0)];
+        let playhead_line = [(0.0, -1.0), (0.0, 1.0)];
+        let mut datasets = vec![self.line_dataset(&zero_line, self.theme.muted)];
+        if clipped {
+            datasets.extend(clip_lines.iter().map(|line| self.line_dataset(line, self.theme.error)));
+        }
+        datasets.push(self.line_dataset(&playhead_line, self.theme.playhead));
+        for (trace, color) in traces.iter().zip([self.theme.waveform, self.theme.selection]) {
+            datasets.push(self.line_dataset(trace, color));
+        }
+
+        let title = if clipped { "Scope (clipping)" } else { "Scope" };
+        let chart = Chart::new(datasets)
+            .block(self.pane(title))
+            .x_axis(Axis::default().bounds([-limit, limit]).labels(vec![
+                Span::raw(format!("-{:.0}ms", limit)),
+                Span::raw(format!("+{:.0}ms", limit)),
+            ]))
+            .y_axis(Axis::default().bounds([-1.0, 1.0]).labels(vec![Span::raw("-1"), Span::raw("1")]));
+        frame.render_widget(chart, area);
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Result};
+use crossterm::event::KeyEvent;
+
+use super::editor::{InputOutcome, LineInput};
+use super::Visualizer;
+use crate::audio::pyramid::EnvelopePyramid;
+use crate::audio::stft::scale_region;
+
+/// A time-frequency box cut out of the audio, kept so it can be drawn on the spectrogram.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SpectralEdit {
+    pub(super) times: (f64, f64),
+    pub(super) band: (f32, f32),
+    /// Attenuation in dB (negative), or `None` when the box was removed outright
+    pub(super) gain_db: Option<f32>,
+}
+
+impl SpectralEdit {
+    /// Corners of the box clipped to `max_frequency`, closed for drawing as a line.
+    pub(super) fn outline(&self, max_frequency: f32) -> Option<[(f64, f64); 5]> {
+        let (start, end) = self.times;
+        let (low, high) = (self.band.0 as f64, self.band.1.min(max_frequency) as f64);
+        (low < high).then_some([(start, low), (end, low), (end, high), (start, high), (start, low)])
+    }
+}
+
+/// Parses `LOW-HIGH` (remove the band) or `LOW-HIGH DB` (attenuate it by DB; the sign is
+/// optional), e.g. `2000-4000 12dB`.
+fn parse_edit(text: &str, times: (f64, f64)) -> Result<SpectralEdit> {
+    let mut parts = text.split_whitespace();
+    let band = parts.next().ok_or_else(|| anyhow!("Enter a band as LOW-HIGH Hz"))?;
+    let band = band
+        .split_once('-')
+        .and_then(|(low, high)| Some((low.parse::<f32>().ok()?, high.parse::<f32>().ok()?)))
+        .filter(|&(low, high)| low >= 0.0 && high > low)
+        .ok_or_else(|| anyhow!("`{}` is not a band of LOW-HIGH Hz", band))?;
+    let gain_db = match parts.next() {
+        Some(gain) => {
+            let number = gain.trim_end_matches(['d', 'D', 'b', 'B']);
+            let db = number.parse::<f32>().map_err(|_| anyhow!("`{}` is not a level in dB", gain))?;
+            Some(-db.abs())
+        }
+        None => None,
+    };
+    if parts.next().is_some() {
+        return Err(anyhow!("Expected LOW-HIGH [DB], got `{}`", text));
+    }
+    Ok(SpectralEdit { times, band, gain_db })
+}
+
+impl Visualizer {
+    /// Opens the prompt for a band to cut from the selection.
+    pub(super) fn start_spectral_edit(&mut self) -> Result<()> {
+        if self.mid_side.is_some() {
+            return Err(anyhow!("Spectral edits need left/right audio (without --mid-side)"));
+        }
+        let selection = self.selection.ok_or_else(|| anyhow!("Select a region with 'v' first"))?;
+        self.box_input = Some((selection, LineInput::new("")));
+        Ok(())
+    }
+
+    pub(super) fn handle_box_key(&mut self, key: KeyEvent) {
+        let Some((times, input)) = &mut self.box_input else {
+            return;
+        };
+        match input.handle(key) {
+            InputOutcome::Editing => {}
+            InputOutcome::Cancel => self.box_input = None,
+            InputOutcome::Commit(text) => {
+                let times = *times;
+                self.box_input = None;
+                let result = parse_edit(&text, times).and_then(|edit| self.apply_spectral_edit(edit));
+                self.report(result);
+            }
+        }
+    }
+
+    /// Cuts the box out of every channel, then rebuilds playback and the views from the
+    /// edited samples.
+    fn apply_spectral_edit(&mut self, edit: SpectralEdit) -> Result<()> {
+        let gain = edit.gain_db.map_or(0.0, |db| 10f32.powf(db / 20.0));
+        scale_region(&mut self.audio_data, edit.times, edit.band, gain)?;
+        self.spectral_edits.push(edit);
+        self.unsaved_edits = true;
+
+        self.reload_player()?;
+        self.envelope = EnvelopePyramid::new(&self.audio_data.samples);
+        *self.average_spectrum.borrow_mut() = None;
+        *self.psd.borrow_mut() = None;
+        *self.tuner.borrow_mut() = None;
+        *self.level_track.borrow_mut() = None;
+        self.image_key = None;
+        self.recompute_spectrogram(self.spectrogram_params)?;
+
+        let (low, high) = edit.band;
+        self.info = Some(match edit.gain_db {
+            Some(db) => format!("Attenuated {:.0}-{:.0} Hz by {:.0} dB", low, high, -db),
+            None => format!("Removed {:.0}-{:.0} Hz", low, high),
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use ratatui::layout::Rect;
+use ratatui::text::Span;
+use ratatui::widgets::{Axis, Chart, Dataset, GraphType, Paragraph};
+
+use super::Visualizer;
+use crate::audio::spectrum::{average_spectrum, Spectrum};
+
+/// FFT size of the average spectrum: about 10 Hz per bin at 44.1 kHz.
+const WINDOW: usize = 4096;
+
+/// Frames averaged, spread over the file or selection.
+const FRAMES: usize = 256;
+
+/// Range of the level axis below the loudest bin, in dB.
+const RANGE: f64 = 90.0;
+
+/// Average spectrum and the selection it was computed for.
+pub(super) type SpectrumCache = (Option<(f64, f64)>, Spectrum);
+
+impl Visualizer {
+    /// Long-term average spectrum of the selection, or of the whole file without one. Kept
+    /// until the selection changes.
+    fn average_spectrum(&self) -> std::cell::Ref<'_, Spectrum> {
+        let stale = self.average_spectrum.borrow().as_ref().is_none_or(|(range, _)| *range != self.selection);
+        if stale {
+            let samples = &self.audio_data.samples;
+            let rate = self.audio_data.sample_rate as f64;
+            let (from, to) = match self.selection {
+                Some((start, end)) => (
+                    ((start * rate) as usize).min(samples.len()),
+                    ((end * rate) as usize).min(samples.len()),
+                ),
+                None => (0, samples.len()),
+            };
+            let spectrum = average_spectrum(&samples[from..to], self.audio_data.sample_rate, WINDOW, FRAMES);
+            *self.average_spectrum.borrow_mut() = Some((self.selection, spectrum));
+        }
+        std::cell::Ref::map(self.average_spectrum.borrow(), |cached| &cached.as_ref().expect("just computed").1)
+    }
+
+    pub(super) fn draw_average_spectrum(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let title = match self.selection {
+            Some(_) => "Average spectrum (selection)",
+            None => "Average spectrum",
+        };
+        let block = self.pane(title);
+        let spectrum = self.average_spectrum();
+        let max_frequency = self
+            .max_frequency
+            .unwrap_or(self.audio_data.sample_rate as f32 / 2.0)
+            .min(spectrum.frequencies.last().copied().unwrap_or(0.0));
+        let bins = spectrum.frequencies.iter().take_while(|&&f| f <= max_frequency).count();
+        if bins < 2 {
+            frame.render_widget(Paragraph::new("Too short").block(block), area);
+            return;
+        }
+
+        // One point per braille column, the loudest bin under it
+        let points = (area.width as usize * 2).clamp(1, bins);
+        let data: Vec<(f64, f64)> = (0..points)
+            .map(|p| {
+                let (from, to) = (p * bins / points, ((p + 1) * bins / points).max(p * bins / points + 1));
+                let level = spectrum.levels[from..to].iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+                (spectrum.frequencies[from] as f64, level as f64)
+            })
+            .collect();
+        let peak = data.iter().fold(f64::NEG_INFINITY, |a, &(_, level)| a.max(level));
+        let top = (peak / 10.0).ceil() * 10.0;
+        let bottom = top - RANGE;
+        let data: Vec<(f64, f64)> = data.into_iter().map(|(f, level)| (f, level.max(bottom))).collect();
+
+        let chart = Chart::new(vec![Dataset::default()
+            .marker(self.capabilities.line_marker())
+            .graph_type(GraphType::Line)
+            .style(self.fg(self.theme.waveform))
+            .data(&data)])
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, max_frequency as f64])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}Hz", max_frequency))]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([bottom, top])
+                .labels(vec![Span::raw(format!("{:.0}", bottom)), Span::raw(format!("{:.0}dB", top))]),
+        );
+        frame.render_widget(chart, area);
+    }
+}
@@ -0,0 +1,70 @@
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Wrap};
+
+use super::Visualizer;
+use crate::stats::stereo::{balance_label, OUT_OF_PHASE};
+
+/// Balance meter range either side of centre, in dB.
+const BALANCE_RANGE: f32 = 12.0;
+
+impl Visualizer {
+    /// A horizontal meter `width` cells wide from -1 to +1 with a centre tick and a needle at
+    /// `position`.
+    fn meter(&self, position: f32, width: usize, needle: Style) -> Vec<Span<'static>> {
+        let width = width.max(3);
+        let at = (((position.clamp(-1.0, 1.0) + 1.0) / 2.0) * (width - 1) as f32).round() as usize;
+        let (track, centre, mark) = if self.capabilities.unicode { ("─", "┼", "●") } else { ("-", "+", "o") };
+        let cell = |i: usize| if i == (width - 1) / 2 { centre } else { track };
+        vec![
+            Span::styled((0..at).map(cell).collect::<String>(), self.fg(self.theme.muted)),
+            Span::styled(mark, needle),
+            Span::styled((at + 1..width).map(cell).collect::<String>(), self.fg(self.theme.muted)),
+        ]
+    }
+
+    /// Correlation and balance meters at the playhead, with the whole-file figures and any
+    /// out-of-phase warning.
+    pub(super) fn draw_stereo(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let block = self.pane("Stereo");
+        let Some(stereo) = &self.stats.stereo else {
+            frame.render_widget(Paragraph::new("Mono input").block(block), area);
+            return;
+        };
+        // Room for the end labels on the meter rows
+        let width = block.inner(area).width.saturating_sub(6) as usize;
+        let now = stereo.at(playhead);
+
+        let mut lines = Vec::new();
+        let correlation = now.map_or("silent".to_string(), |w| format!("{:+.2}", w.correlation));
+        lines.push(Line::from(format!("Correlation {}", correlation)));
+        if let Some(window) = now {
+            let needle = match window.correlation {
+                c if c < OUT_OF_PHASE => self.fg(self.theme.error),
+                _ => self.fg(self.theme.highlight),
+            };
+            let mut meter = vec![Span::raw("-1 ")];
+            meter.extend(self.meter(window.correlation, width, needle));
+            meter.push(Span::raw(" +1"));
+            lines.push(Line::from(meter));
+        }
+        let balance = now.map_or("silent".to_string(), |w| balance_label(w.balance_db));
+        lines.push(Line::from(format!("Balance {}", balance)));
+        if let Some(window) = now {
+            let mut meter = vec![Span::raw(" L ")];
+            meter.extend(self.meter(window.balance_db / BALANCE_RANGE, width, self.fg(self.theme.highlight)));
+            meter.push(Span::raw(" R"));
+            lines.push(Line::from(meter));
+        }
+        lines.push(Line::from(format!(
+            "Overall {:+.2}, {}",
+            stereo.correlation,
+            balance_label(stereo.balance_db)
+        )));
+        if let Some(warning) = stereo.warning() {
+            lines.push(Line::styled(format!("Warning: {}", warning), self.fg(self.theme.error)));
+        }
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(block), area);
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use ratatui::style::Color;
+
+use crate::config::toml::{Table, Value};
+
+/// Colors for every pane and highlight. Configured under `[theme]` in the config: `base`
+/// picks `dark` (the default) or `light`, and any color below can be overridden by name
+/// (`red`, `lightblue`, ...), 256-color index or `#rrggbb`.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub border: Color,
+    pub waveform: Color,
+    pub playhead: Color,
+    pub selection: Color,
+    pub marker: Color,
+    pub chapter: Color,
+    /// Current transcript segment, speed and unsaved-changes indicators
+    pub highlight: Color,
+    /// Clock, labels and other secondary text
+    pub muted: Color,
+    pub info: Color,
+    pub error: Color,
+    /// Spectrogram intensity levels, quietest first
+    pub spectrogram: [Color; 4],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            border: Color::Reset,
+            waveform: Color::Cyan,
+            playhead: Color::White,
+            selection: Color::Magenta,
+            marker: Color::Cyan,
+            chapter: Color::LightGreen,
+            highlight: Color::Yellow,
+            muted: Color::DarkGray,
+            info: Color::Green,
+            error: Color::Red,
+            spectrogram: [Color::Blue, Color::Green, Color::Yellow, Color::Red],
+        }
+    }
+
+    /// For light backgrounds, where yellow, white and cyan wash out.
+    pub fn light() -> Self {
+        Self {
+            border: Color::Reset,
+            waveform: Color::Blue,
+            playhead: Color::Black,
+            selection: Color::Magenta,
+            marker: Color::DarkGray,
+            chapter: Color::Green,
+            highlight: Color::Red,
+            muted: Color::DarkGray,
+            info: Color::Green,
+            error: Color::Red,
+            spectrogram: [Color::Gray, Color::Blue, Color::Magenta, Color::Red],
+        }
+    }
+
+    pub fn from_config(table: &Table) -> Result<Self> {
+        let mut theme = match table.get("theme.base").and_then(Value::as_str) {
+            None | Some("dark") => Self::dark(),
+            Some("light") => Self::light(),
+            Some(other) => return Err(anyhow!("Unknown theme base `{}` (expected dark or light)", other)),
+        };
+        let slots = [
+            ("border", &mut theme.border),
+            ("waveform", &mut theme.waveform),
+            ("playhead", &mut theme.playhead),
+            ("selection", &mut theme.selection),
+            ("marker", &mut theme.marker),
+            ("chapter", &mut theme.chapter),
+            ("highlight", &mut theme.highlight),
+            ("muted", &mut theme.muted),
+            ("info", &mut theme.info),
+            ("error", &mut theme.error),
+        ];
+        for (name, slot) in slots {
+            if let Some(value) = table.get(&format!("theme.{}", name)) {
+                *slot = parse_color(name, value)?;
+            }
+        }
+        if let Some(value) = table.get("theme.spectrogram") {
+            let colors = value
+                .as_array()
+                .filter(|items| items.len() == 4)
+                .ok_or_else(|| anyhow!("theme.spectrogram must be an array of 4 colors"))?;
+            for (slot, value) in theme.spectrogram.iter_mut().zip(colors) {
+                *slot = parse_color("spectrogram", value)?;
+            }
+        }
+        Ok(theme)
+    }
+}
+
+fn parse_color(name: &str, value: &Value) -> Result<Color> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| anyhow!("Invalid color for theme.{}: {}", name, value))
+}
+
+/// Spectrogram palettes selectable in the parameter playground.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// The theme's `spectrogram` colors
+    #[default]
+    Theme,
+    Heat,
+    Grayscale,
+    Viridis,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [Colormap::Theme, Colormap::Heat, Colormap::Grayscale, Colormap::Viridis];
+
+    pub fn colors(self, theme: &Theme) -> [Color; 4] {
+        match self {
+            Colormap::Theme => theme.spectrogram,
+            Colormap::Heat => [Color::Red, Color::LightRed, Color::Yellow, Color::White],
+            Colormap::Grayscale => [Color::Indexed(238), Color::Indexed(244), Color::Indexed(250), Color::Indexed(255)],
+            Colormap::Viridis => [Color::Indexed(54), Color::Indexed(31), Color::Indexed(35), Color::Indexed(226)],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Colormap::Theme => "theme",
+            Colormap::Heat => "heat",
+            Colormap::Grayscale => "grayscale",
+            Colormap::Viridis => "viridis",
+        }
+    }
+}
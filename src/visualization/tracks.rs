@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Chart, Dataset, GraphType};
+
+use super::Visualizer;
+use crate::audio::playback::Player;
+use crate::audio::pyramid::EnvelopePyramid;
+use crate::audio::tracks::mix;
+
+/// One input file of a multi-track session, drawn as its own waveform.
+pub(super) struct Track {
+    name: String,
+    samples: Vec<f32>,
+    envelope: EnvelopePyramid,
+    muted: bool,
+}
+
+impl Track {
+    pub(super) fn new(name: String, samples: Vec<f32>) -> Self {
+        let envelope = EnvelopePyramid::new(&samples);
+        Self { name, samples, envelope, muted: false }
+    }
+}
+
+impl Visualizer {
+    /// Mutes or unmutes track `index` (0 for the first input), rebuilding playback from the
+    /// tracks still playing.
+    pub(super) fn toggle_mute(&mut self, index: usize) -> Result<()> {
+        let count = self.tracks.len();
+        let track = self
+            .tracks
+            .get_mut(index)
+            .ok_or_else(|| match count {
+                0 => anyhow!("Only multi-track sessions (several -i files) have tracks to mute"),
+                _ => anyhow!("No track {}; there are {}", index + 1, count),
+            })?;
+        track.muted = !track.muted;
+        let (name, muted) = (track.name.clone(), track.muted);
+        let playing = self.player.is_playing();
+        self.reload_player()?;
+        if playing {
+            self.player.play()?;
+        }
+        self.info = Some(format!("{} {}", if muted { "Muted" } else { "Unmuted" }, name));
+        Ok(())
+    }
+
+    /// Indices of the muted tracks, for the session file.
+    pub(super) fn muted_tracks(&self) -> Vec<usize> {
+        self.tracks.iter().enumerate().filter(|(_, track)| track.muted).map(|(index, _)| index).collect()
+    }
+
+    /// Mutes exactly the tracks at `indices`, ignoring any past the last; playback is
+    /// rebuilt by the caller.
+    pub(super) fn set_muted(&mut self, indices: &[usize]) {
+        for (index, track) in self.tracks.iter_mut().enumerate() {
+            track.muted = indices.contains(&index);
+        }
+    }
+
+    /// What playback plays: the audio as analyzed and edited, or once any track is muted,
+    /// the mix of the others as loaded.
+    pub(super) fn new_player(&self) -> Player {
+        if !self.tracks.iter().any(|track| track.muted) {
+            return Player::new(&self.audio_data.samples, self.audio_data.sample_rate)
+                .with_time_expansion(self.time_expansion);
+        }
+        let playing: Vec<&[f32]> =
+            self.tracks.iter().filter(|track| !track.muted).map(|track| &track.samples[..]).collect();
+        let mut mixed = mix(&playing);
+        // Keep the length, so positions and loops carry over
+        mixed.resize(self.audio_data.samples.len(), 0.0);
+        Player::new(&mixed.into(), self.audio_data.sample_rate).with_time_expansion(self.time_expansion)
+    }
+
+    /// The tracks' waveforms stacked in `area`, each scaled to its own peak and named in its
+    /// title, with muted ones dimmed.
+    pub(super) fn draw_tracks(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64, simple: bool) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Ratio(1, self.tracks.len() as u32); self.tracks.len()])
+            .split(area);
+        let sample_rate = self.audio_data.sample_rate as f64;
+        let duration = self.audio_data.samples.len() as f64 / sample_rate;
+        let playhead_line = [(playhead, 0.0), (playhead, 1.0)];
+        let selection_lines = self.selection_lines(1.0);
+        for (index, (track, &row)) in self.tracks.iter().zip(rows.iter()).enumerate() {
+            let columns = if simple { row.width / 2 } else { row.width }.max(1) as usize;
+            let peak = track.envelope.total().peak.max(f32::MIN_POSITIVE);
+            let seconds_per_column = track.samples.len() as f64 / columns as f64 / sample_rate;
+            let waveform: Vec<(f64, f64)> = track
+                .envelope
+                .columns(&track.samples, 0, track.samples.len(), columns)
+                .iter()
+                .enumerate()
+                .map(|(i, column)| (i as f64 * seconds_per_column, (column.rms() / peak) as f64))
+                .collect();
+            let color = if track.muted { self.theme.muted } else { self.theme.waveform };
+            let mut datasets = vec![Dataset::default()
+                .marker(self.capabilities.line_marker())
+                .graph_type(GraphType::Line)
+                .style(self.fg(color))
+                .data(&waveform)];
+            for line in &selection_lines {
+                datasets.push(self.line_dataset(line, self.theme.selection));
+            }
+            if playhead > 0.0 {
+                datasets.push(self.line_dataset(&playhead_line, self.theme.playhead));
+            }
+            let muted = if track.muted { " (muted)" } else { "" };
+            let title = Line::from(Span::styled(format!("{} {}{}", index + 1, track.name, muted), self.fg(color)));
+            let chart = Chart::new(datasets)
+                .block(self.pane(title))
+                .x_axis(Axis::default().bounds([0.0, duration]))
+                .y_axis(Axis::default().bounds([0.0, 1.0]));
+            frame.render_widget(chart, row);
+        }
+    }
+}
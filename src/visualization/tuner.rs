@@ -0,0 +1,98 @@
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::text::Span;
+use ratatui::widgets::{Axis, Chart, Dataset, GraphType};
+
+use super::Visualizer;
+use crate::stats::notes::{pitch_frames, segment_notes, NOTE_HOP};
+use crate::stats::tuning::{estimate_tuning, wrap_cents, Tuning};
+use crate::stats::NOTE_NAMES;
+
+/// Pitch history shown left of the playhead, in seconds.
+const HISTORY: f64 = 5.0;
+
+/// Deviation still drawn as in tune, in cents.
+const IN_TUNE: f64 = 10.0;
+
+/// Pitch track (fractional MIDI note numbers every `NOTE_HOP`) and the tuning estimated
+/// from its notes.
+pub(super) type TunerCache = (Vec<Option<f32>>, Option<Tuning>);
+
+impl Visualizer {
+    /// Pitch track and tuning of the first channel, computed on first use.
+    fn tuner(&self) -> std::cell::Ref<'_, TunerCache> {
+        if self.tuner.borrow().is_none() {
+            let pitches = pitch_frames(&self.audio_data);
+            let tuning = estimate_tuning(&segment_notes(&pitches));
+            *self.tuner.borrow_mut() = Some((pitches, tuning));
+        }
+        std::cell::Ref::map(self.tuner.borrow(), |cached| cached.as_ref().expect("just computed"))
+    }
+
+    /// Scrolling tuner: the last few seconds of pitch as cents from the nearest
+    /// equal-tempered note, against the A4 the whole recording is tuned to, with the
+    /// current note in the title.
+    pub(super) fn draw_tuner(&self, frame: &mut ratatui::Frame, area: Rect, playhead: f64) {
+        let cache = self.tuner();
+        let (pitches, tuning) = (&cache.0, &cache.1);
+        let offset = tuning.as_ref().map_or(0.0, |t| t.offset_cents);
+        // Pitch relative to the estimated reference
+        let relative = |midi: f32| midi - offset / 100.0;
+
+        let first = ((playhead - HISTORY) / NOTE_HOP).floor().max(0.0) as usize;
+        let last = ((playhead / NOTE_HOP) as usize).min(pitches.len().saturating_sub(1));
+        let (mut in_tune, mut out_of_tune) = (Vec::new(), Vec::new());
+        for (i, pitch) in pitches.iter().enumerate().take(last + 1).skip(first) {
+            let Some(midi) = pitch else { continue };
+            let cents = wrap_cents(relative(*midi) * 100.0) as f64;
+            let point = (i as f64 * NOTE_HOP - playhead, cents);
+            if cents.abs() <= IN_TUNE {
+                in_tune.push(point);
+            } else {
+                out_of_tune.push(point);
+            }
+        }
+
+        let note = pitches.get(last).copied().flatten().map(|midi| {
+            let nearest = relative(midi).round() as i32;
+            let cents = wrap_cents(relative(midi) * 100.0);
+            format!(
+                "{}{} {:+.0}¢",
+                NOTE_NAMES[nearest.rem_euclid(12) as usize],
+                nearest.div_euclid(12) - 1,
+                cents
+            )
+        });
+        let reference = match tuning {
+            Some(tuning) => format!("A4 = {:.1} Hz, ±{:.0}¢", tuning.reference, tuning.spread_cents),
+            None => "no notes".to_string(),
+        };
+        let title = format!("Tuner{} ({})", note.map(|n| format!(" {}", n)).unwrap_or_default(), reference);
+
+        let centre_line = [(-HISTORY, 0.0), (0.0, 0.0)];
+        let points = |data, color: Color| {
+            Dataset::default()
+                .marker(self.capabilities.line_marker())
+                .graph_type(GraphType::Scatter)
+                .style(self.fg(color))
+                .data(data)
+        };
+        let chart = Chart::new(vec![
+            self.line_dataset(&centre_line, self.theme.muted),
+            points(&out_of_tune, self.theme.error),
+            points(&in_tune, self.theme.highlight),
+        ])
+        .block(self.pane(title))
+        .x_axis(
+            Axis::default()
+                .bounds([-HISTORY, 0.0])
+                .labels(vec![Span::raw(format!("-{:.0}s", HISTORY)), Span::raw("now")]),
+        )
+        .y_axis(Axis::default().bounds([-50.0, 50.0]).labels(vec![
+            Span::raw("-50¢"),
+            Span::raw("0"),
+            Span::raw("+50¢"),
+        ]));
+        frame.render_widget(chart, area);
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::Visualizer;
+use crate::audio::playback::find_program;
+use crate::export::srt::to_srt;
+use crate::export::wav::write_wav;
+
+/// Frame size and rate of MP4 (and other video) exports.
+const VIDEO: (usize, usize, f64) = (1280, 720, 30.0);
+
+/// GIFs are smaller and slower, as every frame is stored with its own palette lookups.
+const GIF: (usize, usize, f64) = (640, 360, 15.0);
+
+impl Visualizer {
+    /// Renders the scrolling spectrogram (the last scroll span up to the playhead, newest at
+    /// the right edge) over the whole file into an animation at `path`, with the transcript
+    /// burned in as captions and, except for GIFs, the audio. Encoding is left to `ffmpeg`.
+    pub fn export_video(&self, path: &Path) -> Result<()> {
+        if !find_program("ffmpeg") {
+            return Err(anyhow!("--export-video needs ffmpeg on the PATH"));
+        }
+        let gif = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+        let (width, height, fps) = if gif { GIF } else { VIDEO };
+        let duration = *self.spectrogram.time_points.last().unwrap_or(&0.0) as f64;
+        let frames = self.spectrogram.time_points.len();
+
+        // ffmpeg runs in a scratch directory so filter arguments name files without escaping
+        let scratch = std::env::temp_dir().join(format!("fourrier-video-{}", std::process::id()));
+        std::fs::create_dir_all(&scratch).with_context(|| format!("Failed to create {}", scratch.display()))?;
+        let output = std::env::current_dir()?.join(path);
+        let mut filters = Vec::new();
+        if !self.transcription.is_empty() {
+            std::fs::write(scratch.join("captions.srt"), to_srt(&self.transcription))?;
+            filters.push("subtitles=captions.srt".to_string());
+        }
+        let mut command = Command::new("ffmpeg");
+        command
+            .current_dir(&scratch)
+            .args(["-v", "error", "-y", "-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", format!("{}x{}", width, height).as_str(), "-r", fps.to_string().as_str(), "-i", "-"]);
+        if gif {
+            // A palette made from the frames themselves keeps the colormap's gradients smooth
+            filters.push("split[a][b];[a]palettegen[p];[b][p]paletteuse".to_string());
+        } else {
+            let audio = &self.audio_data;
+            let channels: Vec<&[f32]> = (0..audio.channels.max(1)).filter_map(|c| audio.channel(c)).collect();
+            write_wav(&scratch.join("audio.wav"), &channels, audio.sample_rate)?;
+            command.args(["-i", "audio.wav", "-pix_fmt", "yuv420p", "-shortest"]);
+        }
+        if !filters.is_empty() {
+            command.args(["-vf", filters.join(",").as_str()]);
+        }
+        let mut child = command
+            .arg(&output)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run ffmpeg")?;
+
+        // Each frame moves the window on by a few columns, so only those are computed, with
+        // the rest kept from the frame before
+        let step = self.scroll_span / width as f64;
+        let column = |index: i64| {
+            let start = index as f64 * step;
+            if frames == 0 || start < 0.0 || start >= duration {
+                return vec![[0; 3]; height];
+            }
+            let frame_at = |time: f64| ((time / duration * frames as f64) as usize).min(frames);
+            self.bitmap_column(frame_at(start), frame_at(start + step), height)
+        };
+        let mut columns: VecDeque<(i64, Vec<[u8; 3]>)> = VecDeque::new();
+        let mut pixels = vec![0; width * height * 3];
+        let mut written = Ok(());
+        let stdin = child.stdin.as_mut().expect("stdin is piped");
+        for index in 0..(duration * fps).ceil() as usize {
+            let last = ((index as f64 / fps) / step) as i64;
+            let first = last - width as i64 + 1;
+            while columns.front().is_some_and(|&(i, _)| i < first) {
+                columns.pop_front();
+            }
+            let next = columns.back().map_or(first, |&(i, _)| i + 1);
+            columns.extend((next..=last).map(|i| (i, column(i))));
+            for (x, (_, colors)) in columns.iter().enumerate() {
+                for (y, color) in colors.iter().enumerate() {
+                    let offset = (y * width + x) * 3;
+                    pixels[offset..offset + 3].copy_from_slice(color);
+                }
+            }
+            written = stdin.write_all(&pixels);
+            if written.is_err() {
+                // ffmpeg stopped reading; its error says why
+                break;
+            }
+        }
+        drop(child.stdin.take());
+        let result = child.wait_with_output().context("Failed to run ffmpeg");
+        let _ = std::fs::remove_dir_all(&scratch);
+        let result = result?;
+        if !result.status.success() {
+            return Err(anyhow!("ffmpeg failed: {}", String::from_utf8_lossy(&result.stderr).trim()));
+        }
+        written.with_context(|| format!("Failed to write {}", path.display()))
+    }
+}